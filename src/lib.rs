@@ -0,0 +1,30 @@
+/*
+* Stygian Sift - A Terminal-based File Manager
+ * Copyright (C) 2024 Maui The Magnificent (Charon)
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published
+ * by the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+ * Contact: Maui_The_Magnificent@proton.me
+ * Project repository: https://github.com/Mauitron/StygianSift.git
+ */
+
+// The browser itself lives in the_tome, main.rs only drives it. Being a
+// library as well lets the tests in tests/ run the UI on a virtual terminal.
+// the_core is the part other tools can link without the terminal: config,
+// color rules, search, file operations with undo and git status. the_cli
+// puts scripting commands on top of it.
+pub mod the_cli;
+pub mod the_core;
+#[cfg(feature = "tui")]
+pub mod the_tome;
@@ -19,17 +19,14 @@
  * Project repository: https://github.com/Mauitron/StygianSift.git
  */
 
-#[allow(dead_code)]
-use crossterm::{
-    event::{Event, KeyCode},
-    terminal,
-};
-use std::{env::current_dir, io};
-use the_tome::main_nav_loop::{browse_fuzzy_file, BrowseResult};
-/////////////////////////////////////////////////
-mod the_tome;
-use the_tome::tome_state::AppState;
-/////////////////////////////////////////////////
+use std::io::IsTerminal;
+use std::{env, io, process};
+use stygian_sift::the_cli;
+use stygian_sift::the_tome::chooser::write_paths;
+use stygian_sift::the_tome::launch::{LaunchOptions, HELP, VERSION};
+use stygian_sift::the_tome::main_nav_loop::{browse_fuzzy_file, BrowseResult};
+use stygian_sift::the_tome::renderer::draw_to_terminal_device;
+use stygian_sift::the_tome::system_functions::cleanup_terminal;
 //sort by color
 //search for colors
 //global extantions rules
@@ -40,40 +37,60 @@ use the_tome::tome_state::AppState;
 //consolidate similar functions.
 
 fn main() -> io::Result<()> {
-    let mut state = AppState::new()?;
-    if state.config.home_folder.is_none() {
-        let current_dir = current_dir();
-        state.config.home_folder = Some(current_dir?);
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.first().is_some_and(|arg| the_cli::is_subcommand(arg)) {
+        let code = the_cli::run(&args, &mut io::stdout()).unwrap_or_else(|e| {
+            eprintln!("StygianSift: {}", e);
+            the_cli::exit_code(&e)
+        });
+        process::exit(code);
     }
 
-    loop {
-        if !state.current_file_selected {
-            match browse_fuzzy_file(&mut state)? {
-                BrowseResult::FileSelected => {
-                    state.current_file_selected = true;
-                }
-                BrowseResult::Exit => break,
-                BrowseResult::Continue => {}
-            }
-        } else {
-            terminal::enable_raw_mode()?;
-            if let Ok(Event::Key(key)) = crossterm::event::read() {
-                match key.code {
-                    KeyCode::Tab => {
-                        state.current_file_selected = false;
-                        continue;
-                    }
-                    KeyCode::Esc => break,
-                    KeyCode::Char(c) => state.input.push(c as u8),
-                    _ => {}
-                }
-            }
-            // terminal::disable_raw_mode()?;
+    let options = LaunchOptions::parse(&args).unwrap_or_else(|e| {
+        eprintln!("StygianSift: {}\nSee StygianSift --help", e);
+        process::exit(2);
+    });
+    if options.help {
+        println!("{}", HELP);
+        return Ok(());
+    }
+    if options.version {
+        println!("StygianSift {}", VERSION);
+        return Ok(());
+    }
 
-            // redraw_interface(&mut stdout, &mut state)?;
-        }
+    let mut state = options.open().unwrap_or_else(|e| {
+        eprintln!("StygianSift: {}", e);
+        process::exit(1);
+    });
+
+    // Keeps stdout clean for p=$(StygianSift --choose-files -). Without a
+    // terminal device there is nowhere better to draw than stdout.
+    if options.writes_to_stdout() || !io::stdout().is_terminal() {
+        let _ = draw_to_terminal_device();
     }
 
+    let chosen = loop {
+        match browse_fuzzy_file(&mut state)? {
+            BrowseResult::FileSelected(paths) => {
+                cleanup_terminal()?;
+                break Some(paths);
+            }
+            BrowseResult::Exit => break None,
+            BrowseResult::Continue => {}
+        }
+    };
+
+    state.undo_manager.save_history()?;
+    if let Some(cwd_file) = &options.cwd_file {
+        write_paths(cwd_file, &[state.current_dir.clone()])?;
+    }
+    match (&state.chooser, chosen) {
+        (Some(chooser), Some(paths)) => write_paths(&chooser.output, &paths)?,
+        // Nothing was picked, so the caller should not go on.
+        (Some(_), None) => process::exit(1),
+        _ => {}
+    }
     Ok(())
 }
 // Part of my search algorithm. might be added later, or will be another project?
@@ -0,0 +1,361 @@
+/*
+* Stygian Sift - A Terminal-based File Manager
+ * Copyright (C) 2024 Maui The Magnificent (Charon)
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published
+ * by the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+ * Contact: Maui_The_Magnificent@proton.me
+ * Project repository: https://github.com/Mauitron/StygianSift.git
+ */
+
+use super::*;
+
+fn entry_json(config: &Config, entry: &FileEntry) -> Json {
+    Json::object([
+        ("name", Json::from(entry.name.as_str())),
+        ("path", Json::from(entry.path.as_path())),
+        ("type", Json::from(format!("{:?}", entry.file_type).to_lowercase())),
+        ("size", Json::from(entry.size)),
+        ("color", Json::from(config.get_item_color(&entry.path).map(|c| c.as_str()))),
+    ])
+}
+
+fn print_entries(session: &mut Session, entries: &[FileEntry]) -> io::Result<()> {
+    if session.options.json {
+        let list = entries
+            .iter()
+            .map(|entry| entry_json(&session.config, entry))
+            .collect();
+        return session.print_json(Json::Array(list));
+    }
+    for entry in entries {
+        let color = session
+            .config
+            .get_item_color(&entry.path)
+            .map_or("-", |c| c.as_str());
+        let slash = if entry.file_type == FileType::Directory { "/" } else { "" };
+        let line = format!(
+            "{:<8} {:>12}  {}{}",
+            color,
+            entry.size,
+            entry.path.display(),
+            slash
+        );
+        session.print(line)?;
+    }
+    Ok(())
+}
+
+fn parse_color(name: &str) -> io::Result<MarkerColor> {
+    MarkerColor::parse(&name.to_lowercase())
+        .ok_or_else(|| usage_error(format!("unknown color '{}'", name)))
+}
+
+fn no_more(args: &[String]) -> io::Result<()> {
+    match args.first() {
+        Some(arg) => Err(usage_error(format!("unexpected argument '{}'", arg))),
+        None => Ok(()),
+    }
+}
+
+//////////////////////////////////////////////////////////Listing//////////////////////////////////////////////////////////////////
+// ls [--sort ORDER] [PATH]
+pub fn list_directory(session: &mut Session, args: &[String]) -> io::Result<i32> {
+    let dir = absolute_path(Path::new(args.first().map_or(".", String::as_str)))?;
+    no_more(args.get(1..).unwrap_or_default())?;
+    session.enter_dir(&dir);
+    let sort_order = session
+        .options
+        .sort
+        .clone()
+        .unwrap_or_else(|| session.config.default_sort.clone());
+    let mut entries = read_entries(&dir)?;
+    entries.retain(|entry| session.config.is_listed(&entry.path));
+    sort_entries(&session.config, &mut entries, &sort_order);
+    print_entries(session, &entries)?;
+    Ok(0)
+}
+
+// search [--depth N] QUERY [PATH]
+pub fn search(session: &mut Session, args: &[String]) -> io::Result<i32> {
+    let query = args
+        .first()
+        .ok_or_else(|| usage_error("search needs a query"))?;
+    let dir = absolute_path(Path::new(args.get(1).map_or(".", String::as_str)))?;
+    no_more(args.get(2..).unwrap_or_default())?;
+    session.enter_dir(&dir);
+    let depth = session
+        .options
+        .depth
+        .unwrap_or(session.config.search_depth_limit);
+    let entries = fuzzy_search_entries(&session.config, &SearchFilters::new(), &dir, query, depth)?;
+    print_entries(session, &entries)?;
+    Ok(0)
+}
+
+///////////////////////////////////////////////////////////Colors///////////////////////////////////////////////////////////////////
+// tag set COLOR PATH... | tag rm PATH... | tag ls [COLOR]
+pub fn tag(session: &mut Session, args: &[String]) -> io::Result<i32> {
+    match args.first().map(String::as_str) {
+        Some("set") => {
+            let color = parse_color(args.get(1).ok_or_else(|| usage_error("tag set needs a color"))?)?;
+            let paths = args.get(2..).unwrap_or_default();
+            if paths.is_empty() {
+                return Err(usage_error("tag set needs at least one path"));
+            }
+            for path in paths {
+                let path = absolute_path(Path::new(path))?;
+                if !path.exists() {
+                    return Err(io::Error::new(
+                        io::ErrorKind::NotFound,
+                        format!("{} does not exist", path.display()),
+                    ));
+                }
+                session.config.set_item_color(path, color);
+            }
+            session.save_config()?;
+            Ok(0)
+        }
+        Some("rm") => {
+            let paths = args.get(1..).unwrap_or_default();
+            if paths.is_empty() {
+                return Err(usage_error("tag rm needs at least one path"));
+            }
+            for path in paths {
+                session.config.remove_item_color(&absolute_path(Path::new(path))?);
+            }
+            session.save_config()?;
+            Ok(0)
+        }
+        Some("ls") => {
+            let only = args.get(1).map(|name| parse_color(name)).transpose()?;
+            no_more(args.get(2..).unwrap_or_default())?;
+            let mut tagged: Vec<(MarkerColor, PathBuf)> = session
+                .config
+                .colored_items
+                .iter()
+                .filter(|(color, _)| only.is_none_or(|only| only == **color))
+                .flat_map(|(color, paths)| paths.iter().map(|path| (*color, path.clone())))
+                .collect();
+            tagged.sort_by(|a, b| {
+                MarkerColor::color_order(Some(a.0))
+                    .cmp(&MarkerColor::color_order(Some(b.0)))
+                    .then_with(|| a.1.cmp(&b.1))
+            });
+            if session.options.json {
+                let list = tagged
+                    .iter()
+                    .map(|(color, path)| {
+                        Json::object([
+                            ("path", Json::from(path.as_path())),
+                            ("color", Json::from(color.as_str())),
+                        ])
+                    })
+                    .collect();
+                return session.print_json(Json::Array(list)).map(|_| 0);
+            }
+            for (color, path) in tagged {
+                if only.is_some() {
+                    session.print(path.display())?;
+                } else {
+                    session.print(format!("{:<8} {}", color.as_str(), path.display()))?;
+                }
+            }
+            Ok(0)
+        }
+        _ => Err(usage_error("tag takes set, rm or ls")),
+    }
+}
+
+////////////////////////////////////////////////////////////Undo////////////////////////////////////////////////////////////////////
+// undo list | undo
+pub fn undo(session: &mut Session, args: &[String]) -> io::Result<i32> {
+    let mut undo_manager = UndoManager::with_config(Arc::new(RealFileSystem), &session.config)?;
+    undo_manager.load_history()?;
+    match args.first().map(String::as_str) {
+        Some("list") => {
+            no_more(&args[1..])?;
+            let newest_first = undo_manager.entries.iter().rev();
+            if session.options.json {
+                let list = newest_first
+                    .map(|entry| {
+                        Json::object([
+                            ("operation", Json::from(entry.operation.name())),
+                            ("time", Json::from(unix_seconds(entry.operation.timestamp()))),
+                            ("path", Json::from(entry.original_path.as_path())),
+                            ("description", Json::from(entry.describe())),
+                        ])
+                    })
+                    .collect();
+                return session.print_json(Json::Array(list)).map(|_| 0);
+            }
+            let lines: Vec<String> = newest_first
+                .enumerate()
+                .map(|(i, entry)| {
+                    let time = unix_seconds(entry.operation.timestamp());
+                    format!(
+                        "{:>3}  {}  {}",
+                        i + 1,
+                        format_local_time(time, &session.config.time_format),
+                        entry.describe()
+                    )
+                })
+                .collect();
+            for line in lines {
+                session.print(line)?;
+            }
+            Ok(0)
+        }
+        None => {
+            let result = FileOperations::new(&session.config, &mut undo_manager).undo();
+            // A blocked undo puts its entry back, so the history is saved either way.
+            undo_manager.save_history()?;
+            let message = match result {
+                Some(result) => result?,
+                None => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::NotFound,
+                        "there is nothing to undo",
+                    ))
+                }
+            };
+            if session.options.json {
+                session.print_json(Json::object([("message", Json::from(message))]))?;
+            } else {
+                session.print(message)?;
+            }
+            Ok(0)
+        }
+        Some(other) => Err(usage_error(format!("undo takes list or nothing, not '{}'", other))),
+    }
+}
+
+///////////////////////////////////////////////////////////Config///////////////////////////////////////////////////////////////////
+// config get [KEY] | config set KEY VALUE
+pub fn config(session: &mut Session, args: &[String]) -> io::Result<i32> {
+    match args.first().map(String::as_str) {
+        Some("get") => {
+            let keys: Vec<&str> = match args.get(1) {
+                Some(key) => vec![key.as_str()],
+                None => SETTINGS.to_vec(),
+            };
+            no_more(args.get(2..).unwrap_or_default())?;
+            let mut values = Vec::new();
+            for key in keys {
+                let value = session
+                    .config
+                    .get_value(key)
+                    .ok_or_else(|| usage_error(format!("unknown setting '{}'", key)))?;
+                values.push((key, value));
+            }
+            if session.options.json {
+                let object = values
+                    .into_iter()
+                    .map(|(key, value)| (key, Json::from(value)));
+                return session.print_json(Json::object(object)).map(|_| 0);
+            }
+            if let [(_, value)] = values.as_slice() {
+                let value = value.clone();
+                return session.print(value).map(|_| 0);
+            }
+            for (key, value) in values {
+                session.print(format!("{} = {}", key, value))?;
+            }
+            Ok(0)
+        }
+        Some("set") => {
+            let (Some(key), Some(value)) = (args.get(1), args.get(2)) else {
+                return Err(usage_error("config set needs a key and a value"));
+            };
+            no_more(&args[3..])?;
+            session.config.set_value(key, value).map_err(usage_error)?;
+            session.save_config()?;
+            Ok(0)
+        }
+        Some("check") => {
+            no_more(&args[1..])?;
+            let code = if session.report.errors.is_empty() { 0 } else { 1 };
+            if session.options.json {
+                let list = session
+                    .report
+                    .errors
+                    .iter()
+                    .map(|error| {
+                        Json::object([
+                            ("line", Json::from(error.line as u64)),
+                            ("message", Json::from(error.message.as_str())),
+                        ])
+                    })
+                    .collect();
+                return session.print_json(Json::Array(list)).map(|_| code);
+            }
+            for message in session.report.messages(&session.config_path) {
+                session.print(message)?;
+            }
+            Ok(code)
+        }
+        _ => Err(usage_error("config takes get, set or check")),
+    }
+}
+
+///////////////////////////////////////////////////////////Rules////////////////////////////////////////////////////////////////////
+// rules check OPERATION PATH...
+pub fn rules(session: &mut Session, args: &[String]) -> io::Result<i32> {
+    if args.first().map(String::as_str) != Some("check") {
+        return Err(usage_error("rules takes check"));
+    }
+    let operation = args
+        .get(1)
+        .ok_or_else(|| usage_error("rules check needs an operation"))?;
+    if !["delete", "rename", "move", "copy", "search"].contains(&operation.as_str()) {
+        return Err(usage_error(format!("unknown operation '{}'", operation)));
+    }
+    let paths = args.get(2..).unwrap_or_default();
+    if paths.is_empty() {
+        return Err(usage_error("rules check needs at least one path"));
+    }
+
+    let mut results = Vec::new();
+    for path in paths {
+        let path = absolute_path(Path::new(path))?;
+        let allowed = if operation == "search" {
+            session.config.is_searchable(&path)
+        } else {
+            session
+                .config
+                .operation_allowed(&RealFileSystem, &path, operation)
+        };
+        results.push((path, allowed));
+    }
+
+    if session.options.json {
+        let list = results
+            .iter()
+            .map(|(path, allowed)| {
+                Json::object([
+                    ("path", Json::from(path.as_path())),
+                    ("operation", Json::from(operation.as_str())),
+                    ("allowed", Json::from(*allowed)),
+                ])
+            })
+            .collect();
+        session.print_json(Json::Array(list))?;
+    } else {
+        for (path, allowed) in &results {
+            let verdict = if *allowed { "allowed" } else { "denied" };
+            session.print(format!("{:<8} {}", verdict, path.display()))?;
+        }
+    }
+    Ok(if results.iter().all(|(_, allowed)| *allowed) { 0 } else { 1 })
+}
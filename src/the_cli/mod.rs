@@ -0,0 +1,223 @@
+/*
+* Stygian Sift - A Terminal-based File Manager
+ * Copyright (C) 2024 Maui The Magnificent (Charon)
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published
+ * by the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+ * Contact: Maui_The_Magnificent@proton.me
+ * Project repository: https://github.com/Mauitron/StygianSift.git
+ */
+
+// The command line side of the core: `StygianSift ls`, `search`, `tag`, `undo`,
+// `config` and `rules` work on the same config and history as the browser,
+// for scripts. Every command prints lines for people or one JSON value with
+// --json.
+
+pub mod commands;
+
+pub use self::commands::*;
+pub use crate::the_core::*;
+use std::{
+    fmt::Display,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+pub const SUBCOMMANDS: &[&str] = &["ls", "search", "tag", "undo", "config", "rules"];
+
+pub const USAGE: &str = "\
+Usage: StygianSift <command> [--json] [--config PATH] [args]
+
+  ls [--sort ORDER] [PATH]      list a directory, ORDER is name, size, type,
+                                date or color with an optional -desc
+  search [--depth N] QUERY [PATH]
+                                fuzzy search below PATH, \"red:\" narrows to a color
+  tag set COLOR PATH...         color items
+  tag rm PATH...                take the color off items
+  tag ls [COLOR]                list colored items
+  undo list                     show the undo history, newest first
+  undo                          undo the newest operation
+  config get [KEY]              print one setting or all of them
+  config set KEY VALUE          change a setting
+  config check                  list the problems in the config file by line,
+                                exits with 1 if there are any
+  rules check OPERATION PATH... whether the color rules allow delete, rename,
+                                move, copy or search; exits with 1 if not";
+
+pub fn is_subcommand(arg: &str) -> bool {
+    SUBCOMMANDS.contains(&arg)
+}
+
+// Wrong arguments, as opposed to a command that failed.
+pub fn usage_error(message: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, message.into())
+}
+
+// 2 for wrong arguments, 1 for anything else that went wrong.
+pub fn exit_code(error: &io::Error) -> i32 {
+    if error.kind() == io::ErrorKind::InvalidInput {
+        2
+    } else {
+        1
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Options {
+    pub json: bool,
+    pub config_path: Option<PathBuf>,
+    pub sort: Option<SortOrder>,
+    pub depth: Option<usize>,
+    pub help: bool,
+    pub positional: Vec<String>,
+}
+
+impl Options {
+    // Options may come anywhere, "--" makes the rest positional.
+    pub fn parse(args: &[String]) -> io::Result<Self> {
+        let mut options = Options::default();
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            let mut value = |name: &str| {
+                args.next()
+                    .ok_or_else(|| usage_error(format!("{} needs a value", name)))
+            };
+            match arg.as_str() {
+                "--json" => options.json = true,
+                "--help" | "-h" => options.help = true,
+                "--config" => options.config_path = Some(PathBuf::from(value(arg)?)),
+                "--sort" => {
+                    let order = value(arg)?;
+                    options.sort = Some(
+                        SortOrder::parse(order)
+                            .ok_or_else(|| usage_error(format!("unknown sort order '{}'", order)))?,
+                    );
+                }
+                "--depth" => {
+                    let depth = value(arg)?;
+                    options.depth = Some(
+                        depth
+                            .parse()
+                            .map_err(|_| usage_error(format!("'{}' is not a depth", depth)))?,
+                    );
+                }
+                "--" => {
+                    options.positional.extend(args.by_ref().cloned());
+                }
+                flag if flag.starts_with("--") => {
+                    return Err(usage_error(format!("unknown option '{}'", flag)));
+                }
+                _ => options.positional.push(arg.clone()),
+            }
+        }
+        Ok(options)
+    }
+}
+
+// What every command works with: the config from the working directory or
+// --config, and where to write.
+pub struct Session<'a> {
+    pub config: Config,
+    pub config_path: PathBuf,
+    // What was wrong with the config file when it was loaded.
+    pub report: ConfigReport,
+    pub options: Options,
+    pub out: &'a mut dyn Write,
+}
+
+impl<'a> Session<'a> {
+    pub fn open(options: Options, out: &'a mut dyn Write) -> io::Result<Self> {
+        let config_path = match &options.config_path {
+            Some(path) => path.clone(),
+            None => Config::get_config_path()?,
+        };
+        let (config, report) = if config_path.exists() {
+            Config::load_checked(&config_path)?
+        } else {
+            (Config::new(), ConfigReport::default())
+        };
+        Ok(Session {
+            config,
+            config_path,
+            report,
+            options,
+            out,
+        })
+    }
+
+    pub fn save_config(&self) -> io::Result<()> {
+        self.config.save_to(&self.config_path)
+    }
+
+    // Merges in the .stygian files for dir, what is wrong with them goes to stderr.
+    pub fn enter_dir(&mut self, dir: &Path) {
+        for message in self.config.enter_dir(dir) {
+            eprintln!("{}", message);
+        }
+    }
+
+    pub fn print(&mut self, line: impl Display) -> io::Result<()> {
+        writeln!(self.out, "{}", line)
+    }
+
+    pub fn print_json(&mut self, value: Json) -> io::Result<()> {
+        writeln!(self.out, "{}", value)
+    }
+}
+
+// The absolute form of path, which is how the config remembers colors.
+// Symlinks are kept, only "." and ".." are folded away.
+pub fn absolute_path(path: &Path) -> io::Result<PathBuf> {
+    let mut absolute = PathBuf::new();
+    for component in std::path::absolute(path)?.components() {
+        match component {
+            std::path::Component::ParentDir => {
+                absolute.pop();
+            }
+            std::path::Component::CurDir => {}
+            other => absolute.push(other),
+        }
+    }
+    Ok(absolute)
+}
+
+// Runs one command line without the program name and gives the exit code.
+pub fn run(args: &[String], out: &mut dyn Write) -> io::Result<i32> {
+    let options = Options::parse(args)?;
+    if options.help {
+        writeln!(out, "{}", USAGE)?;
+        return Ok(0);
+    }
+    let Some((command, rest)) = options.positional.split_first() else {
+        return Err(usage_error(USAGE));
+    };
+    let (command, rest) = (command.clone(), rest.to_vec());
+    let mut session = Session::open(options, out)?;
+    // config check prints them itself.
+    if command != "config" || rest.first().map(String::as_str) != Some("check") {
+        for message in session.report.messages(&session.config_path) {
+            eprintln!("{}", message);
+        }
+    }
+    match command.as_str() {
+        "ls" => list_directory(&mut session, &rest),
+        "search" => search(&mut session, &rest),
+        "tag" => tag(&mut session, &rest),
+        "undo" => undo(&mut session, &rest),
+        "config" => config(&mut session, &rest),
+        "rules" => rules(&mut session, &rest),
+        other => Err(usage_error(format!("unknown command '{}'\n\n{}", other, USAGE))),
+    }
+}
@@ -0,0 +1,1698 @@
+/*
+* Stygian Sift - A Terminal-based File Manager
+ * Copyright (C) 2024 Maui The Magnificent (Charon)
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published
+ * by the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+ * Contact: Maui_The_Magnificent@proton.me
+ * Project repository: https://github.com/Mauitron/StygianSift.git
+ */
+
+use super::*;
+#[derive(Debug, Clone, PartialEq)]
+pub struct Config {
+    pub home_folder: Option<PathBuf>,
+    pub lines_shown: usize,
+    pub text_editor: String,
+    pub keybindings: Option<HashMap<KeySequence, Action>>,
+    pub shortcuts: Option<HashMap<char, (PathBuf, String, usize)>>,
+    pub default_sort: SortOrder,
+    pub ram_undo_limit: usize,
+    pub disk_undo_limit: u64,
+    pub allow_disk_undo: bool,
+    pub search_depth_limit: usize,
+    pub colored_items: HashMap<MarkerColor, HashSet<PathBuf>>,
+    pub color_rules: HashMap<MarkerColor, ColorRule>,
+    pub shortcut_layers: Vec<ShortcutLayer>,
+    pub current_layer: usize,
+    pub draw_simple_borders: bool,
+    pub max_distance: i32,
+    pub dim_step: u8,
+    pub time_format: String,
+    pub relative_times: bool,
+    pub file_type_rules: Vec<FileTypeRule>,
+    // A built-in theme name or the path of a theme file.
+    pub theme: String,
+    // auto, truecolor, 256, 16 or none.
+    pub color_depth: String,
+    // What <leader> in a key sequence stands for.
+    pub leader_key: Keystroke,
+    // Milliseconds to wait for the rest of a key sequence.
+    pub key_timeout: u64,
+    // Normal, visual and command modes over the keybindings.
+    pub modal_input: bool,
+    // What each mode binds on top of the keybindings.
+    pub mode_keybindings: BTreeMap<Mode, HashMap<KeySequence, Action>>,
+    pub show_hidden: bool,
+    // File names left out of listings and searches, * and ? match anything.
+    pub ignore_patterns: Vec<String>,
+    // Names the command prompt runs as a shell command line.
+    pub custom_commands: BTreeMap<String, String>,
+    // Recorded macros by name.
+    pub macros: BTreeMap<String, Vec<MacroStep>>,
+    // Settings changed for this session only, with the value to save instead
+    // and the value they were given.
+    pub overridden: HashMap<String, (String, String)>,
+    // The .stygian files merged in for the current directory.
+    pub local: Option<LocalOverlay>,
+    // The profile to start with, empty for none.
+    pub profile: String,
+    pub profiles: BTreeMap<String, Profile>,
+    // The profile swapped in right now.
+    pub active_profile: Option<String>,
+}
+
+impl Config {
+    pub fn new() -> Self {
+        Config {
+            home_folder: None,
+            lines_shown: 40,
+            default_sort: SortOrder::TypeAsc,
+            shortcuts: None,
+            text_editor: String::from(""),
+            keybindings: Some(Self::default_keybindings()),
+            ram_undo_limit: DEFAULT_RAM_LIMIT,
+            disk_undo_limit: DEFAULT_DISK_LIMIT,
+            allow_disk_undo: false,
+            search_depth_limit: 3,
+            colored_items: HashMap::new(),
+            color_rules: HashMap::new(),
+            shortcut_layers: (0..10)
+                .map(|i| ShortcutLayer::new(format!("Layer {}", i)))
+                .collect(),
+            current_layer: 0,
+            draw_simple_borders: false,
+            max_distance: 50,
+            dim_step: 3,     
+            time_format: DEFAULT_TIME_FORMAT.to_string(),
+            relative_times: true,
+            file_type_rules: Vec::new(),
+            theme: "emoji".to_string(),
+            color_depth: "auto".to_string(),
+            leader_key: Keystroke::new(Key::Char('\\'), Modifiers::NONE),
+            key_timeout: 500,
+            modal_input: false,
+            mode_keybindings: default_mode_keybindings(),
+            show_hidden: true,
+            ignore_patterns: Vec::new(),
+            custom_commands: BTreeMap::new(),
+            macros: BTreeMap::new(),
+            overridden: HashMap::new(),
+            local: None,
+            profile: String::new(),
+            profiles: BTreeMap::new(),
+            active_profile: None,
+        }
+    }
+    //////////////////////////////////////////////////KeyBindings//////////////////////////////////////////////////////////////////////
+#[rustfmt::skip]
+pub fn default_keybindings() -> HashMap<KeySequence, Action> {
+    let mut keybindings = HashMap::new();
+
+    //------------------------------------------------Navigation---------------------------------------------------------------------\\
+    keybindings.insert(Keystroke::new(Key::Up, Modifiers::NONE), Action::MoveUp);
+    keybindings.insert(Keystroke::new(Key::Down, Modifiers::NONE), Action::MoveDown);
+    keybindings.insert(Keystroke::new(Key::Left, Modifiers::NONE), Action::MoveLeft);
+    keybindings.insert(Keystroke::new(Key::Right, Modifiers::NONE), Action::MoveRight);
+    keybindings.insert(Keystroke::new(Key::Char('k'), Modifiers::NONE), Action::MoveUp);
+    keybindings.insert(Keystroke::new(Key::Char('j'), Modifiers::NONE), Action::MoveDown);
+    keybindings.insert(Keystroke::new(Key::Char('h'), Modifiers::NONE), Action::MoveLeft);
+    keybindings.insert(Keystroke::new(Key::Char('l'), Modifiers::NONE), Action::MoveRight);
+    keybindings.insert(Keystroke::new(Key::Enter, Modifiers::NONE), Action::Enter);
+
+    //-------------------------------------------------Selection---------------------------------------------------------------------\\
+    keybindings.insert(Keystroke::new(Key::Char('t'), Modifiers::CONTROL), Action::ToggleSelect);
+    keybindings.insert(Keystroke::new(Key::Char('K'), Modifiers::SHIFT), Action::MultiSelectUp);
+    keybindings.insert(Keystroke::new(Key::Char('J'), Modifiers::SHIFT), Action::MultiSelectDown);
+    keybindings.insert(Keystroke::new(Key::Up, Modifiers::SHIFT), Action::MultiSelectUp);
+    keybindings.insert(Keystroke::new(Key::Down, Modifiers::SHIFT), Action::MultiSelectDown);
+    keybindings.insert(Keystroke::new(Key::Char('a'), Modifiers::CONTROL), Action::SelectAll);
+
+    //----------------------------------------------File Operations------------------------------------------------------------------\\
+    keybindings.insert(Keystroke::new(Key::Char('r'), Modifiers::NONE), Action::Rename);
+    keybindings.insert(Keystroke::new(Key::Char('R'), Modifiers::SHIFT), Action::RenameWithoutExtension);
+    keybindings.insert(Keystroke::new(Key::Char('D'), Modifiers::SHIFT), Action::Murder);
+    keybindings.insert(Keystroke::new(Key::Char('p'), Modifiers::NONE), Action::Copy);
+    keybindings.insert(Keystroke::new(Key::Char('P'), Modifiers::SHIFT), Action::Paste);
+    keybindings.insert(Keystroke::new(Key::Char('d'), Modifiers::NONE), Action::Duplicate);
+    keybindings.insert(Keystroke::new(Key::Char('m'), Modifiers::NONE), Action::MoveItem);
+    keybindings.insert(Keystroke::new(Key::Char('b'), Modifiers::CONTROL), Action::GiveBirthDir);
+    keybindings.insert(Keystroke::new(Key::Char('B'), Modifiers::SHIFT), Action::GiveBirthFile);
+    keybindings.insert(Keystroke::new(Key::Char('x'), Modifiers::NONE), Action::ExtractMembers);
+    keybindings.insert(Keystroke::new(Key::Char('A'), Modifiers::SHIFT), Action::PackSelection);
+    keybindings.insert(Keystroke::new(Key::Char('X'), Modifiers::SHIFT), Action::ExtractArchive);
+    keybindings.insert(Keystroke::new(Key::Char('p'), Modifiers::CONTROL), Action::ChangePermissions);
+
+    //----------------------------------------------View and Display-----------------------------------------------------------------\\
+    keybindings.insert(Keystroke::new(Key::Char(' '), Modifiers::NONE), Action::TogglePreview);
+    keybindings.insert(Keystroke::new(Key::Char('L'), Modifiers::SHIFT), Action::SetLineAmount);
+    keybindings.insert(Keystroke::new(Key::Char('C'), Modifiers::SHIFT), Action::CycleItemColor);
+    keybindings.insert(Keystroke::new(Key::Char('c'), Modifiers::CONTROL), Action::RemoveItemColor);
+    keybindings.insert(Keystroke::new(Key::Char('<'), Modifiers::NONE), Action::DecreaseDimDistance);
+    keybindings.insert(Keystroke::new(Key::Char('>'), Modifiers::NONE), Action::IncreaseDimDistance);
+    
+    // - and = for intensity
+    keybindings.insert(Keystroke::new(Key::Char('?'), Modifiers::NONE), Action::DecreaseDimIntensity);
+    keybindings.insert(Keystroke::new(Key::Char('+'), Modifiers::NONE), Action::IncreaseDimIntensity);
+    keybindings.insert(Keystroke::new(Key::Char('='),Modifiers::NONE), Action::BorderStyle);
+
+    // { and } fold the structured preview, shift + arrows scroll its columns
+    keybindings.insert(Keystroke::new(Key::Char('{'), Modifiers::NONE), Action::PreviewFoldMore);
+    keybindings.insert(Keystroke::new(Key::Char('}'), Modifiers::NONE), Action::PreviewFoldLess);
+    keybindings.insert(Keystroke::new(Key::Left, Modifiers::SHIFT), Action::PreviewScrollLeft);
+    keybindings.insert(Keystroke::new(Key::Right, Modifiers::SHIFT), Action::PreviewScrollRight);
+
+    //---------------------------------------------Search and Sort-------------------------------------------------------------------\\
+    keybindings.insert(Keystroke::new(Key::Char('S'), Modifiers::SHIFT), Action::Search);
+    keybindings.insert(Keystroke::new(Key::Char('F'), Modifiers::SHIFT), Action::SearchFiles);
+    keybindings.insert(Keystroke::new(Key::Char('c'), Modifiers::NONE), Action::ToggleCount);
+    keybindings.insert(Keystroke::new(Key::Char('s'), Modifiers::NONE), Action::SortCycleForward);
+    keybindings.insert(Keystroke::new(Key::Tab, Modifiers::NONE), Action::ToggleFilters);
+
+    //---------------------------------------------System and Tools------------------------------------------------------------------\\
+    keybindings.insert(Keystroke::new(Key::Char(':'), Modifiers::NONE), Action::TerminalCommand);
+    keybindings.insert(Keystroke::new(Key::Char('z'), Modifiers::CONTROL), Action::Undo);
+    keybindings.insert(Keystroke::new(Key::Char(']'), Modifiers::NONE), Action::GitMenu);
+    keybindings.insert(Keystroke::new(Key::Char('|'), Modifiers::NONE), Action::ExecuteFile);
+    keybindings.insert(Keystroke::new(Key::Char('.'), Modifiers::NONE), Action::OpenInEditor);
+
+    //-----------------------------------------Help and Configuration----------------------------------------------------------------\\
+    keybindings.insert(Keystroke::new(Key::F(12), Modifiers::NONE), Action::Help);
+    keybindings.insert(Keystroke::new(Key::F(11), Modifiers::NONE), Action::ShowShortcuts);
+    keybindings.insert(Keystroke::new(Key::F(1),  Modifiers::SHIFT),Action::RenameLayer);
+    keybindings.insert(Keystroke::new(Key::F(2), Modifiers::SHIFT), Action::SetColorRules);
+    keybindings.insert(Keystroke::new(Key::Char('~'),Modifiers::NONE), Action::EditConfig);
+
+    //-------------------------------------------------Shortcuts---------------------------------------------------------------------\\
+    
+    //                                          <|Choose Shortcut Layer|>
+    keybindings.insert(Keystroke::new(Key::F(1), Modifiers::NONE),Action::SwitchLayer1);
+    keybindings.insert(Keystroke::new(Key::F(2), Modifiers::NONE),Action::SwitchLayer2);
+    keybindings.insert(Keystroke::new(Key::F(3), Modifiers::NONE),Action::SwitchLayer3);
+    keybindings.insert(Keystroke::new(Key::F(4), Modifiers::NONE),Action::SwitchLayer4);
+    keybindings.insert(Keystroke::new(Key::F(5), Modifiers::NONE),Action::SwitchLayer5);
+    keybindings.insert(Keystroke::new(Key::F(6), Modifiers::NONE),Action::SwitchLayer6);
+    keybindings.insert(Keystroke::new(Key::F(7), Modifiers::NONE),Action::SwitchLayer7);
+    keybindings.insert(Keystroke::new(Key::F(8), Modifiers::NONE),Action::SwitchLayer8);
+    keybindings.insert(Keystroke::new(Key::F(9), Modifiers::NONE),Action::SwitchLayer9);
+    keybindings.insert(Keystroke::new(Key::F(10), Modifiers::NONE),Action::SwitchLayer0);
+
+
+    //                                             <|Set Shortcuts|>
+    keybindings.insert(Keystroke::new(Key::Char('!'), Modifiers::NONE), Action::SetShortcut1);
+    keybindings.insert(Keystroke::new(Key::Char('@'), Modifiers::NONE), Action::SetShortcut2);
+    keybindings.insert(Keystroke::new(Key::Char('#'), Modifiers::NONE), Action::SetShortcut3);
+    keybindings.insert(Keystroke::new(Key::Char('$'), Modifiers::NONE), Action::SetShortcut4);
+    keybindings.insert(Keystroke::new(Key::Char('%'), Modifiers::NONE), Action::SetShortcut5);
+    keybindings.insert(Keystroke::new(Key::Char('^'), Modifiers::NONE), Action::SetShortcut6);
+    keybindings.insert(Keystroke::new(Key::Char('&'), Modifiers::NONE), Action::SetShortcut7);
+    keybindings.insert(Keystroke::new(Key::Char('*'), Modifiers::NONE), Action::SetShortcut8);
+    keybindings.insert(Keystroke::new(Key::Char('('), Modifiers::NONE), Action::SetShortcut9);
+    keybindings.insert(Keystroke::new(Key::Char(')'), Modifiers::NONE), Action::SetShortcut0);
+
+    //                                             <|Use Shortcuts|>
+    keybindings.insert(Keystroke::new(Key::Char('1'), Modifiers::NONE), Action::UseShortcut1);
+    keybindings.insert(Keystroke::new(Key::Char('2'), Modifiers::NONE), Action::UseShortcut2);
+    keybindings.insert(Keystroke::new(Key::Char('3'), Modifiers::NONE), Action::UseShortcut3);
+    keybindings.insert(Keystroke::new(Key::Char('4'), Modifiers::NONE), Action::UseShortcut4);
+    keybindings.insert(Keystroke::new(Key::Char('5'), Modifiers::NONE), Action::UseShortcut5);
+    keybindings.insert(Keystroke::new(Key::Char('6'), Modifiers::NONE), Action::UseShortcut6);
+    keybindings.insert(Keystroke::new(Key::Char('7'), Modifiers::NONE), Action::UseShortcut7);
+    keybindings.insert(Keystroke::new(Key::Char('8'), Modifiers::NONE), Action::UseShortcut8);
+    keybindings.insert(Keystroke::new(Key::Char('9'), Modifiers::NONE), Action::UseShortcut9);
+    keybindings.insert(Keystroke::new(Key::Char('0'), Modifiers::NONE), Action::UseShortcut0);
+
+    //---------------------------------------------------MISC------------------------------------------------------------------------\\
+    keybindings.insert(Keystroke::new(Key::Esc, Modifiers::NONE), Action::Quit);
+    keybindings.insert(Keystroke::new(Key::Char(' '), Modifiers::ALT), Action::CastCommandLineSpell);
+    keybindings.insert(Keystroke::new(Key::Char('k'), Modifiers::CONTROL), Action::CommandPalette);
+    keybindings.insert(Keystroke::new(Key::Char('q'), Modifiers::NONE), Action::RecordMacro);
+    keybindings.insert(Keystroke::new(Key::Char('Q'), Modifiers::SHIFT), Action::PlayMacro);
+
+    //-------------------------------------------------Sequences---------------------------------------------------------------------\\
+    let mut keybindings: HashMap<KeySequence, Action> =
+        keybindings.into_iter().map(|(key, action)| (key.into(), action)).collect();
+    let sequence = |keys: &[char]| KeySequence(keys.iter().map(|c| Keystroke::new(Key::Char(*c), Modifiers::NONE)).collect());
+    keybindings.insert(sequence(&['g', 'g']), Action::GoToTop);
+    keybindings.insert(sequence(&['g', 'e']), Action::GoToBottom);
+    keybindings.insert(sequence(&['g', 'h']), Action::GoHome);
+    keybindings.insert(sequence(&['y', 'p']), Action::YankPath);
+
+    keybindings
+}
+    // Binds key to action, next to any other keys of that action. Gives back
+    // what key was bound to before.
+    pub fn set_keybinding(&mut self, key: KeySequence, action: Action) -> Option<Action> {
+        self.keybindings
+            .get_or_insert_with(HashMap::new)
+            .insert(key, action)
+    }
+
+    // What binding key to action would get in the way of.
+    pub fn conflicts_with(&self, key: &KeySequence, action: &Action) -> Vec<KeyConflict> {
+        let mut keybindings: Vec<_> = self.keybindings.iter().flatten().collect();
+        keybindings.sort_by_key(|(keys, _)| keys.to_string());
+        keybindings
+            .into_iter()
+            .filter_map(|bound| KeyConflict::between((key, action), bound, self.leader_key))
+            .collect()
+    }
+
+    // Keys that get in each other's way, in the keybindings and in the
+    // bindings of each mode.
+    pub fn key_conflicts(&self) -> Vec<KeyConflict> {
+        let mut conflicts = key_conflicts(self.keybindings.iter().flatten(), self.leader_key);
+        for keybindings in self.mode_keybindings.values() {
+            conflicts.extend(key_conflicts(keybindings, self.leader_key));
+        }
+        conflicts
+    }
+
+    // Actions no key runs.
+    pub fn unbound_actions(&self) -> Vec<&'static Action> {
+        let bound: Vec<&Action> = self
+            .keybindings
+            .iter()
+            .flatten()
+            .map(|(_, action)| action)
+            .chain(
+                self.mode_keybindings
+                    .values()
+                    .filter(|_| self.modal_input)
+                    .flat_map(|keybindings| keybindings.values()),
+            )
+            .collect();
+        Action::iter().filter(|action| !bound.contains(action)).collect()
+    }
+
+    // The keybindings and mode bindings on their own, to share a binding set.
+    pub fn export_keybindings(&self) -> String {
+        let mut lines = Vec::new();
+        if let Some(keybindings) = &self.keybindings {
+            keybinding_table(&mut lines, "keybindings", keybindings);
+        }
+        for (mode, keybindings) in &self.mode_keybindings {
+            keybinding_table(&mut lines, &format!("keybindings.{}", mode), keybindings);
+        }
+        lines.join("\n").trim_start().to_string() + "\n"
+    }
+
+    // Takes the tables of an exported binding set, each one replacing the
+    // bindings it covers. Nothing is taken if the set has mistakes.
+    pub fn import_keybindings(&mut self, text: &str) -> Vec<TomlError> {
+        let (entries, mut errors) = parse_toml(text);
+        for entry in &entries {
+            if entry.path[0] != "keybindings" {
+                errors.push(TomlError::new(
+                    entry.line,
+                    format!("'{}' is not a keybinding", entry.path.join(".")),
+                ));
+            }
+        }
+        let (parsed, more) = Config::from_toml_entries(&entries);
+        errors.extend(more);
+        errors.sort_by_key(|error| error.line);
+        if !errors.is_empty() {
+            return errors;
+        }
+        if entries.iter().any(|entry| entry.path.len() == 2) {
+            self.keybindings = parsed.keybindings;
+        }
+        for mode in MODES {
+            if entries.iter().any(|entry| entry.path.get(1) == Some(&mode.to_string())) {
+                let keybindings = parsed.mode_keybindings.get(&mode).cloned().unwrap_or_default();
+                self.mode_keybindings.insert(mode, keybindings);
+            }
+        }
+        errors
+    }
+
+    pub fn remove_keybinding(&mut self, key: &KeySequence) {
+        if let Some(kb) = self.keybindings.as_mut() {
+            kb.remove(key);
+        }
+    }
+    pub fn reset_keybindings(&mut self) {
+        self.keybindings = Some(Self::default_keybindings());
+    }
+
+    pub fn get_keybindings(&self) -> Option<&HashMap<KeySequence, Action>> {
+        self.keybindings.as_ref()
+    }
+
+    // Every key bound to action, shortest first.
+    pub fn keys_for(&self, action: &Action) -> Vec<&KeySequence> {
+        let mut keys: Vec<&KeySequence> = self
+            .keybindings
+            .iter()
+            .flatten()
+            .filter(|(_, bound)| *bound == action)
+            .map(|(keys, _)| keys)
+            .collect();
+        keys.sort_by_key(|keys| (keys.keys().len(), keys.to_string()));
+        keys
+    }
+
+    // The bindings in mode. The command line has nothing but its own, the
+    // other modes add theirs to the keybindings with modal_input on. A key
+    // a mode binds hides the longer keybindings starting with it, so that
+    // it does not wait for the rest of them.
+    pub fn key_trie(&self, mode: Mode) -> KeyTrie {
+        let global = self.keybindings.iter().flatten();
+        let own = self.mode_keybindings.get(&mode).into_iter().flatten();
+        match mode {
+            Mode::Command => KeyTrie::new(own, self.leader_key),
+            _ if !self.modal_input => KeyTrie::new(global, self.leader_key),
+            _ => {
+                let hidden = |keys: &KeySequence| {
+                    self.mode_keybindings[&mode]
+                        .keys()
+                        .any(|own| keys.keys().starts_with(own.keys()))
+                };
+                let global = global.filter(|(keys, _)| !hidden(keys));
+                KeyTrie::new(global.chain(own), self.leader_key)
+            }
+        }
+    }
+
+    pub fn key_timeout(&self) -> Duration {
+        Duration::from_millis(self.key_timeout)
+    }
+
+    /////////////////////////////////////////////////!KeyBindings!/////////////////////////////////////////////////////////////////////
+     pub fn get_current_layer_info(&self) -> (usize, &str) {
+        (
+            self.current_layer,
+            &self.shortcut_layers[self.current_layer].name
+        )
+    }
+
+    pub fn switch_layer(&mut self, index: usize) {
+        // Ensure the layer exists
+        while self.shortcut_layers.len() <= index {
+            let new_layer_name = format!("Layer {}", self.shortcut_layers.len());
+            self.shortcut_layers.push(ShortcutLayer::new(new_layer_name));
+        }
+
+        self.current_layer = index;
+    }
+    
+       pub fn set_shortcut_in_layer(
+        &mut self,
+        layer_index: usize,
+        key: char,
+        path: PathBuf,
+        name: String,
+        index: usize,
+    ) -> io::Result<()> {
+        if layer_index < self.shortcut_layers.len() {
+            let layer = &mut self.shortcut_layers[layer_index];
+            layer.shortcuts.get_or_insert_with(HashMap::new)
+                .insert(key, (path, name, index));
+            Ok(())
+        } else {
+            Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Invalid layer index",
+            ))
+        }
+    }
+
+    pub fn get_shortcut_from_layer(
+        &self,
+        layer_index: usize,
+        key: char,
+    ) -> Option<&(PathBuf, String, usize)> {
+        self.shortcut_layers.get(layer_index)
+            .and_then(|layer| layer.shortcuts.as_ref())
+            .and_then(|shortcuts| shortcuts.get(&key))
+    }
+
+
+    pub fn add_new_layer(&mut self, name: String) -> usize {
+        self.shortcut_layers.push(ShortcutLayer::new(name));
+        self.shortcut_layers.len() - 1
+    }
+
+    pub fn rename_layer(&mut self, layer_index: usize, new_name: String) -> io::Result<()> {
+        if layer_index < self.shortcut_layers.len() {
+            self.shortcut_layers[layer_index].name = new_name;
+            Ok(())
+        } else {
+            Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Invalid: Layer Doesn't Exist",
+            ))
+        }
+    }
+    pub fn get_action(&self, key: &KeySequence) -> Option<&Action> {
+        self.keybindings.as_ref().and_then(|kb| kb.get(key))
+    }
+    pub fn set_home_folder(&mut self, path: Option<PathBuf>) {
+        self.home_folder = path;
+    }
+
+    pub fn set_item_color(&mut self, path: PathBuf, color: MarkerColor) {
+        for items in self.colored_items.values_mut() {
+            items.remove(&path);
+        }
+        self.colored_items
+            .entry(color)
+            .or_insert_with(HashSet::new)
+            .insert(path);
+    }
+
+    pub fn remove_item_color(&mut self, path: &Path) {
+        for items in self.colored_items.values_mut() {
+            items.remove(path);
+        }
+    }
+
+    pub fn get_item_color(&self, path: &Path) -> Option<MarkerColor> {
+        for (color, items) in &self.colored_items {
+            if items.contains(path) {
+                return Some(*color);
+            }
+        }
+        None
+    }
+    pub fn save_config(&self) -> io::Result<()> {
+        self.save_to(&Self::get_config_path()?)
+    }
+
+    pub fn save_to(&self, config_path: &Path) -> io::Result<()> {
+        fs::write(config_path, self.saved_toml())
+    }
+
+    // The file as saving writes it, without what only holds for this session.
+    pub fn saved_toml(&self) -> String {
+        if self.local.is_none() && self.active_profile.is_none() && self.overridden.is_empty() {
+            return self.to_toml();
+        }
+        // Undone in the opposite order they are put on in.
+        let mut saved = self.clone();
+        saved.leave_local();
+        for (key, (before, given)) in std::mem::take(&mut saved.overridden) {
+            // Changed again since, the session value is the one to keep.
+            if saved.get_value(&key).as_deref() == Some(given.as_str()) {
+                let _ = saved.set_value(&key, &before);
+            }
+        }
+        let _ = saved.use_profile(None);
+        saved.to_toml()
+    }
+
+    pub fn to_toml(&self) -> String {
+        let mut lines = vec![
+            format!("schema_version = {}", CONFIG_SCHEMA_VERSION),
+            String::new(),
+        ];
+        for key in SETTINGS {
+            if *key == "home_folder" && self.home_folder.is_none() {
+                continue;
+            }
+            let value = self.get_value(key).unwrap_or_default();
+            if setting_kind(key) == "a string" {
+                lines.push(format!("{} = {}", key, toml_string(&value)));
+            } else {
+                lines.push(format!("{} = {}", key, value));
+            }
+        }
+        if !self.ignore_patterns.is_empty() {
+            let patterns = self
+                .ignore_patterns
+                .iter()
+                .map(|pattern| TomlValue::String(pattern.clone()))
+                .collect();
+            lines.push(format!("ignore_patterns = {}", TomlValue::Array(patterns)));
+        }
+
+        if let Some(keybindings) = &self.keybindings {
+            keybinding_table(&mut lines, "keybindings", keybindings);
+        }
+        for (mode, keybindings) in &self.mode_keybindings {
+            keybinding_table(&mut lines, &format!("keybindings.{}", mode), keybindings);
+        }
+
+        let mut colors: Vec<&MarkerColor> = self.colored_items.keys().collect();
+        colors.sort_by_key(|color| color.as_str());
+        if !colors.is_empty() {
+            lines.push(String::new());
+            lines.push("[colored_items]".to_string());
+        }
+        for color in colors {
+            let mut paths: Vec<&PathBuf> = self.colored_items[color].iter().collect();
+            paths.sort();
+            let paths = paths
+                .into_iter()
+                .map(|path| TomlValue::String(path.display().to_string()))
+                .collect();
+            lines.push(format!("{} = {}", color.as_str(), TomlValue::Array(paths)));
+        }
+
+        let mut rules: Vec<(&MarkerColor, &ColorRule)> = self.color_rules.iter().collect();
+        rules.sort_by_key(|(color, _)| color.as_str());
+        for (color, rule) in rules {
+            lines.push(String::new());
+            lines.push(format!("[color_rules.{}]", color.as_str()));
+            lines.push(format!("allow_delete = {}", rule.allow_delete));
+            lines.push(format!("allow_rename = {}", rule.allow_rename));
+            lines.push(format!("allow_move = {}", rule.allow_move));
+            lines.push(format!("allow_copy = {}", rule.allow_copy));
+            lines.push(format!("include_in_search = {}", rule.include_in_search));
+        }
+
+        if let Some(shortcuts) = &self.shortcuts {
+            shortcut_tables(&mut lines, "shortcuts", shortcuts);
+        }
+        layer_tables(&mut lines, "layers", &self.shortcut_layers);
+
+        if !self.custom_commands.is_empty() {
+            lines.push(String::new());
+            lines.push("[commands]".to_string());
+            for (name, command) in &self.custom_commands {
+                lines.push(format!("{} = {}", toml_key(name), toml_string(command)));
+            }
+        }
+
+        for (name, steps) in &self.macros {
+            for (i, step) in steps.iter().enumerate() {
+                lines.push(String::new());
+                lines.push(format!("[macros.{}.{}]", toml_key(name), i));
+                lines.push(format!("action = {}", toml_string(&step.call.action.to_string())));
+                if !step.call.args.is_empty() {
+                    let args = step
+                        .call
+                        .args
+                        .iter()
+                        .map(|arg| TomlValue::String(arg.clone()))
+                        .collect();
+                    lines.push(format!("args = {}", TomlValue::Array(args)));
+                }
+                if !step.input.is_empty() {
+                    lines.push(format!("input = {}", toml_string(&input_text(&step.input))));
+                }
+            }
+        }
+
+        if !self.file_type_rules.is_empty() {
+            lines.push(String::new());
+            lines.push("[file_types]".to_string());
+            for rule in &self.file_type_rules {
+                lines.push(format!(
+                    "{} = {}",
+                    toml_key(&rule.pattern),
+                    toml_string(&format!("{:?}", rule.file_type))
+                ));
+            }
+        }
+
+        for (name, profile) in &self.profiles {
+            profile.to_toml(name, &mut lines);
+        }
+
+        lines.push(String::new());
+        lines.join("\n")
+    }
+
+    pub fn load_config() -> io::Result<Self> {
+        Self::load_from(&Self::get_config_path()?)
+    }
+
+    pub fn load_from(config_path: &Path) -> io::Result<Self> {
+        Self::load_checked(config_path).map(|(config, _)| config)
+    }
+
+    // Loads what it can and reports the rest. A file in the old line format
+    // is kept next to it as <name>.old and rewritten in the current one.
+    pub fn load_checked(config_path: &Path) -> io::Result<(Self, ConfigReport)> {
+        let content = fs::read_to_string(config_path)?;
+        let (entries, syntax_errors) = parse_toml(&content);
+        let versioned = entries.iter().any(|entry| entry.path == ["schema_version"]);
+
+        let mut report = ConfigReport::default();
+        let config = if !versioned && !syntax_errors.is_empty() {
+            let (config, errors) = Self::parse_legacy(&content);
+            let mut backup = config_path.as_os_str().to_owned();
+            backup.push(".old");
+            let backup = PathBuf::from(backup);
+            fs::copy(config_path, &backup)?;
+            config.save_to(config_path)?;
+            report.errors = errors;
+            report.migrated_to = Some(backup);
+            config
+        } else {
+            let (config, errors) = Self::from_toml_entries(&entries);
+            report.errors = syntax_errors;
+            report.errors.extend(errors);
+            report.errors.sort_by_key(|error| error.line);
+            config
+        };
+
+        set_file_type_rules(&config.file_type_rules);
+        Ok((config, report))
+    }
+
+    pub fn from_toml(text: &str) -> (Self, Vec<TomlError>) {
+        let (entries, mut errors) = parse_toml(text);
+        let (config, more) = Self::from_toml_entries(&entries);
+        errors.extend(more);
+        errors.sort_by_key(|error| error.line);
+        (config, errors)
+    }
+
+    pub fn from_toml_entries(entries: &[TomlEntry]) -> (Self, Vec<TomlError>) {
+        let mut config = Config::new();
+        let mut errors = Vec::new();
+        let mut current_layer = None;
+        let mut profile = None;
+        let mut profile_entries: BTreeMap<String, Vec<TomlEntry>> = BTreeMap::new();
+        let mut keybindings_read = false;
+        let mut modes_read: HashSet<Mode> = HashSet::new();
+        let mut key_lines: HashMap<(Option<Mode>, KeySequence), usize> = HashMap::new();
+        let mut shortcuts: Vec<(Option<usize>, char, PartialShortcut)> = Vec::new();
+        let mut macro_steps: BTreeMap<String, BTreeMap<usize, PartialStep>> = BTreeMap::new();
+
+        for entry in entries {
+            let path: Vec<&str> = entry.path.iter().map(String::as_str).collect();
+            let fail = |message: String| TomlError::new(entry.line, message);
+            let result = match path.as_slice() {
+                ["schema_version"] => match entry.value {
+                    TomlValue::Integer(version) if version > CONFIG_SCHEMA_VERSION => Err(fail(
+                        format!(
+                            "schema_version {} is newer than this version understands ({})",
+                            version, CONFIG_SCHEMA_VERSION
+                        ),
+                    )),
+                    TomlValue::Integer(version) if version >= 1 => Ok(()),
+                    _ => Err(fail(format!("'{}' is not a schema version", entry.value))),
+                },
+                ["current_layer"] => {
+                    // Checked once all layers are there.
+                    current_layer = Some(entry);
+                    Ok(())
+                }
+                ["profile"] => {
+                    // Checked once all profiles are there.
+                    profile = Some(entry);
+                    Ok(())
+                }
+                ["profiles", name, rest @ ..] if !rest.is_empty() => {
+                    let mut stripped = entry.clone();
+                    stripped.path.drain(..2);
+                    profile_entries
+                        .entry(name.to_string())
+                        .or_default()
+                        .push(stripped);
+                    Ok(())
+                }
+                [key] if SETTINGS.contains(key) => setting_text(key, &entry.value)
+                    .and_then(|value| config.set_value(key, &value))
+                    .map_err(fail),
+                ["keybindings", key] => match KeySequence::parse(key) {
+                    None => Err(fail(format!("unknown key '{}'", key))),
+                    Some(sequence) => match key_lines.insert((None, sequence.clone()), entry.line) {
+                        Some(line) => Err(fail(format!(
+                            "'{}' is bound on line {} already",
+                            sequence, line
+                        ))),
+                        None => text_value(&entry.value, "a keybinding")
+                            .and_then(|action| {
+                                Action::from_str(&action)
+                                    .map_err(|_| format!("unknown action '{}'", action))
+                            })
+                            .map(|action| {
+                                let keybindings =
+                                    config.keybindings.get_or_insert_with(HashMap::new);
+                                // A keybindings table replaces the defaults.
+                                if !keybindings_read {
+                                    keybindings.clear();
+                                    keybindings_read = true;
+                                }
+                                keybindings.insert(sequence, action);
+                            })
+                            .map_err(fail),
+                    },
+                },
+                ["keybindings", mode, key] => match (Mode::parse(mode), KeySequence::parse(key)) {
+                    (None, _) => Err(fail(format!("unknown mode '{}'", mode))),
+                    (_, None) => Err(fail(format!("unknown key '{}'", key))),
+                    (Some(mode), Some(sequence)) => match key_lines
+                        .insert((Some(mode), sequence.clone()), entry.line)
+                    {
+                        Some(line) => Err(fail(format!(
+                            "'{}' is bound on line {} already",
+                            sequence, line
+                        ))),
+                        None => text_value(&entry.value, "a keybinding")
+                            .and_then(|action| {
+                                Action::from_str(&action)
+                                    .map_err(|_| format!("unknown action '{}'", action))
+                            })
+                            .map(|action| {
+                                let keybindings = config.mode_keybindings.entry(mode).or_default();
+                                // Each mode table replaces the defaults of that mode.
+                                if modes_read.insert(mode) {
+                                    keybindings.clear();
+                                }
+                                keybindings.insert(sequence, action);
+                            })
+                            .map_err(fail),
+                    },
+                },
+                ["colored_items", color] => marker_color(color)
+                    .and_then(|color| {
+                        let paths = text_array(&entry.value, "colored items")?;
+                        config
+                            .colored_items
+                            .entry(color)
+                            .or_default()
+                            .extend(paths.into_iter().map(PathBuf::from));
+                        Ok(())
+                    })
+                    .map_err(fail),
+                ["color_rules", color, field] => marker_color(color)
+                    .and_then(|color| {
+                        let allowed = match entry.value {
+                            TomlValue::Boolean(allowed) => allowed,
+                            ref value => {
+                                return Err(format!(
+                                    "{} should be a boolean, not {}",
+                                    field,
+                                    value.kind()
+                                ))
+                            }
+                        };
+                        let rule = config.color_rules.entry(color).or_default();
+                        match *field {
+                            "allow_delete" => rule.allow_delete = allowed,
+                            "allow_rename" => rule.allow_rename = allowed,
+                            "allow_move" => rule.allow_move = allowed,
+                            "allow_copy" => rule.allow_copy = allowed,
+                            "include_in_search" => rule.include_in_search = allowed,
+                            _ => return Err(format!("unknown color rule '{}'", field)),
+                        }
+                        Ok(())
+                    })
+                    .map_err(fail),
+                ["shortcuts", key, field] => {
+                    shortcut_field(&mut shortcuts, None, key, field, entry).map_err(fail)
+                }
+                ["layers", index, "name"] => layer_index(index)
+                    .and_then(|index| {
+                        let name = text_value(&entry.value, "a layer name")?;
+                        while config.shortcut_layers.len() <= index {
+                            config.add_new_layer("New Layer".to_string());
+                        }
+                        config.shortcut_layers[index].name = name;
+                        Ok(())
+                    })
+                    .map_err(fail),
+                ["layers", index, "shortcuts", key, field] => layer_index(index)
+                    .and_then(|index| shortcut_field(&mut shortcuts, Some(index), key, field, entry))
+                    .map_err(fail),
+                ["ignore_patterns"] => text_array(&entry.value, "ignore_patterns")
+                    .map(|patterns| config.ignore_patterns = patterns)
+                    .map_err(fail),
+                ["commands", name] => text_value(&entry.value, "a command")
+                    .map(|command| {
+                        config.custom_commands.insert(name.to_string(), command);
+                    })
+                    .map_err(fail),
+                ["macros", name, index, field] => {
+                    macro_field(&mut macro_steps, name, index, field, entry).map_err(fail)
+                }
+                ["file_types", pattern] => text_value(&entry.value, "a file type")
+                    .and_then(|file_type| {
+                        FileTypeRule::parse(pattern, &file_type)
+                            .ok_or_else(|| format!("unknown file type '{}'", file_type))
+                    })
+                    .map(|rule| config.file_type_rules.push(rule))
+                    .map_err(fail),
+                _ => Err(fail(format!("unknown setting '{}'", entry.path.join(".")))),
+            };
+            if let Err(error) = result {
+                errors.push(error);
+            }
+        }
+
+        for (layer, key, shortcut) in shortcuts {
+            let Some(path) = shortcut.path else {
+                errors.push(TomlError::new(
+                    shortcut.line,
+                    format!("shortcut '{}' has no path", key),
+                ));
+                continue;
+            };
+            let value = (path, shortcut.name, shortcut.index);
+            match layer {
+                None => {
+                    config
+                        .shortcuts
+                        .get_or_insert_with(HashMap::new)
+                        .insert(key, value);
+                }
+                Some(index) => {
+                    while config.shortcut_layers.len() <= index {
+                        config.add_new_layer("New Layer".to_string());
+                    }
+                    config.shortcut_layers[index]
+                        .shortcuts
+                        .get_or_insert_with(HashMap::new)
+                        .insert(key, value);
+                }
+            }
+        }
+        for (name, steps) in macro_steps {
+            let mut complete = Vec::new();
+            for (index, step) in steps {
+                match step.action {
+                    Some(action) => complete.push(MacroStep {
+                        call: CommandCall {
+                            action,
+                            args: step.args,
+                        },
+                        input: step.input,
+                    }),
+                    None if step.unknown_action => {}
+                    None => errors.push(TomlError::new(
+                        step.line,
+                        format!("step {} of macro '{}' has no action", index, name),
+                    )),
+                }
+            }
+            config.macros.insert(name, complete);
+        }
+        // Keys from the file that get in the way of others, reported on the
+        // later line of the two.
+        let mut tables: Vec<(Option<Mode>, &HashMap<KeySequence, Action>)> =
+            config.keybindings.iter().map(|table| (None, table)).collect();
+        tables.extend(config.mode_keybindings.iter().map(|(mode, table)| (Some(*mode), table)));
+        for (mode, table) in tables {
+            for conflict in key_conflicts(table, config.leader_key) {
+                let line = |keys: &KeySequence| key_lines.get(&(mode, keys.clone())).copied();
+                if let Some(line) = line(&conflict.keys).max(line(&conflict.other)) {
+                    errors.push(TomlError::new(line, conflict.to_string()));
+                }
+            }
+        }
+        for (name, entries) in profile_entries {
+            let (parsed, more) = Profile::from_entries(&entries);
+            config.profiles.insert(name, parsed);
+            errors.extend(more);
+        }
+        if let Some(entry) = profile {
+            if let Err(message) = setting_text("profile", &entry.value)
+                .and_then(|value| config.set_value("profile", &value))
+            {
+                errors.push(TomlError::new(entry.line, message));
+            }
+        }
+        if let Some(entry) = current_layer {
+            if let Err(message) = setting_text("current_layer", &entry.value)
+                .and_then(|value| config.set_value("current_layer", &value))
+            {
+                errors.push(TomlError::new(entry.line, message));
+            }
+        }
+        (config, errors)
+    }
+
+    // The line format used before schema_version, only read to migrate it.
+    fn parse_legacy(content: &str) -> (Self, Vec<TomlError>) {
+        let mut config = Config::new();
+        let mut errors = Vec::new();
+
+        let mut current_section: Option<&str> = None;
+        let mut current_color: Option<MarkerColor> = None;
+
+        for (number, line) in content.lines().enumerate() {
+            let trimmed_line = line.trim();
+            if trimmed_line.is_empty() {
+                continue;
+            }
+            if trimmed_line == "keybindings:" {
+                current_section = Some("keybindings");
+                continue;
+            } else if trimmed_line == "colored_items:" {
+                current_section = Some("colored_items");
+                continue;
+            }
+            if trimmed_line == "shortcut_layers:" {
+                current_section = Some("shortcut_layers");
+                continue;
+            }
+            if trimmed_line == "shortcuts:" {
+                current_section = Some("shortcuts");
+                continue;
+            } else if trimmed_line == "keybindings:" || trimmed_line == "colored_items:" {
+                current_section = Some(trimmed_line.trim_end_matches(':'));
+                continue;
+            }
+            if trimmed_line == "file_types:" {
+                current_section = Some("file_types");
+                continue;
+            }
+
+            match current_section {
+                Some("file_types") => {
+                    if let Some(rule) = trimmed_line
+                        .split_once('=')
+                        .and_then(|(pattern, file_type)| FileTypeRule::parse(pattern, file_type))
+                    {
+                        config.file_type_rules.push(rule);
+                    }
+                }
+                Some("shortcut_layers") => {
+                    if trimmed_line.starts_with("layer_") {
+                        if let Some((key, value)) = trimmed_line.split_once('=') {
+                            let key = key.trim();
+                            let value = value.trim();
+
+                            if key.ends_with("_name") {
+                                let layer_index = key
+                                    .strip_prefix("layer_")
+                                    .and_then(|s| s.strip_suffix("_name"))
+                                    .and_then(|s| s.parse::<usize>().ok());
+
+                                if let Some(index) = layer_index {
+                                    while config.shortcut_layers.len() <= index {
+                                        config.add_new_layer("New Layer".to_string());
+                                    }
+                                    config.shortcut_layers[index].name = value.to_string();
+                                }
+                            } else if key.contains("_shortcut_") {
+                                let parts: Vec<&str> = key.split('_').collect();
+                                if parts.len() >= 4 {
+                                    if let (Ok(layer_index), Some(shortcut_key)) =
+                                        (parts[1].parse::<usize>(), parts[3].chars().next())
+                                    {
+                                        let parts: Vec<&str> = value.splitn(3, '|').collect();
+                                        if parts.len() == 3 {
+                                            let path = PathBuf::from(parts[0].trim());
+                                            let name = parts[1].trim().to_string();
+                                            let index = parts[2].trim().parse().unwrap_or(0);
+                                            let _ = config.set_shortcut_in_layer(
+                                                layer_index,
+                                                shortcut_key,
+                                                path,
+                                                name,
+                                                index,
+                                            );
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    } else if trimmed_line.starts_with("current_layer = ") {
+                        if let Some(value) = trimmed_line.strip_prefix("current_layer = ") {
+                            config.current_layer = value.parse().unwrap_or(0);
+                        }
+                    }
+                }
+                Some("shortcuts") => {
+                    if let Some((key, value)) = trimmed_line.split_once('=') {
+                        let key = key.trim().chars().next().unwrap();
+                        let parts: Vec<&str> = value.trim().splitn(3, '|').collect();
+                        if parts.len() == 3 {
+                            let path = PathBuf::from(parts[0].trim());
+                            let name = parts[1].trim().to_string();
+                            let index = parts[2].trim().parse().unwrap_or(0);
+                            config
+                                .shortcuts
+                                .get_or_insert_with(HashMap::new)
+                                .insert(key, (path, name, index));
+                        }
+                    }
+                }
+                Some("keybindings") => {
+                    // From the right, "=" can be a key but not an action.
+                    if let Some((key, action)) = trimmed_line.rsplit_once('=') {
+                        let key_event = Keystroke::parse(key);
+                        if let Ok(action) = Action::from_str(action.trim()) {
+                            // The old browser waited for a second key after
+                            // g, these two were saved as "g g" and "g e".
+                            let g = Keystroke::new(Key::Char('g'), Modifiers::NONE);
+                            let sequence = match (key_event, &action) {
+                                (key, Action::GoToTop) if key == g => KeySequence(vec![g, g]),
+                                (key, Action::GoToBottom)
+                                    if key == Keystroke::new(Key::Char('e'), Modifiers::NONE) =>
+                                {
+                                    KeySequence(vec![g, key])
+                                }
+                                (key, _) => key.into(),
+                            };
+                            config
+                                .keybindings
+                                .get_or_insert_with(HashMap::new)
+                                .insert(sequence, action);
+                        }
+                    }
+                }
+                Some("colored_items") => {
+                    if trimmed_line.ends_with(':') {
+                        current_color =
+                            MarkerColor::parse(&trimmed_line[..trimmed_line.len() - 1]);
+                    } else if trimmed_line.contains("_rule =") {
+                        if let Some(color) = current_color {
+                            let parts: Vec<&str> = trimmed_line.split('=').collect();
+                            if parts.len() == 2 {
+                                let values: Vec<&str> = parts[1].split(',').collect();
+                                if values.len() == 5 {
+                                    let rule = ColorRule {
+                                        allow_delete: values[0].trim().parse().unwrap_or(true),
+                                        allow_rename: values[1].trim().parse().unwrap_or(true),
+                                        allow_move: values[2].trim().parse().unwrap_or(true),
+                                        allow_copy: values[3].trim().parse().unwrap_or(true),
+                                        include_in_search: values[4].trim().parse().unwrap_or(true),
+                                    };
+                                    config.color_rules.insert(color, rule);
+                                }
+                            }
+                        }
+                    } else if let Some(color) = current_color {
+                        config
+                            .colored_items
+                            .entry(color)
+                            .or_insert_with(HashSet::new)
+                            .insert(PathBuf::from(trimmed_line));
+                    }
+                }
+                _ => {
+                    if let Some((key, value)) = trimmed_line.split_once('=') {
+                        // A value that does not parse keeps the default.
+                        if let Err(message) = config.set_value(key.trim(), value.trim()) {
+                            errors.push(TomlError::new(number + 1, message));
+                        }
+                    }
+                }
+            }
+        }
+
+        (config, errors)
+    }
+
+    pub fn get_config_path() -> std::io::Result<PathBuf> {
+        let mut path = std::env::current_dir()?;
+        path.push(".maui");
+        Ok(path)
+    }
+}
+
+//////////////////////////////////////////////////////////////////Settings/////////////////////////////////////////////////////////////////////////////
+// The single valued settings by the name they are saved under.
+pub const SETTINGS: &[&str] = &[
+    "home_folder",
+    "lines_shown",
+    "default_sort",
+    "text_editor",
+    "ram_undo_limit",
+    "disk_undo_limit",
+    "allow_disk_undo",
+    "search_depth_limit",
+    "show_hidden",
+    "draw_simple_borders",
+    "max_distance",
+    "dim_step",
+    "time_format",
+    "relative_times",
+    "theme",
+    "color_depth",
+    "leader_key",
+    "key_timeout",
+    "modal_input",
+    "profile",
+    "current_layer",
+];
+
+fn invalid_value(key: &str, value: &str) -> String {
+    format!("'{}' is not a valid value for {}", value, key)
+}
+
+fn parse_setting<T: FromStr>(key: &str, value: &str) -> Result<T, String> {
+    value.parse().map_err(|_| invalid_value(key, value))
+}
+
+fn not_empty(key: &str, value: &str) -> Result<String, String> {
+    if value.is_empty() {
+        Err(format!("{} can not be empty", key))
+    } else {
+        Ok(value.to_string())
+    }
+}
+
+/////////////////////////////////////////////////////////////////ConfigFile///////////////////////////////////////////////////////////////////////////
+pub const CONFIG_SCHEMA_VERSION: i64 = 1;
+
+// What went wrong while loading, to be shown at startup.
+#[derive(Debug, Default)]
+pub struct ConfigReport {
+    pub errors: Vec<TomlError>,
+    // Where the file in the old format was kept when it was migrated.
+    pub migrated_to: Option<PathBuf>,
+}
+
+impl ConfigReport {
+    pub fn messages(&self, config_path: &Path) -> Vec<String> {
+        let mut messages = Vec::new();
+        if let Some(backup) = &self.migrated_to {
+            messages.push(format!(
+                "{} was converted to the new format, the old one is kept as {}",
+                config_path.display(),
+                backup.display()
+            ));
+        }
+        for error in &self.errors {
+            messages.push(format!("{}: {}", config_path.display(), error));
+        }
+        messages
+    }
+}
+
+fn setting_kind(key: &str) -> &'static str {
+    match key {
+        "home_folder" | "default_sort" | "text_editor" | "time_format" | "theme"
+        | "color_depth" | "leader_key" | "profile" => "a string",
+        "allow_disk_undo" | "show_hidden" | "draw_simple_borders" | "relative_times"
+        | "modal_input" => {
+            "a boolean"
+        }
+        _ => "an integer",
+    }
+}
+
+// The value of a setting as set_value takes it, if it has the right type.
+fn setting_text(key: &str, value: &TomlValue) -> Result<String, String> {
+    let text = match value {
+        TomlValue::String(s) => s.clone(),
+        TomlValue::Integer(n) => n.to_string(),
+        TomlValue::Boolean(b) => b.to_string(),
+        TomlValue::Array(_) => String::new(),
+    };
+    if setting_kind(key) != value.kind() {
+        return Err(format!(
+            "{} should be {}, not {}",
+            key,
+            setting_kind(key),
+            value.kind()
+        ));
+    }
+    Ok(text)
+}
+
+// Whether key is in entries with a value that was taken.
+pub fn is_set(entries: &[TomlEntry], errors: &[TomlError], key: &str) -> bool {
+    entries
+        .iter()
+        .any(|entry| entry.path == [key] && !errors.iter().any(|error| error.line == entry.line))
+}
+
+fn text_value(value: &TomlValue, what: &str) -> Result<String, String> {
+    match value {
+        TomlValue::String(s) => Ok(s.clone()),
+        value => Err(format!("{} should be a string, not {}", what, value.kind())),
+    }
+}
+
+fn text_array(value: &TomlValue, what: &str) -> Result<Vec<String>, String> {
+    match value {
+        TomlValue::Array(items) => items
+            .iter()
+            .map(|item| text_value(item, "each item"))
+            .collect(),
+        value => Err(format!("{} should be an array, not {}", what, value.kind())),
+    }
+}
+
+fn marker_color(name: &str) -> Result<MarkerColor, String> {
+    MarkerColor::parse(name).ok_or_else(|| format!("unknown color '{}'", name))
+}
+
+fn layer_index(index: &str) -> Result<usize, String> {
+    index
+        .parse()
+        .map_err(|_| format!("'{}' is not a layer number", index))
+}
+
+struct PartialShortcut {
+    path: Option<PathBuf>,
+    name: String,
+    index: usize,
+    line: usize,
+}
+
+// Shortcuts are spread over several keys, they are put together here.
+fn shortcut_field(
+    shortcuts: &mut Vec<(Option<usize>, char, PartialShortcut)>,
+    layer: Option<usize>,
+    key: &str,
+    field: &str,
+    entry: &TomlEntry,
+) -> Result<(), String> {
+    let mut chars = key.chars();
+    let (Some(key), None) = (chars.next(), chars.next()) else {
+        return Err(format!("shortcut key '{}' should be one character", key));
+    };
+    let position = match shortcuts
+        .iter()
+        .position(|(l, k, _)| *l == layer && *k == key)
+    {
+        Some(position) => position,
+        None => {
+            let shortcut = PartialShortcut {
+                path: None,
+                name: String::new(),
+                index: 0,
+                line: entry.line,
+            };
+            shortcuts.push((layer, key, shortcut));
+            shortcuts.len() - 1
+        }
+    };
+    let shortcut = &mut shortcuts[position].2;
+    match (field, &entry.value) {
+        ("path", TomlValue::String(path)) => shortcut.path = Some(PathBuf::from(path)),
+        ("name", TomlValue::String(name)) => shortcut.name = name.clone(),
+        ("index", TomlValue::Integer(index)) if *index >= 0 => shortcut.index = *index as usize,
+        ("path" | "name", value) => {
+            return Err(format!("{} should be a string, not {}", field, value.kind()))
+        }
+        ("index", value) => return Err(format!("'{}' is not a valid index", value)),
+        _ => return Err(format!("unknown shortcut field '{}'", field)),
+    }
+    Ok(())
+}
+
+#[derive(Default)]
+struct PartialStep {
+    action: Option<Action>,
+    args: Vec<String>,
+    input: Vec<Keystroke>,
+    line: usize,
+    // Its action was reported wrong already.
+    unknown_action: bool,
+}
+
+// Macro steps are spread over several keys too.
+fn macro_field(
+    steps: &mut BTreeMap<String, BTreeMap<usize, PartialStep>>,
+    name: &str,
+    index: &str,
+    field: &str,
+    entry: &TomlEntry,
+) -> Result<(), String> {
+    let index: usize = index
+        .parse()
+        .map_err(|_| format!("'{}' is not a step number", index))?;
+    let step = steps
+        .entry(name.to_string())
+        .or_default()
+        .entry(index)
+        .or_insert_with(|| PartialStep {
+            line: entry.line,
+            ..PartialStep::default()
+        });
+    match field {
+        "action" => {
+            step.unknown_action = true;
+            let action = text_value(&entry.value, "action")?;
+            step.action = Some(
+                Action::from_str(&action).map_err(|_| format!("unknown action '{}'", action))?,
+            );
+            step.unknown_action = false;
+        }
+        "args" => step.args = text_array(&entry.value, "args")?,
+        "input" => step.input = parse_input(&text_value(&entry.value, "input")?)?,
+        _ => return Err(format!("unknown macro field '{}'", field)),
+    }
+    Ok(())
+}
+
+pub fn keybinding_table(
+    lines: &mut Vec<String>,
+    table: &str,
+    keybindings: &HashMap<KeySequence, Action>,
+) {
+    lines.push(String::new());
+    lines.push(format!("[{}]", table));
+    // In the order of the actions, so saving twice gives the same file.
+    let position = |action: &Action| Action::iter().position(|other| other == action);
+    let mut keybindings: Vec<_> = keybindings.iter().collect();
+    keybindings.sort_by_key(|(key, action)| (position(action), key.keys().len(), key.to_string()));
+    for (key, action) in keybindings {
+        lines.push(format!(
+            "{} = {}",
+            toml_key(&key.to_string()),
+            toml_string(&action.to_string())
+        ));
+    }
+}
+
+pub fn layer_tables(lines: &mut Vec<String>, table: &str, layers: &[ShortcutLayer]) {
+    for (i, layer) in layers.iter().enumerate() {
+        lines.push(String::new());
+        lines.push(format!("[{}.{}]", table, i));
+        lines.push(format!("name = {}", toml_string(&layer.name)));
+        if let Some(shortcuts) = &layer.shortcuts {
+            shortcut_tables(lines, &format!("{}.{}.shortcuts", table, i), shortcuts);
+        }
+    }
+}
+
+fn shortcut_tables(
+    lines: &mut Vec<String>,
+    table: &str,
+    shortcuts: &HashMap<char, (PathBuf, String, usize)>,
+) {
+    let mut keys: Vec<&char> = shortcuts.keys().collect();
+    keys.sort();
+    for key in keys {
+        let (path, name, index) = &shortcuts[key];
+        lines.push(String::new());
+        lines.push(format!("[{}.{}]", table, toml_string(&key.to_string())));
+        lines.push(format!("path = {}", toml_string(&path.display().to_string())));
+        lines.push(format!("name = {}", toml_string(name)));
+        lines.push(format!("index = {}", index));
+    }
+}
+
+impl Config {
+    pub fn get_value(&self, key: &str) -> Option<String> {
+        let value = match key {
+            "home_folder" => match &self.home_folder {
+                Some(path) => path.display().to_string(),
+                None => "None".to_string(),
+            },
+            "lines_shown" => self.lines_shown.to_string(),
+            "default_sort" => self.default_sort.to_string().to_string(),
+            "text_editor" => self.text_editor.clone(),
+            "ram_undo_limit" => self.ram_undo_limit.to_string(),
+            "disk_undo_limit" => self.disk_undo_limit.to_string(),
+            "allow_disk_undo" => self.allow_disk_undo.to_string(),
+            "search_depth_limit" => self.search_depth_limit.to_string(),
+            "show_hidden" => self.show_hidden.to_string(),
+            "draw_simple_borders" => self.draw_simple_borders.to_string(),
+            "max_distance" => self.max_distance.to_string(),
+            "dim_step" => self.dim_step.to_string(),
+            "time_format" => self.time_format.clone(),
+            "relative_times" => self.relative_times.to_string(),
+            "theme" => self.theme.clone(),
+            "color_depth" => self.color_depth.clone(),
+            "leader_key" => self.leader_key.to_string(),
+            "key_timeout" => self.key_timeout.to_string(),
+            "modal_input" => self.modal_input.to_string(),
+            "profile" => self.profile.clone(),
+            "current_layer" => self.current_layer.to_string(),
+            _ => return None,
+        };
+        Some(value)
+    }
+
+    // Changes a setting for this session, saving keeps what was there before.
+    pub fn override_value(&mut self, key: &str, value: &str) -> Result<(), String> {
+        let before = self
+            .get_value(key)
+            .ok_or_else(|| format!("unknown setting '{}'", key))?;
+        self.set_value(key, value)?;
+        let given = self.get_value(key).unwrap_or_default();
+        let entry = self.overridden.entry(key.to_string()).or_insert((before, String::new()));
+        entry.1 = given;
+        Ok(())
+    }
+
+    // Takes a value the way it is written in the config file.
+    pub fn set_value(&mut self, key: &str, value: &str) -> Result<(), String> {
+        match key {
+            "home_folder" => {
+                self.home_folder = if value == "None" {
+                    None
+                } else {
+                    Some(PathBuf::from(value.trim_matches('"')))
+                }
+            }
+            "lines_shown" => self.lines_shown = parse_setting(key, value)?,
+            "default_sort" => {
+                self.default_sort =
+                    SortOrder::parse(value).ok_or_else(|| invalid_value(key, value))?
+            }
+            "text_editor" => self.text_editor = value.to_string(),
+            "ram_undo_limit" => self.ram_undo_limit = parse_setting(key, value)?,
+            "disk_undo_limit" => self.disk_undo_limit = parse_setting(key, value)?,
+            "allow_disk_undo" => self.allow_disk_undo = parse_setting(key, value)?,
+            "search_depth_limit" => self.search_depth_limit = parse_setting(key, value)?,
+            "show_hidden" => self.show_hidden = parse_setting(key, value)?,
+            "draw_simple_borders" => self.draw_simple_borders = parse_setting(key, value)?,
+            "max_distance" => self.max_distance = parse_setting(key, value)?,
+            "dim_step" => self.dim_step = parse_setting(key, value)?,
+            "time_format" => self.time_format = not_empty(key, value)?,
+            "relative_times" => self.relative_times = parse_setting(key, value)?,
+            "theme" => self.theme = not_empty(key, value)?,
+            "color_depth" => self.color_depth = not_empty(key, value)?,
+            "leader_key" => {
+                let leader = Keystroke::parse(value);
+                if matches!(leader.key, Key::Null | Key::Leader) {
+                    return Err(invalid_value(key, value));
+                }
+                self.leader_key = leader;
+            }
+            "key_timeout" => self.key_timeout = parse_setting(key, value)?,
+            "modal_input" => self.modal_input = parse_setting(key, value)?,
+            "profile" => {
+                if !value.is_empty() && !self.profiles.contains_key(value) {
+                    return Err(format!("unknown profile '{}'", value));
+                }
+                self.profile = value.to_string();
+            }
+            "current_layer" => {
+                let layer: usize = parse_setting(key, value)?;
+                if layer >= self.shortcut_layers.len() {
+                    return Err(invalid_value(key, value));
+                }
+                self.current_layer = layer;
+            }
+            _ => return Err(format!("unknown setting '{}'", key)),
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShortcutLayer {
+    pub name: String,
+    pub shortcuts: Option<HashMap<char, (PathBuf, String, usize)>>,
+}
+
+impl ShortcutLayer {
+    pub fn new(name: String) -> Self {
+        ShortcutLayer {
+            name,
+            shortcuts: None,
+        }
+    }
+}
+
+///////////////////////////////////////////////////////////////////Colors///////////////////////////////////////////////////////////////////////////////
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ColorRule {
+    pub allow_delete: bool,
+    pub allow_rename: bool,
+    pub allow_move: bool,
+    pub allow_copy: bool,
+    pub include_in_search: bool,
+}
+
+impl Default for ColorRule {
+    fn default() -> Self {
+        ColorRule {
+            allow_delete: true,
+            allow_rename: true,
+            allow_move: true,
+            allow_copy: true,
+            include_in_search: true,
+        }
+    }
+}
+impl ColorRule {
+    pub fn allows(&self, operation: &str) -> bool {
+        match operation {
+            "delete" => self.allow_delete,
+            "rename" => self.allow_rename,
+            "move" => self.allow_move,
+            "copy" => self.allow_copy,
+            _ => true,
+        }
+    }
+}
+
+// A directory is only as free as the strictest entry inside it. Colors
+// without a rule allow everything.
+impl Config {
+    pub fn operation_allowed(&self, fs: &dyn FileSystem, path: &Path, operation: &str) -> bool {
+        if !self.item_allows(path, operation) {
+            return false;
+        }
+        if fs.is_dir(path) && !fs.is_symlink(path) {
+            if let Ok(children) = fs.read_dir(path) {
+                return children
+                    .iter()
+                    .all(|child| self.operation_allowed(fs, child, operation));
+            }
+        }
+        true
+    }
+
+    fn item_allows(&self, path: &Path, operation: &str) -> bool {
+        self.get_item_color(path)
+            .and_then(|color| self.color_rules.get(&color))
+            .is_none_or(|rule| rule.allows(operation))
+    }
+
+    pub fn is_searchable(&self, path: &Path) -> bool {
+        self.is_listed(path)
+            && self
+                .get_item_color(path)
+                .and_then(|color| self.color_rules.get(&color))
+                .is_none_or(|rule| rule.include_in_search)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MarkerColor {
+    Red,
+    Orange,
+    Yellow,
+    Green,
+    Blue,
+    Cyan,
+    Pink,
+    White,
+    Magenta,
+    Reset,
+}
+
+impl MarkerColor {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MarkerColor::Red => "red",
+            MarkerColor::Orange => "orange",
+            MarkerColor::Yellow => "yellow",
+            MarkerColor::Green => "green",
+            MarkerColor::Blue => "blue",
+            MarkerColor::Cyan => "cyan",
+            MarkerColor::Pink => "pink",
+            MarkerColor::White => "white",
+            MarkerColor::Magenta => "magenta",
+            MarkerColor::Reset => "reset",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "red" => Some(MarkerColor::Red),
+            "orange" => Some(MarkerColor::Orange),
+            "yellow" => Some(MarkerColor::Yellow),
+            "green" => Some(MarkerColor::Green),
+            "blue" => Some(MarkerColor::Blue),
+            "cyan" => Some(MarkerColor::Cyan),
+            "pink" => Some(MarkerColor::Pink),
+            "white" => Some(MarkerColor::White),
+            "magenta" => Some(MarkerColor::Magenta),
+            "reset" => Some(MarkerColor::Reset),
+            _ => None,
+        }
+    }
+
+    pub fn color_order(color: Option<MarkerColor>) -> u8 {
+        match color {
+            None => 0,
+            Some(color) => match color {
+                MarkerColor::Red => 1,
+                MarkerColor::Orange => 2,
+                MarkerColor::Yellow => 3,
+                MarkerColor::Green => 4,
+                MarkerColor::Blue => 5,
+                MarkerColor::Cyan => 6,
+                MarkerColor::Pink => 7,
+                MarkerColor::White => 8,
+                MarkerColor::Magenta => 9,
+                MarkerColor::Reset => 10,
+            },
+        }
+    }
+    pub fn next(&self) -> Self {
+        match self {
+            MarkerColor::Red => MarkerColor::Orange,
+            MarkerColor::Orange => MarkerColor::Yellow,
+            MarkerColor::Yellow => MarkerColor::Green,
+            MarkerColor::Green => MarkerColor::Blue,
+            MarkerColor::Blue => MarkerColor::Cyan,
+            MarkerColor::Cyan => MarkerColor::Pink,
+            MarkerColor::Pink => MarkerColor::White,
+            MarkerColor::White => MarkerColor::Magenta,
+            MarkerColor::Magenta => MarkerColor::Reset,
+            MarkerColor::Reset => MarkerColor::Red,
+        }
+    }
+
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SortOrder {
+    DateModifiedAsc,
+    DateModifiedDesc,
+    NameAsc,
+    NameDesc,
+    SizeAsc,
+    SizeDesc,
+    TypeAsc,
+    TypeDesc,
+    ColorAsc,
+    ColorDesc,
+}
+
+impl SortOrder {
+    // The saved names, or name, size, type, date and color with an optional
+    // "-desc" for the command line.
+    pub fn parse(s: &str) -> Option<Self> {
+        let order = match s.to_lowercase().as_str() {
+            "nameasc" | "name" | "name-asc" => SortOrder::NameAsc,
+            "namedesc" | "name-desc" => SortOrder::NameDesc,
+            "sizeasc" | "size" | "size-asc" => SortOrder::SizeAsc,
+            "sizedesc" | "size-desc" => SortOrder::SizeDesc,
+            "typeasc" | "type" | "type-asc" => SortOrder::TypeAsc,
+            "typedesc" | "type-desc" => SortOrder::TypeDesc,
+            "colorasc" | "color" | "color-asc" => SortOrder::ColorAsc,
+            "colordesc" | "color-desc" => SortOrder::ColorDesc,
+            "datemodifiedasc" | "date" | "date-asc" => SortOrder::DateModifiedAsc,
+            "datemodifieddesc" | "date-desc" => SortOrder::DateModifiedDesc,
+            _ => return None,
+        };
+        Some(order)
+    }
+
+    pub fn to_string(&self) -> &'static str {
+        match self {
+            SortOrder::NameAsc => "NameAsc",
+            SortOrder::NameDesc => "NameDesc",
+            SortOrder::SizeAsc => "SizeAsc",
+            SortOrder::SizeDesc => "SizeDesc",
+            SortOrder::TypeAsc => "TypeAsc",
+            SortOrder::TypeDesc => "TypeDesc",
+            SortOrder::ColorAsc => "ColorAsc",
+            SortOrder::ColorDesc => "ColorDesc",
+            SortOrder::DateModifiedAsc => "DateModifiedAsc",
+            SortOrder::DateModifiedDesc => "DateModifiedDesc",
+        }
+    }
+}
@@ -0,0 +1,181 @@
+/*
+* Stygian Sift - A Terminal-based File Manager
+ * Copyright (C) 2024 Maui The Magnificent (Charon)
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published
+ * by the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+ * Contact: Maui_The_Magnificent@proton.me
+ * Project repository: https://github.com/Mauitron/StygianSift.git
+ */
+
+use super::*;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileEntry {
+    pub path: PathBuf,
+    pub name: String,
+    pub file_type: FileType,
+    pub size: u64,
+    pub admin_required: bool,
+    pub read_only: bool,
+    pub git_status: Option<GitStatus>,
+}
+
+impl FileEntry {
+    pub fn new(path: PathBuf) -> io::Result<Self> {
+        let metadata = fs::metadata(&path)?;
+
+        let name = path
+            .file_name()
+            .map(|n| n.to_string_lossy())
+            .unwrap_or_default()
+            .into_owned();
+
+        let git_info = GitInfo::new();
+        let git_status = if git_info.is_git_repo {
+            git_info.file_statuses.get(&path).cloned()
+        } else {
+            None
+        };
+
+        let file_type = if metadata.is_dir() {
+            FileType::Directory
+        } else {
+            match Self::determine_file_type(&path) {
+                FileType::Unknown => sniffed_file_type(&path, &metadata),
+                file_type => file_type,
+            }
+        };
+
+        let (admin_required, read_only) = Self::check_permissions(&metadata);
+
+        Ok(Self {
+            path,
+            name,
+            file_type,
+            size: metadata.len(),
+            admin_required,
+            read_only,
+            git_status,
+        })
+    }
+
+    #[inline]
+    pub fn determine_file_type(path: &Path) -> FileType {
+        detect_file_type(path)
+    }
+
+    // Picks up a changed mode without rebuilding the whole entry.
+    pub fn refresh_permissions(&mut self) {
+        if let Ok(metadata) = fs::metadata(&self.path) {
+            (self.admin_required, self.read_only) = Self::check_permissions(&metadata);
+        }
+    }
+
+    #[cfg(unix)]
+    pub fn check_permissions(metadata: &fs::Metadata) -> (bool, bool) {
+        use std::os::unix::fs::MetadataExt;
+        let mode = metadata.mode();
+        let uid = metadata.uid();
+        let admin_required = if uid == 0 { false } else { (mode & 0o200) == 0 };
+        (admin_required, (mode & 0o200) == 0)
+    }
+
+    #[cfg(windows)]
+    pub fn check_permissions(metadata: &fs::Metadata) -> (bool, bool) {
+        use std::os::windows::fs::MetadataExt;
+        let attrs = metadata.file_attributes();
+        let readonly = (attrs & 0x1) != 0;
+        let system = (attrs & 0x4) != 0;
+        let hidden = (attrs & 0x2) != 0;
+        let admin_required = system || hidden;
+        (admin_required, readonly)
+    }
+}
+
+////////////////////////////////////////////////////////Listing////////////////////////////////////////////////////////////////////
+pub fn read_entries(dir: &Path) -> io::Result<Vec<FileEntry>> {
+    let entries: Vec<_> = fs::read_dir(dir)?
+        .par_bridge()
+        .filter_map(Result::ok)
+        .collect();
+
+    Ok(entries
+        .par_iter()
+        .with_min_len(256)
+        .filter_map(|entry| FileEntry::new(entry.path()).ok())
+        .collect())
+}
+
+// Names break every tie, so the order is the same on every listing.
+pub fn sort_entries(config: &Config, entries: &mut [FileEntry], sort_order: &SortOrder) {
+    match sort_order {
+        SortOrder::ColorAsc => entries.par_sort_by(|a, b| {
+            let a_color = config.get_item_color(&a.path);
+            let b_color = config.get_item_color(&b.path);
+            MarkerColor::color_order(a_color)
+                .cmp(&MarkerColor::color_order(b_color))
+                .then_with(|| a.name.cmp(&b.name))
+        }),
+        SortOrder::ColorDesc => entries.par_sort_by(|a, b| {
+            let a_color = config.get_item_color(&a.path);
+            let b_color = config.get_item_color(&b.path);
+            MarkerColor::color_order(b_color)
+                .cmp(&MarkerColor::color_order(a_color))
+                .then_with(|| a.name.cmp(&b.name))
+        }),
+        SortOrder::NameAsc => entries.par_sort_unstable_by(|a, b| a.name.cmp(&b.name)),
+        SortOrder::NameDesc => entries.par_sort_unstable_by(|a, b| b.name.cmp(&a.name)),
+        SortOrder::TypeAsc => entries.par_sort_unstable_by(|a, b| {
+            file_type_order(&a.file_type)
+                .cmp(&file_type_order(&b.file_type))
+                .then_with(|| a.name.cmp(&b.name))
+        }),
+        SortOrder::TypeDesc => entries.par_sort_unstable_by(|a, b| {
+            file_type_order(&b.file_type)
+                .cmp(&file_type_order(&a.file_type))
+                .then_with(|| a.name.cmp(&b.name))
+        }),
+        SortOrder::SizeAsc => entries
+            .par_sort_unstable_by(|a, b| a.size.cmp(&b.size).then_with(|| a.name.cmp(&b.name))),
+        SortOrder::SizeDesc => entries
+            .par_sort_unstable_by(|a, b| b.size.cmp(&a.size).then_with(|| a.name.cmp(&b.name))),
+        SortOrder::DateModifiedAsc => {
+            entries.par_sort_unstable_by(|a, b| compare_modified_times(a, b, Ordering::Less))
+        }
+        SortOrder::DateModifiedDesc => {
+            entries.par_sort_unstable_by(|a, b| compare_modified_times(a, b, Ordering::Greater))
+        }
+    }
+}
+
+fn compare_modified_times(a: &FileEntry, b: &FileEntry, order: Ordering) -> Ordering {
+    let a_time = fs::metadata(&a.path).and_then(|m| m.modified()).ok();
+    let b_time = fs::metadata(&b.path).and_then(|m| m.modified()).ok();
+    match (a_time, b_time) {
+        (Some(a_time), Some(b_time)) => {
+            if order == Ordering::Less {
+                a_time.cmp(&b_time).then_with(|| a.name.cmp(&b.name))
+            } else {
+                b_time.cmp(&a_time).then_with(|| a.name.cmp(&b.name))
+            }
+        }
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => a.name.cmp(&b.name),
+    }
+}
@@ -0,0 +1,501 @@
+/*
+* Stygian Sift - A Terminal-based File Manager
+ * Copyright (C) 2024 Maui The Magnificent (Charon)
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published
+ * by the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+ * Contact: Maui_The_Magnificent@proton.me
+ * Project repository: https://github.com/Mauitron/StygianSift.git
+ */
+
+use super::*;
+use std::sync::{Mutex, MutexGuard};
+//////////////////////////////////////////////////////File System/////////////////////////////////////////////////////////////////////
+// Everything the file operations and the undo history do to files goes
+// through here, so they can be run against memory as well as the disk.
+
+pub trait FileSystem: Send + Sync {
+    fn exists(&self, path: &Path) -> bool;
+    fn is_dir(&self, path: &Path) -> bool;
+    fn is_file(&self, path: &Path) -> bool {
+        self.exists(path) && !self.is_dir(path)
+    }
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>>;
+    fn write(&self, path: &Path, data: &[u8]) -> io::Result<()>;
+    fn create_dir(&self, path: &Path) -> io::Result<()>;
+    fn create_dir_all(&self, path: &Path) -> io::Result<()>;
+    // The children of a directory, in no particular order.
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<PathBuf>>;
+    fn copy(&self, from: &Path, to: &Path) -> io::Result<u64>;
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()>;
+    fn remove_file(&self, path: &Path) -> io::Result<()>;
+    fn remove_dir_all(&self, path: &Path) -> io::Result<()>;
+    // Symlinks themselves, never what they point at. read_link fails for
+    // anything that is not a link.
+    fn read_link(&self, path: &Path) -> io::Result<PathBuf>;
+    fn symlink(&self, target: &Path, link: &Path) -> io::Result<()>;
+    fn is_symlink(&self, path: &Path) -> bool {
+        self.read_link(path).is_ok()
+    }
+    // The unix permission bits and owner, where there are none this is
+    // Unsupported.
+    fn mode(&self, path: &Path) -> io::Result<u32>;
+    fn set_mode(&self, path: &Path, mode: u32) -> io::Result<()>;
+    fn owner(&self, path: &Path) -> io::Result<(u32, u32)>;
+    fn set_owner(&self, path: &Path, uid: u32, gid: u32) -> io::Result<()>;
+
+    // Copies a file or a whole tree and returns the bytes copied. Links are
+    // copied as links, following one that points up would never end.
+    fn copy_all(&self, from: &Path, to: &Path) -> io::Result<u64> {
+        if let Ok(target) = self.read_link(from) {
+            self.symlink(&target, to)?;
+            return Ok(0);
+        }
+        if !self.is_dir(from) {
+            return self.copy(from, to);
+        }
+        if to.starts_with(from) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Cannot copy a directory into itself",
+            ));
+        }
+        self.create_dir_all(to)?;
+        let mut total_size = 0;
+        for child in self.read_dir(from)? {
+            if let Some(name) = child.file_name() {
+                total_size += self.copy_all(&child, &to.join(name))?;
+            }
+        }
+        Ok(total_size)
+    }
+
+    fn remove_all(&self, path: &Path) -> io::Result<()> {
+        if self.is_dir(path) && !self.is_symlink(path) {
+            self.remove_dir_all(path)
+        } else {
+            self.remove_file(path)
+        }
+    }
+}
+
+pub struct RealFileSystem;
+
+impl FileSystem for RealFileSystem {
+    fn exists(&self, path: &Path) -> bool {
+        path.exists()
+    }
+
+    fn is_dir(&self, path: &Path) -> bool {
+        path.is_dir()
+    }
+
+    fn is_file(&self, path: &Path) -> bool {
+        path.is_file()
+    }
+
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        fs::read(path)
+    }
+
+    fn write(&self, path: &Path, data: &[u8]) -> io::Result<()> {
+        fs::write(path, data)
+    }
+
+    fn create_dir(&self, path: &Path) -> io::Result<()> {
+        fs::create_dir(path)
+    }
+
+    fn create_dir_all(&self, path: &Path) -> io::Result<()> {
+        fs::create_dir_all(path)
+    }
+
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<PathBuf>> {
+        fs::read_dir(path)?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect()
+    }
+
+    fn copy(&self, from: &Path, to: &Path) -> io::Result<u64> {
+        fs::copy(from, to)
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()> {
+        fs::rename(from, to)
+    }
+
+    fn remove_file(&self, path: &Path) -> io::Result<()> {
+        fs::remove_file(path)
+    }
+
+    fn remove_dir_all(&self, path: &Path) -> io::Result<()> {
+        fs::remove_dir_all(path)
+    }
+
+    fn read_link(&self, path: &Path) -> io::Result<PathBuf> {
+        fs::read_link(path)
+    }
+
+    fn is_symlink(&self, path: &Path) -> bool {
+        fs::symlink_metadata(path).is_ok_and(|metadata| metadata.file_type().is_symlink())
+    }
+
+    #[cfg(unix)]
+    fn symlink(&self, target: &Path, link: &Path) -> io::Result<()> {
+        std::os::unix::fs::symlink(target, link)
+    }
+
+    // Windows wants to know which kind of link it is making.
+    #[cfg(windows)]
+    fn symlink(&self, target: &Path, link: &Path) -> io::Result<()> {
+        let resolved = link.parent().map_or_else(|| target.to_path_buf(), |dir| dir.join(target));
+        if resolved.is_dir() {
+            std::os::windows::fs::symlink_dir(target, link)
+        } else {
+            std::os::windows::fs::symlink_file(target, link)
+        }
+    }
+
+    #[cfg(not(any(unix, windows)))]
+    fn symlink(&self, _target: &Path, _link: &Path) -> io::Result<()> {
+        Err(io::ErrorKind::Unsupported.into())
+    }
+
+    #[cfg(unix)]
+    fn mode(&self, path: &Path) -> io::Result<u32> {
+        use std::os::unix::fs::PermissionsExt;
+        Ok(fs::metadata(path)?.permissions().mode() & 0o7777)
+    }
+
+    #[cfg(unix)]
+    fn set_mode(&self, path: &Path, mode: u32) -> io::Result<()> {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(path, fs::Permissions::from_mode(mode))
+    }
+
+    #[cfg(unix)]
+    fn owner(&self, path: &Path) -> io::Result<(u32, u32)> {
+        use std::os::unix::fs::MetadataExt;
+        let metadata = fs::metadata(path)?;
+        Ok((metadata.uid(), metadata.gid()))
+    }
+
+    #[cfg(unix)]
+    fn set_owner(&self, path: &Path, uid: u32, gid: u32) -> io::Result<()> {
+        std::os::unix::fs::chown(path, Some(uid), Some(gid))
+    }
+
+    #[cfg(not(unix))]
+    fn mode(&self, _path: &Path) -> io::Result<u32> {
+        Err(io::ErrorKind::Unsupported.into())
+    }
+
+    #[cfg(not(unix))]
+    fn set_mode(&self, _path: &Path, _mode: u32) -> io::Result<()> {
+        Err(io::ErrorKind::Unsupported.into())
+    }
+
+    #[cfg(not(unix))]
+    fn owner(&self, _path: &Path) -> io::Result<(u32, u32)> {
+        Err(io::ErrorKind::Unsupported.into())
+    }
+
+    #[cfg(not(unix))]
+    fn set_owner(&self, _path: &Path, _uid: u32, _gid: u32) -> io::Result<()> {
+        Err(io::ErrorKind::Unsupported.into())
+    }
+}
+
+///////////////////////////////////////////////////////In Memory///////////////////////////////////////////////////////////////////////
+// A tree of absolute paths kept in a map. The root always exists, everything
+// else has to be made the same way it would be on disk. Modes and owners are
+// only kept for what had one set, the rest is owned by root with 755 for
+// directories and 644 for files. Links are kept but never followed.
+
+enum Node {
+    Dir,
+    File(Vec<u8>),
+    Link(PathBuf),
+}
+
+#[derive(Clone, Copy)]
+struct Attributes {
+    mode: u32,
+    owner: (u32, u32),
+}
+
+#[derive(Default)]
+pub struct MemoryFileSystem {
+    nodes: Mutex<BTreeMap<PathBuf, Node>>,
+    attributes: Mutex<BTreeMap<PathBuf, Attributes>>,
+}
+
+impl MemoryFileSystem {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn nodes(&self) -> MutexGuard<'_, BTreeMap<PathBuf, Node>> {
+        self.nodes.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    // Always taken after nodes.
+    fn attributes(&self) -> MutexGuard<'_, BTreeMap<PathBuf, Attributes>> {
+        self.attributes.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn attributes_of(&self, path: &Path) -> io::Result<Attributes> {
+        let nodes = self.nodes();
+        if let Some(attributes) = self.attributes().get(path) {
+            return Ok(*attributes);
+        }
+        let mode = match nodes.get(path) {
+            Some(Node::File(_)) => 0o644,
+            Some(Node::Link(_)) => 0o777,
+            _ if dir_exists(&nodes, path) => 0o755,
+            _ => return Err(not_found(path)),
+        };
+        Ok(Attributes { mode, owner: (0, 0) })
+    }
+
+    fn set_attributes(&self, path: &Path, change: impl FnOnce(&mut Attributes)) -> io::Result<()> {
+        let mut attributes = self.attributes_of(path)?;
+        change(&mut attributes);
+        self.attributes().insert(path.to_path_buf(), attributes);
+        Ok(())
+    }
+}
+
+fn is_root(path: &Path) -> bool {
+    path.parent().is_none() || path.as_os_str().is_empty()
+}
+
+fn not_found(path: &Path) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::NotFound,
+        format!("{} does not exist", path.display()),
+    )
+}
+
+fn already_exists(path: &Path) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::AlreadyExists,
+        format!("{} already exists", path.display()),
+    )
+}
+
+fn is_a_directory(path: &Path) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidInput,
+        format!("{} is a directory", path.display()),
+    )
+}
+
+fn dir_exists(nodes: &BTreeMap<PathBuf, Node>, path: &Path) -> bool {
+    is_root(path) || matches!(nodes.get(path), Some(Node::Dir))
+}
+
+fn parent_exists(nodes: &BTreeMap<PathBuf, Node>, path: &Path) -> bool {
+    path.parent().is_none_or(|parent| dir_exists(nodes, parent))
+}
+
+fn descendants(nodes: &BTreeMap<PathBuf, Node>, path: &Path) -> Vec<PathBuf> {
+    nodes
+        .keys()
+        .filter(|key| key.starts_with(path) && key.as_path() != path)
+        .cloned()
+        .collect()
+}
+
+impl FileSystem for MemoryFileSystem {
+    fn exists(&self, path: &Path) -> bool {
+        is_root(path) || self.nodes().contains_key(path)
+    }
+
+    fn is_dir(&self, path: &Path) -> bool {
+        dir_exists(&self.nodes(), path)
+    }
+
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        match self.nodes().get(path) {
+            Some(Node::File(data)) => Ok(data.clone()),
+            Some(Node::Dir) => Err(is_a_directory(path)),
+            Some(Node::Link(_)) | None => Err(not_found(path)),
+        }
+    }
+
+    fn write(&self, path: &Path, data: &[u8]) -> io::Result<()> {
+        let mut nodes = self.nodes();
+        if dir_exists(&nodes, path) {
+            return Err(is_a_directory(path));
+        }
+        if !parent_exists(&nodes, path) {
+            return Err(not_found(path.parent().unwrap_or(path)));
+        }
+        nodes.insert(path.to_path_buf(), Node::File(data.to_vec()));
+        Ok(())
+    }
+
+    fn create_dir(&self, path: &Path) -> io::Result<()> {
+        let mut nodes = self.nodes();
+        if is_root(path) || nodes.contains_key(path) {
+            return Err(already_exists(path));
+        }
+        if !parent_exists(&nodes, path) {
+            return Err(not_found(path.parent().unwrap_or(path)));
+        }
+        nodes.insert(path.to_path_buf(), Node::Dir);
+        Ok(())
+    }
+
+    fn create_dir_all(&self, path: &Path) -> io::Result<()> {
+        let mut nodes = self.nodes();
+        let mut missing: Vec<&Path> = path
+            .ancestors()
+            .take_while(|ancestor| !dir_exists(&nodes, ancestor))
+            .collect();
+        missing.reverse();
+        for dir in missing {
+            if nodes.contains_key(dir) {
+                return Err(already_exists(dir));
+            }
+            nodes.insert(dir.to_path_buf(), Node::Dir);
+        }
+        Ok(())
+    }
+
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<PathBuf>> {
+        let nodes = self.nodes();
+        if !dir_exists(&nodes, path) {
+            return Err(not_found(path));
+        }
+        Ok(nodes
+            .keys()
+            .filter(|key| key.parent() == Some(path))
+            .cloned()
+            .collect())
+    }
+
+    fn copy(&self, from: &Path, to: &Path) -> io::Result<u64> {
+        let data = self.read(from)?;
+        self.write(to, &data)?;
+        Ok(data.len() as u64)
+    }
+
+    // Like the disk, a file in the way is replaced and a directory is not.
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()> {
+        let mut nodes = self.nodes();
+        if !nodes.contains_key(from) {
+            return Err(not_found(from));
+        }
+        if from == to {
+            return Ok(());
+        }
+        if to.starts_with(from) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Cannot move a directory into itself",
+            ));
+        }
+        if dir_exists(&nodes, to) {
+            return Err(already_exists(to));
+        }
+        if !parent_exists(&nodes, to) {
+            return Err(not_found(to.parent().unwrap_or(to)));
+        }
+        let mut attributes = self.attributes();
+        attributes.remove(to);
+        for old in descendants(&nodes, from) {
+            if let (Ok(rest), Some(node)) = (old.strip_prefix(from), nodes.remove(&old)) {
+                if let Some(moved) = attributes.remove(&old) {
+                    attributes.insert(to.join(rest), moved);
+                }
+                nodes.insert(to.join(rest), node);
+            }
+        }
+        if let Some(node) = nodes.remove(from) {
+            if let Some(moved) = attributes.remove(from) {
+                attributes.insert(to.to_path_buf(), moved);
+            }
+            nodes.insert(to.to_path_buf(), node);
+        }
+        Ok(())
+    }
+
+    fn remove_file(&self, path: &Path) -> io::Result<()> {
+        let mut nodes = self.nodes();
+        match nodes.get(path) {
+            Some(Node::File(_) | Node::Link(_)) => {
+                nodes.remove(path);
+                self.attributes().remove(path);
+                Ok(())
+            }
+            Some(Node::Dir) => Err(is_a_directory(path)),
+            None => Err(not_found(path)),
+        }
+    }
+
+    fn remove_dir_all(&self, path: &Path) -> io::Result<()> {
+        let mut nodes = self.nodes();
+        if !matches!(nodes.get(path), Some(Node::Dir)) {
+            return Err(not_found(path));
+        }
+        let mut attributes = self.attributes();
+        for child in descendants(&nodes, path) {
+            nodes.remove(&child);
+            attributes.remove(&child);
+        }
+        nodes.remove(path);
+        attributes.remove(path);
+        Ok(())
+    }
+
+    fn read_link(&self, path: &Path) -> io::Result<PathBuf> {
+        match self.nodes().get(path) {
+            Some(Node::Link(target)) => Ok(target.clone()),
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{} is not a link", path.display()),
+            )),
+        }
+    }
+
+    fn symlink(&self, target: &Path, link: &Path) -> io::Result<()> {
+        let mut nodes = self.nodes();
+        if is_root(link) || nodes.contains_key(link) {
+            return Err(already_exists(link));
+        }
+        if !parent_exists(&nodes, link) {
+            return Err(not_found(link.parent().unwrap_or(link)));
+        }
+        nodes.insert(link.to_path_buf(), Node::Link(target.to_path_buf()));
+        Ok(())
+    }
+
+    fn mode(&self, path: &Path) -> io::Result<u32> {
+        Ok(self.attributes_of(path)?.mode)
+    }
+
+    fn set_mode(&self, path: &Path, mode: u32) -> io::Result<()> {
+        self.set_attributes(path, |attributes| attributes.mode = mode & 0o7777)
+    }
+
+    fn owner(&self, path: &Path) -> io::Result<(u32, u32)> {
+        Ok(self.attributes_of(path)?.owner)
+    }
+
+    fn set_owner(&self, path: &Path, uid: u32, gid: u32) -> io::Result<()> {
+        self.set_attributes(path, |attributes| attributes.owner = (uid, gid))
+    }
+}
@@ -0,0 +1,300 @@
+/*
+* Stygian Sift - A Terminal-based File Manager
+ * Copyright (C) 2024 Maui The Magnificent (Charon)
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published
+ * by the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+ * Contact: Maui_The_Magnificent@proton.me
+ * Project repository: https://github.com/Mauitron/StygianSift.git
+ */
+
+use super::*;
+use std::sync::{Mutex, RwLock};
+//////////////////////////////////////////////////////File Types//////////////////////////////////////////////////////////////////////
+// Works out a FileType from, in this order: the user's rules, well-known
+// file names, the extension and finally the first bytes of the file. Only
+// files the name says nothing about are opened.
+
+const SNIFF_LEN: u64 = 512;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FileType {
+    Directory,
+    Text,
+    Log,
+    Document,
+    Image,
+    Binary,
+    Config,
+    Executable,
+    Unknown,
+    Rust,
+    Nix = 10,
+    Zig = 11,
+    Source,
+    Script,
+    Build,
+    Data,
+    Archive,
+    Audio,
+    Video,
+}
+
+// `Makefile = Build`, `*.tpl = Source` or `#!deno = Script` in the config.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileTypeRule {
+    pub pattern: String,
+    pub file_type: FileType,
+}
+
+impl FileTypeRule {
+    pub fn parse(pattern: &str, file_type: &str) -> Option<Self> {
+        let pattern = pattern.trim();
+        if pattern.is_empty() {
+            return None;
+        }
+        Some(Self {
+            pattern: pattern.to_string(),
+            file_type: file_type.trim().parse().ok()?,
+        })
+    }
+
+    fn matches_name(&self, name: &str) -> bool {
+        match self.pattern.strip_prefix("*.") {
+            Some(extension) => name
+                .rsplit_once('.')
+                .is_some_and(|(_, ext)| ext.eq_ignore_ascii_case(extension)),
+            None => !self.pattern.starts_with("#!") && self.pattern == name,
+        }
+    }
+
+    fn matches_interpreter(&self, interpreter: &str) -> bool {
+        self.pattern
+            .strip_prefix("#!")
+            .is_some_and(|wanted| wanted == interpreter)
+    }
+}
+
+impl FromStr for FileType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "Directory" => FileType::Directory,
+            "Text" => FileType::Text,
+            "Log" => FileType::Log,
+            "Document" => FileType::Document,
+            "Image" => FileType::Image,
+            "Binary" => FileType::Binary,
+            "Config" => FileType::Config,
+            "Executable" => FileType::Executable,
+            "Unknown" => FileType::Unknown,
+            "Rust" => FileType::Rust,
+            "Nix" => FileType::Nix,
+            "Zig" => FileType::Zig,
+            "Source" => FileType::Source,
+            "Script" => FileType::Script,
+            "Build" => FileType::Build,
+            "Data" => FileType::Data,
+            "Archive" => FileType::Archive,
+            "Audio" => FileType::Audio,
+            "Video" => FileType::Video,
+            _ => return Err(format!("Unknown file type: {}", s)),
+        })
+    }
+}
+
+impl FileType {
+    // Types whose content makes no sense as text, previewed as hex instead.
+    pub fn has_binary_content(&self) -> bool {
+        matches!(
+            self,
+            FileType::Binary
+                | FileType::Executable
+                | FileType::Archive
+                | FileType::Audio
+                | FileType::Video
+        )
+    }
+}
+
+static FILE_TYPE_RULES: RwLock<Vec<FileTypeRule>> = RwLock::new(Vec::new());
+
+// Entries are built all over the place, so the rules from the config live
+// here instead of being handed to every FileEntry::new.
+pub fn set_file_type_rules(rules: &[FileTypeRule]) {
+    if let Ok(mut current) = FILE_TYPE_RULES.write() {
+        *current = rules.to_vec();
+    }
+}
+
+fn type_from_rules(matches: impl Fn(&FileTypeRule) -> bool) -> Option<FileType> {
+    let rules = FILE_TYPE_RULES.read().ok()?;
+    rules.iter().find(|rule| matches(rule)).map(|rule| rule.file_type)
+}
+
+fn type_from_name(name: &str) -> Option<FileType> {
+    let file_type = match name {
+        "Makefile" | "makefile" | "GNUmakefile" | "CMakeLists.txt" | "Dockerfile"
+        | "Containerfile" | "Justfile" | "justfile" | "Rakefile" | "Vagrantfile"
+        | "meson.build" | "BUILD" | "BUILD.bazel" | "WORKSPACE" | "build.zig" | "build.rs"
+        | "Taskfile.yml" | "Earthfile" | "Tiltfile" => FileType::Build,
+        "PKGBUILD" | "APKBUILD" | "configure" | ".bashrc" | ".bash_profile" | ".bash_logout"
+        | ".zshrc" | ".zprofile" | ".zshenv" | ".profile" | ".xinitrc" | ".xprofile" => {
+            FileType::Script
+        }
+        ".gitignore" | ".gitattributes" | ".gitmodules" | ".gitconfig" | ".editorconfig"
+        | ".dockerignore" | ".npmrc" | ".env" | ".inputrc" | ".vimrc" | ".tmux.conf"
+        | "Procfile" | "Gemfile" | "Pipfile" | "go.mod" => FileType::Config,
+        "Cargo.lock" | "flake.lock" | "package-lock.json" | "yarn.lock" | "go.sum"
+        | "poetry.lock" => FileType::Data,
+        "README" | "LICENSE" | "LICENCE" | "COPYING" | "AUTHORS" | "CHANGELOG" | "NEWS"
+        | "TODO" | "INSTALL" | "CONTRIBUTORS" => FileType::Text,
+        _ if name.starts_with("Dockerfile.") || name.starts_with("Makefile.") => FileType::Build,
+        _ => return None,
+    };
+    Some(file_type)
+}
+
+fn type_from_extension(extension: &str) -> Option<FileType> {
+    let file_type = match extension.to_ascii_lowercase().as_str() {
+        "rs" => FileType::Rust,
+        "zig" => FileType::Zig,
+        "nix" => FileType::Nix,
+        "txt" | "md" | "markdown" | "rst" | "adoc" | "org" | "nfo" => FileType::Text,
+        "log" | "out" => FileType::Log,
+        "doc" | "docx" | "odt" | "pdf" | "rtf" | "epub" | "tex" | "xls" | "xlsx" | "ods"
+        | "ppt" | "pptx" | "odp" => FileType::Document,
+        "jpg" | "jpeg" | "png" | "svg" | "gif" | "bmp" | "webp" | "ico" | "tif" | "tiff"
+        | "heic" | "avif" | "psd" | "xcf" => FileType::Image,
+        "bin" | "dat" | "o" | "a" | "so" | "dylib" | "dll" | "class" | "pyc" | "wasm"
+        | "obj" | "lib" | "ttf" | "otf" | "woff" | "woff2" => FileType::Binary,
+        "ini" | "config" | "cfg" | "conf" | "yml" | "yaml" | "toml" | "env" | "properties"
+        | "desktop" | "service" | "plist" | "editorconfig" => FileType::Config,
+        "exe" | "msi" | "appimage" | "com" | "sh" | "bat" => FileType::Executable,
+        "c" | "h" | "cpp" | "cc" | "cxx" | "hpp" | "hh" | "cs" | "java" | "kt" | "kts" | "go"
+        | "py" | "rb" | "js" | "mjs" | "cjs" | "ts" | "tsx" | "jsx" | "lua" | "php" | "swift"
+        | "scala" | "hs" | "ml" | "ex" | "exs" | "erl" | "clj" | "dart" | "r" | "jl" | "html"
+        | "htm" | "css" | "scss" | "sass" | "less" | "vue" | "svelte" | "sql" | "asm" | "s"
+        | "f90" | "m" | "mm" | "v" | "sv" | "vhd" | "glsl" | "wgsl" | "proto" => FileType::Source,
+        "bash" | "zsh" | "fish" | "ksh" | "csh" | "cmd" | "ps1" | "pl" | "awk" | "vim" | "nu" => {
+            FileType::Script
+        }
+        "mk" | "mak" | "cmake" | "gradle" | "bazel" | "bzl" | "ninja" | "just" => FileType::Build,
+        "json" | "jsonl" | "ndjson" | "geojson" | "csv" | "tsv" | "xml" | "lock" | "sqlite"
+        | "db" | "parquet" | "webmanifest" => FileType::Data,
+        "zip" | "tar" | "gz" | "tgz" | "bz2" | "xz" | "zst" | "7z" | "rar" | "jar" | "deb"
+        | "rpm" | "iso" | "dmg" | "apk" | "cab" => FileType::Archive,
+        "mp3" | "wav" | "flac" | "ogg" | "opus" | "m4a" | "aac" | "wma" | "aiff" | "mid" => {
+            FileType::Audio
+        }
+        "mp4" | "mkv" | "webm" | "mov" | "avi" | "wmv" | "flv" | "m4v" | "mpeg" | "mpg" => {
+            FileType::Video
+        }
+        _ => return None,
+    };
+    Some(file_type)
+}
+
+// Listings are rebuilt on every key, so what the contents said is kept per
+// path until the file is modified and each file is only opened once.
+static SNIFFED_TYPES: Mutex<BTreeMap<PathBuf, (SystemTime, FileType)>> =
+    Mutex::new(BTreeMap::new());
+const SNIFFED_TYPES_MAX: usize = 4096;
+
+pub fn sniffed_file_type(path: &Path, metadata: &fs::Metadata) -> FileType {
+    if !metadata.is_file() {
+        return FileType::Unknown;
+    }
+    let Ok(modified) = metadata.modified() else {
+        return detect_file_type_from_content(path).unwrap_or(FileType::Unknown);
+    };
+    let mut sniffed = SNIFFED_TYPES.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    if let Some(&(seen, file_type)) = sniffed.get(path) {
+        if seen == modified {
+            return file_type;
+        }
+    }
+    drop(sniffed);
+    let file_type = detect_file_type_from_content(path).unwrap_or(FileType::Unknown);
+    sniffed = SNIFFED_TYPES.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    if sniffed.len() >= SNIFFED_TYPES_MAX {
+        sniffed.clear();
+    }
+    sniffed.insert(path.to_path_buf(), (modified, file_type));
+    file_type
+}
+
+// Opens the file, go through sniffed_file_type for anything that is listed.
+pub fn detect_file_type_from_content(path: &Path) -> Option<FileType> {
+    // Opening a fifo would block until someone writes to it.
+    if !fs::metadata(path).ok()?.is_file() {
+        return None;
+    }
+    let mut header = Vec::with_capacity(SNIFF_LEN as usize);
+    File::open(path).ok()?.take(SNIFF_LEN).read_to_end(&mut header).ok()?;
+    if let Some(interpreter) = shebang_interpreter(&header) {
+        let program = interpreter.split_whitespace().next().unwrap_or_default();
+        let program = program.rsplit('/').next().unwrap_or(program);
+        if let Some(file_type) = type_from_rules(|rule| rule.matches_interpreter(program)) {
+            return Some(file_type);
+        }
+    }
+    match file_type_from_magic(&header) {
+        FileType::Unknown => None,
+        file_type => Some(file_type),
+    }
+}
+
+// Directories are recognised by the caller, this only looks at files and only
+// at their names.
+pub fn detect_file_type(path: &Path) -> FileType {
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy())
+        .unwrap_or_default();
+    type_from_rules(|rule| rule.matches_name(&name))
+        .or_else(|| type_from_name(&name))
+        .or_else(|| {
+            path.extension()
+                .and_then(|ext| ext.to_str())
+                .and_then(type_from_extension)
+        })
+        .unwrap_or(FileType::Unknown)
+}
+
+// Where each type goes when sorting by type.
+pub fn file_type_order(file_type: &FileType) -> u8 {
+    match file_type {
+        FileType::Directory => 0,
+        FileType::Text => 1,
+        FileType::Log => 2,
+        FileType::Document => 3,
+        FileType::Image => 4,
+        FileType::Binary => 5,
+        FileType::Config => 6,
+        FileType::Executable => 7,
+        FileType::Unknown => 8,
+        FileType::Rust => 9,
+        FileType::Nix => 10,
+        FileType::Zig => 11,
+        FileType::Source => 12,
+        FileType::Script => 13,
+        FileType::Build => 14,
+        FileType::Data => 15,
+        FileType::Archive => 16,
+        FileType::Audio => 17,
+        FileType::Video => 18,
+    }
+}
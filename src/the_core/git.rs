@@ -0,0 +1,130 @@
+/*
+* Stygian Sift - A Terminal-based File Manager
+ * Copyright (C) 2024 Maui The Magnificent (Charon)
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published
+ * by the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+ * Contact: Maui_The_Magnificent@proton.me
+ * Project repository: https://github.com/Mauitron/StygianSift.git
+ */
+
+use super::*;
+///////////////////////////////////////////////////////Git/////////////////////////////////////////////////////////////////////////
+// Repository state, read through the git binary. Without git installed a
+// directory simply reads as not being a repository.
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GitStatus {
+    Unmodified,
+    Modified,
+    Added,
+    Deleted,
+    Renamed,
+    Untracked,
+    Ignored,
+}
+
+pub struct GitInfo {
+    pub is_git_repo: bool,
+    pub file_statuses: HashMap<PathBuf, GitStatus>,
+}
+
+impl GitInfo {
+    pub fn new() -> Self {
+        GitInfo {
+            is_git_repo: false,
+            file_statuses: HashMap::new(),
+        }
+    }
+}
+
+pub fn is_git_repo(path: &Path) -> bool {
+    path.join(".git").is_dir()
+}
+
+pub fn get_git_statuses(path: &Path) -> io::Result<HashMap<PathBuf, GitStatus>> {
+    let output = Command::new("git")
+        .args(&["status", "--porcelain", "-z"])
+        .current_dir(path)
+        .output()?;
+
+    if !output.status.success() {
+        return Ok(HashMap::new());
+    }
+
+    let mut statuses = HashMap::new();
+    let entries = output
+        .stdout
+        .split(|&b| b == 0)
+        .filter(|entry| !entry.is_empty());
+
+    for entry in entries {
+        if entry.len() < 3 {
+            continue;
+        }
+        let status_code = &entry[0..2];
+        let file_path = Path::new(std::str::from_utf8(&entry[3..]).unwrap());
+        let status = match status_code {
+            b" M" => GitStatus::Modified,
+            b"A " => GitStatus::Added,
+            b"D " => GitStatus::Deleted,
+            b"R " => GitStatus::Renamed,
+            b"??" => GitStatus::Untracked,
+            _ => GitStatus::Unmodified,
+        };
+        statuses.insert(path.join(file_path), status);
+    }
+
+    Ok(statuses)
+}
+
+pub fn get_current_branch(path: &Path) -> String {
+    Command::new("git")
+        .arg("-C")
+        .arg(path)
+        .arg("rev-parse")
+        .arg("--abbrev-ref")
+        .arg("HEAD")
+        .output()
+        .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+        .unwrap_or_else(|_| "Unknown".to_string())
+}
+
+pub fn get_git_status(path: &Path) -> String {
+    Command::new("git")
+        .arg("-C")
+        .arg(path)
+        .arg("status")
+        .arg("--porcelain")
+        .output()
+        .map(|output| {
+            if output.stdout.is_empty() {
+                "Clean".to_string()
+            } else {
+                "Modified".to_string()
+            }
+        })
+        .unwrap_or_else(|_| "Unknown".to_string())
+}
+
+pub fn is_git_repository(path: &Path) -> bool {
+    Command::new("git")
+        .arg("-C")
+        .arg(path)
+        .arg("rev-parse")
+        .arg("--is-inside-work-tree")
+        .output()
+        .map(|output| output.status.success())
+        .unwrap_or(false)
+}
@@ -0,0 +1,451 @@
+/*
+* Stygian Sift - A Terminal-based File Manager
+ * Copyright (C) 2024 Maui The Magnificent (Charon)
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published
+ * by the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+ * Contact: Maui_The_Magnificent@proton.me
+ * Project repository: https://github.com/Mauitron/StygianSift.git
+ */
+
+use super::*;
+//////////////////////////////////////////////////////Keystrokes///////////////////////////////////////////////////////////////////////
+// A key the way the keybindings store it. The browser turns its terminal
+// events into these, other frontends can do the same with theirs.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Key {
+    Char(char),
+    F(u8),
+    Up,
+    Down,
+    Left,
+    Right,
+    Enter,
+    Esc,
+    Tab,
+    BackTab,
+    Backspace,
+    Delete,
+    Insert,
+    Home,
+    End,
+    PageUp,
+    PageDown,
+    Null,
+    // Stands in for the leader_key setting in key sequences.
+    Leader,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Modifiers(u8);
+
+impl Modifiers {
+    pub const NONE: Modifiers = Modifiers(0);
+    pub const SHIFT: Modifiers = Modifiers(1);
+    pub const CONTROL: Modifiers = Modifiers(2);
+    pub const ALT: Modifiers = Modifiers(4);
+
+    pub fn contains(&self, other: Modifiers) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    pub fn insert(&mut self, other: Modifiers) {
+        self.0 |= other.0;
+    }
+}
+
+impl std::ops::BitOr for Modifiers {
+    type Output = Modifiers;
+
+    fn bitor(self, other: Modifiers) -> Modifiers {
+        Modifiers(self.0 | other.0)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Keystroke {
+    pub key: Key,
+    pub modifiers: Modifiers,
+}
+
+impl Keystroke {
+    pub const fn new(key: Key, modifiers: Modifiers) -> Self {
+        Keystroke { key, modifiers }
+    }
+
+    // Reads what Display writes, "Ctrl+t" or "Shift+K". The quotes the config
+    // file puts around keys are dropped, anything unknown becomes Null.
+    pub fn parse(s: &str) -> Self {
+        let s = s.trim().trim_matches('"');
+        let (prefix, key_name) = if s == "+" {
+            ("", "+")
+        } else if let Some(prefix) = s.strip_suffix("++") {
+            (prefix, "+")
+        } else {
+            s.rsplit_once('+').unwrap_or(("", s))
+        };
+
+        let mut modifiers = Modifiers::NONE;
+        for part in prefix.split('+') {
+            match part {
+                "Shift" => modifiers.insert(Modifiers::SHIFT),
+                "Ctrl" => modifiers.insert(Modifiers::CONTROL),
+                "Alt" => modifiers.insert(Modifiers::ALT),
+                _ => {}
+            }
+        }
+
+        let key = match key_name {
+            "Up" => Key::Up,
+            "Down" => Key::Down,
+            "Left" => Key::Left,
+            "Right" => Key::Right,
+            "Enter" => Key::Enter,
+            "Esc" => Key::Esc,
+            "Tab" => Key::Tab,
+            "BackTab" => Key::BackTab,
+            "Backspace" => Key::Backspace,
+            "Delete" => Key::Delete,
+            "Insert" => Key::Insert,
+            "Home" => Key::Home,
+            "End" => Key::End,
+            "PageUp" => Key::PageUp,
+            "PageDown" => Key::PageDown,
+            "[SPACE]" | " " | "<space>" => Key::Char(' '),
+            "<leader>" => Key::Leader,
+            name if name.chars().count() == 1 => Key::Char(name.chars().next().unwrap()),
+            name => match name.strip_prefix('F').and_then(|n| n.parse().ok()) {
+                Some(n) => Key::F(n),
+                None => Key::Null,
+            },
+        };
+
+        Keystroke::new(key, modifiers)
+    }
+}
+
+impl Display for Keystroke {
+    fn fmt(&self, f: &mut Formatter<'_>) -> OtherResult {
+        if self.modifiers.contains(Modifiers::SHIFT) {
+            write!(f, "Shift+")?;
+        }
+        if self.modifiers.contains(Modifiers::CONTROL) {
+            write!(f, "Ctrl+")?;
+        }
+        if self.modifiers.contains(Modifiers::ALT) {
+            write!(f, "Alt+")?;
+        }
+        match self.key {
+            Key::Char(' ') => write!(f, "[SPACE]"),
+            Key::Char(c) => write!(f, "{}", c),
+            Key::F(n) => write!(f, "F{}", n),
+            Key::Leader => write!(f, "<leader>"),
+            key => write!(f, "{:?}", key),
+        }
+    }
+}
+
+//////////////////////////////////////////////////////Sequences////////////////////////////////////////////////////////////////////////
+// Keys pressed one after the other, like "g g". Written with spaces between
+// the keys, a single key is a sequence of one.
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct KeySequence(pub Vec<Keystroke>);
+
+impl KeySequence {
+    // None when it is empty or one of the keys is unknown.
+    pub fn parse(s: &str) -> Option<Self> {
+        let keys: Vec<Keystroke> = s
+            .split_whitespace()
+            .map(|name| match name {
+                // Keystroke::parse takes quotes off.
+                "\"" => Some(Keystroke::new(Key::Char('"'), Modifiers::NONE)),
+                name => Some(Keystroke::parse(name))
+                    .filter(|key| key.key != Key::Null || name == "Null"),
+            })
+            .collect::<Option<_>>()?;
+        (!keys.is_empty()).then_some(KeySequence(keys))
+    }
+
+    pub fn keys(&self) -> &[Keystroke] {
+        &self.0
+    }
+}
+
+impl From<Keystroke> for KeySequence {
+    fn from(key: Keystroke) -> Self {
+        KeySequence(vec![key])
+    }
+}
+
+impl Display for KeySequence {
+    fn fmt(&self, f: &mut Formatter<'_>) -> OtherResult {
+        for (i, key) in self.0.iter().enumerate() {
+            if i > 0 {
+                write!(f, " ")?;
+            }
+            write!(f, "{}", key)?;
+        }
+        Ok(())
+    }
+}
+
+// The bindings by their keys, with <leader> replaced by the leader key.
+#[derive(Debug, Default)]
+pub struct KeyTrie {
+    binding: Option<(KeySequence, Action)>,
+    children: HashMap<Keystroke, KeyTrie>,
+}
+
+impl KeyTrie {
+    pub fn new<'a>(
+        bindings: impl IntoIterator<Item = (&'a KeySequence, &'a Action)>,
+        leader: Keystroke,
+    ) -> Self {
+        let mut trie = KeyTrie::default();
+        for (sequence, action) in bindings {
+            let mut node = &mut trie;
+            for key in sequence.keys() {
+                let key = if key.key == Key::Leader { leader } else { *key };
+                node = node.children.entry(key).or_default();
+            }
+            node.binding = Some((sequence.clone(), action.clone()));
+        }
+        trie
+    }
+
+    pub fn find(&self, keys: &[Keystroke]) -> Option<&KeyTrie> {
+        keys.iter()
+            .try_fold(self, |node, key| node.children.get(key))
+    }
+
+    // The binding that ends here, as it was written.
+    pub fn binding(&self) -> Option<&(KeySequence, Action)> {
+        self.binding.as_ref()
+    }
+
+    pub fn action(&self) -> Option<&Action> {
+        self.binding.as_ref().map(|(_, action)| action)
+    }
+
+    pub fn has_more(&self) -> bool {
+        !self.children.is_empty()
+    }
+
+    // The keys that go on from here and what they lead to, None for
+    // another prefix.
+    pub fn next_keys(&self) -> Vec<(Keystroke, Option<&Action>)> {
+        let mut next: Vec<_> = self
+            .children
+            .iter()
+            .map(|(key, node)| (*key, node.action()))
+            .collect();
+        next.sort_by_key(|(key, _)| key.to_string());
+        next
+    }
+}
+
+// Two bindings that get in each other's way: the same keys once <leader> is
+// replaced, or keys that start the other ones and so wait for the timeout.
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeyConflict {
+    pub keys: KeySequence,
+    pub action: Action,
+    pub other: KeySequence,
+    pub other_action: Action,
+    pub same: bool,
+}
+
+impl KeyConflict {
+    pub fn between(
+        (keys, action): (&KeySequence, &Action),
+        (other, other_action): (&KeySequence, &Action),
+        leader: Keystroke,
+    ) -> Option<Self> {
+        let resolve = |keys: &KeySequence| -> Vec<Keystroke> {
+            keys.keys()
+                .iter()
+                .map(|key| if key.key == Key::Leader { leader } else { *key })
+                .collect()
+        };
+        let (mine, theirs) = (resolve(keys), resolve(other));
+        let conflict =
+            |keys: &KeySequence, action: &Action, other: &KeySequence, other_action: &Action| {
+                Some(KeyConflict {
+                    keys: keys.clone(),
+                    action: action.clone(),
+                    other: other.clone(),
+                    other_action: other_action.clone(),
+                    same: mine.len() == theirs.len(),
+                })
+            };
+        if mine == theirs && action == other_action {
+            None
+        } else if theirs.starts_with(&mine) {
+            conflict(keys, action, other, other_action)
+        } else if mine.starts_with(&theirs) {
+            conflict(other, other_action, keys, action)
+        } else {
+            None
+        }
+    }
+}
+
+impl Display for KeyConflict {
+    fn fmt(&self, f: &mut Formatter<'_>) -> OtherResult {
+        match self.same {
+            true if self.keys == self.other => {
+                write!(
+                    f,
+                    "'{}' is bound to {} already",
+                    self.keys, self.other_action
+                )
+            }
+            true => write!(
+                f,
+                "'{}' ({}) and '{}' ({}) are the same keys",
+                self.keys, self.action, self.other, self.other_action
+            ),
+            false => write!(
+                f,
+                "'{}' ({}) waits for the timeout, '{}' ({}) starts with it",
+                self.keys, self.action, self.other, self.other_action
+            ),
+        }
+    }
+}
+
+// Every pair of bindings that conflict, in a fixed order.
+pub fn key_conflicts<'a>(
+    bindings: impl IntoIterator<Item = (&'a KeySequence, &'a Action)>,
+    leader: Keystroke,
+) -> Vec<KeyConflict> {
+    let mut bindings: Vec<_> = bindings.into_iter().collect();
+    bindings.sort_by_key(|(keys, _)| keys.to_string());
+    let mut conflicts = Vec::new();
+    for (i, first) in bindings.iter().enumerate() {
+        for second in &bindings[i + 1..] {
+            conflicts.extend(KeyConflict::between(*first, *second, leader));
+        }
+    }
+    conflicts
+}
+
+// Turns key presses into actions. A key that starts a longer binding is
+// held until the rest comes in, the timeout runs out, or a key comes that
+// goes nowhere. Then the longest binding typed so far runs and whatever
+// was typed after it is tried again.
+#[derive(Debug, Default)]
+pub struct KeySequencer {
+    pending: Vec<Keystroke>,
+    last_key: Option<Instant>,
+    // Whose bindings the keys go through, see Config::key_trie.
+    mode: Mode,
+}
+
+impl KeySequencer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn feed(&mut self, config: &Config, key: Keystroke) -> Vec<Action> {
+        self.pending.push(key);
+        self.last_key = Some(Instant::now());
+        self.settle(&config.key_trie(self.mode), false)
+    }
+
+    // Runs what the held keys have, for when the timeout runs out.
+    pub fn flush(&mut self, config: &Config) -> Vec<Action> {
+        self.settle(&config.key_trie(self.mode), true)
+    }
+
+    fn settle(&mut self, trie: &KeyTrie, flush: bool) -> Vec<Action> {
+        let mut actions = Vec::new();
+        while !self.pending.is_empty() {
+            match trie.find(&self.pending) {
+                Some(node) if node.has_more() && !flush => return actions,
+                Some(node) if node.action().is_some() => {
+                    actions.extend(node.action().cloned());
+                    self.pending.clear();
+                }
+                _ => {
+                    let keys = std::mem::take(&mut self.pending);
+                    let bound = (1..keys.len()).rev().find_map(|n| {
+                        trie.find(&keys[..n])
+                            .and_then(KeyTrie::action)
+                            .map(|action| (n, action.clone()))
+                    });
+                    let rest = match bound {
+                        Some((n, action)) => {
+                            actions.push(action);
+                            n
+                        }
+                        None => 1,
+                    };
+                    self.pending = keys[rest..].to_vec();
+                }
+            }
+        }
+        actions
+    }
+
+    pub fn pending(&self) -> &[Keystroke] {
+        &self.pending
+    }
+
+    pub fn is_pending(&self) -> bool {
+        !self.pending.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.pending.clear();
+    }
+
+    pub fn mode(&self) -> Mode {
+        self.mode
+    }
+
+    // Held keys were meant for the old mode and are dropped.
+    pub fn set_mode(&mut self, mode: Mode) {
+        if mode != self.mode {
+            self.pending.clear();
+            self.mode = mode;
+        }
+    }
+
+    // How long the held keys wait for more, None when nothing is held.
+    pub fn time_left(&self, config: &Config) -> Option<Duration> {
+        let last_key = self.last_key.filter(|_| self.is_pending())?;
+        Some(config.key_timeout().saturating_sub(last_key.elapsed()))
+    }
+
+    // The held keys and where they can go, for showing while waiting.
+    pub fn hint(&self, config: &Config) -> String {
+        let keys = KeySequence(self.pending.clone()).to_string();
+        let trie = config.key_trie(self.mode);
+        let next = trie
+            .find(&self.pending)
+            .map(KeyTrie::next_keys)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(key, action)| match action {
+                Some(action) => format!("{} {}", key, action),
+                None => format!("{} ...", key),
+            })
+            .collect::<Vec<_>>()
+            .join("  ");
+        format!("{} ...  {}", keys, next)
+    }
+}
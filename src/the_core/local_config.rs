@@ -0,0 +1,301 @@
+/*
+* Stygian Sift - A Terminal-based File Manager
+ * Copyright (C) 2024 Maui The Magnificent (Charon)
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published
+ * by the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+ * Contact: Maui_The_Magnificent@proton.me
+ * Project repository: https://github.com/Mauitron/StygianSift.git
+ */
+
+// A .stygian file changes some settings for the directory it sits in and
+// everything below, like sort by date in Downloads or protection rules in a
+// repository. The files from the root down to the current directory are
+// merged over the global config, the innermost winning, and taken off again
+// before the config is saved.
+
+use super::*;
+
+pub const LOCAL_CONFIG_NAME: &str = ".stygian";
+
+// What a .stygian file may set, the rest only lives in the main config.
+pub const LOCAL_SETTINGS: &[&str] = &[
+    "default_sort",
+    "show_hidden",
+    "search_depth_limit",
+    "ignore_patterns",
+    "color_rules",
+    "commands",
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LocalConfig {
+    pub path: PathBuf,
+    pub default_sort: Option<SortOrder>,
+    pub show_hidden: Option<bool>,
+    pub search_depth_limit: Option<usize>,
+    pub color_rules: HashMap<MarkerColor, ColorRule>,
+    // Added to the ones from above.
+    pub ignore_patterns: Vec<String>,
+    pub custom_commands: BTreeMap<String, String>,
+}
+
+impl LocalConfig {
+    // Same format as the main config, limited to LOCAL_SETTINGS.
+    pub fn parse(path: &Path, text: &str) -> (Self, Vec<TomlError>) {
+        let (entries, mut errors) = parse_toml(text);
+        let (allowed, refused): (Vec<TomlEntry>, Vec<TomlEntry>) =
+            entries.into_iter().partition(|entry| {
+                entry.path[0] == "schema_version"
+                    || LOCAL_SETTINGS.contains(&entry.path[0].as_str())
+            });
+        for entry in refused {
+            errors.push(TomlError::new(
+                entry.line,
+                format!(
+                    "'{}' can only be set in the main config",
+                    entry.path.join(".")
+                ),
+            ));
+        }
+        let (parsed, more) = Config::from_toml_entries(&allowed);
+        let local = LocalConfig {
+            path: path.to_path_buf(),
+            default_sort: is_set(&allowed, &more, "default_sort")
+                .then(|| parsed.default_sort.clone()),
+            show_hidden: is_set(&allowed, &more, "show_hidden").then_some(parsed.show_hidden),
+            search_depth_limit: is_set(&allowed, &more, "search_depth_limit")
+                .then_some(parsed.search_depth_limit),
+            color_rules: parsed.color_rules,
+            ignore_patterns: parsed.ignore_patterns,
+            custom_commands: parsed.custom_commands,
+        };
+        errors.extend(more);
+        errors.sort_by_key(|error| error.line);
+        (local, errors)
+    }
+
+    pub fn load(path: &Path) -> io::Result<(Self, Vec<TomlError>)> {
+        Ok(Self::parse(path, &fs::read_to_string(path)?))
+    }
+
+    // The .stygian files that apply to dir, the outermost first.
+    pub fn find(dir: &Path) -> Vec<PathBuf> {
+        let mut found: Vec<PathBuf> = dir
+            .ancestors()
+            .map(|ancestor| ancestor.join(LOCAL_CONFIG_NAME))
+            .filter(|path| path.is_file())
+            .collect();
+        found.reverse();
+        found
+    }
+
+    fn apply(&self, settings: &mut DirSettings) {
+        if let Some(sort) = &self.default_sort {
+            settings.default_sort = sort.clone();
+        }
+        if let Some(show_hidden) = self.show_hidden {
+            settings.show_hidden = show_hidden;
+        }
+        if let Some(depth) = self.search_depth_limit {
+            settings.search_depth_limit = depth;
+        }
+        for (color, rule) in &self.color_rules {
+            settings.color_rules.insert(*color, rule.clone());
+        }
+        settings
+            .ignore_patterns
+            .extend(self.ignore_patterns.iter().cloned());
+        for (name, command) in &self.custom_commands {
+            settings
+                .custom_commands
+                .insert(name.clone(), command.clone());
+        }
+    }
+}
+
+// The part of the config a .stygian file can change.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DirSettings {
+    pub default_sort: SortOrder,
+    pub show_hidden: bool,
+    pub search_depth_limit: usize,
+    pub color_rules: HashMap<MarkerColor, ColorRule>,
+    pub ignore_patterns: Vec<String>,
+    pub custom_commands: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LocalOverlay {
+    pub sources: Vec<LocalConfig>,
+    // The settings before the files were merged in, and right after.
+    pub global: DirSettings,
+    pub merged: DirSettings,
+}
+
+impl Config {
+    pub fn dir_settings(&self) -> DirSettings {
+        DirSettings {
+            default_sort: self.default_sort.clone(),
+            show_hidden: self.show_hidden,
+            search_depth_limit: self.search_depth_limit,
+            color_rules: self.color_rules.clone(),
+            ignore_patterns: self.ignore_patterns.clone(),
+            custom_commands: self.custom_commands.clone(),
+        }
+    }
+
+    fn set_dir_settings(&mut self, settings: DirSettings) {
+        self.default_sort = settings.default_sort;
+        self.show_hidden = settings.show_hidden;
+        self.search_depth_limit = settings.search_depth_limit;
+        self.color_rules = settings.color_rules;
+        self.ignore_patterns = settings.ignore_patterns;
+        self.custom_commands = settings.custom_commands;
+    }
+
+    // Merges in the .stygian files for dir when they differ from the ones in
+    // effect. Gives what was wrong with the files it read, by file and line.
+    pub fn enter_dir(&mut self, dir: &Path) -> Vec<String> {
+        let mut messages = Vec::new();
+        let mut sources = Vec::new();
+        for path in LocalConfig::find(dir) {
+            match LocalConfig::load(&path) {
+                Ok((local, errors)) => {
+                    for error in errors {
+                        messages.push(format!("{}: {}", path.display(), error));
+                    }
+                    sources.push(local);
+                }
+                Err(e) => messages.push(format!("{}: {}", path.display(), e)),
+            }
+        }
+        let in_effect = self.local.as_ref().map_or(&[][..], |local| &local.sources);
+        if in_effect == sources.as_slice() {
+            return Vec::new();
+        }
+
+        self.leave_local();
+        if sources.is_empty() {
+            return messages;
+        }
+        let global = self.dir_settings();
+        let mut merged = global.clone();
+        for source in &sources {
+            source.apply(&mut merged);
+        }
+        self.set_dir_settings(merged.clone());
+        self.local = Some(LocalOverlay {
+            sources,
+            global,
+            merged,
+        });
+        messages
+    }
+
+    // Takes the .stygian files off again. What was changed since they were
+    // merged in is kept, as a change to the global config.
+    pub fn leave_local(&mut self) {
+        let Some(LocalOverlay { global, merged, .. }) = self.local.take() else {
+            return;
+        };
+        let now = self.dir_settings();
+        let mut restored = global;
+        if now.default_sort != merged.default_sort {
+            restored.default_sort = now.default_sort;
+        }
+        if now.show_hidden != merged.show_hidden {
+            restored.show_hidden = now.show_hidden;
+        }
+        if now.search_depth_limit != merged.search_depth_limit {
+            restored.search_depth_limit = now.search_depth_limit;
+        }
+        if now.ignore_patterns != merged.ignore_patterns {
+            restored.ignore_patterns = now.ignore_patterns;
+        }
+        if now.custom_commands != merged.custom_commands {
+            restored.custom_commands = now.custom_commands;
+        }
+        let colors: HashSet<MarkerColor> = now
+            .color_rules
+            .keys()
+            .chain(merged.color_rules.keys())
+            .copied()
+            .collect();
+        for color in colors {
+            if now.color_rules.get(&color) != merged.color_rules.get(&color) {
+                match now.color_rules.get(&color) {
+                    Some(rule) => restored.color_rules.insert(color, rule.clone()),
+                    None => restored.color_rules.remove(&color),
+                };
+            }
+        }
+        self.set_dir_settings(restored);
+    }
+
+    // Whether listings and searches show path at all.
+    pub fn is_listed(&self, path: &Path) -> bool {
+        let Some(name) = path.file_name() else {
+            return true;
+        };
+        let name = name.to_string_lossy();
+        (self.show_hidden || !name.starts_with('.'))
+            && !self
+                .ignore_patterns
+                .iter()
+                .any(|pattern| glob_match(pattern, &name))
+    }
+
+    // The shell command line for input when it starts with the name of a
+    // custom command, the rest of input is passed on.
+    pub fn custom_command(&self, input: &str) -> Option<String> {
+        let input = input.trim();
+        let (name, rest) = input.split_once(char::is_whitespace).unwrap_or((input, ""));
+        let command = self.custom_commands.get(name)?;
+        Some(match rest.trim() {
+            "" => command.clone(),
+            rest => format!("{} {}", command, rest),
+        })
+    }
+}
+
+// * matches any run of characters, ? a single one.
+pub fn glob_match(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    let (mut p, mut n) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, n));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match star {
+                // Let the last * take one more character.
+                Some((star_p, star_n)) => {
+                    p = star_p + 1;
+                    n = star_n + 1;
+                    star = Some((star_p, star_n + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
@@ -0,0 +1,161 @@
+/*
+* Stygian Sift - A Terminal-based File Manager
+ * Copyright (C) 2024 Maui The Magnificent (Charon)
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published
+ * by the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+ * Contact: Maui_The_Magnificent@proton.me
+ * Project repository: https://github.com/Mauitron/StygianSift.git
+ */
+
+// Macros: actions recorded one after the other with their arguments and the
+// keys typed into whatever they asked for, so playing them back answers the
+// same prompts the same way. A macro runs on the current entry, or once on
+// each entry of the multi-selection.
+
+use super::*;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MacroStep {
+    pub call: CommandCall,
+    pub input: Vec<Keystroke>,
+}
+
+impl MacroStep {
+    pub fn new(call: CommandCall) -> Self {
+        MacroStep {
+            call,
+            input: Vec::new(),
+        }
+    }
+
+    // Recording and playing are not recorded, and neither are the ":" line
+    // and the palette, since what they run is.
+    pub fn records(action: &Action) -> bool {
+        !matches!(
+            action,
+            Action::RecordMacro | Action::PlayMacro | Action::CommandLine | Action::CommandPalette
+        )
+    }
+}
+
+// Keys the way they are typed, the ones that type nothing between angle
+// brackets: "notes<Enter>" or "<Ctrl+c>y". A "<" is written "<lt>".
+pub fn input_text(keys: &[Keystroke]) -> String {
+    let mut text = String::new();
+    for key in keys {
+        let plain =
+            !key.modifiers.contains(Modifiers::CONTROL) && !key.modifiers.contains(Modifiers::ALT);
+        match key.key {
+            Key::Char('<') if plain => text.push_str("<lt>"),
+            Key::Char(c) if plain => text.push(c),
+            _ => text.push_str(&format!("<{}>", key)),
+        }
+    }
+    text
+}
+
+pub fn parse_input(text: &str) -> Result<Vec<Keystroke>, String> {
+    let mut keys = Vec::new();
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        if c != '<' {
+            let shift = if c.is_uppercase() {
+                Modifiers::SHIFT
+            } else {
+                Modifiers::NONE
+            };
+            keys.push(Keystroke::new(Key::Char(c), shift));
+            rest = &rest[c.len_utf8()..];
+            continue;
+        }
+        // The name has at least one character, and one ending in "+" goes
+        // on to the next ">", so "<>>" and "<Ctrl+>>" are the ">" key.
+        let end = rest
+            .get(2..)
+            .and_then(|after| after.find('>'))
+            .map(|end| end + 2)
+            .map(
+                |end| match rest[..end].ends_with('+') && rest[end + 1..].starts_with('>') {
+                    true => end + 1,
+                    false => end,
+                },
+            )
+            .ok_or_else(|| format!("'{}' is not closed with '>'", rest))?;
+        let name = &rest[1..end];
+        let key = match name {
+            "lt" => Keystroke::new(Key::Char('<'), Modifiers::NONE),
+            name => Some(Keystroke::parse(name))
+                .filter(|key| key.key != Key::Null && key.key != Key::Leader)
+                .ok_or_else(|| format!("unknown key '<{}>'", name))?,
+        };
+        keys.push(key);
+        rest = &rest[end + 1..];
+    }
+    Ok(keys)
+}
+
+// A macro being recorded.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MacroRecorder {
+    pub name: String,
+    pub steps: Vec<MacroStep>,
+}
+
+impl MacroRecorder {
+    pub fn new(name: &str) -> Self {
+        MacroRecorder {
+            name: name.to_string(),
+            steps: Vec::new(),
+        }
+    }
+
+    pub fn begin(&mut self, call: CommandCall) {
+        self.steps.push(MacroStep::new(call));
+    }
+
+    // Keys typed since the last step began are its answers.
+    pub fn add_input(&mut self, keys: impl IntoIterator<Item = Keystroke>) {
+        if let Some(step) = self.steps.last_mut() {
+            step.input.extend(keys);
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReplayStep {
+    // Move the cursor onto this entry before the steps after it.
+    Select(PathBuf),
+    Run(MacroStep),
+}
+
+// What playing steps takes: once where the cursor is without targets,
+// otherwise once on each target in turn.
+pub fn replay_plan(steps: &[MacroStep], targets: &[PathBuf]) -> VecDeque<ReplayStep> {
+    let run = steps.iter().cloned().map(ReplayStep::Run);
+    if targets.is_empty() {
+        return run.collect();
+    }
+    targets
+        .iter()
+        .flat_map(|target| std::iter::once(ReplayStep::Select(target.clone())).chain(run.clone()))
+        .collect()
+}
+
+// Drops the steps left for a target that is gone.
+pub fn skip_target(plan: &mut VecDeque<ReplayStep>) {
+    while let Some(ReplayStep::Run(_)) = plan.front() {
+        plan.pop_front();
+    }
+}
@@ -0,0 +1,530 @@
+/*
+* Stygian Sift - A Terminal-based File Manager
+ * Copyright (C) 2024 Maui The Magnificent (Charon)
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published
+ * by the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+ * Contact: Maui_The_Magnificent@proton.me
+ * Project repository: https://github.com/Mauitron/StygianSift.git
+ */
+
+use super::*;
+use std::io::{Seek, SeekFrom};
+/////////////////////////////////////////////////////////Magic////////////////////////////////////////////////////////////////////////
+// Looks at the first bytes of a file to tell what it really is, whatever
+// the extension says. Executables get a closer look.
+
+pub const MAGIC_HEADER_LEN: usize = 4096;
+
+// (offset, signature, description, file type), the first match wins.
+const SIGNATURES: &[(usize, &[u8], &str, FileType)] = &[
+    (0, b"\x7FELF", "ELF binary", FileType::Executable),
+    (0, b"MZ", "DOS/Windows executable", FileType::Executable),
+    (0, b"\xCF\xFA\xED\xFE", "Mach-O 64-bit binary", FileType::Executable),
+    (0, b"\xCE\xFA\xED\xFE", "Mach-O 32-bit binary", FileType::Executable),
+    (0, b"\0asm", "WebAssembly module", FileType::Binary),
+    (0, b"\x89PNG\r\n\x1A\n", "PNG image", FileType::Image),
+    (0, b"\xFF\xD8\xFF", "JPEG image", FileType::Image),
+    (0, b"GIF87a", "GIF image", FileType::Image),
+    (0, b"GIF89a", "GIF image", FileType::Image),
+    (0, b"BM", "BMP image", FileType::Image),
+    (0, b"II*\0", "TIFF image", FileType::Image),
+    (0, b"MM\0*", "TIFF image", FileType::Image),
+    (0, b"\0\0\x01\0", "Windows icon", FileType::Image),
+    (0, b"%PDF-", "PDF document", FileType::Document),
+    (0, b"%!PS", "PostScript document", FileType::Document),
+    (0, b"PK\x03\x04", "Zip archive", FileType::Archive),
+    (0, b"PK\x05\x06", "Zip archive (empty)", FileType::Archive),
+    (0, b"\x1F\x8B", "gzip compressed data", FileType::Archive),
+    (0, b"BZh", "bzip2 compressed data", FileType::Archive),
+    (0, b"\xFD7zXZ\0", "xz compressed data", FileType::Archive),
+    (0, b"\x28\xB5\x2F\xFD", "Zstandard compressed data", FileType::Archive),
+    (0, b"7z\xBC\xAF\x27\x1C", "7-zip archive", FileType::Archive),
+    (0, b"Rar!\x1A\x07", "RAR archive", FileType::Archive),
+    (0, b"!<arch>\n", "ar archive", FileType::Archive),
+    (257, b"ustar", "tar archive", FileType::Archive),
+    (0, b"SQLite format 3\0", "SQLite database", FileType::Data),
+    (0, b"ID3", "MP3 audio", FileType::Audio),
+    (0, b"OggS", "Ogg media", FileType::Audio),
+    (0, b"fLaC", "FLAC audio", FileType::Audio),
+    (0, b"\x1A\x45\xDF\xA3", "Matroska/WebM video", FileType::Video),
+    (4, b"ftyp", "MP4/QuickTime media", FileType::Video),
+    (0, b"wOFF", "WOFF font", FileType::Binary),
+    (0, b"wOF2", "WOFF2 font", FileType::Binary),
+    (0, b"\0\x01\0\0\0", "TrueType font", FileType::Binary),
+    (0, b"OTTO", "OpenType font", FileType::Binary),
+    (0, b"\xD0\xCF\x11\xE0\xA1\xB1\x1A\xE1", "Microsoft Office document", FileType::Document),
+    (0, b"-----BEGIN ", "PEM encoded data", FileType::Text),
+    (0, b"\xEF\xBB\xBF", "UTF-8 text (with BOM)", FileType::Text),
+];
+
+pub fn read_header(path: &Path) -> io::Result<Vec<u8>> {
+    // Opening a fifo would block until someone writes to it.
+    if !fs::metadata(path)?.is_file() {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "not a regular file"));
+    }
+    let mut header = Vec::with_capacity(MAGIC_HEADER_LEN);
+    File::open(path)?
+        .take(MAGIC_HEADER_LEN as u64)
+        .read_to_end(&mut header)?;
+    Ok(header)
+}
+
+pub fn guess_from_magic(header: &[u8]) -> String {
+    if header.is_empty() {
+        return "empty".to_string();
+    }
+    if header.starts_with(b"RIFF") && header.len() >= 12 {
+        return match &header[8..12] {
+            b"WAVE" => "WAV audio",
+            b"AVI " => "AVI video",
+            b"WEBP" => "WebP image",
+            _ => "RIFF data",
+        }
+        .to_string();
+    }
+    // Java classes and universal Mach-O binaries share a magic number, the
+    // next field is a small architecture count for Mach-O.
+    if header.starts_with(b"\xCA\xFE\xBA\xBE") && header.len() >= 8 {
+        let count = u32::from_be_bytes([header[4], header[5], header[6], header[7]]);
+        return if count < 30 {
+            "Mach-O universal binary".to_string()
+        } else {
+            "Java class file".to_string()
+        };
+    }
+    if let Some(interpreter) = shebang_interpreter(header) {
+        return format!("{} script", interpreter_name(&interpreter));
+    }
+    if let Some((_, _, description, _)) = matching_signature(header) {
+        return description.to_string();
+    }
+
+    if header.contains(&0) {
+        "binary data".to_string()
+    } else if header.is_ascii() {
+        "ASCII text".to_string()
+    } else if text_is_utf8(header) {
+        "UTF-8 text".to_string()
+    } else {
+        "binary data".to_string()
+    }
+}
+
+fn matching_signature(header: &[u8]) -> Option<&'static (usize, &'static [u8], &'static str, FileType)> {
+    SIGNATURES.iter().find(|(offset, signature, _, _)| {
+        header.len() >= offset + signature.len() && &header[*offset..offset + signature.len()] == *signature
+    })
+}
+
+// What the content says the file is, for names that told us nothing.
+pub fn file_type_from_magic(header: &[u8]) -> FileType {
+    if header.is_empty() {
+        return FileType::Unknown;
+    }
+    if shebang_interpreter(header).is_some() {
+        return FileType::Script;
+    }
+    let text = looks_like_text(header);
+    match matching_signature(header) {
+        // "BM" and "MZ" are too short to overrule text that happens to start with them.
+        Some((_, signature, _, _)) if signature.len() <= 2 && text => FileType::Text,
+        Some((_, _, _, file_type)) => *file_type,
+        None if text => FileType::Text,
+        None => FileType::Binary,
+    }
+}
+
+pub fn looks_like_text(header: &[u8]) -> bool {
+    !header.contains(&0) && (header.is_ascii() || text_is_utf8(header))
+}
+
+// The header may cut a multi-byte character in half, that still counts.
+fn text_is_utf8(header: &[u8]) -> bool {
+    match std::str::from_utf8(header) {
+        Ok(_) => true,
+        Err(e) => e.error_len().is_none() && header.len() - e.valid_up_to() < 4,
+    }
+}
+
+// `#!/usr/bin/env -S python3` gives "python3", a plain shebang the whole line.
+pub fn shebang_interpreter(header: &[u8]) -> Option<String> {
+    let rest = header.strip_prefix(b"#!")?;
+    let line_end = rest.iter().position(|&b| b == b'\n').unwrap_or(rest.len());
+    let line = String::from_utf8_lossy(&rest[..line_end]);
+    let mut parts = line.split_whitespace();
+    let program = parts.next()?;
+    let args: Vec<&str> = parts.collect();
+    if program.ends_with("/env") {
+        let command: Vec<&str> = args.iter().copied().filter(|arg| !arg.starts_with('-')).collect();
+        if !command.is_empty() {
+            return Some(command.join(" "));
+        }
+    }
+    if args.is_empty() {
+        Some(program.to_string())
+    } else {
+        Some(format!("{} {}", program, args.join(" ")))
+    }
+}
+
+fn interpreter_name(interpreter: &str) -> &str {
+    let program = interpreter.split_whitespace().next().unwrap_or(interpreter);
+    program.rsplit('/').next().unwrap_or(program)
+}
+
+//////////////////////////////////////////////////////////ELF/////////////////////////////////////////////////////////////////////////
+const PT_LOAD: u32 = 1;
+const PT_DYNAMIC: u32 = 2;
+const PT_INTERP: u32 = 3;
+const SHT_SYMTAB: u32 = 2;
+const DT_NEEDED: u64 = 1;
+const DT_STRTAB: u64 = 5;
+const DT_FLAGS_1: u64 = 0x6FFF_FFFB;
+const DF_1_PIE: u64 = 0x0800_0000;
+// Tables bigger than this are not something a real binary has.
+const MAX_TABLE: u64 = 4 * 1024 * 1024;
+
+pub struct ElfInfo {
+    pub class: u8,
+    pub little_endian: bool,
+    pub kind: String,
+    pub machine: String,
+    pub entry: u64,
+    pub interpreter: Option<String>,
+    pub needed: Vec<String>,
+    pub has_symbols: bool,
+    pub has_debug_info: bool,
+}
+
+struct Segment {
+    kind: u32,
+    offset: u64,
+    vaddr: u64,
+    filesz: u64,
+}
+
+struct ElfReader {
+    file: File,
+    is_64: bool,
+    little_endian: bool,
+}
+
+impl ElfReader {
+    fn read_at(&mut self, offset: u64, len: u64) -> io::Result<Vec<u8>> {
+        if len > MAX_TABLE {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "ELF table too large"));
+        }
+        self.file.seek(SeekFrom::Start(offset))?;
+        let mut data = vec![0; len as usize];
+        self.file.read_exact(&mut data)?;
+        Ok(data)
+    }
+
+    fn u16(&self, data: &[u8], at: usize) -> u16 {
+        let bytes = [data[at], data[at + 1]];
+        if self.little_endian {
+            u16::from_le_bytes(bytes)
+        } else {
+            u16::from_be_bytes(bytes)
+        }
+    }
+
+    fn u32(&self, data: &[u8], at: usize) -> u32 {
+        let bytes = [data[at], data[at + 1], data[at + 2], data[at + 3]];
+        if self.little_endian {
+            u32::from_le_bytes(bytes)
+        } else {
+            u32::from_be_bytes(bytes)
+        }
+    }
+
+    fn u64(&self, data: &[u8], at: usize) -> u64 {
+        let mut bytes = [0u8; 8];
+        bytes.copy_from_slice(&data[at..at + 8]);
+        if self.little_endian {
+            u64::from_le_bytes(bytes)
+        } else {
+            u64::from_be_bytes(bytes)
+        }
+    }
+
+    // Address sized fields, 4 bytes on 32-bit and 8 on 64-bit.
+    fn word(&self, data: &[u8], at: usize) -> u64 {
+        if self.is_64 {
+            self.u64(data, at)
+        } else {
+            self.u32(data, at) as u64
+        }
+    }
+}
+
+fn c_string(data: &[u8], at: usize) -> Option<String> {
+    let rest = data.get(at..)?;
+    let end = rest.iter().position(|&b| b == 0)?;
+    Some(String::from_utf8_lossy(&rest[..end]).into_owned())
+}
+
+fn machine_name(machine: u16) -> String {
+    match machine {
+        2 => "SPARC",
+        3 => "x86",
+        8 => "MIPS",
+        20 => "PowerPC",
+        21 => "PowerPC64",
+        22 => "S/390",
+        40 => "ARM",
+        43 => "SPARC V9",
+        50 => "IA-64",
+        62 => "x86-64",
+        183 => "AArch64",
+        243 => "RISC-V",
+        247 => "BPF",
+        258 => "LoongArch",
+        other => return format!("unknown ({})", other),
+    }
+    .to_string()
+}
+
+pub fn parse_elf(path: &Path) -> io::Result<ElfInfo> {
+    let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidData, message.to_string());
+    let mut reader = ElfReader {
+        file: File::open(path)?,
+        is_64: false,
+        little_endian: true,
+    };
+    let header = reader.read_at(0, 64).or_else(|_| reader.read_at(0, 52))?;
+    if !header.starts_with(b"\x7FELF") {
+        return Err(invalid("not an ELF file"));
+    }
+    reader.is_64 = match header[4] {
+        1 => false,
+        2 => true,
+        _ => return Err(invalid("unknown ELF class")),
+    };
+    reader.little_endian = header[5] != 2;
+    if reader.is_64 && header.len() < 64 {
+        return Err(invalid("truncated ELF header"));
+    }
+
+    let (phoff, shoff, sizes_at) = if reader.is_64 {
+        (reader.u64(&header, 32), reader.u64(&header, 40), 54)
+    } else {
+        (reader.u32(&header, 28) as u64, reader.u32(&header, 32) as u64, 42)
+    };
+    let e_type = reader.u16(&header, 16);
+    let machine = machine_name(reader.u16(&header, 18));
+    let entry = reader.word(&header, 24);
+    let phentsize = reader.u16(&header, sizes_at) as u64;
+    let phnum = reader.u16(&header, sizes_at + 2) as u64;
+    let shentsize = reader.u16(&header, sizes_at + 4) as u64;
+    let shnum = reader.u16(&header, sizes_at + 6) as u64;
+    let shstrndx = reader.u16(&header, sizes_at + 8) as u64;
+
+    let mut segments = Vec::new();
+    if phoff != 0 && phnum > 0 && phentsize >= if reader.is_64 { 56 } else { 32 } {
+        let table = reader.read_at(phoff, phentsize * phnum)?;
+        for i in 0..phnum as usize {
+            let p = &table[i * phentsize as usize..];
+            segments.push(if reader.is_64 {
+                Segment {
+                    kind: reader.u32(p, 0),
+                    offset: reader.u64(p, 8),
+                    vaddr: reader.u64(p, 16),
+                    filesz: reader.u64(p, 32),
+                }
+            } else {
+                Segment {
+                    kind: reader.u32(p, 0),
+                    offset: reader.u32(p, 4) as u64,
+                    vaddr: reader.u32(p, 8) as u64,
+                    filesz: reader.u32(p, 16) as u64,
+                }
+            });
+        }
+    }
+
+    let interpreter = match segments.iter().find(|s| s.kind == PT_INTERP) {
+        Some(segment) => {
+            let data = reader.read_at(segment.offset, segment.filesz)?;
+            c_string(&data, 0).or_else(|| Some(String::from_utf8_lossy(&data).into_owned()))
+        }
+        None => None,
+    };
+
+    // NEEDED entries point into the dynamic string table, which is given as
+    // a virtual address and has to be mapped back through the loaded segments.
+    let mut needed = Vec::new();
+    let mut pie = false;
+    if let Some(dynamic) = segments.iter().find(|s| s.kind == PT_DYNAMIC) {
+        let data = reader.read_at(dynamic.offset, dynamic.filesz)?;
+        let entry_size = if reader.is_64 { 16 } else { 8 };
+        let half = entry_size / 2;
+        let mut needed_offsets = Vec::new();
+        let mut strtab = None;
+        for chunk in data.chunks_exact(entry_size) {
+            let tag = reader.word(chunk, 0);
+            let value = reader.word(chunk, half);
+            match tag {
+                0 => break,
+                DT_NEEDED => needed_offsets.push(value),
+                DT_STRTAB => strtab = Some(value),
+                DT_FLAGS_1 => pie = value & DF_1_PIE != 0,
+                _ => {}
+            }
+        }
+        // A segment whose end does not fit in 64 bits holds nothing.
+        let strtab_offset = strtab.and_then(|address| {
+            segments
+                .iter()
+                .filter(|s| s.kind == PT_LOAD)
+                .find_map(|s| {
+                    let into = address.checked_sub(s.vaddr).filter(|&into| into < s.filesz)?;
+                    Some((s.offset.checked_add(into)?, s.offset.checked_add(s.filesz)?))
+                })
+        });
+        if let Some((start, end)) = strtab_offset {
+            let strings = reader.read_at(start, (end - start).min(MAX_TABLE))?;
+            needed = needed_offsets
+                .iter()
+                .filter_map(|&offset| c_string(&strings, offset as usize))
+                .collect();
+        }
+    }
+
+    let mut has_symbols = false;
+    let mut has_debug_info = false;
+    let min_shentsize = if reader.is_64 { 64 } else { 40 };
+    if shoff != 0 && shnum > 0 && shentsize >= min_shentsize && shstrndx < shnum {
+        let table = reader.read_at(shoff, shentsize * shnum)?;
+        let section = |i: u64| &table[(i * shentsize) as usize..];
+        let names_header = section(shstrndx);
+        let (names_offset, names_size) = if reader.is_64 {
+            (reader.u64(names_header, 24), reader.u64(names_header, 32))
+        } else {
+            (reader.u32(names_header, 16) as u64, reader.u32(names_header, 20) as u64)
+        };
+        let names = reader.read_at(names_offset, names_size).unwrap_or_default();
+        for i in 0..shnum {
+            let s = section(i);
+            has_symbols |= reader.u32(s, 4) == SHT_SYMTAB;
+            if let Some(name) = c_string(&names, reader.u32(s, 0) as usize) {
+                has_debug_info |= name.starts_with(".debug_") || name.starts_with(".zdebug_");
+            }
+        }
+    }
+
+    let kind = match e_type {
+        1 => "relocatable object",
+        2 => "executable",
+        3 if pie || interpreter.is_some() => "position independent executable",
+        3 => "shared object",
+        4 => "core dump",
+        _ => "unknown type",
+    }
+    .to_string();
+
+    Ok(ElfInfo {
+        class: if reader.is_64 { 64 } else { 32 },
+        little_endian: reader.little_endian,
+        kind,
+        machine,
+        entry,
+        interpreter,
+        needed,
+        has_symbols,
+        has_debug_info,
+    })
+}
+
+//////////////////////////////////////////////////////Other Formats//////////////////////////////////////////////////////////////////
+fn pe_summary(header: &[u8]) -> Option<String> {
+    let pe_offset = u32::from_le_bytes(header.get(0x3C..0x40)?.try_into().ok()?) as usize;
+    if header.get(pe_offset..pe_offset + 4)? != b"PE\0\0" {
+        return None;
+    }
+    let machine = u16::from_le_bytes(header.get(pe_offset + 4..pe_offset + 6)?.try_into().ok()?);
+    let characteristics =
+        u16::from_le_bytes(header.get(pe_offset + 22..pe_offset + 24)?.try_into().ok()?);
+    let optional_magic =
+        u16::from_le_bytes(header.get(pe_offset + 24..pe_offset + 26)?.try_into().ok()?);
+    let format = if optional_magic == 0x20B { "PE32+" } else { "PE32" };
+    let kind = if characteristics & 0x2000 != 0 { "DLL" } else { "executable" };
+    let arch = match machine {
+        0x014C => "x86",
+        0x8664 => "x86-64",
+        0x01C4 => "ARM",
+        0xAA64 => "AArch64",
+        _ => "unknown architecture",
+    };
+    Some(format!("{} {} ({})", format, kind, arch))
+}
+
+fn mach_o_arch(header: &[u8]) -> Option<&'static str> {
+    let cpu = i32::from_le_bytes(header.get(4..8)?.try_into().ok()?);
+    Some(match cpu {
+        7 => "x86",
+        0x0100_0007 => "x86-64",
+        12 => "ARM",
+        0x0100_000C => "ARM64",
+        18 => "PowerPC",
+        _ => "unknown architecture",
+    })
+}
+
+// Extra rows for the info pane, read straight from the file's header.
+pub fn binary_info_rows(path: &Path) -> Vec<(&'static str, String)> {
+    let header = match read_header(path) {
+        Ok(header) => header,
+        Err(_) => return Vec::new(),
+    };
+    let mut rows = vec![("Detected", guess_from_magic(&header))];
+
+    if header.starts_with(b"\x7FELF") {
+        match parse_elf(path) {
+            Ok(elf) => {
+                rows[0].1 = format!("ELF {}-bit {}", elf.class, elf.kind);
+                let endian = if elf.little_endian { "little endian" } else { "big endian" };
+                rows.push(("Architecture", format!("{}, {}", elf.machine, endian)));
+                if elf.entry != 0 {
+                    rows.push(("Entry point", format!("{:#x}", elf.entry)));
+                }
+                if let Some(interpreter) = elf.interpreter {
+                    rows.push(("Interpreter", interpreter));
+                }
+                if !elf.needed.is_empty() {
+                    rows.push(("Libraries", elf.needed.join(", ")));
+                }
+                let symbols = match (elf.has_symbols, elf.has_debug_info) {
+                    (_, true) => "not stripped, with debug info",
+                    (true, false) => "not stripped",
+                    (false, false) => "stripped",
+                };
+                rows.push(("Symbols", symbols.to_string()));
+            }
+            Err(e) => rows.push(("ELF", format!("unreadable ({})", e))),
+        }
+    } else if header.starts_with(b"MZ") {
+        if let Some(summary) = pe_summary(&header) {
+            rows[0].1 = summary;
+        }
+    } else if header.starts_with(b"\xCF\xFA\xED\xFE") || header.starts_with(b"\xCE\xFA\xED\xFE") {
+        if let Some(arch) = mach_o_arch(&header) {
+            rows.push(("Architecture", arch.to_string()));
+        }
+    } else if let Some(interpreter) = shebang_interpreter(&header) {
+        rows.push(("Interpreter", interpreter));
+    }
+    rows
+}
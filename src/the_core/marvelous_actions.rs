@@ -32,6 +32,14 @@ pub enum Action {
     ToggleFilters,
     GoToTop,
     GoToBottom,
+    GoHome,
+    YankPath,
+    VisualMode,
+    NormalMode,
+    CommandLine,
+    CommandPalette,
+    RecordMacro,
+    PlayMacro,
     ExecuteFile,
     GiveBirthDir,
     GiveBirthFile,
@@ -102,11 +110,19 @@ pub enum Action {
     OpenInEditor,
     EditConfig,
     SortCycleForward,
+    PreviewFoldMore,
+    PreviewFoldLess,
+    PreviewScrollLeft,
+    PreviewScrollRight,
+    ExtractMembers,
+    PackSelection,
+    ExtractArchive,
+    ChangePermissions,
 }
 
 impl Action {
     pub fn iter() -> Iter<'static, Action> {
-        static ACTIONS: [Action; 76] = [
+        static ACTIONS: [Action; 92] = [
             Action::IncreaseDimDistance,
             Action::DecreaseDimDistance,
             Action::IncreaseDimIntensity,
@@ -116,6 +132,14 @@ impl Action {
             Action::ToggleFilters,
             Action::GoToTop,
             Action::GoToBottom,
+            Action::GoHome,
+            Action::YankPath,
+            Action::VisualMode,
+            Action::NormalMode,
+            Action::CommandLine,
+            Action::CommandPalette,
+            Action::RecordMacro,
+            Action::PlayMacro,
             Action::ExecuteFile,
             Action::GiveBirthDir,
             Action::GiveBirthFile,
@@ -186,6 +210,14 @@ impl Action {
             Action::OpenInEditor,
             Action::EditConfig,
             Action::SortCycleForward,
+            Action::PreviewFoldMore,
+            Action::PreviewFoldLess,
+            Action::PreviewScrollLeft,
+            Action::PreviewScrollRight,
+            Action::ExtractMembers,
+            Action::PackSelection,
+            Action::ExtractArchive,
+            Action::ChangePermissions,
         ];
         ACTIONS.iter()
     }
@@ -204,7 +236,16 @@ impl FromStr for Action {
             "CastCommandLineSpell" => Ok(Action::CastCommandLineSpell),
             "ToggleFilters" => Ok(Action::ToggleFilters),
             "GoToTop" => Ok(Action::GoToTop),
-            "GoToBottam" => Ok(Action::GoToBottom),
+            // The misspelling is what used to be read.
+            "GoToBottom" | "GoToBottam" => Ok(Action::GoToBottom),
+            "GoHome" => Ok(Action::GoHome),
+            "YankPath" => Ok(Action::YankPath),
+            "VisualMode" => Ok(Action::VisualMode),
+            "NormalMode" => Ok(Action::NormalMode),
+            "CommandLine" => Ok(Action::CommandLine),
+            "CommandPalette" => Ok(Action::CommandPalette),
+            "RecordMacro" => Ok(Action::RecordMacro),
+            "PlayMacro" => Ok(Action::PlayMacro),
             "ExecuteFile" => Ok(Action::ExecuteFile),
             "GiveBirthDir" => Ok(Action::GiveBirthDir),
             "GiveBirthFile" => Ok(Action::GiveBirthFile),
@@ -242,6 +283,14 @@ impl FromStr for Action {
             "EditConfig" => Ok(Action::EditConfig),
             "SortCycleForward" => Ok(Action::SortCycleForward),
             "RenameLayer" => Ok(Action::RenameLayer),
+            "PreviewFoldMore" => Ok(Action::PreviewFoldMore),
+            "PreviewFoldLess" => Ok(Action::PreviewFoldLess),
+            "PreviewScrollLeft" => Ok(Action::PreviewScrollLeft),
+            "PreviewScrollRight" => Ok(Action::PreviewScrollRight),
+            "ExtractMembers" => Ok(Action::ExtractMembers),
+            "PackSelection" => Ok(Action::PackSelection),
+            "ExtractArchive" => Ok(Action::ExtractArchive),
+            "ChangePermissions" => Ok(Action::ChangePermissions),
             s if s.starts_with("SwitchLayer") => {
                 let num = s
                     .chars()
@@ -319,6 +368,14 @@ impl Display for Action {
             Action::ToggleFilters => "ToggleFilters",
             Action::GoToTop => "GoToTop",
             Action::GoToBottom => "GoToBottom",
+            Action::GoHome => "GoHome",
+            Action::YankPath => "YankPath",
+            Action::VisualMode => "VisualMode",
+            Action::NormalMode => "NormalMode",
+            Action::CommandLine => "CommandLine",
+            Action::CommandPalette => "CommandPalette",
+            Action::RecordMacro => "RecordMacro",
+            Action::PlayMacro => "PlayMacro",
             Action::ExecuteFile => "ExecuteFile",
             Action::GiveBirthDir => "GiveBirthDir",
             Action::GiveBirthFile => "GiveBirthFile",
@@ -389,7 +446,115 @@ impl Display for Action {
             Action::OpenInEditor => "OpenInEditor",
             Action::EditConfig => "EditConfig",
             Action::SortCycleForward => "SortCycleForward",
+            Action::PreviewFoldMore => "PreviewFoldMore",
+            Action::PreviewFoldLess => "PreviewFoldLess",
+            Action::PreviewScrollLeft => "PreviewScrollLeft",
+            Action::PreviewScrollRight => "PreviewScrollRight",
+            Action::ExtractMembers => "ExtractMembers",
+            Action::PackSelection => "PackSelection",
+            Action::ExtractArchive => "ExtractArchive",
+            Action::ChangePermissions => "ChangePermissions",
         };
         write!(f, "{}", s)
     }
 }
+
+impl Action {
+    // What the action does, in a few words for the command palette.
+    pub fn description(&self) -> &'static str {
+        match self {
+            Action::IncreaseDimDistance => "Dim entries further from the cursor",
+            Action::DecreaseDimDistance => "Dim entries closer to the cursor",
+            Action::IncreaseDimIntensity => "Dim entries more",
+            Action::DecreaseDimIntensity => "Dim entries less",
+            Action::BorderStyle => "Switch between fancy and simple borders",
+            Action::CastCommandLineSpell => "Open a terminal within StygianSift",
+            Action::ToggleFilters => "Toggle the search filters",
+            Action::GoToTop => "Go to the top of the list",
+            Action::GoToBottom => "Go to the bottom of the list",
+            Action::GoHome => "Go to the home folder",
+            Action::YankPath => "Copy the path to the terminal clipboard",
+            Action::VisualMode => "Select from here to where the cursor goes",
+            Action::NormalMode => "Leave visual or command mode",
+            Action::CommandLine => "Type a command with arguments",
+            Action::CommandPalette => "Find and run any command",
+            Action::RecordMacro => "Start or stop recording a macro",
+            Action::PlayMacro => "Play a macro on the entry or the selection",
+            Action::ExecuteFile => "Execute a file",
+            Action::GiveBirthDir => "Create a directory",
+            Action::GiveBirthFile => "Create a file",
+            Action::GitMenu => "Open the Git menu",
+            Action::SetColorRules => "Set color rules",
+            Action::CycleItemColor => "Cycle the item color",
+            Action::RemoveItemColor => "Remove the item color",
+            Action::SelectAll => "Select all files",
+            Action::SearchFiles => "Search for files",
+            Action::ShowShortcuts => "Show your stored shortcuts",
+            Action::TerminalCommand => "Open a terminal",
+            Action::Search => "Search the selected file",
+            Action::Undo => "Undo the last operation",
+            Action::ToggleSelect => "Toggle select mode",
+            Action::MultiSelectUp => "Select multiple files moving up",
+            Action::MultiSelectDown => "Select multiple files moving down",
+            Action::MoveUp => "Move up in the file list",
+            Action::MoveDown => "Move down in the file list",
+            Action::MoveLeft => "Go to the parent directory",
+            Action::MoveRight => "Enter the selected directory",
+            Action::Enter => "Open the selected entry",
+            Action::TogglePreview => "Toggle the preview pane",
+            Action::ToggleCount => "Toggle the item count",
+            Action::Rename => "Rename, keeping the extension",
+            Action::RenameWithoutExtension => "Rename, extension included",
+            Action::Murder => "Delete the selected files or folders",
+            Action::Copy => "Copy to the clipboard",
+            Action::Paste => "Paste from the clipboard",
+            Action::Duplicate => "Duplicate the selected file or folder",
+            Action::MoveItem => "Move the selected file or folder",
+            Action::Quit => "Quit StygianSift",
+            Action::Help => "Show the help menu",
+            Action::RenameLayer => "Rename the current shortcut layer",
+            Action::SwitchLayer0
+            | Action::SwitchLayer1
+            | Action::SwitchLayer2
+            | Action::SwitchLayer3
+            | Action::SwitchLayer4
+            | Action::SwitchLayer5
+            | Action::SwitchLayer6
+            | Action::SwitchLayer7
+            | Action::SwitchLayer8
+            | Action::SwitchLayer9 => "Switch to this shortcut layer",
+            Action::SetShortcut1
+            | Action::SetShortcut2
+            | Action::SetShortcut3
+            | Action::SetShortcut4
+            | Action::SetShortcut5
+            | Action::SetShortcut6
+            | Action::SetShortcut7
+            | Action::SetShortcut8
+            | Action::SetShortcut9
+            | Action::SetShortcut0 => "Store this directory as a shortcut",
+            Action::UseShortcut1
+            | Action::UseShortcut2
+            | Action::UseShortcut3
+            | Action::UseShortcut4
+            | Action::UseShortcut5
+            | Action::UseShortcut6
+            | Action::UseShortcut7
+            | Action::UseShortcut8
+            | Action::UseShortcut9
+            | Action::UseShortcut0 => "Go to a stored shortcut",
+            Action::SetLineAmount => "Set the number of lines shown",
+            Action::OpenInEditor => "Open in the text editor",
+            Action::EditConfig => "Open the configuration menu",
+            Action::SortCycleForward => "Change the sort order",
+            Action::PreviewFoldMore => "Fold the data preview",
+            Action::PreviewFoldLess => "Unfold the data preview",
+            Action::PreviewScrollLeft => "Scroll the data preview left",
+            Action::PreviewScrollRight => "Scroll the data preview right",
+            Action::ExtractMembers => "Extract from an archive",
+            Action::PackSelection => "Pack the selection into an archive",
+            Action::ExtractArchive => "Extract an archive into a folder",
+            Action::ChangePermissions => "Change permissions",
+        }
+    }
+}
@@ -0,0 +1,70 @@
+/*
+* Stygian Sift - A Terminal-based File Manager
+ * Copyright (C) 2024 Maui The Magnificent (Charon)
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published
+ * by the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+ * Contact: Maui_The_Magnificent@proton.me
+ * Project repository: https://github.com/Mauitron/StygianSift.git
+ */
+
+// Everything that does not need a terminal: the config, color rules, search,
+// file operations with their undo, and git status. Nothing in here may use
+// crossterm, the_tome builds the browser on top of it.
+
+pub mod config;
+pub mod file_entry;
+pub mod file_system;
+pub mod file_types;
+pub mod git;
+pub mod json;
+pub mod keys;
+pub mod local_config;
+pub mod macros;
+pub mod magic;
+pub mod marvelous_actions;
+pub mod modes;
+pub mod operations;
+pub mod palette;
+pub mod profile;
+pub mod search;
+pub mod time_format;
+pub mod toml;
+
+//////////////////////////////////////////DEPENDENCIES///////////////////////////////////////////////
+use rayon::prelude::*;
+
+/////////////////////////////////////////!DEPENDENCIES!//////////////////////////////////////////////
+pub use self::{
+    config::*, file_entry::*, file_system::*, file_types::*, git::*, json::*, keys::*,
+    local_config::*, macros::*, magic::*, marvelous_actions::*, modes::*, operations::*,
+    palette::*, profile::*, search::*, time_format::*, toml::*,
+};
+use std::{
+    cmp::Ordering,
+    collections::{BTreeMap, HashMap, HashSet, VecDeque},
+    env,
+    fmt::{Display, Formatter, Result as OtherResult},
+    fs::{self, File},
+    io::{self, Read},
+    path::{Path, PathBuf},
+    process::Command,
+    result::Result,
+    slice::Iter,
+    str::FromStr,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+pub const DEFAULT_RAM_LIMIT: usize = 500 * 1024 * 1024;
+pub const DEFAULT_DISK_LIMIT: u64 = 2 * 1024 * 1024 * 1024;
@@ -0,0 +1,260 @@
+/*
+* Stygian Sift - A Terminal-based File Manager
+ * Copyright (C) 2024 Maui The Magnificent (Charon)
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published
+ * by the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+ * Contact: Maui_The_Magnificent@proton.me
+ * Project repository: https://github.com/Mauitron/StygianSift.git
+ */
+
+// Optional vim-like modes, on with modal_input. Normal mode is the browser
+// as usual, visual mode selects from where it started to the cursor, and
+// command mode is the ":" line. Each mode has bindings of its own on top of
+// the keybindings, command mode only has its own since the rest is typing.
+
+use super::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
+pub enum Mode {
+    #[default]
+    Normal,
+    Visual,
+    Command,
+}
+
+pub const MODES: [Mode; 3] = [Mode::Normal, Mode::Visual, Mode::Command];
+
+impl Mode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Mode::Normal => "normal",
+            Mode::Visual => "visual",
+            Mode::Command => "command",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Mode> {
+        MODES.into_iter().find(|mode| mode.as_str() == s)
+    }
+
+    // The mode to be in once action ran in this one. Visual mode lasts
+    // while the cursor moves, anything else works on the selection and ends it.
+    pub fn after(self, action: &Action) -> Mode {
+        match (self, action) {
+            (_, Action::NormalMode) => Mode::Normal,
+            (_, Action::VisualMode) => Mode::Visual,
+            (
+                Mode::Visual,
+                Action::MoveUp
+                | Action::MoveDown
+                | Action::GoToTop
+                | Action::GoToBottom
+                | Action::MultiSelectUp
+                | Action::MultiSelectDown,
+            ) => Mode::Visual,
+            _ => Mode::Normal,
+        }
+    }
+}
+
+impl Display for Mode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> OtherResult {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+pub fn default_mode_keybindings() -> BTreeMap<Mode, HashMap<KeySequence, Action>> {
+    let bind = |bindings: &[(&str, Action)]| -> HashMap<KeySequence, Action> {
+        bindings
+            .iter()
+            .filter_map(|(keys, action)| Some((KeySequence::parse(keys)?, action.clone())))
+            .collect()
+    };
+    BTreeMap::from([
+        (
+            Mode::Normal,
+            bind(&[("v", Action::VisualMode), (":", Action::CommandLine)]),
+        ),
+        (
+            Mode::Visual,
+            bind(&[
+                ("v", Action::NormalMode),
+                ("Esc", Action::NormalMode),
+                ("y", Action::Copy),
+                (":", Action::CommandLine),
+            ]),
+        ),
+        (
+            Mode::Command,
+            bind(&[
+                ("Esc", Action::NormalMode),
+                ("Enter", Action::Enter),
+                ("Up", Action::MoveUp),
+                ("Down", Action::MoveDown),
+            ]),
+        ),
+    ])
+}
+
+//////////////////////////////////////////////////////CommandLine//////////////////////////////////////////////////////////////////////
+// What the ":" line takes: an action by its config name or one of these
+// shorter ones, then its arguments. A plain number goes to that line.
+// Several commands can be given with ";" between them.
+
+pub const COMMAND_ALIASES: &[(&str, Action)] = &[
+    ("q", Action::Quit),
+    ("quit", Action::Quit),
+    ("cd", Action::GoHome),
+    ("top", Action::GoToTop),
+    ("bottom", Action::GoToBottom),
+    ("sort", Action::SortCycleForward),
+    ("search", Action::SearchFiles),
+    ("yank", Action::YankPath),
+    ("copy", Action::Copy),
+    ("paste", Action::Paste),
+    ("undo", Action::Undo),
+    ("edit", Action::OpenInEditor),
+    ("shell", Action::TerminalCommand),
+    ("help", Action::Help),
+    ("visual", Action::VisualMode),
+    ("normal", Action::NormalMode),
+    ("record", Action::RecordMacro),
+    ("play", Action::PlayMacro),
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CommandCall {
+    pub action: Action,
+    pub args: Vec<String>,
+}
+
+impl CommandCall {
+    pub fn new(action: Action) -> Self {
+        CommandCall {
+            action,
+            args: Vec::new(),
+        }
+    }
+
+    // How many times to run a movement, 1 without a count.
+    pub fn count(&self) -> usize {
+        self.number().unwrap_or(1)
+    }
+
+    pub fn number(&self) -> Option<usize> {
+        self.args.first().and_then(|arg| arg.parse().ok())
+    }
+
+    // The arguments as one text, for a path or a query.
+    pub fn text(&self) -> Option<String> {
+        (!self.args.is_empty()).then(|| self.args.join(" "))
+    }
+
+    // What the action takes after it, None for nothing.
+    pub fn takes(action: &Action) -> Option<&'static str> {
+        match action {
+            Action::MoveUp | Action::MoveDown | Action::MultiSelectUp | Action::MultiSelectDown => {
+                Some("a count")
+            }
+            Action::GoToTop => Some("a line number"),
+            Action::GoHome => Some("a path"),
+            Action::SortCycleForward => Some("a sort order"),
+            Action::SearchFiles => Some("a query"),
+            Action::RecordMacro | Action::PlayMacro => Some("a macro name"),
+            _ => None,
+        }
+    }
+}
+
+pub fn parse_command_line(line: &str) -> Result<Vec<CommandCall>, String> {
+    let mut calls = Vec::new();
+    for command in split_commands(line)? {
+        let Some((name, args)) = command.split_first() else {
+            continue;
+        };
+        let (action, mut args) = match name.parse::<usize>() {
+            Ok(_) => (Action::GoToTop, vec![name.clone()]),
+            Err(_) => (command_action(name)?, args.to_vec()),
+        };
+        match (CommandCall::takes(&action), args.len()) {
+            (_, 0) => {}
+            (None, _) => return Err(format!("{} takes no arguments", action)),
+            (Some("a count") | Some("a line number"), 1) => {
+                if !args[0].parse::<usize>().is_ok_and(|n| n > 0) {
+                    return Err(format!(
+                        "{} takes {}, not '{}'",
+                        action,
+                        CommandCall::takes(&action).unwrap(),
+                        args[0]
+                    ));
+                }
+            }
+            (Some("a sort order"), 1) => {
+                if SortOrder::parse(&args[0]).is_none() {
+                    return Err(format!("unknown sort order '{}'", args[0]));
+                }
+            }
+            (Some("a path") | Some("a query") | Some("a macro name"), _) => {
+                args = vec![args.join(" ")];
+            }
+            (Some(takes), _) => return Err(format!("{} takes only {}", action, takes)),
+        }
+        calls.push(CommandCall { action, args });
+    }
+    Ok(calls)
+}
+
+fn command_action(name: &str) -> Result<Action, String> {
+    if let Some((_, action)) = COMMAND_ALIASES.iter().find(|(alias, _)| *alias == name) {
+        return Ok(action.clone());
+    }
+    Action::iter()
+        .find(|action| action.to_string().eq_ignore_ascii_case(name))
+        .cloned()
+        .ok_or_else(|| format!("unknown command '{}'", name))
+}
+
+// Splits into commands at ";" and each into words at spaces. Double quotes
+// keep spaces and ";" in a word, a backslash keeps the next character.
+fn split_commands(line: &str) -> Result<Vec<Vec<String>>, String> {
+    let mut commands = vec![Vec::new()];
+    let mut word: Option<String> = None;
+    let mut quoted = false;
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                let next = chars.next().ok_or("a \\ at the end of the line")?;
+                word.get_or_insert_with(String::new).push(next);
+            }
+            '"' => {
+                quoted = !quoted;
+                word.get_or_insert_with(String::new);
+            }
+            c if quoted => word.get_or_insert_with(String::new).push(c),
+            ';' => {
+                commands.last_mut().unwrap().extend(word.take());
+                commands.push(Vec::new());
+            }
+            c if c.is_whitespace() => commands.last_mut().unwrap().extend(word.take()),
+            c => word.get_or_insert_with(String::new).push(c),
+        }
+    }
+    if quoted {
+        return Err("a quote is not closed".to_string());
+    }
+    commands.last_mut().unwrap().extend(word);
+    Ok(commands)
+}
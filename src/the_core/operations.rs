@@ -0,0 +1,952 @@
+/*
+* Stygian Sift - A Terminal-based File Manager
+ * Copyright (C) 2024 Maui The Magnificent (Charon)
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published
+ * by the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+ * Contact: Maui_The_Magnificent@proton.me
+ * Project repository: https://github.com/Mauitron/StygianSift.git
+ */
+
+use super::*;
+use std::sync::Arc;
+////////////////////////////////////////////////////////UNDOMANAGER////////////////////////////////////////////////////////////////////////////////
+pub enum UndoStorage {
+    Ram(Vec<u8>),
+    Disk(PathBuf),
+}
+pub struct UndoEntry {
+    pub operation: Operation,
+    pub storage: UndoStorage,
+    pub original_path: PathBuf,
+    pub size: usize,
+}
+
+pub enum Operation {
+    Create {
+        path: PathBuf,
+        is_directory: bool,
+        timestamp: SystemTime,
+    },
+    Delete {
+        timestamp: SystemTime,
+    },
+    Move {
+        old_path: PathBuf,
+        new_path: PathBuf,
+        // is_directory: bool,
+        timestamp: SystemTime,
+    },
+    Duplicate {
+        original_path: PathBuf,
+        new_path: PathBuf,
+        timestamp: SystemTime,
+    },
+    Copy {
+        source_path: PathBuf,
+        dest_path: PathBuf,
+        timestamp: SystemTime,
+    },
+    Rename {
+        old_name: String,
+        new_name: String,
+        path: PathBuf,
+        timestamp: SystemTime,
+    },
+    ChangeMode {
+        previous_modes: PreviousModes,
+        timestamp: SystemTime,
+    },
+    ChangeOwner {
+        previous_owners: PreviousOwners,
+        timestamp: SystemTime,
+    },
+}
+
+pub struct UndoManager {
+    pub fs: Arc<dyn FileSystem>,
+    pub entries: VecDeque<UndoEntry>,
+    pub ram_storage: Vec<u8>,
+    pub temp_dir: PathBuf,
+    pub ram_limit: usize,
+    pub disk_limit: u64,
+    pub total_disk_size: u64,
+    pub allow_disk_storage: bool,
+    pub move_operations: VecDeque<UndoEntry>,
+}
+
+impl UndoManager {
+    pub fn new(
+        fs: Arc<dyn FileSystem>,
+        temp_dir: PathBuf,
+        ram_limit: usize,
+        disk_limit: u64,
+        allow_disk_storage: bool,
+    ) -> io::Result<Self> {
+        fs.create_dir_all(&temp_dir)?;
+        Ok(UndoManager {
+            fs,
+            entries: VecDeque::new(),
+            ram_storage: Vec::with_capacity(ram_limit),
+            temp_dir,
+            ram_limit,
+            disk_limit,
+            total_disk_size: 0,
+            allow_disk_storage,
+            move_operations: VecDeque::new(),
+        })
+    }
+
+    // The limits from the config and the usual folder for spilled entries.
+    pub fn with_config(fs: Arc<dyn FileSystem>, config: &Config) -> io::Result<Self> {
+        Self::new(
+            fs,
+            env::temp_dir().join("file_manager_undo"),
+            config.ram_undo_limit,
+            config.disk_undo_limit,
+            config.allow_disk_undo,
+        )
+    }
+
+    pub fn add_move_operation(&mut self, old_path: PathBuf, new_path: PathBuf) -> io::Result<()> {
+        let entry = UndoEntry {
+            operation: Operation::Move {
+                old_path: old_path.clone(),
+                new_path,
+                timestamp: SystemTime::now(),
+            },
+            storage: UndoStorage::Ram(Vec::new()), // Move operations don't need storage, unless to big i guess, then we might need a temp folder.
+            original_path: old_path,
+            size: 0, // Size is not relevant for move operations
+        };
+        self.add_tome_entry(entry)
+    }
+
+    pub fn add_create_operation(&mut self, path: PathBuf, is_directory: bool) -> io::Result<()> {
+        self.add_tome_entry(UndoEntry {
+            operation: Operation::Create {
+                path: path.clone(),
+                is_directory,
+                timestamp: SystemTime::now(),
+            },
+            storage: UndoStorage::Ram(Vec::new()),
+            original_path: path,
+            size: 0,
+        })
+    }
+
+    pub fn undo_flirty_move(&mut self) -> Option<UndoEntry> {
+        self.move_operations.pop_back()
+    }
+
+    pub fn add_tome_entry(&mut self, entry: UndoEntry) -> io::Result<()> {
+        if entry.size <= self.ram_limit - self.ram_storage.len() {
+            if let UndoStorage::Ram(data) = &entry.storage {
+                self.ram_storage.extend(data);
+            }
+            self.entries.push_back(entry);
+        } else if self.allow_disk_storage {
+            let disk_path = if let UndoStorage::Ram(data) = &entry.storage {
+                Some(self.store_to_disk(data)?)
+            } else {
+                None
+            };
+
+            if let Some(path) = disk_path {
+                self.total_disk_size += entry.size as u64;
+                self.entries.push_back(UndoEntry {
+                    storage: UndoStorage::Disk(path),
+                    ..entry
+                });
+            } else {
+                self.entries.push_back(entry);
+            }
+
+            while self.total_disk_size > self.disk_limit {
+                if let Some(removed_entry) = self.entries.pop_front() {
+                    self.remove_entry(removed_entry)?;
+                }
+            }
+        } else {
+            return Err(io::Error::new(
+                io::ErrorKind::Other,
+                "Undo storage limit reached",
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn undo_haunting_regret(&mut self) -> Option<UndoEntry> {
+        self.entries.pop_back().map(|entry| match entry.storage {
+            UndoStorage::Ram(_) => {
+                let start = self.ram_storage.len().saturating_sub(entry.size);
+                let data = self.ram_storage.split_off(start);
+                UndoEntry {
+                    storage: UndoStorage::Ram(data),
+                    ..entry
+                }
+            }
+            UndoStorage::Disk(_) => {
+                self.total_disk_size = self.total_disk_size.saturating_sub(entry.size as u64);
+                entry
+            }
+        })
+    }
+    fn store_to_disk(&self, data: &[u8]) -> io::Result<PathBuf> {
+        let file_name = format!(
+            "undo_{}",
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_micros()
+        );
+        let path = self.temp_dir.join(file_name);
+        self.fs.write(&path, data)?;
+        Ok(path)
+    }
+
+    fn remove_entry(&mut self, entry: UndoEntry) -> io::Result<()> {
+        match entry.storage {
+            UndoStorage::Ram(_) => {
+                self.ram_storage
+                    .truncate(self.ram_storage.len() - entry.size);
+            }
+            UndoStorage::Disk(path) => {
+                self.fs.remove_all(&path)?;
+                self.total_disk_size -= entry.size as u64;
+            }
+        }
+        Ok(())
+    }
+    pub fn undo_by_type(&mut self, operation_type: &str) -> Option<UndoEntry> {
+        let index = self.entries.iter().rposition(|entry| {
+            matches!(
+                (&entry.operation, operation_type),
+                (Operation::Delete { .. }, "delete")
+                    | (Operation::Move { .. }, "move")
+                    | (Operation::Duplicate { .. }, "duplicate")
+                    | (Operation::Copy { .. }, "copy")
+                    | (Operation::Rename { .. }, "rename")
+                    | (Operation::ChangeMode { .. }, "chmod")
+                    | (Operation::ChangeOwner { .. }, "chown")
+            )
+        })?;
+
+        let entry = self.entries.remove(index).unwrap();
+
+        match entry.storage {
+            UndoStorage::Ram(_) => {
+                let start = self.ram_storage.len().saturating_sub(entry.size);
+                let data = self.ram_storage.split_off(start);
+                Some(UndoEntry {
+                    storage: UndoStorage::Ram(data),
+                    ..entry
+                })
+            }
+            UndoStorage::Disk(_) => {
+                self.total_disk_size = self.total_disk_size.saturating_sub(entry.size as u64);
+                Some(entry)
+            }
+        }
+    }
+}
+
+impl Operation {
+    // The names undo_by_type takes.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Operation::Create { .. } => "create",
+            Operation::Delete { .. } => "delete",
+            Operation::Move { .. } => "move",
+            Operation::Duplicate { .. } => "duplicate",
+            Operation::Copy { .. } => "copy",
+            Operation::Rename { .. } => "rename",
+            Operation::ChangeMode { .. } => "chmod",
+            Operation::ChangeOwner { .. } => "chown",
+        }
+    }
+
+    pub fn timestamp(&self) -> SystemTime {
+        match self {
+            Operation::Create { timestamp, .. }
+            | Operation::Delete { timestamp }
+            | Operation::Move { timestamp, .. }
+            | Operation::Duplicate { timestamp, .. }
+            | Operation::Copy { timestamp, .. }
+            | Operation::Rename { timestamp, .. }
+            | Operation::ChangeMode { timestamp, .. }
+            | Operation::ChangeOwner { timestamp, .. } => *timestamp,
+        }
+    }
+}
+
+impl UndoEntry {
+    pub fn describe(&self) -> String {
+        match &self.operation {
+            Operation::Create { path, .. } => format!("created {}", path.display()),
+            Operation::Delete { .. } => format!("deleted {}", self.original_path.display()),
+            Operation::Move {
+                old_path, new_path, ..
+            } => format!("moved {} -> {}", old_path.display(), new_path.display()),
+            Operation::Duplicate {
+                original_path,
+                new_path,
+                ..
+            } => format!(
+                "duplicated {} -> {}",
+                original_path.display(),
+                new_path.display()
+            ),
+            Operation::Copy {
+                source_path,
+                dest_path,
+                ..
+            } => format!("copied {} -> {}", source_path.display(), dest_path.display()),
+            Operation::Rename {
+                path,
+                old_name,
+                new_name,
+                ..
+            } => format!("renamed {} -> {}", path.join(old_name).display(), new_name),
+            Operation::ChangeMode { previous_modes, .. } => format!(
+                "changed the mode of {} ({} entries)",
+                self.original_path.display(),
+                previous_modes.len()
+            ),
+            Operation::ChangeOwner {
+                previous_owners, ..
+            } => format!(
+                "changed the owner of {} ({} entries)",
+                self.original_path.display(),
+                previous_owners.len()
+            ),
+        }
+    }
+}
+
+/////////////////////////////////////////////////////History File//////////////////////////////////////////////////////////////////
+// The history outlives a session in temp_dir/history so the command line can
+// list and undo what the browser did. One line per entry, fields separated by
+// tabs. Deleted files that only live in memory are written out next to it.
+
+fn escape_field(field: &str) -> String {
+    field
+        .replace('\\', "\\\\")
+        .replace('\t', "\\t")
+        .replace('\n', "\\n")
+}
+
+fn unescape_field(field: &str) -> String {
+    let mut out = String::with_capacity(field.len());
+    let mut chars = field.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('t') => out.push('\t'),
+            Some('n') => out.push('\n'),
+            Some(other) => out.push(other),
+            None => out.push('\\'),
+        }
+    }
+    out
+}
+
+fn path_field(path: &Path) -> String {
+    escape_field(&path.to_string_lossy())
+}
+
+impl UndoManager {
+    pub fn history_path(&self) -> PathBuf {
+        self.temp_dir.join("history")
+    }
+
+    pub fn save_history(&self) -> io::Result<()> {
+        let mut lines = String::new();
+        for (index, entry) in self.entries.iter().enumerate() {
+            let secs = entry
+                .operation
+                .timestamp()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0);
+            let mut fields = vec![entry.operation.name().to_string(), secs.to_string()];
+            match &entry.operation {
+                Operation::Create {
+                    path, is_directory, ..
+                } => {
+                    fields.push(path_field(path));
+                    fields.push(if *is_directory { "dir" } else { "file" }.to_string());
+                }
+                Operation::Delete { .. } => {
+                    // Only deletions need their data to be undone.
+                    let stored = match &entry.storage {
+                        UndoStorage::Disk(path) => path.clone(),
+                        UndoStorage::Ram(data) => {
+                            let path = self.temp_dir.join(format!(
+                                "undo_{}_{}",
+                                SystemTime::now()
+                                    .duration_since(UNIX_EPOCH)
+                                    .unwrap()
+                                    .as_micros(),
+                                index
+                            ));
+                            self.fs.write(&path, data)?;
+                            path
+                        }
+                    };
+                    fields.push(entry.size.to_string());
+                    fields.push(path_field(&entry.original_path));
+                    fields.push(path_field(&stored));
+                }
+                Operation::Move {
+                    old_path, new_path, ..
+                } => {
+                    fields.push(path_field(old_path));
+                    fields.push(path_field(new_path));
+                }
+                Operation::Duplicate {
+                    original_path,
+                    new_path,
+                    ..
+                } => {
+                    fields.push(path_field(original_path));
+                    fields.push(path_field(new_path));
+                }
+                Operation::Copy {
+                    source_path,
+                    dest_path,
+                    ..
+                } => {
+                    fields.push(path_field(source_path));
+                    fields.push(path_field(dest_path));
+                }
+                Operation::Rename {
+                    path,
+                    old_name,
+                    new_name,
+                    ..
+                } => {
+                    fields.push(path_field(path));
+                    fields.push(escape_field(old_name));
+                    fields.push(escape_field(new_name));
+                }
+                Operation::ChangeMode { previous_modes, .. } => {
+                    for (path, mode) in previous_modes {
+                        fields.push(path_field(path));
+                        fields.push(format!("{:o}", mode));
+                    }
+                }
+                Operation::ChangeOwner {
+                    previous_owners, ..
+                } => {
+                    for (path, (uid, gid)) in previous_owners {
+                        fields.push(path_field(path));
+                        fields.push(format!("{}:{}", uid, gid));
+                    }
+                }
+            }
+            lines.push_str(&fields.join("\t"));
+            lines.push('\n');
+        }
+        self.fs.create_dir_all(&self.temp_dir)?;
+        self.fs.write(&self.history_path(), lines.as_bytes())
+    }
+
+    // Replaces the entries with the saved ones. No history file is an empty
+    // history, lines that do not parse are skipped.
+    pub fn load_history(&mut self) -> io::Result<()> {
+        let content = match self.fs.read(&self.history_path()) {
+            Ok(content) => content,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(e),
+        };
+        self.entries.clear();
+        self.ram_storage.clear();
+        self.total_disk_size = 0;
+        for line in String::from_utf8_lossy(&content).lines() {
+            if let Some(entry) = parse_history_line(line) {
+                if let UndoStorage::Disk(_) = entry.storage {
+                    self.total_disk_size += entry.size as u64;
+                }
+                self.entries.push_back(entry);
+            }
+        }
+        Ok(())
+    }
+}
+
+fn parse_history_line(line: &str) -> Option<UndoEntry> {
+    let fields: Vec<String> = line.split('\t').map(unescape_field).collect();
+    let timestamp = UNIX_EPOCH + Duration::from_secs(fields.get(1)?.parse().ok()?);
+    let path = |index: usize| fields.get(index).map(PathBuf::from);
+    let mut storage = UndoStorage::Ram(Vec::new());
+    let mut size = 0;
+    let (operation, original_path) = match fields[0].as_str() {
+        "create" => (
+            Operation::Create {
+                path: path(2)?,
+                is_directory: fields.get(3)? == "dir",
+                timestamp,
+            },
+            path(2)?,
+        ),
+        "delete" => {
+            size = fields.get(2)?.parse().ok()?;
+            storage = UndoStorage::Disk(path(4)?);
+            (Operation::Delete { timestamp }, path(3)?)
+        }
+        "move" => (
+            Operation::Move {
+                old_path: path(2)?,
+                new_path: path(3)?,
+                timestamp,
+            },
+            path(2)?,
+        ),
+        "duplicate" => (
+            Operation::Duplicate {
+                original_path: path(2)?,
+                new_path: path(3)?,
+                timestamp,
+            },
+            path(3)?,
+        ),
+        "copy" => (
+            Operation::Copy {
+                source_path: path(2)?,
+                dest_path: path(3)?,
+                timestamp,
+            },
+            path(3)?,
+        ),
+        "rename" => {
+            let parent = path(2)?;
+            let new_name = fields.get(4)?.clone();
+            (
+                Operation::Rename {
+                    path: parent.clone(),
+                    old_name: fields.get(3)?.clone(),
+                    new_name: new_name.clone(),
+                    timestamp,
+                },
+                parent.join(new_name),
+            )
+        }
+        "chmod" => {
+            let previous_modes = fields[2..]
+                .chunks(2)
+                .map(|pair| match pair {
+                    [path, mode] => Some((PathBuf::from(path), u32::from_str_radix(mode, 8).ok()?)),
+                    _ => None,
+                })
+                .collect::<Option<PreviousModes>>()?;
+            let first = previous_modes.first()?.0.clone();
+            (
+                Operation::ChangeMode {
+                    previous_modes,
+                    timestamp,
+                },
+                first,
+            )
+        }
+        "chown" => {
+            let previous_owners = fields[2..]
+                .chunks(2)
+                .map(|pair| match pair {
+                    [path, owner] => {
+                        let (uid, gid) = owner.split_once(':')?;
+                        Some((PathBuf::from(path), (uid.parse().ok()?, gid.parse().ok()?)))
+                    }
+                    _ => None,
+                })
+                .collect::<Option<PreviousOwners>>()?;
+            let first = previous_owners.first()?.0.clone();
+            (
+                Operation::ChangeOwner {
+                    previous_owners,
+                    timestamp,
+                },
+                first,
+            )
+        }
+        _ => return None,
+    };
+    Some(UndoEntry {
+        operation,
+        storage,
+        original_path,
+        size,
+    })
+}
+
+// Paths with the mode they had before a change, in the order it was made.
+pub type PreviousModes = Vec<(PathBuf, u32)>;
+
+// Puts back modes recorded by apply_mode_changes, parents first.
+pub fn restore_modes(fs: &dyn FileSystem, previous: &[(PathBuf, u32)]) -> usize {
+    previous
+        .iter()
+        .rev()
+        .filter(|(path, mode)| fs.set_mode(path, *mode).is_ok())
+        .count()
+}
+
+// Paths with the uid and gid they had before a change.
+pub type PreviousOwners = Vec<(PathBuf, (u32, u32))>;
+
+pub fn restore_owners(fs: &dyn FileSystem, previous: &[(PathBuf, (u32, u32))]) -> usize {
+    previous
+        .iter()
+        .rev()
+        .filter(|(path, (uid, gid))| fs.set_owner(path, *uid, *gid).is_ok())
+        .count()
+}
+
+/////////////////////////////////////////////////////File Operations//////////////////////////////////////////////////////////////////
+// Changes to files that follow the color rules and can be undone. Nothing is
+// ever overwritten: a taken name gets a number, or the operation is refused.
+
+pub struct FileOperations<'a> {
+    pub config: &'a Config,
+    pub undo_manager: &'a mut UndoManager,
+}
+
+fn not_allowed(path: &Path, operation: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::PermissionDenied,
+        format!("{} is not allowed to be {}", path.display(), operation),
+    )
+}
+
+fn file_name_of(path: &Path) -> io::Result<&std::ffi::OsStr> {
+    path.file_name()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "Invalid file name"))
+}
+
+// The path itself when it is free, otherwise "name (n).ext" with the first
+// number that is.
+pub fn free_path(fs: &dyn FileSystem, path: &Path) -> PathBuf {
+    if !fs.exists(path) {
+        return path.to_path_buf();
+    }
+    let parent = path.parent().unwrap_or(Path::new(""));
+    let file_stem = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let extension = path
+        .extension()
+        .map(|ext| format!(".{}", ext.to_string_lossy()))
+        .unwrap_or_default();
+    let mut counter = 1;
+    loop {
+        let candidate = parent.join(format!("{} ({}){}", file_stem, counter, extension));
+        if !fs.exists(&candidate) {
+            return candidate;
+        }
+        counter += 1;
+    }
+}
+
+// Files are kept in memory, directories and links are copied into the undo folder.
+fn copy_to_storage(undo_manager: &UndoManager, path: &Path) -> io::Result<(UndoStorage, usize)> {
+    let fs = undo_manager.fs.as_ref();
+    if fs.is_file(path) && !fs.is_symlink(path) {
+        let buffer = fs.read(path)?;
+        let size = buffer.len();
+        Ok((UndoStorage::Ram(buffer), size))
+    } else {
+        fs.create_dir_all(&undo_manager.temp_dir)?;
+        let temp_path = free_path(fs, &undo_manager.temp_dir.join(file_name_of(path)?));
+        let size = fs.copy_all(path, &temp_path)?;
+        Ok((UndoStorage::Disk(temp_path), size as usize))
+    }
+}
+
+impl<'a> FileOperations<'a> {
+    pub fn new(config: &'a Config, undo_manager: &'a mut UndoManager) -> Self {
+        FileOperations {
+            config,
+            undo_manager,
+        }
+    }
+
+    fn fs(&self) -> Arc<dyn FileSystem> {
+        self.undo_manager.fs.clone()
+    }
+
+    fn check(&self, path: &Path, operation: &str, done: &str) -> io::Result<()> {
+        if self
+            .config
+            .operation_allowed(self.undo_manager.fs.as_ref(), path, operation)
+        {
+            Ok(())
+        } else {
+            Err(not_allowed(path, done))
+        }
+    }
+
+    // Copies a file or directory into dest_dir.
+    pub fn paste(&mut self, source_path: &Path, dest_dir: &Path) -> io::Result<PathBuf> {
+        self.check(source_path, "copy", "copied")?;
+        let fs = self.fs();
+        let destination = free_path(fs.as_ref(), &dest_dir.join(file_name_of(source_path)?));
+        fs.copy_all(source_path, &destination)?;
+
+        let file_content = if fs.is_file(&destination) {
+            fs.read(&destination)?
+        } else {
+            Vec::new()
+        };
+
+        self.undo_manager.add_tome_entry(UndoEntry {
+            operation: Operation::Copy {
+                source_path: source_path.to_path_buf(),
+                dest_path: destination.clone(),
+                timestamp: SystemTime::now(),
+            },
+            size: file_content.len(),
+            storage: UndoStorage::Ram(file_content),
+            original_path: destination.clone(),
+        })?;
+        Ok(destination)
+    }
+
+    // Copies a file or directory next to itself as "name (n).ext".
+    pub fn duplicate(&mut self, path: &Path) -> io::Result<PathBuf> {
+        self.check(path, "copy", "copied")?;
+        let fs = self.fs();
+        let new_path = free_path(fs.as_ref(), path);
+        fs.copy_all(path, &new_path)?;
+
+        let file_content = if fs.is_file(&new_path) {
+            fs.read(&new_path)?
+        } else {
+            Vec::new()
+        };
+
+        self.undo_manager.add_tome_entry(UndoEntry {
+            operation: Operation::Duplicate {
+                original_path: path.to_path_buf(),
+                new_path: new_path.clone(),
+                timestamp: SystemTime::now(),
+            },
+            size: file_content.len(),
+            storage: UndoStorage::Ram(file_content),
+            original_path: new_path.clone(),
+        })?;
+        Ok(new_path)
+    }
+
+    // Deletes a file or directory, keeping a copy so it can be undone.
+    pub fn delete(&mut self, path: &Path) -> io::Result<()> {
+        self.check(path, "delete", "deleted")?;
+        let (storage, size) = copy_to_storage(self.undo_manager, path)?;
+        self.undo_manager.add_tome_entry(UndoEntry {
+            operation: Operation::Delete {
+                timestamp: SystemTime::now(),
+            },
+            storage,
+            original_path: path.to_path_buf(),
+            size,
+        })?;
+        self.fs().remove_all(path)
+    }
+
+    // Renames in place. An existing entry under the new name is left alone.
+    pub fn rename(&mut self, path: &Path, new_name: &str) -> io::Result<PathBuf> {
+        self.check(path, "rename", "renamed")?;
+        let old_name = file_name_of(path)?.to_string_lossy().into_owned();
+        let parent = path.parent().unwrap_or(Path::new(""));
+        let new_path = parent.join(new_name);
+        // Only the case changes, which some file systems see as the same name.
+        let same_name = old_name.to_lowercase() == new_name.to_lowercase();
+        let fs = self.fs();
+        if fs.exists(&new_path) && !same_name {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("'{}' already exists", new_name),
+            ));
+        }
+        fs.rename(path, &new_path)?;
+        self.undo_manager.add_tome_entry(UndoEntry {
+            operation: Operation::Rename {
+                old_name,
+                new_name: new_name.to_string(),
+                path: parent.to_path_buf(),
+                timestamp: SystemTime::now(),
+            },
+            storage: UndoStorage::Ram(Vec::new()),
+            original_path: new_path.clone(),
+            size: 0,
+        })?;
+        Ok(new_path)
+    }
+
+    // Moves a file or directory into dest_dir, where it keeps its name.
+    // Moving over something would lose it for good, so that is refused.
+    pub fn move_into(&mut self, source_path: &Path, dest_dir: &Path) -> io::Result<PathBuf> {
+        self.check(source_path, "move", "moved")?;
+        let dest_path = dest_dir.join(file_name_of(source_path)?);
+        if dest_path == source_path {
+            return Ok(dest_path);
+        }
+        let fs = self.fs();
+        if fs.exists(&dest_path) {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("{} already exists", dest_path.display()),
+            ));
+        }
+        fs.rename(source_path, &dest_path)?;
+        self.undo_manager
+            .add_move_operation(source_path.to_path_buf(), dest_path.clone())?;
+        Ok(dest_path)
+    }
+
+    pub fn make_directory(&mut self, path: &Path) -> io::Result<()> {
+        self.check_creatable(path)?;
+        self.fs().create_dir(path)?;
+        self.undo_manager.add_create_operation(path.to_path_buf(), true)
+    }
+
+    pub fn make_file(&mut self, path: &Path) -> io::Result<()> {
+        self.check_creatable(path)?;
+        self.fs().write(path, &[])?;
+        self.undo_manager.add_create_operation(path.to_path_buf(), false)
+    }
+
+    fn check_creatable(&self, path: &Path) -> io::Result<()> {
+        if self.undo_manager.fs.exists(path) {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("{} already exists", path.display()),
+            ));
+        }
+        let parent_path = path.parent().unwrap_or(Path::new(""));
+        if !self
+            .config
+            .operation_allowed(self.undo_manager.fs.as_ref(), parent_path, "create")
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                "Creation not allowed due to color rules",
+            ));
+        }
+        Ok(())
+    }
+
+    // Reverts the newest entry on the history, None when there is nothing left.
+    pub fn undo(&mut self) -> Option<io::Result<String>> {
+        let entry = self.undo_manager.undo_haunting_regret()?;
+        Some(self.revert(entry))
+    }
+
+    // Puts back what one history entry changed and says what was done. When the
+    // way back is blocked by something new, nothing is touched and the entry goes
+    // back on the history to be tried again.
+    pub fn revert(&mut self, entry: UndoEntry) -> io::Result<String> {
+        let fs = self.fs();
+        let blocked_by = match &entry.operation {
+            Operation::Move { old_path, .. } => Some(old_path.clone()),
+            Operation::Delete { .. } => Some(entry.original_path.clone()),
+            Operation::Rename {
+                path,
+                old_name,
+                new_name,
+                ..
+            } if old_name.to_lowercase() != new_name.to_lowercase() => Some(path.join(old_name)),
+            _ => None,
+        }
+        .filter(|path| fs.exists(path));
+        if let Some(path) = blocked_by {
+            self.undo_manager.add_tome_entry(entry)?;
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("{} already exists", path.display()),
+            ));
+        }
+
+        match entry.operation {
+            Operation::Move {
+                old_path, new_path, ..
+            } => {
+                if !fs.exists(&new_path) {
+                    return Err(io::Error::new(
+                        io::ErrorKind::NotFound,
+                        "the moved item no longer exists",
+                    ));
+                }
+                fs.rename(&new_path, &old_path)?;
+                Ok(format!(
+                    "Undid move. Moved back: {} -> {}",
+                    new_path.display(),
+                    old_path.display()
+                ))
+            }
+            Operation::Delete { .. } => {
+                match entry.storage {
+                    UndoStorage::Ram(data) => fs.write(&entry.original_path, &data)?,
+                    UndoStorage::Disk(temp_path) => fs.rename(&temp_path, &entry.original_path)?,
+                }
+                Ok("Undid deletion. File/directory restored.".to_string())
+            }
+            Operation::Rename {
+                path,
+                old_name,
+                new_name,
+                ..
+            } => {
+                fs.rename(&path.join(&new_name), &path.join(&old_name))?;
+                Ok(format!(
+                    "Undid rename. File/directory name restored to '{}'.",
+                    old_name
+                ))
+            }
+            Operation::Create { path, .. } => {
+                let _ = fs.remove_all(&path);
+                Ok(format!("Undid creation. Removed: {}", path.display()))
+            }
+            Operation::Copy { dest_path, .. } => {
+                fs.remove_all(&dest_path)?;
+                Ok(format!("Undid copy. Removed: {}", dest_path.display()))
+            }
+            Operation::Duplicate { new_path, .. } => {
+                fs.remove_all(&new_path)?;
+                Ok(format!("Undid duplication. Removed: {}", new_path.display()))
+            }
+            Operation::ChangeMode { previous_modes, .. } => {
+                let restored = restore_modes(fs.as_ref(), &previous_modes);
+                Ok(format!(
+                    "Undid permission change. Restored {} of {} entries.",
+                    restored,
+                    previous_modes.len()
+                ))
+            }
+            Operation::ChangeOwner {
+                previous_owners, ..
+            } => {
+                let restored = restore_owners(fs.as_ref(), &previous_owners);
+                Ok(format!(
+                    "Undid ownership change. Restored {} of {} entries.",
+                    restored,
+                    previous_owners.len()
+                ))
+            }
+        }
+    }
+}
@@ -0,0 +1,141 @@
+/*
+* Stygian Sift - A Terminal-based File Manager
+ * Copyright (C) 2024 Maui The Magnificent (Charon)
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published
+ * by the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+ * Contact: Maui_The_Magnificent@proton.me
+ * Project repository: https://github.com/Mauitron/StygianSift.git
+ */
+
+// The command palette: every action with its keys and what it does, the
+// context menu entries and the custom commands, found by typing part of a
+// name. The ones run last come first.
+
+use super::*;
+
+// How many recently run commands are kept.
+pub const RECENT_COMMANDS: usize = 10;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PaletteCommand {
+    Action(Action),
+    // A context menu entry by its label.
+    Menu(String),
+    // A custom command by its name.
+    Custom(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PaletteEntry {
+    pub command: PaletteCommand,
+    pub name: String,
+    pub keys: String,
+    pub description: String,
+}
+
+// Takes the labels of the context menu, which the core does not know.
+pub fn palette_entries(config: &Config, menu: &[String]) -> Vec<PaletteEntry> {
+    let mut entries: Vec<PaletteEntry> = Action::iter()
+        .filter(|action| **action != Action::CommandPalette)
+        .map(|action| {
+            let mut keys: Vec<String> = config
+                .keys_for(action)
+                .iter()
+                .map(|keys| keys.to_string())
+                .collect();
+            if config.modal_input {
+                for (mode, bindings) in &config.mode_keybindings {
+                    let mut bound: Vec<String> = bindings
+                        .iter()
+                        .filter(|(_, bound)| *bound == action)
+                        .map(|(keys, _)| format!("{} in {}", keys, mode))
+                        .collect();
+                    bound.sort();
+                    keys.extend(bound);
+                }
+            }
+            PaletteEntry {
+                command: PaletteCommand::Action(action.clone()),
+                name: action.to_string(),
+                keys: keys.join(", "),
+                description: action.description().to_string(),
+            }
+        })
+        .collect();
+    entries.extend(menu.iter().map(|label| PaletteEntry {
+        command: PaletteCommand::Menu(label.clone()),
+        name: label.clone(),
+        keys: String::new(),
+        description: "From the context menu".to_string(),
+    }));
+    entries.extend(
+        config
+            .custom_commands
+            .iter()
+            .map(|(name, line)| PaletteEntry {
+                command: PaletteCommand::Custom(name.clone()),
+                name: name.clone(),
+                keys: String::new(),
+                description: format!("Runs {}", line),
+            }),
+    );
+    entries
+}
+
+// The entries with every character of query in their name or description,
+// best first. Among equal ones, and when nothing is typed, the recent ones
+// lead.
+pub fn rank_palette<'a>(
+    entries: &'a [PaletteEntry],
+    query: &str,
+    recent: &[PaletteCommand],
+) -> Vec<&'a PaletteEntry> {
+    let query = query.trim();
+    let recency = |entry: &PaletteEntry| {
+        recent
+            .iter()
+            .position(|command| *command == entry.command)
+            .unwrap_or(recent.len())
+    };
+    let mut ranked: Vec<(usize, usize, usize, &PaletteEntry)> = entries
+        .iter()
+        .enumerate()
+        .filter_map(|(i, entry)| {
+            let score = |text: &str| match has_in_order(text, query) {
+                true => fuzzy_match(text, query).max(1),
+                false => 0,
+            };
+            let score = score(&entry.name).max(score(&entry.description));
+            (query.is_empty() || score > 0).then(|| (score, recency(entry), i, entry))
+        })
+        .collect();
+    ranked.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)).then(a.2.cmp(&b.2)));
+    ranked.into_iter().map(|(.., entry)| entry).collect()
+}
+
+fn has_in_order(text: &str, query: &str) -> bool {
+    let mut text = text.chars().flat_map(char::to_lowercase);
+    query
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .flat_map(char::to_lowercase)
+        .all(|c| text.any(|t| t == c))
+}
+
+pub fn remember_command(recent: &mut Vec<PaletteCommand>, command: PaletteCommand) {
+    recent.retain(|earlier| *earlier != command);
+    recent.insert(0, command);
+    recent.truncate(RECENT_COMMANDS);
+}
@@ -0,0 +1,152 @@
+/*
+* Stygian Sift - A Terminal-based File Manager
+ * Copyright (C) 2024 Maui The Magnificent (Charon)
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published
+ * by the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+ * Contact: Maui_The_Magnificent@proton.me
+ * Project repository: https://github.com/Mauitron/StygianSift.git
+ */
+
+// Named sets of keybindings, theme and shortcut layers, like a "work" and
+// a "minimal" setup in one config file. The profile in use is swapped into
+// the config, so everything reads it as usual and changes made meanwhile
+// end up in the profile when it is swapped out again for saving.
+
+use super::*;
+
+// What a [profiles.NAME] table may set.
+pub const PROFILE_SETTINGS: &[&str] = &[
+    "theme",
+    "color_depth",
+    "draw_simple_borders",
+    "keybindings",
+    "layers",
+];
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Profile {
+    pub theme: Option<String>,
+    pub color_depth: Option<String>,
+    pub draw_simple_borders: Option<bool>,
+    pub keybindings: Option<HashMap<KeySequence, Action>>,
+    pub shortcut_layers: Option<Vec<ShortcutLayer>>,
+}
+
+impl Profile {
+    // Takes the entries of one profile table with its name stripped off.
+    pub fn from_entries(entries: &[TomlEntry]) -> (Self, Vec<TomlError>) {
+        let (allowed, refused): (Vec<TomlEntry>, Vec<TomlEntry>) = entries
+            .iter()
+            .cloned()
+            .partition(|entry| {
+                // Mode bindings are for the whole config.
+                PROFILE_SETTINGS.contains(&entry.path[0].as_str())
+                    && !(entry.path[0] == "keybindings" && entry.path.len() > 2)
+            });
+        let mut errors: Vec<TomlError> = refused
+            .into_iter()
+            .map(|entry| {
+                TomlError::new(
+                    entry.line,
+                    format!("'{}' can not be set in a profile", entry.path.join(".")),
+                )
+            })
+            .collect();
+        let (parsed, more) = Config::from_toml_entries(&allowed);
+        let has_table = |name: &str| allowed.iter().any(|entry| entry.path[0] == name);
+        let profile = Profile {
+            theme: is_set(&allowed, &more, "theme").then(|| parsed.theme.clone()),
+            color_depth: is_set(&allowed, &more, "color_depth").then(|| parsed.color_depth.clone()),
+            draw_simple_borders: is_set(&allowed, &more, "draw_simple_borders")
+                .then_some(parsed.draw_simple_borders),
+            keybindings: has_table("keybindings")
+                .then(|| parsed.keybindings.clone().unwrap_or_default()),
+            shortcut_layers: has_table("layers").then(|| parsed.shortcut_layers.clone()),
+        };
+        errors.extend(more);
+        (profile, errors)
+    }
+
+    pub fn to_toml(&self, name: &str, lines: &mut Vec<String>) {
+        let table = format!("profiles.{}", toml_key(name));
+        lines.push(String::new());
+        lines.push(format!("[{}]", table));
+        if let Some(theme) = &self.theme {
+            lines.push(format!("theme = {}", toml_string(theme)));
+        }
+        if let Some(depth) = &self.color_depth {
+            lines.push(format!("color_depth = {}", toml_string(depth)));
+        }
+        if let Some(simple) = self.draw_simple_borders {
+            lines.push(format!("draw_simple_borders = {}", simple));
+        }
+        if let Some(keybindings) = &self.keybindings {
+            keybinding_table(lines, &format!("{}.keybindings", table), keybindings);
+        }
+        if let Some(layers) = &self.shortcut_layers {
+            layer_tables(lines, &format!("{}.layers", table), layers);
+        }
+    }
+}
+
+impl Config {
+    // Swaps the profile in use out and name in, None goes back to the plain
+    // config. The profile setting, which one to start with, stays as it is.
+    pub fn use_profile(&mut self, name: Option<&str>) -> Result<(), String> {
+        if let Some(name) = name {
+            if !self.profiles.contains_key(name) {
+                return Err(format!("unknown profile '{}'", name));
+            }
+        }
+        if let Some(active) = self.active_profile.take() {
+            self.swap_profile(&active);
+        }
+        if let Some(name) = name {
+            self.swap_profile(name);
+            self.active_profile = Some(name.to_string());
+        }
+        Ok(())
+    }
+
+    fn swap_profile(&mut self, name: &str) {
+        let Some(profile) = self.profiles.get_mut(name) else {
+            return;
+        };
+        if let Some(theme) = profile.theme.as_mut() {
+            std::mem::swap(theme, &mut self.theme);
+        }
+        if let Some(depth) = profile.color_depth.as_mut() {
+            std::mem::swap(depth, &mut self.color_depth);
+        }
+        if let Some(simple) = profile.draw_simple_borders.as_mut() {
+            std::mem::swap(simple, &mut self.draw_simple_borders);
+        }
+        if let (Some(theirs), Some(mine)) =
+            (profile.keybindings.as_mut(), self.keybindings.as_mut())
+        {
+            std::mem::swap(theirs, mine);
+        }
+        if let Some(layers) = profile.shortcut_layers.as_mut() {
+            std::mem::swap(layers, &mut self.shortcut_layers);
+            self.current_layer = self
+                .current_layer
+                .min(self.shortcut_layers.len().saturating_sub(1));
+        }
+    }
+
+    pub fn profile_names(&self) -> Vec<&str> {
+        self.profiles.keys().map(String::as_str).collect()
+    }
+}
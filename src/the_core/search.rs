@@ -0,0 +1,357 @@
+/*
+* Stygian Sift - A Terminal-based File Manager
+ * Copyright (C) 2024 Maui The Magnificent (Charon)
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published
+ * by the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+ * Contact: Maui_The_Magnificent@proton.me
+ * Project repository: https://github.com/Mauitron/StygianSift.git
+ */
+
+use super::*;
+//////////////////////////////////////////////////////Search/////////////////////////////////////////////////////////////////////////
+fn parse_color_search(search_term: &str) -> (Option<MarkerColor>, String) {
+    let color_prefixes = [
+        ("red:", MarkerColor::Red),
+        ("orange:", MarkerColor::Orange),
+        ("yellow:", MarkerColor::Yellow),
+        ("blue:", MarkerColor::Blue),
+        ("cyan:", MarkerColor::Cyan),
+        ("pink:", MarkerColor::Pink),
+        ("white:", MarkerColor::White),
+        ("green:", MarkerColor::Green),
+        ("magenta:", MarkerColor::Magenta),
+        ("reset:", MarkerColor::Reset),
+        ("colored:", MarkerColor::Reset),
+        ("nocolor:", MarkerColor::Reset),
+        ("hidecolor:", MarkerColor::Reset),
+    ];
+
+    color_prefixes
+        .par_iter()
+        .find_first(|(prefix, _)| search_term.to_lowercase().starts_with(prefix))
+        .map(|(prefix, color)| (Some(*color), search_term[prefix.len()..].to_string()))
+        .unwrap_or((None, search_term.to_string()))
+}
+
+pub struct SearchFilters {
+    pub color_filters: HashMap<MarkerColor, bool>,
+    pub show_uncolored: bool,
+    pub show_filters: bool,
+    pub hide_all_colors: bool,
+}
+
+impl SearchFilters {
+    pub fn new() -> Self {
+        let mut color_filters = HashMap::new();
+        for color in [
+            MarkerColor::Red,
+            MarkerColor::Orange,
+            MarkerColor::Yellow,
+            MarkerColor::Blue,
+            MarkerColor::Cyan,
+            MarkerColor::Pink,
+            MarkerColor::White,
+            MarkerColor::Green,
+            MarkerColor::Magenta,
+            MarkerColor::Reset,
+        ] {
+            color_filters.insert(color, false);
+        }
+        Self {
+            color_filters,
+            show_uncolored: true,
+            show_filters: false,
+            hide_all_colors: false,
+        }
+    }
+}
+
+pub fn fuzzy_navigate(
+    current_dir: &Path,
+    input: &str,
+    max_results: usize,
+) -> io::Result<Vec<PathBuf>> {
+    let entries = fs::read_dir(current_dir)?
+        .filter_map(|entry| entry.ok())
+        .collect::<Vec<_>>();
+
+    let results: Vec<_> = entries
+        .into_par_iter()
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let score = fuzzy_match(&name, input);
+            if score > 0 {
+                Some((entry.path(), score))
+            } else {
+                None
+            }
+        })
+        .collect();
+
+    let mut sorted_results = results;
+    sorted_results.sort_by(|a, b| b.1.cmp(&a.1));
+
+    Ok(sorted_results
+        .into_iter()
+        .take(max_results)
+        .map(|(path, _)| path)
+        .collect())
+}
+
+// Everything under dir, up to max_depth levels down, whose name fuzzy matches
+// the term. A "red:" style prefix narrows it to a color, the filters and the
+// search rules of the config apply on top.
+pub fn fuzzy_search_entries(
+    config: &Config,
+    filters: &SearchFilters,
+    dir: &Path,
+    search_term: &str,
+    max_depth: usize,
+) -> io::Result<Vec<FileEntry>> {
+    let (color_filter, actual_search_term) = parse_color_search(search_term);
+
+    fn search_directory(
+        config: &Config,
+        filters: &SearchFilters,
+        dir: &Path,
+        search_term: &str,
+        color_filter: Option<MarkerColor>,
+        depth: usize,
+        max_depth: usize,
+    ) -> Vec<FileEntry> {
+        if depth > max_depth {
+            return Vec::new();
+        }
+
+        let mut results = Vec::new();
+
+        if let Ok(entries) = fs::read_dir(dir) {
+            let accessible_entries: Vec<_> = entries.filter_map(Result::ok).collect();
+
+            let mut entry_results: Vec<_> = accessible_entries
+                .par_iter()
+                .filter_map(|entry| {
+                    let path = entry.path();
+                    if !config.is_searchable(&path) {
+                        return None;
+                    }
+
+                    let item_color = config.get_item_color(&path);
+                    if filters.hide_all_colors && item_color.is_some() {
+                        return None;
+                    } else if search_term.to_lowercase().starts_with("hidecolor:") {
+                        if item_color.is_some() {
+                            return None;
+                        }
+                    }
+
+                    match color_filter {
+                        Some(filter_color) => {
+                            if filters.hide_all_colors && item_color.is_some() {
+                                return None;
+                            }
+                            if search_term.to_lowercase().starts_with("hidecolor:")
+                                && item_color.is_some()
+                            {
+                                return None;
+                            }
+                            if search_term.to_lowercase().starts_with("colored:")
+                                && item_color.is_none()
+                            {
+                                return None;
+                            }
+                            if search_term.to_lowercase().starts_with("nocolor:")
+                                && item_color.is_some()
+                            {
+                                return None;
+                            }
+                            if !search_term.to_lowercase().starts_with("colored:")
+                                && !search_term.to_lowercase().starts_with("nocolor:")
+                                && item_color != Some(filter_color)
+                            {
+                                return None;
+                            }
+                        }
+                        None => {
+                            let any_filters_active =
+                                filters.color_filters.values().any(|&v| v);
+                            if any_filters_active {
+                                match item_color {
+                                    Some(color) => {
+                                        if !filters
+                                            .color_filters
+                                            .get(&color)
+                                            .copied()
+                                            .unwrap_or(false)
+                                        {
+                                            return None;
+                                        }
+                                    }
+                                    None => {
+                                        if !filters.show_uncolored {
+                                            return None;
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+
+                    let name = path.file_name().unwrap_or_default().to_string_lossy();
+                    let score = if search_term.is_empty() {
+                        1
+                    } else {
+                        fuzzy_match(&name, search_term)
+                    };
+
+                    if score > 0 {
+                        FileEntry::new(path).ok()
+                    } else {
+                        None
+                    }
+                })
+                .collect();
+
+            results.append(&mut entry_results);
+
+            let subdirs: Vec<_> = accessible_entries
+                .par_iter()
+                .filter(|entry| entry.path().is_dir() && config.is_searchable(&entry.path()))
+                .map(|entry| entry.path())
+                .collect();
+
+            let subdir_results: Vec<_> = subdirs
+                .par_iter()
+                .flat_map(|path| {
+                    search_directory(
+                        config,
+                        filters,
+                        path,
+                        search_term,
+                        color_filter,
+                        depth + 1,
+                        max_depth,
+                    )
+                })
+                .collect();
+
+            results.extend(subdir_results);
+        }
+
+        results
+    }
+
+    let results = search_directory(
+        config,
+        filters,
+        dir,
+        &actual_search_term,
+        color_filter,
+        0,
+        max_depth,
+    );
+
+    let mut scored_results: Vec<_> = results
+        .into_par_iter()
+        .map(|entry| {
+            let score = if actual_search_term.is_empty() {
+                1
+            } else {
+                fuzzy_match(&entry.name, &actual_search_term)
+            };
+            (entry, score)
+        })
+        .collect();
+
+    scored_results.par_sort_unstable_by(|a, b| b.1.cmp(&a.1));
+
+    Ok(scored_results.into_iter().map(|(entry, _)| entry).collect())
+}
+
+pub fn fuzzy_match(name: &str, input: &str) -> usize {
+    let name_lower = name.to_lowercase();
+    let input_lower = input.to_lowercase();
+
+    if input_lower.len() > 10 {
+        let chars: Vec<_> = name_lower.chars().collect();
+        let input_chars: Vec<_> = input_lower.chars().collect();
+
+        let score = chars
+            .par_windows(input_chars.len())
+            .map(|window| {
+                let mut local_score = 0;
+                let mut consecutive = 0;
+
+                for (window_c, input_c) in window.iter().zip(input_chars.iter()) {
+                    if window_c == input_c {
+                        local_score += 1 + consecutive;
+                        consecutive += 1;
+                    } else {
+                        consecutive = 0;
+                    }
+                }
+
+                local_score
+            })
+            .max()
+            .unwrap_or(0);
+
+        let boundary_bonus = if name_lower.starts_with(&input_lower)
+            || name_lower
+                .split(|c| c == ' ' || c == '_' || c == '-')
+                .any(|word| word.starts_with(&input_lower))
+        {
+            5
+        } else {
+            0
+        };
+
+        (score + boundary_bonus).try_into().unwrap_or(0)
+    } else {
+        let mut score: i32 = 0;
+        let mut name_iter = name_lower.chars().peekable();
+        let mut input_iter = input_lower.chars().peekable();
+        let mut consecutive = 0;
+        let mut last_matched_pos = None;
+
+        while let (Some(name_c), Some(input_c)) = (name_iter.peek(), input_iter.peek()) {
+            if name_c == input_c {
+                score += 1 + consecutive;
+                consecutive += 1;
+                last_matched_pos = Some(name_lower.len() - name_iter.clone().count());
+                name_iter.next();
+                input_iter.next();
+            } else {
+                consecutive = 0;
+                name_iter.next();
+            }
+        }
+
+        score = score.saturating_sub(input_iter.count() as i32);
+
+        if let Some(pos) = last_matched_pos {
+            if pos == 0
+                || name_lower
+                    .chars()
+                    .nth(pos.saturating_sub(1))
+                    .map_or(false, |c| c == ' ' || c == '_' || c == '-')
+            {
+                score += 5;
+            }
+        }
+
+        score.try_into().unwrap_or(0)
+    }
+}
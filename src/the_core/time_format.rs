@@ -0,0 +1,558 @@
+/*
+* Stygian Sift - A Terminal-based File Manager
+ * Copyright (C) 2024 Maui The Magnificent (Charon)
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published
+ * by the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+ * Contact: Maui_The_Magnificent@proton.me
+ * Project repository: https://github.com/Mauitron/StygianSift.git
+ */
+
+use super::*;
+use std::sync::OnceLock;
+/////////////////////////////////////////////////////////Time/////////////////////////////////////////////////////////////////////////
+// Civil dates, the local time zone and a small strftime. The zone comes from
+// TZ or /etc/localtime in the TZif format, without that we stay in UTC.
+
+pub const DEFAULT_TIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+const MONTHS: [&str; 12] = [
+    "January", "February", "March", "April", "May", "June", "July", "August", "September",
+    "October", "November", "December",
+];
+const WEEKDAYS: [&str; 7] = [
+    "Sunday", "Monday", "Tuesday", "Wednesday", "Thursday", "Friday", "Saturday",
+];
+
+// Days since 1970-01-01 for a proleptic Gregorian date, and back again.
+pub fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
+
+pub fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let day_of_era = z - era * 146097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+fn is_leap_year(year: i64) -> bool {
+    (year % 4 == 0 && year % 100 != 0) || year % 400 == 0
+}
+
+fn days_in_month(year: i64, month: u32) -> u32 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+// 0 is Sunday, 1970-01-01 was a Thursday.
+fn weekday(days: i64) -> u32 {
+    (days + 4).rem_euclid(7) as u32
+}
+
+////////////////////////////////////////////////////////Time Zone/////////////////////////////////////////////////////////////////////
+#[derive(Debug, Clone)]
+struct LocalType {
+    offset: i64,
+    abbreviation: String,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum RuleDay {
+    // Jn, 1 to 365 where February 29th is never counted.
+    Julian(i64),
+    // n, 0 to 365 counting February 29th.
+    DayOfYear(i64),
+    // Mm.w.d, week 5 means the last one.
+    MonthWeekDay(u32, u32, u32),
+}
+
+#[derive(Debug, Clone, Copy)]
+struct RuleTime {
+    day: RuleDay,
+    seconds: i64,
+}
+
+// The POSIX TZ string at the end of a TZif file covers the years after the
+// last listed transition, "CET-1CEST,M3.5.0,M10.5.0/3" and the like.
+#[derive(Debug, Clone)]
+struct PosixRule {
+    standard: LocalType,
+    daylight: Option<(LocalType, RuleTime, RuleTime)>,
+}
+
+// A zone read from a TZif file or a TZ string. The browser only ever uses
+// the local one, the rest is for tools and tests that need a fixed zone.
+#[derive(Debug, Clone, Default)]
+pub struct TimeZone {
+    transitions: Vec<i64>,
+    transition_types: Vec<usize>,
+    types: Vec<LocalType>,
+    rule: Option<PosixRule>,
+}
+
+impl RuleTime {
+    // Local seconds since the epoch at which this rule fires in `year`.
+    fn local_seconds(&self, year: i64) -> i64 {
+        let day = match self.day {
+            RuleDay::Julian(n) => {
+                let skip_leap_day = is_leap_year(year) && n >= 60;
+                days_from_civil(year, 1, 1) + n - 1 + skip_leap_day as i64
+            }
+            RuleDay::DayOfYear(n) => days_from_civil(year, 1, 1) + n,
+            RuleDay::MonthWeekDay(month, week, day) => {
+                let first = days_from_civil(year, month as i64, 1);
+                let mut date = 1 + (day + 7 - weekday(first)) % 7 + (week - 1) * 7;
+                while date > days_in_month(year, month) {
+                    date -= 7;
+                }
+                first + date as i64 - 1
+            }
+        };
+        day * 86400 + self.seconds
+    }
+}
+
+impl PosixRule {
+    fn local_type(&self, utc: i64) -> &LocalType {
+        let (daylight, start, end) = match &self.daylight {
+            Some((daylight, start, end)) => (daylight, start, end),
+            None => return &self.standard,
+        };
+        let (year, _, _) = civil_from_days((utc + self.standard.offset).div_euclid(86400));
+        // Start is given in standard time, end in daylight time.
+        let start = start.local_seconds(year) - self.standard.offset;
+        let end = end.local_seconds(year) - daylight.offset;
+        let in_daylight = if start < end {
+            utc >= start && utc < end
+        } else {
+            // Southern hemisphere, daylight time spans the new year.
+            !(utc >= end && utc < start)
+        };
+        if in_daylight {
+            daylight
+        } else {
+            &self.standard
+        }
+    }
+}
+
+impl TimeZone {
+    pub fn utc() -> Self {
+        Self {
+            types: vec![LocalType {
+                offset: 0,
+                abbreviation: "UTC".to_string(),
+            }],
+            ..Self::default()
+        }
+    }
+
+    // The contents of a file like /usr/share/zoneinfo/Europe/Amsterdam.
+    pub fn from_tzif(data: &[u8]) -> Option<Self> {
+        parse_tzif(data)
+    }
+
+    // Just a rule, "EST5EDT,M3.2.0,M11.1.0" and the like.
+    pub fn from_posix(text: &str) -> Option<Self> {
+        Some(Self {
+            rule: Some(parse_posix_tz(text)?),
+            ..Self::utc()
+        })
+    }
+
+    fn local_type(&self, utc: i64) -> &LocalType {
+        let after_last = self.transitions.last().is_none_or(|&last| utc >= last);
+        if after_last {
+            if let Some(rule) = &self.rule {
+                return rule.local_type(utc);
+            }
+        }
+        let index = self.transitions.partition_point(|&t| t <= utc);
+        let type_index = match index {
+            0 => 0,
+            i => self.transition_types[i - 1],
+        };
+        &self.types[type_index.min(self.types.len() - 1)]
+    }
+}
+
+fn parse_tzif(data: &[u8]) -> Option<TimeZone> {
+    if !data.starts_with(b"TZif") {
+        return None;
+    }
+    let version = *data.get(4)?;
+    let counts = |at: usize| -> Option<[usize; 6]> {
+        let mut counts = [0; 6];
+        for (i, count) in counts.iter_mut().enumerate() {
+            let bytes = data.get(at + 20 + i * 4..at + 24 + i * 4)?;
+            *count = u32::from_be_bytes(bytes.try_into().ok()?) as usize;
+        }
+        Some(counts)
+    };
+    let block_len = |counts: [usize; 6], time_size: usize| {
+        let [isut, isstd, leap, time, types, chars] = counts;
+        time * time_size + time + types * 6 + chars + leap * (time_size + 4) + isstd + isut
+    };
+
+    // Version 2 and later repeat everything with 64-bit times after the
+    // 32-bit block, followed by the POSIX rule for later years.
+    let mut at = 0;
+    let mut time_size = 4;
+    let mut header = counts(0)?;
+    if version >= b'2' {
+        at = 44 + block_len(header, 4);
+        header = counts(at)?;
+        time_size = 8;
+    }
+    let [_, _, _, time_count, type_count, char_count] = header;
+    // The counts come from the file, so check they fit before sizing anything by them.
+    if type_count == 0 || data.len() < at + 44 + block_len(header, time_size) {
+        return None;
+    }
+    let mut p = at + 44;
+
+    let mut transitions = Vec::with_capacity(time_count);
+    for _ in 0..time_count {
+        let bytes = data.get(p..p + time_size)?;
+        transitions.push(if time_size == 8 {
+            i64::from_be_bytes(bytes.try_into().ok()?)
+        } else {
+            i32::from_be_bytes(bytes.try_into().ok()?) as i64
+        });
+        p += time_size;
+    }
+    let transition_types: Vec<usize> = data
+        .get(p..p + time_count)?
+        .iter()
+        .map(|&i| i as usize)
+        .collect();
+    p += time_count;
+
+    let type_data = data.get(p..p + type_count * 6)?;
+    let chars = data.get(p + type_count * 6..p + type_count * 6 + char_count)?;
+    let types = type_data
+        .chunks_exact(6)
+        .map(|t| {
+            let offset = i32::from_be_bytes([t[0], t[1], t[2], t[3]]) as i64;
+            let start = (t[5] as usize).min(chars.len());
+            let end = chars[start..].iter().position(|&c| c == 0).map_or(chars.len(), |e| start + e);
+            LocalType {
+                offset,
+                abbreviation: String::from_utf8_lossy(&chars[start..end]).into_owned(),
+            }
+        })
+        .collect();
+
+    let rule = if version >= b'2' {
+        let footer = data.get(at + 44 + block_len(header, 8)..)?;
+        let footer = String::from_utf8_lossy(footer);
+        parse_posix_tz(footer.trim_matches(|c: char| c == '\n' || c.is_whitespace()))
+    } else {
+        None
+    };
+
+    Some(TimeZone {
+        transitions,
+        transition_types,
+        types,
+        rule,
+    })
+}
+
+struct TzCursor<'a> {
+    rest: &'a str,
+}
+
+impl<'a> TzCursor<'a> {
+    fn name(&mut self) -> Option<String> {
+        if let Some(quoted) = self.rest.strip_prefix('<') {
+            let end = quoted.find('>')?;
+            self.rest = &quoted[end + 1..];
+            return Some(quoted[..end].to_string());
+        }
+        let end = self
+            .rest
+            .find(|c: char| !c.is_ascii_alphabetic())
+            .unwrap_or(self.rest.len());
+        if end < 3 {
+            return None;
+        }
+        let name = self.rest[..end].to_string();
+        self.rest = &self.rest[end..];
+        Some(name)
+    }
+
+    fn number(&mut self) -> Option<i64> {
+        let end = self
+            .rest
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(self.rest.len());
+        let value = self.rest[..end].parse().ok()?;
+        self.rest = &self.rest[end..];
+        Some(value)
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        match self.rest.strip_prefix(c) {
+            Some(rest) => {
+                self.rest = rest;
+                true
+            }
+            None => false,
+        }
+    }
+
+    // [+-]hh[:mm[:ss]]
+    fn duration(&mut self) -> Option<i64> {
+        let sign = if self.eat('-') {
+            -1
+        } else {
+            self.eat('+');
+            1
+        };
+        let mut seconds = self.number()? * 3600;
+        if self.eat(':') {
+            seconds += self.number()? * 60;
+            if self.eat(':') {
+                seconds += self.number()?;
+            }
+        }
+        Some(sign * seconds)
+    }
+
+    fn rule_time(&mut self) -> Option<RuleTime> {
+        let day = if self.eat('J') {
+            RuleDay::Julian(self.number()?)
+        } else if self.eat('M') {
+            let month = self.number()? as u32;
+            self.eat('.').then_some(())?;
+            let week = self.number()? as u32;
+            self.eat('.').then_some(())?;
+            let day = self.number()? as u32;
+            if !(1..=12).contains(&month) || !(1..=5).contains(&week) || day > 6 {
+                return None;
+            }
+            RuleDay::MonthWeekDay(month, week, day)
+        } else {
+            RuleDay::DayOfYear(self.number()?)
+        };
+        let seconds = if self.eat('/') { self.duration()? } else { 7200 };
+        Some(RuleTime { day, seconds })
+    }
+}
+
+fn parse_posix_tz(text: &str) -> Option<PosixRule> {
+    let mut cursor = TzCursor { rest: text };
+    // POSIX offsets count westwards, "EST5" is five hours behind UTC.
+    let standard = LocalType {
+        abbreviation: cursor.name()?,
+        offset: -cursor.duration()?,
+    };
+    if cursor.rest.is_empty() {
+        return Some(PosixRule {
+            standard,
+            daylight: None,
+        });
+    }
+
+    let abbreviation = cursor.name()?;
+    let offset = if cursor.rest.is_empty() || cursor.rest.starts_with(',') {
+        standard.offset + 3600
+    } else {
+        -cursor.duration()?
+    };
+    let daylight = LocalType {
+        abbreviation,
+        offset,
+    };
+    // Zones without explicit rules fall back on the old US ones.
+    let (start, end) = if cursor.eat(',') {
+        let start = cursor.rule_time()?;
+        cursor.eat(',').then_some(())?;
+        (start, cursor.rule_time()?)
+    } else {
+        (
+            RuleTime {
+                day: RuleDay::MonthWeekDay(3, 2, 0),
+                seconds: 7200,
+            },
+            RuleTime {
+                day: RuleDay::MonthWeekDay(11, 1, 0),
+                seconds: 7200,
+            },
+        )
+    };
+    if !cursor.rest.is_empty() {
+        return None;
+    }
+    Some(PosixRule {
+        standard,
+        daylight: Some((daylight, start, end)),
+    })
+}
+
+fn load_time_zone() -> TimeZone {
+    let from_file = |path: &Path| fs::read(path).ok().and_then(|data| parse_tzif(&data));
+
+    if let Ok(tz) = env::var("TZ") {
+        let tz = tz.trim_start_matches(':');
+        if tz.is_empty() || tz == "UTC" || tz == "UTC0" {
+            return TimeZone::utc();
+        }
+        let zone = if tz.starts_with('/') {
+            from_file(Path::new(tz))
+        } else {
+            from_file(&Path::new("/usr/share/zoneinfo").join(tz))
+        };
+        if let Some(zone) = zone {
+            return zone;
+        }
+        if let Some(zone) = TimeZone::from_posix(tz) {
+            return zone;
+        }
+    }
+    from_file(Path::new("/etc/localtime")).unwrap_or_else(TimeZone::utc)
+}
+
+fn local_zone() -> &'static TimeZone {
+    static ZONE: OnceLock<TimeZone> = OnceLock::new();
+    ZONE.get_or_init(load_time_zone)
+}
+
+// Wall clock seconds in the local zone, and back. Going back guesses the
+// offset twice so times near a transition land on the right side of it.
+pub fn unix_to_local(secs: i64) -> i64 {
+    secs + local_zone().local_type(secs).offset
+}
+
+pub fn local_to_unix(local: i64) -> i64 {
+    let zone = local_zone();
+    let guess = local - zone.local_type(local).offset;
+    local - zone.local_type(guess).offset
+}
+
+////////////////////////////////////////////////////////Formatting////////////////////////////////////////////////////////////////////
+pub fn unix_seconds(time: SystemTime) -> i64 {
+    match time.duration_since(UNIX_EPOCH) {
+        Ok(duration) => duration.as_secs() as i64,
+        Err(e) => -(e.duration().as_secs() as i64),
+    }
+}
+
+pub fn format_local_time(secs: i64, format: &str) -> String {
+    local_zone().format(secs, format)
+}
+
+impl TimeZone {
+    // A strftime subset: %Y %y %m %d %e %H %I %M %S %p %b %B %a %A %j %Z %z %s %%.
+    pub fn format(&self, secs: i64, format: &str) -> String {
+        let zone = self.local_type(secs);
+        let local = secs + zone.offset;
+        let days = local.div_euclid(86400);
+        let seconds = local.rem_euclid(86400);
+        let (year, month, day) = civil_from_days(days);
+        let (hour, minute, second) = (seconds / 3600, (seconds % 3600) / 60, seconds % 60);
+
+        let mut out = String::with_capacity(format.len() + 16);
+        let mut chars = format.chars();
+        while let Some(c) = chars.next() {
+            if c != '%' {
+                out.push(c);
+                continue;
+            }
+            match chars.next() {
+                Some('Y') => out.push_str(&year.to_string()),
+                Some('y') => out.push_str(&format!("{:02}", year.rem_euclid(100))),
+                Some('m') => out.push_str(&format!("{:02}", month)),
+                Some('d') => out.push_str(&format!("{:02}", day)),
+                Some('e') => out.push_str(&format!("{:2}", day)),
+                Some('H') => out.push_str(&format!("{:02}", hour)),
+                Some('I') => out.push_str(&format!("{:02}", (hour + 11) % 12 + 1)),
+                Some('M') => out.push_str(&format!("{:02}", minute)),
+                Some('S') => out.push_str(&format!("{:02}", second)),
+                Some('p') => out.push_str(if hour < 12 { "AM" } else { "PM" }),
+                Some('b') => out.push_str(&MONTHS[month as usize - 1][..3]),
+                Some('B') => out.push_str(MONTHS[month as usize - 1]),
+                Some('a') => out.push_str(&WEEKDAYS[weekday(days) as usize][..3]),
+                Some('A') => out.push_str(WEEKDAYS[weekday(days) as usize]),
+                Some('j') => out.push_str(&format!(
+                    "{:03}",
+                    days - days_from_civil(year, 1, 1) + 1
+                )),
+                Some('Z') => out.push_str(&zone.abbreviation),
+                Some('z') => {
+                    let sign = if zone.offset < 0 { '-' } else { '+' };
+                    let offset = zone.offset.abs();
+                    let (hours, minutes) = (offset / 3600, (offset % 3600) / 60);
+                    out.push_str(&format!("{}{:02}{:02}", sign, hours, minutes));
+                }
+                Some('s') => out.push_str(&secs.to_string()),
+                Some('%') => out.push('%'),
+                Some(other) => {
+                    out.push('%');
+                    out.push(other);
+                }
+                None => out.push('%'),
+            }
+        }
+        out
+    }
+}
+
+pub fn relative_time(secs: i64, now: i64) -> String {
+    let delta = now - secs;
+    let distance = delta.abs();
+    let amount = match distance {
+        0..=9 => return "just now".to_string(),
+        10..=59 => format!("{} s", distance),
+        60..=3599 => format!("{} min", distance / 60),
+        3600..=86399 => format!("{} h", distance / 3600),
+        86400..=2_591_999 => format!("{} d", distance / 86400),
+        2_592_000..=31_535_999 => format!("{} mo", distance / 2_592_000),
+        _ => format!("{} y", distance / 31_536_000),
+    };
+    if delta >= 0 {
+        format!("{} ago", amount)
+    } else {
+        format!("in {}", amount)
+    }
+}
+
+// What the info panes show for a timestamp, following the config.
+pub fn describe_time(secs: i64, config: &Config) -> String {
+    let absolute = format_local_time(secs, &config.time_format);
+    if config.relative_times {
+        let now = unix_seconds(SystemTime::now());
+        format!("{} ({})", absolute, relative_time(secs, now))
+    } else {
+        absolute
+    }
+}
@@ -0,0 +1,470 @@
+/*
+* Stygian Sift - A Terminal-based File Manager
+ * Copyright (C) 2024 Maui The Magnificent (Charon)
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published
+ * by the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+ * Contact: Maui_The_Magnificent@proton.me
+ * Project repository: https://github.com/Mauitron/StygianSift.git
+ */
+
+// The part of TOML the config file needs: [tables] with dotted names, keys
+// set to strings, integers, booleans or arrays of those, and # comments.
+// Inline tables, arrays of tables, floats, dates and multi-line strings are
+// reported as errors. A broken line is skipped and the rest still parsed, so
+// one typo does not cost the whole config. A broken array is skipped up to its
+// closing ']', and the keys under a broken [table] up to the next header.
+
+use super::*;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TomlValue {
+    String(String),
+    Integer(i64),
+    Boolean(bool),
+    Array(Vec<TomlValue>),
+}
+
+impl TomlValue {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            TomlValue::String(_) => "a string",
+            TomlValue::Integer(_) => "an integer",
+            TomlValue::Boolean(_) => "a boolean",
+            TomlValue::Array(_) => "an array",
+        }
+    }
+}
+
+impl Display for TomlValue {
+    fn fmt(&self, f: &mut Formatter) -> OtherResult {
+        match self {
+            TomlValue::String(s) => write!(f, "{}", toml_string(s)),
+            TomlValue::Integer(n) => write!(f, "{}", n),
+            TomlValue::Boolean(b) => write!(f, "{}", b),
+            TomlValue::Array(items) => {
+                write!(f, "[")?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", item)?;
+                }
+                write!(f, "]")
+            }
+        }
+    }
+}
+
+// A key with the names of the tables it sits in, in the order of the file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TomlEntry {
+    pub path: Vec<String>,
+    pub value: TomlValue,
+    pub line: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TomlError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl TomlError {
+    pub fn new(line: usize, message: impl Into<String>) -> Self {
+        TomlError {
+            line,
+            message: message.into(),
+        }
+    }
+}
+
+impl Display for TomlError {
+    fn fmt(&self, f: &mut Formatter) -> OtherResult {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+//////////////////////////////////////////////////////////Writing///////////////////////////////////////////////////////////////////
+pub fn toml_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c.is_control() => out.push_str(&format!("\\u{:04X}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+// Bare when TOML allows it, quoted otherwise.
+pub fn toml_key(key: &str) -> String {
+    let bare = !key.is_empty()
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+    if bare {
+        key.to_string()
+    } else {
+        toml_string(key)
+    }
+}
+
+//////////////////////////////////////////////////////////Parsing///////////////////////////////////////////////////////////////////
+struct Cursor {
+    chars: Vec<char>,
+    pos: usize,
+    line: usize,
+}
+
+impl Cursor {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<char> {
+        self.chars.get(self.pos + offset).copied()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += 1;
+        if c == '\n' {
+            self.line += 1;
+        }
+        Some(c)
+    }
+
+    fn error(&self, message: impl Into<String>) -> TomlError {
+        TomlError::new(self.line, message)
+    }
+
+    fn skip_spaces(&mut self) {
+        while matches!(self.peek(), Some(' ' | '\t')) {
+            self.bump();
+        }
+    }
+
+    // Spaces, comments and line breaks, as allowed inside arrays.
+    fn skip_blank(&mut self) {
+        loop {
+            match self.peek() {
+                Some(' ' | '\t' | '\r' | '\n') => {
+                    self.bump();
+                }
+                Some('#') => self.skip_comment(),
+                _ => return,
+            }
+        }
+    }
+
+    fn skip_comment(&mut self) {
+        while !matches!(self.peek(), None | Some('\n')) {
+            self.bump();
+        }
+    }
+
+    fn skip_line(&mut self) {
+        self.skip_comment();
+        self.bump();
+    }
+
+    // Moves past a key and its value without reading them. An array goes up
+    // to its closing ']' even across lines, so its items are not taken for
+    // keys. One that never closes only costs its first line.
+    fn skip_statement(&mut self) {
+        let (start, line) = (self.pos, self.line);
+        let mut depth = 0usize;
+        while let Some(c) = self.bump() {
+            match c {
+                '[' | '{' => depth += 1,
+                ']' | '}' => depth = depth.saturating_sub(1),
+                '"' | '\'' => self.skip_quoted(c),
+                '#' => self.skip_comment(),
+                '\n' if depth == 0 => return,
+                _ => {}
+            }
+        }
+        if depth > 0 {
+            self.pos = start;
+            self.line = line;
+            self.skip_line();
+        }
+    }
+
+    fn skip_quoted(&mut self, quote: char) {
+        while !matches!(self.peek(), None | Some('\n')) {
+            match self.bump() {
+                Some('\\') if quote == '"' && self.peek() != Some('\n') => {
+                    self.bump();
+                }
+                Some(c) if c == quote => return,
+                _ => {}
+            }
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), TomlError> {
+        match self.peek() {
+            Some(c) if c == expected => {
+                self.bump();
+                Ok(())
+            }
+            Some(c) => Err(self.error(format!("expected '{}', found '{}'", expected, c))),
+            None => Err(self.error(format!("expected '{}' before the end", expected))),
+        }
+    }
+
+    // Nothing but a comment may follow on the line.
+    fn end_of_line(&mut self) -> Result<(), TomlError> {
+        self.skip_spaces();
+        match self.peek() {
+            None => Ok(()),
+            Some('#') => {
+                self.skip_comment();
+                Ok(())
+            }
+            Some('\r') if self.peek_at(1) == Some('\n') => {
+                self.bump();
+                self.bump();
+                Ok(())
+            }
+            Some('\n') => {
+                self.bump();
+                Ok(())
+            }
+            Some(c) => Err(self.error(format!("unexpected '{}' after the value", c))),
+        }
+    }
+
+    fn key(&mut self) -> Result<String, TomlError> {
+        match self.peek() {
+            Some('"') => self.basic_string(),
+            Some('\'') => self.literal_string(),
+            _ => {
+                let start = self.pos;
+                while self
+                    .peek()
+                    .is_some_and(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+                {
+                    self.bump();
+                }
+                if start == self.pos {
+                    return Err(match self.peek() {
+                        Some(c) => self.error(format!("expected a key, found '{}'", c)),
+                        None => self.error("expected a key"),
+                    });
+                }
+                Ok(self.chars[start..self.pos].iter().collect())
+            }
+        }
+    }
+
+    fn dotted_key(&mut self) -> Result<Vec<String>, TomlError> {
+        let mut parts = vec![self.key()?];
+        loop {
+            self.skip_spaces();
+            if self.peek() != Some('.') {
+                return Ok(parts);
+            }
+            self.bump();
+            self.skip_spaces();
+            parts.push(self.key()?);
+        }
+    }
+
+    fn basic_string(&mut self) -> Result<String, TomlError> {
+        self.expect('"')?;
+        if self.peek() == Some('"') && self.peek_at(1) == Some('"') {
+            return Err(self.error("multi-line strings are not supported"));
+        }
+        let mut out = String::new();
+        loop {
+            if matches!(self.peek(), None | Some('\n')) {
+                return Err(self.error("unterminated string"));
+            }
+            match self.bump() {
+                None => return Err(self.error("unterminated string")),
+                Some('"') => return Ok(out),
+                Some('\\') => match self.bump() {
+                    Some('"') => out.push('"'),
+                    Some('\\') => out.push('\\'),
+                    Some('n') => out.push('\n'),
+                    Some('t') => out.push('\t'),
+                    Some('r') => out.push('\r'),
+                    Some('b') => out.push('\u{8}'),
+                    Some('f') => out.push('\u{c}'),
+                    Some(kind @ ('u' | 'U')) => {
+                        let len = if kind == 'u' { 4 } else { 8 };
+                        let digits: String = (0..len).filter_map(|_| self.bump()).collect();
+                        let c = u32::from_str_radix(&digits, 16)
+                            .ok()
+                            .and_then(char::from_u32)
+                            .ok_or_else(|| {
+                                self.error(format!("bad escape \\{}{}", kind, digits))
+                            })?;
+                        out.push(c);
+                    }
+                    Some(c) => return Err(self.error(format!("unknown escape \\{}", c))),
+                    None => return Err(self.error("unterminated string")),
+                },
+                Some(c) => out.push(c),
+            }
+        }
+    }
+
+    fn literal_string(&mut self) -> Result<String, TomlError> {
+        self.expect('\'')?;
+        let mut out = String::new();
+        loop {
+            if matches!(self.peek(), None | Some('\n')) {
+                return Err(self.error("unterminated string"));
+            }
+            match self.bump() {
+                None => return Err(self.error("unterminated string")),
+                Some('\'') => return Ok(out),
+                Some(c) => out.push(c),
+            }
+        }
+    }
+
+    fn value(&mut self) -> Result<TomlValue, TomlError> {
+        match self.peek() {
+            Some('"') => self.basic_string().map(TomlValue::String),
+            Some('\'') => self.literal_string().map(TomlValue::String),
+            Some('[') => self.array(),
+            Some('{') => Err(self.error("inline tables are not supported")),
+            Some(_) => {
+                let start = self.pos;
+                while self
+                    .peek()
+                    .is_some_and(|c| !matches!(c, ' ' | '\t' | '\r' | '\n' | '#' | ',' | ']'))
+                {
+                    self.bump();
+                }
+                let word: String = self.chars[start..self.pos].iter().collect();
+                match word.as_str() {
+                    "true" => Ok(TomlValue::Boolean(true)),
+                    "false" => Ok(TomlValue::Boolean(false)),
+                    _ => word
+                        .replace('_', "")
+                        .parse()
+                        .map(TomlValue::Integer)
+                        .map_err(|_| self.error(format!("'{}' is not a value", word))),
+                }
+            }
+            None => Err(self.error("expected a value")),
+        }
+    }
+
+    fn array(&mut self) -> Result<TomlValue, TomlError> {
+        self.expect('[')?;
+        let mut items = Vec::new();
+        loop {
+            self.skip_blank();
+            if self.peek() == Some(']') {
+                self.bump();
+                return Ok(TomlValue::Array(items));
+            }
+            items.push(self.value()?);
+            self.skip_blank();
+            match self.peek() {
+                Some(',') => {
+                    self.bump();
+                }
+                Some(']') => {}
+                Some(c) => return Err(self.error(format!("expected ',' or ']', found '{}'", c))),
+                None => return Err(self.error("unterminated array")),
+            }
+        }
+    }
+}
+
+pub fn parse_toml(text: &str) -> (Vec<TomlEntry>, Vec<TomlError>) {
+    let mut cursor = Cursor {
+        chars: text.chars().collect(),
+        pos: 0,
+        line: 1,
+    };
+    let mut entries: Vec<TomlEntry> = Vec::new();
+    let mut errors = Vec::new();
+    // None after a broken header, until the next good one.
+    let mut table: Option<Vec<String>> = Some(Vec::new());
+    let mut seen: HashMap<Vec<String>, usize> = HashMap::new();
+
+    loop {
+        cursor.skip_blank();
+        let (start, line) = (cursor.pos, cursor.line);
+        let header = cursor.peek() == Some('[');
+        let result = match cursor.peek() {
+            None => break,
+            Some('[') if cursor.peek_at(1) == Some('[') => {
+                Err(cursor.error("arrays of tables are not supported"))
+            }
+            Some('[') => (|| {
+                cursor.bump();
+                cursor.skip_spaces();
+                let name = cursor.dotted_key()?;
+                cursor.skip_spaces();
+                cursor.expect(']')?;
+                cursor.end_of_line()?;
+                table = Some(name);
+                Ok(())
+            })(),
+            // They would end up in the table before the broken one.
+            Some(_) if table.is_none() => {
+                cursor.skip_statement();
+                continue;
+            }
+            Some(_) => (|| {
+                let key = cursor.dotted_key()?;
+                cursor.skip_spaces();
+                cursor.expect('=')?;
+                cursor.skip_spaces();
+                let value = cursor.value()?;
+                cursor.end_of_line()?;
+                let path: Vec<String> = table.iter().flatten().cloned().chain(key).collect();
+                if let Some(first) = seen.get(&path) {
+                    return Err(TomlError::new(
+                        line,
+                        format!("'{}' is already set on line {}", path.join("."), first),
+                    ));
+                }
+                seen.insert(path.clone(), line);
+                entries.push(TomlEntry { path, value, line });
+                Ok(())
+            })(),
+        };
+        if let Err(error) = result {
+            errors.push(error);
+            // Carry on after what failed, from where it started.
+            cursor.pos = start;
+            cursor.line = line;
+            if header {
+                table = None;
+                cursor.skip_line();
+            } else {
+                cursor.skip_statement();
+            }
+        }
+    }
+    (entries, errors)
+}
@@ -0,0 +1,1326 @@
+/*
+* Stygian Sift - A Terminal-based File Manager
+ * Copyright (C) 2024 Maui The Magnificent (Charon)
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published
+ * by the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+ * Contact: Maui_The_Magnificent@proton.me
+ * Project repository: https://github.com/Mauitron/StygianSift.git
+ */
+
+use super::*;
+use std::io::{Cursor, SeekFrom};
+use std::io::Seek;
+////////////////////////////////////////////////////////Archives//////////////////////////////////////////////////////////////////////
+// Archives are browsed as read-only virtual directories. Entries inside get
+// paths of the form `/some/dir/archive.tar/inner/file`, which never exist on
+// disk, so anything that touches the file system has to ask the view first.
+
+// A .tar.gz has to be unpacked in memory to be listed, this keeps that sane.
+const MAX_UNPACKED_SIZE: usize = 512 * 1024 * 1024;
+const MAX_ZIP_DIRECTORY: u64 = 64 * 1024 * 1024;
+
+fn corrupt(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveKind {
+    Tar,
+    TarGz,
+    Zip,
+}
+
+impl ArchiveKind {
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let name = path.file_name()?.to_str()?.to_lowercase();
+        if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+            Some(ArchiveKind::TarGz)
+        } else if name.ends_with(".tar") {
+            Some(ArchiveKind::Tar)
+        } else if name.ends_with(".zip") || name.ends_with(".jar") {
+            Some(ArchiveKind::Zip)
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MemberType {
+    File,
+    Directory,
+    Symlink(String),
+    Other,
+}
+
+#[derive(Debug, Clone)]
+pub struct ArchiveMember {
+    pub path: String,
+    pub member_type: MemberType,
+    pub size: u64,
+    pub compressed_size: u64,
+    pub mode: u32,
+    pub mtime: u64,
+    crc: Option<u32>,
+    // Tar: start of the data. Zip: start of the local header.
+    offset: u64,
+    method: u16,
+}
+
+impl ArchiveMember {
+    fn directory(path: String) -> Self {
+        Self {
+            path,
+            member_type: MemberType::Directory,
+            size: 0,
+            compressed_size: 0,
+            mode: 0o755,
+            mtime: 0,
+            crc: None,
+            offset: 0,
+            method: 0,
+        }
+    }
+
+    pub fn is_dir(&self) -> bool {
+        self.member_type == MemberType::Directory
+    }
+
+    pub fn name(&self) -> &str {
+        self.path.rsplit('/').next().unwrap_or(&self.path)
+    }
+
+    fn parent(&self) -> &str {
+        self.path.rsplit_once('/').map_or("", |(parent, _)| parent)
+    }
+
+    // Absolute paths and `..` are never written outside the target.
+    pub fn is_safe(&self) -> bool {
+        Path::new(&self.path)
+            .components()
+            .all(|c| matches!(c, std::path::Component::Normal(_)))
+    }
+}
+
+pub struct ArchiveView {
+    pub archive_path: PathBuf,
+    pub kind: ArchiveKind,
+    pub members: Vec<ArchiveMember>,
+    unpacked: Option<Vec<u8>>,
+}
+
+impl ArchiveView {
+    pub fn open(path: &Path) -> io::Result<Self> {
+        let kind = ArchiveKind::from_path(path)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "not an archive"))?;
+        let mut file = File::open(path)?;
+
+        let (members, unpacked) = match kind {
+            ArchiveKind::Tar => (read_tar(&mut file)?, None),
+            ArchiveKind::TarGz => {
+                let mut packed = Vec::new();
+                file.read_to_end(&mut packed)?;
+                let data = gunzip(&packed, Some(MAX_UNPACKED_SIZE))?;
+                if data.len() >= MAX_UNPACKED_SIZE {
+                    return Err(io::Error::other("archive is too large to browse"));
+                }
+                (read_tar(&mut Cursor::new(&data))?, Some(data))
+            }
+            ArchiveKind::Zip => (read_zip(&mut file)?, None),
+        };
+
+        Ok(Self {
+            archive_path: path.to_path_buf(),
+            kind,
+            members: normalize_members(members),
+            unpacked,
+        })
+    }
+
+    pub fn contains(&self, path: &Path) -> bool {
+        path.starts_with(&self.archive_path)
+    }
+
+    // "" is the root of the archive.
+    pub fn inner_path(&self, path: &Path) -> Option<String> {
+        let inner = path.strip_prefix(&self.archive_path).ok()?;
+        Some(
+            inner
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/"),
+        )
+    }
+
+    pub fn member(&self, path: &Path) -> Option<&ArchiveMember> {
+        let inner = self.inner_path(path)?;
+        self.members.iter().find(|m| m.path == inner)
+    }
+
+    pub fn virtual_path(&self, member: &ArchiveMember) -> PathBuf {
+        self.archive_path.join(&member.path)
+    }
+
+    pub fn children(&self, dir: &Path) -> Vec<&ArchiveMember> {
+        match self.inner_path(dir) {
+            Some(inner) => self.members.iter().filter(|m| m.parent() == inner).collect(),
+            None => Vec::new(),
+        }
+    }
+
+    pub fn list(&self, dir: &Path) -> Vec<FileEntry> {
+        self.children(dir)
+            .into_iter()
+            .map(|member| {
+                let path = self.virtual_path(member);
+                FileEntry {
+                    name: member.name().to_string(),
+                    file_type: if member.is_dir() {
+                        FileType::Directory
+                    } else {
+                        FileEntry::determine_file_type(&path)
+                    },
+                    path,
+                    size: member.size,
+                    admin_required: false,
+                    read_only: true,
+                    git_status: None,
+                }
+            })
+            .collect()
+    }
+
+    // The member itself plus, for directories, everything below it.
+    pub fn members_under<'a>(&'a self, inner: &'a str) -> impl Iterator<Item = &'a ArchiveMember> {
+        self.members.iter().filter(move |m| {
+            inner.is_empty()
+                || m.path == inner
+                || (m.path.starts_with(inner) && m.path[inner.len()..].starts_with('/'))
+        })
+    }
+
+    pub fn read_member(&self, member: &ArchiveMember, limit: Option<usize>) -> io::Result<Vec<u8>> {
+        if member.member_type != MemberType::File {
+            return Ok(Vec::new());
+        }
+        let wanted = limit.map_or(member.size, |limit| member.size.min(limit as u64));
+
+        match self.kind {
+            ArchiveKind::TarGz => {
+                let data = self.unpacked.as_deref().unwrap_or_default();
+                let start = (member.offset as usize).min(data.len());
+                let end = (start + wanted as usize).min(data.len());
+                Ok(data[start..end].to_vec())
+            }
+            ArchiveKind::Tar => {
+                let mut file = File::open(&self.archive_path)?;
+                file.seek(SeekFrom::Start(member.offset))?;
+                let mut data = Vec::new();
+                file.take(wanted).read_to_end(&mut data)?;
+                Ok(data)
+            }
+            ArchiveKind::Zip => read_zip_member(&self.archive_path, member, limit),
+        }
+    }
+}
+
+// Later duplicates win, just like extracting with tar would, and every
+// parent directory gets an entry even when the archive skipped it.
+fn normalize_members(members: Vec<ArchiveMember>) -> Vec<ArchiveMember> {
+    let mut index: HashMap<String, usize> = HashMap::new();
+    let mut normalized: Vec<ArchiveMember> = Vec::new();
+
+    for mut member in members {
+        let path = member.path.replace('\\', "/");
+        let parts: Vec<&str> = path
+            .split('/')
+            .filter(|part| !part.is_empty() && *part != ".")
+            .collect();
+        if parts.is_empty() {
+            continue;
+        }
+        member.path = parts.join("/");
+
+        for depth in 1..parts.len() {
+            let parent = parts[..depth].join("/");
+            if !index.contains_key(&parent) {
+                index.insert(parent.clone(), normalized.len());
+                normalized.push(ArchiveMember::directory(parent));
+            }
+        }
+        match index.get(&member.path) {
+            Some(&i) => normalized[i] = member,
+            None => {
+                index.insert(member.path.clone(), normalized.len());
+                normalized.push(member);
+            }
+        }
+    }
+    normalized
+}
+
+/////////////////////////////////////////////////////////Tar//////////////////////////////////////////////////////////////////////////
+fn tar_string(field: &[u8]) -> String {
+    let end = field.iter().position(|&b| b == 0).unwrap_or(field.len());
+    String::from_utf8_lossy(&field[..end]).into_owned()
+}
+
+fn tar_number(field: &[u8]) -> io::Result<u64> {
+    // GNU tar switches to base-256 for values that do not fit in octal.
+    if field[0] & 0x80 != 0 {
+        let mut value = (field[0] & 0x7F) as u64;
+        for &byte in &field[1..] {
+            value = (value << 8) | byte as u64;
+        }
+        return Ok(value);
+    }
+    let text = tar_string(field);
+    let text = text.trim_matches(|c: char| c == ' ' || c == '\0');
+    if text.is_empty() {
+        return Ok(0);
+    }
+    u64::from_str_radix(text, 8).map_err(|_| corrupt("invalid number in tar header"))
+}
+
+fn read_tar_data<R: Read>(reader: &mut R, size: u64) -> io::Result<Vec<u8>> {
+    if size > 1024 * 1024 {
+        return Err(corrupt("tar extended header is too large"));
+    }
+    let mut data = vec![0; size as usize];
+    reader.read_exact(&mut data)?;
+    Ok(data)
+}
+
+fn read_tar<R: Read + Seek>(reader: &mut R) -> io::Result<Vec<ArchiveMember>> {
+    let mut members = Vec::new();
+    let mut header = [0u8; 512];
+    let mut offset = 0u64;
+    let mut long_name: Option<String> = None;
+    let mut long_link: Option<String> = None;
+    let mut pax: HashMap<String, String> = HashMap::new();
+
+    loop {
+        match reader.read_exact(&mut header) {
+            Ok(()) => {}
+            // Plenty of tools forget the two zero blocks at the end.
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => break,
+            Err(e) => return Err(e),
+        }
+        offset += 512;
+        if header.iter().all(|&b| b == 0) {
+            break;
+        }
+
+        let stored_sum = tar_number(&header[148..156])?;
+        let sum: u64 = header
+            .iter()
+            .enumerate()
+            .map(|(i, &b)| if (148..156).contains(&i) { 32 } else { b as u64 })
+            .sum();
+        if sum != stored_sum {
+            return Err(corrupt("tar header checksum mismatch"));
+        }
+
+        let mut size = tar_number(&header[124..136])?;
+        let type_flag = header[156];
+        match type_flag {
+            b'L' => {
+                long_name = Some(tar_string(&read_tar_data(reader, size)?));
+            }
+            b'K' => {
+                long_link = Some(tar_string(&read_tar_data(reader, size)?));
+            }
+            b'x' => {
+                let data = read_tar_data(reader, size)?;
+                pax = parse_pax(&String::from_utf8_lossy(&data));
+            }
+            b'g' => {}
+            _ => {
+                let mut name = tar_string(&header[0..100]);
+                if &header[257..262] == b"ustar" {
+                    let prefix = tar_string(&header[345..500]);
+                    if !prefix.is_empty() {
+                        name = format!("{}/{}", prefix, name);
+                    }
+                }
+                let name = pax.remove("path").or(long_name.take()).unwrap_or(name);
+                let link = pax
+                    .remove("linkpath")
+                    .or(long_link.take())
+                    .unwrap_or_else(|| tar_string(&header[157..257]));
+                if let Some(pax_size) = pax.remove("size").and_then(|s| s.parse().ok()) {
+                    size = pax_size;
+                }
+                let mtime = pax
+                    .remove("mtime")
+                    .and_then(|s| s.split('.').next().and_then(|s| s.parse().ok()))
+                    .unwrap_or(tar_number(&header[136..148])?);
+                pax.clear();
+
+                let member_type = match type_flag {
+                    b'0' | b'\0' | b'7' if name.ends_with('/') => MemberType::Directory,
+                    b'0' | b'\0' | b'7' => MemberType::File,
+                    b'5' => MemberType::Directory,
+                    b'2' => MemberType::Symlink(link),
+                    _ => MemberType::Other,
+                };
+                members.push(ArchiveMember {
+                    path: name,
+                    member_type,
+                    size,
+                    compressed_size: size,
+                    mode: tar_number(&header[100..108])? as u32,
+                    mtime,
+                    crc: None,
+                    offset,
+                    method: 0,
+                });
+            }
+        }
+
+        offset += size.div_ceil(512) * 512;
+        reader.seek(SeekFrom::Start(offset))?;
+    }
+    Ok(members)
+}
+
+// Records look like "27 path=some/long/name.txt\n", the length counts itself.
+fn parse_pax(data: &str) -> HashMap<String, String> {
+    let mut records = HashMap::new();
+    let mut rest = data;
+    while let Some((len, _)) = rest.split_once(' ') {
+        let Ok(len) = len.parse::<usize>() else { break };
+        if len == 0 || len > rest.len() {
+            break;
+        }
+        let record = &rest[..len];
+        if let Some((_, key_value)) = record.split_once(' ') {
+            if let Some((key, value)) = key_value.trim_end_matches('\n').split_once('=') {
+                records.insert(key.to_string(), value.to_string());
+            }
+        }
+        rest = &rest[len..];
+    }
+    records
+}
+
+/////////////////////////////////////////////////////////Zip//////////////////////////////////////////////////////////////////////////
+fn le16(data: &[u8], at: usize) -> io::Result<u16> {
+    data.get(at..at + 2)
+        .map(|b| u16::from_le_bytes([b[0], b[1]]))
+        .ok_or_else(|| corrupt("truncated zip record"))
+}
+
+fn le32(data: &[u8], at: usize) -> io::Result<u32> {
+    data.get(at..at + 4)
+        .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .ok_or_else(|| corrupt("truncated zip record"))
+}
+
+fn le64(data: &[u8], at: usize) -> io::Result<u64> {
+    Ok(le32(data, at)? as u64 | (le32(data, at + 4)? as u64) << 32)
+}
+
+// Zip stores local time without a zone, taken to be our own.
+fn dos_time_to_unix(date: u16, time: u16) -> u64 {
+    let year = 1980 + (date >> 9) as i64;
+    let month = ((date >> 5) & 0x0F).clamp(1, 12) as i64;
+    let day = (date & 0x1F).max(1) as i64;
+    let seconds = (time >> 11) as i64 * 3600 + ((time >> 5) & 0x3F) as i64 * 60 + (time & 0x1F) as i64 * 2;
+    local_to_unix(days_from_civil(year, month, day) * 86400 + seconds).max(0) as u64
+}
+
+fn read_zip(file: &mut File) -> io::Result<Vec<ArchiveMember>> {
+    let len = file.seek(SeekFrom::End(0))?;
+    let tail_len = len.min(65535 + 22);
+    file.seek(SeekFrom::Start(len - tail_len))?;
+    let mut tail = vec![0; tail_len as usize];
+    file.read_exact(&mut tail)?;
+
+    let eocd = (0..tail.len().saturating_sub(21))
+        .rev()
+        .find(|&i| tail[i..i + 4] == [0x50, 0x4B, 0x05, 0x06])
+        .ok_or_else(|| corrupt("zip end of central directory not found"))?;
+    let mut count = le16(&tail, eocd + 10)? as u64;
+    let mut directory_size = le32(&tail, eocd + 12)? as u64;
+    let mut directory_offset = le32(&tail, eocd + 16)? as u64;
+
+    if eocd >= 20 && tail[eocd - 20..eocd - 16] == [0x50, 0x4B, 0x06, 0x07] {
+        let record_offset = le64(&tail, eocd - 20 + 8)?;
+        file.seek(SeekFrom::Start(record_offset))?;
+        let mut record = [0u8; 56];
+        file.read_exact(&mut record)?;
+        if record[..4] != [0x50, 0x4B, 0x06, 0x06] {
+            return Err(corrupt("invalid zip64 end of central directory"));
+        }
+        count = le64(&record, 32)?;
+        directory_size = le64(&record, 40)?;
+        directory_offset = le64(&record, 48)?;
+    }
+    if directory_size > MAX_ZIP_DIRECTORY {
+        return Err(corrupt("zip central directory is too large"));
+    }
+
+    file.seek(SeekFrom::Start(directory_offset))?;
+    let mut directory = vec![0; directory_size as usize];
+    file.read_exact(&mut directory)?;
+
+    let mut members = Vec::new();
+    let mut at = 0;
+    for _ in 0..count {
+        if le32(&directory, at)? != 0x0201_4B50 {
+            return Err(corrupt("invalid zip central directory entry"));
+        }
+        let host = *directory
+            .get(at + 5)
+            .ok_or_else(|| corrupt("truncated zip record"))?;
+        let method = le16(&directory, at + 10)?;
+        let time = le16(&directory, at + 12)?;
+        let date = le16(&directory, at + 14)?;
+        let crc = le32(&directory, at + 16)?;
+        let mut compressed_size = le32(&directory, at + 20)? as u64;
+        let mut size = le32(&directory, at + 24)? as u64;
+        let name_len = le16(&directory, at + 28)? as usize;
+        let extra_len = le16(&directory, at + 30)? as usize;
+        let comment_len = le16(&directory, at + 32)? as usize;
+        let external = le32(&directory, at + 38)?;
+        let mut offset = le32(&directory, at + 42)? as u64;
+
+        let name_start = at + 46;
+        let name = directory
+            .get(name_start..name_start + name_len)
+            .map(|n| String::from_utf8_lossy(n).into_owned())
+            .ok_or_else(|| corrupt("truncated zip record"))?;
+
+        // Zip64 sizes only appear for the fields that overflowed.
+        let extra = directory
+            .get(name_start + name_len..name_start + name_len + extra_len)
+            .ok_or_else(|| corrupt("truncated zip record"))?;
+        let mut e = 0;
+        while e + 4 <= extra.len() {
+            let id = le16(extra, e)?;
+            let field_len = le16(extra, e + 2)? as usize;
+            let field = extra
+                .get(e + 4..e + 4 + field_len)
+                .ok_or_else(|| corrupt("truncated zip extra field"))?;
+            if id == 0x0001 {
+                let mut f = 0;
+                for value in [&mut size, &mut compressed_size, &mut offset] {
+                    if *value == 0xFFFF_FFFF {
+                        *value = le64(field, f)?;
+                        f += 8;
+                    }
+                }
+            }
+            e += 4 + field_len;
+        }
+
+        let mode = if host == 3 { external >> 16 } else { 0 };
+        let member_type = if name.ends_with('/') || mode & 0o170000 == 0o040000 {
+            MemberType::Directory
+        } else if mode & 0o170000 == 0o120000 {
+            MemberType::Symlink(String::new())
+        } else {
+            MemberType::File
+        };
+
+        members.push(ArchiveMember {
+            path: name,
+            member_type,
+            size,
+            compressed_size,
+            mode: mode & 0o7777,
+            mtime: dos_time_to_unix(date, time),
+            crc: Some(crc),
+            offset,
+            method,
+        });
+        at = name_start + name_len + extra_len + comment_len;
+    }
+    Ok(members)
+}
+
+fn read_zip_member(path: &Path, member: &ArchiveMember, limit: Option<usize>) -> io::Result<Vec<u8>> {
+    let mut file = File::open(path)?;
+    file.seek(SeekFrom::Start(member.offset))?;
+    let mut header = [0u8; 30];
+    file.read_exact(&mut header)?;
+    if le32(&header, 0)? != 0x0403_4B50 {
+        return Err(corrupt("invalid zip local header"));
+    }
+    if le16(&header, 6)? & 0x0001 != 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "encrypted zip members are not supported",
+        ));
+    }
+    let skip = le16(&header, 26)? as i64 + le16(&header, 28)? as i64;
+    file.seek(SeekFrom::Current(skip))?;
+
+    // Deflate never needs much more input than output, so a preview does
+    // not have to pull a whole multi gigabyte member off the disk.
+    let wanted = match (limit, member.method) {
+        (Some(limit), 0) => member.compressed_size.min(limit as u64),
+        (Some(limit), _) => member.compressed_size.min(limit as u64 * 2 + 64 * 1024),
+        (None, _) => member.compressed_size,
+    };
+    let mut packed = Vec::new();
+    file.take(wanted).read_to_end(&mut packed)?;
+
+    let data = match member.method {
+        0 => packed,
+        8 => inflate(&packed, limit)?.0,
+        method => {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!("zip compression method {} is not supported", method),
+            ))
+        }
+    };
+
+    if limit.is_none() && member.crc.is_some_and(|crc| crc != crc32(&data)) {
+        return Err(corrupt("zip member checksum mismatch"));
+    }
+    Ok(data)
+}
+
+//////////////////////////////////////////////////////Browsing/////////////////////////////////////////////////////////////////////////
+pub fn read_entry_bytes(app_state: &AppState, path: &Path, limit: usize) -> io::Result<Vec<u8>> {
+    if let Some(view) = app_state.archive_view.as_ref().filter(|view| view.contains(path)) {
+        let member = view
+            .member(path)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "member not found"))?;
+        return view.read_member(member, Some(limit));
+    }
+    let mut data = Vec::new();
+    File::open(path)?.take(limit as u64).read_to_end(&mut data)?;
+    Ok(data)
+}
+
+pub fn archive_blocks_action(app_state: &AppState, action: &Action) -> io::Result<bool> {
+    if !app_state.in_archive() {
+        return Ok(false);
+    }
+    let blocked = matches!(
+        action,
+        Action::Rename
+            | Action::RenameWithoutExtension
+            | Action::Murder
+            | Action::Copy
+            | Action::Paste
+            | Action::Duplicate
+            | Action::MoveItem
+            | Action::GiveBirthDir
+            | Action::GiveBirthFile
+            | Action::OpenInEditor
+            | Action::ExecuteFile
+            | Action::CycleItemColor
+            | Action::RemoveItemColor
+    );
+    if blocked {
+        interaction_field!("Archives are read-only, extract members to work on them")?;
+    }
+    Ok(blocked)
+}
+
+pub fn display_archive_folder_preview(
+    view: &ArchiveView,
+    path: &Path,
+    stdout: &mut impl Write,
+    start_x: u16,
+    width: u16,
+    start_y: u16,
+    end_y: u16,
+) -> io::Result<()> {
+    let theme = current_theme();
+    for (y, member) in (start_y..end_y).zip(view.children(path)) {
+        let icon = &theme.entry_glyph(member.is_dir()).symbol;
+        let display_string = format!("{} {}", icon, member.name());
+        queue!(stdout, MoveTo(start_x + 2, y))?;
+        write!(
+            stdout,
+            " {}",
+            truncate_str(&display_string, width as usize - 2).with(theme.palette.folder_preview)
+        )?;
+    }
+    Ok(())
+}
+
+pub fn display_archive_member_info(
+    app_state: &AppState,
+    stdout: &mut impl Write,
+    entry: &FileEntry,
+    nav_width: u16,
+    preview_width: u16,
+    start_y: u16,
+    end_y: u16,
+) -> io::Result<()> {
+    let view = match &app_state.archive_view {
+        Some(view) => view,
+        None => return Ok(()),
+    };
+    let member = match view.member(&entry.path) {
+        Some(member) => member,
+        None => return Ok(()),
+    };
+    let dimming_config = DimmingConfig::new((end_y - start_y) as usize, &app_state.config);
+
+    for i in 0..12 {
+        queue!(stdout, MoveTo(nav_width + 4, start_y + i - 5))?;
+        write!(stdout, "{}", " ".repeat(preview_width as usize - 7))?;
+    }
+
+    let kind = match &member.member_type {
+        MemberType::File => format!("{:?}", entry.file_type),
+        MemberType::Directory => "Directory".to_string(),
+        MemberType::Symlink(target) if target.is_empty() => "Symlink".to_string(),
+        MemberType::Symlink(target) => format!("Symlink -> {}", target),
+        MemberType::Other => "Special".to_string(),
+    };
+    let mut info = vec![
+        ("Type", kind),
+        ("Size", format_size(member.size)),
+        ("Modified", describe_time(member.mtime as i64, &app_state.config)),
+        ("Permissions", format!("{:o}", member.mode)),
+        ("Archive", view.archive_path.file_name().unwrap_or_default().to_string_lossy().into_owned()),
+        ("Inside", member.parent().to_string()),
+    ];
+    if view.kind == ArchiveKind::Zip && !member.is_dir() {
+        info.insert(2, ("Compressed", format_size(member.compressed_size)));
+    }
+    if !member.is_safe() {
+        info.push(("Unsafe path", "will not be extracted".to_string()));
+    }
+
+    queue!(
+        stdout,
+        MoveTo(nav_width + 4, start_y - 5),
+        SetForegroundColor(Color::Green)
+    )?;
+    write!(stdout, "{}", format!("CHAPTER {}", entry.name.to_ascii_uppercase()).bold())?;
+
+    for (i, (label, value)) in info.iter().enumerate() {
+        let y = start_y - 2 + i as u16;
+        if y >= end_y {
+            break;
+        }
+        let dim_factor = dimming_config.calculate_dimming(i as i32);
+        queue!(
+            stdout,
+            MoveTo(nav_width + 4, y),
+            SetForegroundColor(DimmingConfig::dim_color(Color::Reset, dim_factor))
+        )?;
+        write!(stdout, "{}: ", label)?;
+        queue!(stdout, SetForegroundColor(Color::Green))?;
+        write!(
+            stdout,
+            "{}",
+            truncate_str(value, (preview_width as usize).saturating_sub(label.len() + 12))
+        )?;
+    }
+    queue!(stdout, SetForegroundColor(Color::Reset))?;
+    Ok(())
+}
+
+// Writes one member below `destination`. Returns false for members that are
+// left alone: links, special files, unsafe paths and files that exist.
+fn extract_member(
+    view: &ArchiveView,
+    member: &ArchiveMember,
+    out: &Path,
+    destination: &Path,
+) -> io::Result<bool> {
+    if !member.is_safe() || !out.starts_with(destination) {
+        return Ok(false);
+    }
+    match &member.member_type {
+        MemberType::Directory => {
+            fs::create_dir_all(out)?;
+            Ok(true)
+        }
+        MemberType::File if !out.exists() => {
+            if let Some(parent) = out.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            let data = view.read_member(member, None)?;
+            fs::write(out, data)?;
+            #[cfg(unix)]
+            if member.mode != 0 {
+                use std::os::unix::fs::PermissionsExt;
+                let _ = fs::set_permissions(out, fs::Permissions::from_mode(member.mode & 0o777));
+            }
+            Ok(true)
+        }
+        // A link made here could point the next member somewhere it should
+        // not go, so links are never recreated.
+        _ => Ok(false),
+    }
+}
+
+pub fn handle_extract_members(
+    app_state: &mut AppState,
+    entries: &[FileEntry],
+    selected_index: usize,
+) -> io::Result<()> {
+    let view = match app_state.archive_view.as_ref() {
+        Some(view) if view.contains(&app_state.current_dir) => view,
+        _ => {
+            interaction_field!("Extracting only works from inside an archive")?;
+            return Ok(());
+        }
+    };
+    if !app_state.check_operation_allowed(&view.archive_path, "copy") {
+        interaction_field!("The color rule of this archive does not allow copying")?;
+        return Ok(());
+    }
+
+    let targets: Vec<PathBuf> = match &app_state.multiple_selected_files {
+        Some(selected) if !selected.is_empty() => selected
+            .iter()
+            .filter(|path| view.contains(path))
+            .cloned()
+            .collect(),
+        _ => entries
+            .get(selected_index)
+            .map(|entry| vec![entry.path.clone()])
+            .unwrap_or_default(),
+    };
+
+    let destination = view
+        .archive_path
+        .parent()
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|| PathBuf::from("."));
+    let base = view.inner_path(&app_state.current_dir).unwrap_or_default();
+    let relative = |inner: &str| -> PathBuf {
+        let trimmed = if base.is_empty() {
+            inner
+        } else {
+            inner
+                .strip_prefix(base.as_str())
+                .map(|rest| rest.trim_start_matches('/'))
+                .unwrap_or(inner)
+        };
+        destination.join(trimmed)
+    };
+
+    let mut created: Vec<(PathBuf, bool)> = Vec::new();
+    let mut extracted = 0;
+    let mut skipped = 0;
+
+    for target in targets {
+        let Some(inner) = view.inner_path(&target) else { continue };
+        let top = relative(&inner);
+        if !top.exists() {
+            created.push((top, view.member(&target).is_some_and(|m| m.is_dir())));
+        }
+
+        for member in view.members_under(&inner) {
+            match extract_member(view, member, &relative(&member.path), &destination)? {
+                true if member.is_dir() => {}
+                true => extracted += 1,
+                false => skipped += 1,
+            }
+        }
+    }
+
+    for (path, is_directory) in created {
+        if path.exists() {
+            app_state.add_create_undo_entry(&path, is_directory)?;
+        }
+    }
+
+    if skipped > 0 {
+        interaction_field!(
+            "Extracted {} files to {}, skipped {}",
+            extracted,
+            destination.display(),
+            skipped
+        )?;
+    } else {
+        interaction_field!("Extracted {} files to {}", extracted, destination.display())?;
+    }
+    Ok(())
+}
+
+////////////////////////////////////////////////////////Packing///////////////////////////////////////////////////////////////////////
+struct PackItem {
+    path: PathBuf,
+    // Name inside the archive, always with forward slashes.
+    name: String,
+    metadata: fs::Metadata,
+}
+
+// Every source keeps its own name at the root of the archive. Links are
+// stored as links and never followed.
+fn collect_pack_items(sources: &[PathBuf], output: &Path) -> io::Result<Vec<PackItem>> {
+    let mut items = Vec::new();
+    let mut stack: Vec<(PathBuf, String)> = sources
+        .iter()
+        .rev()
+        .map(|path| {
+            let name = path.file_name().unwrap_or_default().to_string_lossy().into_owned();
+            (path.clone(), name)
+        })
+        .collect();
+
+    while let Some((path, name)) = stack.pop() {
+        if path == output {
+            continue;
+        }
+        let metadata = fs::symlink_metadata(&path)?;
+        if metadata.is_dir() {
+            let mut children: Vec<_> = fs::read_dir(&path)?.filter_map(Result::ok).collect();
+            children.sort_by_key(|child| child.file_name());
+            for child in children.into_iter().rev() {
+                let child_name = format!("{}/{}", name, child.file_name().to_string_lossy());
+                stack.push((child.path(), child_name));
+            }
+        }
+        items.push(PackItem {
+            path,
+            name,
+            metadata,
+        });
+    }
+    Ok(items)
+}
+
+fn unix_mode(metadata: &fs::Metadata) -> u32 {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        metadata.permissions().mode() & 0o7777
+    }
+    #[cfg(not(unix))]
+    {
+        if metadata.is_dir() {
+            0o755
+        } else if metadata.permissions().readonly() {
+            0o444
+        } else {
+            0o644
+        }
+    }
+}
+
+fn modified_secs(metadata: &fs::Metadata) -> u64 {
+    metadata
+        .modified()
+        .ok()
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |duration| duration.as_secs())
+}
+
+struct CountingWriter<W: Write> {
+    inner: W,
+    count: u64,
+}
+
+impl<W: Write> Write for CountingWriter<W> {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(data)?;
+        self.count += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+fn put_tar_number(field: &mut [u8], value: u64) {
+    let digits = field.len() - 1;
+    if value < 1u64 << (3 * digits) {
+        let text = format!("{:0width$o}", value, width = digits);
+        field[..digits].copy_from_slice(text.as_bytes());
+        field[digits] = 0;
+    } else {
+        // Base-256, the same escape hatch GNU tar uses for huge files.
+        for (i, byte) in field.iter_mut().rev().enumerate() {
+            *byte = if i < 8 { (value >> (8 * i)) as u8 } else { 0 };
+        }
+        field[0] |= 0x80;
+    }
+}
+
+fn tar_header(name: &[u8], link: &[u8], mode: u32, size: u64, mtime: u64, type_flag: u8) -> [u8; 512] {
+    let mut header = [0u8; 512];
+    header[..name.len().min(100)].copy_from_slice(&name[..name.len().min(100)]);
+    put_tar_number(&mut header[100..108], mode as u64);
+    put_tar_number(&mut header[108..116], 0);
+    put_tar_number(&mut header[116..124], 0);
+    put_tar_number(&mut header[124..136], size);
+    put_tar_number(&mut header[136..148], mtime);
+    header[156] = type_flag;
+    header[157..157 + link.len().min(100)].copy_from_slice(&link[..link.len().min(100)]);
+    header[257..265].copy_from_slice(b"ustar  \0");
+
+    header[148..156].copy_from_slice(b"        ");
+    let sum: u64 = header.iter().map(|&b| b as u64).sum();
+    let text = format!("{:06o}\0 ", sum);
+    header[148..156].copy_from_slice(text.as_bytes());
+    header
+}
+
+fn write_tar_padding<W: Write>(writer: &mut W, size: u64) -> io::Result<()> {
+    let padding = (512 - size % 512) % 512;
+    writer.write_all(&vec![0; padding as usize])
+}
+
+// GNU long name records, for names and link targets over 100 bytes.
+fn write_tar_long<W: Write>(writer: &mut W, value: &[u8], type_flag: u8) -> io::Result<()> {
+    let size = value.len() as u64 + 1;
+    writer.write_all(&tar_header(b"././@LongLink", b"", 0o644, size, 0, type_flag))?;
+    writer.write_all(value)?;
+    writer.write_all(&[0])?;
+    write_tar_padding(writer, size)
+}
+
+fn write_tar<W: Write>(writer: &mut W, items: &[PackItem]) -> io::Result<()> {
+    for item in items {
+        let file_type = item.metadata.file_type();
+        let mut name = item.name.clone();
+        let (type_flag, size, link) = if file_type.is_dir() {
+            name.push('/');
+            (b'5', 0, String::new())
+        } else if file_type.is_symlink() {
+            let target = fs::read_link(&item.path)?.to_string_lossy().into_owned();
+            (b'2', 0, target)
+        } else if file_type.is_file() {
+            (b'0', item.metadata.len(), String::new())
+        } else {
+            continue;
+        };
+
+        if name.len() > 100 {
+            write_tar_long(writer, name.as_bytes(), b'L')?;
+        }
+        if link.len() > 100 {
+            write_tar_long(writer, link.as_bytes(), b'K')?;
+        }
+        let mode = unix_mode(&item.metadata);
+        let mtime = modified_secs(&item.metadata);
+        writer.write_all(&tar_header(name.as_bytes(), link.as_bytes(), mode, size, mtime, type_flag))?;
+
+        if type_flag == b'0' {
+            // The header promised `size` bytes, a file that changed since
+            // has to be cut or padded to match.
+            let copied = io::copy(&mut File::open(&item.path)?.take(size), writer)?;
+            writer.write_all(&vec![0; (size - copied) as usize])?;
+            write_tar_padding(writer, size)?;
+        }
+    }
+    writer.write_all(&[0; 1024])
+}
+
+fn dos_time(secs: u64) -> (u16, u16) {
+    let local = unix_to_local(secs as i64);
+    let (year, month, day) = civil_from_days(local.div_euclid(86400));
+    if year < 1980 {
+        return (0x21, 0);
+    }
+    let seconds = local.rem_euclid(86400);
+    let date = (((year - 1980).min(127) as u16) << 9) | ((month as u16) << 5) | day as u16;
+    let time = ((seconds / 3600) as u16) << 11 | (((seconds % 3600) / 60) as u16) << 5 | ((seconds % 60) / 2) as u16;
+    (date, time)
+}
+
+fn write_zip<W: Write>(writer: W, items: &[PackItem]) -> io::Result<()> {
+    const TOO_LARGE: &str = "too large for zip, pack it as tar instead";
+    let mut writer = CountingWriter {
+        inner: writer,
+        count: 0,
+    };
+    let mut directory = Vec::new();
+    let mut entries = 0u16;
+
+    for item in items {
+        let file_type = item.metadata.file_type();
+        let mut name = item.name.clone();
+        let mode = unix_mode(&item.metadata);
+        let (kind, content): (u32, Option<Vec<u8>>) = if file_type.is_dir() {
+            name.push('/');
+            (0o040000, None)
+        } else if file_type.is_symlink() {
+            // Info-ZIP keeps a link target as the member's content.
+            let target = fs::read_link(&item.path)?.to_string_lossy().into_owned();
+            (0o120000, Some(target.into_bytes()))
+        } else if file_type.is_file() {
+            (0o100000, None)
+        } else {
+            continue;
+        };
+
+        let offset = writer.count;
+        let method: u16 = if file_type.is_dir() { 0 } else { 8 };
+        // Sizes follow the data in a descriptor, so files stream through.
+        let flags: u16 = 0x0808;
+        let (date, time) = dos_time(modified_secs(&item.metadata));
+
+        let mut local = Vec::with_capacity(30 + name.len());
+        local.extend_from_slice(&0x0403_4B50u32.to_le_bytes());
+        local.extend_from_slice(&20u16.to_le_bytes());
+        local.extend_from_slice(&flags.to_le_bytes());
+        local.extend_from_slice(&method.to_le_bytes());
+        local.extend_from_slice(&time.to_le_bytes());
+        local.extend_from_slice(&date.to_le_bytes());
+        local.extend_from_slice(&[0; 12]);
+        local.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        local.extend_from_slice(&0u16.to_le_bytes());
+        local.extend_from_slice(name.as_bytes());
+        writer.write_all(&local)?;
+
+        let start = writer.count;
+        let mut crc = 0;
+        let mut size = 0u64;
+        if !file_type.is_dir() {
+            let mut encoder = DeflateEncoder::new(&mut writer);
+            let mut source: Box<dyn Read> = match content {
+                Some(content) => Box::new(Cursor::new(content)),
+                None => Box::new(File::open(&item.path)?),
+            };
+            let mut buffer = vec![0; 64 * 1024];
+            loop {
+                let n = source.read(&mut buffer)?;
+                if n == 0 {
+                    break;
+                }
+                crc = crc32_update(crc, &buffer[..n]);
+                size += n as u64;
+                encoder.write_all(&buffer[..n])?;
+            }
+            encoder.finish()?;
+        }
+        let compressed = writer.count - start;
+        if size > u32::MAX as u64 || compressed > u32::MAX as u64 || offset > u32::MAX as u64 {
+            return Err(io::Error::other(TOO_LARGE));
+        }
+
+        writer.write_all(&0x0807_4B50u32.to_le_bytes())?;
+        writer.write_all(&crc.to_le_bytes())?;
+        writer.write_all(&(compressed as u32).to_le_bytes())?;
+        writer.write_all(&(size as u32).to_le_bytes())?;
+
+        directory.extend_from_slice(&0x0201_4B50u32.to_le_bytes());
+        directory.extend_from_slice(&(3u16 << 8 | 20).to_le_bytes());
+        directory.extend_from_slice(&20u16.to_le_bytes());
+        directory.extend_from_slice(&flags.to_le_bytes());
+        directory.extend_from_slice(&method.to_le_bytes());
+        directory.extend_from_slice(&time.to_le_bytes());
+        directory.extend_from_slice(&date.to_le_bytes());
+        directory.extend_from_slice(&crc.to_le_bytes());
+        directory.extend_from_slice(&(compressed as u32).to_le_bytes());
+        directory.extend_from_slice(&(size as u32).to_le_bytes());
+        directory.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        directory.extend_from_slice(&[0; 8]);
+        let dos_directory = if file_type.is_dir() { 0x10 } else { 0 };
+        directory.extend_from_slice(&(((kind | mode) << 16) | dos_directory).to_le_bytes());
+        directory.extend_from_slice(&(offset as u32).to_le_bytes());
+        directory.extend_from_slice(name.as_bytes());
+
+        entries = entries.checked_add(1).ok_or_else(|| io::Error::other(TOO_LARGE))?;
+    }
+
+    let directory_offset = writer.count;
+    if directory_offset > u32::MAX as u64 {
+        return Err(io::Error::other(TOO_LARGE));
+    }
+    writer.write_all(&directory)?;
+    writer.write_all(&0x0605_4B50u32.to_le_bytes())?;
+    writer.write_all(&[0; 4])?;
+    writer.write_all(&entries.to_le_bytes())?;
+    writer.write_all(&entries.to_le_bytes())?;
+    writer.write_all(&(directory.len() as u32).to_le_bytes())?;
+    writer.write_all(&(directory_offset as u32).to_le_bytes())?;
+    writer.write_all(&0u16.to_le_bytes())?;
+    writer.flush()
+}
+
+// Writes the archive into file, which the caller created at output.
+pub fn pack_paths(sources: &[PathBuf], output: &Path, file: File) -> io::Result<String> {
+    let kind = ArchiveKind::from_path(output)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "unknown archive type"))?;
+    let items = collect_pack_items(sources, output)?;
+    let file = io::BufWriter::new(file);
+
+    match kind {
+        ArchiveKind::Tar => {
+            let mut file = file;
+            write_tar(&mut file, &items)?;
+            file.flush()?;
+        }
+        ArchiveKind::TarGz => {
+            let mut encoder = GzipEncoder::new(file)?;
+            write_tar(&mut encoder, &items)?;
+            encoder.finish()?;
+        }
+        ArchiveKind::Zip => write_zip(file, &items)?,
+    }
+    Ok(format!(
+        "Packed {} items into {}",
+        items.len(),
+        output.file_name().unwrap_or_default().to_string_lossy()
+    ))
+}
+
+// Extracts into destination, a directory the caller created for it.
+pub fn unpack_archive(archive: &Path, destination: &Path) -> io::Result<String> {
+    let view = ArchiveView::open(archive)?;
+    let mut extracted = 0;
+    let mut skipped = 0;
+    for member in &view.members {
+        match extract_member(&view, member, &destination.join(&member.path), destination)? {
+            true if member.is_dir() => {}
+            true => extracted += 1,
+            false => skipped += 1,
+        }
+    }
+    let name = destination.file_name().unwrap_or_default().to_string_lossy();
+    Ok(if skipped > 0 {
+        format!("Extracted {} files to {}, skipped {}", extracted, name, skipped)
+    } else {
+        format!("Extracted {} files to {}", extracted, name)
+    })
+}
+
+// `photos.tar.gz` unpacks into `photos`, or `photos (1)` when that is taken.
+fn unpack_destination(archive: &Path) -> PathBuf {
+    let name = archive.file_name().unwrap_or_default().to_string_lossy();
+    let lower = name.to_lowercase();
+    let stem_len = [".tar.gz", ".tgz", ".tar", ".zip", ".jar"]
+        .iter()
+        .find(|extension| lower.ends_with(*extension))
+        .map_or(name.len(), |extension| name.len() - extension.len());
+    let stem = &name[..stem_len];
+    let parent = archive.parent().unwrap_or(Path::new("."));
+
+    let mut destination = parent.join(stem);
+    let mut counter = 1;
+    while destination.exists() {
+        destination = parent.join(format!("{} ({})", stem, counter));
+        counter += 1;
+    }
+    destination
+}
+
+pub fn handle_pack_selection(
+    app_state: &mut AppState,
+    stdout: &mut impl Write,
+    entries: &[FileEntry],
+    selected_index: usize,
+) -> io::Result<()> {
+    if app_state.in_archive() {
+        interaction_field!("Extract the members first, archives are read-only")?;
+        return Ok(());
+    }
+    let sources: Vec<PathBuf> = match &app_state.multiple_selected_files {
+        Some(selected) if !selected.is_empty() => {
+            let mut sources: Vec<_> = selected.iter().cloned().collect();
+            sources.sort();
+            sources
+        }
+        _ => match entries.get(selected_index) {
+            Some(entry) => vec![entry.path.clone()],
+            None => return Ok(()),
+        },
+    };
+
+    let (allowed, refused): (Vec<_>, Vec<_>) = sources
+        .into_iter()
+        .partition(|path| app_state.check_operation_allowed(path, "copy"));
+    if allowed.is_empty() {
+        interaction_field!("The color rules do not allow copying the selection")?;
+        return Ok(());
+    }
+
+    let name = match app_state.prompt_for_name(stdout, "Archive name (.tar, .tar.gz or .zip): ") {
+        Ok(Some(name)) => name,
+        Ok(None) => {
+            interaction_field!("Packing cancelled.")?;
+            return Ok(());
+        }
+        Err(e) => {
+            interaction_field!("Error during name input: {}", e)?;
+            return Ok(());
+        }
+    };
+    let mut output = app_state.current_dir.join(&name);
+    if ArchiveKind::from_path(&output).is_none() {
+        output = app_state.current_dir.join(format!("{}.tar.gz", name));
+    }
+    if output.exists() {
+        interaction_field!("Error: '{}' already exists.", name)?;
+        return Ok(());
+    }
+
+    let description = if refused.is_empty() {
+        format!("Packing {} items", allowed.len())
+    } else {
+        format!("Packing {} items, {} not allowed", allowed.len(), refused.len())
+    };
+    // Made here, so a failed job only ever removes its own file.
+    let file = match OpenOptions::new().write(true).create_new(true).open(&output) {
+        Ok(file) => file,
+        Err(e) => {
+            interaction_field!("Error: could not create '{}': {}", name, e)?;
+            return Ok(());
+        }
+    };
+    let target = output.clone();
+    let job = BackgroundJob::spawn(description, output, false, move || {
+        pack_paths(&allowed, &target, file)
+    });
+    start_background_job(app_state, job)
+}
+
+pub fn handle_unpack_archive(
+    app_state: &mut AppState,
+    entries: &[FileEntry],
+    selected_index: usize,
+) -> io::Result<()> {
+    let entry = match entries.get(selected_index) {
+        Some(entry) => entry,
+        None => return Ok(()),
+    };
+    if app_state.in_archive() {
+        return handle_extract_members(app_state, entries, selected_index);
+    }
+    if entry.file_type == FileType::Directory || ArchiveKind::from_path(&entry.path).is_none() {
+        interaction_field!("'{}' is not an archive", entry.name)?;
+        return Ok(());
+    }
+    if !app_state.check_operation_allowed(&entry.path, "copy") {
+        interaction_field!("The color rule of this archive does not allow copying")?;
+        return Ok(());
+    }
+
+    let destination = unpack_destination(&entry.path);
+    // Made here, so a failed job only ever removes its own directory.
+    if let Err(e) = fs::create_dir(&destination) {
+        interaction_field!("Error: could not create '{}': {}", destination.display(), e)?;
+        return Ok(());
+    }
+    let archive = entry.path.clone();
+    let target = destination.clone();
+    let job = BackgroundJob::spawn(
+        format!("Extracting {}", entry.name),
+        destination,
+        true,
+        move || unpack_archive(&archive, &target),
+    );
+    start_background_job(app_state, job)
+}
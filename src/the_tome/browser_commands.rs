@@ -40,7 +40,7 @@ pub fn open_file_with_editor(
     editor: &str,
     stdout: &mut impl Write,
 ) -> io::Result<()> {
-    terminal::disable_raw_mode()?;
+    disable_raw_mode()?;
     execute!(
         stdout,
         terminal::Clear(ClearType::All),
@@ -58,7 +58,7 @@ pub fn open_file_with_editor(
         )
     })?;
 
-    terminal::enable_raw_mode()?;
+    enable_raw_mode()?;
 
     if !status.success() {
         return Err(io::Error::new(
@@ -102,7 +102,7 @@ pub fn read_new_sort(stdout: &mut impl Write) -> io::Result<SortOrder> {
         queue!(stdout, MoveTo(nav_width + 20, 7 + height / 8 + 9 as u16))?;
         stdout.flush()?;
 
-        if let Event::Key(key) = event::read()? {
+        if let Event::Key(key) = read_event()? {
             match key.code {
                 KeyCode::Char('1') => return Ok(SortOrder::NameAsc),
                 KeyCode::Char('2') => return Ok(SortOrder::NameDesc),
@@ -190,7 +190,7 @@ pub fn navigate_to_shortcut(app_state: &mut AppState, key: char) -> io::Result<(
             app_state.current_dir = path.clone();
             Ok(())
         } else {
-            queue!(stdout(), MoveTo(preview_width + 34, height - 10))?;
+            queue!(screen(), MoveTo(preview_width + 34, height - 10))?;
             Err(io::Error::new(
                 io::ErrorKind::InvalidInput,
                 "Shortcut does not point to a directory",
@@ -209,7 +209,7 @@ pub fn edit_config(
     stdout: &mut impl Write,
     current_dir: &Path,
 ) -> io::Result<()> {
-    let config_path = Config::get_config_path()?;
+    let config_path = app_state.config_path.clone();
     let (width, height) = size()?;
     let nav_width = width / 2;
     let preview_width = width - nav_width - 2;
@@ -227,6 +227,9 @@ pub fn edit_config(
         "Set Search Depth Limit",
         "Set Dimming Settings",
         "Undo Settings",
+        "Time Format",
+        "Theme",
+        "Profile",
         "Return to Browser",
     ];
 
@@ -310,6 +313,34 @@ pub fn edit_config(
                         "Disabled".red()
                     }
                 )?,
+                7 => writeln!(
+                    stdout,
+                    "{} (current: {}, relative: {})\r",
+                    item.cyan(),
+                    app_state.config.time_format.clone().green(),
+                    if app_state.config.relative_times {
+                        "On".green()
+                    } else {
+                        "Off".red()
+                    }
+                )?,
+                8 => writeln!(
+                    stdout,
+                    "{} (current: {})\r",
+                    item.cyan(),
+                    app_state.config.theme.clone().green()
+                )?,
+                9 => writeln!(
+                    stdout,
+                    "{} (current: {})\r",
+                    item.cyan(),
+                    app_state
+                        .config
+                        .active_profile
+                        .clone()
+                        .unwrap_or_else(|| "none".to_string())
+                        .green()
+                )?,
                 _ => writeln!(stdout, "{}\r", item.cyan())?,
             }
         }
@@ -335,7 +366,7 @@ pub fn edit_config(
 
         stdout.flush()?;
 
-        if let Event::Key(key) = event::read()? {
+        if let Event::Key(key) = read_event()? {
             match key.code {
                 KeyCode::Up | KeyCode::Char('k') => {
                     let _ = clear_interaction_field();
@@ -357,7 +388,7 @@ pub fn edit_config(
                         1 => {
                             if let Ok(new_sort) = read_new_sort(stdout) {
                                 app_state.config.default_sort = new_sort;
-                                app_state.config.save_config()?;
+                                app_state.save_config()?;
                             }
                         }
                         2 => {
@@ -370,7 +401,7 @@ pub fn edit_config(
                             if let Ok(new_editor) = read_line() {
                                 if !new_editor.trim().is_empty() {
                                     app_state.config.text_editor = new_editor;
-                                    app_state.config.save_config()?;
+                                    app_state.save_config()?;
                                     interaction_field!("Text editor updated")?;
                                 }
                             }
@@ -380,7 +411,7 @@ pub fn edit_config(
                             if let Ok(new_depth) = read_line()?.trim().parse() {
                                 app_state.search_depth_limit = new_depth;
                                 app_state.config.search_depth_limit = new_depth;
-                                app_state.config.save_config()?;
+                                app_state.save_config()?;
                                 interaction_field!("Search depth updated to {}", new_depth)?;
                             } else {
                                 interaction_field!("Invalid input. Search depth not changed")?;
@@ -389,6 +420,77 @@ pub fn edit_config(
                         5 => {
                             configure_undo_settings(app_state, stdout)?;
                         }
+                        7 => {
+                            let _ = interaction_field!(
+                                "Enter a time format like %Y-%m-%d %H:%M, or 'relative' to toggle relative times:"
+                            )?;
+                            let input = read_line()?;
+                            let input = input.trim();
+                            if input.eq_ignore_ascii_case("relative") {
+                                app_state.config.relative_times = !app_state.config.relative_times;
+                                app_state.save_config()?;
+                                interaction_field!(
+                                    "Relative times {}",
+                                    if app_state.config.relative_times { "on" } else { "off" }
+                                )?;
+                            } else if !input.is_empty() {
+                                app_state.config.time_format = input.to_string();
+                                app_state.save_config()?;
+                                interaction_field!(
+                                    "Times now look like {}",
+                                    format_local_time(unix_seconds(SystemTime::now()), input)
+                                )?;
+                            }
+                        }
+                        8 => {
+                            interaction_field!(
+                                "Enter a theme ({}) or a theme file path, empty for the next built-in one:",
+                                BUILTIN_THEMES.join(", ")
+                            )?;
+                            let input = read_line()?;
+                            let spec = match input.trim() {
+                                "" => {
+                                    let current = BUILTIN_THEMES
+                                        .iter()
+                                        .position(|name| *name == app_state.config.theme);
+                                    let next = current.map_or(0, |i| (i + 1) % BUILTIN_THEMES.len());
+                                    BUILTIN_THEMES[next].to_string()
+                                }
+                                spec => spec.to_string(),
+                            };
+                            match Theme::load(&spec) {
+                                Ok(theme) => {
+                                    set_theme(theme);
+                                    app_state.config.theme = spec;
+                                    app_state.save_config()?;
+                                    interaction_field!("Theme set to {}", app_state.config.theme)?;
+                                }
+                                Err(e) => interaction_field!("Could not load theme {}: {}", spec, e)?,
+                            }
+                        }
+                        9 => {
+                            if app_state.config.profiles.is_empty() {
+                                interaction_field!("No profiles yet, add a [profiles.NAME] table to the config")?;
+                            } else {
+                                interaction_field!(
+                                    "Enter a profile ({}), empty for none:",
+                                    app_state.config.profile_names().join(", ")
+                                )?;
+                                let input = read_line()?;
+                                let name = input.trim();
+                                let profile = (!name.is_empty()).then_some(name);
+                                match app_state.switch_profile(profile) {
+                                    Ok(()) => {
+                                        // Picked here, it is also the one to start with.
+                                        app_state.config.overridden.remove("profile");
+                                        app_state.config.profile = name.to_string();
+                                        app_state.save_config()?;
+                                        interaction_field!("Profile set to {}", profile.unwrap_or("none"))?;
+                                    }
+                                    Err(e) => interaction_field!("{}", e)?,
+                                }
+                            }
+                        }
                         6 | _ => break,
                     }
                     let _ = clear_nav();
@@ -466,7 +568,7 @@ fn configure_undo_settings(app_state: &mut AppState, stdout: &mut impl Write) ->
 
         stdout.flush()?;
 
-        if let Event::Key(key) = event::read()? {
+        if let Event::Key(key) = read_event()? {
             match key.code {
                 KeyCode::Up | KeyCode::Char('k') => {
                     selected_item = selected_item.saturating_sub(1);
@@ -487,7 +589,7 @@ fn configure_undo_settings(app_state: &mut AppState, stdout: &mut impl Write) ->
                                     app_state.undo_manager.ram_limit = limit * 1_048_576; // Mibs to bibs
                                     app_state.config.ram_undo_limit =
                                         app_state.undo_manager.ram_limit;
-                                    app_state.config.save_config()?;
+                                    app_state.save_config()?;
                                     let _ = clear_interaction_field();
                                     interaction_field!("RAM limit update.\r")?;
                                 } else {
@@ -507,7 +609,7 @@ fn configure_undo_settings(app_state: &mut AppState, stdout: &mut impl Write) ->
                                     app_state.undo_manager.disk_limit = limit * 1_073_741_824; // Gigs to bibs
                                     app_state.config.disk_undo_limit =
                                         app_state.undo_manager.disk_limit;
-                                    app_state.config.save_config()?;
+                                    app_state.save_config()?;
                                     interaction_field!("Disk limit updated successfully.\r")?;
                                 } else {
                                     interaction_field!("Invalid input. Please enter a number.\r")?;
@@ -521,7 +623,7 @@ fn configure_undo_settings(app_state: &mut AppState, stdout: &mut impl Write) ->
                                 !app_state.undo_manager.allow_disk_storage;
                             app_state.config.allow_disk_undo =
                                 app_state.undo_manager.allow_disk_storage;
-                            app_state.config.save_config()?;
+                            app_state.save_config()?;
                             execute!(stdout, MoveTo(nav_width / 8, 16))?;
                             writeln!(
                                 stdout,
@@ -580,7 +682,7 @@ pub fn rename_file(
     let mut new_name = String::new();
     execute!(stdout, MoveTo(nav_width + 4, start_y))?;
     loop {
-        if let Event::Key(key) = event::read()? {
+        if let Event::Key(key) = read_event()? {
             match key.code {
                 KeyCode::Char(c) => {
                     new_name.push(c);
@@ -593,21 +695,19 @@ pub fn rename_file(
                             height - 10
                         )
                     )?;
-                    print!("{}", c.red());
+                    write!(stdout, "{}", c.red())?;
                     stdout.flush()?;
                 }
                 KeyCode::Backspace => {
                     if !new_name.is_empty() {
                         new_name.pop();
-                        print!("\x08 \x08");
+                        write!(stdout, "\x08 \x08")?;
                         stdout.flush()?;
                     }
                 }
                 KeyCode::Enter => {
                     if !new_name.is_empty() {
                         let old_path = entry.path.clone();
-                        let old_name = old_path.file_name().unwrap().to_string_lossy().into_owned();
-                        let parent = old_path.parent().unwrap_or(Path::new(""));
 
                         let mut new_file_name = new_name.clone();
                         if keep_extension {
@@ -617,26 +717,12 @@ pub fn rename_file(
                             }
                         }
 
-                        let new_path = parent.join(&new_file_name);
-
                         queue!(
                             stdout,
                             MoveTo((preview_width * 11 / 8) + 18 as u16, height - 10)
                         )?;
-                        match fs::rename(&old_path, &new_path) {
+                        match app_state.file_operations().rename(&old_path, &new_file_name) {
                             Ok(_) => {
-                                app_state.undo_manager.add_tome_entry(UndoEntry {
-                                    operation: Operation::Rename {
-                                        old_name,
-                                        new_name: new_file_name,
-                                        path: parent.to_path_buf(),
-                                        timestamp: SystemTime::now(),
-                                    },
-                                    storage: UndoStorage::Ram(Vec::new()),
-                                    original_path: new_path,
-                                    size: 0,
-                                })?;
-
                                 execute!(stdout, MoveTo(nav_width + 4, start_y + 2))?;
                                 interaction_field!("File renamed successfully.\r")?;
                             }
@@ -688,58 +774,71 @@ pub fn copy_files(app_state: &mut AppState, entries: &[FileEntry], selected_inde
     }
 }
 
-pub fn paste_files(app_state: &mut AppState, current_dir: &Path) -> io::Result<()> {
-    if let Some(files_to_paste) = &app_state.clipboard {
-        for source_path in files_to_paste {
-            if !app_state.check_operation_allowed(source_path, "copy") {
-                let _ = interaction_field!("Not allowed to be pasted");
-                continue;
-            }
-            let file_name = source_path.file_name().unwrap_or_default();
-            let destination = current_dir.join(file_name);
+// Puts the paths of the selection, or the entry under the cursor, on the
+// terminal's clipboard with OSC 52, one per line.
+pub fn yank_paths(
+    app_state: &AppState,
+    stdout: &mut impl Write,
+    entries: &[FileEntry],
+    selected_index: usize,
+) -> io::Result<()> {
+    let mut paths = match &app_state.multiple_selected_files {
+        Some(selected) => selected.iter().cloned().collect::<Vec<_>>(),
+        None => entries
+            .get(selected_index)
+            .map(|entry| vec![entry.path.clone()])
+            .unwrap_or_default(),
+    };
+    if paths.is_empty() {
+        return interaction_field!("Nothing to yank");
+    }
+    paths.sort();
+    let text = paths
+        .iter()
+        .map(|path| path.display().to_string())
+        .collect::<Vec<_>>()
+        .join("\n");
+    write!(stdout, "\x1b]52;c;{}\x07", base64(text.as_bytes()))?;
+    stdout.flush()?;
+    match paths.as_slice() {
+        [path] => interaction_field!("Yanked {}", path.display()),
+        _ => interaction_field!("Yanked {} paths", paths.len()),
+    }
+}
 
-            if source_path.is_dir() {
-                copy_dir_all(source_path, &destination)?;
+fn base64(data: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] =
+        b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut encoded = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let bytes = [chunk[0], *chunk.get(1).unwrap_or(&0), *chunk.get(2).unwrap_or(&0)];
+        let bits = u32::from_be_bytes([0, bytes[0], bytes[1], bytes[2]]);
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(ALPHABET[(bits >> (18 - 6 * i) & 63) as usize] as char);
             } else {
-                fs::copy(source_path, &destination)?;
+                encoded.push('=');
             }
-
-            let file_content = if destination.is_file() {
-                fs::read(&destination)?
-            } else {
-                Vec::new()
-            };
-
-            app_state.undo_manager.add_tome_entry(UndoEntry {
-                operation: Operation::Copy {
-                    source_path: source_path.clone(),
-                    dest_path: destination.clone(),
-                    timestamp: SystemTime::now(),
-                },
-                storage: UndoStorage::Ram(file_content.clone()),
-                original_path: destination,
-                size: file_content.len(),
-            })?;
         }
     }
-    let _ = interaction_field!("Content pasted");
-    Ok(())
+    encoded
 }
 
-fn copy_dir_all(src: &Path, dst: &Path) -> io::Result<u64> {
-    let mut total_size = 0;
-    fs::create_dir_all(dst)?;
-    for entry in fs::read_dir(src)? {
-        let entry = entry?;
-        let typ = entry.file_type()?;
-        if typ.is_dir() {
-            total_size += copy_dir_all(&entry.path(), &dst.join(entry.file_name()))?;
-        } else {
-            total_size += fs::copy(entry.path(), dst.join(entry.file_name()))?;
+pub fn paste_files(app_state: &mut AppState, current_dir: &Path) -> io::Result<()> {
+    let files_to_paste = app_state.clipboard.clone().unwrap_or_default();
+    for source_path in files_to_paste {
+        match app_state.file_operations().paste(&source_path, current_dir) {
+            Ok(_) => {}
+            Err(e) if e.kind() == io::ErrorKind::PermissionDenied => {
+                let _ = interaction_field!("Not allowed to be pasted");
+            }
+            Err(e) => return Err(e),
         }
     }
-    Ok(total_size)
+    let _ = interaction_field!("Content pasted");
+    Ok(())
 }
+
 pub fn duplicate_files(
     stdout: &mut impl Write,
     app_state: &mut AppState,
@@ -754,60 +853,19 @@ pub fn duplicate_files(
     ) {
         vec![entries[selected_index.unwrap()].path.clone()]
     } else {
-        println!("No files were duplicated (permission denied or no selection)");
+        writeln!(stdout, "No files were duplicated (permission denied or no selection)\r")?;
         return Ok(());
     };
 
     for path in files_to_duplicate {
-        let parent = path.parent().unwrap();
-        let file_stem = path.file_stem().unwrap().to_str().unwrap();
-        let extension = path
-            .extension()
-            .map(|ext| ext.to_str().unwrap())
-            .unwrap_or("");
-        let mut counter = 1;
-        let mut new_path = parent.join(format!(
-            "{} ({}){}.{}",
-            file_stem,
-            counter,
-            if extension.is_empty() { "" } else { "." },
-            extension
-        ));
-        while new_path.exists() {
-            counter += 1;
-            new_path = parent.join(format!(
-                "{} ({}){}.{}",
-                file_stem,
-                counter,
-                if extension.is_empty() { "" } else { "." },
-                extension
-            ));
-        }
-
-        if path.is_dir() {
-            fs::create_dir_all(&new_path)?;
-            copy_dir_all(&path, &new_path)?;
-        } else {
-            fs::copy(&path, &new_path)?;
-        }
-
-        let file_content = if new_path.is_file() {
-            fs::read(&new_path)?
-        } else {
-            Vec::new()
+        let new_path = match app_state.file_operations().duplicate(&path) {
+            Ok(new_path) => new_path,
+            Err(e) if e.kind() == io::ErrorKind::PermissionDenied => {
+                writeln!(stdout, "Not duplicated: {}\r", e)?;
+                continue;
+            }
+            Err(e) => return Err(e),
         };
-
-        app_state.undo_manager.add_tome_entry(UndoEntry {
-            operation: Operation::Duplicate {
-                original_path: path.clone(),
-                new_path: new_path.clone(),
-                timestamp: SystemTime::now(),
-            },
-            storage: UndoStorage::Ram(file_content.clone()),
-            original_path: new_path.clone(),
-            size: file_content.len(),
-        })?;
-
         writeln!(
             stdout,
             "Duplicated: {} -> {}\r",
@@ -818,27 +876,30 @@ pub fn duplicate_files(
 
     Ok(())
 }
+
 pub fn prompt_line_amount(current_lines: usize, page_state: &PageState) -> io::Result<usize> {
-    let mut stdout = stdout();
+    let mut stdout = screen();
     let mut lines = current_lines;
     let mut input_buffer = String::new();
     let mut input_mode = false;
 
     loop {
         execute!(stdout, Clear(ClearType::All), MoveTo(0, 0))?;
-        println!("How many lines do you want to display?\r");
-        println!("Current selection: {}\r", lines.to_string().green());
-        println!("Enter a number (min 20, max 100k)\r");
-        println!(
+        writeln!(stdout, "How many lines do you want to display?\r")?;
+        writeln!(stdout, "Current selection: {}\r", lines.to_string().green())?;
+        writeln!(stdout, "Enter a number (min 20, max 100k)\r")?;
+        writeln!(
+            stdout,
             "Press Enter to confirm, 'r' to reset to current ({}), or Esc to cancel\r",
             current_lines
-        );
+        )?;
 
         if input_mode {
-            println!("Enter number: {}", input_buffer);
+            writeln!(stdout, "Enter number: {}\r", input_buffer)?;
         }
+        stdout.flush()?;
 
-        if let Event::Key(key) = event::read()? {
+        if let Event::Key(key) = read_event()? {
             match key.code {
                 KeyCode::Char(c) if c.is_ascii_digit() => {
                     input_mode = true;
@@ -890,87 +951,9 @@ pub fn prompt_line_amount(current_lines: usize, page_state: &PageState) -> io::R
     }
 }
 
-pub fn parse_key_event(s: &str) -> KeyEvent {
-    let parts: Vec<&str> = s.split('+').collect();
-    let mut modifiers = KeyModifiers::empty();
-    let key_code = parts.last().unwrap_or(&"");
-
-    for part in &parts[..parts.len().saturating_sub(1)] {
-        match *part {
-            "Shift" => modifiers.insert(KeyModifiers::SHIFT),
-            "Ctrl" => modifiers.insert(KeyModifiers::CONTROL),
-            "Alt" => modifiers.insert(KeyModifiers::ALT),
-            _ => {}
-        }
-    }
-
-    let code = match *key_code {
-        "Up" => KeyCode::Up,
-        "Down" => KeyCode::Down,
-        "Left" => KeyCode::Left,
-        "Right" => KeyCode::Right,
-        "Enter" => KeyCode::Enter,
-        "Esc" => KeyCode::Esc,
-        "F1" => KeyCode::F(1),
-        " " => KeyCode::Char(' '),
-        c if c.len() == 1 => KeyCode::Char(c.chars().next().unwrap()),
-        _ => KeyCode::Null,
-    };
-
-    KeyEvent::new(code, modifiers)
-}
-
-pub fn key_event_to_string(key: &KeyEvent) -> String {
-    let mut parts = Vec::new();
-
-    if key.modifiers.contains(KeyModifiers::SHIFT) {
-        parts.push("Shift".to_string());
-    }
-    if key.modifiers.contains(KeyModifiers::CONTROL) {
-        parts.push("Ctrl".to_string());
-    }
-    if key.modifiers.contains(KeyModifiers::ALT) {
-        parts.push("Alt".to_string());
-    }
-
-    parts.push(match key.code {
-        KeyCode::Backspace => "Backspace".to_string(),
-        KeyCode::Enter => "Enter".to_string(),
-        KeyCode::Left => "Left".to_string(),
-        KeyCode::Right => "Right".to_string(),
-        KeyCode::Up => "Up".to_string(),
-        KeyCode::Down => "Down".to_string(),
-        KeyCode::Home => "Home".to_string(),
-        KeyCode::End => "End".to_string(),
-        KeyCode::PageUp => "PageUp".to_string(),
-        KeyCode::PageDown => "PageDown".to_string(),
-        KeyCode::Tab => "Tab".to_string(),
-        KeyCode::Delete => "Delete".to_string(),
-        KeyCode::Insert => "Insert".to_string(),
-        KeyCode::Esc => "Esc".to_string(),
-        KeyCode::Char(' ') => "[SPACE]".to_string(),
-        KeyCode::Char(c) => c.to_string(),
-        KeyCode::F(n) => format!("F{}", n),
-        _ => format!("{:?}", key.code),
-    });
-
-    // format!("\"{}\" ({:?}, )", parts.join("+"), key.code)
-    format!("\"{}\"", parts.join("+"))
-}
-
-fn copy_to_storage(path: &Path) -> io::Result<(UndoStorage, usize)> {
-    if path.is_file() {
-        let mut file = File::open(path)?;
-        let mut buffer = Vec::new();
-        let size = file.read_to_end(&mut buffer)?;
-        Ok((UndoStorage::Ram(buffer), size))
-    } else {
-        let temp_dir = std::env::temp_dir().join("file_manager_undo_temp");
-        fs::create_dir_all(&temp_dir)?;
-        let temp_path = temp_dir.join(path.file_name().unwrap());
-        let size = copy_dir_all(path, &temp_path)?;
-        Ok((UndoStorage::Disk(temp_path), size as usize))
-    }
+// Quoted, the way the config file and the keybinding screens show keys.
+pub fn key_event_to_string(key: impl Into<KeySequence>) -> String {
+    format!("\"{}\"", key.into())
 }
 
 pub fn murder_files(
@@ -1014,7 +997,7 @@ pub fn murder_files(
         )?;
         stdout.flush()?;
 
-        if let Event::Key(key) = event::read()? {
+        if let Event::Key(key) = read_event()? {
             if key.code != KeyCode::Char('y') {
                 let _ = clear_interaction_field();
                 interaction_field!("Deletion cancelled.\r")?;
@@ -1024,21 +1007,7 @@ pub fn murder_files(
     }
 
     for path in &allowed_files {
-        let (storage, size) = copy_to_storage(path)?;
-        app_state.undo_manager.add_tome_entry(UndoEntry {
-            operation: Operation::Delete {
-                timestamp: SystemTime::now(),
-            },
-            storage,
-            original_path: path.to_path_buf(),
-            size,
-        })?;
-
-        if path.is_dir() {
-            fs::remove_dir_all(path)?;
-        } else {
-            fs::remove_file(path)?;
-        }
+        app_state.file_operations().delete(path)?;
     }
 
     let _ = clear_interaction_field();
@@ -1172,111 +1141,68 @@ fn copy_dir_contents(src: &Path, dst: &Path) -> io::Result<u64> {
     }
     Ok(total_size)
 }
-pub fn undo_last_operation(app_state: &mut AppState, stdout: &mut impl Write) -> io::Result<()> {
-    if let Some(entry) = app_state.undo_manager.undo_haunting_regret() {
-        match entry.operation {
-            Operation::Move {
-                old_path, new_path, ..
-            } => {
-                if new_path.exists() {
-                    if old_path.exists() {
-                        let _ = clear_interaction_field();
-                        interaction_field!(
-                            "Cannot undo move: both source and destination exist.\r"
-                        )?;
-                    } else {
-                        match fs::rename(&new_path, &old_path) {
-                            Ok(_) => {
-                                let _ = clear_interaction_field();
-                                interaction_field!(
-                                    "Undid move. Moved back: {} -> {}\r",
-                                    new_path.display(),
-                                    old_path.display()
-                                )?;
-                                if let Some(parent) = old_path.parent() {
-                                    app_state.current_dir = parent.to_path_buf();
-                                }
-                            }
-                            Err(e) => {
-                                let _ = clear_interaction_field();
-                                interaction_field!("Error undoing move: {}\r", e)?;
-                            }
-                        }
-                    }
-                } else {
-                    let _ = clear_interaction_field();
-                    interaction_field!("Cannot undo move: destination no longer exists.\r")?;
-                }
-            }
-            Operation::Delete { .. } => {
-                match entry.storage {
-                    UndoStorage::Ram(data) => {
-                        let mut file = File::create(&entry.original_path)?;
-                        file.write_all(&data)?;
-                    }
-                    UndoStorage::Disk(temp_path) => {
-                        fs::rename(temp_path, &entry.original_path)?;
+pub fn undo_last_operation(app_state: &mut AppState, _stdout: &mut impl Write) -> io::Result<()> {
+    let message = match app_state.undo_manager.undo_haunting_regret() {
+        Some(entry) => {
+            // Undoing a move follows the item back to where it came from.
+            let moved_back_to = match &entry.operation {
+                Operation::Move { old_path, .. } => old_path.parent().map(Path::to_path_buf),
+                _ => None,
+            };
+            match app_state.file_operations().revert(entry) {
+                Ok(message) => {
+                    if let Some(dir) = moved_back_to {
+                        app_state.current_dir = dir;
                     }
+                    message
                 }
-                let _ = clear_interaction_field();
-                interaction_field!("Undid deletion. File/directory restored.\r")?;
-            }
-            Operation::Rename {
-                path,
-                old_name,
-                new_name,
-                ..
-            } => {
-                let current_path = path.join(&new_name);
-                let old_path = path.join(&old_name);
-                fs::rename(&current_path, &old_path)?;
-                let _ = clear_interaction_field();
-                interaction_field!(
-                    "Undid rename. File/directory name restored to '{}'.\r",
-                    old_name
-                )?;
-            }
-            Operation::Create {
-                path, is_directory, ..
-            } => {
-                if is_directory {
-                    let _ = fs::remove_dir_all(path);
-                } else {
-                    let _ = fs::remove_file(path);
-                }
-            }
-            Operation::Copy {
-                source_path: _,
-                dest_path,
-                ..
-            } => {
-                if dest_path.is_dir() {
-                    fs::remove_dir_all(&dest_path)?;
-                } else {
-                    fs::remove_file(&dest_path)?;
-                }
-                let _ = clear_interaction_field();
-                interaction_field!("Undid copy. Removed: {}\r", dest_path.display())?;
-            }
-            Operation::Duplicate {
-                original_path: _,
-                new_path,
-                ..
-            } => {
-                if new_path.is_dir() {
-                    fs::remove_dir_all(&new_path)?;
-                } else {
-                    fs::remove_file(&new_path)?;
-                }
-                let _ = clear_interaction_field();
-                interaction_field!("Undid duplication. Removed: {}\r", new_path.display())?;
+                Err(e) => format!("Cannot undo: {}", e),
             }
         }
-        Ok(())
+        None => "No operations to undo.".to_string(),
+    };
+    let _ = clear_interaction_field();
+    interaction_field!("{}\r", message)?;
+    Ok(())
+}
+
+// A command line for the system shell.
+pub fn shell_command(line: &str) -> Command {
+    let mut process = if cfg!(target_os = "windows") {
+        let mut process = Command::new("cmd");
+        process.arg("/C");
+        process
     } else {
-        let _ = clear_interaction_field();
-        interaction_field!("No operations to undo.\r")?;
-        Ok(())
+        let mut process = Command::new("sh");
+        process.arg("-c");
+        process
+    };
+    process.arg(line);
+    process
+}
+
+// Runs a custom command in the current directory and shows the last line
+// it printed, for when it is picked from the command palette.
+pub fn run_custom_command(app_state: &mut AppState, name: &str) -> io::Result<()> {
+    let Some(line) = app_state.config.custom_command(name) else {
+        return interaction_field!("Unknown command: {}", name);
+    };
+    match shell_command(&line).current_dir(&app_state.current_dir).output() {
+        Ok(output) => {
+            let printed = if output.status.success() {
+                &output.stdout
+            } else {
+                &output.stderr
+            };
+            let printed = String::from_utf8_lossy(printed);
+            match (output.status.success(), printed.lines().last()) {
+                (true, Some(last)) => interaction_field!("{}: {}", name, last),
+                (true, None) => interaction_field!("{} done", name),
+                (false, Some(last)) => interaction_field!("{} failed: {}", name, last),
+                (false, None) => interaction_field!("{} failed: {}", name, output.status),
+            }
+        }
+        Err(e) => interaction_field!("{} failed: {}", name, e),
     }
 }
 
@@ -1328,7 +1254,7 @@ pub fn execute_terminal_command(
         write!(stdout, ">")?;
 
         for (i, c) in command_buffer.chars().enumerate() {
-            let color = if i % 2 == 0 {
+            let color = fit_color(if i % 2 == 0 {
                 Color::Rgb {
                     r: 200,
                     g: 100,
@@ -1340,7 +1266,7 @@ pub fn execute_terminal_command(
                     g: 250,
                     b: 150,
                 }
-            };
+            });
             execute!(stdout, SetForegroundColor(color))?;
             write!(stdout, "{}", c)?;
         }
@@ -1350,11 +1276,11 @@ pub fn execute_terminal_command(
         write!(stdout, ">")?;
         execute!(
             stdout,
-            SetForegroundColor(Color::Rgb {
+            SetForegroundColor(fit_color(Color::Rgb {
                 r: 255,
                 g: 255,
                 b: 255
-            })
+            }))
         )?;
         write!(stdout, " {}", command_buffer)?;
 
@@ -1392,7 +1318,7 @@ pub fn execute_terminal_command(
         };
 
         let get_color = |distance: i32| -> Color {
-            match distance.abs() {
+            fit_color(match distance.abs() {
                 0 => Color::Rgb {
                     r: 150,
                     g: 255,
@@ -1413,7 +1339,7 @@ pub fn execute_terminal_command(
                     g: 70,
                     b: 50,
                 },
-            }
+            })
         };
 
         for (i, suggestion) in suggestions
@@ -1534,11 +1460,15 @@ pub fn execute_terminal_command(
                 }
             }
             _ => {
-                match Command::new(parts[0])
-                    .args(&parts[1..])
-                    .current_dir(&app_state.current_dir)
-                    .output()
-                {
+                let mut process = match app_state.config.custom_command(command) {
+                    Some(line) => shell_command(&line),
+                    None => {
+                        let mut process = Command::new(parts[0]);
+                        process.args(&parts[1..]);
+                        process
+                    }
+                };
+                match process.current_dir(&app_state.current_dir).output() {
                     Ok(output) => {
                         if !output.stdout.is_empty() {
                             String::from_utf8_lossy(&output.stdout)
@@ -1588,7 +1518,7 @@ pub fn execute_terminal_command(
     let mut command_buffer = String::new();
     let mut last_tab_word = String::new();
     let mut tab_count = 0;
-    while let Ok(Event::Key(key)) = event::read() {
+    while let Ok(Event::Key(key)) = read_event() {
         match key.code {
             KeyCode::Esc => {
                 execute!(stdout, cursor::Hide)?;
@@ -1615,7 +1545,7 @@ pub fn execute_terminal_command(
             }
             KeyCode::Tab => {
                 if autocomplete.suggestions.is_empty() {
-                    autocomplete.get_suggestions(&command_buffer, &app_state.current_dir)?;
+                    autocomplete.get_suggestions(&command_buffer, &app_state.current_dir, &app_state.config)?;
                 }
 
                 if !autocomplete.suggestions.is_empty() {
@@ -1663,15 +1593,15 @@ pub fn execute_terminal_command(
                 write!(stdout, ">")?;
                 execute!(
                     stdout,
-                    SetForegroundColor(Color::Rgb {
+                    SetForegroundColor(fit_color(Color::Rgb {
                         r: 200,
                         g: 255,
                         b: 200
-                    })
+                    }))
                 )?;
                 write!(stdout, " {}", command_buffer)?;
 
-                autocomplete.get_suggestions(&command_buffer, &app_state.current_dir)?;
+                autocomplete.get_suggestions(&command_buffer, &app_state.current_dir, &app_state.config)?;
                 display_suggestions(
                     stdout,
                     &autocomplete.suggestions,
@@ -1695,15 +1625,15 @@ pub fn execute_terminal_command(
                     write!(stdout, ">")?;
                     execute!(
                         stdout,
-                        SetForegroundColor(Color::Rgb {
+                        SetForegroundColor(fit_color(Color::Rgb {
                             r: 200,
                             g: 255,
                             b: 200
-                        })
+                        }))
                     )?;
                     write!(stdout, " {}", command_buffer)?;
 
-                    autocomplete.get_suggestions(&command_buffer, &app_state.current_dir)?;
+                    autocomplete.get_suggestions(&command_buffer, &app_state.current_dir, &app_state.config)?;
                     display_suggestions(
                         stdout,
                         &autocomplete.suggestions,
@@ -1900,210 +1830,6 @@ fn update_navigation_stack(app_state: &mut AppState, new_dir: PathBuf) {
     }
 }
 
-////////////////////////////////////////////////////////UNDOMANAGER////////////////////////////////////////////////////////////////////////////////
-pub enum UndoStorage {
-    Ram(Vec<u8>),
-    Disk(PathBuf),
-}
-pub struct UndoEntry {
-    pub operation: Operation,
-    pub storage: UndoStorage,
-    pub original_path: PathBuf,
-    pub size: usize,
-}
-
-pub enum Operation {
-    Create {
-        path: PathBuf,
-        is_directory: bool,
-        timestamp: SystemTime,
-    },
-    Delete {
-        timestamp: SystemTime,
-    },
-    Move {
-        old_path: PathBuf,
-        new_path: PathBuf,
-        // is_directory: bool,
-        timestamp: SystemTime,
-    },
-    Duplicate {
-        original_path: PathBuf,
-        new_path: PathBuf,
-        timestamp: SystemTime,
-    },
-    Copy {
-        source_path: PathBuf,
-        dest_path: PathBuf,
-        timestamp: SystemTime,
-    },
-    Rename {
-        old_name: String,
-        new_name: String,
-        path: PathBuf,
-        timestamp: SystemTime,
-    },
-}
-
-pub struct UndoManager {
-    pub entries: VecDeque<UndoEntry>,
-    pub ram_storage: Vec<u8>,
-    pub temp_dir: PathBuf,
-    pub ram_limit: usize,
-    pub disk_limit: u64,
-    pub total_disk_size: u64,
-    pub allow_disk_storage: bool,
-    pub move_operations: VecDeque<UndoEntry>,
-}
-
-impl UndoManager {
-    pub fn new(
-        temp_dir: PathBuf,
-        ram_limit: usize,
-        disk_limit: u64,
-        allow_disk_storage: bool,
-    ) -> io::Result<Self> {
-        fs::create_dir_all(&temp_dir)?;
-        Ok(UndoManager {
-            entries: VecDeque::new(),
-            ram_storage: Vec::with_capacity(ram_limit),
-            temp_dir,
-            ram_limit,
-            disk_limit,
-            total_disk_size: 0,
-            allow_disk_storage,
-            move_operations: VecDeque::new(),
-        })
-    }
-
-    pub fn add_move_operation(&mut self, old_path: PathBuf, new_path: PathBuf) -> io::Result<()> {
-        let entry = UndoEntry {
-            operation: Operation::Move {
-                old_path: old_path.clone(),
-                new_path,
-                timestamp: SystemTime::now(),
-            },
-            storage: UndoStorage::Ram(Vec::new()), // Move operations don't need storage, unless to big i guess, then we might need a temp folder.
-            original_path: old_path,
-            size: 0, // Size is not relevant for move operations
-        };
-        self.add_tome_entry(entry)
-    }
-
-    pub fn undo_flirty_move(&mut self) -> Option<UndoEntry> {
-        self.move_operations.pop_back()
-    }
-
-    pub fn add_tome_entry(&mut self, entry: UndoEntry) -> io::Result<()> {
-        if entry.size <= self.ram_limit - self.ram_storage.len() {
-            if let UndoStorage::Ram(data) = &entry.storage {
-                self.ram_storage.extend(data);
-            }
-            self.entries.push_back(entry);
-        } else if self.allow_disk_storage {
-            let disk_path = if let UndoStorage::Ram(data) = &entry.storage {
-                Some(self.store_to_disk(data)?)
-            } else {
-                None
-            };
-
-            if let Some(path) = disk_path {
-                self.total_disk_size += entry.size as u64;
-                self.entries.push_back(UndoEntry {
-                    storage: UndoStorage::Disk(path),
-                    ..entry
-                });
-            } else {
-                self.entries.push_back(entry);
-            }
-
-            while self.total_disk_size > self.disk_limit {
-                if let Some(removed_entry) = self.entries.pop_front() {
-                    self.remove_entry(removed_entry)?;
-                }
-            }
-        } else {
-            return Err(io::Error::new(
-                io::ErrorKind::Other,
-                "Undo storage limit reached",
-            ));
-        }
-        Ok(())
-    }
-
-    pub fn undo_haunting_regret(&mut self) -> Option<UndoEntry> {
-        self.entries.pop_back().map(|entry| match entry.storage {
-            UndoStorage::Ram(_) => {
-                let start = self.ram_storage.len().saturating_sub(entry.size);
-                let data = self.ram_storage.split_off(start);
-                UndoEntry {
-                    storage: UndoStorage::Ram(data),
-                    ..entry
-                }
-            }
-            UndoStorage::Disk(_) => {
-                self.total_disk_size = self.total_disk_size.saturating_sub(entry.size as u64);
-                entry
-            }
-        })
-    }
-    fn store_to_disk(&self, data: &[u8]) -> io::Result<PathBuf> {
-        let file_name = format!(
-            "undo_{}",
-            SystemTime::now()
-                .duration_since(UNIX_EPOCH)
-                .unwrap()
-                .as_micros()
-        );
-        let path = self.temp_dir.join(file_name);
-        let mut file = File::create(&path)?;
-        file.write_all(data)?;
-        Ok(path)
-    }
-
-    fn remove_entry(&mut self, entry: UndoEntry) -> io::Result<()> {
-        match entry.storage {
-            UndoStorage::Ram(_) => {
-                self.ram_storage
-                    .truncate(self.ram_storage.len() - entry.size);
-            }
-            UndoStorage::Disk(path) => {
-                fs::remove_file(path)?;
-                self.total_disk_size -= entry.size as u64;
-            }
-        }
-        Ok(())
-    }
-    pub fn undo_by_type(&mut self, operation_type: &str) -> Option<UndoEntry> {
-        let index = self.entries.iter().rposition(|entry| {
-            matches!(
-                (&entry.operation, operation_type),
-                (Operation::Delete { .. }, "delete")
-                    | (Operation::Move { .. }, "move")
-                    | (Operation::Duplicate { .. }, "duplicate")
-                    | (Operation::Copy { .. }, "copy")
-                    | (Operation::Rename { .. }, "rename")
-            )
-        })?;
-
-        let entry = self.entries.remove(index).unwrap();
-
-        match entry.storage {
-            UndoStorage::Ram(_) => {
-                let start = self.ram_storage.len().saturating_sub(entry.size);
-                let data = self.ram_storage.split_off(start);
-                Some(UndoEntry {
-                    storage: UndoStorage::Ram(data),
-                    ..entry
-                })
-            }
-            UndoStorage::Disk(_) => {
-                self.total_disk_size = self.total_disk_size.saturating_sub(entry.size as u64);
-                Some(entry)
-            }
-        }
-    }
-}
 pub fn set_color_rules(app_state: &mut AppState, stdout: &mut impl Write) -> io::Result<()> {
     let (width, height) = size()?;
     let nav_width = width / 2;
@@ -2181,7 +1907,7 @@ pub fn set_color_rules(app_state: &mut AppState, stdout: &mut impl Write) -> io:
         )?;
         stdout.flush()?;
 
-        if let Event::Key(key) = event::read()? {
+        if let Event::Key(key) = read_event()? {
             match key.code {
                 KeyCode::Char('1') => {
                     edit_color_rule(app_state, stdout, MarkerColor::Red).expect("color_error")
@@ -2220,7 +1946,7 @@ pub fn set_color_rules(app_state: &mut AppState, stdout: &mut impl Write) -> io:
     }
     let _ = clear_nav();
     let _ = clear_preview();
-    app_state.config.save_config()?;
+    app_state.save_config()?;
     Ok(())
 }
 
@@ -2292,7 +2018,7 @@ fn edit_color_rule(
         writeln!(stdout, "Press enter or escape to go back: \r")?;
         stdout.flush()?;
 
-        if let Event::Key(key) = event::read()? {
+        if let Event::Key(key) = read_event()? {
             match key.code {
                 KeyCode::Char('1') => rule.allow_delete = !rule.allow_delete,
                 KeyCode::Char('2') => rule.allow_rename = !rule.allow_rename,
@@ -2324,7 +2050,7 @@ impl Autocomplete {
         }
     }
 
-    fn get_suggestions(&mut self, input: &str, current_dir: &Path) -> io::Result<()> {
+    fn get_suggestions(&mut self, input: &str, current_dir: &Path, config: &Config) -> io::Result<()> {
         self.suggestions.clear();
         let words: Vec<&str> = input.split_whitespace().collect();
         let current_word = words.last().unwrap_or(&"").to_lowercase();
@@ -2337,6 +2063,11 @@ impl Autocomplete {
                     self.suggestions.push((*cmd).to_string());
                 }
             }
+            for name in config.custom_commands.keys() {
+                if name.to_lowercase().starts_with(&current_word) {
+                    self.suggestions.push(name.clone());
+                }
+            }
 
             if let Ok(path) = std::env::var("PATH") {
                 for path_entry in path.split(':') {
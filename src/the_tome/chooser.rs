@@ -0,0 +1,94 @@
+/*
+* Stygian Sift - A Terminal-based File Manager
+ * Copyright (C) 2024 Maui The Magnificent (Charon)
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published
+ * by the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+ * Contact: Maui_The_Magnificent@proton.me
+ * Project repository: https://github.com/Mauitron/StygianSift.git
+ */
+
+// StygianSift as a picker for editors and scripts. With --choose-files or
+// --choose-dir, Enter ends the browser with a choice that main writes out,
+// one path per line. "-" means stdout, written once the screen is restored.
+
+use super::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChooseMode {
+    Files,
+    Directory,
+}
+
+#[derive(Debug, Clone)]
+pub struct Chooser {
+    pub mode: ChooseMode,
+    pub output: PathBuf,
+}
+
+impl Chooser {
+    pub fn new(mode: ChooseMode, output: PathBuf) -> Self {
+        Chooser { mode, output }
+    }
+
+    // What Enter on entry picks. The multi-selection wins when it has anything
+    // of the right kind. None lets Enter open the entry as usual, which is how
+    // directories are entered while choosing files.
+    pub fn pick(&self, app_state: &AppState, entry: Option<&FileEntry>) -> Option<Vec<PathBuf>> {
+        // Entries inside an archive only exist in the view.
+        if app_state.in_archive() {
+            return None;
+        }
+        let wanted = |path: &Path| match self.mode {
+            ChooseMode::Files => !path.is_dir(),
+            ChooseMode::Directory => path.is_dir(),
+        };
+        let mut selected: Vec<PathBuf> = app_state
+            .multiple_selected_files
+            .iter()
+            .flatten()
+            .filter(|path| wanted(path))
+            .cloned()
+            .collect();
+        if !selected.is_empty() {
+            selected.sort();
+            return Some(selected);
+        }
+        match (self.mode, entry) {
+            (ChooseMode::Files, Some(entry)) if entry.file_type != FileType::Directory => {
+                Some(vec![entry.path.clone()])
+            }
+            (ChooseMode::Files, _) => None,
+            (ChooseMode::Directory, Some(entry)) if entry.file_type == FileType::Directory => {
+                Some(vec![entry.path.clone()])
+            }
+            (ChooseMode::Directory, _) => Some(vec![app_state.current_dir.clone()]),
+        }
+    }
+}
+
+pub fn write_paths(output: &Path, paths: &[PathBuf]) -> io::Result<()> {
+    let mut text = String::new();
+    for path in paths {
+        text.push_str(&path.to_string_lossy());
+        text.push('\n');
+    }
+    if output == Path::new("-") {
+        let mut stdout = io::stdout();
+        stdout.write_all(text.as_bytes())?;
+        stdout.flush()
+    } else {
+        fs::write(output, text)
+    }
+}
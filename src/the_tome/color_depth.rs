@@ -0,0 +1,206 @@
+/*
+* Stygian Sift - A Terminal-based File Manager
+ * Copyright (C) 2024 Maui The Magnificent (Charon)
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published
+ * by the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+ * Contact: Maui_The_Magnificent@proton.me
+ * Project repository: https://github.com/Mauitron/StygianSift.git
+ */
+
+use super::*;
+use std::sync::RwLock;
+//////////////////////////////////////////////////////Color Depth//////////////////////////////////////////////////////////////////////
+// The fades and marker colors are worked out in RGB. Terminals that cannot
+// show 24-bit color get the nearest entry of the 256 or 16 color palette
+// instead, and NO_COLOR turns colors off altogether.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorDepth {
+    TrueColor,
+    Ansi256,
+    Ansi16,
+    Monochrome,
+}
+
+// The 16 basic colors, as xterm draws them by default.
+const BASIC_COLORS: [(Color, (u8, u8, u8)); 16] = [
+    (Color::Black, (0, 0, 0)),
+    (Color::DarkRed, (205, 0, 0)),
+    (Color::DarkGreen, (0, 205, 0)),
+    (Color::DarkYellow, (205, 205, 0)),
+    (Color::DarkBlue, (0, 0, 238)),
+    (Color::DarkMagenta, (205, 0, 205)),
+    (Color::DarkCyan, (0, 205, 205)),
+    (Color::Grey, (229, 229, 229)),
+    (Color::DarkGrey, (127, 127, 127)),
+    (Color::Red, (255, 0, 0)),
+    (Color::Green, (0, 255, 0)),
+    (Color::Yellow, (255, 255, 0)),
+    (Color::Blue, (92, 92, 255)),
+    (Color::Magenta, (255, 0, 255)),
+    (Color::Cyan, (0, 255, 255)),
+    (Color::White, (255, 255, 255)),
+];
+
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+impl ColorDepth {
+    // `auto` (or anything unknown) leaves it to the environment.
+    pub fn from_setting(setting: &str) -> Option<Self> {
+        match setting.trim().to_ascii_lowercase().as_str() {
+            "truecolor" | "24bit" | "24-bit" => Some(ColorDepth::TrueColor),
+            "256" | "256color" => Some(ColorDepth::Ansi256),
+            "16" | "16color" | "8" => Some(ColorDepth::Ansi16),
+            "none" | "off" | "monochrome" => Some(ColorDepth::Monochrome),
+            _ => None,
+        }
+    }
+
+    pub fn detect(setting: &str) -> Self {
+        Self::from_setting(setting).unwrap_or_else(Self::from_env)
+    }
+
+    fn from_env() -> Self {
+        let var = |name: &str| env::var(name).unwrap_or_default();
+        // https://no-color.org: set and not empty.
+        if !var("NO_COLOR").is_empty() {
+            return ColorDepth::Monochrome;
+        }
+        let colorterm = var("COLORTERM").to_ascii_lowercase();
+        if colorterm == "truecolor" || colorterm == "24bit" {
+            return ColorDepth::TrueColor;
+        }
+        let term = var("TERM").to_ascii_lowercase();
+        if term.is_empty() {
+            // Windows Terminal and recent conhost do not set TERM at all.
+            return if cfg!(windows) {
+                ColorDepth::TrueColor
+            } else {
+                ColorDepth::Ansi16
+            };
+        }
+        if term == "dumb" {
+            ColorDepth::Monochrome
+        } else if term.ends_with("-direct")
+            || ["kitty", "alacritty", "wezterm", "foot", "contour", "ghostty"]
+                .iter()
+                .any(|name| term.contains(name))
+        {
+            ColorDepth::TrueColor
+        } else if term.contains("256color") || term == "xterm" {
+            ColorDepth::Ansi256
+        } else {
+            // The linux console, plain screen/tmux, vt100 and friends.
+            ColorDepth::Ansi16
+        }
+    }
+}
+
+static COLOR_DEPTH: RwLock<Option<ColorDepth>> = RwLock::new(None);
+
+// Like the theme, colors are made in places that never see the config.
+pub fn set_color_depth(depth: ColorDepth) {
+    if let Ok(mut current) = COLOR_DEPTH.write() {
+        *current = Some(depth);
+    }
+    // crossterm already skips colors when NO_COLOR is set, an explicit
+    // depth in the config wins over that either way.
+    crossterm::style::force_color_output(depth != ColorDepth::Monochrome);
+}
+
+pub fn color_depth() -> ColorDepth {
+    if let Some(depth) = COLOR_DEPTH.read().ok().and_then(|current| *current) {
+        return depth;
+    }
+    let depth = ColorDepth::detect("auto");
+    if let Ok(mut current) = COLOR_DEPTH.write() {
+        current.get_or_insert(depth);
+    }
+    depth
+}
+
+// What a color comes out as on the current terminal. Named colors are left
+// alone, the terminal knows best what those look like.
+pub fn fit_color(color: Color) -> Color {
+    let rgb = match color {
+        Color::Rgb { r, g, b } => (r, g, b),
+        Color::AnsiValue(value) => ansi_to_rgb(value),
+        other => return other,
+    };
+    match color_depth() {
+        ColorDepth::TrueColor | ColorDepth::Monochrome => color,
+        ColorDepth::Ansi256 => match color {
+            Color::AnsiValue(_) => color,
+            _ => Color::AnsiValue(nearest_ansi256(rgb)),
+        },
+        ColorDepth::Ansi16 => nearest_basic(rgb),
+    }
+}
+
+pub fn ansi_to_rgb(value: u8) -> (u8, u8, u8) {
+    match value {
+        0..=15 => BASIC_COLORS[value as usize].1,
+        16..=231 => {
+            let index = value - 16;
+            (
+                CUBE_LEVELS[(index / 36) as usize],
+                CUBE_LEVELS[(index / 6 % 6) as usize],
+                CUBE_LEVELS[(index % 6) as usize],
+            )
+        }
+        _ => {
+            let level = 8 + (value - 232) * 10;
+            (level, level, level)
+        }
+    }
+}
+
+// Green counts for more than red and red for more than blue, close enough
+// to how far apart two colors look without going through a color space.
+fn distance(a: (u8, u8, u8), b: (u8, u8, u8)) -> u32 {
+    let diff = |x: u8, y: u8| (x as i32 - y as i32).pow(2) as u32;
+    2 * diff(a.0, b.0) + 4 * diff(a.1, b.1) + 3 * diff(a.2, b.2)
+}
+
+fn nearest_ansi256(rgb: (u8, u8, u8)) -> u8 {
+    let cube_index = |v: u8| match v {
+        0..=47 => 0,
+        48..=114 => 1,
+        _ => (v - 35) / 40,
+    };
+    let (r, g, b) = (cube_index(rgb.0), cube_index(rgb.1), cube_index(rgb.2));
+    let cube = 16 + 36 * r + 6 * g + b;
+
+    let average = (rgb.0 as u16 + rgb.1 as u16 + rgb.2 as u16) / 3;
+    let gray = if average < 8 {
+        232
+    } else {
+        232 + ((average - 8) / 10).min(23) as u8
+    };
+
+    if distance(rgb, ansi_to_rgb(gray)) < distance(rgb, ansi_to_rgb(cube)) {
+        gray
+    } else {
+        cube
+    }
+}
+
+fn nearest_basic(rgb: (u8, u8, u8)) -> Color {
+    BASIC_COLORS
+        .iter()
+        .min_by_key(|(_, basic)| distance(rgb, *basic))
+        .map(|(color, _)| *color)
+        .unwrap_or(Color::Reset)
+}
@@ -0,0 +1,84 @@
+/*
+* Stygian Sift - A Terminal-based File Manager
+ * Copyright (C) 2024 Maui The Magnificent (Charon)
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published
+ * by the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+ * Contact: Maui_The_Magnificent@proton.me
+ * Project repository: https://github.com/Mauitron/StygianSift.git
+ */
+
+// The ":" line of the command mode. Typed keys go into the line and what the
+// command mode binds runs instead: Enter runs the line, NormalMode leaves it
+// and MoveUp and MoveDown go through the lines run before.
+
+use super::*;
+
+pub fn read_command_line(app_state: &mut AppState) -> io::Result<Option<String>> {
+    let trie = app_state.config.key_trie(Mode::Command);
+    let history_len = app_state.command_history.len();
+    let mut back = history_len;
+    let mut line = String::new();
+    loop {
+        interaction_field!(":{}", line)?;
+        let Event::Key(key) = read_event()? else {
+            continue;
+        };
+        if key.kind == KeyEventKind::Release {
+            continue;
+        }
+        match trie.find(&[key.into()]).and_then(KeyTrie::action) {
+            Some(Action::Enter) => break,
+            Some(Action::NormalMode) => {
+                clear_interaction_field()?;
+                return Ok(None);
+            }
+            Some(Action::MoveUp) => {
+                back = back.saturating_sub(1);
+                if let Some(earlier) = app_state.command_history.get(back) {
+                    line = earlier.clone();
+                }
+            }
+            Some(Action::MoveDown) => {
+                back = (back + 1).min(history_len);
+                line = app_state
+                    .command_history
+                    .get(back)
+                    .cloned()
+                    .unwrap_or_default();
+            }
+            _ => match key.code {
+                KeyCode::Char(c) => line.push(c),
+                // Like vim, backspace on an empty line leaves it.
+                KeyCode::Backspace if line.is_empty() => {
+                    clear_interaction_field()?;
+                    return Ok(None);
+                }
+                KeyCode::Backspace => {
+                    line.pop();
+                }
+                _ => {}
+            },
+        }
+    }
+    clear_interaction_field()?;
+    let line = line.trim().to_string();
+    if line.is_empty() {
+        return Ok(None);
+    }
+    if app_state.command_history.last() != Some(&line) {
+        app_state.command_history.push(line.clone());
+    }
+    Ok(Some(line))
+}
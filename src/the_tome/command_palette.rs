@@ -0,0 +1,103 @@
+/*
+* Stygian Sift - A Terminal-based File Manager
+ * Copyright (C) 2024 Maui The Magnificent (Charon)
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published
+ * by the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+ * Contact: Maui_The_Magnificent@proton.me
+ * Project repository: https://github.com/Mauitron/StygianSift.git
+ */
+
+// Draws the command palette over the preview pane. Typing narrows it down,
+// Up and Down pick, Enter runs and Esc leaves.
+
+use super::*;
+
+pub fn open_command_palette(
+    app_state: &mut AppState,
+    stdout: &mut impl Write,
+) -> io::Result<Option<PaletteCommand>> {
+    let menu: Vec<String> = ContextMenu::new((0, 0))
+        .items
+        .into_iter()
+        .map(|item| item.label)
+        .collect();
+    let entries = palette_entries(&app_state.config, &menu);
+    let (width, height) = size()?;
+    let left = width - width / 2 + 1;
+    let room = (width / 2).saturating_sub(12) as usize;
+    let rows = height.saturating_sub(20).max(1) as usize;
+    let mut query = String::new();
+    let mut chosen = 0;
+    loop {
+        let ranked = rank_palette(&entries, &query, &app_state.recent_commands);
+        chosen = chosen.min(ranked.len().saturating_sub(1));
+        let _ = clear_preview();
+        queue!(stdout, MoveTo(left, 4))?;
+        write!(stdout, "{} {}", "Command:".green().bold(), query)?;
+        let first = chosen.saturating_sub(rows - 1);
+        for (row, entry) in ranked.iter().enumerate().skip(first).take(rows) {
+            let line: String = format!(
+                "{:<22} {:<10} {}",
+                entry.name, entry.keys, entry.description
+            )
+            .chars()
+            .take(room)
+            .collect();
+            queue!(stdout, MoveTo(left, 6 + (row - first) as u16))?;
+            if row == chosen {
+                write!(stdout, "{}", line.green().on_dark_grey())?;
+            } else {
+                write!(stdout, "{}", line)?;
+            }
+        }
+        if ranked.is_empty() {
+            queue!(stdout, MoveTo(left, 6))?;
+            write!(stdout, "{}", "Nothing matches".italic())?;
+        }
+        stdout.flush()?;
+
+        let Event::Key(key) = read_event()? else {
+            continue;
+        };
+        if key.kind == KeyEventKind::Release {
+            continue;
+        }
+        match key.code {
+            KeyCode::Esc => {
+                let _ = clear_preview();
+                return Ok(None);
+            }
+            KeyCode::Enter => {
+                let _ = clear_preview();
+                let command = ranked.get(chosen).map(|entry| entry.command.clone());
+                if let Some(command) = &command {
+                    remember_command(&mut app_state.recent_commands, command.clone());
+                }
+                return Ok(command);
+            }
+            KeyCode::Up => chosen = chosen.saturating_sub(1),
+            KeyCode::Down => chosen += 1,
+            KeyCode::Backspace => {
+                query.pop();
+                chosen = 0;
+            }
+            KeyCode::Char(c) => {
+                query.push(c);
+                chosen = 0;
+            }
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,842 @@
+/*
+* Stygian Sift - A Terminal-based File Manager
+ * Copyright (C) 2024 Maui The Magnificent (Charon)
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published
+ * by the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+ * Contact: Maui_The_Magnificent@proton.me
+ * Project repository: https://github.com/Mauitron/StygianSift.git
+ */
+
+use super::*;
+//////////////////////////////////////////////////////Compression////////////////////////////////////////////////////////////////////
+// DEFLATE (RFC 1951) and the gzip wrapper (RFC 1952), enough to read the
+// archives we browse and to write the ones we pack. Written by hand to avoid
+// another dependency.
+
+fn corrupt(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+pub fn crc32(data: &[u8]) -> u32 {
+    crc32_update(0, data)
+}
+
+pub fn crc32_update(crc: u32, data: &[u8]) -> u32 {
+    static TABLE: std::sync::OnceLock<[u32; 256]> = std::sync::OnceLock::new();
+    let table = TABLE.get_or_init(|| {
+        let mut table = [0u32; 256];
+        for (i, slot) in table.iter_mut().enumerate() {
+            let mut c = i as u32;
+            for _ in 0..8 {
+                c = if c & 1 != 0 { 0xEDB8_8320 ^ (c >> 1) } else { c >> 1 };
+            }
+            *slot = c;
+        }
+        table
+    });
+
+    let mut crc = !crc;
+    for &byte in data {
+        crc = table[((crc ^ byte as u32) & 0xFF) as usize] ^ (crc >> 8);
+    }
+    !crc
+}
+
+struct BitReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+    bit_buf: u64,
+    bit_count: u32,
+    // Zero bits added past the end so that peeking never has to fail.
+    padded: u32,
+}
+
+impl<'a> BitReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self {
+            data,
+            pos: 0,
+            bit_buf: 0,
+            bit_count: 0,
+            padded: 0,
+        }
+    }
+
+    fn refill(&mut self) {
+        while self.bit_count <= 56 {
+            let byte = match self.data.get(self.pos) {
+                Some(&byte) => {
+                    self.pos += 1;
+                    byte
+                }
+                None => {
+                    self.padded += 8;
+                    0
+                }
+            };
+            self.bit_buf |= (byte as u64) << self.bit_count;
+            self.bit_count += 8;
+        }
+    }
+
+    fn peek(&mut self, n: u32) -> u32 {
+        if self.bit_count < n {
+            self.refill();
+        }
+        (self.bit_buf & ((1u64 << n) - 1)) as u32
+    }
+
+    fn consume(&mut self, n: u32) -> io::Result<()> {
+        self.bit_buf >>= n;
+        self.bit_count -= n;
+        if self.bit_count < self.padded {
+            return Err(corrupt("unexpected end of deflate stream"));
+        }
+        Ok(())
+    }
+
+    fn bits(&mut self, n: u32) -> io::Result<u32> {
+        let value = self.peek(n);
+        self.consume(n)?;
+        Ok(value)
+    }
+
+    fn align_to_byte(&mut self) -> io::Result<()> {
+        self.consume(self.bit_count % 8)
+    }
+
+    // Bytes of input that have actually been used, once aligned.
+    fn bytes_consumed(&self) -> usize {
+        self.pos - ((self.bit_count - self.padded) / 8) as usize
+    }
+}
+
+struct Huffman {
+    table: Vec<(u16, u8)>,
+    bits: u32,
+}
+
+impl Huffman {
+    fn new(lengths: &[u8]) -> io::Result<Self> {
+        let bits = lengths.iter().copied().max().unwrap_or(0).max(1) as u32;
+        let mut count = [0u16; 16];
+        for &len in lengths {
+            count[len as usize] += 1;
+        }
+        count[0] = 0;
+
+        let mut next_code = [0u32; 16];
+        let mut code = 0u32;
+        for len in 1..16 {
+            code = (code + count[len - 1] as u32) << 1;
+            next_code[len] = code;
+        }
+
+        let mut table = vec![(0u16, 0u8); 1 << bits];
+        for (symbol, &len) in lengths.iter().enumerate() {
+            if len == 0 {
+                continue;
+            }
+            let code = next_code[len as usize];
+            next_code[len as usize] += 1;
+            if code >= 1 << len {
+                return Err(corrupt("over-subscribed huffman code"));
+            }
+            let mut reversed = 0usize;
+            for i in 0..len {
+                reversed |= (((code >> i) & 1) as usize) << (len - 1 - i);
+            }
+            let mut index = reversed;
+            while index < table.len() {
+                table[index] = (symbol as u16, len);
+                index += 1 << len;
+            }
+        }
+        Ok(Self { table, bits })
+    }
+
+    fn decode(&self, reader: &mut BitReader) -> io::Result<u16> {
+        let (symbol, len) = self.table[reader.peek(self.bits) as usize];
+        if len == 0 {
+            return Err(corrupt("invalid huffman code"));
+        }
+        reader.consume(len as u32)?;
+        Ok(symbol)
+    }
+}
+
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+const DIST_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DIST_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+const CODE_LENGTH_ORDER: [usize; 19] = [
+    16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+];
+
+fn fixed_tables() -> io::Result<(Huffman, Huffman)> {
+    let mut lengths = [0u8; 288];
+    lengths[..144].fill(8);
+    lengths[144..256].fill(9);
+    lengths[256..280].fill(7);
+    lengths[280..].fill(8);
+    Ok((Huffman::new(&lengths)?, Huffman::new(&[5; 30])?))
+}
+
+fn dynamic_tables(reader: &mut BitReader) -> io::Result<(Huffman, Huffman)> {
+    let literal_count = reader.bits(5)? as usize + 257;
+    let distance_count = reader.bits(5)? as usize + 1;
+    let code_length_count = reader.bits(4)? as usize + 4;
+
+    let mut code_lengths = [0u8; 19];
+    for &index in CODE_LENGTH_ORDER.iter().take(code_length_count) {
+        code_lengths[index] = reader.bits(3)? as u8;
+    }
+    let code_length_table = Huffman::new(&code_lengths)?;
+
+    let mut lengths = vec![0u8; literal_count + distance_count];
+    let mut i = 0;
+    while i < lengths.len() {
+        let symbol = code_length_table.decode(reader)?;
+        let (value, repeat) = match symbol {
+            0..=15 => (symbol as u8, 1),
+            16 => {
+                let previous = *lengths[..i]
+                    .last()
+                    .ok_or_else(|| corrupt("repeat with no previous length"))?;
+                (previous, 3 + reader.bits(2)? as usize)
+            }
+            17 => (0, 3 + reader.bits(3)? as usize),
+            _ => (0, 11 + reader.bits(7)? as usize),
+        };
+        if i + repeat > lengths.len() {
+            return Err(corrupt("code lengths overflow"));
+        }
+        lengths[i..i + repeat].fill(value);
+        i += repeat;
+    }
+    if lengths[256] == 0 {
+        return Err(corrupt("missing end of block code"));
+    }
+
+    Ok((
+        Huffman::new(&lengths[..literal_count])?,
+        Huffman::new(&lengths[literal_count..])?,
+    ))
+}
+
+// Returns the inflated data and how many input bytes the stream used.
+// With a limit, decoding stops early once that much output exists.
+pub fn inflate(data: &[u8], limit: Option<usize>) -> io::Result<(Vec<u8>, usize)> {
+    let mut reader = BitReader::new(data);
+    let mut out: Vec<u8> = Vec::with_capacity(data.len().saturating_mul(3).min(1 << 24));
+    let limit = limit.unwrap_or(usize::MAX);
+
+    loop {
+        let last = reader.bits(1)? == 1;
+        match reader.bits(2)? {
+            0 => {
+                reader.align_to_byte()?;
+                let len = reader.bits(16)?;
+                let nlen = reader.bits(16)?;
+                if len != !nlen & 0xFFFF {
+                    return Err(corrupt("stored block length mismatch"));
+                }
+                for _ in 0..len {
+                    out.push(reader.bits(8)? as u8);
+                }
+            }
+            kind @ (1 | 2) => {
+                let (literals, distances) = if kind == 1 {
+                    fixed_tables()?
+                } else {
+                    dynamic_tables(&mut reader)?
+                };
+                loop {
+                    let symbol = literals.decode(&mut reader)? as usize;
+                    if symbol < 256 {
+                        out.push(symbol as u8);
+                    } else if symbol == 256 {
+                        break;
+                    } else {
+                        let index = symbol - 257;
+                        if index >= LENGTH_BASE.len() {
+                            return Err(corrupt("invalid length symbol"));
+                        }
+                        let length = LENGTH_BASE[index] as usize
+                            + reader.bits(LENGTH_EXTRA[index] as u32)? as usize;
+                        let index = distances.decode(&mut reader)? as usize;
+                        if index >= DIST_BASE.len() {
+                            return Err(corrupt("invalid distance symbol"));
+                        }
+                        let distance = DIST_BASE[index] as usize
+                            + reader.bits(DIST_EXTRA[index] as u32)? as usize;
+                        if distance > out.len() {
+                            return Err(corrupt("distance reaches before start of output"));
+                        }
+                        let start = out.len() - distance;
+                        for i in 0..length {
+                            out.push(out[start + i]);
+                        }
+                    }
+                    if out.len() >= limit {
+                        return Ok((out, reader.bytes_consumed()));
+                    }
+                }
+            }
+            _ => return Err(corrupt("invalid block type")),
+        }
+        if last || out.len() >= limit {
+            break;
+        }
+    }
+
+    reader.align_to_byte()?;
+    Ok((out, reader.bytes_consumed()))
+}
+
+pub fn is_gzip(data: &[u8]) -> bool {
+    data.len() >= 3 && data[..3] == [0x1F, 0x8B, 0x08]
+}
+
+// Handles concatenated members, which is what `cat a.gz b.gz` produces.
+pub fn gunzip(data: &[u8], limit: Option<usize>) -> io::Result<Vec<u8>> {
+    let mut out = Vec::new();
+    let mut pos = 0;
+
+    while pos < data.len() && is_gzip(&data[pos..]) {
+        let header = &data[pos..];
+        if header.len() < 10 {
+            return Err(corrupt("truncated gzip header"));
+        }
+        let flags = header[3];
+        let mut offset = 10;
+        if flags & 0x04 != 0 {
+            let extra = header
+                .get(offset..offset + 2)
+                .ok_or_else(|| corrupt("truncated gzip header"))?;
+            offset += 2 + u16::from_le_bytes([extra[0], extra[1]]) as usize;
+        }
+        for flag in [0x08, 0x10] {
+            if flags & flag != 0 {
+                let end = header
+                    .get(offset..)
+                    .and_then(|rest| rest.iter().position(|&b| b == 0))
+                    .ok_or_else(|| corrupt("truncated gzip header"))?;
+                offset += end + 1;
+            }
+        }
+        if flags & 0x02 != 0 {
+            offset += 2;
+        }
+        let body = header
+            .get(offset..)
+            .ok_or_else(|| corrupt("truncated gzip header"))?;
+
+        let remaining = limit.map(|limit| limit.saturating_sub(out.len()));
+        let (member, used) = inflate(body, remaining)?;
+        let complete = remaining.is_none_or(|remaining| member.len() < remaining);
+        out.extend_from_slice(&member);
+        if !complete {
+            out.truncate(limit.unwrap_or(usize::MAX));
+            return Ok(out);
+        }
+
+        let trailer = body
+            .get(used..used + 8)
+            .ok_or_else(|| corrupt("truncated gzip trailer"))?;
+        let expected = u32::from_le_bytes([trailer[0], trailer[1], trailer[2], trailer[3]]);
+        if crc32(&member) != expected {
+            return Err(corrupt("gzip checksum mismatch"));
+        }
+        pos += offset + used + 8;
+    }
+
+    if pos == 0 {
+        return Err(corrupt("not a gzip stream"));
+    }
+    Ok(out)
+}
+
+///////////////////////////////////////////////////////Encoding//////////////////////////////////////////////////////////////////////
+const WINDOW_SIZE: usize = 32 * 1024;
+const BLOCK_SIZE: usize = 128 * 1024;
+const HASH_BITS: usize = 15;
+const MIN_MATCH: usize = 3;
+const MAX_MATCH: usize = 258;
+const MAX_CHAIN: usize = 64;
+const NICE_MATCH: usize = 128;
+
+struct BitWriter {
+    out: Vec<u8>,
+    bit_buf: u64,
+    bit_count: u32,
+}
+
+impl BitWriter {
+    fn new() -> Self {
+        Self {
+            out: Vec::new(),
+            bit_buf: 0,
+            bit_count: 0,
+        }
+    }
+
+    fn bits(&mut self, value: u32, n: u32) {
+        self.bit_buf |= (value as u64) << self.bit_count;
+        self.bit_count += n;
+        while self.bit_count >= 8 {
+            self.out.push(self.bit_buf as u8);
+            self.bit_buf >>= 8;
+            self.bit_count -= 8;
+        }
+    }
+
+    fn align_to_byte(&mut self) {
+        if self.bit_count > 0 {
+            self.bits(0, 8 - self.bit_count);
+        }
+    }
+}
+
+enum Token {
+    Literal(u8),
+    Match { length: u16, distance: u16 },
+}
+
+fn length_code(length: usize) -> usize {
+    LENGTH_BASE.iter().rposition(|&base| base as usize <= length).unwrap_or(0)
+}
+
+fn distance_code(distance: usize) -> usize {
+    DIST_BASE.iter().rposition(|&base| base as usize <= distance).unwrap_or(0)
+}
+
+// Plain Huffman lengths, halving the counts until the longest code fits.
+fn code_lengths(freqs: &[u32], max_bits: u8) -> Vec<u8> {
+    use std::cmp::Reverse;
+    use std::collections::BinaryHeap;
+
+    let mut freqs = freqs.to_vec();
+    // A tree needs two leaves, decoders reject a lone code in some trees.
+    let used = freqs.iter().filter(|&&f| f > 0).count();
+    for slot in freqs.iter_mut().filter(|f| **f == 0).take(2usize.saturating_sub(used)) {
+        *slot = 1;
+    }
+
+    loop {
+        let mut heap = BinaryHeap::new();
+        let mut parent: Vec<usize> = vec![usize::MAX; freqs.len()];
+        for (symbol, &freq) in freqs.iter().enumerate() {
+            if freq > 0 {
+                heap.push(Reverse((freq as u64, symbol)));
+            }
+        }
+        while heap.len() > 1 {
+            let Reverse((a_freq, a)) = heap.pop().unwrap_or_default();
+            let Reverse((b_freq, b)) = heap.pop().unwrap_or_default();
+            let node = parent.len();
+            parent.push(usize::MAX);
+            parent[a] = node;
+            parent[b] = node;
+            heap.push(Reverse((a_freq + b_freq, node)));
+        }
+
+        let mut lengths = vec![0u8; freqs.len()];
+        let mut too_long = false;
+        for (symbol, length) in lengths.iter_mut().enumerate() {
+            if freqs[symbol] == 0 {
+                continue;
+            }
+            let mut depth = 0;
+            let mut node = symbol;
+            while parent[node] != usize::MAX {
+                node = parent[node];
+                depth += 1;
+            }
+            too_long |= depth > max_bits as usize;
+            *length = depth as u8;
+        }
+        if !too_long {
+            return lengths;
+        }
+        for freq in freqs.iter_mut().filter(|f| **f > 0) {
+            *freq = (*freq).div_ceil(2);
+        }
+    }
+}
+
+// Canonical codes, already bit reversed since deflate writes them backwards.
+fn canonical_codes(lengths: &[u8]) -> Vec<u16> {
+    let mut count = [0u16; 16];
+    for &length in lengths {
+        count[length as usize] += 1;
+    }
+    count[0] = 0;
+    let mut next = [0u16; 16];
+    let mut code = 0u16;
+    for bits in 1..16 {
+        code = (code + count[bits - 1]) << 1;
+        next[bits] = code;
+    }
+    lengths
+        .iter()
+        .map(|&length| {
+            if length == 0 {
+                return 0;
+            }
+            let code = next[length as usize];
+            next[length as usize] += 1;
+            code.reverse_bits() >> (16 - length)
+        })
+        .collect()
+}
+
+// Run length encoding of the code lengths with symbols 16, 17 and 18.
+fn run_length_codes(lengths: &[u8]) -> Vec<(u8, u8)> {
+    let mut codes = Vec::new();
+    let mut i = 0;
+    while i < lengths.len() {
+        let length = lengths[i];
+        let run = lengths[i..].iter().take_while(|&&l| l == length).count();
+        if length == 0 && run >= 11 {
+            let n = run.min(138);
+            codes.push((18, (n - 11) as u8));
+            i += n;
+        } else if length == 0 && run >= 3 {
+            codes.push((17, (run - 3) as u8));
+            i += run;
+        } else if length != 0 && run >= 4 {
+            codes.push((length, 0));
+            let n = (run - 1).min(6);
+            codes.push((16, (n - 3) as u8));
+            i += 1 + n;
+        } else {
+            codes.push((length, 0));
+            i += 1;
+        }
+    }
+    codes
+}
+
+pub struct DeflateEncoder<W: Write> {
+    inner: W,
+    writer: BitWriter,
+    window: Vec<u8>,
+    // Absolute stream position of window[0], the hash chains use those.
+    base: usize,
+    pending: usize,
+    head: Vec<usize>,
+    prev: Vec<usize>,
+}
+
+impl<W: Write> DeflateEncoder<W> {
+    pub fn new(inner: W) -> Self {
+        Self {
+            inner,
+            writer: BitWriter::new(),
+            window: Vec::with_capacity(WINDOW_SIZE + BLOCK_SIZE),
+            base: 0,
+            pending: 0,
+            head: vec![usize::MAX; 1 << HASH_BITS],
+            prev: vec![usize::MAX; WINDOW_SIZE],
+        }
+    }
+
+    pub fn finish(mut self) -> io::Result<W> {
+        self.compress_block(true)?;
+        self.writer.align_to_byte();
+        self.inner.write_all(&self.writer.out)?;
+        self.inner.flush()?;
+        Ok(self.inner)
+    }
+
+    fn hash(&self, i: usize) -> usize {
+        let w = &self.window;
+        (((w[i] as usize) << 10) ^ ((w[i + 1] as usize) << 5) ^ w[i + 2] as usize)
+            & ((1 << HASH_BITS) - 1)
+    }
+
+    fn insert(&mut self, i: usize) {
+        if i + MIN_MATCH <= self.window.len() {
+            let hash = self.hash(i);
+            let position = self.base + i;
+            self.prev[position % WINDOW_SIZE] = self.head[hash];
+            self.head[hash] = position;
+        }
+    }
+
+    fn longest_match(&self, i: usize) -> (usize, usize) {
+        let end = self.window.len();
+        let max = (end - i).min(MAX_MATCH);
+        if max < MIN_MATCH {
+            return (0, 0);
+        }
+        let position = self.base + i;
+        let mut candidate = self.head[self.hash(i)];
+        let (mut best_length, mut best_distance) = (0, 0);
+
+        for _ in 0..MAX_CHAIN {
+            if candidate == usize::MAX
+                || candidate < self.base
+                || candidate >= position
+                || position - candidate > WINDOW_SIZE
+            {
+                break;
+            }
+            let j = candidate - self.base;
+            if self.window[j + best_length] == self.window[i + best_length] {
+                let length = self.window[j..]
+                    .iter()
+                    .zip(&self.window[i..i + max])
+                    .take_while(|(a, b)| a == b)
+                    .count();
+                if length > best_length {
+                    best_length = length;
+                    best_distance = position - candidate;
+                    if length >= NICE_MATCH.min(max) {
+                        break;
+                    }
+                }
+            }
+            candidate = self.prev[candidate % WINDOW_SIZE];
+        }
+        if best_length >= MIN_MATCH {
+            (best_length, best_distance)
+        } else {
+            (0, 0)
+        }
+    }
+
+    fn tokenize(&mut self) -> Vec<Token> {
+        let mut tokens = Vec::new();
+        let end = self.window.len();
+        let mut i = self.pending;
+        while i < end {
+            let (length, distance) = self.longest_match(i);
+            self.insert(i);
+            if length == 0 {
+                tokens.push(Token::Literal(self.window[i]));
+                i += 1;
+                continue;
+            }
+            // One step of lazy matching, a longer match right after wins.
+            if length < NICE_MATCH && i + 1 < end && self.longest_match(i + 1).0 > length {
+                tokens.push(Token::Literal(self.window[i]));
+                i += 1;
+                continue;
+            }
+            tokens.push(Token::Match {
+                length: length as u16,
+                distance: distance as u16,
+            });
+            for k in i + 1..i + length {
+                self.insert(k);
+            }
+            i += length;
+        }
+        tokens
+    }
+
+    fn compress_block(&mut self, last: bool) -> io::Result<()> {
+        let tokens = self.tokenize();
+
+        let mut lit_freqs = [0u32; 286];
+        let mut dist_freqs = [0u32; 30];
+        for token in &tokens {
+            match *token {
+                Token::Literal(byte) => lit_freqs[byte as usize] += 1,
+                Token::Match { length, distance } => {
+                    lit_freqs[257 + length_code(length as usize)] += 1;
+                    dist_freqs[distance_code(distance as usize)] += 1;
+                }
+            }
+        }
+        lit_freqs[256] = 1;
+
+        let lit_lengths = code_lengths(&lit_freqs, 15);
+        let dist_lengths = code_lengths(&dist_freqs, 15);
+        let hlit = 257.max(lit_lengths.iter().rposition(|&l| l > 0).unwrap_or(0) + 1);
+        let hdist = 1.max(dist_lengths.iter().rposition(|&l| l > 0).unwrap_or(0) + 1);
+        let mut all_lengths = lit_lengths[..hlit].to_vec();
+        all_lengths.extend_from_slice(&dist_lengths[..hdist]);
+        let rle = run_length_codes(&all_lengths);
+
+        let mut cl_freqs = [0u32; 19];
+        for &(symbol, _) in &rle {
+            cl_freqs[symbol as usize] += 1;
+        }
+        let cl_lengths = code_lengths(&cl_freqs, 7);
+        let hclen = 4.max(
+            CODE_LENGTH_ORDER
+                .iter()
+                .rposition(|&symbol| cl_lengths[symbol] > 0)
+                .unwrap_or(0)
+                + 1,
+        );
+
+        let extra_bits = |symbol: u8| match symbol {
+            16 => 2,
+            17 => 3,
+            18 => 7,
+            _ => 0,
+        };
+        let mut dynamic_bits = 17 + 3 * hclen as u64;
+        for &(symbol, _) in &rle {
+            dynamic_bits += cl_lengths[symbol as usize] as u64 + extra_bits(symbol) as u64;
+        }
+        for (symbol, &freq) in lit_freqs.iter().enumerate() {
+            let extra = if symbol > 256 { LENGTH_EXTRA[symbol - 257] as u64 } else { 0 };
+            dynamic_bits += freq as u64 * (lit_lengths[symbol] as u64 + extra);
+        }
+        for (symbol, &freq) in dist_freqs.iter().enumerate() {
+            dynamic_bits += freq as u64 * (dist_lengths[symbol] as u64 + DIST_EXTRA[symbol] as u64);
+        }
+
+        let raw = &self.window[self.pending..];
+        let stored_bits = raw.len() as u64 * 8 + 40 * (raw.len() as u64 / 65535 + 1);
+
+        if stored_bits < dynamic_bits {
+            let chunks: Vec<&[u8]> = if raw.is_empty() {
+                vec![raw]
+            } else {
+                raw.chunks(65535).collect()
+            };
+            let count = chunks.len();
+            for (n, chunk) in chunks.into_iter().enumerate() {
+                let final_chunk = last && n + 1 == count;
+                self.writer.bits(final_chunk as u32, 3);
+                self.writer.align_to_byte();
+                let len = chunk.len() as u16;
+                self.writer.out.extend_from_slice(&len.to_le_bytes());
+                self.writer.out.extend_from_slice(&(!len).to_le_bytes());
+                self.writer.out.extend_from_slice(chunk);
+            }
+        } else {
+            let lit_codes = canonical_codes(&lit_lengths);
+            let dist_codes = canonical_codes(&dist_lengths);
+            let cl_codes = canonical_codes(&cl_lengths);
+            let w = &mut self.writer;
+
+            w.bits(last as u32 | 0b100, 3);
+            w.bits(hlit as u32 - 257, 5);
+            w.bits(hdist as u32 - 1, 5);
+            w.bits(hclen as u32 - 4, 4);
+            for &symbol in &CODE_LENGTH_ORDER[..hclen] {
+                w.bits(cl_lengths[symbol] as u32, 3);
+            }
+            for &(symbol, extra) in &rle {
+                w.bits(cl_codes[symbol as usize] as u32, cl_lengths[symbol as usize] as u32);
+                w.bits(extra as u32, extra_bits(symbol));
+            }
+            for token in &tokens {
+                match *token {
+                    Token::Literal(byte) => {
+                        w.bits(lit_codes[byte as usize] as u32, lit_lengths[byte as usize] as u32)
+                    }
+                    Token::Match { length, distance } => {
+                        let code = length_code(length as usize);
+                        w.bits(lit_codes[257 + code] as u32, lit_lengths[257 + code] as u32);
+                        w.bits(
+                            length as u32 - LENGTH_BASE[code] as u32,
+                            LENGTH_EXTRA[code] as u32,
+                        );
+                        let code = distance_code(distance as usize);
+                        w.bits(dist_codes[code] as u32, dist_lengths[code] as u32);
+                        w.bits(
+                            distance as u32 - DIST_BASE[code] as u32,
+                            DIST_EXTRA[code] as u32,
+                        );
+                    }
+                }
+            }
+            w.bits(lit_codes[256] as u32, lit_lengths[256] as u32);
+        }
+
+        self.inner.write_all(&self.writer.out)?;
+        self.writer.out.clear();
+
+        if self.window.len() > WINDOW_SIZE {
+            let drop = self.window.len() - WINDOW_SIZE;
+            self.window.drain(..drop);
+            self.base += drop;
+        }
+        self.pending = self.window.len();
+        Ok(())
+    }
+}
+
+impl<W: Write> Write for DeflateEncoder<W> {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        self.window.extend_from_slice(data);
+        if self.window.len() - self.pending >= BLOCK_SIZE {
+            self.compress_block(false)?;
+        }
+        Ok(data.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+pub struct GzipEncoder<W: Write> {
+    encoder: DeflateEncoder<W>,
+    crc: u32,
+    size: u32,
+}
+
+impl<W: Write> GzipEncoder<W> {
+    pub fn new(mut inner: W) -> io::Result<Self> {
+        // No name, no timestamp, unix as the OS.
+        inner.write_all(&[0x1F, 0x8B, 8, 0, 0, 0, 0, 0, 0, 3])?;
+        Ok(Self {
+            encoder: DeflateEncoder::new(inner),
+            crc: 0,
+            size: 0,
+        })
+    }
+
+    pub fn finish(self) -> io::Result<W> {
+        let mut inner = self.encoder.finish()?;
+        inner.write_all(&self.crc.to_le_bytes())?;
+        inner.write_all(&self.size.to_le_bytes())?;
+        inner.flush()?;
+        Ok(inner)
+    }
+}
+
+impl<W: Write> Write for GzipEncoder<W> {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        self.encoder.write_all(data)?;
+        self.crc = crc32_update(self.crc, data);
+        self.size = self.size.wrapping_add(data.len() as u32);
+        Ok(data.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.encoder.flush()
+    }
+}
@@ -0,0 +1,888 @@
+/*
+* Stygian Sift - A Terminal-based File Manager
+ * Copyright (C) 2024 Maui The Magnificent (Charon)
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published
+ * by the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+ * Contact: Maui_The_Magnificent@proton.me
+ * Project repository: https://github.com/Mauitron/StygianSift.git
+ */
+
+use super::*;
+//////////////////////////////////////////////////Structured Preview//////////////////////////////////////////////////////////////////
+// Data files get a preview that shows their shape rather than their bytes.
+// Everything here is parsed by hand, so there is no need to pull in serde
+// and friends just to look at a config file.
+
+const MAX_JSON_DEPTH: usize = 256;
+const MAX_TABLE_ROWS: usize = 500;
+const MAX_COLUMN_WIDTH: usize = 24;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StructuredKind {
+    Json,
+    Delimited(char),
+    Toml,
+    Ini,
+}
+
+impl StructuredKind {
+    pub fn detect(entry: &FileEntry) -> Option<Self> {
+        // Some well-known names carry their format without an extension.
+        match (&entry.file_type, entry.name.as_str()) {
+            (FileType::Data, "Cargo.lock" | "poetry.lock") => return Some(StructuredKind::Toml),
+            (FileType::Config, "Pipfile") => return Some(StructuredKind::Toml),
+            (FileType::Data, "flake.lock") => return Some(StructuredKind::Json),
+            (FileType::Config, ".gitconfig" | ".gitmodules" | ".editorconfig") => {
+                return Some(StructuredKind::Ini)
+            }
+            _ => {}
+        }
+        let ext = entry
+            .path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(|ext| ext.to_lowercase())?;
+
+        match (&entry.file_type, ext.as_str()) {
+            (FileType::Config, "toml") => Some(StructuredKind::Toml),
+            (FileType::Config, "ini" | "config") => Some(StructuredKind::Ini),
+            (_, "cfg" | "conf" | "desktop") => Some(StructuredKind::Ini),
+            (_, "json" | "geojson" | "webmanifest") => Some(StructuredKind::Json),
+            (_, "csv") => Some(StructuredKind::Delimited(',')),
+            (_, "tsv" | "tab") => Some(StructuredKind::Delimited('\t')),
+            _ => None,
+        }
+    }
+}
+
+// What a file parsed into, kept until the file changes so a redraw only has
+// to fold and scroll it.
+pub enum ParsedPreview {
+    Json(Result<JsonValue, JsonError>, String),
+    Table(Vec<Vec<String>>),
+    Sections(Vec<PreviewLine>),
+    // Left to the plain byte preview.
+    Raw,
+}
+
+pub struct CachedPreview {
+    pub path: PathBuf,
+    pub modified: Option<SystemTime>,
+    pub truncated: bool,
+    pub parsed: ParsedPreview,
+}
+
+pub struct StructuredPreviewState {
+    pub path: Option<PathBuf>,
+    pub fold_depth: usize,
+    pub column_offset: usize,
+    pub cached: Option<CachedPreview>,
+}
+
+impl StructuredPreviewState {
+    pub fn new() -> Self {
+        Self {
+            path: None,
+            fold_depth: 3,
+            column_offset: 0,
+            cached: None,
+        }
+    }
+
+    // The fold depth is kept between files, the horizontal scroll is not.
+    pub fn follow(&mut self, path: &Path) {
+        if self.path.as_deref() != Some(path) {
+            self.path = Some(path.to_path_buf());
+            self.column_offset = 0;
+        }
+    }
+
+    pub fn fold_less(&mut self) {
+        self.fold_depth = (self.fold_depth + 1).min(MAX_JSON_DEPTH);
+    }
+
+    pub fn fold_more(&mut self) {
+        self.fold_depth = self.fold_depth.saturating_sub(1).max(1);
+    }
+
+    pub fn scroll_left(&mut self) {
+        self.column_offset = self.column_offset.saturating_sub(1);
+    }
+
+    pub fn scroll_right(&mut self) {
+        self.column_offset += 1;
+    }
+}
+
+impl Default for StructuredPreviewState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Clone)]
+pub struct PreviewLine {
+    pub text: String,
+    pub color: Color,
+}
+
+impl PreviewLine {
+    fn new(text: impl Into<String>, color: Color) -> Self {
+        Self {
+            text: text.into(),
+            color,
+        }
+    }
+}
+
+// Returns false when the file should fall back to the plain byte preview.
+pub fn render_structured_preview(
+    app_state: &mut AppState,
+    stdout: &mut impl Write,
+    entry: &FileEntry,
+    nav_width: u16,
+    preview_width: u16,
+    start_y: u16,
+    end_y: u16,
+) -> io::Result<bool> {
+    let kind = match StructuredKind::detect(entry) {
+        Some(kind) => kind,
+        None => return Ok(false),
+    };
+
+    // Members of an archive have no time of their own, they never change.
+    let modified = fs::metadata(&entry.path).and_then(|metadata| metadata.modified()).ok();
+    let fresh = app_state
+        .structured_preview
+        .cached
+        .as_ref()
+        .is_some_and(|cached| cached.path == entry.path && cached.modified == modified);
+    if !fresh {
+        let mut buffer = read_entry_bytes(app_state, &entry.path, STRUCTURED_PREVIEW_LIMIT + 1)?;
+        let truncated = buffer.len() > STRUCTURED_PREVIEW_LIMIT;
+        buffer.truncate(STRUCTURED_PREVIEW_LIMIT);
+        app_state.structured_preview.cached = Some(CachedPreview {
+            path: entry.path.clone(),
+            modified,
+            truncated,
+            parsed: parse_preview(buffer, kind, truncated),
+        });
+    }
+
+    let width = preview_width.saturating_sub(14) as usize;
+    let dimming_config = DimmingConfig::new((end_y - start_y) as usize, &app_state.config);
+    app_state.structured_preview.follow(&entry.path);
+    let state = &mut app_state.structured_preview;
+    let Some(cached) = state.cached.as_ref() else {
+        return Ok(false);
+    };
+    let truncated = cached.truncated;
+
+    let lines = match &cached.parsed {
+        ParsedPreview::Json(parsed, text) => json_lines(parsed, text, state.fold_depth),
+        ParsedPreview::Table(rows) => table_preview_lines(rows, &mut state.column_offset, width),
+        ParsedPreview::Sections(lines) => lines.clone(),
+        ParsedPreview::Raw => return Ok(false),
+    };
+
+    let mut y = start_y;
+    for line in lines {
+        if y >= end_y {
+            break;
+        }
+        let distance = ((y - 3).saturating_sub(start_y)) as i32;
+        let dim_factor = dimming_config.calculate_dimming(distance);
+        queue!(
+            stdout,
+            MoveTo(nav_width + 2, y - 3),
+            SetForegroundColor(DimmingConfig::dim_color(line.color, dim_factor))
+        )?;
+        write!(stdout, " {}", truncate_str(&line.text, width.max(2)))?;
+        y += 1;
+    }
+
+    if truncated {
+        queue!(
+            stdout,
+            MoveTo(nav_width + 2, y.min(end_y) - 3),
+            SetForegroundColor(Color::DarkGrey)
+        )?;
+        write!(
+            stdout,
+            " (showing first {})",
+            format_size(STRUCTURED_PREVIEW_LIMIT as u64)
+        )?;
+    }
+
+    queue!(stdout, SetForegroundColor(Color::Reset))?;
+    Ok(true)
+}
+
+fn parse_preview(buffer: Vec<u8>, kind: StructuredKind, truncated: bool) -> ParsedPreview {
+    let text = match String::from_utf8(buffer) {
+        Ok(text) => text,
+        Err(e) if truncated && e.utf8_error().error_len().is_none() => {
+            let valid = e.utf8_error().valid_up_to();
+            let mut bytes = e.into_bytes();
+            bytes.truncate(valid);
+            String::from_utf8(bytes).unwrap_or_default()
+        }
+        Err(_) => return ParsedPreview::Raw,
+    };
+    match kind {
+        // A cut off document can never validate, so leave it to the raw view.
+        StructuredKind::Json if truncated => ParsedPreview::Raw,
+        StructuredKind::Json => ParsedPreview::Json(parse_json(&text), text),
+        StructuredKind::Delimited(delimiter) => {
+            ParsedPreview::Table(parse_delimited(&text, delimiter, truncated))
+        }
+        StructuredKind::Toml | StructuredKind::Ini => {
+            ParsedPreview::Sections(section_preview_lines(&text, kind))
+        }
+    }
+}
+
+///////////////////////////////////////////////////////JSON///////////////////////////////////////////////////////////////////////////
+#[derive(Debug, Clone, PartialEq)]
+pub enum JsonValue {
+    Null,
+    Bool(bool),
+    Number(String),
+    Str(String),
+    Array(Vec<JsonValue>),
+    Object(Vec<(String, JsonValue)>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JsonError {
+    pub message: String,
+    pub line: usize,
+    pub column: usize,
+}
+
+pub fn parse_json(text: &str) -> Result<JsonValue, JsonError> {
+    let mut parser = JsonParser {
+        bytes: text.as_bytes(),
+        pos: 0,
+    };
+    // A byte order mark is not valid json, but editors love to write one.
+    if text.starts_with('\u{feff}') {
+        parser.pos = 3;
+    }
+    parser.skip_whitespace();
+    let value = parser.parse_value(0)?;
+    parser.skip_whitespace();
+    if parser.pos < parser.bytes.len() {
+        return Err(parser.error("unexpected data after the top level value"));
+    }
+    Ok(value)
+}
+
+struct JsonParser<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl JsonParser<'_> {
+    fn error(&self, message: &str) -> JsonError {
+        let consumed = &self.bytes[..self.pos.min(self.bytes.len())];
+        let line = consumed.iter().filter(|&&b| b == b'\n').count() + 1;
+        let line_start = consumed
+            .iter()
+            .rposition(|&b| b == b'\n')
+            .map_or(0, |i| i + 1);
+        let column = String::from_utf8_lossy(&consumed[line_start..])
+            .chars()
+            .count()
+            + 1;
+        JsonError {
+            message: message.to_string(),
+            line,
+            column,
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.bytes.get(self.pos).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(b' ' | b'\t' | b'\n' | b'\r') = self.peek() {
+            self.pos += 1;
+        }
+    }
+
+    fn expect_literal(&mut self, literal: &str, value: JsonValue) -> Result<JsonValue, JsonError> {
+        if self.bytes[self.pos..].starts_with(literal.as_bytes()) {
+            self.pos += literal.len();
+            Ok(value)
+        } else {
+            Err(self.error(&format!("expected '{}'", literal)))
+        }
+    }
+
+    fn parse_value(&mut self, depth: usize) -> Result<JsonValue, JsonError> {
+        if depth > MAX_JSON_DEPTH {
+            return Err(self.error("nesting is too deep"));
+        }
+        match self.peek() {
+            None => Err(self.error("unexpected end of input")),
+            Some(b'{') => self.parse_object(depth),
+            Some(b'[') => self.parse_array(depth),
+            Some(b'"') => self.parse_string().map(JsonValue::Str),
+            Some(b't') => self.expect_literal("true", JsonValue::Bool(true)),
+            Some(b'f') => self.expect_literal("false", JsonValue::Bool(false)),
+            Some(b'n') => self.expect_literal("null", JsonValue::Null),
+            Some(b'-' | b'0'..=b'9') => self.parse_number(),
+            Some(_) => Err(self.error("expected a value")),
+        }
+    }
+
+    fn parse_object(&mut self, depth: usize) -> Result<JsonValue, JsonError> {
+        self.pos += 1;
+        let mut members = Vec::new();
+        self.skip_whitespace();
+        if self.peek() == Some(b'}') {
+            self.pos += 1;
+            return Ok(JsonValue::Object(members));
+        }
+        loop {
+            self.skip_whitespace();
+            if self.peek() != Some(b'"') {
+                return Err(self.error("expected a string key"));
+            }
+            let key = self.parse_string()?;
+            self.skip_whitespace();
+            if self.peek() != Some(b':') {
+                return Err(self.error("expected ':' after key"));
+            }
+            self.pos += 1;
+            self.skip_whitespace();
+            let value = self.parse_value(depth + 1)?;
+            members.push((key, value));
+            self.skip_whitespace();
+            match self.peek() {
+                Some(b',') => self.pos += 1,
+                Some(b'}') => {
+                    self.pos += 1;
+                    return Ok(JsonValue::Object(members));
+                }
+                _ => return Err(self.error("expected ',' or '}'")),
+            }
+        }
+    }
+
+    fn parse_array(&mut self, depth: usize) -> Result<JsonValue, JsonError> {
+        self.pos += 1;
+        let mut items = Vec::new();
+        self.skip_whitespace();
+        if self.peek() == Some(b']') {
+            self.pos += 1;
+            return Ok(JsonValue::Array(items));
+        }
+        loop {
+            self.skip_whitespace();
+            items.push(self.parse_value(depth + 1)?);
+            self.skip_whitespace();
+            match self.peek() {
+                Some(b',') => self.pos += 1,
+                Some(b']') => {
+                    self.pos += 1;
+                    return Ok(JsonValue::Array(items));
+                }
+                _ => return Err(self.error("expected ',' or ']'")),
+            }
+        }
+    }
+
+    fn parse_hex4(&mut self) -> Result<u32, JsonError> {
+        let digits = self
+            .bytes
+            .get(self.pos..self.pos + 4)
+            .and_then(|d| std::str::from_utf8(d).ok())
+            .and_then(|d| u32::from_str_radix(d, 16).ok())
+            .ok_or_else(|| self.error("invalid \\u escape"))?;
+        self.pos += 4;
+        Ok(digits)
+    }
+
+    fn parse_string(&mut self) -> Result<String, JsonError> {
+        self.pos += 1;
+        let mut out = Vec::new();
+        loop {
+            match self.peek() {
+                None => return Err(self.error("unterminated string")),
+                Some(b'"') => {
+                    self.pos += 1;
+                    return String::from_utf8(out).map_err(|_| self.error("invalid utf-8 in string"));
+                }
+                Some(b'\\') => {
+                    self.pos += 1;
+                    let escaped = self.peek().ok_or_else(|| self.error("unterminated string"))?;
+                    self.pos += 1;
+                    match escaped {
+                        b'"' => out.push(b'"'),
+                        b'\\' => out.push(b'\\'),
+                        b'/' => out.push(b'/'),
+                        b'b' => out.push(0x08),
+                        b'f' => out.push(0x0c),
+                        b'n' => out.push(b'\n'),
+                        b'r' => out.push(b'\r'),
+                        b't' => out.push(b'\t'),
+                        b'u' => {
+                            let mut code = self.parse_hex4()?;
+                            if (0xD800..0xDC00).contains(&code) {
+                                if !self.bytes[self.pos..].starts_with(b"\\u") {
+                                    return Err(self.error("unpaired surrogate"));
+                                }
+                                self.pos += 2;
+                                let low = self.parse_hex4()?;
+                                if !(0xDC00..0xE000).contains(&low) {
+                                    return Err(self.error("unpaired surrogate"));
+                                }
+                                code = 0x10000 + ((code - 0xD800) << 10) + (low - 0xDC00);
+                            }
+                            let c = char::from_u32(code)
+                                .ok_or_else(|| self.error("invalid \\u escape"))?;
+                            let mut utf8 = [0; 4];
+                            out.extend_from_slice(c.encode_utf8(&mut utf8).as_bytes());
+                        }
+                        _ => {
+                            self.pos -= 1;
+                            return Err(self.error("invalid escape sequence"));
+                        }
+                    }
+                }
+                Some(byte) if byte < 0x20 => {
+                    return Err(self.error("control character in string"));
+                }
+                Some(byte) => {
+                    out.push(byte);
+                    self.pos += 1;
+                }
+            }
+        }
+    }
+
+    fn parse_number(&mut self) -> Result<JsonValue, JsonError> {
+        let start = self.pos;
+        let digits = |parser: &mut Self| {
+            let from = parser.pos;
+            while let Some(b'0'..=b'9') = parser.peek() {
+                parser.pos += 1;
+            }
+            parser.pos - from
+        };
+
+        if self.peek() == Some(b'-') {
+            self.pos += 1;
+        }
+        if self.peek() == Some(b'0') {
+            self.pos += 1;
+            if let Some(b'0'..=b'9') = self.peek() {
+                return Err(self.error("leading zeros are not allowed"));
+            }
+        } else if digits(self) == 0 {
+            return Err(self.error("expected a digit"));
+        }
+        if self.peek() == Some(b'.') {
+            self.pos += 1;
+            if digits(self) == 0 {
+                return Err(self.error("expected a digit after '.'"));
+            }
+        }
+        if let Some(b'e' | b'E') = self.peek() {
+            self.pos += 1;
+            if let Some(b'+' | b'-') = self.peek() {
+                self.pos += 1;
+            }
+            if digits(self) == 0 {
+                return Err(self.error("expected a digit in exponent"));
+            }
+        }
+        let number = String::from_utf8_lossy(&self.bytes[start..self.pos]).into_owned();
+        Ok(JsonValue::Number(number))
+    }
+}
+
+// Written the way json has it, not with Rust's escapes.
+fn json_string(s: &str) -> String {
+    Json::from(s).to_string()
+}
+
+fn json_scalar(value: &JsonValue) -> (String, Color) {
+    match value {
+        JsonValue::Null => ("null".to_string(), Color::Magenta),
+        JsonValue::Bool(b) => (b.to_string(), Color::Magenta),
+        JsonValue::Number(n) => (n.clone(), Color::Yellow),
+        JsonValue::Str(s) => (json_string(s), Color::Green),
+        JsonValue::Array(items) => (format!("[…] {} items", items.len()), Color::DarkGrey),
+        JsonValue::Object(members) => (format!("{{…}} {} keys", members.len()), Color::DarkGrey),
+    }
+}
+
+fn push_json_lines(
+    value: &JsonValue,
+    key: Option<&str>,
+    depth: usize,
+    fold_depth: usize,
+    trailing: &str,
+    lines: &mut Vec<PreviewLine>,
+) {
+    let indent = "  ".repeat(depth);
+    let prefix = match key {
+        Some(key) => format!("{}{}: ", indent, json_string(key)),
+        None => indent.clone(),
+    };
+
+    let (open, close, children): (&str, &str, Vec<(Option<&str>, &JsonValue)>) = match value {
+        JsonValue::Array(items) if !items.is_empty() && depth < fold_depth => {
+            ("[", "]", items.iter().map(|item| (None, item)).collect())
+        }
+        JsonValue::Object(members) if !members.is_empty() && depth < fold_depth => (
+            "{",
+            "}",
+            members
+                .iter()
+                .map(|(key, value)| (Some(key.as_str()), value))
+                .collect(),
+        ),
+        JsonValue::Array(items) if items.is_empty() => {
+            lines.push(PreviewLine::new(format!("{}[]{}", prefix, trailing), Color::Reset));
+            return;
+        }
+        JsonValue::Object(members) if members.is_empty() => {
+            lines.push(PreviewLine::new(format!("{}{{}}{}", prefix, trailing), Color::Reset));
+            return;
+        }
+        _ => {
+            let (text, color) = json_scalar(value);
+            lines.push(PreviewLine::new(format!("{}{}{}", prefix, text, trailing), color));
+            return;
+        }
+    };
+
+    lines.push(PreviewLine::new(format!("{}{}", prefix, open), Color::Cyan));
+    let last = children.len() - 1;
+    for (i, (child_key, child)) in children.into_iter().enumerate() {
+        let comma = if i == last { "" } else { "," };
+        push_json_lines(child, child_key, depth + 1, fold_depth, comma, lines);
+    }
+    lines.push(PreviewLine::new(format!("{}{}{}", indent, close, trailing), Color::Cyan));
+}
+
+pub fn json_preview_lines(text: &str, fold_depth: usize) -> Vec<PreviewLine> {
+    json_lines(&parse_json(text), text, fold_depth)
+}
+
+fn json_lines(
+    parsed: &Result<JsonValue, JsonError>,
+    text: &str,
+    fold_depth: usize,
+) -> Vec<PreviewLine> {
+    match parsed {
+        Ok(value) => {
+            let summary = match value {
+                JsonValue::Array(_) | JsonValue::Object(_) => json_scalar(value).0,
+                _ => "scalar".to_string(),
+            };
+            let mut lines = vec![
+                PreviewLine::new(
+                    format!("✓ Valid JSON · {} · folded at depth {}", summary, fold_depth),
+                    Color::Green,
+                ),
+                PreviewLine::new("", Color::Reset),
+            ];
+            push_json_lines(value, None, 0, fold_depth, "", &mut lines);
+            lines
+        }
+        Err(e) => {
+            let mut lines = vec![
+                PreviewLine::new("✗ Invalid JSON", Color::Red),
+                PreviewLine::new(
+                    format!("line {}, column {}: {}", e.line, e.column, e.message),
+                    Color::Red,
+                ),
+                PreviewLine::new("", Color::Reset),
+            ];
+            let source: Vec<&str> = text.lines().collect();
+            let first = e.line.saturating_sub(4);
+            for (i, line) in source.iter().enumerate().take(e.line).skip(first) {
+                let color = if i + 1 == e.line {
+                    Color::Yellow
+                } else {
+                    Color::DarkGrey
+                };
+                lines.push(PreviewLine::new(format!("{:>5} │ {}", i + 1, line), color));
+            }
+            lines.push(PreviewLine::new(
+                format!("{:>5} │ {}^", "", " ".repeat(e.column.saturating_sub(1))),
+                Color::Red,
+            ));
+            lines
+        }
+    }
+}
+
+/////////////////////////////////////////////////////CSV / TSV////////////////////////////////////////////////////////////////////////
+// Quoted fields may contain the delimiter, doubled quotes and newlines.
+pub fn parse_delimited(text: &str, delimiter: char, truncated: bool) -> Vec<Vec<String>> {
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = text.trim_start_matches('\u{feff}').chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if in_quotes {
+            match c {
+                '"' if chars.peek() == Some(&'"') => {
+                    field.push('"');
+                    chars.next();
+                }
+                '"' => in_quotes = false,
+                _ => field.push(c),
+            }
+            continue;
+        }
+        match c {
+            '"' if field.is_empty() => in_quotes = true,
+            '\r' => {}
+            '\n' => {
+                row.push(std::mem::take(&mut field));
+                rows.push(std::mem::take(&mut row));
+                if rows.len() > MAX_TABLE_ROWS {
+                    return rows;
+                }
+            }
+            c if c == delimiter => row.push(std::mem::take(&mut field)),
+            _ => field.push(c),
+        }
+    }
+
+    // The last row of a cut off file is most likely incomplete.
+    if !truncated && (!field.is_empty() || !row.is_empty()) {
+        row.push(field);
+        rows.push(row);
+    }
+    rows
+}
+
+pub fn table_preview_lines(
+    rows: &[Vec<String>],
+    column_offset: &mut usize,
+    width: usize,
+) -> Vec<PreviewLine> {
+    let columns = rows.iter().map(|row| row.len()).max().unwrap_or(0);
+    if columns == 0 {
+        return vec![PreviewLine::new("(empty table)", Color::DarkGrey)];
+    }
+    *column_offset = (*column_offset).min(columns - 1);
+
+    let cell = |row: &Vec<String>, col: usize| -> String {
+        row.get(col)
+            .map(|field| field.replace(['\n', '\t'], " "))
+            .unwrap_or_default()
+    };
+
+    let widths: Vec<usize> = (0..columns)
+        .map(|col| {
+            rows.iter()
+                .map(|row| cell(row, col).chars().count())
+                .max()
+                .unwrap_or(0)
+                .clamp(1, MAX_COLUMN_WIDTH)
+        })
+        .collect();
+
+    let mut last_visible = *column_offset;
+    let mut used = 0;
+    for (col, col_width) in widths.iter().enumerate().skip(*column_offset) {
+        used += col_width + 3;
+        if used > width && col > *column_offset {
+            break;
+        }
+        last_visible = col;
+    }
+
+    let format_row = |row: &Vec<String>| -> String {
+        (*column_offset..=last_visible)
+            .map(|col| {
+                let text = truncate_str(&cell(row, col), widths[col]);
+                format!("{:<width$}", text, width = widths[col])
+            })
+            .collect::<Vec<_>>()
+            .join(" │ ")
+    };
+
+    let mut lines = vec![
+        PreviewLine::new(
+            format!(
+                "{} rows · columns {}-{} of {}",
+                rows.len().saturating_sub(1),
+                *column_offset + 1,
+                last_visible + 1,
+                columns
+            ),
+            Color::DarkGrey,
+        ),
+        PreviewLine::new(format_row(&rows[0]), Color::Cyan),
+        PreviewLine::new(
+            (*column_offset..=last_visible)
+                .map(|col| "─".repeat(widths[col]))
+                .collect::<Vec<_>>()
+                .join("─┼─"),
+            Color::DarkGrey,
+        ),
+    ];
+    lines.extend(
+        rows.iter()
+            .skip(1)
+            .map(|row| PreviewLine::new(format_row(row), Color::Reset)),
+    );
+    lines
+}
+
+/////////////////////////////////////////////////////TOML / INI///////////////////////////////////////////////////////////////////////
+struct Section {
+    name: String,
+    entries: Vec<(String, String)>,
+}
+
+// Multi-line strings and arrays are folded down to their first line.
+enum Continuation {
+    Quote(&'static str),
+    Brackets(usize),
+}
+
+fn bracket_balance(text: &str) -> isize {
+    text.matches(['[', '{']).count() as isize - text.matches([']', '}']).count() as isize
+}
+
+fn value_continuation(value: &str) -> Option<Continuation> {
+    for quote in ["\"\"\"", "'''"] {
+        if let Some(rest) = value.strip_prefix(quote) {
+            if rest.contains(quote) {
+                return None;
+            }
+            return Some(Continuation::Quote(quote));
+        }
+    }
+    match bracket_balance(value) {
+        open if open > 0 => Some(Continuation::Brackets(open as usize)),
+        _ => None,
+    }
+}
+
+pub fn section_preview_lines(text: &str, kind: StructuredKind) -> Vec<PreviewLine> {
+    let is_comment =
+        |line: &str| line.starts_with('#') || (kind == StructuredKind::Ini && line.starts_with(';'));
+    let mut sections = vec![Section {
+        name: String::new(),
+        entries: Vec::new(),
+    }];
+    let mut problems = Vec::new();
+    let mut continuation: Option<Continuation> = None;
+
+    for (number, raw) in text.lines().enumerate() {
+        let line = raw.trim();
+        match continuation {
+            Some(Continuation::Quote(quote)) => {
+                if line.contains(quote) {
+                    continuation = None;
+                }
+                continue;
+            }
+            Some(Continuation::Brackets(open)) => {
+                let open = open as isize + bracket_balance(line);
+                continuation = (open > 0).then_some(Continuation::Brackets(open as usize));
+                continue;
+            }
+            None => {}
+        }
+        if line.is_empty() || is_comment(line) {
+            continue;
+        }
+        if line.starts_with('[') {
+            if let Some(end) = line.rfind(']') {
+                sections.push(Section {
+                    name: line[..=end].to_string(),
+                    entries: Vec::new(),
+                });
+                continue;
+            }
+        }
+        let separator = match kind {
+            StructuredKind::Ini => line.find(['=', ':']),
+            _ => line.find('='),
+        };
+        match separator {
+            Some(split) => {
+                let key = line[..split].trim().to_string();
+                let value = line[split + 1..].trim();
+                if kind == StructuredKind::Toml {
+                    continuation = value_continuation(value);
+                }
+                let shown = match continuation {
+                    Some(_) => format!("{} …", value),
+                    None => value.to_string(),
+                };
+                if let Some(section) = sections.last_mut() {
+                    section.entries.push((key, shown));
+                }
+            }
+            None => problems.push(number + 1),
+        }
+    }
+
+    let mut lines = Vec::new();
+    let named = sections.iter().filter(|s| !s.name.is_empty()).count();
+    let label = if kind == StructuredKind::Toml { "TOML" } else { "INI" };
+    lines.push(PreviewLine::new(
+        format!("{} · {} sections", label, named),
+        if problems.is_empty() {
+            Color::Green
+        } else {
+            Color::Yellow
+        },
+    ));
+    if !problems.is_empty() {
+        let listed: Vec<String> = problems.iter().take(8).map(|n| n.to_string()).collect();
+        lines.push(PreviewLine::new(
+            format!("unrecognised lines: {}", listed.join(", ")),
+            Color::Red,
+        ));
+    }
+
+    for section in sections {
+        if section.name.is_empty() && section.entries.is_empty() {
+            continue;
+        }
+        lines.push(PreviewLine::new("", Color::Reset));
+        let name = if section.name.is_empty() {
+            "(top level)".to_string()
+        } else {
+            section.name
+        };
+        lines.push(PreviewLine::new(
+            format!("▸ {}  ({} keys)", name, section.entries.len()),
+            Color::Cyan,
+        ));
+        for (key, value) in section.entries {
+            lines.push(PreviewLine::new(format!("    {} = {}", key, value), Color::Reset));
+        }
+    }
+    lines
+}
@@ -0,0 +1,101 @@
+/*
+* Stygian Sift - A Terminal-based File Manager
+ * Copyright (C) 2024 Maui The Magnificent (Charon)
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published
+ * by the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+ * Contact: Maui_The_Magnificent@proton.me
+ * Project repository: https://github.com/Mauitron/StygianSift.git
+ */
+
+use super::*;
+use std::thread::{self, JoinHandle};
+////////////////////////////////////////////////////Background Jobs///////////////////////////////////////////////////////////////////
+// Packing and unpacking can take a while, so they run on their own thread.
+// The main loop polls them and reports back once they are done.
+
+pub struct BackgroundJob {
+    pub description: String,
+    // What the job writes into, created before it starts. Removed again when
+    // the job fails, and by undo.
+    pub output: PathBuf,
+    pub is_directory: bool,
+    handle: JoinHandle<io::Result<String>>,
+}
+
+impl BackgroundJob {
+    pub fn spawn<F>(description: String, output: PathBuf, is_directory: bool, work: F) -> Self
+    where
+        F: FnOnce() -> io::Result<String> + Send + 'static,
+    {
+        let cleanup = output.clone();
+        let handle = thread::spawn(move || {
+            let result = work();
+            if result.is_err() {
+                remove_output(&cleanup, is_directory);
+            }
+            result
+        });
+        Self {
+            description,
+            output,
+            is_directory,
+            handle,
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.handle.is_finished()
+    }
+}
+
+// Never leave half an archive behind.
+fn remove_output(output: &Path, is_directory: bool) {
+    if is_directory {
+        let _ = fs::remove_dir_all(output);
+    } else {
+        let _ = fs::remove_file(output);
+    }
+}
+
+pub fn start_background_job(app_state: &mut AppState, job: BackgroundJob) -> io::Result<()> {
+    interaction_field!("{} in the background", job.description)?;
+    app_state.jobs.push(job);
+    Ok(())
+}
+
+// Returns true when a job finished, so the listing can be refreshed.
+pub fn poll_background_jobs(app_state: &mut AppState) -> io::Result<bool> {
+    let (finished, running): (Vec<_>, Vec<_>) = std::mem::take(&mut app_state.jobs)
+        .into_iter()
+        .partition(BackgroundJob::is_finished);
+    app_state.jobs = running;
+
+    let any_finished = !finished.is_empty();
+    for job in finished {
+        match job.handle.join() {
+            Ok(Ok(summary)) => {
+                app_state.add_create_undo_entry(&job.output, job.is_directory)?;
+                interaction_field!("{}", summary)?;
+            }
+            Ok(Err(e)) => interaction_field!("{} failed: {}", job.description, e)?,
+            // A panic skips the cleanup in the job's own thread.
+            Err(_) => {
+                remove_output(&job.output, job.is_directory);
+                interaction_field!("{} stopped unexpectedly", job.description)?
+            }
+        }
+    }
+    Ok(any_finished)
+}
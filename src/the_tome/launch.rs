@@ -0,0 +1,184 @@
+/*
+* Stygian Sift - A Terminal-based File Manager
+ * Copyright (C) 2024 Maui The Magnificent (Charon)
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published
+ * by the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+ * Contact: Maui_The_Magnificent@proton.me
+ * Project repository: https://github.com/Mauitron/StygianSift.git
+ */
+
+// What the browser takes on the command line. Settings given here only last
+// for the session, the config file keeps its own values.
+
+use super::*;
+
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+pub const HELP: &str = "\
+Usage: StygianSift [options] [PATH]
+       StygianSift <command> ...  ls, search, tag, undo, config or rules,
+                                  StygianSift ls --help lists them
+
+Opens the browser in PATH, or with PATH selected when it is a file.
+
+Options:
+  --config PATH          load and save this config file instead of ./.maui
+  --profile NAME         use a profile from the config
+  --sort ORDER           name, size, type, date or color, with -desc to reverse
+  --no-mouse             leave the mouse to the terminal
+  --simple-borders       draw plain borders
+  --search QUERY         open on the results of a search
+  --choose-files OUT     Enter writes the chosen files to OUT and quits
+  --choose-dir OUT       Enter writes the chosen directory to OUT and quits
+  --cwd-file OUT         write the last directory to OUT on quit
+  --version              print the version
+  --help                 print this help
+
+OUT may be - for stdout, the browser then draws on the terminal itself.";
+
+#[derive(Debug, Default)]
+pub struct LaunchOptions {
+    pub start_path: Option<PathBuf>,
+    pub config_path: Option<PathBuf>,
+    pub profile: Option<String>,
+    pub sort: Option<SortOrder>,
+    pub no_mouse: bool,
+    pub simple_borders: bool,
+    pub search: Option<String>,
+    pub chooser: Option<Chooser>,
+    pub cwd_file: Option<PathBuf>,
+    pub help: bool,
+    pub version: bool,
+}
+
+impl LaunchOptions {
+    pub fn parse(args: &[String]) -> Result<Self, String> {
+        let mut options = LaunchOptions::default();
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            let mut value = |what: &str| {
+                args.next()
+                    .cloned()
+                    .ok_or_else(|| format!("{} needs {}", arg, what))
+            };
+            match arg.as_str() {
+                "--config" => options.config_path = Some(PathBuf::from(value("a file")?)),
+                "--profile" => options.profile = Some(value("a name")?),
+                "--sort" => {
+                    let order = value("an order")?;
+                    options.sort = Some(
+                        SortOrder::parse(&order)
+                            .ok_or_else(|| format!("unknown sort order '{}'", order))?,
+                    );
+                }
+                "--no-mouse" => options.no_mouse = true,
+                "--simple-borders" => options.simple_borders = true,
+                "--search" => options.search = Some(value("a query")?),
+                "--choose-files" => {
+                    let output = value("a file, or - for stdout")?;
+                    options.chooser = Some(Chooser::new(ChooseMode::Files, output.into()));
+                }
+                "--choose-dir" => {
+                    let output = value("a file, or - for stdout")?;
+                    options.chooser = Some(Chooser::new(ChooseMode::Directory, output.into()));
+                }
+                "--cwd-file" => options.cwd_file = Some(PathBuf::from(value("a file, or - for stdout")?)),
+                "--help" | "-h" => options.help = true,
+                "--version" | "-V" => options.version = true,
+                flag if flag.starts_with('-') && flag != "-" => {
+                    return Err(format!("unknown option '{}'", flag));
+                }
+                path => {
+                    if options.start_path.is_some() {
+                        return Err(format!("only one path can be opened, '{}' is one too many", path));
+                    }
+                    options.start_path = Some(PathBuf::from(path));
+                }
+            }
+        }
+        Ok(options)
+    }
+
+    // Whether a chosen path or the last directory is written to stdout, which
+    // then has to be kept free of the UI.
+    pub fn writes_to_stdout(&self) -> bool {
+        let stdout = Path::new("-");
+        self.chooser.as_ref().is_some_and(|chooser| chooser.output == stdout)
+            || self.cwd_file.as_deref() == Some(stdout)
+    }
+
+    // The browser state these options ask for, on top of the config.
+    pub fn open(&self) -> io::Result<AppState> {
+        let mut app_state = match &self.config_path {
+            Some(config_path) => AppState::load(config_path.clone())?,
+            None => AppState::new()?,
+        };
+        if app_state.config.home_folder.is_none() {
+            app_state.config.home_folder = Some(env::current_dir()?);
+        }
+        self.apply(&mut app_state)?;
+        Ok(app_state)
+    }
+
+    pub fn apply(&self, app_state: &mut AppState) -> io::Result<()> {
+        let invalid = |e: String| io::Error::new(io::ErrorKind::InvalidInput, e);
+        if let Some(profile) = &self.profile {
+            app_state
+                .config
+                .override_value("profile", profile)
+                .map_err(invalid)?;
+            app_state.switch_profile(Some(profile)).map_err(invalid)?;
+        }
+        if let Some(sort) = &self.sort {
+            app_state
+                .config
+                .override_value("default_sort", sort.to_string())
+                .map_err(invalid)?;
+        }
+        if self.simple_borders {
+            app_state
+                .config
+                .override_value("draw_simple_borders", "true")
+                .map_err(invalid)?;
+        }
+        app_state.mouse_enabled = !self.no_mouse;
+        app_state.start_search = self.search.clone();
+        app_state.chooser = self.chooser.clone();
+
+        if let Some(path) = &self.start_path {
+            let path = std::path::absolute(path)?;
+            let metadata = fs::metadata(&path).map_err(|e| {
+                io::Error::new(e.kind(), format!("can not open {}: {}", path.display(), e))
+            })?;
+            let dir = if metadata.is_dir() {
+                path.clone()
+            } else {
+                path.parent().map(Path::to_path_buf).unwrap_or_else(|| path.clone())
+            };
+            app_state.current_dir = dir.clone();
+            app_state.last_browsed_dir = dir.clone();
+            app_state.selected_index = if metadata.is_dir() {
+                0
+            } else {
+                let sort_order = app_state.config.default_sort.clone();
+                get_sorted_entries(app_state, &dir, &sort_order)?
+                    .iter()
+                    .position(|entry| entry.path == path)
+                    .unwrap_or(0)
+            };
+        }
+        Ok(())
+    }
+}
@@ -21,28 +21,71 @@
 
 use super::*;
 pub enum BrowseResult {
-    FileSelected,
+    // What the chooser picked.
+    FileSelected(Vec<PathBuf>),
     Exit,
     Continue,
 }
 
+// Merges in the .stygian files for dir. A sort or search depth they bring
+// along replaces the one in use.
+fn follow_local_config(app_state: &mut AppState, dir: &Path, sort_order: &mut SortOrder) {
+    let before = app_state.config.dir_settings();
+    let messages = app_state.config.enter_dir(dir);
+    app_state.config_messages.extend(messages);
+    if app_state.config.default_sort != before.default_sort {
+        *sort_order = app_state.config.default_sort.clone();
+    }
+    if app_state.config.search_depth_limit != before.search_depth_limit {
+        app_state.search_depth_limit = app_state.config.search_depth_limit;
+    }
+}
+
+fn reload_config(
+    app_state: &mut AppState,
+    dir: &Path,
+    sort_order: &mut SortOrder,
+) -> io::Result<bool> {
+    let before = app_state.config.default_sort.clone();
+    if !app_state.reload_changed_config(dir)? {
+        return Ok(false);
+    }
+    if app_state.config.default_sort != before {
+        *sort_order = app_state.config.default_sort.clone();
+    }
+    Ok(true)
+}
+
 pub fn browse_fuzzy_file(app_state: &mut AppState) -> io::Result<BrowseResult> {
-    stdout().flush()?;
-    terminal::enable_raw_mode()?;
-    execute!(stdout(), EnableMouseCapture)?;
-    // execute!(stdout(), EnableMouseCapture)?;
+    screen().flush()?;
+    enable_raw_mode()?;
+    if app_state.mouse_enabled {
+        execute!(screen(), EnableMouseCapture)?;
+    }
     let mut selected_index = app_state.selected_index;
     let mut current_dir = app_state.current_dir.clone();
     let mut scroll_offset = 0;
-    let mut stdout = io::stdout();
+    let mut stdout = screen();
     let mut terminal_state = TerminalState::new(80, 24)?;
     let mut sort_order = app_state.config.default_sort.clone();
     let mut last_entries: Vec<FileEntry> = Vec::new();
-    let mut last_key_time = Instant::now();
-    let mut g_pressed = false;
+    let mut showing_keys = false;
     let mut preview_active = false;
 
     execute!(stdout, terminal::Clear(ClearType::All))?;
+    let mut settings_dir = current_dir.clone();
+    follow_local_config(app_state, &settings_dir, &mut sort_order);
+    if let Some(search_term) = app_state.start_search.take() {
+        show_search_results(
+            app_state,
+            &search_term,
+            &mut current_dir,
+            &mut selected_index,
+            &mut scroll_offset,
+            &mut stdout,
+            &sort_order,
+        )?;
+    }
 
     let (width, height) = size()?;
     let visible_lines = (height - 9) as usize;
@@ -53,7 +96,19 @@ pub fn browse_fuzzy_file(app_state: &mut AppState) -> io::Result<BrowseResult> {
     let preview_width = width - nav_width;
 
     loop {
-        let entries = match get_sorted_entries(&app_state, &current_dir, &sort_order) {
+        // Leaving an archive closes it, the next visit opens it fresh.
+        if app_state
+            .archive_view
+            .as_ref()
+            .is_some_and(|view| !view.contains(&current_dir))
+        {
+            app_state.archive_view = None;
+        }
+        if current_dir != settings_dir {
+            settings_dir = current_dir.clone();
+            follow_local_config(app_state, &settings_dir, &mut sort_order);
+        }
+        let mut entries = match get_sorted_entries(&app_state, &current_dir, &sort_order) {
             Ok(entries) => entries,
             Err(e) if e.kind() == io::ErrorKind::PermissionDenied => {
                 let dir_name = current_dir
@@ -80,12 +135,36 @@ pub fn browse_fuzzy_file(app_state: &mut AppState) -> io::Result<BrowseResult> {
             Err(e) => return Err(e),
         };
 
+        // A macro being played runs its next step instead of waiting for a
+        // key, after moving onto the entry it plays on.
+        let mut replayed = None;
+        while replayed.is_none() {
+            match app_state.macro_replay.pop_front() {
+                Some(ReplayStep::Select(path)) => {
+                    match entries.iter().position(|entry| entry.path == path) {
+                        Some(index) => handle_go_to_line(
+                            index + 1,
+                            &mut selected_index,
+                            &mut scroll_offset,
+                            &entries,
+                            middle_line,
+                            visible_lines,
+                        ),
+                        None => skip_target(&mut app_state.macro_replay),
+                    }
+                }
+                Some(ReplayStep::Run(step)) => replayed = Some(step),
+                None => break,
+            }
+        }
+
         let mut full_redraw = entries != last_entries;
         if terminal_state.has_size_changed()? {
             terminal_state.update()?;
             full_redraw = true;
             // draw_initial_border(&mut stdout, &app_state.page_state)?;
         }
+        let frame = begin_frame();
         display_directory(
             app_state,
             &entries,
@@ -99,53 +178,135 @@ pub fn browse_fuzzy_file(app_state: &mut AppState) -> io::Result<BrowseResult> {
             visible_lines,
             full_redraw,
         )?;
+        drop(frame);
         last_entries = entries.clone();
-        if let Ok(event) = event::read() {
-            match event {
-                Event::Mouse(mouse_event) => {
-                    if app_state.input_mode != InputMode::Mouse {
-                        app_state.input_mode = InputMode::Mouse;
-                        selected_index = 0;
-                    }
-                    if let Some(result) = handle_mouse_event(
-                        app_state,
-                        mouse_event,
-                        &entries,
-                        visible_lines,
-                        start_y,
-                        nav_width,
-                    )? {
-                        return Ok(result);
+        if !app_state.config_messages.is_empty() {
+            let messages = std::mem::take(&mut app_state.config_messages);
+            match messages.len() {
+                1 => interaction_field!("{}", messages[0])?,
+                n => interaction_field!("{} (and {} more, see config check)", messages[0], n - 1)?,
+            }
+        }
+        if app_state.key_sequence.is_pending() {
+            interaction_field!("{}", app_state.key_sequence.hint(&app_state.config))?;
+            showing_keys = true;
+        } else if app_state.key_sequence.mode() == Mode::Visual {
+            interaction_field!("-- VISUAL --")?;
+            showing_keys = true;
+        } else if showing_keys {
+            clear_interaction_field()?;
+            showing_keys = false;
+        }
+        // What the last recorded step read is its input.
+        if let Some(recorder) = app_state.macro_recorder.as_mut() {
+            recorder.add_input(take_recorded_input());
+        }
+        // Wake up now and then to report finished jobs and to notice the
+        // config file being changed, and when held keys run out of time.
+        let mut changed = false;
+        let mut timed_out = false;
+        loop {
+            let time_left = app_state.key_sequence.time_left(&app_state.config);
+            let wait = time_left.map_or(Duration::from_millis(250), |left| {
+                left.min(Duration::from_millis(250))
+            });
+            if changed || replayed.is_some() || poll_event(wait)? {
+                break;
+            }
+            if time_left.is_some_and(|left| left <= wait) {
+                timed_out = true;
+                break;
+            }
+            changed = poll_background_jobs(app_state)?;
+            if reload_config(app_state, &current_dir, &mut sort_order)? {
+                changed = true;
+            }
+        }
+        if changed {
+            continue;
+        }
+        // A timeout runs what the held keys have so far. It stands in as an
+        // empty key event, told apart from a real Null key by timed_out.
+        // Reading only fails for good, or when a virtual terminal runs out
+        // of events.
+        let event = if replayed.is_some() || timed_out {
+            Event::Key(KeyEvent::from(KeyCode::Null))
+        } else {
+            read_event()?
+        };
+        match event {
+            Event::Mouse(mouse_event) => {
+                if app_state.input_mode != InputMode::Mouse {
+                    app_state.input_mode = InputMode::Mouse;
+                    selected_index = 0;
+                }
+                if let Some(result) = handle_mouse_event(
+                    app_state,
+                    mouse_event,
+                    &entries,
+                    visible_lines,
+                    start_y,
+                    nav_width,
+                )? {
+                    return Ok(result);
+                }
+            }
+            Event::Key(key) => {
+                if app_state.input_mode != InputMode::Keyboard {
+                    app_state.input_mode = InputMode::Keyboard;
+                    app_state.mouse_state.hovered_index = None;
+                }
+                if let Some(context_menu) = &mut app_state.mouse_state.context_menu {
+                    if let Some(action) = context_menu.handle_key_event(key).clone() {
+                        handle_context_menu_action(app_state, action, &entries)?;
+                        continue;
                     }
                 }
-                Event::Key(key) => {
-                    if app_state.input_mode != InputMode::Keyboard {
-                        app_state.input_mode = InputMode::Keyboard;
-                        app_state.mouse_state.hovered_index = None;
+
+                if !cfg!(target_os = "windows") || key.kind == KeyEventKind::Press {
+                    let actions = if replayed.is_some() {
+                        Vec::new()
+                    } else if timed_out {
+                        app_state.key_sequence.flush(&app_state.config)
+                    } else {
+                        app_state.key_sequence.feed(&app_state.config, key.into())
+                    };
+                    // The ":" line adds its commands here, with their arguments.
+                    let mut calls: VecDeque<CommandCall> =
+                        actions.into_iter().map(CommandCall::new).collect();
+                    if let Some(step) = replayed.take() {
+                        queue_input(&step.input);
+                        calls.push_back(step.call);
                     }
-                    if let Some(context_menu) = &mut app_state.mouse_state.context_menu {
-                        if let Some(action) = context_menu.handle_key_event(key).clone() {
-                            handle_context_menu_action(app_state, action, &entries)?;
+                    while let Some(call) = calls.pop_front() {
+                        let action = &call.action;
+                        if archive_blocks_action(app_state, action)? {
                             continue;
                         }
-                    }
-
-                    if !cfg!(target_os = "windows") || key.kind == KeyEventKind::Press {
-                        if let Some(action) = app_state.config.get_action(&key) {
-                            match action {
-                                Action::ExecuteFile => {
-                                    if let Some(entry) = entries.get(selected_index as usize) {
-                                        let _ = app_state.execute_file(&mut stdout, &entry.path);
-                                    }
+                        if let Some(recorder) = app_state.macro_recorder.as_mut() {
+                            recorder.add_input(take_recorded_input());
+                            if MacroStep::records(action) {
+                                recorder.begin(call.clone());
+                                record_input();
+                            }
+                        }
+                        let mode = app_state.key_sequence.mode();
+                        app_state.key_sequence.set_mode(mode.after(action));
+                        match action {
+                            Action::ExecuteFile => {
+                                if let Some(entry) = entries.get(selected_index as usize) {
+                                    let _ = app_state.execute_file(&mut stdout, &entry.path);
                                 }
-                                Action::TerminalCommand => {
-                                    if let Err(_e) = open_terminal_command(app_state, &mut stdout) {
-                                    }
-                                    current_dir = app_state.current_dir.clone();
-                                    selected_index = 0;
-                                    scroll_offset = 0;
+                            }
+                            Action::TerminalCommand => {
+                                if let Err(_e) = open_terminal_command(app_state, &mut stdout) {
                                 }
-                                Action::MultiSelectUp | Action::MultiSelectDown => {
+                                current_dir = app_state.current_dir.clone();
+                                selected_index = 0;
+                                scroll_offset = 0;
+                            }
+                            Action::MultiSelectUp | Action::MultiSelectDown => {
+                                for _ in 0..call.count() {
                                     handle_multi_select(
                                         app_state,
                                         &mut selected_index,
@@ -156,281 +317,413 @@ pub fn browse_fuzzy_file(app_state: &mut AppState) -> io::Result<BrowseResult> {
                                         visible_lines,
                                     );
                                 }
-                                Action::IncreaseDimDistance
-                                | Action::DecreaseDimDistance
-                                | Action::IncreaseDimIntensity
-                                | Action::DecreaseDimIntensity => {
-                                    let _ = handle_dim_controls(app_state, action.clone());
-                                }
-                                Action::BorderStyle => {
-                                    handle_change_border(app_state);
-                                    // let _ = full_redraw = true;
+                            }
+                            Action::VisualMode => {
+                                app_state.visual_anchor = Some(selected_index);
+                                app_state.multiple_selected_files = None;
+                            }
+                            Action::NormalMode => {
+                                if mode == Mode::Visual {
+                                    app_state.clear_multi_select()?;
                                 }
-                                Action::ToggleSelect => {
-                                    app_state.select_mode = !app_state.select_mode;
-                                    if app_state.select_mode {
-                                        app_state.selection_amont = None;
+                            }
+                            Action::CommandPalette => {
+                                match open_command_palette(app_state, &mut stdout)? {
+                                    Some(PaletteCommand::Action(action)) => {
+                                        calls.push_back(CommandCall::new(action));
                                     }
+                                    Some(PaletteCommand::Menu(label)) => {
+                                        let item = ContextMenu::new((0, 0))
+                                            .items
+                                            .into_iter()
+                                            .find(|item| item.label == label);
+                                        if let Some(item) = item {
+                                            app_state.selected_index = selected_index;
+                                            handle_context_menu_action(app_state, item.action, &entries)?;
+                                        }
+                                    }
+                                    Some(PaletteCommand::Custom(name)) => {
+                                        run_custom_command(app_state, &name)?;
+                                    }
+                                    None => {}
                                 }
-                                Action::TogglePreview => {
-                                    let _ = clear_preview();
-                                    app_state.preview_active = !app_state.preview_active;
-                                    preview_active = !preview_active;
-                                }
-                                Action::ToggleFilters => {
-                                    handle_search_filter_keys(app_state, key);
+                                last_entries.clear();
+                            }
+                            Action::RecordMacro => {
+                                toggle_macro_recording(app_state, call.text())?;
+                            }
+                            Action::PlayMacro => {
+                                play_macro(app_state, call.text())?;
+                            }
+                            Action::CommandLine => {
+                                app_state.key_sequence.set_mode(Mode::Command);
+                                let line = read_command_line(app_state)?;
+                                app_state.key_sequence.set_mode(Mode::Normal);
+                                match line.as_deref().map(parse_command_line) {
+                                    Some(Ok(more)) => calls.extend(more),
+                                    Some(Err(e)) => interaction_field!("{}", e)?,
+                                    None => {}
                                 }
-                                Action::RenameLayer => {
-                                    handle_layer_actions(
-                                        &Action::RenameLayer,
-                                        &mut stdout,
-                                        preview_width,
-                                        height,
-                                        app_state,
-                                    )?;
+                            }
+                            Action::IncreaseDimDistance
+                            | Action::DecreaseDimDistance
+                            | Action::IncreaseDimIntensity
+                            | Action::DecreaseDimIntensity => {
+                                let _ = handle_dim_controls(app_state, action.clone());
+                            }
+                            Action::BorderStyle => {
+                                handle_change_border(app_state);
+                                // let _ = full_redraw = true;
+                            }
+                            Action::ToggleSelect => {
+                                app_state.select_mode = !app_state.select_mode;
+                                if app_state.select_mode {
+                                    app_state.selection_amont = None;
                                 }
-                                Action::SwitchLayer1
-                                | Action::SwitchLayer2
-                                | Action::SwitchLayer3
-                                | Action::SwitchLayer4
-                                | Action::SwitchLayer5
-                                | Action::SwitchLayer6
-                                | Action::SwitchLayer7
-                                | Action::SwitchLayer8
-                                | Action::SwitchLayer9
-                                | Action::SwitchLayer0 => {
-                                    handle_layer_actions(
-                                        &action.clone(),
-                                        &mut stdout,
-                                        preview_width,
-                                        height,
-                                        app_state,
-                                    )?;
+                            }
+                            Action::TogglePreview => {
+                                let _ = clear_preview();
+                                app_state.preview_active = !app_state.preview_active;
+                                preview_active = !preview_active;
+                            }
+                            Action::PreviewFoldMore
+                            | Action::PreviewFoldLess
+                            | Action::PreviewScrollLeft
+                            | Action::PreviewScrollRight => {
+                                handle_structured_preview_keys(app_state, action.clone());
+                            }
+                            Action::ExtractMembers => {
+                                handle_extract_members(app_state, &entries, selected_index)?;
+                            }
+                            Action::PackSelection => {
+                                handle_pack_selection(
+                                    app_state,
+                                    &mut stdout,
+                                    &entries,
+                                    selected_index,
+                                )?;
+                            }
+                            Action::ExtractArchive => {
+                                handle_unpack_archive(app_state, &entries, selected_index)?;
+                            }
+                            Action::ChangePermissions => {
+                                handle_change_permissions(
+                                    app_state,
+                                    &mut stdout,
+                                    &mut entries,
+                                    selected_index,
+                                )?;
+                            }
+                            Action::ToggleFilters => {
+                                handle_search_filter_keys(app_state, key);
+                            }
+                            Action::RenameLayer => {
+                                handle_layer_actions(
+                                    &Action::RenameLayer,
+                                    &mut stdout,
+                                    preview_width,
+                                    height,
+                                    app_state,
+                                )?;
+                            }
+                            Action::SwitchLayer1
+                            | Action::SwitchLayer2
+                            | Action::SwitchLayer3
+                            | Action::SwitchLayer4
+                            | Action::SwitchLayer5
+                            | Action::SwitchLayer6
+                            | Action::SwitchLayer7
+                            | Action::SwitchLayer8
+                            | Action::SwitchLayer9
+                            | Action::SwitchLayer0 => {
+                                handle_layer_actions(
+                                    &action.clone(),
+                                    &mut stdout,
+                                    preview_width,
+                                    height,
+                                    app_state,
+                                )?;
 
-                                    app_state.display_current_layer(&mut stdout)?;
-                                }
+                                app_state.display_current_layer(&mut stdout)?;
+                            }
 
-                                #[rustfmt::skip]
-                        Action::SetShortcut1 | Action::SetShortcut2 | Action::SetShortcut3 |
-                        Action::SetShortcut4 | Action::SetShortcut5 | Action::SetShortcut6 |
-                        Action::SetShortcut7 | Action::SetShortcut8 | Action::SetShortcut9 |
-                        Action::SetShortcut0 => {
-                            handle_set_shortcut(
-                                app_state,
-                                &mut stdout,
-                                &current_dir,
-                                selected_index ,
-                                action.clone(),
-                                height,
-                                preview_width,
-                            )?;
-                        }
+                            #[rustfmt::skip]
+                    Action::SetShortcut1 | Action::SetShortcut2 | Action::SetShortcut3 |
+                    Action::SetShortcut4 | Action::SetShortcut5 | Action::SetShortcut6 |
+                    Action::SetShortcut7 | Action::SetShortcut8 | Action::SetShortcut9 |
+                    Action::SetShortcut0 => {
+                        handle_set_shortcut(
+                            app_state,
+                            &mut stdout,
+                            &current_dir,
+                            selected_index ,
+                            action.clone(),
+                            height,
+                            preview_width,
+                        )?;
+                    }
 
-                                #[rustfmt::skip]
-                        Action::UseShortcut1 | Action::UseShortcut2 | Action::UseShortcut3 |
-                        Action::UseShortcut4 | Action::UseShortcut5 | Action::UseShortcut6 |
-                        Action::UseShortcut7 | Action::UseShortcut8 | Action::UseShortcut9 |
-                        Action::UseShortcut0 => {
-                            handle_use_shortcut(
-                                app_state,
-                                &mut current_dir,
-                                &mut selected_index,
-                                &mut scroll_offset,
-                                action.clone(),
-                                &sort_order,
-                                &mut stdout,
-                                height,
-                            )?;
-                        }
-                                Action::SetColorRules => {
-                                    let _ = set_color_rules(app_state, &mut stdout);
-                                    let _ = app_state.config.save_config();
-                                }
-                                Action::ShowShortcuts => {
-                                    display_shortcuts(app_state, &mut stdout)?;
-                                    continue;
-                                }
-                                Action::ToggleCount => {
-                                    app_state.show_count = !app_state.show_count;
-                                    // not in use as of yet.
-                                }
-                                Action::SetLineAmount => {
-                                    if let Ok(new_lines) =
-                                        prompt_line_amount(app_state.lines, &app_state.page_state)
-                                    {
-                                        app_state.lines = new_lines;
-                                    }
-                                }
-                                Action::Help => {
-                                    display_help_screen(
-                                        &mut stdout,
-                                        &app_state.config,
-                                        app_state,
-                                        full_redraw,
-                                    )?;
-                                    let _ = clear_nav();
-                                    let _ = clear_preview();
-                                    continue;
-                                }
-                                Action::Quit => {
-                                    if handle_quit(app_state, &mut stdout)? {
-                                        return Err(io::Error::new(
-                                            io::ErrorKind::Interrupted,
-                                            "User quit",
-                                        ));
-                                    }
-                                }
-                                Action::CastCommandLineSpell => {
-                                    let _ = execute_terminal_command(app_state, &mut stdout);
-                                }
-                                Action::GiveBirthFile => {
-                                    let _ = app_state.create_file(&mut stdout);
-                                }
-                                Action::GiveBirthDir => {
-                                    let _ = app_state.create_directory(&mut stdout);
-                                }
-                                Action::SelectAll => {
-                                    let _ = app_state.select_all(&entries);
-                                }
-                                Action::CycleItemColor => {
-                                    app_state.cycle_item_color(&entries, selected_index)?;
-                                    let _ = app_state.config.save_config();
-                                }
-                                Action::RemoveItemColor => {
-                                    if let Some(selected_path) =
-                                        Config::get_selected_path(app_state, &entries)
-                                    {
-                                        app_state.remove_item_color(&selected_path);
-                                        let _ = app_state.config.save_config();
-                                    }
-                                }
-                                Action::Undo => {
-                                    let _ = undo_last_operation(app_state, &mut stdout);
-                                }
-                                Action::SearchFiles => {
-                                    handle_search_files(
-                                        app_state,
-                                        &mut current_dir,
-                                        &mut selected_index,
-                                        &mut scroll_offset,
-                                        &mut stdout,
-                                        &sort_order,
-                                    )?;
-                                }
-                                Action::OpenInEditor => {
-                                    handle_open_in_editor(
-                                        app_state,
-                                        &entries,
-                                        selected_index,
-                                        &mut stdout,
-                                        height,
-                                        &mut g_pressed,
-                                    )?;
-                                }
-                                Action::EditConfig => {
-                                    edit_config(app_state, &mut stdout, &current_dir)?;
-                                }
-                                Action::Rename => {
-                                    if let Some(entry) = entries.get(selected_index as usize) {
-                                        rename_file(&mut stdout, entry, true, app_state)?;
-                                    }
+                            #[rustfmt::skip]
+                    Action::UseShortcut1 | Action::UseShortcut2 | Action::UseShortcut3 |
+                    Action::UseShortcut4 | Action::UseShortcut5 | Action::UseShortcut6 |
+                    Action::UseShortcut7 | Action::UseShortcut8 | Action::UseShortcut9 |
+                    Action::UseShortcut0 => {
+                        handle_use_shortcut(
+                            app_state,
+                            &mut current_dir,
+                            &mut selected_index,
+                            &mut scroll_offset,
+                            action.clone(),
+                            &sort_order,
+                            &mut stdout,
+                            height,
+                        )?;
+                    }
+                            Action::SetColorRules => {
+                                let _ = set_color_rules(app_state, &mut stdout);
+                                let _ = app_state.save_config();
+                            }
+                            Action::ShowShortcuts => {
+                                display_shortcuts(app_state, &mut stdout)?;
+                                continue;
+                            }
+                            Action::ToggleCount => {
+                                app_state.show_count = !app_state.show_count;
+                                // not in use as of yet.
+                            }
+                            Action::SetLineAmount => {
+                                if let Ok(new_lines) =
+                                    prompt_line_amount(app_state.lines, &app_state.page_state)
+                                {
+                                    app_state.lines = new_lines;
                                 }
-                                Action::RenameWithoutExtension => {
-                                    if let Some(entry) = entries.get(selected_index as usize) {
-                                        rename_file(&mut stdout, entry, false, app_state)?;
-                                    }
+                            }
+                            Action::Help => {
+                                display_help_screen(
+                                    &mut stdout,
+                                    &app_state.config,
+                                    app_state,
+                                    full_redraw,
+                                )?;
+                                let _ = clear_nav();
+                                let _ = clear_preview();
+                                continue;
+                            }
+                            Action::Quit => {
+                                if handle_quit(app_state, &mut stdout)? {
+                                    return Ok(BrowseResult::Exit);
                                 }
-                                Action::Murder => {
-                                    if let Some(_entry) = entries.get(selected_index as usize) {
-                                        murder_files(
-                                            app_state,
-                                            &mut stdout,
-                                            &entries,
-                                            selected_index,
-                                            false,
-                                        )?;
-                                    }
+                            }
+                            Action::CastCommandLineSpell => {
+                                let _ = execute_terminal_command(app_state, &mut stdout);
+                            }
+                            Action::GiveBirthFile => {
+                                let _ = app_state.create_file(&mut stdout);
+                            }
+                            Action::GiveBirthDir => {
+                                let _ = app_state.create_directory(&mut stdout);
+                            }
+                            Action::SelectAll => {
+                                let _ = app_state.select_all(&entries);
+                            }
+                            Action::CycleItemColor => {
+                                app_state.cycle_item_color(&entries, selected_index)?;
+                                let _ = app_state.save_config();
+                            }
+                            Action::RemoveItemColor => {
+                                if let Some(selected_path) =
+                                    Config::get_selected_path(app_state, &entries)
+                                {
+                                    app_state.remove_item_color(&selected_path);
+                                    let _ = app_state.save_config();
                                 }
-                                Action::Copy => {
-                                    copy_files(app_state, &entries, selected_index);
+                            }
+                            Action::Undo => {
+                                let _ = undo_last_operation(app_state, &mut stdout);
+                            }
+                            Action::SearchFiles if call.text().is_some() => {
+                                show_search_results(
+                                    app_state,
+                                    &call.text().unwrap_or_default(),
+                                    &mut current_dir,
+                                    &mut selected_index,
+                                    &mut scroll_offset,
+                                    &mut stdout,
+                                    &sort_order,
+                                )?;
+                            }
+                            Action::SearchFiles => {
+                                handle_search_files(
+                                    app_state,
+                                    &mut current_dir,
+                                    &mut selected_index,
+                                    &mut scroll_offset,
+                                    &mut stdout,
+                                    &sort_order,
+                                )?;
+                            }
+                            Action::OpenInEditor => {
+                                handle_open_in_editor(
+                                    app_state,
+                                    &entries,
+                                    selected_index,
+                                    &mut stdout,
+                                    height,
+                                )?;
+                            }
+                            Action::EditConfig => {
+                                edit_config(app_state, &mut stdout, &current_dir)?;
+                            }
+                            Action::Rename => {
+                                if let Some(entry) = entries.get(selected_index as usize) {
+                                    rename_file(&mut stdout, entry, true, app_state)?;
                                 }
-                                Action::Paste => {
-                                    paste_files(app_state, &current_dir)?;
+                            }
+                            Action::RenameWithoutExtension => {
+                                if let Some(entry) = entries.get(selected_index as usize) {
+                                    rename_file(&mut stdout, entry, false, app_state)?;
                                 }
-                                Action::Duplicate => {
-                                    handle_duplicate(
+                            }
+                            Action::Murder => {
+                                if let Some(_entry) = entries.get(selected_index as usize) {
+                                    murder_files(
                                         app_state,
                                         &mut stdout,
                                         &entries,
                                         selected_index,
+                                        false,
                                     )?;
                                 }
-                                Action::MoveItem => {
-                                    handle_move_item(
-                                        app_state,
-                                        &entries,
-                                        selected_index,
-                                        &current_dir,
-                                    )?;
-                                }
-                                // removed from code for the time being
-                                Action::Search => {
-                                    continue;
-                                }
-                                // Is unstable at the moment.
-                                Action::GitMenu => {
-                                    handle_git_menu(
-                                        app_state,
-                                        &mut stdout,
-                                        &current_dir,
-                                        nav_width,
-                                        preview_width,
-                                        start_y,
-                                        _end_y,
-                                        height,
-                                    )?;
-                                }
-                                Action::SortCycleForward => {
-                                    handle_sort_cycle(
+                            }
+                            Action::Copy => {
+                                copy_files(app_state, &entries, selected_index);
+                            }
+                            Action::Paste => {
+                                paste_files(app_state, &current_dir)?;
+                            }
+                            Action::Duplicate => {
+                                handle_duplicate(
+                                    app_state,
+                                    &mut stdout,
+                                    &entries,
+                                    selected_index,
+                                )?;
+                            }
+                            Action::MoveItem => {
+                                handle_move_item(
+                                    app_state,
+                                    &entries,
+                                    selected_index,
+                                    &current_dir,
+                                )?;
+                            }
+                            // removed from code for the time being
+                            Action::Search => {
+                                continue;
+                            }
+                            // Is unstable at the moment.
+                            Action::GitMenu => {
+                                handle_git_menu(
+                                    app_state,
+                                    &mut stdout,
+                                    &current_dir,
+                                    nav_width,
+                                    preview_width,
+                                    start_y,
+                                    _end_y,
+                                    height,
+                                )?;
+                            }
+                            Action::SortCycleForward if call.text().is_some() => {
+                                if let Some(order) = call.text().as_deref().and_then(SortOrder::parse) {
+                                    handle_sort_by(
                                         app_state,
+                                        order,
                                         &mut sort_order,
                                         &mut selected_index,
                                         &mut scroll_offset,
                                     );
                                 }
-                                Action::MoveRight | Action::Enter => {
-                                    handle_move_right(
-                                        app_state,
-                                        &mut current_dir,
-                                        &mut selected_index,
-                                        &mut scroll_offset,
-                                        &mut preview_active,
-                                        &entries,
-                                        &mut stdout,
-                                    )?;
-                                }
-                                Action::MoveLeft => {
-                                    handle_move_left(
-                                        app_state,
-                                        &mut current_dir,
-                                        &mut selected_index,
-                                        &mut scroll_offset,
-                                        &mut preview_active,
-                                        &sort_order,
-                                        &mut stdout,
-                                    )?;
-                                }
-                                Action::GoToTop | Action::GoToBottom => {
-                                    handle_go_to_top_bottom(
-                                        action.clone(),
-                                        &mut g_pressed,
-                                        &mut last_key_time,
-                                        &mut selected_index,
-                                        &mut scroll_offset,
-                                        &entries,
-                                    );
+                            }
+                            Action::SortCycleForward => {
+                                handle_sort_cycle(
+                                    app_state,
+                                    &mut sort_order,
+                                    &mut selected_index,
+                                    &mut scroll_offset,
+                                );
+                            }
+                            Action::MoveRight | Action::Enter => {
+                                if matches!(action, Action::Enter) {
+                                    if let Some(paths) = app_state.chooser.as_ref().and_then(
+                                        |chooser| {
+                                            chooser.pick(app_state, entries.get(selected_index))
+                                        },
+                                    ) {
+                                        return Ok(BrowseResult::FileSelected(paths));
+                                    }
                                 }
-                                Action::MoveDown | Action::MoveUp => {
+                                handle_move_right(
+                                    app_state,
+                                    &mut current_dir,
+                                    &mut selected_index,
+                                    &mut scroll_offset,
+                                    &mut preview_active,
+                                    &entries,
+                                    &mut stdout,
+                                )?;
+                            }
+                            Action::MoveLeft => {
+                                handle_move_left(
+                                    app_state,
+                                    &mut current_dir,
+                                    &mut selected_index,
+                                    &mut scroll_offset,
+                                    &mut preview_active,
+                                    &sort_order,
+                                    &mut stdout,
+                                )?;
+                            }
+                            Action::GoToTop if call.number().is_some() => {
+                                handle_go_to_line(
+                                    call.count(),
+                                    &mut selected_index,
+                                    &mut scroll_offset,
+                                    &entries,
+                                    middle_line,
+                                    visible_lines,
+                                );
+                            }
+                            Action::GoToTop | Action::GoToBottom => {
+                                handle_go_to_top_bottom(
+                                    action.clone(),
+                                    &mut selected_index,
+                                    &mut scroll_offset,
+                                    &entries,
+                                );
+                            }
+                            Action::GoHome if call.text().is_some() => {
+                                handle_go_to_dir(
+                                    app_state,
+                                    &call.text().unwrap_or_default(),
+                                    &mut current_dir,
+                                    &mut selected_index,
+                                    &mut scroll_offset,
+                                )?;
+                            }
+                            Action::GoHome => {
+                                handle_go_home(
+                                    app_state,
+                                    &mut current_dir,
+                                    &mut selected_index,
+                                    &mut scroll_offset,
+                                );
+                            }
+                            Action::YankPath => {
+                                yank_paths(app_state, &mut stdout, &entries, selected_index)?;
+                            }
+                            Action::MoveDown | Action::MoveUp => {
+                                for _ in 0..call.count() {
                                     let _ = handle_move_updown(
                                         action.clone(),
                                         &mut selected_index,
@@ -441,22 +734,34 @@ pub fn browse_fuzzy_file(app_state: &mut AppState) -> io::Result<BrowseResult> {
                                         app_state,
                                         app_state.is_search,
                                     );
-                                    g_pressed = false;
                                 }
                             }
-                            // } else {
-                            //     println!("action not mapped");
-                            // }
                         }
+                        // Visual mode keeps the selection from where it started
+                        // to the cursor, leaving it keeps what was selected.
+                        match app_state.visual_anchor {
+                            Some(anchor)
+                                if app_state.key_sequence.mode() == Mode::Visual
+                                    && !entries.is_empty() =>
+                            {
+                                let anchor = anchor.min(entries.len() - 1);
+                                app_state.select_range(selected_index, anchor, selected_index, &entries)?;
+                            }
+                            _ => app_state.visual_anchor = None,
+                        }
+                        // } else {
+                        //     println!("action not mapped");
+                        // }
                     }
+                    clear_queued_input();
                 }
-                Event::FocusGained => {
-                    full_redraw = true;
-                }
-                Event::FocusLost => {}
-                Event::Paste(data) => {}
-                _ => {}
             }
+            Event::FocusGained => {
+                full_redraw = true;
+            }
+            Event::FocusLost => {}
+            Event::Paste(data) => {}
+            _ => {}
         }
     }
 }
@@ -29,22 +29,33 @@ macro_rules! interaction_field {
     };
 }
 
+pub mod archive;
 #[allow(clippy::complexity, dead_code, clippy::if_same_then_else)]
 pub mod browser_commands;
+pub mod chooser;
+pub mod color_depth;
+pub mod command_line;
+pub mod command_palette;
+pub mod compression;
 pub mod config;
-pub mod file_entry;
+pub mod data_preview;
+pub mod jobs;
+pub mod launch;
 pub mod main_nav_loop;
-pub mod marvelous_actions;
 pub mod mouse;
 pub mod nav_functions;
+pub mod permissions;
+pub mod recording;
+pub mod renderer;
 pub mod system_functions;
 pub mod the_search;
+pub mod theme;
 pub mod tome_state;
 pub mod ui_components;
 
 //////////////////////////////////////////DEPENDENCIES///////////////////////////////////////////////
 // If possible, do not add any more dependencies. Rather work to remove them.
-pub use crossterm::{
+use crossterm::{
     cursor::{Hide, MoveTo, Show},
     event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
     event::{DisableMouseCapture, EnableMouseCapture},
@@ -53,36 +64,33 @@ pub use crossterm::{
         Attribute, Color, ResetColor, SetAttribute, SetBackgroundColor, SetForegroundColor,
         StyledContent, Stylize,
     },
-    terminal::{
-        disable_raw_mode, enable_raw_mode, size, Clear, ClearType, EnterAlternateScreen,
-        LeaveAlternateScreen, SetSize,
-    },
+    terminal::{Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen, SetSize},
     {cursor, execute, queue, terminal},
 };
 
-pub use rayon::prelude::*;
+use rayon::prelude::*;
 
 /////////////////////////////////////////!DEPENDENCIES!//////////////////////////////////////////////
 pub use self::{
-    browser_commands::*, config::*, file_entry::*, main_nav_loop::*, marvelous_actions::*,
-    mouse::*, nav_functions::*, system_functions::*, the_search::*, tome_state::*,
-    ui_components::*,
+    archive::*, browser_commands::*, chooser::*, color_depth::*, command_line::*,
+    command_palette::*, compression::*, config::*, data_preview::*, jobs::*, launch::*,
+    main_nav_loop::*, mouse::*, nav_functions::*, permissions::*, recording::*, renderer::*, system_functions::*,
+    the_search::*, theme::*, tome_state::*, ui_components::*,
 };
+pub use crate::the_core::*;
 #[cfg(unix)]
 use std::os::unix::fs::MetadataExt;
 #[cfg(windows)]
 use std::os::windows::fs::MetadataExt;
-pub use std::{
+use std::{
     cmp::Ordering,
     collections::{BTreeMap, HashMap, HashSet, VecDeque},
     env,
-    fmt::{Arguments, Display, Formatter, Result as OtherResult},
     fs::{self, File, OpenOptions},
-    io::{self, stdout, BufRead, Read, Write},
+    io::{self, Read, Write},
     path::{Path, PathBuf},
     process::{Child, Command, Stdio},
     result::Result,
-    slice::Iter,
     str::FromStr,
     time::{Duration, Instant, SystemTime, UNIX_EPOCH},
     usize,
@@ -91,6 +99,5 @@ pub use std::{
 pub const INPUT_TIMEOUT: Duration = Duration::from_secs(30);
 pub const MAX_NAME_LENGTH: usize = 255;
 pub const VISIBLE_LINES: usize = 35;
-pub const DEFAULT_RAM_LIMIT: usize = 500 * 1024 * 1024;
-pub const DEFAULT_DISK_LIMIT: u64 = 2 * 1024 * 1024 * 1024;
 pub const PREVIEW_LIMIT: usize = 5 * 1024;
+pub const STRUCTURED_PREVIEW_LIMIT: usize = 512 * 1024;
@@ -416,12 +416,12 @@ impl ContextMenu {
                     let hover_index = (event.row - menu_y - 1) as usize;
                     if hover_index < self.items.len() {
                         self.hovered = Some(hover_index);
-                        let _ = self.draw(&mut std::io::stdout());
+                        let _ = self.draw(&mut screen());
                     }
                 } else {
                     if self.hovered.is_some() {
                         self.hovered = None;
-                        let _ = self.draw(&mut std::io::stdout());
+                        let _ = self.draw(&mut screen());
                     }
                 }
             }
@@ -474,10 +474,27 @@ pub fn handle_context_menu_action(
     entries: &[FileEntry],
 ) -> io::Result<()> {
     let sort_order = app_state.config.default_sort.clone();
+    let equivalent = match action {
+        ContextMenuAction::Open => Some(Action::ExecuteFile),
+        ContextMenuAction::Copy => Some(Action::Copy),
+        ContextMenuAction::Cut => Some(Action::MoveItem),
+        ContextMenuAction::Paste => Some(Action::Paste),
+        ContextMenuAction::Rename => Some(Action::Rename),
+        ContextMenuAction::Delete => Some(Action::Murder),
+        ContextMenuAction::Duplicate => Some(Action::Duplicate),
+        ContextMenuAction::CreateFile => Some(Action::GiveBirthFile),
+        ContextMenuAction::CreateDirectory => Some(Action::GiveBirthDir),
+        _ => None,
+    };
+    let blocked = match equivalent {
+        Some(equivalent) => archive_blocks_action(app_state, &equivalent)?,
+        None => false,
+    };
     match action {
+        _ if blocked => {}
         ContextMenuAction::Open => {
             if let Some(entry) = entries.get(app_state.selected_index) {
-                app_state.execute_file(&mut stdout(), &entry.path)?;
+                app_state.execute_file(&mut screen(), &entry.path)?;
             }
         }
         ContextMenuAction::Copy => {
@@ -494,13 +511,13 @@ pub fn handle_context_menu_action(
         }
         ContextMenuAction::Rename => {
             if let Some(entry) = entries.get(app_state.selected_index) {
-                rename_file(&mut stdout(), entry, true, app_state)?;
+                rename_file(&mut screen(), entry, true, app_state)?;
             }
         }
         ContextMenuAction::Delete => {
             app_state.mouse_state.context_menu = None;
 
-            let mut stdout = stdout();
+            let mut stdout = screen();
             if app_state.config.draw_simple_borders {
                 draw_simple_border(&mut stdout, &app_state.page_state)?;
             } else {
@@ -529,7 +546,7 @@ pub fn handle_context_menu_action(
             dialog.draw(&mut stdout)?;
 
             while dialog.result.is_none() {
-                if let Ok(event) = event::read() {
+                if let Ok(event) = read_event() {
                     if let Some(confirmed) = dialog.confirmation_handle_event(event) {
                         if confirmed {
                             murder_files(
@@ -564,7 +581,7 @@ pub fn handle_context_menu_action(
                 let preview = app_state.preview_active;
                 display_file_info_or_preview(
                     app_state,
-                    &mut stdout(),
+                    &mut screen(),
                     entry,
                     0,
                     0,
@@ -575,13 +592,13 @@ pub fn handle_context_menu_action(
             }
         }
         ContextMenuAction::Duplicate => {
-            handle_duplicate(app_state, &mut stdout(), entries, app_state.selected_index)?;
+            handle_duplicate(app_state, &mut screen(), entries, app_state.selected_index)?;
         }
         ContextMenuAction::CreateFile => {
-            app_state.create_file(&mut stdout())?;
+            app_state.create_file(&mut screen())?;
         }
         ContextMenuAction::CreateDirectory => {
-            app_state.create_directory(&mut stdout())?;
+            app_state.create_directory(&mut screen())?;
         }
         ContextMenuAction::SelectAll => {
             app_state.select_all(entries);
@@ -590,7 +607,7 @@ pub fn handle_context_menu_action(
     }
 
     app_state.mouse_state.context_menu = None;
-    let mut stdout = stdout();
+    let mut stdout = screen();
     if app_state.config.draw_simple_borders {
         draw_simple_border(&mut stdout, &app_state.page_state)?;
     } else {
@@ -612,7 +629,7 @@ pub fn handle_context_menu_action(
 }
 
 pub fn show_context_menu(app_state: &mut AppState, position: (u16, u16)) -> io::Result<()> {
-    let mut stdout = stdout();
+    let mut stdout = screen();
     let context_menu = ContextMenu::new(position);
     context_menu.draw(&mut stdout)?;
     app_state.mouse_state.context_menu = Some(context_menu);
@@ -647,7 +664,7 @@ pub fn handle_mouse_event(
                         &mut scroll_offset,
                         &mut preview_active,
                         &app_state.config.default_sort.clone(),
-                        &mut stdout(),
+                        &mut screen(),
                     )
                     .map(|_| Some(BrowseResult::Continue));
                 }
@@ -691,7 +708,7 @@ pub fn handle_mouse_event(
                         entries,
                         &app_state.current_dir.clone(),
                         app_state.selected_index,
-                        &mut stdout(),
+                        &mut screen(),
                         app_state.scroll_state.offset,
                         visible_lines,
                         false,
@@ -704,7 +721,7 @@ pub fn handle_mouse_event(
                     entries,
                     &app_state.current_dir.clone(),
                     app_state.selected_index,
-                    &mut stdout(),
+                    &mut screen(),
                     app_state.scroll_state.offset,
                     visible_lines,
                     false,
@@ -732,7 +749,7 @@ pub fn handle_mouse_event(
                             &mut scroll_offset,
                             &mut preview_active,
                             &app_state.config.default_sort.clone(),
-                            &mut stdout(),
+                            &mut screen(),
                         )
                         .map(|_| Some(BrowseResult::Continue));
                     }
@@ -766,7 +783,7 @@ pub fn handle_mouse_event(
                                 &mut scroll_offset,
                                 &mut preview_active,
                                 entries,
-                                &mut stdout(),
+                                &mut screen(),
                             )
                             .map(|_| Some(BrowseResult::Continue));
                         }
@@ -801,7 +818,7 @@ pub fn handle_mouse_event(
                         entries,
                         &app_state.current_dir.clone(),
                         app_state.selected_index,
-                        &mut stdout(),
+                        &mut screen(),
                         app_state.scroll_state.offset,
                         visible_lines,
                         false,
@@ -851,7 +868,7 @@ pub fn handle_mouse_event(
                                 entries,
                                 &app_state.current_dir.clone(),
                                 app_state.selected_index,
-                                &mut stdout(),
+                                &mut screen(),
                                 app_state.scroll_state.offset,
                                 visible_lines,
                                 false,
@@ -879,7 +896,7 @@ pub fn handle_mouse_event(
                     entries,
                     &app_state.current_dir.clone(),
                     app_state.selected_index,
-                    &mut stdout(),
+                    &mut screen(),
                     app_state.scroll_state.offset,
                     visible_lines,
                     false,
@@ -896,7 +913,7 @@ pub fn handle_mouse_event(
                     entries,
                     &app_state.current_dir.clone(),
                     app_state.selected_index,
-                    &mut stdout(),
+                    &mut screen(),
                     app_state.scroll_state.offset,
                     visible_lines,
                     false,
@@ -906,7 +923,7 @@ pub fn handle_mouse_event(
 
         MouseEventKind::Down(MouseButton::Right) => {
             if event.column < nav_width {
-                let mut stdout = stdout();
+                let mut stdout = screen();
                 if app_state.config.draw_simple_borders {
                     draw_simple_border(&mut stdout, &app_state.page_state)?;
                 } else {
@@ -85,7 +85,7 @@ pub fn handle_layer_switch(
         "-".green().to_string().repeat((preview_width - 4).into())
     )?;
     let _ = clear_interaction_field();
-    app_state.config.save_config()?;
+    app_state.save_config()?;
     Ok(())
 }
 
@@ -107,7 +107,7 @@ pub fn handle_layer_rename(
         app_state
             .config
             .rename_layer(current_layer, new_name.trim().to_string())?;
-        app_state.config.save_config()?;
+        app_state.save_config()?;
     }
     let _ = clear_interaction_field();
     queue!(stdout, SetForegroundColor(Color::Reset))?;
@@ -177,7 +177,7 @@ pub fn handle_set_shortcut(
         shortcut_name.to_string(),
         selected_index,
     )?;
-    app_state.config.save_config()?;
+    app_state.save_config()?;
 
     let layer_name = &app_state.config.shortcut_layers[current_layer].name;
     queue!(stdout, MoveTo(preview_width + 3, height - 10))?;
@@ -308,8 +308,30 @@ pub fn handle_search_files(
 ) -> io::Result<()> {
     app_state.is_search = true;
     let search_term = read_search_input(stdout, app_state)?;
+    show_search_results(
+        app_state,
+        &search_term,
+        current_dir,
+        selected_index,
+        scroll_offset,
+        stdout,
+        sort_order,
+    )
+}
+
+// Lists what search_term finds and goes to the result picked from it.
+pub fn show_search_results(
+    app_state: &mut AppState,
+    search_term: &str,
+    current_dir: &mut PathBuf,
+    selected_index: &mut usize,
+    scroll_offset: &mut usize,
+    stdout: &mut impl Write,
+    sort_order: &SortOrder,
+) -> io::Result<()> {
+    app_state.is_search = true;
     if !search_term.is_empty() {
-        let search_results = fuzzy_search_entries(app_state, &search_term)?;
+        let search_results = app_state.search_entries(search_term)?;
         if let Ok(Some(selected_path)) = display_search_results(app_state, search_results, stdout) {
             app_state.nav_stack.push(NavigationInfo {
                 dir_name: app_state
@@ -346,30 +368,26 @@ pub fn handle_open_in_editor(
     selected_index: usize,
     stdout: &mut impl Write,
     height: u16,
-    g_pressed: &mut bool,
 ) -> io::Result<()> {
-    if !*g_pressed {
-        if let Some(entry) = entries.get(selected_index as usize) {
-            if entry.file_type != FileType::Directory {
-                match open_file_with_editor(&entry.path, &app_state.config.text_editor, stdout) {
-                    Ok(_) => {
-                        terminal::enable_raw_mode()?;
-                        execute!(
-                            stdout,
-                            terminal::EnterAlternateScreen,
-                            terminal::Clear(ClearType::All),
-                            cursor::Hide
-                        )?;
-                    }
-                    Err(e) => {
-                        let _ = clear_interaction_field();
-                        interaction_field!("Error opening file: {}", e)?;
-                    }
+    if let Some(entry) = entries.get(selected_index as usize) {
+        if entry.file_type != FileType::Directory {
+            match open_file_with_editor(&entry.path, &app_state.config.text_editor, stdout) {
+                Ok(_) => {
+                    enable_raw_mode()?;
+                    execute!(
+                        stdout,
+                        terminal::EnterAlternateScreen,
+                        terminal::Clear(ClearType::All),
+                        cursor::Hide
+                    )?;
+                }
+                Err(e) => {
+                    let _ = clear_interaction_field();
+                    interaction_field!("Error opening file: {}", e)?;
                 }
             }
         }
     }
-    *g_pressed = false;
     Ok(())
 }
 
@@ -458,7 +476,7 @@ pub fn handle_git_menu(
                 display_git_menu(git_menu, stdout, nav_width, preview_width, start_y, end_y)?;
             }
 
-            if let Event::Key(key) = event::read()? {
+            if let Event::Key(key) = read_event()? {
                 match key.code {
                     KeyCode::Up => {
                         if let Some(git_menu) = &mut app_state.git_menu {
@@ -496,7 +514,7 @@ pub fn handle_sort_cycle(
     selected_index: &mut usize,
     scroll_offset: &mut usize,
 ) {
-    app_state.config.default_sort = match app_state.config.default_sort {
+    let next = match app_state.config.default_sort {
         SortOrder::NameAsc => SortOrder::NameDesc,
         SortOrder::NameDesc => SortOrder::TypeAsc,
         SortOrder::TypeAsc => SortOrder::TypeDesc,
@@ -508,6 +526,17 @@ pub fn handle_sort_cycle(
         SortOrder::ColorAsc => SortOrder::ColorDesc,
         SortOrder::ColorDesc => SortOrder::NameAsc,
     };
+    handle_sort_by(app_state, next, sort_order, selected_index, scroll_offset);
+}
+
+pub fn handle_sort_by(
+    app_state: &mut AppState,
+    order: SortOrder,
+    sort_order: &mut SortOrder,
+    selected_index: &mut usize,
+    scroll_offset: &mut usize,
+) {
+    app_state.config.default_sort = order;
     *sort_order = app_state.config.default_sort.clone();
     *selected_index = 0;
     *scroll_offset = 0;
@@ -528,8 +557,30 @@ pub fn handle_move_right(
     let nav_width = width / 2;
     let preview_width = width - nav_width - 1;
     if let Some(entry) = entries.get(*selected_index as usize) {
-        if entry.file_type == FileType::Directory {
-            match fs::read_dir(&entry.path) {
+        let virtual_dir = app_state
+            .archive_view
+            .as_ref()
+            .is_some_and(|view| view.contains(&entry.path));
+        let is_archive = entry.file_type != FileType::Directory
+            && !virtual_dir
+            && ArchiveKind::from_path(&entry.path).is_some();
+        if is_archive {
+            match ArchiveView::open(&entry.path) {
+                Ok(view) => app_state.archive_view = Some(view),
+                Err(e) => {
+                    interaction_field!("Could not open {}: {}", entry.name, e)?;
+                    return Ok(());
+                }
+            }
+        }
+        if entry.file_type == FileType::Directory || is_archive {
+            // Directories inside an archive only exist in the view.
+            let listing = if virtual_dir || is_archive {
+                Ok(())
+            } else {
+                fs::read_dir(&entry.path).map(|_| ())
+            };
+            match listing {
                 Ok(_) => {
                     if let Some(nav_info) = app_state.nav_stack.pop() {
                         if nav_info.dir_name == entry.name {
@@ -643,38 +694,78 @@ pub fn handle_move_left(
 
 pub fn handle_go_to_top_bottom(
     action: Action,
-    g_pressed: &mut bool,
-    last_key_time: &mut Instant,
     selected_index: &mut usize,
     scroll_offset: &mut usize,
     entries: &[FileEntry],
 ) {
     match action {
         Action::GoToTop => {
-            if *g_pressed && last_key_time.elapsed() < Duration::from_millis(500) {
-                *selected_index = 0;
-                *scroll_offset = 0;
-                *g_pressed = false;
-            } else {
-                *g_pressed = true;
-                *last_key_time = Instant::now();
-            }
+            *selected_index = 0;
+            *scroll_offset = 0;
         }
         Action::GoToBottom => {
-            if *g_pressed && last_key_time.elapsed() < Duration::from_millis(500) {
-                *selected_index = entries.len() - 1;
-                if entries.len() > VISIBLE_LINES as usize {
-                    *scroll_offset = entries.len() - VISIBLE_LINES as usize;
-                }
-                *g_pressed = false;
-            } else {
-                *g_pressed = false;
+            *selected_index = entries.len().saturating_sub(1);
+            if entries.len() > VISIBLE_LINES as usize {
+                *scroll_offset = entries.len() - VISIBLE_LINES as usize;
             }
         }
         _ => {}
     }
 }
 
+// Line numbers start at 1, past the end is the last entry.
+pub fn handle_go_to_line(
+    line: usize,
+    selected_index: &mut usize,
+    scroll_offset: &mut usize,
+    entries: &[FileEntry],
+    middle_line: usize,
+    visible_lines: usize,
+) {
+    *selected_index = line.saturating_sub(1).min(entries.len().saturating_sub(1));
+    *scroll_offset = selected_index
+        .saturating_sub(middle_line)
+        .min(entries.len().saturating_sub(visible_lines));
+}
+
+// A path from the ":" line, relative to the current directory.
+pub fn handle_go_to_dir(
+    app_state: &mut AppState,
+    path: &str,
+    current_dir: &mut PathBuf,
+    selected_index: &mut usize,
+    scroll_offset: &mut usize,
+) -> io::Result<()> {
+    let target = match (path.strip_prefix('~'), &app_state.config.home_folder) {
+        (Some(rest), Some(home)) => home.join(rest.trim_start_matches('/')),
+        _ => current_dir.join(path),
+    };
+    if !target.is_dir() {
+        return interaction_field!("Not a directory: {}", target.display());
+    }
+    *current_dir = fs::canonicalize(&target).unwrap_or(target);
+    app_state.current_dir = current_dir.clone();
+    *selected_index = 0;
+    *scroll_offset = 0;
+    let _ = clear_nav();
+    Ok(())
+}
+
+pub fn handle_go_home(
+    app_state: &mut AppState,
+    current_dir: &mut PathBuf,
+    selected_index: &mut usize,
+    scroll_offset: &mut usize,
+) {
+    if let Some(home) = app_state.config.home_folder.clone() {
+        *current_dir = home;
+        app_state.current_dir = current_dir.clone();
+        *selected_index = 0;
+        *scroll_offset = 0;
+        let _ = clear_nav();
+    }
+}
+
 pub fn handle_move_updown(
     action: Action,
     selected_index: &mut usize,
@@ -717,33 +808,33 @@ pub fn handle_move_updown(
     }
 
     if !is_search && !app_state.preview_active && !app_state.select_mode {
-        queue!(stdout(), MoveTo(preview_width, height - 12))?;
+        queue!(screen(), MoveTo(preview_width, height - 12))?;
         write!(
-            stdout(),
+            screen(),
             "{}",
             " ".green().to_string().repeat((preview_width - 6).into())
         )?;
-        queue!(stdout(), MoveTo(preview_width, height - 11))?;
+        queue!(screen(), MoveTo(preview_width, height - 11))?;
         write!(
-            stdout(),
+            screen(),
             "{}",
             " ".green().to_string().repeat((preview_width - 6).into())
         )?;
-        queue!(stdout(), MoveTo(preview_width, height - 10))?;
+        queue!(screen(), MoveTo(preview_width, height - 10))?;
         write!(
-            stdout(),
+            screen(),
             "{}",
             " ".green().to_string().repeat((preview_width - 6).into())
         )?;
-        queue!(stdout(), MoveTo(preview_width, height - 9))?;
+        queue!(screen(), MoveTo(preview_width, height - 9))?;
         write!(
-            stdout(),
+            screen(),
             "{}",
             " ".green().to_string().repeat((preview_width - 6).into())
         )?;
-        queue!(stdout(), MoveTo(preview_width, height - 8))?;
+        queue!(screen(), MoveTo(preview_width, height - 8))?;
         write!(
-            stdout(),
+            screen(),
             "{}",
             " ".green().to_string().repeat((preview_width - 6).into())
         )?;
@@ -829,7 +920,7 @@ fn toggle_filter(app_state: &mut AppState, color: MarkerColor) -> bool {
 }
 // didn't work consistantly with just "border.config.draw_simple_borders = !border.config.draw_simple_borders"
 pub fn handle_change_border(border: &mut AppState) {
-    let mut stdout = stdout();
+    let mut stdout = screen();
     if border.config.draw_simple_borders {
         let _ = execute!(stdout, Clear(ClearType::All));
         border.config.draw_simple_borders = !border.config.draw_simple_borders;
@@ -839,7 +930,20 @@ pub fn handle_change_border(border: &mut AppState) {
         border.config.draw_simple_borders = !border.config.draw_simple_borders;
         let _ = draw_simple_border(&mut stdout, &border.page_state);
     }
-    let _ = border.config.save_config();
+    let _ = border.save_config();
+}
+pub fn handle_structured_preview_keys(app_state: &mut AppState, action: Action) {
+    if !app_state.preview_active {
+        return;
+    }
+    let preview = &mut app_state.structured_preview;
+    match action {
+        Action::PreviewFoldMore => preview.fold_more(),
+        Action::PreviewFoldLess => preview.fold_less(),
+        Action::PreviewScrollLeft => preview.scroll_left(),
+        Action::PreviewScrollRight => preview.scroll_right(),
+        _ => {}
+    }
 }
 pub fn handle_dim_controls(app_state: &mut AppState, action: Action) -> io::Result<()> {
     match action {
@@ -861,6 +965,6 @@ pub fn handle_dim_controls(app_state: &mut AppState, action: Action) -> io::Resu
         }
         _ => {}
     }
-    app_state.config.save_config()?;
+    app_state.save_config()?;
     Ok(())
 }
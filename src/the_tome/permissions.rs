@@ -0,0 +1,846 @@
+/*
+* Stygian Sift - A Terminal-based File Manager
+ * Copyright (C) 2024 Maui The Magnificent (Charon)
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published
+ * by the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+ * Contact: Maui_The_Magnificent@proton.me
+ * Project repository: https://github.com/Mauitron/StygianSift.git
+ */
+
+use super::*;
+use std::sync::OnceLock;
+//////////////////////////////////////////////////////Permissions/////////////////////////////////////////////////////////////////////
+// Who owns a file and what they may do with it. Names come straight from
+// /etc/passwd and /etc/group, extended attributes and ACLs from the kernel.
+
+const ACL_XATTRS: [(&str, &str); 2] = [
+    ("system.posix_acl_access", "ACL"),
+    ("system.posix_acl_default", "Default ACL"),
+];
+const MAX_XATTRS_SHOWN: usize = 8;
+
+// name:x:id:... lines, shared by passwd and group.
+fn read_id_names(path: &str) -> HashMap<u32, String> {
+    fs::read_to_string(path)
+        .unwrap_or_default()
+        .lines()
+        .filter(|line| !line.starts_with('#'))
+        .filter_map(|line| {
+            let mut fields = line.split(':');
+            let name = fields.next()?;
+            let id = fields.nth(1)?.parse().ok()?;
+            Some((id, name.to_string()))
+        })
+        .collect()
+}
+
+fn users() -> &'static HashMap<u32, String> {
+    static USERS: OnceLock<HashMap<u32, String>> = OnceLock::new();
+    USERS.get_or_init(|| read_id_names("/etc/passwd"))
+}
+
+fn groups() -> &'static HashMap<u32, String> {
+    static GROUPS: OnceLock<HashMap<u32, String>> = OnceLock::new();
+    GROUPS.get_or_init(|| read_id_names("/etc/group"))
+}
+
+pub fn user_name(uid: u32) -> Option<&'static str> {
+    users().get(&uid).map(String::as_str)
+}
+
+pub fn group_name(gid: u32) -> Option<&'static str> {
+    groups().get(&gid).map(String::as_str)
+}
+
+// A name or a number, the way chown takes them.
+fn find_id(names: &HashMap<u32, String>, text: &str) -> Option<u32> {
+    text.parse().ok().or_else(|| {
+        names
+            .iter()
+            .find(|(_, name)| name.as_str() == text)
+            .map(|(id, _)| *id)
+    })
+}
+
+pub fn user_id(text: &str) -> Option<u32> {
+    find_id(users(), text)
+}
+
+pub fn group_id(text: &str) -> Option<u32> {
+    find_id(groups(), text)
+}
+
+fn user_label(uid: u32) -> String {
+    user_name(uid).map_or_else(|| uid.to_string(), str::to_string)
+}
+
+fn group_label(gid: u32) -> String {
+    group_name(gid).map_or_else(|| gid.to_string(), str::to_string)
+}
+
+// The nine rwx characters, with s/S and t/T for the special bits like ls.
+pub fn mode_string(mode: u32) -> String {
+    let special = |set: bool, execute: bool, lower: char, upper: char, plain: char| match (set, execute) {
+        (true, true) => lower,
+        (true, false) => upper,
+        (false, true) => plain,
+        (false, false) => '-',
+    };
+    let mut out = String::with_capacity(9);
+    for (shift, special_bit, lower, upper) in [
+        (6, 0o4000, 's', 'S'),
+        (3, 0o2000, 's', 'S'),
+        (0, 0o1000, 't', 'T'),
+    ] {
+        let bits = (mode >> shift) & 0o7;
+        out.push(if bits & 0o4 != 0 { 'r' } else { '-' });
+        out.push(if bits & 0o2 != 0 { 'w' } else { '-' });
+        out.push(special(mode & special_bit != 0, bits & 0o1 != 0, lower, upper, 'x'));
+    }
+    out
+}
+
+#[cfg(unix)]
+fn file_type_char(mode: u32) -> char {
+    match mode & 0o170000 {
+        0o040000 => 'd',
+        0o120000 => 'l',
+        0o020000 => 'c',
+        0o060000 => 'b',
+        0o010000 => 'p',
+        0o140000 => 's',
+        _ => '-',
+    }
+}
+
+// Permissions as ls shows them, followed by the octal mode.
+#[cfg(unix)]
+pub fn describe_mode(mode: u32) -> String {
+    format!(
+        "{}{} ({:04o})",
+        file_type_char(mode),
+        mode_string(mode),
+        mode & 0o7777
+    )
+}
+
+#[cfg(unix)]
+pub fn describe_owner(uid: u32, gid: u32) -> String {
+    format!("{}:{} ({}:{})", user_label(uid), group_label(gid), uid, gid)
+}
+
+/////////////////////////////////////////////////////Extended Attributes//////////////////////////////////////////////////////////////
+#[cfg(target_os = "linux")]
+mod sys {
+    use std::ffi::{c_char, c_void, CString};
+    use std::io;
+    use std::os::unix::ffi::OsStrExt;
+    use std::path::Path;
+
+    extern "C" {
+        fn listxattr(path: *const c_char, list: *mut c_char, size: usize) -> isize;
+        fn getxattr(path: *const c_char, name: *const c_char, value: *mut c_void, size: usize)
+            -> isize;
+    }
+
+    fn c_path(path: &Path) -> io::Result<CString> {
+        CString::new(path.as_os_str().as_bytes())
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "path contains a nul byte"))
+    }
+
+    // Asks for the size first, then fetches. The value may grow in between,
+    // in which case the kernel says ERANGE and we simply ask again.
+    fn sized_call(mut call: impl FnMut(*mut u8, usize) -> isize) -> io::Result<Vec<u8>> {
+        for _ in 0..4 {
+            let size = call(std::ptr::null_mut(), 0);
+            if size < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            let mut buffer = vec![0u8; size as usize];
+            let read = call(buffer.as_mut_ptr(), buffer.len());
+            if read >= 0 {
+                buffer.truncate(read as usize);
+                return Ok(buffer);
+            }
+            let error = io::Error::last_os_error();
+            if error.raw_os_error() != Some(34) {
+                return Err(error);
+            }
+        }
+        Err(io::Error::other("extended attributes kept changing"))
+    }
+
+    pub fn list(path: &Path) -> io::Result<Vec<String>> {
+        let path = c_path(path)?;
+        // SAFETY: the path is nul terminated and the buffer is as long as we say.
+        let names = sized_call(|buffer, size| unsafe {
+            listxattr(path.as_ptr(), buffer as *mut c_char, size)
+        })?;
+        Ok(names
+            .split(|&b| b == 0)
+            .filter(|name| !name.is_empty())
+            .map(|name| String::from_utf8_lossy(name).into_owned())
+            .collect())
+    }
+
+    pub fn get(path: &Path, name: &str) -> io::Result<Vec<u8>> {
+        let path = c_path(path)?;
+        let name = CString::new(name).map_err(|_| io::Error::other("bad attribute name"))?;
+        // SAFETY: as above, both strings are nul terminated.
+        sized_call(|buffer, size| unsafe {
+            getxattr(path.as_ptr(), name.as_ptr(), buffer as *mut c_void, size)
+        })
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+mod sys {
+    use std::io;
+    use std::path::Path;
+
+    pub fn list(_path: &Path) -> io::Result<Vec<String>> {
+        Ok(Vec::new())
+    }
+
+    pub fn get(_path: &Path, _name: &str) -> io::Result<Vec<u8>> {
+        Err(io::Error::from(io::ErrorKind::Unsupported))
+    }
+}
+
+pub fn list_xattrs(path: &Path) -> io::Result<Vec<String>> {
+    sys::list(path)
+}
+
+pub fn get_xattr(path: &Path, name: &str) -> io::Result<Vec<u8>> {
+    sys::get(path, name)
+}
+
+// Text values are shown as they are, anything else as hex.
+fn describe_xattr_value(value: &[u8]) -> String {
+    let text = value.strip_suffix(&[0]).unwrap_or(value);
+    match std::str::from_utf8(text) {
+        Ok(text) if !text.chars().any(char::is_control) => format!("\"{}\"", text),
+        _ => {
+            let hex: String = value.iter().take(32).map(|b| format!("{:02x}", b)).collect();
+            if value.len() > 32 {
+                format!("0x{}… ({} bytes)", hex, value.len())
+            } else {
+                format!("0x{}", hex)
+            }
+        }
+    }
+}
+
+// The kernel's binary ACL: a version word, then tag, permissions and id
+// for every entry. Rendered the way getfacl writes them.
+pub fn describe_acl(value: &[u8]) -> Option<String> {
+    const ACL_VERSION: u32 = 2;
+    if value.len() < 4 || u32::from_le_bytes(value[..4].try_into().ok()?) != ACL_VERSION {
+        return None;
+    }
+    let entries: Vec<String> = value[4..]
+        .chunks_exact(8)
+        .map(|entry| {
+            let tag = u16::from_le_bytes([entry[0], entry[1]]);
+            let perm = u16::from_le_bytes([entry[2], entry[3]]) as u32;
+            let id = u32::from_le_bytes([entry[4], entry[5], entry[6], entry[7]]);
+            let rwx = &mode_string(perm)[6..];
+            match tag {
+                0x01 => format!("user::{}", rwx),
+                0x02 => format!("user:{}:{}", user_label(id), rwx),
+                0x04 => format!("group::{}", rwx),
+                0x08 => format!("group:{}:{}", group_label(id), rwx),
+                0x10 => format!("mask::{}", rwx),
+                0x20 => format!("other::{}", rwx),
+                _ => format!("?{:x}::{}", tag, rwx),
+            }
+        })
+        .collect();
+    Some(entries.join(", "))
+}
+
+// Info pane rows for ACLs and extended attributes, empty when there are none.
+pub fn attribute_rows(path: &Path) -> Vec<(&'static str, String)> {
+    let names = match list_xattrs(path) {
+        Ok(names) => names,
+        Err(_) => return Vec::new(),
+    };
+    let mut rows = Vec::new();
+    for (xattr, label) in ACL_XATTRS {
+        if names.iter().any(|name| name == xattr) {
+            if let Some(acl) = get_xattr(path, xattr).ok().and_then(|value| describe_acl(&value)) {
+                rows.push((label, acl));
+            }
+        }
+    }
+
+    let plain: Vec<&String> = names
+        .iter()
+        .filter(|name| !ACL_XATTRS.iter().any(|(xattr, _)| xattr == name))
+        .collect();
+    for name in plain.iter().take(MAX_XATTRS_SHOWN) {
+        let value = get_xattr(path, name)
+            .map(|value| describe_xattr_value(&value))
+            .unwrap_or_else(|e| format!("<{}>", e.kind()));
+        rows.push(("Attribute", format!("{} = {}", name, value)));
+    }
+    if plain.len() > MAX_XATTRS_SHOWN {
+        rows.push(("Attribute", format!("{} more", plain.len() - MAX_XATTRS_SHOWN)));
+    }
+    rows
+}
+
+//////////////////////////////////////////////////////Mode Editor/////////////////////////////////////////////////////////////////////
+// Every bit is either set, cleared or left as it is, so one edit can be
+// applied to a selection of entries that started out with different modes.
+// Owner and group are likewise either given or left alone.
+
+const MODE_BITS: [[(u32, char); 3]; 4] = [
+    [(0o400, 'r'), (0o200, 'w'), (0o100, 'x')],
+    [(0o040, 'r'), (0o020, 'w'), (0o010, 'x')],
+    [(0o004, 'r'), (0o002, 'w'), (0o001, 'x')],
+    [(0o4000, 's'), (0o2000, 's'), (0o1000, 't')],
+];
+const MODE_ROWS: [&str; 4] = ["Owner", "Group", "Other", "Special"];
+const MODE_COLUMNS: [&str; 4] = ["read", "write", "exec", "(setuid setgid sticky)"];
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ModeMask {
+    pub set: u32,
+    pub clear: u32,
+}
+
+impl ModeMask {
+    // A mask that turns any mode into exactly `mode`.
+    pub fn exact(mode: u32) -> Self {
+        Self {
+            set: mode & 0o7777,
+            clear: !mode & 0o7777,
+        }
+    }
+
+    pub fn apply(&self, mode: u32) -> u32 {
+        (mode & 0o7777 & !self.clear) | self.set
+    }
+
+    // Keep, set, clear and round again.
+    fn cycle(&mut self, bit: u32) {
+        if self.set & bit != 0 {
+            self.set &= !bit;
+            self.clear |= bit;
+        } else if self.clear & bit != 0 {
+            self.clear &= !bit;
+        } else {
+            self.set |= bit;
+        }
+    }
+
+    pub fn is_keep_all(&self) -> bool {
+        self.set == 0 && self.clear == 0
+    }
+}
+
+pub struct ModeChange {
+    pub path: PathBuf,
+    pub old_mode: u32,
+    pub new_mode: u32,
+    pub old_owner: (u32, u32),
+    pub new_owner: (u32, u32),
+}
+
+#[cfg(unix)]
+fn current_mode(metadata: &fs::Metadata) -> u32 {
+    use std::os::unix::fs::MetadataExt;
+    metadata.mode() & 0o7777
+}
+
+#[cfg(unix)]
+fn current_owner(metadata: &fs::Metadata) -> (u32, u32) {
+    use std::os::unix::fs::MetadataExt;
+    (metadata.uid(), metadata.gid())
+}
+
+// The preview pass. Symlinks are never followed or changed, chmod would
+// land on whatever they point at. Returns the changes and how many entries
+// were looked at but left alone.
+#[cfg(unix)]
+pub fn plan_mode_changes(
+    targets: &[PathBuf],
+    file_mask: ModeMask,
+    dir_mask: ModeMask,
+    owner: (Option<u32>, Option<u32>),
+    recursive: bool,
+) -> (Vec<ModeChange>, usize) {
+    fn visit(
+        path: &Path,
+        masks: (ModeMask, ModeMask),
+        owner: (Option<u32>, Option<u32>),
+        recursive: bool,
+        changes: &mut Vec<ModeChange>,
+        unchanged: &mut usize,
+    ) {
+        let metadata = match fs::symlink_metadata(path) {
+            Ok(metadata) if !metadata.file_type().is_symlink() => metadata,
+            _ => {
+                *unchanged += 1;
+                return;
+            }
+        };
+        let mask = if metadata.is_dir() { masks.1 } else { masks.0 };
+        let old_mode = current_mode(&metadata);
+        let new_mode = mask.apply(old_mode);
+        let old_owner = current_owner(&metadata);
+        let new_owner = (owner.0.unwrap_or(old_owner.0), owner.1.unwrap_or(old_owner.1));
+        if new_mode == old_mode && new_owner == old_owner {
+            *unchanged += 1;
+        } else {
+            changes.push(ModeChange {
+                path: path.to_path_buf(),
+                old_mode,
+                new_mode,
+                old_owner,
+                new_owner,
+            });
+        }
+        if recursive && metadata.is_dir() {
+            if let Ok(read_dir) = fs::read_dir(path) {
+                let mut children: Vec<PathBuf> = read_dir.filter_map(|e| e.ok()).map(|e| e.path()).collect();
+                children.sort();
+                for child in children {
+                    visit(&child, masks, owner, recursive, changes, unchanged);
+                }
+            }
+        }
+    }
+
+    let mut changes = Vec::new();
+    let mut unchanged = 0;
+    for target in targets {
+        visit(target, (file_mask, dir_mask), owner, recursive, &mut changes, &mut unchanged);
+    }
+    (changes, unchanged)
+}
+
+// Children go first, taking x off a directory would otherwise lock us out of
+// everything below it. The owner goes before the mode, chown can drop the
+// setuid and setgid bits. Returns the old modes and owners of what was
+// actually changed.
+#[cfg(unix)]
+pub fn apply_mode_changes(
+    fs: &dyn FileSystem,
+    changes: &[ModeChange],
+) -> (PreviousModes, PreviousOwners, Vec<(PathBuf, io::Error)>) {
+    let mut modes = Vec::new();
+    let mut owners = Vec::new();
+    let mut failed = Vec::new();
+    for change in changes.iter().rev() {
+        let chown = change.new_owner != change.old_owner;
+        if chown {
+            let (uid, gid) = change.new_owner;
+            match fs.set_owner(&change.path, uid, gid) {
+                Ok(()) => owners.push((change.path.clone(), change.old_owner)),
+                Err(e) => {
+                    failed.push((change.path.clone(), e));
+                    continue;
+                }
+            }
+        }
+        if chown || change.new_mode != change.old_mode {
+            match fs.set_mode(&change.path, change.new_mode) {
+                Ok(()) => modes.push((change.path.clone(), change.old_mode)),
+                Err(e) => failed.push((change.path.clone(), e)),
+            }
+        }
+    }
+    (modes, owners, failed)
+}
+
+struct ModeEditor {
+    file_mask: ModeMask,
+    dir_mask: ModeMask,
+    owner: Option<u32>,
+    group: Option<u32>,
+    editing_dirs: bool,
+    recursive: bool,
+    row: usize,
+    column: usize,
+}
+
+impl ModeEditor {
+    fn mask_mut(&mut self) -> &mut ModeMask {
+        if self.editing_dirs {
+            &mut self.dir_mask
+        } else {
+            &mut self.file_mask
+        }
+    }
+
+    fn mask(&self) -> ModeMask {
+        if self.editing_dirs {
+            self.dir_mask
+        } else {
+            self.file_mask
+        }
+    }
+}
+
+fn draw_mode_editor(
+    stdout: &mut impl Write,
+    editor: &ModeEditor,
+    summary: &str,
+    has_dirs: bool,
+) -> io::Result<()> {
+    let (width, height) = size()?;
+    let nav_width = width / 2;
+    let preview_width = width - nav_width - 2;
+    let x = nav_width / 8;
+    let _ = clear_nav();
+    let _ = clear_preview();
+
+    let title = "Change Permissions";
+    execute!(stdout, MoveTo(nav_width / 3, 3))?;
+    write!(stdout, "{}", title.bold().green())?;
+    execute!(stdout, MoveTo(nav_width / 3, 4))?;
+    write!(stdout, "{}", "=".repeat(title.len()).green())?;
+    execute!(stdout, MoveTo(x, 6))?;
+    write!(stdout, "{}", truncate_str(summary, nav_width as usize - x as usize - 2).yellow())?;
+
+    execute!(stdout, MoveTo(x, 8))?;
+    let (files, dirs) = if editor.editing_dirs {
+        ("Files".to_string().dark_grey(), "[Directories]".to_string().cyan().bold())
+    } else {
+        ("[Files]".to_string().cyan().bold(), "Directories".to_string().dark_grey())
+    };
+    write!(stdout, "Editing: {}  {}", files, dirs)?;
+
+    execute!(stdout, MoveTo(x + 10, 10))?;
+    write!(stdout, "{}", MODE_COLUMNS[..3].join("  ").dark_grey())?;
+    let mask = editor.mask();
+    for (row, (label, bits)) in MODE_ROWS.iter().zip(MODE_BITS.iter()).enumerate() {
+        execute!(stdout, MoveTo(x, 11 + row as u16))?;
+        write!(stdout, "{:<9}", label)?;
+        for (column, (bit, letter)) in bits.iter().enumerate() {
+            let cell = if mask.set & bit != 0 {
+                letter.to_string().green()
+            } else if mask.clear & bit != 0 {
+                "-".to_string().red()
+            } else {
+                "·".to_string().dark_grey()
+            };
+            let cursor = row == editor.row && column == editor.column;
+            if cursor {
+                write!(stdout, " {}{}{}  ", "[".green(), cell, "]".green())?;
+            } else {
+                write!(stdout, "  {}   ", cell)?;
+            }
+        }
+        if row == 3 {
+            write!(stdout, "{}", MODE_COLUMNS[3].dark_grey())?;
+        }
+    }
+
+    execute!(stdout, MoveTo(x, 16))?;
+    write!(
+        stdout,
+        "Recursive: {}",
+        if editor.recursive { "Yes".green() } else { "No".red() }
+    )?;
+    execute!(stdout, MoveTo(x, 17))?;
+    write!(
+        stdout,
+        "Files: {}   Directories: {}",
+        mask_preview(editor.file_mask).green(),
+        mask_preview(editor.dir_mask).green()
+    )?;
+    execute!(stdout, MoveTo(x, 18))?;
+    let label =
+        |id: Option<u32>, name: fn(u32) -> String| id.map_or_else(|| "unchanged".to_string(), name);
+    write!(
+        stdout,
+        "Owner: {}   Group: {}",
+        label(editor.owner, user_label).green(),
+        label(editor.group, group_label).green()
+    )?;
+
+    let mut hints = vec![
+        "Use ←↓↑→ (or h/j/k/l) to move",
+        "Space cycles keep (·), set and clear (-)",
+        "r toggles recursive application",
+        "o and g change the owner and group",
+        "Enter previews the changes, ESC cancels",
+    ];
+    if has_dirs {
+        hints.insert(2, "Tab switches between file and directory bits");
+    }
+    execute!(stdout, MoveTo(preview_width + 2, height - 12))?;
+    write!(stdout, "{}", "-".repeat((preview_width - 4) as usize).green())?;
+    for (i, hint) in hints.iter().enumerate() {
+        execute!(stdout, MoveTo(preview_width + 4, height - 11 + i as u16))?;
+        write!(stdout, "{}", hint)?;
+    }
+    stdout.flush()
+}
+
+// What a mask does to a mode, with · for the bits it leaves alone.
+fn mask_preview(mask: ModeMask) -> String {
+    if mask.is_keep_all() {
+        return "unchanged".to_string();
+    }
+    let mut out = String::new();
+    for (row, bits) in MODE_BITS.iter().take(3).enumerate() {
+        for (column, (bit, letter)) in bits.iter().enumerate() {
+            let c = if mask.set & bit != 0 {
+                *letter
+            } else if mask.clear & bit != 0 {
+                '-'
+            } else {
+                '·'
+            };
+            let (special, special_letter) = MODE_BITS[3][row];
+            out.push(match c {
+                _ if column != 2 || mask.set & special == 0 => c,
+                'x' => special_letter,
+                _ => special_letter.to_ascii_uppercase(),
+            });
+        }
+    }
+    out
+}
+
+// Lists the planned changes and asks before touching anything.
+#[cfg(unix)]
+fn confirm_mode_changes(
+    stdout: &mut impl Write,
+    current_dir: &Path,
+    changes: &[ModeChange],
+    unchanged: usize,
+) -> io::Result<bool> {
+    let (width, height) = size()?;
+    let nav_width = width / 2;
+    let preview_width = width - nav_width - 2;
+    let _ = clear_preview();
+
+    execute!(stdout, MoveTo(nav_width + 4, 3))?;
+    write!(
+        stdout,
+        "{}",
+        format!("{} will change, {} stay as they are", changes.len(), unchanged).bold().green()
+    )?;
+    let rows = height.saturating_sub(10) as usize;
+    for (i, change) in changes.iter().take(rows).enumerate() {
+        let shown = change.path.strip_prefix(current_dir).unwrap_or(&change.path);
+        let mut line = format!(
+            "{} → {}  ",
+            mode_string(change.old_mode),
+            mode_string(change.new_mode)
+        );
+        if change.new_owner != change.old_owner {
+            let (old_uid, old_gid) = change.old_owner;
+            let (new_uid, new_gid) = change.new_owner;
+            line.push_str(&format!(
+                "{}:{} → {}:{}  ",
+                user_label(old_uid),
+                group_label(old_gid),
+                user_label(new_uid),
+                group_label(new_gid)
+            ));
+        }
+        line.push_str(&shown.display().to_string());
+        execute!(stdout, MoveTo(nav_width + 4, 5 + i as u16))?;
+        write!(stdout, "{}", truncate_str(&line, preview_width as usize - 6))?;
+    }
+    if changes.len() > rows {
+        execute!(stdout, MoveTo(nav_width + 4, 5 + rows as u16))?;
+        write!(stdout, "{}", format!("… and {} more", changes.len() - rows).dark_grey())?;
+    }
+    stdout.flush()?;
+
+    interaction_field!("Apply these permission changes? (y/n)")?;
+    loop {
+        if let Event::Key(key) = read_event()? {
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            return Ok(matches!(key.code, KeyCode::Char('y') | KeyCode::Char('Y')));
+        }
+    }
+}
+
+// An empty answer leaves it alone again, a name that is not known keeps
+// what was there.
+#[cfg(unix)]
+fn ask_for_id(id: &mut Option<u32>, what: &str, lookup: fn(&str) -> Option<u32>) -> io::Result<()> {
+    interaction_field!("New {} by name or id, empty leaves it as it is:", what)?;
+    let input = read_line()?;
+    let input = input.trim();
+    if input.is_empty() {
+        *id = None;
+    } else if let Some(found) = lookup(input) {
+        *id = Some(found);
+    } else {
+        interaction_field!("There is no {} called {}", what, input)?;
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+pub fn handle_change_permissions(
+    app_state: &mut AppState,
+    stdout: &mut impl Write,
+    entries: &mut [FileEntry],
+    selected_index: usize,
+) -> io::Result<()> {
+    if app_state.in_archive() {
+        interaction_field!("Archives are read-only, extract members to work on them")?;
+        return Ok(());
+    }
+    let targets: Vec<PathBuf> = match &app_state.multiple_selected_files {
+        Some(selected) if !selected.is_empty() => {
+            let mut targets: Vec<_> = selected.iter().cloned().collect();
+            targets.sort();
+            targets
+        }
+        _ => match entries.get(selected_index) {
+            Some(entry) => vec![entry.path.clone()],
+            None => return Ok(()),
+        },
+    };
+
+    let dir_count = targets.iter().filter(|path| path.is_dir()).count();
+    let file_count = targets.len() - dir_count;
+    let mut editor = ModeEditor {
+        file_mask: ModeMask::default(),
+        dir_mask: ModeMask::default(),
+        owner: None,
+        group: None,
+        editing_dirs: file_count == 0,
+        recursive: false,
+        row: 0,
+        column: 0,
+    };
+    // A single entry starts out showing its own mode.
+    if let [target] = targets.as_slice() {
+        if let Ok(metadata) = fs::symlink_metadata(target) {
+            *editor.mask_mut() = ModeMask::exact(current_mode(&metadata));
+        }
+    }
+    let summary = match targets.as_slice() {
+        [target] => target.file_name().unwrap_or_default().to_string_lossy().into_owned(),
+        _ => format!("{} files and {} directories selected", file_count, dir_count),
+    };
+
+    loop {
+        draw_mode_editor(stdout, &editor, &summary, dir_count > 0)?;
+        let key = match read_event()? {
+            Event::Key(key) if key.kind == KeyEventKind::Press => key,
+            _ => continue,
+        };
+        match key.code {
+            KeyCode::Up | KeyCode::Char('k') => editor.row = editor.row.saturating_sub(1),
+            KeyCode::Down | KeyCode::Char('j') => editor.row = (editor.row + 1).min(3),
+            KeyCode::Left | KeyCode::Char('h') => editor.column = editor.column.saturating_sub(1),
+            KeyCode::Right | KeyCode::Char('l') => editor.column = (editor.column + 1).min(2),
+            KeyCode::Char(' ') => {
+                let bit = MODE_BITS[editor.row][editor.column].0;
+                editor.mask_mut().cycle(bit);
+            }
+            KeyCode::Tab if dir_count > 0 => editor.editing_dirs = !editor.editing_dirs,
+            KeyCode::Char('r') => editor.recursive = !editor.recursive,
+            KeyCode::Char('o') => ask_for_id(&mut editor.owner, "owner", user_id)?,
+            KeyCode::Char('g') => ask_for_id(&mut editor.group, "group", group_id)?,
+            KeyCode::Enter => break,
+            KeyCode::Esc => {
+                let _ = clear_nav();
+                let _ = clear_preview();
+                interaction_field!("Permissions left as they were")?;
+                return Ok(());
+            }
+            _ => {}
+        }
+    }
+
+    let (changes, unchanged) = plan_mode_changes(
+        &targets,
+        editor.file_mask,
+        editor.dir_mask,
+        (editor.owner, editor.group),
+        editor.recursive,
+    );
+    if changes.is_empty() {
+        let _ = clear_nav();
+        let _ = clear_preview();
+        interaction_field!("Nothing to change, all {} entries already match", unchanged)?;
+        return Ok(());
+    }
+    let confirmed = confirm_mode_changes(stdout, &app_state.current_dir, &changes, unchanged)?;
+    let _ = clear_nav();
+    let _ = clear_preview();
+    if !confirmed {
+        interaction_field!("Permissions left as they were")?;
+        return Ok(());
+    }
+
+    let (modes, owners, failed) = apply_mode_changes(app_state.fs.as_ref(), &changes);
+    for entry in entries.iter_mut() {
+        if modes.iter().any(|(path, _)| *path == entry.path) {
+            entry.refresh_permissions();
+        }
+    }
+    let changed = modes.len();
+    // The owner is undone first, so the mode is put back after chown.
+    if !modes.is_empty() {
+        app_state.undo_manager.add_tome_entry(UndoEntry {
+            original_path: modes[0].0.clone(),
+            operation: Operation::ChangeMode {
+                previous_modes: modes,
+                timestamp: SystemTime::now(),
+            },
+            storage: UndoStorage::Ram(Vec::new()),
+            size: 0,
+        })?;
+    }
+    if !owners.is_empty() {
+        app_state.undo_manager.add_tome_entry(UndoEntry {
+            original_path: owners[0].0.clone(),
+            operation: Operation::ChangeOwner {
+                previous_owners: owners,
+                timestamp: SystemTime::now(),
+            },
+            storage: UndoStorage::Ram(Vec::new()),
+            size: 0,
+        })?;
+    }
+    match failed.first() {
+        Some((path, e)) => interaction_field!(
+            "Changed {} entries, {} failed ({}: {})",
+            changed,
+            failed.len(),
+            path.display(),
+            e
+        )?,
+        None => interaction_field!("Changed permissions of {} entries", changed)?,
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+pub fn handle_change_permissions(
+    _app_state: &mut AppState,
+    _stdout: &mut impl Write,
+    _entries: &mut [FileEntry],
+    _selected_index: usize,
+) -> io::Result<()> {
+    interaction_field!("Changing permissions needs a Unix system")
+}
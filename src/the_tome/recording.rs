@@ -0,0 +1,105 @@
+/*
+* Stygian Sift - A Terminal-based File Manager
+ * Copyright (C) 2024 Maui The Magnificent (Charon)
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published
+ * by the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+ * Contact: Maui_The_Magnificent@proton.me
+ * Project repository: https://github.com/Mauitron/StygianSift.git
+ */
+
+// Recording and playing macros. The main loop records each action it runs
+// and the keys read meanwhile, and plays a macro back one step at a time.
+
+use super::*;
+
+// Starts recording under name, asked for when there is none, or stops the
+// recording and keeps the macro.
+pub fn toggle_macro_recording(app_state: &mut AppState, name: Option<String>) -> io::Result<()> {
+    if let Some(recorder) = app_state.macro_recorder.take() {
+        take_recorded_input();
+        if recorder.steps.is_empty() {
+            return interaction_field!("Nothing recorded for '{}'", recorder.name);
+        }
+        let steps = recorder.steps.len();
+        app_state
+            .config
+            .macros
+            .insert(recorder.name.clone(), recorder.steps);
+        app_state.save_config()?;
+        return interaction_field!("Macro '{}' saved with {} step(s)", recorder.name, steps);
+    }
+    let name = match name {
+        Some(name) => name,
+        None => {
+            interaction_field!("Record macro named: ")?;
+            read_line()?.trim().to_string()
+        }
+    };
+    if name.is_empty() {
+        return clear_interaction_field();
+    }
+    let keys = app_state.config.keys_for(&Action::RecordMacro);
+    let stop = keys
+        .first()
+        .map_or(":record".to_string(), |keys| keys.to_string());
+    app_state.macro_recorder = Some(MacroRecorder::new(&name));
+    interaction_field!("Recording '{}', {} stops it", name, stop)
+}
+
+// Plays name once on each entry of the multi-selection, or on the current
+// entry without one. Asks which macro when there is more than one.
+pub fn play_macro(app_state: &mut AppState, name: Option<String>) -> io::Result<()> {
+    let name = match name {
+        Some(name) => name,
+        None if app_state.config.macros.len() == 1 => app_state
+            .config
+            .macros
+            .keys()
+            .next()
+            .cloned()
+            .unwrap_or_default(),
+        None if app_state.config.macros.is_empty() => {
+            return interaction_field!("No macros recorded yet");
+        }
+        None => {
+            let names: Vec<&str> = app_state.config.macros.keys().map(String::as_str).collect();
+            interaction_field!("Play macro ({}): ", names.join(", "))?;
+            read_line()?.trim().to_string()
+        }
+    };
+    if name.is_empty() {
+        return clear_interaction_field();
+    }
+    if app_state
+        .macro_recorder
+        .as_ref()
+        .is_some_and(|recorder| recorder.name == name)
+    {
+        return interaction_field!("'{}' can not play while it is recorded", name);
+    }
+    let Some(steps) = app_state.config.macros.get(&name) else {
+        return interaction_field!("No macro named '{}'", name);
+    };
+    let mut targets: Vec<PathBuf> = app_state
+        .multiple_selected_files
+        .iter()
+        .flatten()
+        .cloned()
+        .collect();
+    targets.sort();
+    app_state.macro_replay = replay_plan(steps, &targets);
+    // Each step works on the one entry the cursor is on.
+    app_state.clear_multi_select()
+}
@@ -0,0 +1,1090 @@
+/*
+* Stygian Sift - A Terminal-based File Manager
+ * Copyright (C) 2024 Maui The Magnificent (Charon)
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published
+ * by the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+ * Contact: Maui_The_Magnificent@proton.me
+ * Project repository: https://github.com/Mauitron/StygianSift.git
+ */
+
+use super::*;
+use crossterm::style::Attributes;
+use std::sync::Mutex;
+//////////////////////////////////////////////////////Renderer//////////////////////////////////////////////////////////////////////
+// Everything drawn goes into an off-screen grid of cells instead of straight
+// to the terminal. The grid understands the escape codes crossterm writes, so
+// drawing code keeps using queue!/write! on `screen()` as it would on stdout.
+// On flush the grid is compared with what the terminal shows and only the
+// cells that changed are sent, which keeps clearing and redrawing a pane
+// from flickering, most of all over SSH.
+
+// Attributes the grid keeps track of, in the order they are sent.
+const TRACKED_ATTRIBUTES: [Attribute; 10] = [
+    Attribute::Bold,
+    Attribute::Dim,
+    Attribute::Italic,
+    Attribute::Underlined,
+    Attribute::DoubleUnderlined,
+    Attribute::SlowBlink,
+    Attribute::RapidBlink,
+    Attribute::Reverse,
+    Attribute::Hidden,
+    Attribute::CrossedOut,
+];
+
+// Escape sequences the grid does not understand are passed on as they are,
+// but an unterminated one is not held back forever.
+const MAX_SEQUENCE_LEN: usize = 4096;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Pen {
+    fg: Color,
+    bg: Color,
+    attributes: Attributes,
+}
+
+impl Default for Pen {
+    fn default() -> Self {
+        Self {
+            fg: Color::Reset,
+            bg: Color::Reset,
+            attributes: Attributes::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cell {
+    // Empty for the right half of a wide character.
+    pub symbol: String,
+    pub fg: Color,
+    pub bg: Color,
+    pub attributes: Attributes,
+}
+
+impl Cell {
+    fn blank(bg: Color) -> Self {
+        Self {
+            symbol: " ".to_string(),
+            fg: Color::Reset,
+            bg,
+            attributes: Attributes::default(),
+        }
+    }
+
+    fn is_continuation(&self) -> bool {
+        self.symbol.is_empty()
+    }
+
+    fn pen(&self) -> Pen {
+        Pen {
+            fg: self.fg,
+            bg: self.bg,
+            attributes: self.attributes,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Grid {
+    width: u16,
+    height: u16,
+    cells: Vec<Cell>,
+}
+
+impl Grid {
+    pub fn new(width: u16, height: u16) -> Self {
+        Self {
+            width,
+            height,
+            cells: vec![Cell::blank(Color::Reset); width as usize * height as usize],
+        }
+    }
+
+    pub fn cell(&self, x: u16, y: u16) -> Option<&Cell> {
+        if x < self.width && y < self.height {
+            self.cells.get(self.index(x, y))
+        } else {
+            None
+        }
+    }
+
+    fn index(&self, x: u16, y: u16) -> usize {
+        y as usize * self.width as usize + x as usize
+    }
+
+    // Keeps whatever still fits.
+    fn resized(&self, width: u16, height: u16) -> Self {
+        let mut grid = Self::new(width, height);
+        for y in 0..height.min(self.height) {
+            for x in 0..width.min(self.width) {
+                let at = grid.index(x, y);
+                grid.cells[at] = self.cells[self.index(x, y)].clone();
+            }
+        }
+        grid
+    }
+
+    fn clear(&mut self, range: std::ops::Range<usize>, bg: Color) {
+        let end = range.end.min(self.cells.len());
+        for cell in &mut self.cells[range.start.min(end)..end] {
+            *cell = Cell::blank(bg);
+        }
+    }
+
+    fn scroll_up(&mut self, bg: Color) {
+        let width = self.width as usize;
+        if width == 0 || self.cells.is_empty() {
+            return;
+        }
+        self.cells.drain(..width);
+        self.cells
+            .extend(std::iter::repeat_n(Cell::blank(bg), width));
+    }
+}
+
+pub struct Renderer {
+    back: Grid,
+    front: Grid,
+    cursor: (u16, u16),
+    saved_cursor: (u16, u16),
+    // The last column was just written, the next character goes on a new line.
+    wrap_pending: bool,
+    pen: Pen,
+    cursor_visible: bool,
+    shown_cursor: Option<(u16, u16)>,
+    shown_cursor_visible: bool,
+    // Bytes of a sequence or character that has not been fully written yet.
+    pending: Vec<u8>,
+    passthrough: Vec<u8>,
+    full_redraw: bool,
+    frame_depth: usize,
+    output: Box<dyn Write + Send>,
+    // Set for a virtual terminal, see VirtualTerminal.
+    headless: Option<Headless>,
+    // Keys a macro answers its prompts with, read before any others.
+    queued_input: VecDeque<Event>,
+    // Keys read while a macro is recorded.
+    recorded_input: Option<Vec<Keystroke>>,
+}
+
+struct Headless {
+    size: (u16, u16),
+    events: VecDeque<Event>,
+    output: Vec<u8>,
+}
+
+impl Renderer {
+    fn terminal() -> Self {
+        let (width, height) = terminal::size().unwrap_or((80, 24));
+        Self::terminal_sized(width, height)
+    }
+
+    fn terminal_sized(width: u16, height: u16) -> Self {
+        Self {
+            back: Grid::new(width, height),
+            front: Grid::new(width, height),
+            cursor: (0, 0),
+            saved_cursor: (0, 0),
+            wrap_pending: false,
+            pen: Pen::default(),
+            cursor_visible: true,
+            shown_cursor: None,
+            shown_cursor_visible: true,
+            pending: Vec::new(),
+            passthrough: Vec::new(),
+            // Whatever was on the terminal before is unknown.
+            full_redraw: true,
+            frame_depth: 0,
+            output: Box::new(io::stdout()),
+            headless: None,
+            queued_input: VecDeque::new(),
+            recorded_input: None,
+        }
+    }
+
+    fn draw_to(&mut self, output: File) {
+        if self.headless.is_none() {
+            self.output = Box::new(output);
+        }
+    }
+
+    fn headless(width: u16, height: u16) -> Self {
+        Self {
+            output: Box::new(io::sink()),
+            headless: Some(Headless {
+                size: (width, height),
+                events: VecDeque::new(),
+                output: Vec::new(),
+            }),
+            ..Self::terminal_sized(width, height)
+        }
+    }
+
+    fn terminal_size(&self) -> io::Result<(u16, u16)> {
+        match &self.headless {
+            Some(headless) => Ok(headless.size),
+            None => terminal::size(),
+        }
+    }
+
+    fn feed(&mut self, bytes: &[u8]) {
+        self.pending.extend_from_slice(bytes);
+        let pending = std::mem::take(&mut self.pending);
+        let mut at = 0;
+        while at < pending.len() {
+            let rest = &pending[at..];
+            let used = match rest[0] {
+                0x1b => match self.escape(rest) {
+                    Some(used) => used,
+                    None => break,
+                },
+                b'\r' => {
+                    self.move_to(0, self.cursor.1);
+                    1
+                }
+                b'\n' => {
+                    self.line_feed();
+                    // Without raw mode the terminal returns the carriage too.
+                    let raw = self.headless.is_some()
+                        || terminal::is_raw_mode_enabled().unwrap_or(true);
+                    if !raw {
+                        self.cursor.0 = 0;
+                    }
+                    1
+                }
+                b'\t' => {
+                    let next = (self.cursor.0 / 8 + 1) * 8;
+                    self.move_to(next.min(self.back.width.saturating_sub(1)), self.cursor.1);
+                    1
+                }
+                0x08 => {
+                    self.move_to(self.cursor.0.saturating_sub(1), self.cursor.1);
+                    1
+                }
+                0x07 => {
+                    self.passthrough.push(0x07);
+                    1
+                }
+                byte if byte < 0x20 || byte == 0x7f => 1,
+                _ => match decode_char(rest) {
+                    Some((c, used)) => {
+                        self.put_char(c);
+                        used
+                    }
+                    None => break,
+                },
+            };
+            at += used;
+        }
+        self.pending = pending[at..].to_vec();
+    }
+
+    // How many bytes the sequence at the start of `bytes` takes, None while
+    // it is incomplete.
+    fn escape(&mut self, bytes: &[u8]) -> Option<usize> {
+        let used = match *bytes.get(1)? {
+            b'[' => {
+                let end = match bytes[2..].iter().position(|b| (0x40..=0x7e).contains(b)) {
+                    Some(end) => end + 2,
+                    None if bytes.len() > MAX_SEQUENCE_LEN => return Some(self.pass(bytes)),
+                    None => return None,
+                };
+                self.csi(&bytes[2..end], bytes[end], &bytes[..=end]);
+                end + 1
+            }
+            // OSC, DCS and friends run until BEL or ESC \.
+            b']' | b'P' | b'_' | b'^' => {
+                let mut end = 2;
+                loop {
+                    match bytes.get(end) {
+                        Some(0x07) => break end + 1,
+                        Some(0x1b) => match bytes.get(end + 1) {
+                            Some(b'\\') => break end + 2,
+                            Some(_) => end += 1,
+                            None => return None,
+                        },
+                        Some(_) => end += 1,
+                        None if bytes.len() > MAX_SEQUENCE_LEN => return Some(self.pass(bytes)),
+                        None => return None,
+                    }
+                }
+            }
+            b'7' => {
+                self.saved_cursor = self.cursor;
+                2
+            }
+            b'8' => {
+                self.move_to(self.saved_cursor.0, self.saved_cursor.1);
+                2
+            }
+            // Character set selection and the like: ESC, intermediates, final.
+            b' '..=b'/' => {
+                let end = bytes[1..].iter().position(|b| (0x30..=0x7e).contains(b))? + 1;
+                end + 1
+            }
+            _ => 2,
+        };
+        if !matches!(bytes[1], b'[' | b'7' | b'8') {
+            self.passthrough.extend_from_slice(&bytes[..used]);
+        }
+        Some(used)
+    }
+
+    fn pass(&mut self, bytes: &[u8]) -> usize {
+        self.passthrough.extend_from_slice(bytes);
+        bytes.len()
+    }
+
+    fn csi(&mut self, params: &[u8], final_byte: u8, raw: &[u8]) {
+        let text = std::str::from_utf8(params).unwrap_or_default();
+        if text.starts_with(['?', '>', '<', '=']) {
+            match (text, final_byte) {
+                ("?25", b'l') => self.cursor_visible = false,
+                ("?25", b'h') => self.cursor_visible = true,
+                ("?1049" | "?1047" | "?47", switch) => {
+                    // What was drawn belongs on the screen being left.
+                    self.settle();
+                    self.passthrough.extend_from_slice(raw);
+                    // A fresh alternate screen, nothing on it matches the grid.
+                    self.full_redraw |= switch == b'h';
+                }
+                _ => self.passthrough.extend_from_slice(raw),
+            }
+            return;
+        }
+        let numbers: Vec<u16> = text
+            .split(';')
+            .map(|number| number.parse().unwrap_or(0))
+            .collect();
+        let count = numbers.first().copied().filter(|n| *n != 0).unwrap_or(1);
+        let (x, y) = self.cursor;
+        match final_byte {
+            b'H' | b'f' => {
+                let column = numbers.get(1).copied().unwrap_or(1).max(1);
+                self.move_to(column - 1, count - 1);
+            }
+            b'A' => self.move_to(x, y.saturating_sub(count)),
+            b'B' => self.move_to(x, y.saturating_add(count)),
+            b'C' => self.move_to(x.saturating_add(count), y),
+            b'D' => self.move_to(x.saturating_sub(count), y),
+            b'E' => self.move_to(0, y.saturating_add(count)),
+            b'F' => self.move_to(0, y.saturating_sub(count)),
+            b'G' => self.move_to(count - 1, y),
+            b'd' => self.move_to(x, count - 1),
+            b'J' => {
+                let here = self.back.index(x, y);
+                match numbers[0] {
+                    0 => self.back.clear(here..usize::MAX, self.pen.bg),
+                    1 => self.back.clear(0..here + 1, self.pen.bg),
+                    2 => self.clear_all(),
+                    _ => self.passthrough.extend_from_slice(raw),
+                }
+            }
+            b'K' => {
+                let start = self.back.index(0, y);
+                let end = start + self.back.width as usize;
+                let here = self.back.index(x, y);
+                match numbers[0] {
+                    0 => self.back.clear(here..end, self.pen.bg),
+                    1 => self.back.clear(start..here + 1, self.pen.bg),
+                    _ => self.back.clear(start..end, self.pen.bg),
+                }
+            }
+            b'm' => self.sgr(&numbers),
+            b's' => self.saved_cursor = self.cursor,
+            b'u' => self.move_to(self.saved_cursor.0, self.saved_cursor.1),
+            _ => self.passthrough.extend_from_slice(raw),
+        }
+    }
+
+    fn sgr(&mut self, numbers: &[u16]) {
+        let pen = &mut self.pen;
+        let mut at = 0;
+        while at < numbers.len() {
+            match numbers[at] {
+                0 => *pen = Pen::default(),
+                code @ 1..=9 => pen.attributes.set(match code {
+                    1 => Attribute::Bold,
+                    2 => Attribute::Dim,
+                    3 => Attribute::Italic,
+                    4 => Attribute::Underlined,
+                    5 => Attribute::SlowBlink,
+                    6 => Attribute::RapidBlink,
+                    7 => Attribute::Reverse,
+                    8 => Attribute::Hidden,
+                    _ => Attribute::CrossedOut,
+                }),
+                21 => pen.attributes.set(Attribute::DoubleUnderlined),
+                22 => {
+                    pen.attributes.unset(Attribute::Bold);
+                    pen.attributes.unset(Attribute::Dim);
+                }
+                23 => pen.attributes.unset(Attribute::Italic),
+                24 => {
+                    pen.attributes.unset(Attribute::Underlined);
+                    pen.attributes.unset(Attribute::DoubleUnderlined);
+                }
+                25 => {
+                    pen.attributes.unset(Attribute::SlowBlink);
+                    pen.attributes.unset(Attribute::RapidBlink);
+                }
+                27 => pen.attributes.unset(Attribute::Reverse),
+                28 => pen.attributes.unset(Attribute::Hidden),
+                29 => pen.attributes.unset(Attribute::CrossedOut),
+                code @ 30..=37 => pen.fg = Color::AnsiValue((code - 30) as u8),
+                code @ 40..=47 => pen.bg = Color::AnsiValue((code - 40) as u8),
+                code @ 90..=97 => pen.fg = Color::AnsiValue((code - 90 + 8) as u8),
+                code @ 100..=107 => pen.bg = Color::AnsiValue((code - 100 + 8) as u8),
+                39 => pen.fg = Color::Reset,
+                49 => pen.bg = Color::Reset,
+                code @ (38 | 48 | 58) => {
+                    let channel = |offset: usize| numbers.get(at + offset).copied().unwrap_or(0) as u8;
+                    let (color, used) = match numbers.get(at + 1) {
+                        Some(5) => (Color::AnsiValue(channel(2)), 2),
+                        Some(2) => (
+                            Color::Rgb {
+                                r: channel(2),
+                                g: channel(3),
+                                b: channel(4),
+                            },
+                            4,
+                        ),
+                        _ => (Color::Reset, 0),
+                    };
+                    match code {
+                        38 => pen.fg = color,
+                        48 => pen.bg = color,
+                        // Underline colors are not kept.
+                        _ => {}
+                    }
+                    at += used;
+                }
+                _ => {}
+            }
+            at += 1;
+        }
+    }
+
+    fn move_to(&mut self, x: u16, y: u16) {
+        self.cursor = (
+            x.min(self.back.width.saturating_sub(1)),
+            y.min(self.back.height.saturating_sub(1)),
+        );
+        self.wrap_pending = false;
+    }
+
+    fn line_feed(&mut self) {
+        self.wrap_pending = false;
+        if self.cursor.1 + 1 >= self.back.height {
+            self.back.scroll_up(self.pen.bg);
+        } else {
+            self.cursor.1 += 1;
+        }
+    }
+
+    fn clear_all(&mut self) {
+        if let Ok((width, height)) = self.terminal_size() {
+            if (width, height) != (self.back.width, self.back.height) {
+                self.resize(width, height);
+            }
+        }
+        let cells = self.back.cells.len();
+        self.back.clear(0..cells, self.pen.bg);
+    }
+
+    fn resize(&mut self, width: u16, height: u16) {
+        self.back = self.back.resized(width, height);
+        self.front = Grid::new(width, height);
+        self.move_to(self.cursor.0, self.cursor.1);
+        // The terminal reflows its own content on a resize, so start over.
+        self.full_redraw = true;
+    }
+
+    fn put_char(&mut self, c: char) {
+        let width = char_width(c);
+        if width == 0 {
+            // Combining marks and joiners stay with the character before.
+            let x = if self.wrap_pending {
+                Some(self.cursor.0)
+            } else {
+                self.cursor.0.checked_sub(1)
+            };
+            if let Some(mut x) = x {
+                let y = self.cursor.1;
+                if x > 0 && self.back.cell(x, y).is_some_and(Cell::is_continuation) {
+                    x -= 1;
+                }
+                let at = self.back.index(x, y);
+                if let Some(cell) = self.back.cells.get_mut(at) {
+                    cell.symbol.push(c);
+                }
+            }
+            return;
+        }
+        if self.wrap_pending || self.cursor.0 + width > self.back.width {
+            if self.back.width < width {
+                return;
+            }
+            self.cursor.0 = 0;
+            self.line_feed();
+        }
+        let (x, y) = self.cursor;
+        if y >= self.back.height {
+            return;
+        }
+        for column in x..x + width {
+            self.unhalve(column, y);
+        }
+        let at = self.back.index(x, y);
+        let pen = self.pen;
+        let cell = &mut self.back.cells[at];
+        cell.symbol.clear();
+        cell.symbol.push(c);
+        (cell.fg, cell.bg, cell.attributes) = (pen.fg, pen.bg, pen.attributes);
+        if width == 2 {
+            let cell = &mut self.back.cells[at + 1];
+            cell.symbol.clear();
+            (cell.fg, cell.bg, cell.attributes) = (pen.fg, pen.bg, pen.attributes);
+        }
+        if x + width >= self.back.width {
+            self.cursor.0 = self.back.width - 1;
+            self.wrap_pending = true;
+        } else {
+            self.cursor.0 = x + width;
+        }
+    }
+
+    // Writing over one half of a wide character wipes the other half.
+    fn unhalve(&mut self, x: u16, y: u16) {
+        let at = self.back.index(x, y);
+        if self.back.cells[at].is_continuation() && x > 0 {
+            self.back.cells[at - 1].symbol = " ".to_string();
+        }
+        if self.back.cell(x + 1, y).is_some_and(Cell::is_continuation) {
+            self.back.cells[at + 1].symbol = " ".to_string();
+        }
+    }
+
+    fn present(&mut self) -> io::Result<()> {
+        if let Ok((width, height)) = self.terminal_size() {
+            if (width, height) != (self.back.width, self.back.height) {
+                self.resize(width, height);
+            }
+        }
+        let mut out = std::mem::take(&mut self.passthrough);
+        if self.full_redraw {
+            self.full_redraw = false;
+            self.front = Grid::new(self.back.width, self.back.height);
+            self.shown_cursor = None;
+            queue!(out, SetAttribute(Attribute::Reset), Clear(ClearType::All))?;
+        }
+
+        let mut cells = Vec::new();
+        self.draw_changes(&mut cells)?;
+        if !cells.is_empty() {
+            if self.shown_cursor_visible {
+                queue!(out, Hide)?;
+                self.shown_cursor_visible = false;
+            }
+            out.extend_from_slice(&cells);
+            self.shown_cursor = None;
+        }
+        if self.shown_cursor != Some(self.cursor) {
+            queue!(out, MoveTo(self.cursor.0, self.cursor.1))?;
+            self.shown_cursor = Some(self.cursor);
+        }
+        if self.cursor_visible != self.shown_cursor_visible {
+            if self.cursor_visible {
+                queue!(out, Show)?;
+            } else {
+                queue!(out, Hide)?;
+            }
+            self.shown_cursor_visible = self.cursor_visible;
+        }
+
+        self.front.clone_from(&self.back);
+        if let Some(headless) = &mut self.headless {
+            headless.output.extend_from_slice(&out);
+            return Ok(());
+        }
+        self.output.write_all(&out)?;
+        self.output.flush()
+    }
+
+    // Queues the changed cells ahead of whatever is passed through next.
+    fn settle(&mut self) {
+        let mut cells = Vec::new();
+        if self.draw_changes(&mut cells).is_ok() && !cells.is_empty() {
+            self.passthrough.append(&mut cells);
+            self.front.clone_from(&self.back);
+            self.shown_cursor = None;
+        }
+    }
+
+    fn draw_changes(&self, out: &mut Vec<u8>) -> io::Result<()> {
+        let mut style: Option<Pen> = None;
+        let mut at: Option<(u16, u16)> = None;
+        for y in 0..self.back.height {
+            for x in 0..self.back.width {
+                let index = self.back.index(x, y);
+                let cell = &self.back.cells[index];
+                if *cell == self.front.cells[index] || cell.is_continuation() {
+                    continue;
+                }
+                if at != Some((x, y)) {
+                    queue!(out, MoveTo(x, y))?;
+                }
+                let pen = cell.pen();
+                if style != Some(pen) {
+                    // Starting from a reset is the only way to drop attributes.
+                    queue!(out, SetAttribute(Attribute::Reset))?;
+                    if pen.fg != Color::Reset {
+                        queue!(out, SetForegroundColor(pen.fg))?;
+                    }
+                    if pen.bg != Color::Reset {
+                        queue!(out, SetBackgroundColor(pen.bg))?;
+                    }
+                    for attribute in TRACKED_ATTRIBUTES {
+                        if pen.attributes.has(attribute) {
+                            queue!(out, SetAttribute(attribute))?;
+                        }
+                    }
+                    style = Some(pen);
+                }
+                out.extend_from_slice(cell.symbol.as_bytes());
+                let wide = self.back.cell(x + 1, y).is_some_and(Cell::is_continuation);
+                // Terminals do not agree on how wide some characters are, so
+                // the position is never trusted after a wide one.
+                at = if wide || x + 1 >= self.back.width {
+                    None
+                } else {
+                    Some((x + 1, y))
+                };
+            }
+        }
+        if style.is_some_and(|pen| pen != Pen::default()) {
+            queue!(out, SetAttribute(Attribute::Reset))?;
+        }
+        Ok(())
+    }
+}
+
+fn decode_char(bytes: &[u8]) -> Option<(char, usize)> {
+    let len = match bytes[0] {
+        0x00..=0x7f => 1,
+        0xc0..=0xdf => 2,
+        0xe0..=0xef => 3,
+        0xf0..=0xf7 => 4,
+        _ => return Some((char::REPLACEMENT_CHARACTER, 1)),
+    };
+    if bytes.len() < len {
+        return None;
+    }
+    match std::str::from_utf8(&bytes[..len]) {
+        Ok(text) => text.chars().next().map(|c| (c, len)),
+        Err(_) => Some((char::REPLACEMENT_CHARACTER, 1)),
+    }
+}
+
+// Columns a character takes up, after the East Asian width tables and the
+// emoji most terminals draw double wide.
+pub fn char_width(c: char) -> u16 {
+    let code = c as u32;
+    const ZERO: &[(u32, u32)] = &[
+        (0x0300, 0x036f),
+        (0x0483, 0x0489),
+        (0x0591, 0x05bd),
+        (0x0610, 0x061a),
+        (0x064b, 0x065f),
+        (0x0e31, 0x0e31),
+        (0x0e34, 0x0e3a),
+        (0x1ab0, 0x1aff),
+        (0x1dc0, 0x1dff),
+        (0x200b, 0x200f),
+        (0x20d0, 0x20ff),
+        (0xfe00, 0xfe0f),
+        (0xfe20, 0xfe2f),
+        (0xe0100, 0xe01ef),
+    ];
+    const WIDE: &[(u32, u32)] = &[
+        (0x1100, 0x115f),
+        (0x231a, 0x231b),
+        (0x2329, 0x232a),
+        (0x23e9, 0x23ec),
+        (0x23f0, 0x23f0),
+        (0x23f3, 0x23f3),
+        (0x25fd, 0x25fe),
+        (0x2614, 0x2615),
+        (0x2648, 0x2653),
+        (0x267f, 0x267f),
+        (0x2693, 0x2693),
+        (0x26a1, 0x26a1),
+        (0x26aa, 0x26ab),
+        (0x26bd, 0x26be),
+        (0x26c4, 0x26c5),
+        (0x26ce, 0x26ce),
+        (0x26d4, 0x26d4),
+        (0x26ea, 0x26ea),
+        (0x26f2, 0x26f3),
+        (0x26f5, 0x26f5),
+        (0x26fa, 0x26fa),
+        (0x26fd, 0x26fd),
+        (0x2705, 0x2705),
+        (0x270a, 0x270b),
+        (0x2728, 0x2728),
+        (0x274c, 0x274c),
+        (0x274e, 0x274e),
+        (0x2753, 0x2755),
+        (0x2757, 0x2757),
+        (0x2795, 0x2797),
+        (0x27b0, 0x27b0),
+        (0x27bf, 0x27bf),
+        (0x2b1b, 0x2b1c),
+        (0x2b50, 0x2b50),
+        (0x2b55, 0x2b55),
+        (0x2e80, 0x303e),
+        (0x3041, 0x33ff),
+        (0x3400, 0x4dbf),
+        (0x4e00, 0x9fff),
+        (0xa000, 0xa4cf),
+        (0xa960, 0xa97f),
+        (0xac00, 0xd7a3),
+        (0xf900, 0xfaff),
+        (0xfe10, 0xfe19),
+        (0xfe30, 0xfe6f),
+        (0xff00, 0xff60),
+        (0xffe0, 0xffe6),
+        (0x1f004, 0x1f004),
+        (0x1f0cf, 0x1f0cf),
+        (0x1f18e, 0x1f18e),
+        (0x1f191, 0x1f19a),
+        (0x1f200, 0x1f251),
+        (0x1f300, 0x1f320),
+        (0x1f32d, 0x1f335),
+        (0x1f337, 0x1f37c),
+        (0x1f37e, 0x1f393),
+        (0x1f3a0, 0x1f3ca),
+        (0x1f3cf, 0x1f3d3),
+        (0x1f3e0, 0x1f3f0),
+        (0x1f3f4, 0x1f3f4),
+        (0x1f3f8, 0x1f43e),
+        (0x1f440, 0x1f440),
+        (0x1f442, 0x1f4fc),
+        (0x1f4ff, 0x1f53d),
+        (0x1f54b, 0x1f54e),
+        (0x1f550, 0x1f567),
+        (0x1f57a, 0x1f57a),
+        (0x1f595, 0x1f596),
+        (0x1f5a4, 0x1f5a4),
+        (0x1f5fb, 0x1f64f),
+        (0x1f680, 0x1f6c5),
+        (0x1f6cc, 0x1f6cc),
+        (0x1f6d0, 0x1f6d2),
+        (0x1f6d5, 0x1f6d7),
+        (0x1f6eb, 0x1f6ec),
+        (0x1f6f4, 0x1f6fc),
+        (0x1f7e0, 0x1f7eb),
+        (0x1f90c, 0x1f93a),
+        (0x1f93c, 0x1f945),
+        (0x1f947, 0x1f9ff),
+        (0x1fa70, 0x1faff),
+        (0x20000, 0x2fffd),
+        (0x30000, 0x3fffd),
+    ];
+    let within = |ranges: &[(u32, u32)]| {
+        ranges
+            .binary_search_by(|&(start, end)| {
+                if end < code {
+                    Ordering::Less
+                } else if start > code {
+                    Ordering::Greater
+                } else {
+                    Ordering::Equal
+                }
+            })
+            .is_ok()
+    };
+    if within(ZERO) {
+        0
+    } else if within(WIDE) {
+        2
+    } else {
+        1
+    }
+}
+
+static RENDERER: Mutex<Option<Renderer>> = Mutex::new(None);
+
+fn with_renderer<T>(f: impl FnOnce(&mut Renderer) -> T) -> T {
+    let mut renderer = RENDERER.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    f(renderer.get_or_insert_with(Renderer::terminal))
+}
+
+// A handle on the grid, used wherever stdout was. Flushing sends the
+// changed cells, unless a frame is being drawn.
+pub struct Screen;
+
+pub fn screen() -> Screen {
+    Screen
+}
+
+impl Write for Screen {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        with_renderer(|renderer| renderer.feed(buf));
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        with_renderer(|renderer| {
+            if renderer.frame_depth == 0 {
+                renderer.present()
+            } else {
+                Ok(())
+            }
+        })
+    }
+}
+
+// Holds back flushes while a whole frame is drawn, so clearing a pane and
+// drawing it again reaches the terminal as just the difference.
+pub struct ScreenFrame;
+
+pub fn begin_frame() -> ScreenFrame {
+    with_renderer(|renderer| renderer.frame_depth += 1);
+    ScreenFrame
+}
+
+impl Drop for ScreenFrame {
+    fn drop(&mut self) {
+        with_renderer(|renderer| {
+            renderer.frame_depth = renderer.frame_depth.saturating_sub(1);
+            if renderer.frame_depth == 0 {
+                let _ = renderer.present();
+            }
+        });
+    }
+}
+
+// The size of the terminal the grid is drawn for. Use this instead of
+// crossterm's, which only knows about the real one.
+pub fn size() -> io::Result<(u16, u16)> {
+    with_renderer(|renderer| renderer.terminal_size())
+}
+
+pub fn enable_raw_mode() -> io::Result<()> {
+    match with_renderer(|renderer| renderer.headless.is_some()) {
+        true => Ok(()),
+        false => terminal::enable_raw_mode(),
+    }
+}
+
+pub fn disable_raw_mode() -> io::Result<()> {
+    match with_renderer(|renderer| renderer.headless.is_some()) {
+        true => Ok(()),
+        false => terminal::disable_raw_mode(),
+    }
+}
+
+// Whatever was drawn reaches the terminal before waiting on the user. A
+// virtual terminal hands out its scripted events instead, and reports the
+// end of the script as an UnexpectedEof error.
+pub fn read_event() -> io::Result<Event> {
+    let scripted = with_renderer(|renderer| {
+        renderer.present()?;
+        if let Some(event) = renderer.queued_input.pop_front() {
+            return Ok(Some(Some(event)));
+        }
+        Ok::<_, io::Error>(
+            renderer
+                .headless
+                .as_mut()
+                .map(|headless| headless.events.pop_front()),
+        )
+    })?;
+    let event = match scripted {
+        Some(Some(event)) => event,
+        Some(None) => {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "no more scripted events",
+            ))
+        }
+        None => event::read()?,
+    };
+    if let Event::Key(key) = event {
+        with_renderer(|renderer| {
+            if let Some(recorded) = renderer.recorded_input.as_mut() {
+                if key.kind != KeyEventKind::Release {
+                    recorded.push(key.into());
+                }
+            }
+        });
+    }
+    Ok(event)
+}
+
+// Keys read before the terminal's, so a macro step gets the answers it was
+// recorded with. Whatever the step did not read is cleared after it.
+pub fn queue_input(keys: &[Keystroke]) {
+    with_renderer(|renderer| {
+        renderer.queued_input = keys.iter().map(|key| Event::Key((*key).into())).collect();
+    });
+}
+
+pub fn clear_queued_input() {
+    with_renderer(|renderer| renderer.queued_input.clear());
+}
+
+// Keeps the keys read from now on, until take_recorded_input.
+pub fn record_input() {
+    with_renderer(|renderer| renderer.recorded_input = Some(Vec::new()));
+}
+
+pub fn take_recorded_input() -> Vec<Keystroke> {
+    with_renderer(|renderer| renderer.recorded_input.take().unwrap_or_default())
+}
+
+// A virtual terminal always has an answer: the next scripted event, or the
+// end of the script, which read_event turns into an error.
+pub fn poll_event(timeout: Duration) -> io::Result<bool> {
+    let headless = with_renderer(|renderer| {
+        renderer.present()?;
+        Ok::<_, io::Error>(renderer.headless.is_some())
+    })?;
+    if headless {
+        Ok(true)
+    } else {
+        event::poll(timeout)
+    }
+}
+
+// Draws on the terminal device instead of stdout, for when stdout is a pipe
+// or is kept for what the browser prints when it is done.
+pub fn draw_to_terminal_device() -> io::Result<()> {
+    #[cfg(windows)]
+    let device = OpenOptions::new().read(true).write(true).open("CONOUT$")?;
+    #[cfg(not(windows))]
+    let device = OpenOptions::new().write(true).open("/dev/tty")?;
+    with_renderer(|renderer| renderer.draw_to(device));
+    Ok(())
+}
+
+// For when something other than the grid wrote to the terminal, like a
+// sudo password prompt.
+pub fn invalidate_screen() {
+    with_renderer(|renderer| renderer.full_redraw = true);
+}
+
+static VIRTUAL_TERMINAL: Mutex<()> = Mutex::new(());
+
+// Swaps the real terminal for one of any size that only exists in memory,
+// so the whole UI can be driven by scripted events and its screen checked.
+// There is only one screen, so virtual terminals are handed out one at a
+// time and the real one comes back when this is dropped.
+pub struct VirtualTerminal {
+    _turn: std::sync::MutexGuard<'static, ()>,
+}
+
+impl VirtualTerminal {
+    pub fn new(width: u16, height: u16) -> Self {
+        let turn = VIRTUAL_TERMINAL
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        with_renderer(|renderer| *renderer = Renderer::headless(width, height));
+        Self { _turn: turn }
+    }
+
+    pub fn push_event(&self, event: Event) {
+        with_renderer(|renderer| {
+            if let Some(headless) = &mut renderer.headless {
+                headless.events.push_back(event);
+            }
+        });
+    }
+
+    pub fn push_key(&self, code: KeyCode, modifiers: KeyModifiers) {
+        self.push_event(Event::Key(KeyEvent::new(code, modifiers)));
+    }
+
+    // Every character as its own key press.
+    pub fn push_keys(&self, keys: &str) {
+        for c in keys.chars() {
+            self.push_key(KeyCode::Char(c), KeyModifiers::NONE);
+        }
+    }
+
+    pub fn push_mouse(&self, kind: MouseEventKind, column: u16, row: u16) {
+        self.push_event(Event::Mouse(MouseEvent {
+            kind,
+            column,
+            row,
+            modifiers: KeyModifiers::NONE,
+        }));
+    }
+
+    pub fn resize(&self, width: u16, height: u16) {
+        with_renderer(|renderer| {
+            if let Some(headless) = &mut renderer.headless {
+                headless.size = (width, height);
+            }
+        });
+        self.push_event(Event::Resize(width, height));
+    }
+
+    pub fn cell(&self, x: u16, y: u16) -> Option<Cell> {
+        with_renderer(|renderer| renderer.back.cell(x, y).cloned())
+    }
+
+    // The screen as text, one line per row with trailing blanks trimmed.
+    pub fn lines(&self) -> Vec<String> {
+        let (width, height) = size().unwrap_or_default();
+        self.region(0, 0, width, height)
+    }
+
+    pub fn region(&self, x: u16, y: u16, width: u16, height: u16) -> Vec<String> {
+        with_renderer(|renderer| {
+            let grid = &renderer.back;
+            (y..y.saturating_add(height).min(grid.height))
+                .map(|row| {
+                    let line: String = (x..x.saturating_add(width).min(grid.width))
+                        .filter_map(|column| grid.cell(column, row))
+                        .map(|cell| cell.symbol.as_str())
+                        .collect();
+                    line.trim_end().to_string()
+                })
+                .collect()
+        })
+    }
+
+    // Everything the grid would have sent to a real terminal so far.
+    pub fn take_output(&self) -> Vec<u8> {
+        with_renderer(|renderer| {
+            let _ = renderer.present();
+            renderer
+                .headless
+                .as_mut()
+                .map(|headless| std::mem::take(&mut headless.output))
+                .unwrap_or_default()
+        })
+    }
+}
+
+impl Drop for VirtualTerminal {
+    fn drop(&mut self) {
+        let mut renderer = RENDERER.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        *renderer = None;
+    }
+}
@@ -62,12 +62,12 @@ impl TerminalState {
         if current_width < self.min_width || current_height < self.min_height {
             #[cfg(not(target_os = "windows"))]
             {
-                execute!(io::stdout(), SetSize(self.min_width, self.min_height))?;
+                execute!(screen(), SetSize(self.min_width, self.min_height))?;
             }
 
             #[cfg(target_os = "windows")]
             {
-                let _ = execute!(io::stdout(), SetSize(self.min_width, self.min_height));
+                let _ = execute!(screen(), SetSize(self.min_width, self.min_height));
             }
 
             self.width = self.min_width;
@@ -77,19 +77,6 @@ impl TerminalState {
         Ok(())
     }
 }
-pub struct GitInfo {
-    pub is_git_repo: bool,
-    pub file_statuses: HashMap<PathBuf, GitStatus>,
-}
-
-impl GitInfo {
-    pub fn new() -> Self {
-        GitInfo {
-            is_git_repo: false,
-            file_statuses: HashMap::new(),
-        }
-    }
-}
 pub struct GitMenuItem {
     pub label: &'static str,
     pub description: &'static str,
@@ -150,45 +137,6 @@ impl GitMenu {
         self.items[self.selected].command
     }
 }
-pub fn is_git_repo(path: &Path) -> bool {
-    path.join(".git").is_dir()
-}
-
-pub fn get_git_statuses(path: &Path) -> io::Result<HashMap<PathBuf, GitStatus>> {
-    let output = Command::new("git")
-        .args(&["status", "--porcelain", "-z"])
-        .current_dir(path)
-        .output()?;
-
-    if !output.status.success() {
-        return Ok(HashMap::new());
-    }
-
-    let mut statuses = HashMap::new();
-    let entries = output
-        .stdout
-        .split(|&b| b == 0)
-        .filter(|entry| !entry.is_empty());
-
-    for entry in entries {
-        if entry.len() < 3 {
-            continue;
-        }
-        let status_code = &entry[0..2];
-        let file_path = Path::new(std::str::from_utf8(&entry[3..]).unwrap());
-        let status = match status_code {
-            b" M" => GitStatus::Modified,
-            b"A " => GitStatus::Added,
-            b"D " => GitStatus::Deleted,
-            b"R " => GitStatus::Renamed,
-            b"??" => GitStatus::Untracked,
-            _ => GitStatus::Unmodified,
-        };
-        statuses.insert(path.join(file_path), status);
-    }
-
-    Ok(statuses)
-}
 pub fn handle_permission_issue(
     stdout: &mut impl Write,
     item_name: &str,
@@ -213,7 +161,7 @@ pub fn handle_permission_issue(
     stdout.flush()?;
 
     loop {
-        if let Event::Key(key) = event::read()? {
+        if let Event::Key(key) = read_event()? {
             match key.code {
                 KeyCode::Char('y') | KeyCode::Char('Y') => {
                     if cfg!(unix) {
@@ -222,6 +170,8 @@ pub fn handle_permission_issue(
                             .arg("-la")
                             .arg(path)
                             .output()?;
+                        // sudo asks for the password on the terminal itself.
+                        invalidate_screen();
 
                         if output.status.success() {
                             let contents = String::from_utf8_lossy(&output.stdout)
@@ -251,8 +201,8 @@ pub fn handle_permission_issue(
                     )?;
                     stdout.flush()?;
                     loop {
-                        if event::poll(Duration::from_millis(100))? {
-                            if let Event::Key(_) = event::read()? {
+                        if poll_event(Duration::from_millis(100))? {
+                            if let Event::Key(_) = read_event()? {
                                 break;
                             }
                         }
@@ -269,6 +219,7 @@ pub fn handle_permission_issue(
 fn handle_elevated_access(stdout: &mut impl Write, path: &Path) -> io::Result<bool> {
     let _ = stdout;
     let status = Command::new("sudo").arg("-v").status();
+    invalidate_screen();
 
     match status {
         Ok(exit_status) if exit_status.success() => {
@@ -302,73 +253,13 @@ pub fn get_sorted_entries(
     dir: &Path,
     sort_order: &SortOrder,
 ) -> io::Result<Vec<FileEntry>> {
-    let entries: Vec<_> = fs::read_dir(dir)?
-        .par_bridge()
-        .filter_map(Result::ok)
-        .collect();
-
-    let mut entries: Vec<FileEntry> = entries
-        .par_iter()
-        .with_min_len(256)
-        .filter_map(|entry| FileEntry::new(entry.path()).ok())
-        .collect();
-
-    match sort_order {
-        SortOrder::ColorAsc => entries.par_sort_by(|a, b| {
-            let a_color = app_state.get_item_color(&a.path);
-            let b_color = app_state.get_item_color(&b.path);
-            MarkerColor::color_order(a_color)
-                .cmp(&MarkerColor::color_order(b_color))
-                .then_with(|| a.name.cmp(&b.name))
-        }),
-        SortOrder::ColorDesc => entries.par_sort_by(|a, b| {
-            let a_color = app_state.get_item_color(&a.path);
-            let b_color = app_state.get_item_color(&b.path);
-            MarkerColor::color_order(b_color)
-                .cmp(&MarkerColor::color_order(a_color))
-                .then_with(|| a.name.cmp(&b.name))
-        }),
-        SortOrder::NameAsc => entries.par_sort_unstable_by(|a, b| a.name.cmp(&b.name)),
-        SortOrder::NameDesc => entries.par_sort_unstable_by(|a, b| b.name.cmp(&a.name)),
-        SortOrder::TypeAsc => entries.par_sort_unstable_by(|a, b| {
-            file_type_order(&a.file_type)
-                .cmp(&file_type_order(&b.file_type))
-                .then_with(|| a.name.cmp(&b.name))
-        }),
-        SortOrder::TypeDesc => entries.par_sort_unstable_by(|a, b| {
-            file_type_order(&b.file_type)
-                .cmp(&file_type_order(&a.file_type))
-                .then_with(|| a.name.cmp(&b.name))
-        }),
-        SortOrder::SizeAsc => entries
-            .par_sort_unstable_by(|a, b| a.size.cmp(&b.size).then_with(|| a.name.cmp(&b.name))),
-        SortOrder::SizeDesc => entries
-            .par_sort_unstable_by(|a, b| b.size.cmp(&a.size).then_with(|| a.name.cmp(&b.name))),
-        SortOrder::DateModifiedAsc => {
-            entries.par_sort_unstable_by(|a, b| compare_modified_times(a, b, Ordering::Less))
-        }
-        SortOrder::DateModifiedDesc => {
-            entries.par_sort_unstable_by(|a, b| compare_modified_times(a, b, Ordering::Greater))
-        }
-    }
+    let mut entries: Vec<FileEntry> = match &app_state.archive_view {
+        Some(view) if view.contains(dir) => view.list(dir),
+        _ => read_entries(dir)?,
+    };
+    entries.retain(|entry| app_state.config.is_listed(&entry.path));
+    sort_entries(&app_state.config, &mut entries, sort_order);
     Ok(entries)
-}
-
-fn compare_modified_times(a: &FileEntry, b: &FileEntry, order: Ordering) -> Ordering {
-    let a_time = fs::metadata(&a.path).and_then(|m| m.modified()).ok();
-    let b_time = fs::metadata(&b.path).and_then(|m| m.modified()).ok();
-    match (a_time, b_time) {
-        (Some(a_time), Some(b_time)) => {
-            if order == Ordering::Less {
-                a_time.cmp(&b_time).then_with(|| a.name.cmp(&b.name))
-            } else {
-                b_time.cmp(&a_time).then_with(|| a.name.cmp(&b.name))
-            }
-        }
-        (Some(_), None) => Ordering::Less,
-        (None, Some(_)) => Ordering::Greater,
-        (None, None) => a.name.cmp(&b.name),
-    }
 } // clear_preview();
 
 pub fn parse_ls_output(contents: &[String], dir: &Path) -> Vec<FileEntry> {
@@ -400,87 +291,39 @@ pub fn parse_ls_output(contents: &[String], dir: &Path) -> Vec<FileEntry> {
 }
 
 /////////////////////////////////////////////////////////////////CONFIG////////////////////////////////////////////////////////////////////////////////////
-pub fn create_default_config(config_path: &PathBuf, app_state: &mut AppState) -> io::Result<()> {
-    let mut file = File::create(config_path)?;
-    writeln!(
-        file,
-        "home_folder = {}",
-        app_state.current_dir.to_string_lossy()
-    )?;
-    writeln!(file, "lines_shown = {}", app_state.config.lines_shown)?;
-    writeln!(file, "default_sort = {:?}", app_state.config.default_sort)?;
-    writeln!(file, "# Shortcuts")?;
-    writeln!(file, "# Format: shortcut_X = /path/to/directory")?;
-    writeln!(file, "# Example: shortcut_1 = /home/user/Documents")?;
-    Ok(())
+pub fn create_default_config(config_path: &Path, app_state: &mut AppState) -> io::Result<()> {
+    let mut config = app_state.config.clone();
+    config.home_folder = Some(app_state.current_dir.clone());
+    config.save_to(config_path)
 }
 
 pub fn read_line() -> io::Result<String> {
     let mut input = String::new();
     loop {
-        if let Event::Key(key_event) = event::read()? {
+        if let Event::Key(key_event) = read_event()? {
             match key_event.code {
                 KeyCode::Enter => {
-                    println!();
+                    writeln!(screen())?;
                     break;
                 }
                 KeyCode::Char(c) => {
                     input.push(c);
-                    print!("{}", c);
+                    write!(screen(), "{}", c)?;
                 }
                 KeyCode::Backspace => {
                     if !input.is_empty() {
                         input.pop();
-                        print!("\x08 \x08");
+                        write!(screen(), "\x08 \x08")?;
                     }
                 }
                 _ => {}
             }
-            io::stdout().flush()?;
+            screen().flush()?;
         }
     }
     Ok(input)
 }
 
-pub fn get_current_branch(path: &Path) -> String {
-    Command::new("git")
-        .arg("-C")
-        .arg(path)
-        .arg("rev-parse")
-        .arg("--abbrev-ref")
-        .arg("HEAD")
-        .output()
-        .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
-        .unwrap_or_else(|_| "Unknown".to_string())
-}
-
-pub fn get_git_status(path: &Path) -> String {
-    Command::new("git")
-        .arg("-C")
-        .arg(path)
-        .arg("status")
-        .arg("--porcelain")
-        .output()
-        .map(|output| {
-            if output.stdout.is_empty() {
-                "Clean".to_string()
-            } else {
-                "Modified".to_string()
-            }
-        })
-        .unwrap_or_else(|_| "Unknown".to_string())
-}
-
-pub fn is_git_repository(path: &Path) -> bool {
-    Command::new("git")
-        .arg("-C")
-        .arg(path)
-        .arg("rev-parse")
-        .arg("--is-inside-work-tree")
-        .output()
-        .map(|output| output.status.success())
-        .unwrap_or(false)
-}
 pub fn truncate_path(path: &Path, max_length: usize) -> String {
     let path_str = path.to_string_lossy();
     if path_str.len() <= max_length {
@@ -561,7 +404,7 @@ pub fn check_admin_required_cross_platform(path: &Path) -> io::Result<bool> {
 }
 
 pub fn cleanup_terminal() -> io::Result<()> {
-    let mut stdout = io::stdout();
+    let mut stdout = screen();
     execute!(stdout, Clear(ClearType::All))?;
     execute!(stdout, MoveTo(0, 0))?;
     execute!(stdout, Show)?;
@@ -572,7 +415,7 @@ pub fn cleanup_terminal() -> io::Result<()> {
         event::DisableBracketedPaste,
         LeaveAlternateScreen
     )?;
-    terminal::disable_raw_mode()?;
+    disable_raw_mode()?;
     stdout.flush();
     Ok(())
 }
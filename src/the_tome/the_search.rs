@@ -23,332 +23,6 @@ use std::env::current_dir;
 
 use super::*;
 //////////////////////////////////////////////////////Search/////////////////////////////////////////////////////////////////////////
-fn parse_color_search(search_term: &str) -> (Option<MarkerColor>, String) {
-    let color_prefixes = [
-        ("red:", MarkerColor::Red),
-        ("orange:", MarkerColor::Orange),
-        ("yellow:", MarkerColor::Yellow),
-        ("blue:", MarkerColor::Blue),
-        ("cyan:", MarkerColor::Cyan),
-        ("pink:", MarkerColor::Pink),
-        ("white:", MarkerColor::White),
-        ("green:", MarkerColor::Green),
-        ("magenta:", MarkerColor::Magenta),
-        ("reset:", MarkerColor::Reset),
-        ("colored:", MarkerColor::Reset),
-        ("nocolor:", MarkerColor::Reset),
-        ("hidecolor:", MarkerColor::Reset),
-    ];
-
-    color_prefixes
-        .par_iter()
-        .find_first(|(prefix, _)| search_term.to_lowercase().starts_with(prefix))
-        .map(|(prefix, color)| (Some(*color), search_term[prefix.len()..].to_string()))
-        .unwrap_or((None, search_term.to_string()))
-}
-
-pub struct SearchFilters {
-    pub color_filters: HashMap<MarkerColor, bool>,
-    pub show_uncolored: bool,
-    pub show_filters: bool,
-    pub hide_all_colors: bool,
-}
-
-impl SearchFilters {
-    pub fn new() -> Self {
-        let mut color_filters = HashMap::new();
-        for color in [
-            MarkerColor::Red,
-            MarkerColor::Orange,
-            MarkerColor::Yellow,
-            MarkerColor::Blue,
-            MarkerColor::Cyan,
-            MarkerColor::Pink,
-            MarkerColor::White,
-            MarkerColor::Green,
-            MarkerColor::Magenta,
-            MarkerColor::Reset,
-        ] {
-            color_filters.insert(color, false);
-        }
-        Self {
-            color_filters,
-            show_uncolored: true,
-            show_filters: false,
-            hide_all_colors: false,
-        }
-    }
-}
-
-pub fn fuzzy_navigate(
-    current_dir: &Path,
-    input: &str,
-    max_results: usize,
-) -> io::Result<Vec<PathBuf>> {
-    let entries = fs::read_dir(current_dir)?
-        .filter_map(|entry| entry.ok())
-        .collect::<Vec<_>>();
-
-    let results: Vec<_> = entries
-        .into_par_iter()
-        .filter_map(|entry| {
-            let name = entry.file_name().to_string_lossy().into_owned();
-            let score = fuzzy_match(&name, input);
-            if score > 0 {
-                Some((entry.path(), score))
-            } else {
-                None
-            }
-        })
-        .collect();
-
-    let mut sorted_results = results;
-    sorted_results.sort_by(|a, b| b.1.cmp(&a.1));
-
-    Ok(sorted_results
-        .into_iter()
-        .take(max_results)
-        .map(|(path, _)| path)
-        .collect())
-}
-
-pub fn fuzzy_search_entries(
-    app_state: &mut AppState,
-    search_term: &str,
-) -> io::Result<Vec<FileEntry>> {
-    let (color_filter, actual_search_term) = parse_color_search(search_term);
-
-    fn search_directory(
-        app_state: &AppState,
-        dir: &Path,
-        search_term: &str,
-        color_filter: Option<MarkerColor>,
-        depth: usize,
-        max_depth: usize,
-    ) -> Vec<FileEntry> {
-        if depth > max_depth {
-            return Vec::new();
-        }
-
-        let mut results = Vec::new();
-
-        if let Ok(entries) = fs::read_dir(dir) {
-            let accessible_entries: Vec<_> = entries.filter_map(Result::ok).collect();
-
-            let mut entry_results: Vec<_> = accessible_entries
-                .par_iter()
-                .filter_map(|entry| {
-                    let path = entry.path();
-                    if !app_state.is_searchable(&path) {
-                        return None;
-                    }
-
-                    let item_color = app_state.get_item_color(&path);
-                    if app_state.search_filters.hide_all_colors && item_color.is_some() {
-                        return None;
-                    } else if search_term.to_lowercase().starts_with("hidecolor:") {
-                        if item_color.is_some() {
-                            return None;
-                        }
-                    }
-
-                    match color_filter {
-                        Some(filter_color) => {
-                            if app_state.search_filters.hide_all_colors && item_color.is_some() {
-                                return None;
-                            }
-                            if search_term.to_lowercase().starts_with("hidecolor:")
-                                && item_color.is_some()
-                            {
-                                return None;
-                            }
-                            if search_term.to_lowercase().starts_with("colored:")
-                                && item_color.is_none()
-                            {
-                                return None;
-                            }
-                            if search_term.to_lowercase().starts_with("nocolor:")
-                                && item_color.is_some()
-                            {
-                                return None;
-                            }
-                            if !search_term.to_lowercase().starts_with("colored:")
-                                && !search_term.to_lowercase().starts_with("nocolor:")
-                                && item_color != Some(filter_color)
-                            {
-                                return None;
-                            }
-                        }
-                        None => {
-                            let any_filters_active =
-                                app_state.search_filters.color_filters.values().any(|&v| v);
-                            if any_filters_active {
-                                match item_color {
-                                    Some(color) => {
-                                        if !app_state
-                                            .search_filters
-                                            .color_filters
-                                            .get(&color)
-                                            .copied()
-                                            .unwrap_or(false)
-                                        {
-                                            return None;
-                                        }
-                                    }
-                                    None => {
-                                        if !app_state.search_filters.show_uncolored {
-                                            return None;
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                    }
-
-                    let name = path.file_name().unwrap_or_default().to_string_lossy();
-                    let score = if search_term.is_empty() {
-                        1
-                    } else {
-                        fuzzy_match(&name, search_term)
-                    };
-
-                    if score > 0 {
-                        FileEntry::new(path).ok()
-                    } else {
-                        None
-                    }
-                })
-                .collect();
-
-            results.append(&mut entry_results);
-
-            let subdirs: Vec<_> = accessible_entries
-                .par_iter()
-                .filter(|entry| entry.path().is_dir() && app_state.is_searchable(&entry.path()))
-                .map(|entry| entry.path())
-                .collect();
-
-            let subdir_results: Vec<_> = subdirs
-                .par_iter()
-                .flat_map(|path| {
-                    search_directory(
-                        app_state,
-                        path,
-                        search_term,
-                        color_filter,
-                        depth + 1,
-                        max_depth,
-                    )
-                })
-                .collect();
-
-            results.extend(subdir_results);
-        }
-
-        results
-    }
-
-    let results = search_directory(
-        app_state,
-        &app_state.current_dir,
-        &actual_search_term,
-        color_filter,
-        0,
-        app_state.search_depth_limit,
-    );
-
-    let mut scored_results: Vec<_> = results
-        .into_par_iter()
-        .map(|entry| {
-            let score = if actual_search_term.is_empty() {
-                1
-            } else {
-                fuzzy_match(&entry.name, &actual_search_term)
-            };
-            (entry, score)
-        })
-        .collect();
-
-    scored_results.par_sort_unstable_by(|a, b| b.1.cmp(&a.1));
-
-    Ok(scored_results.into_iter().map(|(entry, _)| entry).collect())
-}
-
-pub fn fuzzy_match(name: &str, input: &str) -> usize {
-    let name_lower = name.to_lowercase();
-    let input_lower = input.to_lowercase();
-
-    if input_lower.len() > 10 {
-        let chars: Vec<_> = name_lower.chars().collect();
-        let input_chars: Vec<_> = input_lower.chars().collect();
-
-        let score = chars
-            .par_windows(input_chars.len())
-            .map(|window| {
-                let mut local_score = 0;
-                let mut consecutive = 0;
-
-                for (window_c, input_c) in window.iter().zip(input_chars.iter()) {
-                    if window_c == input_c {
-                        local_score += 1 + consecutive;
-                        consecutive += 1;
-                    } else {
-                        consecutive = 0;
-                    }
-                }
-
-                local_score
-            })
-            .max()
-            .unwrap_or(0);
-
-        let boundary_bonus = if name_lower.starts_with(&input_lower)
-            || name_lower
-                .split(|c| c == ' ' || c == '_' || c == '-')
-                .any(|word| word.starts_with(&input_lower))
-        {
-            5
-        } else {
-            0
-        };
-
-        (score + boundary_bonus).try_into().unwrap_or(0)
-    } else {
-        let mut score: i32 = 0;
-        let mut name_iter = name_lower.chars().peekable();
-        let mut input_iter = input_lower.chars().peekable();
-        let mut consecutive = 0;
-        let mut last_matched_pos = None;
-
-        while let (Some(name_c), Some(input_c)) = (name_iter.peek(), input_iter.peek()) {
-            if name_c == input_c {
-                score += 1 + consecutive;
-                consecutive += 1;
-                last_matched_pos = Some(name_lower.len() - name_iter.clone().count());
-                name_iter.next();
-                input_iter.next();
-            } else {
-                consecutive = 0;
-                name_iter.next();
-            }
-        }
-
-        score = score.saturating_sub(input_iter.count() as i32);
-
-        if let Some(pos) = last_matched_pos {
-            if pos == 0
-                || name_lower
-                    .chars()
-                    .nth(pos.saturating_sub(1))
-                    .map_or(false, |c| c == ' ' || c == '_' || c == '-')
-            {
-                score += 5;
-            }
-        }
-
-        score.try_into().unwrap_or(0)
-    }
-}
 // Remember to fix so that these entries can be sorted.
 pub fn read_search_input(stdout: &mut impl Write, app_state: &mut AppState) -> io::Result<String> {
     let (width, height) = size()?;
@@ -374,7 +48,7 @@ pub fn read_search_input(stdout: &mut impl Write, app_state: &mut AppState) -> i
     let mut cursor_pos = prompt_length;
 
     loop {
-        if let Event::Key(key_event) = event::read()? {
+        if let Event::Key(key_event) = read_event()? {
             match key_event.code {
                 KeyCode::Enter => {
                     app_state.last_search_term = input.clone();
@@ -427,7 +101,7 @@ pub fn display_search_results(
     mut results: Vec<FileEntry>,
     stdout: &mut impl Write,
 ) -> io::Result<Option<PathBuf>> {
-    let (width, height) = crossterm::terminal::size()?;
+    let (width, height) = size()?;
     let start_y = 11;
     let end_y = height - 2;
     let visible_lines = (end_y - start_y) as usize;
@@ -435,7 +109,7 @@ pub fn display_search_results(
     let mut scroll_offset = 0;
     let mut last_selected_index = 0;
     let mut last_scroll_offset = 0;
-    let mut g_pressed = false;
+    let mut keys = KeySequencer::new();
     let mut sort_order = app_state.config.default_sort.clone();
     draw_search_results(
         app_state,
@@ -451,12 +125,22 @@ pub fn display_search_results(
     )?;
     execute!(stdout, cursor::Hide)?;
     loop {
-        if let Event::Key(key) = event::read()? {
-            if let Some(action) = app_state.config.clone().get_action(&key) {
+        // Held keys run what they have once their time is up.
+        let (event, timed_out) = match keys.time_left(&app_state.config) {
+            Some(left) if !poll_event(left)? => (Event::Key(KeyEvent::from(KeyCode::Null)), true),
+            _ => (read_event()?, false),
+        };
+        if let Event::Key(key) = event {
+            let actions = if timed_out {
+                keys.flush(&app_state.config)
+            } else {
+                keys.feed(&app_state.config, key.into())
+            };
+            for action in actions {
                 if app_state.search_filters.show_filters
                     && handle_search_filter_keys(app_state, key)
                 {
-                    results = fuzzy_search_entries(app_state, &app_state.last_search_term.clone())?;
+                    results = app_state.search_entries(&app_state.last_search_term.clone())?;
                     draw_search_results(
                         app_state,
                         stdout,
@@ -473,24 +157,14 @@ pub fn display_search_results(
                 }
                 match action {
                     Action::GoToTop => {
-                        if g_pressed {
-                            selected_index = 0;
-                            scroll_offset = 0;
-                            g_pressed = false;
-                        } else {
-                            g_pressed = true;
-                        }
+                        selected_index = 0;
+                        scroll_offset = 0;
                     }
 
                     Action::GoToBottom => {
-                        if g_pressed {
-                            selected_index = results.len() - 1;
-                            if results.len() > (visible_lines) as usize {
-                                scroll_offset = (results.len()) - visible_lines as usize;
-                            }
-                            g_pressed = false;
-                        } else {
-                            g_pressed = false;
+                        selected_index = results.len().saturating_sub(1);
+                        if results.len() > (visible_lines) as usize {
+                            scroll_offset = (results.len()) - visible_lines as usize;
                         }
                     }
 
@@ -599,7 +273,7 @@ pub fn display_search_results(
                             clear_nav()?;
                             clear_preview()?;
                             draw_initial_border(stdout, &app_state.page_state)?;
-                            let _ = app_state.config.save_config();
+                            let _ = app_state.save_config();
 
                             return Ok(None);
                         }
@@ -622,26 +296,27 @@ pub fn display_search_results(
                     }
                     Action::MoveItem => {
                         if let Some(entry) = results.get(selected_index as usize) {
-                            if app_state.is_moving {
-                                app_state
-                                    .finish_move(&app_state.current_dir.clone())
-                                    .expect("could not move");
+                            let moved = if app_state.is_moving {
+                                app_state.finish_move(&app_state.current_dir.clone())
                             } else {
-                                app_state
-                                    .start_move(Some(entry))
-                                    .expect("could not start moving file");
+                                app_state.start_move(Some(entry))
+                            };
+                            if let Err(e) = moved {
+                                let _ = clear_interaction_field();
+                                interaction_field!("Error moving file(s): {}", e)?;
+                                app_state.cancel_move();
                             }
                         }
                     }
                     Action::Paste => {
                         let _ = paste_files(app_state, &app_state.current_dir.clone());
                         results =
-                            fuzzy_search_entries(app_state, &app_state.last_search_term.clone())?;
+                            app_state.search_entries(&app_state.last_search_term.clone())?;
                     }
                     Action::Duplicate => {
                         duplicate_files(stdout, app_state, &results, Some(selected_index))?;
                         results =
-                            fuzzy_search_entries(app_state, &app_state.last_search_term.clone())?;
+                            app_state.search_entries(&app_state.last_search_term.clone())?;
                     }
                     Action::CycleItemColor => {
                         app_state.changing_color = true;
@@ -710,7 +385,7 @@ pub fn display_search_results(
                                 true,
                             )?;
                         }
-                        let _ = app_state.config.save_config();
+                        let _ = app_state.save_config();
                     }
                     Action::OpenInEditor => {
                         if let Some(entry) = results.get(selected_index as usize) {
@@ -964,6 +639,9 @@ pub fn draw_search_results(
     Ok(())
 }
 fn dim_color(color: Color, dim_factor: u8) -> Color {
+    fit_color(dim_rgb(color, dim_factor))
+}
+fn dim_rgb(color: Color, dim_factor: u8) -> Color {
     match color {
         Color::Rgb { r, g, b } => {
             let dim = |v: u8| -> u8 {
@@ -976,7 +654,7 @@ fn dim_color(color: Color, dim_factor: u8) -> Color {
                 b: dim(b),
             }
         }
-        Color::Red => dim_color(Color::Rgb { r: 255, g: 0, b: 0 }, dim_factor),
+        Color::Red => dim_rgb(Color::Rgb { r: 255, g: 0, b: 0 }, dim_factor),
         Color::DarkGrey => {
             let base = 128u8;
             let dim = (base as f32 * (100 - dim_factor) as f32 / 100.0) as u8;
@@ -1012,7 +690,7 @@ pub fn set_search_depth_limit(app_state: &mut AppState, stdout: &mut impl Write)
 
     writeln!(stdout, "Press any key to continue...")?;
     stdout.flush()?;
-    event::read()?;
+    read_event()?;
 
     Ok(())
 }
@@ -0,0 +1,469 @@
+/*
+* Stygian Sift - A Terminal-based File Manager
+ * Copyright (C) 2024 Maui The Magnificent (Charon)
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published
+ * by the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+ * Contact: Maui_The_Magnificent@proton.me
+ * Project repository: https://github.com/Mauitron/StygianSift.git
+ */
+
+use super::*;
+use std::sync::{Arc, RwLock};
+/////////////////////////////////////////////////////////Themes///////////////////////////////////////////////////////////////////////
+// Glyphs and colors for entries, plus the palette the borders, headers and
+// previews are drawn with. Three themes are built in, anything else is read
+// from a theme file:
+//
+//   base = nerd
+//   type.Rust = 🦀 #dea584
+//   ext.md = "M " blue
+//   filename.Makefile = 🔨
+//   palette.border = dark_grey
+
+pub const BUILTIN_THEMES: [&str; 3] = ["emoji", "nerd", "ascii"];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Glyph {
+    pub symbol: String,
+    pub color: Option<Color>,
+}
+
+impl Glyph {
+    fn new(symbol: &str) -> Self {
+        Self {
+            symbol: symbol.to_string(),
+            color: None,
+        }
+    }
+
+    fn colored(symbol: &str, color: Color) -> Self {
+        Self {
+            symbol: symbol.to_string(),
+            color: Some(color),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Palette {
+    pub border: Color,
+    pub spine: Color,
+    pub accent: Color,
+    pub accent_dark: Color,
+    pub header: Color,
+    // The colors the dimming starts out from.
+    pub entry: Color,
+    pub selection: Color,
+    pub preview: Color,
+    pub folder_preview: Color,
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        Self {
+            border: Color::Reset,
+            spine: Color::DarkYellow,
+            accent: Color::Red,
+            accent_dark: Color::DarkRed,
+            header: Color::Green,
+            entry: Color::Reset,
+            selection: Color::DarkGreen,
+            preview: Color::Yellow,
+            folder_preview: Color::DarkYellow,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Theme {
+    pub name: String,
+    pub directory: Glyph,
+    pub file: Glyph,
+    pub admin_required: String,
+    pub read_only: String,
+    pub types: HashMap<FileType, Glyph>,
+    pub extensions: HashMap<String, Glyph>,
+    pub names: HashMap<String, Glyph>,
+    pub palette: Palette,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::emoji()
+    }
+}
+
+impl Theme {
+    fn with_types(name: &str, directory: Glyph, file: Glyph, types: &[(FileType, Glyph)]) -> Self {
+        Self {
+            name: name.to_string(),
+            directory,
+            file,
+            admin_required: "🔐".to_string(),
+            read_only: "🔏".to_string(),
+            types: types.iter().cloned().collect(),
+            extensions: HashMap::new(),
+            names: HashMap::new(),
+            palette: Palette::default(),
+        }
+    }
+
+    pub fn emoji() -> Self {
+        Self::with_types(
+            "emoji",
+            Glyph::new("📁"),
+            Glyph::new("📄"),
+            &[
+                (FileType::Directory, Glyph::new(" ")),
+                (FileType::Text, Glyph::new("📄")),
+                (FileType::Log, Glyph::new("📜")),
+                (FileType::Document, Glyph::new("📘")),
+                (FileType::Image, Glyph::new("🌃")),
+                (FileType::Binary, Glyph::new("💽")),
+                (FileType::Config, Glyph::new("📑")),
+                (FileType::Executable, Glyph::new("🎮")),
+                (FileType::Unknown, Glyph::new("  ")),
+                (FileType::Rust, Glyph::new("🦀")),
+                (FileType::Nix, Glyph::new("❄")),
+                (FileType::Zig, Glyph::new("⚡")),
+                (FileType::Source, Glyph::new("🧩")),
+                (FileType::Script, Glyph::new("🐚")),
+                (FileType::Build, Glyph::new("🔨")),
+                (FileType::Data, Glyph::new("📊")),
+                (FileType::Archive, Glyph::new("📦")),
+                (FileType::Audio, Glyph::new("🎵")),
+                (FileType::Video, Glyph::new("🎬")),
+            ],
+        )
+    }
+
+    // Needs a Nerd Font, in return every type gets its own color.
+    pub fn nerd() -> Self {
+        let mut theme = Self::with_types(
+            "nerd",
+            Glyph::colored("\u{f07b}", Color::Blue),
+            Glyph::new("\u{f15b}"),
+            &[
+                (FileType::Directory, Glyph::new(" ")),
+                (FileType::Text, Glyph::new("\u{f15c}")),
+                (FileType::Log, Glyph::colored("\u{f0f6}", Color::DarkGrey)),
+                (FileType::Document, Glyph::colored("\u{f1c2}", Color::Blue)),
+                (FileType::Image, Glyph::colored("\u{f1c5}", Color::Magenta)),
+                (FileType::Binary, Glyph::colored("\u{f471}", Color::DarkGrey)),
+                (FileType::Config, Glyph::colored("\u{e615}", Color::DarkYellow)),
+                (FileType::Executable, Glyph::colored("\u{f013}", Color::Green)),
+                (FileType::Unknown, Glyph::new(" ")),
+                (FileType::Rust, Glyph::colored("\u{e7a8}", Color::Rgb { r: 222, g: 165, b: 132 })),
+                (FileType::Nix, Glyph::colored("\u{f313}", Color::Rgb { r: 126, g: 186, b: 228 })),
+                (FileType::Zig, Glyph::colored("\u{f0e7}", Color::Rgb { r: 247, g: 164, b: 29 })),
+                (FileType::Source, Glyph::colored("\u{f121}", Color::Cyan)),
+                (FileType::Script, Glyph::colored("\u{f489}", Color::Green)),
+                (FileType::Build, Glyph::colored("\u{f0ad}", Color::Yellow)),
+                (FileType::Data, Glyph::colored("\u{f1c0}", Color::DarkCyan)),
+                (FileType::Archive, Glyph::colored("\u{f410}", Color::Red)),
+                (FileType::Audio, Glyph::colored("\u{f001}", Color::DarkMagenta)),
+                (FileType::Video, Glyph::colored("\u{f03d}", Color::DarkMagenta)),
+            ],
+        );
+        theme.admin_required = "\u{f023}".to_string();
+        theme.read_only = "\u{f06e}".to_string();
+        for (extension, symbol) in [
+            ("py", "\u{e606}"),
+            ("js", "\u{e74e}"),
+            ("ts", "\u{e628}"),
+            ("go", "\u{e626}"),
+            ("md", "\u{e609}"),
+            ("json", "\u{e60b}"),
+            ("html", "\u{e736}"),
+            ("css", "\u{e749}"),
+            ("c", "\u{e61e}"),
+            ("cpp", "\u{e61d}"),
+            ("java", "\u{e738}"),
+            ("lua", "\u{e620}"),
+        ] {
+            theme.extensions.insert(extension.to_string(), Glyph::new(symbol));
+        }
+        for (name, symbol) in [
+            ("Dockerfile", "\u{f308}"),
+            (".gitignore", "\u{e702}"),
+            (".gitattributes", "\u{e702}"),
+            ("Cargo.toml", "\u{e7a8}"),
+        ] {
+            theme.names.insert(name.to_string(), Glyph::new(symbol));
+        }
+        theme
+    }
+
+    // Plain ASCII for terminals and fonts that cannot show anything else.
+    pub fn ascii() -> Self {
+        let mut theme = Self::with_types(
+            "ascii",
+            Glyph::new("+ "),
+            Glyph::new("- "),
+            &[
+                (FileType::Directory, Glyph::new("  ")),
+                (FileType::Text, Glyph::new("tx")),
+                (FileType::Log, Glyph::new("lg")),
+                (FileType::Document, Glyph::new("dc")),
+                (FileType::Image, Glyph::new("im")),
+                (FileType::Binary, Glyph::new("bn")),
+                (FileType::Config, Glyph::new("cf")),
+                (FileType::Executable, Glyph::new("ex")),
+                (FileType::Unknown, Glyph::new("  ")),
+                (FileType::Rust, Glyph::new("rs")),
+                (FileType::Nix, Glyph::new("nx")),
+                (FileType::Zig, Glyph::new("zg")),
+                (FileType::Source, Glyph::new("<>")),
+                (FileType::Script, Glyph::new("$ ")),
+                (FileType::Build, Glyph::new("mk")),
+                (FileType::Data, Glyph::new("db")),
+                (FileType::Archive, Glyph::new("ar")),
+                (FileType::Audio, Glyph::new("au")),
+                (FileType::Video, Glyph::new("vd")),
+            ],
+        );
+        theme.admin_required = "!!".to_string();
+        theme.read_only = "ro".to_string();
+        theme
+    }
+
+    pub fn builtin(name: &str) -> Option<Self> {
+        match name {
+            "emoji" => Some(Self::emoji()),
+            "nerd" => Some(Self::nerd()),
+            "ascii" => Some(Self::ascii()),
+            _ => None,
+        }
+    }
+
+    // A built-in name or the path of a theme file.
+    pub fn load(spec: &str) -> io::Result<Self> {
+        if let Some(theme) = Self::builtin(spec) {
+            return Ok(theme);
+        }
+        let text = fs::read_to_string(spec)?;
+        Self::parse(&text).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut theme = Self::emoji();
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let fail = |message: &str| format!("line {}: {}", number + 1, message);
+            let (key, value) = line
+                .split_once('=')
+                .map(|(key, value)| (key.trim(), value.trim()))
+                .ok_or_else(|| fail("expected key = value"))?;
+
+            match key {
+                "base" => {
+                    let name = theme.name.clone();
+                    theme = Self::builtin(value).ok_or_else(|| fail("unknown base theme"))?;
+                    theme.name = name;
+                }
+                "name" => theme.name = value.to_string(),
+                "directory" => theme.directory = parse_glyph(value).ok_or_else(|| fail("bad glyph"))?,
+                "file" => theme.file = parse_glyph(value).ok_or_else(|| fail("bad glyph"))?,
+                "admin_required" => theme.admin_required = unquote(value).to_string(),
+                "read_only" => theme.read_only = unquote(value).to_string(),
+                _ => {
+                    let (group, item) = key.split_once('.').ok_or_else(|| fail("unknown key"))?;
+                    if group == "palette" {
+                        let color = parse_color(value).ok_or_else(|| fail("unknown color"))?;
+                        theme.set_palette(item, color).ok_or_else(|| fail("unknown palette entry"))?;
+                        continue;
+                    }
+                    let glyph = parse_glyph(value).ok_or_else(|| fail("bad glyph"))?;
+                    match group {
+                        "type" => {
+                            let file_type = item.parse().map_err(|e: String| fail(&e))?;
+                            theme.types.insert(file_type, glyph);
+                        }
+                        "ext" => {
+                            theme.extensions.insert(item.to_ascii_lowercase(), glyph);
+                        }
+                        "filename" => {
+                            theme.names.insert(item.to_string(), glyph);
+                        }
+                        _ => return Err(fail("unknown key")),
+                    }
+                }
+            }
+        }
+        Ok(theme)
+    }
+
+    fn set_palette(&mut self, slot: &str, color: Color) -> Option<()> {
+        let palette = &mut self.palette;
+        let target = match slot {
+            "border" => &mut palette.border,
+            "spine" => &mut palette.spine,
+            "accent" => &mut palette.accent,
+            "accent_dark" => &mut palette.accent_dark,
+            "header" => &mut palette.header,
+            "entry" => &mut palette.entry,
+            "selection" => &mut palette.selection,
+            "preview" => &mut palette.preview,
+            "folder_preview" => &mut palette.folder_preview,
+            _ => return None,
+        };
+        *target = color;
+        Some(())
+    }
+
+    // File names win over extensions, extensions over the detected type.
+    pub fn glyph_for(&self, name: &str, file_type: FileType) -> Option<&Glyph> {
+        let extension = name.rsplit_once('.').map(|(_, ext)| ext.to_ascii_lowercase());
+        self.names
+            .get(name)
+            .or_else(|| extension.and_then(|ext| self.extensions.get(&ext)))
+            .or_else(|| self.types.get(&file_type))
+    }
+
+    pub fn entry_glyph(&self, is_dir: bool) -> &Glyph {
+        if is_dir {
+            &self.directory
+        } else {
+            &self.file
+        }
+    }
+
+    // Hex colors from theme files are brought down to what the terminal has.
+    fn fit_colors(&mut self) {
+        let glyphs = [&mut self.directory, &mut self.file]
+            .into_iter()
+            .chain(self.types.values_mut())
+            .chain(self.extensions.values_mut())
+            .chain(self.names.values_mut());
+        for glyph in glyphs {
+            glyph.color = glyph.color.map(fit_color);
+        }
+        let palette = &mut self.palette;
+        for color in [
+            &mut palette.border,
+            &mut palette.spine,
+            &mut palette.accent,
+            &mut palette.accent_dark,
+            &mut palette.header,
+            &mut palette.entry,
+            &mut palette.selection,
+            &mut palette.preview,
+            &mut palette.folder_preview,
+        ] {
+            *color = fit_color(*color);
+        }
+    }
+
+    pub fn lock_glyph(&self, admin_required: bool, read_only: bool) -> &str {
+        if admin_required {
+            &self.admin_required
+        } else if read_only {
+            &self.read_only
+        } else {
+            " "
+        }
+    }
+}
+
+fn unquote(value: &str) -> &str {
+    value
+        .strip_prefix('"')
+        .and_then(|value| value.strip_suffix('"'))
+        .unwrap_or(value)
+}
+
+// `🦀`, `🦀 red` or `"  " #ff0000`, quotes keep spaces in the glyph.
+fn parse_glyph(value: &str) -> Option<Glyph> {
+    let (symbol, rest) = match value.strip_prefix('"') {
+        Some(quoted) => {
+            let end = quoted.find('"')?;
+            (&quoted[..end], quoted[end + 1..].trim())
+        }
+        None => match value.split_once(char::is_whitespace) {
+            Some((symbol, rest)) => (symbol, rest.trim()),
+            None => (value, ""),
+        },
+    };
+    let color = if rest.is_empty() {
+        None
+    } else {
+        Some(parse_color(rest)?)
+    };
+    Some(Glyph {
+        symbol: symbol.to_string(),
+        color,
+    })
+}
+
+pub fn parse_color(value: &str) -> Option<Color> {
+    if let Some(hex) = value.strip_prefix('#') {
+        if hex.len() != 6 {
+            return None;
+        }
+        // get, not indexing: six bytes can still split a character.
+        let channel = |at: usize| u8::from_str_radix(hex.get(at..at + 2)?, 16).ok();
+        return Some(Color::Rgb {
+            r: channel(0)?,
+            g: channel(2)?,
+            b: channel(4)?,
+        });
+    }
+    let color = match value.to_ascii_lowercase().replace(['-', ' '], "_").as_str() {
+        "reset" | "default" => Color::Reset,
+        "black" => Color::Black,
+        "dark_grey" | "dark_gray" => Color::DarkGrey,
+        "red" => Color::Red,
+        "dark_red" => Color::DarkRed,
+        "green" => Color::Green,
+        "dark_green" => Color::DarkGreen,
+        "yellow" => Color::Yellow,
+        "dark_yellow" => Color::DarkYellow,
+        "blue" => Color::Blue,
+        "dark_blue" => Color::DarkBlue,
+        "magenta" => Color::Magenta,
+        "dark_magenta" => Color::DarkMagenta,
+        "cyan" => Color::Cyan,
+        "dark_cyan" => Color::DarkCyan,
+        "white" => Color::White,
+        "grey" | "gray" => Color::Grey,
+        _ => return None,
+    };
+    Some(color)
+}
+
+static ACTIVE_THEME: RwLock<Option<Arc<Theme>>> = RwLock::new(None);
+
+// Borders and previews are drawn from places that never see the config,
+// so the active theme is kept here, like the file type rules.
+pub fn set_theme(mut theme: Theme) {
+    theme.fit_colors();
+    if let Ok(mut active) = ACTIVE_THEME.write() {
+        *active = Some(Arc::new(theme));
+    }
+}
+
+pub fn current_theme() -> Arc<Theme> {
+    if let Some(theme) = ACTIVE_THEME.read().ok().and_then(|active| active.clone()) {
+        return theme;
+    }
+    let theme = Arc::new(Theme::default());
+    if let Ok(mut active) = ACTIVE_THEME.write() {
+        active.get_or_insert_with(|| theme.clone());
+    }
+    theme
+}
@@ -22,6 +22,7 @@
 use main_nav_loop::BrowseResult;
 
 use super::*;
+use std::sync::Arc;
 pub struct ScrollState {
     pub offset: usize,
 }
@@ -56,6 +57,7 @@ pub struct AppState {
     pub file_to_move: Option<PathBuf>,
     pub is_moving: bool,
     pub undo_manager: UndoManager,
+    pub fs: Arc<dyn FileSystem>,
     pub current_dir: PathBuf,
     pub last_browsed_dir: PathBuf,
     pub nav_stack: Vec<NavigationInfo>,
@@ -71,18 +73,74 @@ pub struct AppState {
     pub search_filters: SearchFilters,
     pub mouse_state: MouseState,
     pub input_mode: InputMode,
+    pub structured_preview: StructuredPreviewState,
+    pub archive_view: Option<ArchiveView>,
+    pub jobs: Vec<BackgroundJob>,
+    // Set when another program uses the browser to pick paths.
+    pub chooser: Option<Chooser>,
+    pub mouse_enabled: bool,
+    // A search to run as soon as the browser opens.
+    pub start_search: Option<String>,
+    // Where the config is read from and saved to, ./.maui unless --config
+    // names another file.
+    pub config_path: PathBuf,
+    // Problems with the config file, shown once the browser is up.
+    pub config_messages: Vec<String>,
+    // When the config file last changed, to notice edits from outside.
+    pub config_modified: Option<SystemTime>,
+    // Keys held while waiting for the rest of a sequence, and the mode.
+    pub key_sequence: KeySequencer,
+    // Where visual mode started, the selection runs from here to the cursor.
+    pub visual_anchor: Option<usize>,
+    // Lines run from the ":" line, oldest first.
+    pub command_history: Vec<String>,
+    // What the command palette ran, latest first.
+    pub recent_commands: Vec<PaletteCommand>,
+    // The macro being recorded, if one is.
+    pub macro_recorder: Option<MacroRecorder>,
+    // What is left of the macro being played.
+    pub macro_replay: VecDeque<ReplayStep>,
 }
 
 impl AppState {
     pub fn new() -> io::Result<Self> {
-        let config = Config::load_config().unwrap_or_else(|_| Config::new());
+        Self::load(Config::get_config_path()?)
+    }
+
+    // Loads the config from config_path, which is also where it gets saved.
+    pub fn load(config_path: PathBuf) -> io::Result<Self> {
+        let (config, messages) = match Config::load_checked(&config_path) {
+            Ok((mut config, report)) => {
+                let profile = config.profile.clone();
+                if !profile.is_empty() {
+                    let _ = config.use_profile(Some(&profile));
+                }
+                apply_look(&config);
+                (config, report.messages(&config_path))
+            }
+            Err(_) => (Config::new(), Vec::new()),
+        };
+        let mut state = Self::with_config(config)?;
+        state.config_messages = messages;
+        state.config_modified = config_modified(&config_path);
+        state.config_path = config_path;
+        // What was done in earlier sessions can still be undone.
+        let _ = state.undo_manager.load_history();
+        Ok(state)
+    }
+
+    // Starts from the given config instead of the one in the working directory.
+    pub fn with_config(config: Config) -> io::Result<Self> {
+        Self::with_file_system(config, Arc::new(RealFileSystem))
+    }
+
+    // File operations and undo go through fs, the listing still reads the disk.
+    pub fn with_file_system(config: Config, fs: Arc<dyn FileSystem>) -> io::Result<Self> {
         let current_dir = config
             .home_folder
             .clone()
             .unwrap_or_else(|| std::env::current_dir().unwrap());
 
-        let temp_dir = std::env::temp_dir().join("file_manager_undo");
-
         Ok(Self {
             lines: config.lines_shown,
             input: Vec::new(),
@@ -99,15 +157,11 @@ impl AppState {
             file_to_move: None,
             is_moving: false,
             scroll_state: ScrollState::new(),
-            undo_manager: UndoManager::new(
-                temp_dir,
-                config.ram_undo_limit,
-                config.disk_undo_limit,
-                config.allow_disk_undo,
-            )?,
+            undo_manager: UndoManager::with_config(fs.clone(), &config)?,
             search_depth_limit: config.search_depth_limit,
             nav_stack: Vec::new(),
             colored_items: HashMap::new(),
+            fs,
             current_dir,
             git_menu: None,
             config,
@@ -121,9 +175,96 @@ impl AppState {
             search_filters: SearchFilters::new(),
             mouse_state: MouseState::new(),
             input_mode: InputMode::Keyboard,
+            structured_preview: StructuredPreviewState::new(),
+            archive_view: None,
+            jobs: Vec::new(),
+            chooser: None,
+            mouse_enabled: true,
+            start_search: None,
+            config_path: Config::get_config_path()?,
+            config_messages: Vec::new(),
+            config_modified: None,
+            key_sequence: KeySequencer::new(),
+            visual_anchor: None,
+            command_history: Vec::new(),
+            recent_commands: Vec::new(),
+            macro_recorder: None,
+            macro_replay: VecDeque::new(),
         })
     }
 
+    pub fn switch_profile(&mut self, name: Option<&str>) -> Result<(), String> {
+        self.config.use_profile(name)?;
+        apply_look(&self.config);
+        Ok(())
+    }
+
+    // Loads the config file again when it changed on disk other than by our
+    // own saving. What only holds for this session, overrides from the
+    // command line, the profile and .stygian files, is kept. Gives whether
+    // the config was reloaded.
+    pub fn reload_changed_config(&mut self, current_dir: &Path) -> io::Result<bool> {
+        let config_path = self.config_path.clone();
+        let modified = config_modified(&config_path);
+        if modified == self.config_modified {
+            return Ok(false);
+        }
+        self.config_modified = modified;
+        match fs::read_to_string(&config_path) {
+            Ok(text) if text != self.config.saved_toml() => {}
+            _ => return Ok(false),
+        }
+        let (mut config, report) = match Config::load_checked(&config_path) {
+            Ok(loaded) => loaded,
+            Err(e) => {
+                self.config_messages
+                    .push(format!("Could not reload {}: {}", config_path.display(), e));
+                return Ok(false);
+            }
+        };
+
+        // Put back on in the order they went on at startup.
+        let mut session = self.config.clone();
+        session.leave_local();
+        let overrides: Vec<(String, String)> = self
+            .config
+            .overridden
+            .keys()
+            .filter_map(|key| Some((key.clone(), session.get_value(key)?)))
+            .collect();
+        let (profile, overrides): (Vec<_>, Vec<_>) =
+            overrides.into_iter().partition(|(key, _)| key == "profile");
+        for (key, value) in profile {
+            let _ = config.override_value(&key, &value);
+        }
+        let profile = config.profile.clone();
+        if !profile.is_empty() {
+            let _ = config.use_profile(Some(&profile));
+        }
+        for (key, value) in overrides {
+            let _ = config.override_value(&key, &value);
+        }
+        let mut messages = vec![format!("Reloaded {}", config_path.display())];
+        messages.extend(report.messages(&config_path));
+        messages.extend(config.enter_dir(current_dir));
+
+        apply_look(&config);
+        self.lines = config.lines_shown;
+        self.search_depth_limit = config.search_depth_limit;
+        self.undo_manager.ram_limit = config.ram_undo_limit;
+        self.undo_manager.disk_limit = config.disk_undo_limit;
+        self.undo_manager.allow_disk_storage = config.allow_disk_undo;
+        self.config = config;
+        self.config_messages.extend(messages);
+        Ok(true)
+    }
+
+    pub fn in_archive(&self) -> bool {
+        self.archive_view
+            .as_ref()
+            .is_some_and(|view| view.contains(&self.current_dir))
+    }
+
     pub fn display_current_layer(&self, stdout: &mut impl Write) -> io::Result<()> {
         let (width, height) = size()?;
         let nav_width = width / 2;
@@ -241,20 +382,13 @@ impl AppState {
 
         let new_dir_path = self.current_dir.join(&name);
 
-        if new_dir_path.exists() {
-            interaction_field!("Error: '{}' already exists.", name)?;
-            return Ok(());
-        }
-
-        if !self.check_creation_allowed(&new_dir_path) {
-            interaction_field!("Creation not allowed due to color rules or permissions.")?;
-            return Ok(());
-        }
-
-        match fs::create_dir(&new_dir_path) {
-            Ok(_) => {
-                self.add_create_undo_entry(&new_dir_path, true)?;
-                interaction_field!("Directory '{}' created successfully.", name)?;
+        match self.file_operations().make_directory(&new_dir_path) {
+            Ok(_) => interaction_field!("Directory '{}' created successfully.", name)?,
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {
+                interaction_field!("Error: '{}' already exists.", name)?
+            }
+            Err(e) if e.kind() == io::ErrorKind::PermissionDenied => {
+                interaction_field!("Creation not allowed due to color rules or permissions.")?
             }
             Err(e) => interaction_field!("Failed to create directory: {}", e)?,
         }
@@ -277,36 +411,27 @@ impl AppState {
 
         let new_file_path = self.current_dir.join(&name);
 
-        if new_file_path.exists() {
-            writeln!(stdout, "Error: '{}' already exists.", name)?;
-            return Ok(());
-        }
-
-        if !self.check_creation_allowed(&new_file_path) {
-            writeln!(
+        match self.file_operations().make_file(&new_file_path) {
+            Ok(_) => writeln!(stdout, "File '{}' created successfully.", name)?,
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {
+                writeln!(stdout, "Error: '{}' already exists.", name)?
+            }
+            Err(e) if e.kind() == io::ErrorKind::PermissionDenied => writeln!(
                 stdout,
                 "Creation not allowed due to color rules or permissions."
-            )?;
-            return Ok(());
-        }
-
-        match fs::File::create(&new_file_path) {
-            Ok(_) => {
-                self.add_create_undo_entry(&new_file_path, false)?;
-                writeln!(stdout, "File '{}' created successfully.", name)?;
-            }
+            )?,
             Err(e) => writeln!(stdout, "Failed to create file: {}", e)?,
         }
 
         Ok(())
     }
 
-    fn check_creation_allowed(&self, path: &Path) -> bool {
-        let parent_path = path.parent().unwrap_or(Path::new(""));
-        self.check_operation_allowed(parent_path, "create")
+    // The file operations, checked against this config and recorded for undo.
+    pub fn file_operations(&mut self) -> FileOperations<'_> {
+        FileOperations::new(&self.config, &mut self.undo_manager)
     }
 
-    fn prompt_for_name(&self, stdout: &mut impl Write, prompt: &str) -> io::Result<Option<String>> {
+    pub fn prompt_for_name(&self, stdout: &mut impl Write, prompt: &str) -> io::Result<Option<String>> {
         writeln!(stdout, "{}", prompt)?;
         stdout.flush()?;
 
@@ -318,8 +443,8 @@ impl AppState {
                 return Ok(None);
             }
 
-            if event::poll(Duration::from_millis(100))? {
-                if let Event::Key(key) = event::read()? {
+            if poll_event(Duration::from_millis(100))? {
+                if let Event::Key(key) = read_event()? {
                     match key.code {
                         KeyCode::Enter => {
                             if !name.is_empty() {
@@ -350,17 +475,9 @@ impl AppState {
         Ok(Some(name))
     }
 
-    fn add_create_undo_entry(&mut self, path: &Path, is_directory: bool) -> io::Result<()> {
-        self.undo_manager.add_tome_entry(UndoEntry {
-            operation: Operation::Create {
-                path: path.to_path_buf(),
-                is_directory,
-                timestamp: std::time::SystemTime::now(),
-            },
-            storage: UndoStorage::Ram(Vec::new()),
-            original_path: path.to_path_buf(),
-            size: 0,
-        })
+    pub fn add_create_undo_entry(&mut self, path: &Path, is_directory: bool) -> io::Result<()> {
+        self.undo_manager
+            .add_create_operation(path.to_path_buf(), is_directory)
     }
 
     pub fn update_current_dir(&mut self, new_dir: PathBuf) {
@@ -383,20 +500,20 @@ impl AppState {
             .current_dir(&self.current_dir)
             .output()?;
 
-        let mut stdout = io::stdout();
+        let mut stdout = screen();
         execute!(stdout, Clear(ClearType::All))?;
         writeln!(stdout, "Git command output:\r")?;
         writeln!(stdout, "{}\r", String::from_utf8_lossy(&output.stdout))?;
         writeln!(stdout, "{}\r", String::from_utf8_lossy(&output.stderr))?;
         writeln!(stdout, "Press any key to continue...\r")?;
         stdout.flush()?;
-        event::read()?;
+        read_event()?;
 
         Ok(())
     }
 
     pub fn read_input(&self, prompt: &str) -> io::Result<String> {
-        let mut stdout = io::stdout();
+        let mut stdout = screen();
         execute!(stdout, Clear(ClearType::CurrentLine))?;
         write!(stdout, "{}", prompt)?;
         stdout.flush()?;
@@ -407,57 +524,22 @@ impl AppState {
     }
 
     pub fn check_operation_allowed(&self, path: &Path, operation: &str) -> bool {
-        if !self.check_single_item_allowed(path, operation) {
-            return false;
-        }
-
-        if path.is_dir() {
-            self.check_directory_contents_allowed(path, operation)
-        } else {
-            true
-        }
-    }
-    fn check_single_item_allowed(&self, path: &Path, operation: &str) -> bool {
-        if let Some(color) = self.get_item_color(path) {
-            if let Some(rule) = self.config.color_rules.get(&color) {
-                match operation {
-                    "delete" => rule.allow_delete,
-                    "rename" => rule.allow_rename,
-                    "move" => rule.allow_move,
-                    "copy" => rule.allow_copy,
-                    _ => true,
-                }
-            } else {
-                true
-            }
-        } else {
-            true
-        }
+        self.config.operation_allowed(self.fs.as_ref(), path, operation)
     }
 
-    fn check_directory_contents_allowed(&self, dir_path: &Path, operation: &str) -> bool {
-        if let Ok(entries) = fs::read_dir(dir_path) {
-            for entry in entries {
-                if let Ok(entry) = entry {
-                    let path = entry.path();
-                    if !self.check_operation_allowed(&path, operation) {
-                        return false;
-                    }
-                }
-            }
-        }
-        true
-    }
     pub fn is_searchable(&self, path: &Path) -> bool {
-        if let Some(color) = self.get_item_color(path) {
-            if let Some(rule) = self.config.color_rules.get(&color) {
-                rule.include_in_search
-            } else {
-                true // If no rule is set for the color, include in search
-            }
-        } else {
-            true // If the item has no color, include in search
-        }
+        self.config.is_searchable(path)
+    }
+
+    // Searches from the current directory with the filters set in the browser.
+    pub fn search_entries(&self, search_term: &str) -> io::Result<Vec<FileEntry>> {
+        fuzzy_search_entries(
+            &self.config,
+            &self.search_filters,
+            &self.current_dir,
+            search_term,
+            self.search_depth_limit,
+        )
     }
     pub fn set_color_rule(&mut self, color: MarkerColor, rule: ColorRule) {
         self.config.color_rules.insert(color, rule);
@@ -571,23 +653,23 @@ impl AppState {
                     .map(|entry| entry.path.clone()),
             );
         }
-        queue!(stdout(), MoveTo(preview_width + 3, height - 12))?;
+        queue!(screen(), MoveTo(preview_width + 3, height - 12))?;
         write!(
-            stdout(),
+            screen(),
             "{}",
             "-".green().to_string().repeat((preview_width - 4).into())
         )?;
-        queue!(stdout(), MoveTo(preview_width + 35, height - 10))?;
-        writeln!(stdout(), "                                    ")?;
-        queue!(stdout(), MoveTo(preview_width + 34, height - 10))?;
+        queue!(screen(), MoveTo(preview_width + 35, height - 10))?;
+        writeln!(screen(), "                                    ")?;
+        queue!(screen(), MoveTo(preview_width + 34, height - 10))?;
         writeln!(
-            stdout(),
+            screen(),
             "Selected Files: {}\r",
             selected.len().to_string().red()
         )?;
-        queue!(stdout(), MoveTo(preview_width + 3, height - 8))?;
+        queue!(screen(), MoveTo(preview_width + 3, height - 8))?;
         write!(
-            stdout(),
+            screen(),
             "{}",
             "-".green().to_string().repeat((preview_width - 4).into())
         )?;
@@ -606,7 +688,7 @@ impl AppState {
     }
 
     pub fn save_config(&self) -> io::Result<()> {
-        self.config.save_config()
+        self.config.save_to(&self.config_path)
     }
 
     pub fn set_home_folder(&mut self, path: Option<PathBuf>) -> io::Result<()> {
@@ -614,7 +696,7 @@ impl AppState {
         if let Some(path) = path {
             self.current_dir = path.clone();
         }
-        self.config.save_config()
+        self.save_config()
     }
 
     pub fn start_move(&mut self, current_entry: Option<&FileEntry>) -> Result<(), io::Error> {
@@ -656,20 +738,11 @@ impl AppState {
         };
 
         for source_path in files_to_move {
-            let file_name = source_path.file_name().unwrap_or_default();
-            let dest_path = dest_dir.join(file_name);
-
-            fs::rename(&source_path, &dest_path)?;
-            self.undo_manager.add_tome_entry(UndoEntry {
-                operation: Operation::Move {
-                    old_path: source_path.clone(),
-                    new_path: dest_path.clone(),
-                    timestamp: SystemTime::now(),
-                },
-                storage: UndoStorage::Ram(Vec::new()),
-                original_path: source_path,
-                size: 0,
-            })?;
+            if let Err(e) = self.file_operations().move_into(&source_path, dest_dir) {
+                self.is_moving = false;
+                self.clear_selection();
+                return Err(e);
+            }
         }
 
         self.is_moving = false;
@@ -688,53 +761,6 @@ impl AppState {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
-pub enum SortOrder {
-    DateModifiedAsc,
-    DateModifiedDesc,
-    NameAsc,
-    NameDesc,
-    SizeAsc,
-    SizeDesc,
-    TypeAsc,
-    TypeDesc,
-    ColorAsc,
-    ColorDesc,
-}
-
-impl SortOrder {
-    pub fn from_str(s: &str) -> Self {
-        match s {
-            "NameAsc" => SortOrder::NameAsc,
-            "NameDesc" => SortOrder::NameDesc,
-            "SizeAsc" => SortOrder::SizeAsc,
-            "SizeDesc" => SortOrder::SizeDesc,
-            "TypeAsc" => SortOrder::TypeAsc,
-            "TypeDesc" => SortOrder::TypeDesc,
-            "ColorAsc" => SortOrder::ColorAsc,
-            "ColorDesc" => SortOrder::ColorDesc,
-            "DateModifiedAsc" => SortOrder::DateModifiedAsc,
-            "DateModifiedDesc" => SortOrder::DateModifiedDesc,
-            _ => SortOrder::NameAsc,
-        }
-    }
-
-    pub fn to_string(&self) -> &'static str {
-        match self {
-            SortOrder::NameAsc => "NameAsc",
-            SortOrder::NameDesc => "NameDesc",
-            SortOrder::SizeAsc => "SizeAsc",
-            SortOrder::SizeDesc => "SizeDesc",
-            SortOrder::TypeAsc => "TypeAsc",
-            SortOrder::TypeDesc => "TypeDesc",
-            SortOrder::ColorAsc => "ColorAsc",
-            SortOrder::ColorDesc => "ColorDesc",
-            SortOrder::DateModifiedAsc => "DateModifiedAsc",
-            SortOrder::DateModifiedDesc => "DateModifiedDesc",
-        }
-    }
-}
-
 pub struct PageState {
     pub left_page: u32,
     pub right_page: u32,
@@ -779,3 +805,16 @@ impl PageState {
         }
     }
 }
+
+fn config_modified(config_path: &Path) -> Option<SystemTime> {
+    fs::metadata(config_path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+// Sets the colors and theme the config asks for. A broken theme file should
+// not keep the browser from starting.
+pub fn apply_look(config: &Config) {
+    set_color_depth(ColorDepth::detect(&config.color_depth));
+    set_theme(Theme::load(&config.theme).unwrap_or_default());
+}
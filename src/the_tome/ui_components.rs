@@ -24,22 +24,6 @@ use crossterm::style::SetColors;
 use super::*;
 use std::{fmt, io::Cursor, sync::Mutex};
 
-#[derive(Debug, Clone, PartialEq, Eq)]
-pub enum FileType {
-    Directory,
-    Text,
-    Log,
-    Document,
-    Image,
-    Binary,
-    Config,
-    Executable,
-    Unknown,
-    Rust,
-    Nix = 10,
-    Zig = 11,
-}
-
 pub struct DimmingConfig {
     max_distance: i32,
     dim_step: u8,
@@ -75,8 +59,12 @@ impl DimmingConfig {
         }
     }
 
-#[rustfmt::skip]
     pub fn dim_color(color: Color, dim_factor: u8) -> Color {
+        fit_color(Self::dim_rgb(color, dim_factor))
+    }
+
+#[rustfmt::skip]
+    fn dim_rgb(color: Color, dim_factor: u8) -> Color {
         match color {
             Color::Rgb { r, g, b } => {
                 let brightness_factor = (100 - dim_factor) as f32 / 100.0;
@@ -111,9 +99,13 @@ impl DimmingConfig {
                     }
                 }
             }
+            Color::AnsiValue(value) => {
+                let (r, g, b) = ansi_to_rgb(value);
+                Self::dim_rgb(Color::Rgb { r, g, b }, dim_factor)
+            }
             Color::Black => Color::Rgb { r: 0, g: 0, b: 0 },
-            Color::Red => Self::dim_color(Color::Rgb { r: 255, g: 0, b: 0 }, dim_factor),
-            Color::Yellow => Self::dim_color(
+            Color::Red => Self::dim_rgb(Color::Rgb { r: 255, g: 0, b: 0 }, dim_factor),
+            Color::Yellow => Self::dim_rgb(
                 Color::Rgb {
                     r: 255,
                     g: 255,
@@ -121,8 +113,8 @@ impl DimmingConfig {
                 },
                 dim_factor,
             ),
-            Color::Green => Self::dim_color(Color::Rgb { r: 0, g: 255, b: 0 }, dim_factor),
-            Color::Blue => Self::dim_color(
+            Color::Green => Self::dim_rgb(Color::Rgb { r: 0, g: 255, b: 0 }, dim_factor),
+            Color::Blue => Self::dim_rgb(
                 Color::Rgb {
                     r: 0,
                     g: 100,
@@ -130,7 +122,7 @@ impl DimmingConfig {
                 },
                 dim_factor,
             ),
-            Color::Magenta => Self::dim_color(
+            Color::Magenta => Self::dim_rgb(
                 Color::Rgb {
                     r: 255,
                     g: 0,
@@ -138,7 +130,7 @@ impl DimmingConfig {
                 },
                 dim_factor,
             ),
-            Color::Cyan => Self::dim_color(
+            Color::Cyan => Self::dim_rgb(
                 Color::Rgb {
                     r: 0,
                     g: 255,
@@ -146,7 +138,7 @@ impl DimmingConfig {
                 },
                 dim_factor,
             ),
-            Color::White => Self::dim_color(
+            Color::White => Self::dim_rgb(
                 Color::Rgb {
                     r: 255,
                     g: 255,
@@ -154,7 +146,7 @@ impl DimmingConfig {
                 },
                 dim_factor,
             ),
-            Color::Reset => Self::dim_color(
+            Color::Reset => Self::dim_rgb(
                 Color::Rgb {
                     r: 200,
                     g: 200,
@@ -169,7 +161,7 @@ impl DimmingConfig {
 // Did not have the effect i wanted.
 // needs heavy calibration to make sense.
 fn add_green_tint(color: Color) -> Color {
-    match color {
+    fit_color(match color {
         Color::Rgb { r, g, b } => {
             let new_g = (g as u16 + 25).min(255) as u8;
             Color::Rgb { r, g: new_g, b }
@@ -197,7 +189,7 @@ fn add_green_tint(color: Color) -> Color {
                 b: rgb.2,
             }
         }
-    }
+    })
 }
 
 #[rustfmt::skip]
@@ -232,7 +224,7 @@ let mut current_page = 1;
             .and_then(|kb| {
                 kb.iter().find_map(|(k, v)| {
                     if v == action {
-                        Some(key_event_to_string(k))
+                        Some(key_event_to_string(k.clone()))
                     } else {
                         None
                     }
@@ -250,6 +242,7 @@ let mut current_page = 1;
                 (format!("{} / {}", get_key_for_action(&Action::MoveRight).trim_matches('"'), "l"), "Enter selected directory"),
                 (get_key_for_action(&Action::GoToTop).trim_matches('"').to_string(), "Go to top of list"),
                 (get_key_for_action(&Action::GoToBottom).trim_matches('"').to_string(), "Go to bottom of list"),
+                (get_key_for_action(&Action::GoHome).trim_matches('"').to_string(), "Go to the home folder"),
                 (get_key_for_action(&Action::SearchFiles).trim_matches('"').to_string(), "Search the file system"),
                 ("0-9".to_string(), "Navigate to shortcut directory"),
             ],
@@ -266,8 +259,13 @@ let mut current_page = 1;
                 (get_key_for_action(&Action::Duplicate).trim_matches('"').to_string(), "Duplicate selected file/folder"),
                 (get_key_for_action(&Action::Murder).trim_matches('"').to_string(), "Delete selected file(s)/folder(s)"),
                 (get_key_for_action(&Action::Copy).trim_matches('"').to_string(), "Copy to clipboard"),
+                (get_key_for_action(&Action::YankPath).trim_matches('"').to_string(), "Copy the path to the terminal clipboard"),
                 (get_key_for_action(&Action::Paste).trim_matches('"').to_string(), "Paste from clipboard"),
                 (get_key_for_action(&Action::OpenInEditor).trim_matches('"').to_string(), "Open in text editor"),
+                (get_key_for_action(&Action::ExtractMembers).trim_matches('"').to_string(), "Extract from an archive"),
+                (get_key_for_action(&Action::PackSelection).trim_matches('"').to_string(), "Pack selection into an archive"),
+                (get_key_for_action(&Action::ExtractArchive).trim_matches('"').to_string(), "Extract archive into a folder"),
+                (get_key_for_action(&Action::ChangePermissions).trim_matches('"').to_string(), "Change permissions"),
             ],
         ),
         (
@@ -280,6 +278,8 @@ let mut current_page = 1;
                 (get_key_for_action(&Action::CycleItemColor).trim_matches('"').to_string(), "Cycle item color"),
                 (get_key_for_action(&Action::RemoveItemColor).trim_matches('"').to_string(), "Remove item color"),
                 (get_key_for_action(&Action::SetColorRules).trim_matches('"').to_string(), "Set color rules"),
+                (format!("{} / {}", get_key_for_action(&Action::PreviewFoldMore).trim_matches('"'), get_key_for_action(&Action::PreviewFoldLess).trim_matches('"')), "Fold / unfold data preview"),
+                (format!("{} / {}", get_key_for_action(&Action::PreviewScrollLeft).trim_matches('"'), get_key_for_action(&Action::PreviewScrollRight).trim_matches('"')), "Scroll data preview columns"),
             ],
         ),
         (
@@ -303,6 +303,9 @@ let mut current_page = 1;
             vec![
                 (get_key_for_action(&Action::TerminalCommand).trim_matches('"').to_string(), "Open terminal"),
                 (get_key_for_action(&Action::CastCommandLineSpell).trim_matches('"').to_string(), "Open terminal within in StygianSift"),
+                (get_key_for_action(&Action::CommandPalette).trim_matches('"').to_string(), "Find and run any command"),
+                (get_key_for_action(&Action::RecordMacro).trim_matches('"').to_string(), "Record a macro"),
+                (get_key_for_action(&Action::PlayMacro).trim_matches('"').to_string(), "Play a macro"),
                 (get_key_for_action(&Action::GitMenu).trim_matches('"').to_string(), "Open Git menu"),
                 (get_key_for_action(&Action::Undo).trim_matches('"').to_string(), "Undo last operation"),
             ],
@@ -435,7 +438,7 @@ let mut current_page = 1;
 
         stdout.flush()?;
 
-        if let Event::Key(key) = event::read()? {
+        if let Event::Key(key) = read_event()? {
             match key.code {
                 KeyCode::Esc => break,
                 KeyCode::Right | KeyCode::Char('l') if current_page < total_pages => {
@@ -456,6 +459,7 @@ pub fn draw_simple_border(stdout: &mut impl Write, page_state: &PageState) -> io
     let (width, height) = size()?;
     let nav_width = width / 2;
     let preview_width = width - nav_width - 1;
+    queue!(stdout, SetForegroundColor(current_theme().palette.border))?;
     // Draw top border
     queue!(stdout, MoveTo(4, 2))?;
     write!(
@@ -510,6 +514,7 @@ pub fn draw_simple_border(stdout: &mut impl Write, page_state: &PageState) -> io
     queue!(stdout, MoveTo(nav_width, height - 2))?;
     write!(stdout, "┴")?;
 
+    queue!(stdout, SetForegroundColor(Color::Reset))?;
     stdout.flush()
 }
 
@@ -519,6 +524,8 @@ pub fn draw_initial_border(stdout: &mut impl Write, page_state: &PageState) -> i
     let (width, height) = size()?;
     let nav_width = width / 2;
     let preview_width = width - nav_width - 1;
+    let palette = current_theme().palette.clone();
+    queue!(stdout, SetForegroundColor(palette.border))?;
 
     // Draw top border
     queue!(stdout, MoveTo(4, 2))?;
@@ -526,11 +533,12 @@ pub fn draw_initial_border(stdout: &mut impl Write, page_state: &PageState) -> i
         stdout,
         "{}{}{}",
         "═".repeat(nav_width as usize - 7),
-        "⨅⨅⨅⨅".red(),
+        "⨅⨅⨅⨅".with(palette.accent),
         "═".repeat(preview_width as usize - 5),
     )?;
 
     stdout.flush()?;
+    queue!(stdout, SetForegroundColor(palette.border))?;
     queue!(stdout, MoveTo(4, 3))?;
     write!(stdout, "∥")?;
     queue!(stdout, MoveTo(3, 3))?;
@@ -538,9 +546,10 @@ pub fn draw_initial_border(stdout: &mut impl Write, page_state: &PageState) -> i
 
     // Draw side borders
     for y in 3..height - 2 {
+        queue!(stdout, SetForegroundColor(palette.border))?;
         // Left side
         queue!(stdout, MoveTo(0, y + 3))?;
-        write!(stdout, "{}", "▒".dark_red())?;
+        write!(stdout, "{}", "▒".with(palette.accent_dark))?;
         queue!(stdout, MoveTo(1, y + 3))?;
         write!(stdout, "∥")?;
         queue!(stdout, MoveTo(2, y + 2))?;
@@ -551,14 +560,14 @@ pub fn draw_initial_border(stdout: &mut impl Write, page_state: &PageState) -> i
         write!(stdout, "│")?;
 
         // Middle spine
-        queue!(stdout, SetForegroundColor(Color::DarkYellow))?;
+        queue!(stdout, SetForegroundColor(palette.spine))?;
         queue!(stdout, MoveTo(nav_width - 3, y))?;
         write!(stdout, "│")?;
         queue!(stdout, MoveTo(nav_width - 2, y))?;
         write!(stdout, "⎞⎛")?;
         queue!(stdout, MoveTo(nav_width, y))?;
         write!(stdout, "│")?;
-        queue!(stdout, SetForegroundColor(Color::Reset))?;
+        queue!(stdout, SetForegroundColor(palette.border))?;
 
         // Right side
         queue!(stdout, MoveTo(width - 5, y))?;
@@ -570,7 +579,7 @@ pub fn draw_initial_border(stdout: &mut impl Write, page_state: &PageState) -> i
         queue!(stdout, MoveTo(width - 2, y + 3))?;
         write!(stdout, "∥")?;
         queue!(stdout, MoveTo(width - 1, y + 3))?;
-        write!(stdout, "{}", "▒".dark_red())?;
+        write!(stdout, "{}", "▒".with(palette.accent_dark))?;
     }
 
     // Draw bottom border
@@ -579,13 +588,13 @@ pub fn draw_initial_border(stdout: &mut impl Write, page_state: &PageState) -> i
     write!(
         stdout,
         "{}",
-        "⨌".dark_yellow().to_string().repeat(nav_width as usize - 7),
+        "⨌".with(palette.spine).to_string().repeat(nav_width as usize - 7),
     )?;
     queue!(stdout, MoveTo(2, height))?;
     write!(
         stdout,
         "{}",
-        "⩲".dark_red().to_string().repeat(nav_width as usize - 4)
+        "⩲".with(palette.accent_dark).to_string().repeat(nav_width as usize - 4)
     )?;
 
     // Draw corners
@@ -613,72 +622,74 @@ pub fn draw_initial_border(stdout: &mut impl Write, page_state: &PageState) -> i
     write!(
         stdout,
         "{}",
-        "⩲".dark_red().to_string().repeat(nav_width as usize - 1),
+        "⩲".with(palette.accent_dark).to_string().repeat(nav_width as usize - 1),
     )?;
     queue!(stdout, MoveTo(preview_width + 1 , height - 2))?;
     write!(
         stdout,
         "{}",
-        "⨌".dark_yellow().to_string().repeat(nav_width as usize - 5),
+        "⨌".with(palette.spine).to_string().repeat(nav_width as usize - 5),
     )?;
-    queue!(stdout, SetForegroundColor(Color::DarkYellow))?;
+    queue!(stdout, SetForegroundColor(palette.spine))?;
     queue!(stdout, MoveTo(nav_width - 3, height - 3))?;
     write!(
         stdout,
         "{}{}{}{}",
-        "\\".red(),
-        "\\".dark_yellow(),
-        "/".dark_yellow(),
-        "/".red()
+        "\\".with(palette.accent),
+        "\\".with(palette.spine),
+        "/".with(palette.spine),
+        "/".with(palette.accent)
     )?;
 
     queue!(stdout, MoveTo(preview_width - 2, height - 2))?;
-    write!(stdout, "{}", "\\/".red())?;
+    write!(stdout, "{}", "\\/".with(palette.accent))?;
 
     queue!(stdout, MoveTo(nav_width - 3, height))?;
-    write!(stdout, "{}", "⨅⨅⨅⨅".red())?;
+    write!(stdout, "{}", "⨅⨅⨅⨅".with(palette.accent))?;
     queue!(stdout, MoveTo(nav_width - 4, height - 1))?;
-    write!(stdout, "{}", "//".red())?;
+    write!(stdout, "{}", "//".with(palette.accent))?;
     queue!(stdout, MoveTo(nav_width, height - 1))?;
-    write!(stdout, "{}", "\\\\".red())?;
+    write!(stdout, "{}", "\\\\".with(palette.accent))?;
     queue!(stdout, MoveTo(nav_width - 3, height - 2))?;
-    write!(stdout, "{}", "/".red())?;
+    write!(stdout, "{}", "/".with(palette.accent))?;
     queue!(stdout, MoveTo(nav_width, height - 2))?;
-    write!(stdout, "{}", "\\".red())?;
-    queue!(stdout, SetForegroundColor(Color::Reset))?;
+    write!(stdout, "{}", "\\".with(palette.accent))?;
+    queue!(stdout, SetForegroundColor(palette.border))?;
     stdout.flush()?;
 
-    queue!(stdout, SetBackgroundColor(Color::Reset))?;
+    queue!(stdout, SetForegroundColor(Color::Reset), SetBackgroundColor(Color::Reset))?;
     stdout.flush()
 }
 pub fn update_page_num(page_state: &PageState) -> io::Result<()> {
     let (width, height) = size()?;
-
-    execute!(stdout(), SetForegroundColor(Color::Green))?;
-    queue!(stdout(), MoveTo(width / 18, height - 4))?;
-    write!(stdout(), "Page")?;
-    queue!(stdout(), MoveTo(width / 18 + 5, height - 4))?;
-    write!(stdout(), "{}", page_state.left_page)?;
-    queue!(stdout(), MoveTo(width - 16, height - 4))?;
-    write!(stdout(), "Page")?;
-    queue!(stdout(), MoveTo(width - 11, height - 4))?;
-    write!(stdout(), "{}", page_state.right_page)?;
-    execute!(stdout(), SetForegroundColor(Color::Reset))?;
-    stdout().flush()?;
+    let mut stdout = screen();
+
+    queue!(stdout, SetForegroundColor(Color::Green))?;
+    queue!(stdout, MoveTo(width / 18, height - 4))?;
+    write!(stdout, "Page")?;
+    queue!(stdout, MoveTo(width / 18 + 5, height - 4))?;
+    write!(stdout, "{}", page_state.left_page)?;
+    queue!(stdout, MoveTo(width - 16, height - 4))?;
+    write!(stdout, "Page")?;
+    queue!(stdout, MoveTo(width - 11, height - 4))?;
+    write!(stdout, "{}", page_state.right_page)?;
+    queue!(stdout, SetForegroundColor(Color::Reset))?;
+    stdout.flush()?;
     Ok(())
 }
 
+// Only blanks the pane in the grid, whatever is drawn next is sent along
+// with it, so the pane never shows up empty in between.
 pub fn clear_nav() -> io::Result<()> {
     let (width, height) = size()?;
     let end_y = height - 3;
     let start_y = 3;
     let nav_width = width / 2;
+    let mut stdout = screen();
     for a in start_y..end_y {
-        execute!(stdout(), MoveTo(5, a))?;
-        write!(stdout(), "{}", " ".repeat(nav_width as usize - 8),)?;
+        queue!(stdout, MoveTo(5, a))?;
+        write!(stdout, "{}", " ".repeat(nav_width as usize - 8),)?;
     }
-
-    stdout().flush()?;
     Ok(())
 }
 
@@ -688,30 +699,50 @@ pub fn clear_preview() -> io::Result<()> {
     let start_y = 5;
     let nav_width = width / 2;
     let preview_width = width - nav_width;
+    let mut stdout = screen();
     for a in start_y..end_y {
-        execute!(stdout(), MoveTo(preview_width, a - 2))?;
-        write!(stdout(), "{}", " ".repeat(preview_width as usize - 9),)?;
+        queue!(stdout, MoveTo(preview_width, a - 2))?;
+        write!(stdout, "{}", " ".repeat(preview_width as usize - 9),)?;
     }
-
-    stdout().flush()?;
     Ok(())
 }
 
-pub fn file_type_order(file_type: &FileType) -> u8 {
-    match file_type {
-        FileType::Directory => 0,
-        FileType::Text => 1,
-        FileType::Log => 2,
-        FileType::Document => 3,
-        FileType::Image => 4,
-        FileType::Binary => 5,
-        FileType::Config => 6,
-        FileType::Executable => 7,
-        FileType::Unknown => 8,
-        FileType::Rust => 9,
-        FileType::Nix => 10,
-        FileType::Zig => 11,
+// Offset, hex bytes and the printable ones, as many per row as fit.
+fn display_hex_preview(
+    stdout: &mut impl Write,
+    buffer: &[u8],
+    nav_width: u16,
+    preview_width: u16,
+    start_y: u16,
+    end_y: u16,
+    dimming_config: &DimmingConfig,
+) -> io::Result<()> {
+    let per_row = ((preview_width as usize).saturating_sub(26) / 4 / 4 * 4).clamp(4, 16);
+    let color = current_theme().palette.preview;
+    for (y, (row, chunk)) in (start_y..end_y).zip(buffer.chunks(per_row).enumerate()) {
+        let hex: Vec<String> = chunk.iter().map(|b| format!("{:02x}", b)).collect();
+        let printable: String = chunk
+            .iter()
+            .map(|&b| if b.is_ascii_graphic() || b == b' ' { b as char } else { '.' })
+            .collect();
+        let line = format!(
+            "{:08x}  {:<width$}  |{}|",
+            row * per_row,
+            hex.join(" "),
+            printable,
+            width = per_row * 3 - 1
+        );
+
+        let distance = ((y - 3).saturating_sub(start_y)) as i32;
+        let dim_factor = dimming_config.calculate_dimming(distance);
+        queue!(
+            stdout,
+            MoveTo(nav_width + 2, y - 3),
+            SetForegroundColor(DimmingConfig::dim_color(color, dim_factor))
+        )?;
+        write!(stdout, " {}", truncate_str(&line, preview_width as usize - 14))?;
     }
+    Ok(())
 }
 
 pub fn format_size(size: u64) -> String {
@@ -755,22 +786,23 @@ pub fn write_header(
     let _ = show_count;
     let (width, height) = size()?;
     let nav_width = width / 2;
+    let palette = current_theme().palette.clone();
     queue!(stdout, MoveTo(0, 1))?;
     writeln!(
         stdout,
         "{} {} for Help | {} {} for shortcuts | {} {} for config | {} {} for color ruleset",
-        "Press".green(),
-        "F12".red(),
-        "Press".green(),
-        "F11".red(),
-        "Press".green(),
-        "~".red(),
-        "Press".green(),
-        "Shift + F2".red()
+        "Press".with(palette.header),
+        "F12".with(palette.accent),
+        "Press".with(palette.header),
+        "F11".with(palette.accent),
+        "Press".with(palette.header),
+        "~".with(palette.accent),
+        "Press".with(palette.header),
+        "Shift + F2".with(palette.accent)
     )?;
     let truncated_dir = truncate_path(current_dir, (nav_width / 2) as usize);
     queue!(stdout, MoveTo(nav_width / 12, height / 10))?;
-    writeln!(stdout, " 🖥  Current directory: {}", truncated_dir.green())?;
+    writeln!(stdout, " 🖥  Current directory: {}", truncated_dir.with(palette.header))?;
 
     queue!(stdout, MoveTo(nav_width / 12 + 1, height / 10 + 1))?;
     writeln!(
@@ -820,6 +852,8 @@ pub fn display_file_info_or_preview(
             let progress = elapsed.as_secs_f32() / animation_duration.as_secs_f32();
             render_preview_frame(app_state, stdout, entry, nav_width, preview_width, start_y, end_y, progress)?;
         }
+    } else if app_state.archive_view.as_ref().is_some_and(|view| view.contains(&entry.path)) {
+        display_archive_member_info(app_state, stdout, entry, nav_width, preview_width, start_y, end_y)?;
     } else {
         let metadata = match fs::metadata(&entry.path) {
             Ok(meta) => meta,
@@ -832,21 +866,10 @@ pub fn display_file_info_or_preview(
             }
         };
 
-        let format_time = |duration: Option<Duration>| -> String {
-            duration.map_or_else(
-                || "Unknown".to_string(),
-                |d| {
-                    let secs = d.as_secs();
-                    format!(
-                        "{}-{:02}-{:02} {:02}:{:02}:{:02}",
-                        1970 + secs / 31536000,
-                        (secs % 31536000) / 2592000 + 1,
-                        (secs % 2592000) / 86400 + 1,
-                        (secs % 86400) / 3600,
-                        (secs % 3600) / 60,
-                        secs % 60
-                    )
-                },
+        let format_time = |time: io::Result<SystemTime>| -> String {
+            time.map_or_else(
+                |_| "Unknown".to_string(),
+                |t| describe_time(unix_seconds(t), &app_state.config),
             )
         };
 
@@ -861,7 +884,7 @@ pub fn display_file_info_or_preview(
         #[cfg(unix)]
         let owner_info = {
             use std::os::unix::fs::MetadataExt;
-            format!("{}:{}", metadata.uid(), metadata.gid())
+            describe_owner(metadata.uid(), metadata.gid())
         };
         #[cfg(windows)]
         let owner_info = "N/A".to_string();
@@ -869,7 +892,7 @@ pub fn display_file_info_or_preview(
         #[cfg(unix)]
         let permissions = {
             use std::os::unix::fs::MetadataExt;
-            format!("{:o}", metadata.mode())
+            describe_mode(metadata.mode())
         };
         #[cfg(windows)]
         let permissions = {
@@ -896,9 +919,9 @@ pub fn display_file_info_or_preview(
             ("CHAPTER", entry.name.to_ascii_uppercase()),
             ("Type", format!("{:?}", entry.file_type).green().to_string()),
             ("Size", format_size(entry.size).green().to_string()),
-            ("Created", format_time(metadata.created().ok().and_then(|t| t.duration_since(UNIX_EPOCH).ok())).green().to_string()),
-            ("Modified", format_time(metadata.modified().ok().and_then(|t| t.duration_since(UNIX_EPOCH).ok())).green().to_string()),
-            ("Accessed", format_time(metadata.accessed().ok().and_then(|t| t.duration_since(UNIX_EPOCH).ok())).green().to_string()),
+            ("Created", format_time(metadata.created()).green().to_string()),
+            ("Modified", format_time(metadata.modified()).green().to_string()),
+            ("Accessed", format_time(metadata.accessed()).green().to_string()),
             ("Permissions", permissions.green().to_string()),
             ("Owner", owner_info.green().to_string()),
             #[cfg(unix)]
@@ -909,6 +932,12 @@ pub fn display_file_info_or_preview(
             ("Admin required", if admin_required { "Yes ".red() } else { "No ".green() }.to_string()),
         ];
 
+        info.extend(attribute_rows(&entry.path).into_iter().map(|(label, value)| (label, value.green().to_string())));
+
+        if metadata.is_file() {
+            info.extend(binary_info_rows(&entry.path).into_iter().map(|(label, value)| (label, value.green().to_string())));
+        }
+
         if !color_info.is_empty() {
             info.extend(color_info);
         }
@@ -976,7 +1005,21 @@ fn render_preview_frame(
 
     clear_preview()?;
 
-    if entry.path.is_dir() {
+    let archive_view = app_state
+        .archive_view
+        .as_ref()
+        .filter(|view| view.contains(&entry.path));
+    if let Some(view) = archive_view.filter(|_| entry.file_type == FileType::Directory) {
+        display_archive_folder_preview(
+            view,
+            &entry.path,
+            stdout,
+            nav_width,
+            preview_width,
+            start_y,
+            current_end_y,
+        )?;
+    } else if entry.path.is_dir() {
         display_folder_preview(
             &entry.path,
             stdout,
@@ -985,12 +1028,31 @@ fn render_preview_frame(
             start_y,
             current_end_y,
         )?;
-    } else {
-        match File::open(&entry.path) {
-            Ok(mut file) => {
-                let mut buffer = vec![0; PREVIEW_LIMIT];
-                let bytes_read = file.read(&mut buffer)?;
-                buffer.truncate(bytes_read);
+    } else if !render_structured_preview(
+        app_state,
+        stdout,
+        entry,
+        nav_width,
+        preview_width,
+        start_y,
+        current_end_y,
+    )
+    .unwrap_or(false)
+    {
+        match read_entry_bytes(app_state, &entry.path, PREVIEW_LIMIT) {
+            Ok(buffer) if entry.file_type.has_binary_content() || !looks_like_text(&buffer) => {
+                display_hex_preview(
+                    stdout,
+                    &buffer,
+                    nav_width,
+                    preview_width,
+                    start_y,
+                    current_end_y,
+                    &dimming_config,
+                )?;
+            }
+            Ok(buffer) => {
+                let bytes_read = buffer.len();
 
                 let mut y = start_y;
                 let mut byte_index = 0;
@@ -1017,7 +1079,7 @@ fn render_preview_frame(
 
                     let distance = ((y - 3).saturating_sub(start_y)) as i32;
                     let dim_factor = dimming_config.calculate_dimming(distance);
-                    let dimmed_color = DimmingConfig::dim_color(Color::Yellow, dim_factor);
+                    let dimmed_color = DimmingConfig::dim_color(current_theme().palette.preview, dim_factor);
 
                     queue!(
                         stdout,
@@ -1065,49 +1127,39 @@ pub fn write_entry(
     let is_hovered = app_state.mouse_state.hovered_index
         .map_or(false, |idx| idx == app_state.scroll_state.offset + distance_from_selected as usize);
 
-    let (icon, name) = match entry.file_type {
-        FileType::Directory => ("📁", format!("{}/", entry.name)),
-        _ => ("📄", entry.name.clone()),
-    };
+    let theme = current_theme();
+    let is_dir = entry.file_type == FileType::Directory;
+    let icon = &theme.entry_glyph(is_dir).symbol;
+    let name = if is_dir { format!("{}/", entry.name) } else { entry.name.clone() };
     let size_str = if let FileType::Directory = entry.file_type {
         String::new()
     } else {
         format_size(entry.size)
     };
 
-    let metadata = fs::metadata(&entry.path)?;
-    let admin_required = check_admin_required_cross_platform(&entry.path).expect("Checking admin failed");
-    let readonly = check_readonly(&metadata);
-
-    let (type_icon, permission_icon) = match entry.file_type {
-        FileType::Directory => (" ", if admin_required { "🔐" } else if readonly { "🔏" } else { " " }),
-        FileType::Text => ("📄", if admin_required { "🔐" } else if readonly { "🔏" } else { " " }),
-        FileType::Log => ("📜", if admin_required { "🔐" } else if readonly { "🔏" } else { " " }),
-        FileType::Document => ("📘", if admin_required { "🔐" } else if readonly { "🔏" } else { " " }),
-        FileType::Image => ("🌃", if admin_required { "🔐" } else if readonly { "🔏" } else { " " }),
-        FileType::Binary => ("💽", if admin_required { "🔐" } else if readonly { "🔏" } else { " " }),
-        FileType::Config => ("📑", if admin_required { "🔐" } else if readonly { "🔏" } else { " " }),
-        FileType::Executable => ("🎮", if admin_required { "🔐" } else if readonly { "🔏" } else { " " }),
-        FileType::Unknown => ("  ", if admin_required { "🔐" } else if readonly { "🔏" } else { " " }),
-        FileType::Rust => ("🦀", if admin_required { "🔐" } else if readonly { "🔏" } else { " " }),
-        FileType::Nix => ("❄", if admin_required { "🔐" } else if readonly { "🔏" } else { " " }),
-        FileType::Zig => ("⚡", if admin_required { "🔐" } else if readonly { "🔏" } else { " " }),
+    // Entries inside an archive have no metadata of their own.
+    let (admin_required, readonly) = match fs::metadata(&entry.path) {
+        Ok(metadata) => (check_admin_required_cross_platform(&entry.path).expect("Checking admin failed"), check_readonly(&metadata)),
+        Err(_) => (entry.admin_required, entry.read_only),
     };
 
+    let type_glyph = theme.glyph_for(&entry.name, entry.file_type);
+    let type_icon = type_glyph.map_or(" ", |glyph| glyph.symbol.as_str());
+    let permission_icon = theme.lock_glyph(admin_required, readonly);
+
     let available_width = width as usize - 34;
     let truncated_name = truncate_str(&name, available_width);
     let marker_color = app_state.config.get_item_color(&entry.path);
-    let base_color = if let Some(color) = marker_color {
-        color.to_color()
-    } else {
-        Color::Reset
+    let base_color = match marker_color {
+        Some(color) => color.to_color(),
+        None => type_glyph.and_then(|glyph| glyph.color).unwrap_or(theme.palette.entry),
     };
 
     match app_state.input_mode {
         InputMode::Keyboard => {
             let dim_factor = dimming_config.calculate_dimming(distance_from_selected);
             let fg_color = if is_selected || is_multi_selected {  
-                Color::DarkGreen
+                theme.palette.selection
             } else {
                 DimmingConfig::dim_color(base_color, dim_factor)
             };
@@ -1138,7 +1190,7 @@ pub fn write_entry(
 
             let dim_factor = dimming_config.calculate_dimming(distance_from_hover);
             let fg_color = if is_multi_selected {  
-                Color::DarkGreen
+                theme.palette.selection
             } else {
                 DimmingConfig::dim_color(base_color, dim_factor)
             };
@@ -1313,7 +1365,8 @@ pub fn display_directory(
         InputMode::Keyboard => entries.get(adjusted_selected_index),
         InputMode::Mouse => {
             if let Some((_, y)) = app_state.mouse_state.last_click_pos {
-                let clicked_index = (y - start_y + 2) as usize + app_state.scroll_state.offset;
+                let clicked_index =
+                    (y + 2).saturating_sub(start_y) as usize + app_state.scroll_state.offset;
                 if clicked_index < entries.len() {
                     entries.get(clicked_index)
                 } else {
@@ -1361,6 +1414,7 @@ pub fn display_folder_preview(
     start_y: u16,
     end_y: u16,
 ) -> io::Result<()> {
+    let theme = current_theme();
     match fs::read_dir(path) {
         Ok(entries) => {
             let mut y = start_y;
@@ -1370,7 +1424,7 @@ pub fn display_folder_preview(
             {
                 let file_name = entry.file_name().to_string_lossy().into_owned();
                 let is_dir = entry.file_type()?.is_dir();
-                let file_type = if is_dir { "📁" } else { "📄" };
+                let file_type = &theme.entry_glyph(is_dir).symbol;
 
                 #[cfg(unix)]
                 let (admin_required, readonly) = {
@@ -1395,13 +1449,7 @@ pub fn display_folder_preview(
                         attrs & FILE_ATTRIBUTE_READONLY != 0,
                     )
                 };
-                let permission_icon = if admin_required {
-                    "🔐"
-                } else if readonly {
-                    "🔏"
-                } else {
-                    " "
-                };
+                let permission_icon = theme.lock_glyph(admin_required, readonly);
 
                 let display_string = format!("{} {} {}", file_type, file_name, permission_icon);
                 queue!(stdout, MoveTo(start_x + 2, y))?;
@@ -1409,7 +1457,7 @@ pub fn display_folder_preview(
                     stdout,
                     " {}",
                     truncate_str(
-                        &display_string.as_str().dark_yellow().to_string(),
+                        &display_string.as_str().with(theme.palette.folder_preview).to_string(),
                         width as usize - 2
                     )
                 )?;
@@ -1532,12 +1580,13 @@ pub fn display_shortcuts(app_state: &mut AppState, stdout: &mut impl Write) -> i
 
         stdout.flush()?;
 
-        if let Event::Key(key) = event::read()? {
+        if let Event::Key(key) = read_event()? {
             match key.code {
                 KeyCode::Esc => break,
                 KeyCode::F(n) if n >= 1 && n <= 10 => {
                     let layer_index = (n - 1) as usize;
-                    app_state.config.switch_layer(layer_index)?;
+                    app_state.config.switch_layer(layer_index);
+                    app_state.save_config()?;
                 }
                 _ => {}
             }
@@ -1622,7 +1671,7 @@ pub fn clear_interaction_field() -> io::Result<()> {
     let (width, height) = size()?;
     let nav_width = width / 2;
     let preview_width = width - nav_width;
-    let mut stdout = stdout();
+    let mut stdout = screen();
 
     queue!(stdout, MoveTo(preview_width, height - 12))?;
     write!(stdout, "{}", " ".repeat((preview_width - 6).into()))?;
@@ -1634,7 +1683,6 @@ pub fn clear_interaction_field() -> io::Result<()> {
     write!(stdout, "{}", " ".repeat((preview_width - 6).into()))?;
     queue!(stdout, MoveTo(preview_width, height - 8))?;
     write!(stdout, "{}", " ".repeat((preview_width - 6).into()))?;
-    stdout.flush()?;
     Ok(())
 }
 pub fn interaction_field(fmt: fmt::Arguments) -> io::Result<()> {
@@ -1643,7 +1691,7 @@ pub fn interaction_field(fmt: fmt::Arguments) -> io::Result<()> {
     let (width, height) = size()?;
     let nav_width = width / 2;
     let preview_width = width - nav_width - 1;
-    let mut stdout = stdout();
+    let mut stdout = screen();
 
     queue!(stdout, MoveTo(preview_width + 1, height - 12))?;
     write!(stdout, "{}", "-".repeat((preview_width - 5).into()).green())?;
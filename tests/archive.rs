@@ -0,0 +1,358 @@
+/*
+* Stygian Sift - A Terminal-based File Manager
+ * Copyright (C) 2024 Maui The Magnificent (Charon)
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published
+ * by the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+ * Contact: Maui_The_Magnificent@proton.me
+ * Project repository: https://github.com/Mauitron/StygianSift.git
+ */
+
+// Packing and browsing tar, tar.gz and zip. Most archives come from our own
+// writer, the odd ones are put together by hand below.
+#![cfg(feature = "tui")]
+
+use std::{
+    fs::{self, File},
+    io,
+    path::{Path, PathBuf},
+    thread,
+    time::Duration,
+};
+use stygian_sift::the_tome::*;
+
+mod common;
+use common::TempDir;
+
+const LONG_NAME: &str = concat!(
+    "a-file-name-long-enough-that-a-plain-tar-header-",
+    "cannot-hold-it-with-room-to-spare-for-the-dir.txt",
+);
+
+// tree/guide.md, tree/empty/, tree/deep/<LONG_NAME> and on unix tree/run.sh
+// with its execute bits and tree/link -> guide.md.
+fn tree(root: &Path) -> PathBuf {
+    let tree = root.join("tree");
+    fs::create_dir_all(tree.join("empty")).unwrap();
+    fs::create_dir_all(tree.join("deep")).unwrap();
+    fs::write(tree.join("guide.md"), "# Guide\n".repeat(200)).unwrap();
+    fs::write(tree.join("deep").join(LONG_NAME), "long").unwrap();
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::write(tree.join("run.sh"), "#!/bin/sh\n").unwrap();
+        fs::set_permissions(tree.join("run.sh"), fs::Permissions::from_mode(0o755)).unwrap();
+        std::os::unix::fs::symlink("guide.md", tree.join("link")).unwrap();
+    }
+    tree
+}
+
+fn pack(root: &Path, name: &str) -> PathBuf {
+    let output = root.join(name);
+    pack_paths(&[tree(root)], &output, File::create(&output).unwrap()).unwrap();
+    output
+}
+
+fn member<'a>(view: &'a ArchiveView, path: &str) -> &'a ArchiveMember {
+    view.members.iter().find(|m| m.path == path).unwrap()
+}
+
+#[test]
+fn every_kind_unpacks_to_what_was_packed() {
+    for name in ["tree.tar", "tree.tar.gz", "tree.zip"] {
+        let root = TempDir::new(&format!("archive-{}", name));
+        let archive = pack(&root, name);
+        let out = root.join("out");
+        fs::create_dir(&out).unwrap();
+        unpack_archive(&archive, &out).unwrap();
+
+        let read = |path: &str| fs::read_to_string(out.join("tree").join(path)).unwrap();
+        assert_eq!(read("guide.md"), "# Guide\n".repeat(200), "{}", name);
+        assert_eq!(read(&format!("deep/{}", LONG_NAME)), "long", "{}", name);
+        assert!(out.join("tree").join("empty").is_dir(), "{}", name);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(out.join("tree").join("run.sh"))
+                .unwrap()
+                .permissions()
+                .mode();
+            assert_eq!(mode & 0o777, 0o755, "{}", name);
+            // Links are listed but never made.
+            assert!(!out.join("tree").join("link").exists(), "{}", name);
+        }
+    }
+}
+
+#[test]
+fn a_long_tar_name_survives() {
+    let root = TempDir::new("archive-long");
+    let view = ArchiveView::open(&pack(&root, "tree.tar")).unwrap();
+    let long = member(&view, &format!("tree/deep/{}", LONG_NAME));
+    assert_eq!(view.read_member(long, None).unwrap(), b"long");
+    #[cfg(unix)]
+    assert_eq!(
+        member(&view, "tree/link").member_type,
+        MemberType::Symlink("guide.md".into())
+    );
+}
+
+#[test]
+fn a_pax_path_wins_over_the_header() {
+    let root = TempDir::new("archive-pax");
+    let long = format!("pax/{}", LONG_NAME);
+    let record = pax_record("path", &long);
+
+    let mut tar = tar_header("PaxHeaders/short", b'x', record.len());
+    tar.extend(padded(record.as_bytes()));
+    tar.extend(tar_header("short", b'0', 5));
+    tar.extend(padded(b"hello"));
+    tar.extend([0; 1024]);
+    let path = root.join("pax.tar");
+    fs::write(&path, tar).unwrap();
+
+    let view = ArchiveView::open(&path).unwrap();
+    let paths: Vec<&str> = view.members.iter().map(|m| m.path.as_str()).collect();
+    assert_eq!(paths, ["pax", long.as_str()]);
+    assert_eq!(
+        view.read_member(member(&view, &long), None).unwrap(),
+        b"hello"
+    );
+}
+
+#[test]
+fn zip_keeps_stored_and_deflated_members_apart() {
+    let root = TempDir::new("archive-methods");
+    let deflated = ArchiveView::open(&pack(&root, "tree.zip")).unwrap();
+    let guide = member(&deflated, "tree/guide.md");
+    assert!(guide.compressed_size < guide.size);
+
+    let path = root.join("stored.zip");
+    fs::write(
+        &path,
+        stored_zip(&[("guide.md", "# Guide\n".repeat(200).as_bytes())], false),
+    )
+    .unwrap();
+    let stored = ArchiveView::open(&path).unwrap();
+    let plain = member(&stored, "guide.md");
+    assert_eq!(plain.compressed_size, plain.size);
+    assert_eq!(
+        stored.read_member(plain, None).unwrap(),
+        deflated.read_member(guide, None).unwrap()
+    );
+    assert_eq!(stored.read_member(plain, Some(7)).unwrap(), b"# Guide");
+}
+
+#[test]
+fn zip64_sizes_and_counts_are_read() {
+    let root = TempDir::new("archive-zip64");
+    let path = root.join("big.zip");
+    fs::write(
+        &path,
+        stored_zip(&[("a.txt", b"first"), ("b.txt", b"second")], true),
+    )
+    .unwrap();
+
+    let view = ArchiveView::open(&path).unwrap();
+    assert_eq!(view.members.len(), 2);
+    let second = member(&view, "b.txt");
+    assert_eq!((second.size, second.compressed_size), (6, 6));
+    assert_eq!(view.read_member(second, None).unwrap(), b"second");
+}
+
+#[test]
+fn broken_archives_are_errors() {
+    let root = TempDir::new("archive-broken");
+    for name in ["tree.tar", "tree.tar.gz", "tree.zip"] {
+        let whole = fs::read(pack(&root, name)).unwrap();
+        let cut = root.join(format!("cut-{}", name));
+        // Tar has no index to lose, a cut one lists what is still there.
+        for len in (0..whole.len()).step_by(97) {
+            fs::write(&cut, &whole[..len]).unwrap();
+            if let Ok(view) = ArchiveView::open(&cut) {
+                assert_eq!(name, "tree.tar", "{} cut to {} bytes", name, len);
+                for member in &view.members {
+                    let _ = view.read_member(member, None);
+                }
+            }
+        }
+        fs::remove_dir_all(root.join("tree")).unwrap();
+    }
+
+    let mut zip = stored_zip(&[("a.txt", b"first")], false);
+    let at = zip.windows(5).position(|w| w == b"first").unwrap();
+    zip[at] = b'F';
+    let path = root.join("flipped.zip");
+    fs::write(&path, &zip).unwrap();
+    let view = ArchiveView::open(&path).unwrap();
+    assert!(view.read_member(&view.members[0], None).is_err());
+
+    // A central directory with room for nothing but its signature.
+    let mut short = 0x0201_4B50u32.to_le_bytes().to_vec();
+    short.extend(end_of_directory(1, 4, 0));
+    let path = root.join("short.zip");
+    fs::write(&path, short).unwrap();
+    assert!(ArchiveView::open(&path).is_err());
+
+    let mut tar = tar_header("a.txt", b'0', 0);
+    tar[148] = b'7';
+    tar.extend([0; 1024]);
+    let path = root.join("sum.tar");
+    fs::write(&path, tar).unwrap();
+    assert!(ArchiveView::open(&path).is_err());
+}
+
+/////////////////////////////////////////////////////Hand Made//////////////////////////////////////////////////////////////////////
+
+// Whether the work fails or panics, what it had written so far goes.
+#[test]
+fn a_job_that_dies_leaves_nothing_behind() {
+    let root = TempDir::new("archive-job");
+    let _terminal = VirtualTerminal::new(80, 24);
+    let mut config = Config::new();
+    config.home_folder = Some(root.to_path_buf());
+    let mut app = AppState::with_config(config).unwrap();
+
+    for (name, is_directory) in [("failed.tar", false), ("panicked", true)] {
+        let output = root.join(name);
+        if is_directory {
+            fs::create_dir(&output).unwrap();
+            fs::write(output.join("half.txt"), "half").unwrap();
+        } else {
+            fs::write(&output, "half").unwrap();
+        }
+        let job = BackgroundJob::spawn(name.to_string(), output.clone(), is_directory, move || {
+            if is_directory {
+                panic!("unpacking went wrong");
+            }
+            Err(io::Error::other("packing went wrong"))
+        });
+        start_background_job(&mut app, job).unwrap();
+        while !poll_background_jobs(&mut app).unwrap() {
+            thread::sleep(Duration::from_millis(10));
+        }
+        assert!(!output.exists(), "{} was left behind", name);
+    }
+}
+
+fn padded(data: &[u8]) -> Vec<u8> {
+    let mut block = data.to_vec();
+    block.resize(data.len().div_ceil(512) * 512, 0);
+    block
+}
+
+fn tar_header(name: &str, type_flag: u8, size: usize) -> Vec<u8> {
+    let mut header = vec![0u8; 512];
+    header[..name.len()].copy_from_slice(name.as_bytes());
+    header[100..108].copy_from_slice(b"0000644\0");
+    header[124..136].copy_from_slice(format!("{:011o}\0", size).as_bytes());
+    header[136..148].copy_from_slice(b"00000000000\0");
+    header[156] = type_flag;
+    header[257..265].copy_from_slice(b"ustar\x0000");
+    header[148..156].copy_from_slice(b"        ");
+    let sum: u32 = header.iter().map(|&b| b as u32).sum();
+    header[148..156].copy_from_slice(format!("{:06o}\0 ", sum).as_bytes());
+    header
+}
+
+// "<len> key=value\n" where the length counts its own digits.
+fn pax_record(key: &str, value: &str) -> String {
+    let body = format!(" {}={}\n", key, value);
+    let mut len = body.len() + 1;
+    while len.to_string().len() + body.len() != len {
+        len += 1;
+    }
+    format!("{}{}", len, body)
+}
+
+fn end_of_directory(count: u16, size: u32, offset: u32) -> Vec<u8> {
+    let mut record = 0x0605_4B50u32.to_le_bytes().to_vec();
+    record.extend([0; 4]);
+    record.extend(count.to_le_bytes());
+    record.extend(count.to_le_bytes());
+    record.extend(size.to_le_bytes());
+    record.extend(offset.to_le_bytes());
+    record.extend(0u16.to_le_bytes());
+    record
+}
+
+// Stored members only. With zip64 every size and count is left to the
+// zip64 extra fields and end records.
+fn stored_zip(files: &[(&str, &[u8])], zip64: bool) -> Vec<u8> {
+    let mut zip = Vec::new();
+    let mut directory = Vec::new();
+    for (name, data) in files {
+        let offset = zip.len() as u32;
+        let crc = crc32(data);
+        let size = if zip64 { u32::MAX } else { data.len() as u32 };
+
+        zip.extend(0x0403_4B50u32.to_le_bytes());
+        zip.extend([45, 0, 0, 0, 0, 0, 0, 0, 0x21, 0]);
+        zip.extend(crc.to_le_bytes());
+        zip.extend(size.to_le_bytes());
+        zip.extend(size.to_le_bytes());
+        zip.extend((name.len() as u16).to_le_bytes());
+        zip.extend(0u16.to_le_bytes());
+        zip.extend(name.as_bytes());
+        zip.extend(*data);
+
+        let mut extra = Vec::new();
+        if zip64 {
+            extra.extend(1u16.to_le_bytes());
+            extra.extend(16u16.to_le_bytes());
+            extra.extend((data.len() as u64).to_le_bytes());
+            extra.extend((data.len() as u64).to_le_bytes());
+        }
+        directory.extend(0x0201_4B50u32.to_le_bytes());
+        directory.extend((3u16 << 8 | 45).to_le_bytes());
+        directory.extend([45, 0, 0, 0, 0, 0, 0, 0, 0x21, 0]);
+        directory.extend(crc.to_le_bytes());
+        directory.extend(size.to_le_bytes());
+        directory.extend(size.to_le_bytes());
+        directory.extend((name.len() as u16).to_le_bytes());
+        directory.extend((extra.len() as u16).to_le_bytes());
+        directory.extend([0; 6]);
+        directory.extend((0o100644u32 << 16).to_le_bytes());
+        directory.extend(offset.to_le_bytes());
+        directory.extend(name.as_bytes());
+        directory.extend(extra);
+    }
+
+    let directory_offset = zip.len() as u64;
+    let count = files.len() as u64;
+    zip.extend(&directory);
+    if zip64 {
+        let record_offset = zip.len() as u64;
+        zip.extend(0x0606_4B50u32.to_le_bytes());
+        zip.extend(44u64.to_le_bytes());
+        zip.extend([45, 0, 45, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+        zip.extend(count.to_le_bytes());
+        zip.extend(count.to_le_bytes());
+        zip.extend((directory.len() as u64).to_le_bytes());
+        zip.extend(directory_offset.to_le_bytes());
+
+        zip.extend(0x0706_4B50u32.to_le_bytes());
+        zip.extend(0u32.to_le_bytes());
+        zip.extend(record_offset.to_le_bytes());
+        zip.extend(1u32.to_le_bytes());
+        zip.extend(end_of_directory(u16::MAX, u32::MAX, u32::MAX));
+    } else {
+        zip.extend(end_of_directory(
+            count as u16,
+            directory.len() as u32,
+            directory_offset as u32,
+        ));
+    }
+    zip
+}
@@ -0,0 +1,223 @@
+/*
+* Stygian Sift - A Terminal-based File Manager
+ * Copyright (C) 2024 Maui The Magnificent (Charon)
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published
+ * by the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+ * Contact: Maui_The_Magnificent@proton.me
+ * Project repository: https://github.com/Mauitron/StygianSift.git
+ */
+
+// The scripting commands, run in process against a scratch directory with its
+// own config file.
+
+use std::fs;
+use stygian_sift::the_cli::{self, *};
+
+mod common;
+use common::TempDir;
+
+struct Workspace {
+    root: TempDir,
+}
+
+impl Workspace {
+    // root/notes.txt, root/code/main.rs and root/code/lib.rs.
+    fn new(name: &str) -> Self {
+        let root = TempDir::new(&format!("cli-{}", name));
+        fs::create_dir(root.join("code")).unwrap();
+        fs::write(root.join("notes.txt"), "notes").unwrap();
+        fs::write(root.join("code/main.rs"), "fn main() {}").unwrap();
+        fs::write(root.join("code/lib.rs"), "").unwrap();
+        Workspace { root }
+    }
+
+    fn path(&self, name: &str) -> String {
+        self.root.join(name).display().to_string()
+    }
+
+    // The exit code with what went to stdout, or the error message.
+    fn sift(&self, args: &[&str]) -> (i32, String) {
+        let mut args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+        args.extend(["--config".to_string(), self.path(".maui")]);
+        let mut out = Vec::new();
+        match the_cli::run(&args, &mut out) {
+            Ok(code) => (code, String::from_utf8(out).unwrap()),
+            Err(e) => (exit_code(&e), e.to_string()),
+        }
+    }
+}
+
+#[test]
+fn ls_sorts_by_color_and_speaks_json() {
+    let ws = Workspace::new("ls");
+    assert_eq!(ws.sift(&["tag", "set", "blue", &ws.path("code/lib.rs")]).0, 0);
+    assert_eq!(ws.sift(&["tag", "set", "red", &ws.path("code/main.rs")]).0, 0);
+
+    let (code, out) = ws.sift(&["ls", "--sort", "color-desc", &ws.path("code")]);
+    assert_eq!(code, 0);
+    let colors: Vec<&str> = out.lines().map(|line| line.split_whitespace().next().unwrap()).collect();
+    assert_eq!(colors, ["blue", "red"]);
+
+    let (_, out) = ws.sift(&["ls", "--json", "--sort", "name", &ws.path("code")]);
+    assert_eq!(
+        out.trim(),
+        format!(
+            r#"[{{"name":"lib.rs","path":"{}","type":"rust","size":0,"color":"blue"}},{{"name":"main.rs","path":"{}","type":"rust","size":12,"color":"red"}}]"#,
+            ws.path("code/lib.rs"),
+            ws.path("code/main.rs")
+        )
+    );
+}
+
+#[test]
+fn ls_types_and_sorts_files_by_their_contents() {
+    let ws = Workspace::new("sniff");
+    fs::create_dir(ws.root.join("bin")).unwrap();
+    fs::write(ws.root.join("bin/deploy"), "#!/bin/sh\necho deployed\n").unwrap();
+    fs::write(ws.root.join("bin/readme"), "just some words\n").unwrap();
+    fs::write(ws.root.join("bin/main.rs"), "fn main() {}").unwrap();
+
+    let (_, out) = ws.sift(&["ls", "--json", "--sort", "type", &ws.path("bin")]);
+    let names: Vec<&str> = out
+        .split(r#""name":""#)
+        .skip(1)
+        .map(|rest| &rest[..rest.find('"').unwrap()])
+        .collect();
+    // Text, then Rust, then the shell script, which by name alone are unknown.
+    assert_eq!(names, ["readme", "main.rs", "deploy"]);
+    assert!(out.contains(r#""type":"script""#));
+}
+
+#[test]
+fn tags_are_saved_listed_and_removed() {
+    let ws = Workspace::new("tag");
+    ws.sift(&["tag", "set", "green", &ws.path("notes.txt"), &ws.path("code")]);
+    // Relative parts are folded away, like the browser stores them.
+    ws.sift(&["tag", "set", "red", &ws.path("code/../code/main.rs")]);
+
+    let (_, out) = ws.sift(&["tag", "ls", "green"]);
+    assert_eq!(out, format!("{}\n{}\n", ws.path("code"), ws.path("notes.txt")));
+    let (_, out) = ws.sift(&["tag", "ls"]);
+    assert_eq!(out.lines().next().unwrap(), format!("red      {}", ws.path("code/main.rs")));
+
+    ws.sift(&["tag", "rm", &ws.path("code")]);
+    let (_, out) = ws.sift(&["tag", "ls", "green", "--json"]);
+    assert_eq!(
+        out.trim(),
+        format!(r#"[{{"path":"{}","color":"green"}}]"#, ws.path("notes.txt"))
+    );
+
+    // Nothing but the tags went into the file.
+    let saved = fs::read_to_string(ws.root.join(".maui")).unwrap();
+    assert!(!saved.contains("home_folder"), "{}", saved);
+
+    assert_eq!(ws.sift(&["tag", "set", "mauve", &ws.path("notes.txt")]).0, 2);
+    assert_eq!(ws.sift(&["tag", "set", "red", &ws.path("missing")]).0, 1);
+}
+
+#[test]
+fn rules_check_follows_the_color_rules() {
+    let ws = Workspace::new("rules");
+    let mut config = Config::new();
+    config.home_folder = Some(ws.root.to_path_buf());
+    config.set_item_color(ws.root.join("code/main.rs"), MarkerColor::Red);
+    config.color_rules.insert(
+        MarkerColor::Red,
+        ColorRule {
+            allow_delete: false,
+            include_in_search: false,
+            ..ColorRule::default()
+        },
+    );
+    config.save_to(&ws.root.join(".maui")).unwrap();
+
+    let (code, out) = ws.sift(&["rules", "check", "delete", &ws.path("notes.txt"), &ws.path("code")]);
+    assert_eq!(code, 1);
+    assert_eq!(
+        out,
+        format!("allowed  {}\ndenied   {}\n", ws.path("notes.txt"), ws.path("code"))
+    );
+    assert_eq!(ws.sift(&["rules", "check", "rename", &ws.path("code")]).0, 0);
+
+    let (_, out) = ws.sift(&["search", "main.rs", &ws.path(""), "--json"]);
+    assert_eq!(out.trim(), "[]");
+    assert_eq!(ws.sift(&["rules", "check", "fly", &ws.path("code")]).0, 2);
+}
+
+#[test]
+fn config_set_checks_values_before_saving() {
+    let ws = Workspace::new("config");
+    assert_eq!(ws.sift(&["config", "get", "lines_shown"]), (0, "40\n".to_string()));
+
+    assert_eq!(ws.sift(&["config", "set", "lines_shown", "60"]).0, 0);
+    assert_eq!(ws.sift(&["config", "set", "default_sort", "size-desc"]).0, 0);
+    let (code, message) = ws.sift(&["config", "set", "lines_shown", "many"]);
+    assert_eq!(code, 2);
+    assert_eq!(message, "'many' is not a valid value for lines_shown");
+    assert_eq!(ws.sift(&["config", "set", "colour", "red"]).0, 2);
+
+    let (_, out) = ws.sift(&["config", "get", "--json"]);
+    assert!(out.contains(r#""lines_shown":"60""#), "{}", out);
+    assert!(out.contains(r#""default_sort":"SizeDesc""#), "{}", out);
+}
+
+#[test]
+fn config_check_lists_problems_by_line() {
+    let ws = Workspace::new("check");
+    assert_eq!(ws.sift(&["config", "check"]), (0, String::new()));
+
+    fs::write(
+        ws.root.join(".maui"),
+        "schema_version = 1\nlines_shown = many\ndim_step = 2\n[keybindings]\nq = \"Fly\"\n",
+    )
+    .unwrap();
+    let (code, out) = ws.sift(&["config", "check"]);
+    assert_eq!(code, 1);
+    assert_eq!(
+        out,
+        format!(
+            "{0}: line 2: 'many' is not a value\n{0}: line 5: unknown action 'Fly'\n",
+            ws.path(".maui")
+        )
+    );
+    let (_, out) = ws.sift(&["config", "check", "--json"]);
+    assert_eq!(
+        out.trim(),
+        r#"[{"line":2,"message":"'many' is not a value"},{"line":5,"message":"unknown action 'Fly'"}]"#
+    );
+}
+
+#[test]
+fn ls_and_search_follow_a_stygian_file() {
+    let ws = Workspace::new("local");
+    fs::write(
+        ws.root.join("code/.stygian"),
+        "default_sort = \"name-desc\"\nignore_patterns = [\"lib.*\"]\n",
+    )
+    .unwrap();
+
+    let (_, out) = ws.sift(&["ls", "--json", &ws.path("code")]);
+    assert!(out.contains("main.rs") && out.contains(".stygian"), "{}", out);
+    assert!(!out.contains("lib.rs"), "{}", out);
+    let (_, out) = ws.sift(&["search", "lib", &ws.path("code"), "--json"]);
+    assert_eq!(out.trim(), "[]");
+
+    // Not above the file, and not into the saved config.
+    let (_, out) = ws.sift(&["search", "lib", &ws.path(""), "--json"]);
+    assert!(out.contains("lib.rs"), "{}", out);
+    ws.sift(&["tag", "set", "red", &ws.path("code/main.rs")]);
+    let saved = fs::read_to_string(ws.root.join(".maui")).unwrap();
+    assert!(!saved.contains("lib.*") && !saved.contains("NameDesc"), "{}", saved);
+}
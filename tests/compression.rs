@@ -0,0 +1,141 @@
+/*
+* Stygian Sift - A Terminal-based File Manager
+ * Copyright (C) 2024 Maui The Magnificent (Charon)
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published
+ * by the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+ * Contact: Maui_The_Magnificent@proton.me
+ * Project repository: https://github.com/Mauitron/StygianSift.git
+ */
+
+// Deflate and gzip, written and read back by our own coder.
+#![cfg(feature = "tui")]
+
+use std::io::Write;
+use stygian_sift::the_tome::*;
+
+// The same bytes on every run, with no patterns for deflate to find.
+fn noise(len: usize) -> Vec<u8> {
+    let mut state = 0x2545_F491_4F6C_DD1Du64;
+    (0..len)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state as u8
+        })
+        .collect()
+}
+
+fn samples() -> Vec<Vec<u8>> {
+    vec![
+        Vec::new(),
+        b"a".to_vec(),
+        b"the quick brown fox jumps over the lazy dog\n".repeat(500),
+        vec![0; 100_000],
+        noise(300_000),
+        [noise(1000), vec![7; 5000], noise(1000)].concat(),
+    ]
+}
+
+fn deflate(data: &[u8]) -> Vec<u8> {
+    let mut encoder = DeflateEncoder::new(Vec::new());
+    encoder.write_all(data).unwrap();
+    encoder.finish().unwrap()
+}
+
+fn gzip(data: &[u8]) -> Vec<u8> {
+    let mut encoder = GzipEncoder::new(Vec::new()).unwrap();
+    encoder.write_all(data).unwrap();
+    encoder.finish().unwrap()
+}
+
+#[test]
+fn crc32_matches_the_check_value() {
+    assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+    assert_eq!(crc32_update(crc32(b"12345"), b"6789"), 0xCBF4_3926);
+    assert_eq!(crc32(b""), 0);
+}
+
+#[test]
+fn deflate_round_trips() {
+    for data in samples() {
+        let packed = deflate(&data);
+        let (unpacked, used) = inflate(&packed, None).unwrap();
+        assert_eq!(unpacked, data);
+        assert_eq!(used, packed.len());
+    }
+    // Repeats have to come out smaller, noise may not grow by much.
+    assert!(deflate(&vec![0; 100_000]).len() < 1000);
+    assert!(deflate(&noise(300_000)).len() < 310_000);
+}
+
+#[test]
+fn a_stored_block_is_copied_through() {
+    let mut block = vec![0x01, 5, 0, !5, 0xFF];
+    block.extend_from_slice(b"hello");
+    assert_eq!(inflate(&block, None).unwrap(), (b"hello".to_vec(), 10));
+
+    block[3] = 0;
+    assert!(inflate(&block, None).is_err());
+}
+
+#[test]
+fn gzip_round_trips_and_concatenates() {
+    for data in samples() {
+        let packed = gzip(&data);
+        assert!(is_gzip(&packed));
+        assert_eq!(gunzip(&packed, None).unwrap(), data);
+    }
+    let both = [gzip(b"first "), gzip(b"second")].concat();
+    assert_eq!(gunzip(&both, None).unwrap(), b"first second");
+}
+
+#[test]
+fn a_limit_stops_early() {
+    let text = b"the quick brown fox jumps over the lazy dog\n".repeat(500);
+    assert_eq!(gunzip(&gzip(&text), Some(10)).unwrap(), &text[..10]);
+    let (unpacked, _) = inflate(&deflate(&text), Some(100)).unwrap();
+    assert!(unpacked.len() >= 100 && text.starts_with(&unpacked));
+}
+
+#[test]
+fn broken_streams_are_errors() {
+    let packed = gzip(&samples()[2]);
+    for len in 0..packed.len() {
+        assert!(gunzip(&packed[..len], None).is_err(), "{} bytes", len);
+    }
+
+    let mut wrong_sum = packed.clone();
+    let at = wrong_sum.len() - 8;
+    wrong_sum[at] ^= 0xFF;
+    assert!(gunzip(&wrong_sum, None).is_err());
+
+    assert!(gunzip(b"plain text", None).is_err());
+    // Block type 3 does not exist.
+    assert!(inflate(&[0x07], None).is_err());
+    // A back reference before the first byte.
+    assert!(inflate(&[0x03, 0x02, 0x00, 0x00], None).is_err());
+}
+
+#[test]
+fn garbage_never_panics() {
+    for seed in 0..200 {
+        let garbage = noise(64 + seed);
+        let _ = inflate(&garbage[seed % 64..], None);
+        let mut gz = vec![0x1F, 0x8B, 0x08, (seed % 32) as u8, 0, 0, 0, 0, 0, 3];
+        gz.extend_from_slice(&garbage);
+        let _ = gunzip(&gz, None);
+    }
+}
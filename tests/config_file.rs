@@ -0,0 +1,455 @@
+/*
+* Stygian Sift - A Terminal-based File Manager
+ * Copyright (C) 2024 Maui The Magnificent (Charon)
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published
+ * by the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+ * Contact: Maui_The_Magnificent@proton.me
+ * Project repository: https://github.com/Mauitron/StygianSift.git
+ */
+
+// The config file: saving and loading must keep everything, the old line
+// format is migrated, and what is wrong is reported by line.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+use stygian_sift::the_core::*;
+
+mod common;
+use common::TempDir;
+
+fn keys(text: &str) -> KeySequence {
+    KeySequence::parse(text).unwrap()
+}
+
+// A config with every field away from its default.
+fn everything() -> Config {
+    let mut config = Config::new();
+    config.home_folder = Some(PathBuf::from(
+        "/home/someone/with \"quotes\" and \\ slashes",
+    ));
+    config.lines_shown = 12;
+    config.text_editor = "hx --vsplit".to_string();
+    config.default_sort = SortOrder::SizeDesc;
+    config.ram_undo_limit = 1024;
+    config.disk_undo_limit = 4096;
+    config.allow_disk_undo = true;
+    config.search_depth_limit = 7;
+    config.draw_simple_borders = true;
+    config.max_distance = -5;
+    config.dim_step = 9;
+    config.time_format = "%Y-%m-%d # %H:%M".to_string();
+    config.relative_times = false;
+    config.theme = "themes/dark = night.theme".to_string();
+    config.color_depth = "256".to_string();
+    config.show_hidden = false;
+    config.ignore_patterns = vec!["target".to_string(), "*.o".to_string()];
+    config.custom_commands = BTreeMap::from([
+        ("build".to_string(), "cargo build".to_string()),
+        ("two words".to_string(), "echo \"hi\" | wc -c".to_string()),
+    ]);
+
+    let keybindings = config.keybindings.as_mut().unwrap();
+    keybindings.remove(&keys("q"));
+    keybindings.insert(keys("Ctrl+="), Action::GoToTop);
+    keybindings.insert(keys("[SPACE]"), Action::GitMenu);
+    keybindings.insert(keys("<leader> Ctrl+y \""), Action::YankPath);
+    config.leader_key = Keystroke::parse("Alt+,");
+    config.key_timeout = 1200;
+    config.modal_input = true;
+    let modes = &mut config.mode_keybindings;
+    modes.get_mut(&Mode::Normal).unwrap().remove(&keys(":"));
+    modes.get_mut(&Mode::Visual).unwrap().insert(keys("d"), Action::Murder);
+
+    config.set_item_color(PathBuf::from("/work/a, b.txt"), MarkerColor::Red);
+    config.set_item_color(PathBuf::from("/work/[brackets]"), MarkerColor::Red);
+    config.set_item_color(PathBuf::from("/work/plans"), MarkerColor::Blue);
+    // A rule for a color nothing has.
+    config.color_rules.insert(
+        MarkerColor::Green,
+        ColorRule {
+            allow_delete: false,
+            allow_rename: true,
+            allow_move: false,
+            allow_copy: true,
+            include_in_search: false,
+        },
+    );
+
+    config.shortcuts = Some(HashMap::from([(
+        '"',
+        (PathBuf::from("/tmp/odd|path"), "odd = name".to_string(), 3),
+    )]));
+    config.shortcut_layers[2].name = "Projects #2".to_string();
+    let index = config.add_new_layer("Extra".to_string());
+    config
+        .set_shortcut_in_layer(index, '.', PathBuf::from("/srv"), "srv".to_string(), 1)
+        .unwrap();
+    config.current_layer = index;
+
+    let mut work_layers: Vec<ShortcutLayer> = (0..10)
+        .map(|i| ShortcutLayer::new(format!("Layer {}", i)))
+        .collect();
+    work_layers[0].name = "Work".to_string();
+    work_layers[0].shortcuts = Some(HashMap::from([(
+        'w',
+        (PathBuf::from("/work"), "work".to_string(), 0),
+    )]));
+    config.profiles.insert(
+        "work".to_string(),
+        Profile {
+            theme: Some("plain".to_string()),
+            keybindings: Some(HashMap::from([(keys("x"), Action::Quit)])),
+            shortcut_layers: Some(work_layers),
+            ..Profile::default()
+        },
+    );
+    config.profiles.insert(
+        "minimal UI".to_string(),
+        Profile {
+            color_depth: Some("16".to_string()),
+            draw_simple_borders: Some(true),
+            ..Profile::default()
+        },
+    );
+    config.profile = "minimal UI".to_string();
+
+    config.file_type_rules = vec![
+        FileTypeRule::parse("*.tpl", "Source").unwrap(),
+        FileTypeRule::parse("#!deno", "Script").unwrap(),
+        FileTypeRule::parse("Makefile", "Build").unwrap(),
+    ];
+    config
+}
+
+#[test]
+fn nothing_is_lost_in_a_round_trip() {
+    let dir = TempDir::new("config-round-trip");
+    let path = dir.join(".maui");
+    let config = everything();
+    config.save_to(&path).unwrap();
+
+    let (loaded, report) = Config::load_checked(&path).unwrap();
+    assert_eq!(report.errors, Vec::new());
+    assert_eq!(report.migrated_to, None);
+    assert_eq!(loaded, config);
+
+    // Saving what was loaded writes the same file.
+    let first = fs::read_to_string(&path).unwrap();
+    assert!(first.starts_with("schema_version = 1\n"));
+    loaded.save_to(&path).unwrap();
+    let (again, _) = Config::load_checked(&path).unwrap();
+    assert_eq!(again, config);
+
+    let defaults = Config::new();
+    defaults.save_to(&path).unwrap();
+    assert_eq!(Config::load_from(&path).unwrap(), defaults);
+}
+
+#[test]
+fn every_action_reads_back_as_written() {
+    for action in Action::iter() {
+        assert_eq!(Action::from_str(&action.to_string()).as_ref(), Ok(action));
+    }
+}
+
+#[test]
+fn the_old_format_is_migrated_and_kept() {
+    let dir = TempDir::new("config-migrate");
+    let path = dir.join(".maui");
+    let old = "\
+home_folder = \"/home/someone\"
+lines_shown = 25
+default_sort = NameDesc
+text_editor = nvim
+relative_times = maybe
+keybindings:
+  \"Ctrl+t\" = GoToTop
+  g = GoToTop
+  e = GoToBottom
+colored_items:
+  red:
+    /work/secret
+  red_rule = false,true,true,true,false
+shortcuts:
+  a = /work|work|2
+shortcut_layers:
+  layer_1_name = Music
+  layer_1_shortcut_m = /music|music|0
+current_layer = 1
+file_types:
+  *.tpl = Source
+";
+    fs::write(&path, old).unwrap();
+
+    let (config, report) = Config::load_checked(&path).unwrap();
+    assert_eq!(config.home_folder, Some(PathBuf::from("/home/someone")));
+    assert_eq!(config.lines_shown, 25);
+    assert_eq!(config.default_sort, SortOrder::NameDesc);
+    assert_eq!(config.text_editor, "nvim");
+    assert_eq!(
+        config.get_action(&keys("Ctrl+t")),
+        Some(&Action::GoToTop)
+    );
+    // The old g and e were read as the second key after g.
+    assert_eq!(config.get_action(&keys("g")), None);
+    assert_eq!(config.get_action(&keys("e")), None);
+    assert_eq!(config.get_action(&keys("g e")), Some(&Action::GoToBottom));
+    assert_eq!(config.key_conflicts(), []);
+    assert_eq!(
+        config.get_item_color(Path::new("/work/secret")),
+        Some(MarkerColor::Red)
+    );
+    assert!(!config.color_rules[&MarkerColor::Red].allow_delete);
+    assert_eq!(
+        config.shortcuts.as_ref().unwrap()[&'a'],
+        (PathBuf::from("/work"), "work".to_string(), 2)
+    );
+    assert_eq!(config.shortcut_layers[1].name, "Music");
+    assert_eq!(config.current_layer, 1);
+    assert_eq!(config.file_type_rules.len(), 1);
+    assert_eq!(
+        report.errors,
+        vec![TomlError::new(
+            5,
+            "'maybe' is not a valid value for relative_times"
+        )]
+    );
+
+    let backup = report.migrated_to.unwrap();
+    assert_eq!(backup, dir.join(".maui.old"));
+    assert_eq!(fs::read_to_string(&backup).unwrap(), old);
+    let (reloaded, report) = Config::load_checked(&path).unwrap();
+    assert_eq!(report.errors, Vec::new());
+    assert_eq!(report.migrated_to, None);
+    assert_eq!(reloaded, config);
+}
+
+#[test]
+fn problems_are_reported_by_line() {
+    let text = "\
+schema_version = 1
+lines_shown = \"many\"
+dim_step = 2
+colour = \"red\"
+theme = \"dark
+search_depth_limit = 5
+
+[keybindings]
+\"Ctrl+t\" = \"FlyAway\"
+\"Ctrl+g\" = \"GoToTop\"
+\"Ctrl+g\" = \"GoToBottom\"
+
+[color_rules.purple]
+allow_delete = false
+
+[shortcuts.a]
+name = \"no path\"
+";
+    let (config, errors) = Config::from_toml(text);
+    let errors: Vec<String> = errors.iter().map(|error| error.to_string()).collect();
+    assert_eq!(
+        errors,
+        [
+            "line 2: lines_shown should be an integer, not a string",
+            "line 4: unknown setting 'colour'",
+            "line 5: unterminated string",
+            "line 9: unknown action 'FlyAway'",
+            "line 11: 'keybindings.Ctrl+g' is already set on line 10",
+            "line 14: unknown color 'purple'",
+            "line 17: shortcut 'a' has no path",
+        ]
+    );
+    // The rest still applies.
+    assert_eq!(config.lines_shown, Config::new().lines_shown);
+    assert_eq!(config.dim_step, 2);
+    assert_eq!(config.search_depth_limit, 5);
+    let keybindings = config.keybindings.as_ref().unwrap();
+    assert_eq!(keybindings.len(), 1);
+    assert_eq!(
+        keybindings.get(&keys("Ctrl+g")),
+        Some(&Action::GoToTop)
+    );
+}
+
+#[test]
+fn a_newer_schema_is_reported() {
+    let (_, errors) = Config::from_toml("schema_version = 2\nlines_shown = 10\n");
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].line, 1);
+    assert!(errors[0].message.contains("newer"));
+}
+
+#[test]
+fn profiles_swap_in_and_take_their_changes_along() {
+    let dir = TempDir::new("config-profiles");
+    let path = dir.join(".maui");
+    let global = everything();
+    let mut config = global.clone();
+
+    config.use_profile(Some("work")).unwrap();
+    assert_eq!(config.theme, "plain");
+    assert_eq!(config.shortcut_layers[0].name, "Work");
+    // Layer 10 is past the profile's layers.
+    assert_eq!(config.current_layer, 9);
+    assert_eq!(
+        config.get_action(&keys("x")),
+        Some(&Action::Quit)
+    );
+    assert_eq!(config.get_action(&keys("q")), None);
+    // Not in the profile, so the plain config's.
+    assert_eq!(config.color_depth, "256");
+
+    config.set_keybinding(keys("y"), Action::GoToTop);
+    config.save_to(&path).unwrap();
+    let saved = Config::load_from(&path).unwrap();
+    assert_eq!(saved.theme, global.theme);
+    assert_eq!(saved.keybindings, global.keybindings);
+    assert_eq!(saved.active_profile, None);
+    let work = &saved.profiles["work"];
+    assert_eq!(work.keybindings.as_ref().unwrap().len(), 2);
+
+    config.use_profile(Some("minimal UI")).unwrap();
+    assert_eq!(config.theme, global.theme);
+    assert!(config.draw_simple_borders);
+    config.use_profile(None).unwrap();
+    assert_eq!(config.shortcut_layers, global.shortcut_layers);
+    assert_eq!(
+        config.use_profile(Some("gaming")),
+        Err("unknown profile 'gaming'".to_string())
+    );
+}
+
+#[test]
+fn profile_mistakes_are_reported() {
+    let text = "\
+profile = \"home\"
+
+[profiles.work]
+theme = \"plain\"
+lines_shown = 10
+
+[profiles.work.keybindings]
+\"x\" = \"Quit\"
+
+[profiles.work.keybindings.visual]
+d = \"Delete\"
+";
+    let (config, errors) = Config::from_toml(text);
+    let errors: Vec<String> = errors.iter().map(|error| error.to_string()).collect();
+    assert_eq!(
+        errors,
+        [
+            "line 1: unknown profile 'home'",
+            "line 5: 'lines_shown' can not be set in a profile",
+            "line 11: 'keybindings.visual.d' can not be set in a profile",
+        ]
+    );
+    assert_eq!(config.profile, "");
+    assert_eq!(config.profile_names(), ["work"]);
+}
+
+#[test]
+fn parses_the_toml_subset() {
+    let text = "\
+# comment
+top = 'literal \\n' # trailing
+[a.\"b c\"]
+list = [
+  1, -2_000, # numbers
+  true,
+  \"\\u00e9\\t\",
+]
+empty = []
+inline = { x = 1 }
+[[nope]]
+ignored = 1
+";
+    let (entries, errors) = parse_toml(text);
+    let found: Vec<(String, TomlValue, usize)> = entries
+        .into_iter()
+        .map(|entry| (entry.path.join("/"), entry.value, entry.line))
+        .collect();
+    assert_eq!(
+        found,
+        [
+            (
+                "top".to_string(),
+                TomlValue::String("literal \\n".to_string()),
+                2
+            ),
+            (
+                "a/b c/list".to_string(),
+                TomlValue::Array(vec![
+                    TomlValue::Integer(1),
+                    TomlValue::Integer(-2000),
+                    TomlValue::Boolean(true),
+                    TomlValue::String("é\t".to_string()),
+                ]),
+                4
+            ),
+            ("a/b c/empty".to_string(), TomlValue::Array(Vec::new()), 9),
+        ]
+    );
+    assert_eq!(
+        errors,
+        [
+            TomlError::new(10, "inline tables are not supported"),
+            TomlError::new(11, "arrays of tables are not supported"),
+        ]
+    );
+}
+
+#[test]
+fn a_broken_header_or_array_does_not_spill_over() {
+    let text = "\
+[good]
+a = 1
+[bad
+b = 2
+list = [
+  1,
+]
+[also.good]
+c = [
+  1,
+  oops, # not a value
+  \"]\",
+]
+d = 4
+e = [1,
+f = 5
+";
+    let (entries, errors) = parse_toml(text);
+    let found: Vec<(String, usize)> = entries
+        .into_iter()
+        .map(|entry| (entry.path.join("/"), entry.line))
+        .collect();
+    // What sat under [bad is dropped, the items of c are not read as keys and
+    // an array that never closes only loses its own line.
+    assert_eq!(
+        found,
+        [
+            ("good/a".to_string(), 2),
+            ("also/good/d".to_string(), 14),
+            ("also/good/f".to_string(), 16),
+        ]
+    );
+    let lines: Vec<usize> = errors.iter().map(|error| error.line).collect();
+    assert_eq!(lines, [3, 11, 16]);
+}
@@ -0,0 +1,194 @@
+/*
+* Stygian Sift - A Terminal-based File Manager
+ * Copyright (C) 2024 Maui The Magnificent (Charon)
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published
+ * by the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+ * Contact: Maui_The_Magnificent@proton.me
+ * Project repository: https://github.com/Mauitron/StygianSift.git
+ */
+
+// The structured preview's own parsers: json, csv and the section view of
+// toml and ini files.
+#![cfg(feature = "tui")]
+
+use stygian_sift::the_tome::*;
+
+fn text(lines: &[PreviewLine]) -> Vec<&str> {
+    lines.iter().map(|line| line.text.as_str()).collect()
+}
+
+fn object(members: &[(&str, JsonValue)]) -> JsonValue {
+    JsonValue::Object(
+        members
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.clone()))
+            .collect(),
+    )
+}
+
+fn error(json: &str) -> JsonError {
+    parse_json(json).unwrap_err()
+}
+
+#[test]
+fn json_escapes_are_decoded() {
+    let parsed = parse_json(r#"{"say \"hi\"": "tab\there\nnew \/ \u00e9 \ud83d\ude00 \\"}"#);
+    assert_eq!(
+        parsed.unwrap(),
+        object(&[(
+            "say \"hi\"",
+            JsonValue::Str("tab\there\nnew / é 😀 \\".into())
+        )])
+    );
+}
+
+#[test]
+fn json_keeps_numbers_as_written() {
+    assert_eq!(
+        parse_json("\u{feff} [0, -1.50e+3, true, null] ").unwrap(),
+        JsonValue::Array(vec![
+            JsonValue::Number("0".into()),
+            JsonValue::Number("-1.50e+3".into()),
+            JsonValue::Bool(true),
+            JsonValue::Null,
+        ])
+    );
+}
+
+#[test]
+fn surrogates_have_to_pair_up() {
+    assert_eq!(error(r#""\ud83d""#).message, "unpaired surrogate");
+    assert_eq!(error(r#""\ud83d\u0041""#).message, "unpaired surrogate");
+    assert_eq!(error(r#""\ude00""#).message, "invalid \\u escape");
+    assert_eq!(error(r#""\u12g4""#).message, "invalid \\u escape");
+}
+
+#[test]
+fn malformed_json_says_where() {
+    let e = error("{\n  \"a\": 1,\n  \"b\": tru\n}");
+    assert_eq!(e.line, 3);
+
+    assert_eq!(error("[1, 2,]").message, "expected a value");
+    assert_eq!(error("[01]").message, "leading zeros are not allowed");
+    assert_eq!(error("{\"a\" 1}").message, "expected ':' after key");
+    assert_eq!(error("\"open").message, "unterminated string");
+    assert_eq!(error("\"a\u{1}b\"").message, "control character in string");
+    assert_eq!(error("\"\\x\"").message, "invalid escape sequence");
+    assert_eq!(
+        error("{} {}").message,
+        "unexpected data after the top level value"
+    );
+    assert_eq!(error(&"[".repeat(1000)).message, "nesting is too deep");
+    assert_eq!(error("").message, "unexpected end of input");
+}
+
+#[test]
+fn json_is_shown_with_json_escapes() {
+    let lines = json_preview_lines("{\"key\\n\": [\"\\u001b\", \"quote \\\"\"], \"n\": 1}", 8);
+    assert_eq!(
+        text(&lines)[2..],
+        [
+            "{",
+            "  \"key\\n\": [",
+            "    \"\\u001b\",",
+            "    \"quote \\\"\"",
+            "  ],",
+            "  \"n\": 1",
+            "}"
+        ]
+    );
+
+    let folded = json_preview_lines("{\"list\": [1, 2, 3]}", 1);
+    assert_eq!(text(&folded)[2..], ["{", "  \"list\": […] 3 items", "}"]);
+}
+
+#[test]
+fn quoted_fields_keep_their_newlines() {
+    let csv = "name,notes\r\n\"Smith, J\",\"first line\nsecond \"\"quoted\"\"\"\nplain,\n";
+    assert_eq!(
+        parse_delimited(csv, ',', false),
+        [
+            vec!["name", "notes"],
+            vec!["Smith, J", "first line\nsecond \"quoted\""],
+            vec!["plain", ""],
+        ]
+    );
+    assert_eq!(
+        parse_delimited("a\tb\nc\td", '\t', false),
+        [["a", "b"], ["c", "d"]]
+    );
+    // The row a cut off file ends in is left out.
+    assert_eq!(
+        parse_delimited("a,b\nc,\"unfinish", ',', true),
+        [["a", "b"]]
+    );
+}
+
+#[test]
+fn toml_is_shown_by_section() {
+    let toml = r#"
+# a comment
+title = "demo"
+
+[server]
+ports = [
+  8000,
+  8001,
+]
+motd = """
+hello
+"""
+host = "localhost"
+
+[[plugins]]
+name = "one"
+stray line
+"#;
+    let lines = section_preview_lines(toml, StructuredKind::Toml);
+    assert_eq!(
+        text(&lines),
+        [
+            "TOML · 2 sections",
+            "unrecognised lines: 17",
+            "",
+            "▸ (top level)  (1 keys)",
+            "    title = \"demo\"",
+            "",
+            "▸ [server]  (3 keys)",
+            "    ports = [ …",
+            "    motd = \"\"\" …",
+            "    host = \"localhost\"",
+            "",
+            "▸ [[plugins]]  (1 keys)",
+            "    name = \"one\"",
+        ]
+    );
+}
+
+#[test]
+fn ini_takes_colons_and_semicolons() {
+    let ini = "; comment\n[core]\nbare: true\nname = demo\n# also a comment\n";
+    let lines = section_preview_lines(ini, StructuredKind::Ini);
+    assert_eq!(
+        text(&lines),
+        [
+            "INI · 1 sections",
+            "",
+            "▸ [core]  (2 keys)",
+            "    bare = true",
+            "    name = demo"
+        ]
+    );
+}
@@ -0,0 +1,518 @@
+/*
+* Stygian Sift - A Terminal-based File Manager
+ * Copyright (C) 2024 Maui The Magnificent (Charon)
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published
+ * by the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+ * Contact: Maui_The_Magnificent@proton.me
+ * Project repository: https://github.com/Mauitron/StygianSift.git
+ */
+
+// File operations and their undo, run through the core library against the
+// in-memory file system. The browser only adds its own state on top, the
+// last section checks that.
+//
+// /home
+//   notes.txt     "hello"
+//   docs/
+//     guide.md    "# Guide"
+//     drafts/
+//       plan.md   "plan"
+//   archive/
+
+use std::{
+    io,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::SystemTime,
+};
+use stygian_sift::the_core::*;
+
+mod common;
+use common::TempDir;
+
+struct Session {
+    config: Config,
+    undo_manager: UndoManager,
+}
+
+impl Session {
+    fn ops(&mut self) -> FileOperations<'_> {
+        FileOperations::new(&self.config, &mut self.undo_manager)
+    }
+}
+
+fn setup() -> (Session, Arc<MemoryFileSystem>) {
+    let fs = Arc::new(MemoryFileSystem::new());
+    fs.create_dir_all(Path::new("/home/docs/drafts")).unwrap();
+    fs.create_dir(Path::new("/home/archive")).unwrap();
+    fs.write(Path::new("/home/notes.txt"), b"hello").unwrap();
+    fs.write(Path::new("/home/docs/guide.md"), b"# Guide").unwrap();
+    fs.write(Path::new("/home/docs/drafts/plan.md"), b"plan").unwrap();
+
+    let config = Config::new();
+    let undo_manager = UndoManager::with_config(fs.clone(), &config).unwrap();
+    (Session { config, undo_manager }, fs)
+}
+
+fn p(path: &str) -> PathBuf {
+    PathBuf::from(path)
+}
+
+fn contents(fs: &MemoryFileSystem, path: &str) -> String {
+    String::from_utf8(fs.read(Path::new(path)).unwrap()).unwrap()
+}
+
+fn tree(fs: &MemoryFileSystem, dir: &str) -> Vec<String> {
+    fn walk(fs: &MemoryFileSystem, dir: &Path, out: &mut Vec<String>) {
+        let mut children = fs.read_dir(dir).unwrap();
+        children.sort();
+        for child in children {
+            if fs.is_dir(&child) {
+                out.push(format!("{}/", child.display()));
+                walk(fs, &child, out);
+            } else {
+                let data = fs.read(&child).unwrap();
+                out.push(format!("{} {}", child.display(), String::from_utf8_lossy(&data)));
+            }
+        }
+    }
+    let mut out = Vec::new();
+    walk(fs, Path::new(dir), &mut out);
+    out
+}
+
+fn undo(app: &mut Session) -> io::Result<String> {
+    app.ops().undo().expect("nothing to undo")
+}
+
+fn protect(app: &mut Session, path: &str, color: MarkerColor, rule: ColorRule) {
+    app.config.set_item_color(p(path), color);
+    app.config.color_rules.insert(color, rule);
+}
+
+////////////////////////////////////////////////////////Round Trips////////////////////////////////////////////////////////////////////
+
+#[test]
+fn deleted_file_comes_back() {
+    let (mut app, fs) = setup();
+    app.ops().delete(&p("/home/notes.txt")).unwrap();
+    assert!(!fs.exists(&p("/home/notes.txt")));
+
+    undo(&mut app).unwrap();
+    assert_eq!(contents(&fs, "/home/notes.txt"), "hello");
+}
+
+#[test]
+fn deleted_directory_comes_back_whole() {
+    let (mut app, fs) = setup();
+    let before = tree(&fs, "/home/docs");
+    app.ops().delete(&p("/home/docs")).unwrap();
+    assert!(!fs.exists(&p("/home/docs")));
+
+    undo(&mut app).unwrap();
+    assert_eq!(tree(&fs, "/home/docs"), before);
+}
+
+#[test]
+fn rename_is_undone() {
+    let (mut app, fs) = setup();
+    let renamed = app.ops().rename(&p("/home/notes.txt"), "todo.txt").unwrap();
+    assert_eq!(renamed, p("/home/todo.txt"));
+    assert_eq!(contents(&fs, "/home/todo.txt"), "hello");
+
+    undo(&mut app).unwrap();
+    assert!(!fs.exists(&p("/home/todo.txt")));
+    assert_eq!(contents(&fs, "/home/notes.txt"), "hello");
+}
+
+#[test]
+fn move_is_undone() {
+    let (mut app, fs) = setup();
+    let moved = app.ops().move_into(&p("/home/docs"), &p("/home/archive")).unwrap();
+    assert_eq!(moved, p("/home/archive/docs"));
+    assert_eq!(contents(&fs, "/home/archive/docs/drafts/plan.md"), "plan");
+    assert!(!fs.exists(&p("/home/docs")));
+
+    undo(&mut app).unwrap();
+    assert_eq!(contents(&fs, "/home/docs/drafts/plan.md"), "plan");
+    assert!(fs.read_dir(&p("/home/archive")).unwrap().is_empty());
+}
+
+#[test]
+fn paste_and_duplicate_are_undone() {
+    let (mut app, fs) = setup();
+    let pasted = app.ops().paste(&p("/home/docs"), &p("/home/archive")).unwrap();
+    let copy = app.ops().duplicate(&p("/home/notes.txt")).unwrap();
+    assert_eq!(pasted, p("/home/archive/docs"));
+    assert_eq!(copy, p("/home/notes (1).txt"));
+    assert_eq!(contents(&fs, "/home/archive/docs/guide.md"), "# Guide");
+
+    undo(&mut app).unwrap();
+    assert!(!fs.exists(&copy));
+    undo(&mut app).unwrap();
+    assert!(!fs.exists(&pasted));
+    assert_eq!(contents(&fs, "/home/docs/guide.md"), "# Guide");
+}
+
+#[test]
+fn created_entries_are_undone() {
+    let (mut app, fs) = setup();
+    app.ops().make_directory(&p("/home/new")).unwrap();
+    app.ops().make_file(&p("/home/new.txt")).unwrap();
+    assert!(fs.is_dir(&p("/home/new")));
+    assert_eq!(contents(&fs, "/home/new.txt"), "");
+
+    undo(&mut app).unwrap();
+    undo(&mut app).unwrap();
+    assert!(!fs.exists(&p("/home/new")));
+    assert!(!fs.exists(&p("/home/new.txt")));
+}
+
+#[test]
+fn a_mode_change_is_undone() {
+    let (mut app, fs) = setup();
+    fs.set_mode(&p("/home/docs"), 0o700).unwrap();
+    fs.set_mode(&p("/home/docs/guide.md"), 0o600).unwrap();
+    app.undo_manager
+        .add_tome_entry(UndoEntry {
+            original_path: p("/home/docs"),
+            operation: Operation::ChangeMode {
+                previous_modes: vec![(p("/home/docs/guide.md"), 0o644), (p("/home/docs"), 0o755)],
+                timestamp: SystemTime::now(),
+            },
+            storage: UndoStorage::Ram(Vec::new()),
+            size: 0,
+        })
+        .unwrap();
+
+    undo(&mut app).unwrap();
+    assert_eq!(fs.mode(&p("/home/docs")).unwrap(), 0o755);
+    assert_eq!(fs.mode(&p("/home/docs/guide.md")).unwrap(), 0o644);
+}
+
+#[test]
+fn an_owner_change_is_undone_after_a_restart() {
+    let (mut app, fs) = setup();
+    fs.set_owner(&p("/home/notes.txt"), 1000, 100).unwrap();
+    app.undo_manager
+        .add_tome_entry(UndoEntry {
+            original_path: p("/home/notes.txt"),
+            operation: Operation::ChangeOwner {
+                previous_owners: vec![(p("/home/notes.txt"), (0, 0))],
+                timestamp: SystemTime::now(),
+            },
+            storage: UndoStorage::Ram(Vec::new()),
+            size: 0,
+        })
+        .unwrap();
+    app.undo_manager.save_history().unwrap();
+
+    let mut restarted = Session {
+        config: Config::new(),
+        undo_manager: UndoManager::with_config(fs.clone(), &Config::new()).unwrap(),
+    };
+    restarted.undo_manager.load_history().unwrap();
+    undo(&mut restarted).unwrap();
+    assert_eq!(fs.owner(&p("/home/notes.txt")).unwrap(), (0, 0));
+}
+
+#[test]
+fn a_mode_moves_with_its_file() {
+    let (_, fs) = setup();
+    fs.set_mode(&p("/home/docs/drafts/plan.md"), 0o600).unwrap();
+    fs.rename(&p("/home/docs"), &p("/home/archive/docs")).unwrap();
+    assert_eq!(fs.mode(&p("/home/archive/docs/drafts/plan.md")).unwrap(), 0o600);
+    assert_eq!(fs.mode(&p("/home/archive/docs")).unwrap(), 0o755);
+    assert!(fs.mode(&p("/home/docs/drafts/plan.md")).is_err());
+}
+
+#[test]
+fn a_whole_session_unwinds_to_the_start() {
+    let (mut app, fs) = setup();
+    let before = tree(&fs, "/home");
+
+    app.ops().rename(&p("/home/notes.txt"), "journal.txt").unwrap();
+    app.ops().duplicate(&p("/home/journal.txt")).unwrap();
+    app.ops().delete(&p("/home/docs/drafts")).unwrap();
+    app.ops().paste(&p("/home/docs"), &p("/home/archive")).unwrap();
+    app.ops().move_into(&p("/home/journal (1).txt"), &p("/home/docs")).unwrap();
+    app.ops().delete(&p("/home/journal.txt")).unwrap();
+    app.ops().make_file(&p("/home/archive/index")).unwrap();
+    assert_ne!(tree(&fs, "/home"), before);
+
+    while !app.undo_manager.entries.is_empty() {
+        undo(&mut app).unwrap();
+    }
+    assert_eq!(tree(&fs, "/home"), before);
+}
+
+#[test]
+fn the_history_survives_a_restart() {
+    let (mut app, fs) = setup();
+    let before = tree(&fs, "/home");
+
+    app.ops().rename(&p("/home/notes.txt"), "journal\tnew.txt").unwrap();
+    app.ops().delete(&p("/home/journal\tnew.txt")).unwrap();
+    app.ops().delete(&p("/home/docs/drafts")).unwrap();
+    app.ops().move_into(&p("/home/docs"), &p("/home/archive")).unwrap();
+    app.ops().make_directory(&p("/home/new")).unwrap();
+    app.undo_manager.save_history().unwrap();
+    let described: Vec<String> = app.undo_manager.entries.iter().map(UndoEntry::describe).collect();
+
+    let mut restarted = Session {
+        config: Config::new(),
+        undo_manager: UndoManager::with_config(fs.clone(), &Config::new()).unwrap(),
+    };
+    restarted.undo_manager.load_history().unwrap();
+    let reloaded: Vec<String> = restarted.undo_manager.entries.iter().map(UndoEntry::describe).collect();
+    assert_eq!(reloaded, described);
+
+    while !restarted.undo_manager.entries.is_empty() {
+        undo(&mut restarted).unwrap();
+    }
+    assert_eq!(tree(&fs, "/home"), before);
+}
+
+/////////////////////////////////////////////////////////Conflicts////////////////////////////////////////////////////////////////////
+
+#[test]
+fn paste_never_overwrites() {
+    let (mut app, fs) = setup();
+    fs.write(&p("/home/archive/notes.txt"), b"older").unwrap();
+
+    let first = app.ops().paste(&p("/home/notes.txt"), &p("/home/archive")).unwrap();
+    let second = app.ops().paste(&p("/home/notes.txt"), &p("/home/archive")).unwrap();
+    assert_eq!(first, p("/home/archive/notes (1).txt"));
+    assert_eq!(second, p("/home/archive/notes (2).txt"));
+    assert_eq!(contents(&fs, "/home/archive/notes.txt"), "older");
+
+    // Pasting where it came from makes a copy instead of truncating it.
+    let beside = app.ops().paste(&p("/home/notes.txt"), &p("/home")).unwrap();
+    assert_eq!(beside, p("/home/notes (1).txt"));
+    assert_eq!(contents(&fs, "/home/notes.txt"), "hello");
+}
+
+#[test]
+fn a_directory_is_not_pasted_into_itself() {
+    let (mut app, fs) = setup();
+    let before = tree(&fs, "/home");
+    assert!(app.ops().paste(&p("/home/docs"), &p("/home/docs/drafts")).is_err());
+    assert_eq!(tree(&fs, "/home"), before);
+}
+
+#[test]
+fn move_and_rename_refuse_to_overwrite() {
+    let (mut app, fs) = setup();
+    fs.write(&p("/home/archive/notes.txt"), b"older").unwrap();
+    fs.write(&p("/home/todo.txt"), b"todo").unwrap();
+
+    let error = app.ops().move_into(&p("/home/notes.txt"), &p("/home/archive")).unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::AlreadyExists);
+
+    let error = app.ops().rename(&p("/home/notes.txt"), "todo.txt").unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::AlreadyExists);
+
+    assert_eq!(contents(&fs, "/home/notes.txt"), "hello");
+    assert_eq!(contents(&fs, "/home/archive/notes.txt"), "older");
+    assert_eq!(contents(&fs, "/home/todo.txt"), "todo");
+    assert!(app.undo_manager.entries.is_empty());
+}
+
+#[test]
+fn blocked_undo_keeps_the_entry_for_later() {
+    let (mut app, fs) = setup();
+    app.ops().delete(&p("/home/notes.txt")).unwrap();
+    fs.write(&p("/home/notes.txt"), b"new").unwrap();
+
+    let error = undo(&mut app).unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::AlreadyExists);
+    assert_eq!(contents(&fs, "/home/notes.txt"), "new");
+    assert_eq!(app.undo_manager.entries.len(), 1);
+
+    fs.remove_file(&p("/home/notes.txt")).unwrap();
+    undo(&mut app).unwrap();
+    assert_eq!(contents(&fs, "/home/notes.txt"), "hello");
+}
+
+#[test]
+fn undo_does_not_move_over_a_newcomer() {
+    let (mut app, fs) = setup();
+    app.ops().move_into(&p("/home/notes.txt"), &p("/home/archive")).unwrap();
+    fs.write(&p("/home/notes.txt"), b"new").unwrap();
+
+    assert!(undo(&mut app).is_err());
+    assert_eq!(contents(&fs, "/home/notes.txt"), "new");
+    assert_eq!(contents(&fs, "/home/archive/notes.txt"), "hello");
+}
+
+#[test]
+fn undo_of_a_vanished_move_is_dropped() {
+    let (mut app, fs) = setup();
+    app.ops().move_into(&p("/home/notes.txt"), &p("/home/archive")).unwrap();
+    fs.remove_file(&p("/home/archive/notes.txt")).unwrap();
+
+    let error = undo(&mut app).unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::NotFound);
+    assert!(app.undo_manager.entries.is_empty());
+}
+
+////////////////////////////////////////////////////////Protection////////////////////////////////////////////////////////////////////
+
+#[test]
+fn protected_file_stays_put() {
+    let (mut app, fs) = setup();
+    let locked = ColorRule {
+        allow_delete: false,
+        allow_rename: false,
+        allow_move: false,
+        allow_copy: false,
+        include_in_search: true,
+    };
+    protect(&mut app, "/home/notes.txt", MarkerColor::Red, locked);
+    let notes = p("/home/notes.txt");
+
+    for error in [
+        app.ops().delete(&notes).unwrap_err(),
+        app.ops().rename(&notes, "other.txt").unwrap_err(),
+        app.ops().paste(&notes, &p("/home/archive")).unwrap_err(),
+        app.ops().duplicate(&notes).unwrap_err(),
+        app.ops().move_into(&notes, &p("/home/archive")).unwrap_err(),
+    ] {
+        assert_eq!(error.kind(), io::ErrorKind::PermissionDenied);
+    }
+    assert_eq!(contents(&fs, "/home/notes.txt"), "hello");
+    assert!(fs.read_dir(&p("/home/archive")).unwrap().is_empty());
+    assert!(app.undo_manager.entries.is_empty());
+}
+
+#[test]
+fn a_protected_child_protects_its_directories() {
+    let (mut app, fs) = setup();
+    let rule = ColorRule {
+        allow_delete: false,
+        ..ColorRule::default()
+    };
+    protect(&mut app, "/home/docs/drafts/plan.md", MarkerColor::Blue, rule);
+
+    let error = app.ops().delete(&p("/home/docs")).unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::PermissionDenied);
+    assert!(fs.exists(&p("/home/docs/drafts/plan.md")));
+
+    // Only deleting is forbidden.
+    app.ops().rename(&p("/home/docs"), "papers").unwrap();
+    assert!(fs.exists(&p("/home/papers/drafts/plan.md")));
+}
+
+#[test]
+fn colors_without_rules_allow_everything() {
+    let (mut app, fs) = setup();
+    app.config.set_item_color(p("/home/notes.txt"), MarkerColor::Green);
+    app.ops().delete(&p("/home/notes.txt")).unwrap();
+    assert!(!fs.exists(&p("/home/notes.txt")));
+}
+
+///////////////////////////////////////////////////////On The Disk////////////////////////////////////////////////////////////////////
+
+#[test]
+fn the_disk_behaves_like_memory() {
+    let root = TempDir::new("ops");
+    std::fs::create_dir(root.join("docs")).unwrap();
+    std::fs::write(root.join("docs").join("guide.md"), "# Guide").unwrap();
+
+    let config = Config::new();
+    let undo_manager = UndoManager::with_config(Arc::new(RealFileSystem), &config).unwrap();
+    let mut app = Session { config, undo_manager };
+
+    let docs = root.join("docs");
+    let copy = app.ops().duplicate(&docs).unwrap();
+    assert_eq!(copy, root.join("docs (1)"));
+    app.ops().delete(&docs).unwrap();
+    assert!(!docs.exists());
+
+    undo(&mut app).unwrap();
+    undo(&mut app).unwrap();
+    assert_eq!(std::fs::read_to_string(docs.join("guide.md")).unwrap(), "# Guide");
+    assert!(!copy.exists());
+}
+
+#[cfg(unix)]
+#[test]
+fn a_link_back_up_is_copied_as_a_link() {
+    let root = TempDir::new("ops-link");
+    let docs = root.join("docs");
+    std::fs::create_dir(&docs).unwrap();
+    std::fs::write(docs.join("guide.md"), "# Guide").unwrap();
+    std::os::unix::fs::symlink("..", docs.join("up")).unwrap();
+
+    let config = Config::new();
+    let undo_manager = UndoManager::with_config(Arc::new(RealFileSystem), &config).unwrap();
+    let mut app = Session { config, undo_manager };
+
+    let copy = app.ops().duplicate(&docs).unwrap();
+    assert_eq!(std::fs::read_link(copy.join("up")).unwrap(), Path::new(".."));
+    assert_eq!(std::fs::read_to_string(copy.join("guide.md")).unwrap(), "# Guide");
+
+    app.ops().delete(&docs).unwrap();
+    assert!(!docs.exists());
+    assert!(root.join("docs (1)").exists());
+    undo(&mut app).unwrap();
+    assert_eq!(std::fs::read_link(docs.join("up")).unwrap(), Path::new(".."));
+}
+
+//////////////////////////////////////////////////////////Browser/////////////////////////////////////////////////////////////////////
+
+#[cfg(feature = "tui")]
+mod browser {
+    use super::{contents, p};
+    use std::{io, path::PathBuf, sync::Arc};
+    use stygian_sift::the_tome::*;
+
+    fn setup() -> (AppState, Arc<MemoryFileSystem>) {
+        let (_, fs) = super::setup();
+        let mut config = Config::new();
+        config.home_folder = Some(PathBuf::from("/home"));
+        let app = AppState::with_file_system(config, fs.clone()).unwrap();
+        (app, fs)
+    }
+
+    #[test]
+    fn a_refused_move_ends_moving() {
+        let (mut app, fs) = setup();
+        fs.write(&p("/home/archive/notes.txt"), b"older").unwrap();
+        app.multiple_selected_files = Some([p("/home/notes.txt")].into_iter().collect());
+        app.start_move(None).unwrap();
+
+        let error = app.finish_move(&p("/home/archive")).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::AlreadyExists);
+        assert!(!app.is_moving);
+        assert!(app.multiple_selected_files.is_none());
+        assert_eq!(contents(&fs, "/home/notes.txt"), "hello");
+    }
+
+    #[test]
+    fn undoing_a_move_follows_it_back() {
+        let (mut app, fs) = setup();
+        app.file_to_move = Some(p("/home/docs"));
+        app.finish_move(&p("/home/archive")).unwrap();
+        app.current_dir = p("/home/archive");
+
+        // The outcome is shown in the interaction field.
+        let terminal = VirtualTerminal::new(100, 30);
+        undo_last_operation(&mut app, &mut io::sink()).unwrap();
+        assert!(terminal.lines().iter().any(|line| line.contains("Undid move.")));
+        assert_eq!(contents(&fs, "/home/docs/guide.md"), "# Guide");
+        assert_eq!(app.current_dir, p("/home"));
+    }
+}
@@ -0,0 +1,298 @@
+/*
+* Stygian Sift - A Terminal-based File Manager
+ * Copyright (C) 2024 Maui The Magnificent (Charon)
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published
+ * by the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+ * Contact: Maui_The_Magnificent@proton.me
+ * Project repository: https://github.com/Mauitron/StygianSift.git
+ */
+
+// Key sequences: how held keys turn into actions, and how they are written.
+
+use std::time::Duration;
+use stygian_sift::the_core::*;
+
+fn keys(text: &str) -> KeySequence {
+    KeySequence::parse(text).unwrap()
+}
+
+fn press(sequencer: &mut KeySequencer, config: &Config, text: &str) -> Vec<Action> {
+    keys(text)
+        .keys()
+        .iter()
+        .flat_map(|key| sequencer.feed(config, *key))
+        .collect()
+}
+
+fn bound(bindings: &[(&str, Action)]) -> Config {
+    let mut config = Config::new();
+    config.keybindings = Some(
+        bindings
+            .iter()
+            .map(|(text, action)| (keys(text), action.clone()))
+            .collect(),
+    );
+    config
+}
+
+#[test]
+fn sequences_are_written_the_way_they_are_read() {
+    for text in ["g g", "Ctrl+x Shift+K F5", "<leader> [SPACE]", "y \"", "q"] {
+        assert_eq!(keys(text).to_string(), text);
+    }
+    assert_eq!(keys("<space>  f").to_string(), "[SPACE] f");
+    assert_eq!(keys("y \"").keys().len(), 2);
+    assert_eq!(KeySequence::parse("g Bogus"), None);
+    assert_eq!(KeySequence::parse("  "), None);
+}
+
+#[test]
+fn the_defaults_keep_gg_and_ge() {
+    let config = Config::new();
+    let mut sequencer = KeySequencer::new();
+    assert_eq!(press(&mut sequencer, &config, "g"), []);
+    assert!(sequencer.is_pending());
+    assert_eq!(press(&mut sequencer, &config, "g"), [Action::GoToTop]);
+    assert!(!sequencer.is_pending());
+    assert_eq!(press(&mut sequencer, &config, "g e"), [Action::GoToBottom]);
+    assert_eq!(press(&mut sequencer, &config, "g h"), [Action::GoHome]);
+    assert_eq!(press(&mut sequencer, &config, "y p"), [Action::YankPath]);
+    // Neither starts anything on its own.
+    assert_eq!(press(&mut sequencer, &config, "e"), []);
+    assert_eq!(sequencer.flush(&config), []);
+}
+
+#[test]
+fn a_key_that_goes_nowhere_is_tried_on_its_own() {
+    let config = Config::new();
+    let mut sequencer = KeySequencer::new();
+    assert_eq!(press(&mut sequencer, &config, "g j"), [Action::MoveDown]);
+    assert_eq!(press(&mut sequencer, &config, "g y p"), [Action::YankPath]);
+    assert_eq!(press(&mut sequencer, &config, "g y"), []);
+    assert_eq!(sequencer.pending(), keys("y").keys());
+}
+
+#[test]
+fn a_binding_that_starts_a_longer_one_waits_for_the_timeout() {
+    let config = bound(&[
+        ("d", Action::Duplicate),
+        ("d d", Action::Murder),
+        ("d x y", Action::ExtractArchive),
+        ("j", Action::MoveDown),
+    ]);
+    let mut sequencer = KeySequencer::new();
+    assert_eq!(press(&mut sequencer, &config, "d"), []);
+    assert!(sequencer.time_left(&config).unwrap() <= config.key_timeout());
+    assert_eq!(sequencer.flush(&config), [Action::Duplicate]);
+    assert_eq!(sequencer.time_left(&config), None);
+
+    assert_eq!(press(&mut sequencer, &config, "d d"), [Action::Murder]);
+    assert_eq!(
+        press(&mut sequencer, &config, "d j"),
+        [Action::Duplicate, Action::MoveDown]
+    );
+    // The longest binding typed so far runs, the rest is tried again.
+    assert_eq!(press(&mut sequencer, &config, "d x d"), [Action::Duplicate]);
+    assert_eq!(sequencer.flush(&config), [Action::Duplicate]);
+
+    let mut config = config;
+    config.key_timeout = 0;
+    press(&mut sequencer, &config, "d");
+    assert_eq!(sequencer.time_left(&config), Some(Duration::ZERO));
+}
+
+#[test]
+fn leader_stands_for_the_leader_key() {
+    let mut config = bound(&[("<leader> f", Action::SearchFiles)]);
+    let mut sequencer = KeySequencer::new();
+    assert_eq!(
+        press(&mut sequencer, &config, "\\ f"),
+        [Action::SearchFiles]
+    );
+
+    config.set_value("leader_key", "[SPACE]").unwrap();
+    assert_eq!(press(&mut sequencer, &config, "\\ f"), []);
+    assert_eq!(
+        press(&mut sequencer, &config, "[SPACE] f"),
+        [Action::SearchFiles]
+    );
+    assert_eq!(config.get_value("leader_key").as_deref(), Some("[SPACE]"));
+    assert!(config.set_value("leader_key", "<leader>").is_err());
+    assert!(config.set_value("leader_key", "Bogus").is_err());
+}
+
+#[test]
+fn held_keys_show_where_they_can_go() {
+    let config = Config::new();
+    let mut sequencer = KeySequencer::new();
+    press(&mut sequencer, &config, "g");
+    assert_eq!(
+        sequencer.hint(&config),
+        "g ...  e GoToBottom  g GoToTop  h GoHome"
+    );
+    sequencer.clear();
+    assert!(!sequencer.is_pending());
+}
+
+#[test]
+fn sequences_load_from_the_config_file() {
+    let text = "\
+key_timeout = 800
+leader_key = \",\"
+
+[keybindings]
+\"<leader> g\" = \"GitMenu\"
+\"g g\" = \"GoToTop\"
+\"g Bogus\" = \"GoToBottom\"
+";
+    let (config, errors) = Config::from_toml(text);
+    let errors: Vec<String> = errors.iter().map(|error| error.to_string()).collect();
+    assert_eq!(errors, ["line 7: unknown key 'g Bogus'"]);
+    assert_eq!(config.key_timeout(), Duration::from_millis(800));
+    assert_eq!(config.get_action(&keys("g g")), Some(&Action::GoToTop));
+    let mut sequencer = KeySequencer::new();
+    assert_eq!(press(&mut sequencer, &config, ", g"), [Action::GitMenu]);
+}
+
+#[test]
+fn keys_that_get_in_each_others_way_are_found() {
+    assert_eq!(Config::new().key_conflicts(), []);
+
+    let mut config = bound(&[
+        ("g", Action::Copy),
+        ("g g", Action::GoToTop),
+        ("<leader> x", Action::Help),
+        ("q", Action::Quit),
+    ]);
+    config.leader_key = Keystroke::parse("q");
+    let conflicts: Vec<String> = config
+        .key_conflicts()
+        .iter()
+        .map(|conflict| conflict.to_string())
+        .collect();
+    assert_eq!(
+        conflicts,
+        [
+            "'q' (Quit) waits for the timeout, '<leader> x' (Help) starts with it",
+            "'g' (Copy) waits for the timeout, 'g g' (GoToTop) starts with it",
+        ]
+    );
+
+    let config = Config::new();
+    let warnings = |text: &str, action: Action| -> Vec<String> {
+        config
+            .conflicts_with(&keys(text), &action)
+            .iter()
+            .map(|conflict| conflict.to_string())
+            .collect()
+    };
+    assert_eq!(
+        warnings("p", Action::Paste),
+        ["'p' is bound to Copy already"]
+    );
+    assert_eq!(warnings("p", Action::Copy), Vec::<String>::new());
+    assert_eq!(
+        warnings("g", Action::Help),
+        [
+            "'g' (Help) waits for the timeout, 'g e' (GoToBottom) starts with it",
+            "'g' (Help) waits for the timeout, 'g g' (GoToTop) starts with it",
+            "'g' (Help) waits for the timeout, 'g h' (GoHome) starts with it",
+        ]
+    );
+}
+
+#[test]
+fn an_action_keeps_its_other_keys() {
+    let mut config = Config::new();
+    assert_eq!(
+        config.set_keybinding(keys("Ctrl+y"), Action::YankPath),
+        None
+    );
+    assert_eq!(
+        config.keys_for(&Action::YankPath),
+        [&keys("Ctrl+y"), &keys("y p")]
+    );
+    assert_eq!(
+        config.set_keybinding(keys("p"), Action::Paste),
+        Some(Action::Copy)
+    );
+
+    assert!(config.unbound_actions().contains(&&Action::Copy));
+    assert!(config.unbound_actions().contains(&&Action::VisualMode));
+    assert!(!config.unbound_actions().contains(&&Action::Paste));
+    config.modal_input = true;
+    assert!(!config.unbound_actions().contains(&&Action::VisualMode));
+}
+
+#[test]
+fn shadowed_keys_are_reported_when_the_config_loads() {
+    let text = "\
+[keybindings]
+g = \"Copy\"
+\"[SPACE]\" = \"TogglePreview\"
+\"g g\" = \"GoToTop\"
+\"<space>\" = \"Help\"
+
+[keybindings.visual]
+y = \"Copy\"
+\"y y\" = \"YankPath\"
+";
+    let (config, errors) = Config::from_toml(text);
+    let errors: Vec<String> = errors.iter().map(|error| error.to_string()).collect();
+    assert_eq!(
+        errors,
+        [
+            "line 4: 'g' (Copy) waits for the timeout, 'g g' (GoToTop) starts with it",
+            "line 5: '[SPACE]' is bound on line 3 already",
+            "line 9: 'y' (Copy) waits for the timeout, 'y y' (YankPath) starts with it",
+        ]
+    );
+    assert_eq!(
+        config.get_action(&keys("[SPACE]")),
+        Some(&Action::TogglePreview)
+    );
+}
+
+#[test]
+fn binding_sets_export_and_import() {
+    let mut config = Config::new();
+    config.set_keybinding(keys("Ctrl+y"), Action::YankPath);
+    config.remove_keybinding(&keys("k"));
+    let exported = config.export_keybindings();
+    assert!(exported.starts_with("[keybindings]\n"));
+    assert!(exported.contains("\nDown = \"MoveDown\"\nj = \"MoveDown\"\n"));
+    assert!(exported.contains("\n[keybindings.visual]\n"));
+
+    let mut imported = Config::new();
+    assert_eq!(imported.import_keybindings(&exported), []);
+    // The same bindings are written the same way.
+    assert_eq!(imported.export_keybindings(), exported);
+    assert_eq!(imported.keybindings, config.keybindings);
+    assert_eq!(imported.mode_keybindings, config.mode_keybindings);
+
+    // A set with mistakes leaves the bindings alone.
+    let errors = imported.import_keybindings("show_hidden = true\n[keybindings]\nq = \"Quit\"\n");
+    let errors: Vec<String> = errors.iter().map(|error| error.to_string()).collect();
+    assert_eq!(errors, ["line 1: 'show_hidden' is not a keybinding"]);
+    assert_eq!(imported.keybindings, config.keybindings);
+
+    // Only the tables in the set are replaced.
+    assert_eq!(
+        imported.import_keybindings("[keybindings.visual]\nx = \"Copy\"\n"),
+        []
+    );
+    assert_eq!(imported.keybindings, config.keybindings);
+    assert_eq!(imported.mode_keybindings[&Mode::Visual].len(), 1);
+}
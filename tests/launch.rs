@@ -0,0 +1,185 @@
+/*
+* Stygian Sift - A Terminal-based File Manager
+ * Copyright (C) 2024 Maui The Magnificent (Charon)
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published
+ * by the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+ * Contact: Maui_The_Magnificent@proton.me
+ * Project repository: https://github.com/Mauitron/StygianSift.git
+ */
+
+// Command line options of the browser, applied to a fresh state without
+// opening a terminal.
+#![cfg(feature = "tui")]
+
+use std::{
+    env,
+    fs::{self, File},
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+use stygian_sift::the_tome::*;
+
+mod common;
+use common::TempDir;
+
+fn args(line: &str) -> Vec<String> {
+    line.split_whitespace().map(String::from).collect()
+}
+
+fn scratch(name: &str) -> TempDir {
+    let dir = TempDir::new(&format!("launch-{}", name));
+    fs::create_dir(dir.join("docs")).unwrap();
+    fs::write(dir.join("a.txt"), "a").unwrap();
+    fs::write(dir.join("b.txt"), "bb").unwrap();
+    fs::write(dir.join("c.txt"), "ccc").unwrap();
+    dir
+}
+
+fn app_in(dir: &Path) -> AppState {
+    let mut config = Config::new();
+    config.home_folder = Some(dir.to_path_buf());
+    AppState::with_config(config).unwrap()
+}
+
+#[test]
+fn parses_every_option() {
+    let options = LaunchOptions::parse(&args(
+        "--sort size-desc --no-mouse --simple-borders --search notes \
+         --choose-files - --cwd-file /tmp/cwd --config /tmp/conf docs",
+    ))
+    .unwrap();
+    assert_eq!(options.sort, Some(SortOrder::SizeDesc));
+    assert!(options.no_mouse && options.simple_borders);
+    assert_eq!(options.search.as_deref(), Some("notes"));
+    assert_eq!(options.chooser.as_ref().unwrap().mode, ChooseMode::Files);
+    assert_eq!(options.cwd_file, Some(PathBuf::from("/tmp/cwd")));
+    assert_eq!(options.config_path, Some(PathBuf::from("/tmp/conf")));
+    assert_eq!(options.start_path, Some(PathBuf::from("docs")));
+    assert!(options.writes_to_stdout());
+    assert!(LaunchOptions::parse(&args("--cwd-file -")).unwrap().writes_to_stdout());
+    assert!(!LaunchOptions::parse(&args("--choose-dir /tmp/out --cwd-file /tmp/cwd"))
+        .unwrap()
+        .writes_to_stdout());
+
+    assert!(LaunchOptions::parse(&args("--help")).unwrap().help);
+    assert!(LaunchOptions::parse(&args("--sort sideways")).is_err());
+    assert!(LaunchOptions::parse(&args("--search")).is_err());
+    assert!(LaunchOptions::parse(&args("--frobnicate")).is_err());
+    assert!(LaunchOptions::parse(&args("one two")).is_err());
+}
+
+#[test]
+fn a_file_opens_selected_in_its_directory() {
+    let dir = scratch("file");
+    let mut app = app_in(&env::temp_dir());
+    let options = LaunchOptions::parse(&[dir.join("b.txt").display().to_string()]).unwrap();
+    options.apply(&mut app).unwrap();
+    assert_eq!(app.current_dir, *dir);
+    // docs, a.txt, b.txt
+    assert_eq!(app.selected_index, 2);
+
+    let options = LaunchOptions::parse(&[dir.join("docs").display().to_string()]).unwrap();
+    options.apply(&mut app).unwrap();
+    assert_eq!(app.current_dir, dir.join("docs"));
+    assert_eq!(app.selected_index, 0);
+
+    let missing = LaunchOptions::parse(&[dir.join("z.txt").display().to_string()]).unwrap();
+    assert!(missing.apply(&mut app).is_err());
+}
+
+#[test]
+fn overrides_last_for_the_session_only() {
+    let dir = scratch("session");
+    let mut app = app_in(&dir);
+    let options = LaunchOptions::parse(&args("--sort size-desc --simple-borders --no-mouse")).unwrap();
+    options.apply(&mut app).unwrap();
+    assert_eq!(app.config.default_sort, SortOrder::SizeDesc);
+    assert!(app.config.draw_simple_borders);
+    assert!(!app.mouse_enabled);
+
+    let saved = dir.join(".maui");
+    app.config.save_to(&saved).unwrap();
+    let reloaded = Config::load_from(&saved).unwrap();
+    assert_eq!(reloaded.default_sort, SortOrder::TypeAsc);
+    assert!(!reloaded.draw_simple_borders);
+}
+
+#[test]
+fn a_setting_changed_after_its_override_is_saved() {
+    let dir = scratch("changed");
+    let mut app = app_in(&dir);
+    let options = LaunchOptions::parse(&args("--sort size-desc --simple-borders")).unwrap();
+    options.apply(&mut app).unwrap();
+    app.config.set_value("default_sort", "NameDesc").unwrap();
+
+    let saved = dir.join(".maui");
+    app.config.save_to(&saved).unwrap();
+    let reloaded = Config::load_from(&saved).unwrap();
+    assert_eq!(reloaded.default_sort, SortOrder::NameDesc);
+    assert!(!reloaded.draw_simple_borders);
+}
+
+#[test]
+fn outside_edits_to_the_config_are_reloaded() {
+    let dir = scratch("reload");
+    let path = dir.join(".maui");
+    let mut config = Config::new();
+    config.home_folder = Some(dir.to_path_buf());
+    config.profiles.insert(
+        "work".to_string(),
+        Profile {
+            theme: Some("plain".to_string()),
+            ..Profile::default()
+        },
+    );
+    config.save_to(&path).unwrap();
+
+    let options = LaunchOptions::parse(&[
+        "--config".to_string(),
+        path.display().to_string(),
+        "--profile".to_string(),
+        "work".to_string(),
+        "--sort".to_string(),
+        "size-desc".to_string(),
+    ])
+    .unwrap();
+    let mut app = options.open().unwrap();
+    assert_eq!(app.config.active_profile.as_deref(), Some("work"));
+    assert_eq!(app.config.theme, "plain");
+    assert!(!app.reload_changed_config(&dir).unwrap());
+
+    // Our own saving is not an outside edit.
+    app.save_config().unwrap();
+    assert!(!app.reload_changed_config(&dir).unwrap());
+    assert!(app.config_messages.is_empty());
+
+    config.lines_shown = 12;
+    config.profiles.get_mut("work").unwrap().theme = Some("emoji".to_string());
+    config.save_to(&path).unwrap();
+    let later = SystemTime::now() + Duration::from_secs(10);
+    File::options().write(true).open(&path).unwrap().set_modified(later).unwrap();
+    assert!(app.reload_changed_config(&dir).unwrap());
+    assert_eq!(app.config.lines_shown, 12);
+    assert_eq!(app.lines, 12);
+    assert_eq!(app.config.active_profile.as_deref(), Some("work"));
+    assert_eq!(app.config.theme, "emoji");
+    assert_eq!(app.config.default_sort, SortOrder::SizeDesc);
+    assert_eq!(app.config_messages, [format!("Reloaded {}", path.display())]);
+
+    // Still only for the session.
+    let saved = Config::load_from(&path).unwrap();
+    assert_eq!(saved.default_sort, SortOrder::TypeAsc);
+    assert_eq!(saved.profile, "");
+}
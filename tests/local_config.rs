@@ -0,0 +1,176 @@
+/*
+* Stygian Sift - A Terminal-based File Manager
+ * Copyright (C) 2024 Maui The Magnificent (Charon)
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published
+ * by the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+ * Contact: Maui_The_Magnificent@proton.me
+ * Project repository: https://github.com/Mauitron/StygianSift.git
+ */
+
+// .stygian files: merged in for their subtree, taken off again when leaving
+// it or saving.
+
+use std::{fs, path::Path};
+use stygian_sift::the_core::*;
+
+mod common;
+use common::TempDir;
+
+struct Tree {
+    root: TempDir,
+}
+
+impl Tree {
+    // root/repo/src with a .stygian in repo, and root/downloads with its own.
+    fn new(name: &str) -> Self {
+        let root = TempDir::new(&format!("local-{}", name));
+        fs::create_dir_all(root.join("repo/src")).unwrap();
+        fs::create_dir_all(root.join("downloads")).unwrap();
+        fs::write(
+            root.join("repo/.stygian"),
+            "\
+default_sort = \"type\"
+ignore_patterns = [\"target\", \"*.o\"]
+
+[color_rules.red]
+allow_delete = false
+allow_move = false
+
+[commands]
+build = \"cargo build\"
+",
+        )
+        .unwrap();
+        fs::write(
+            root.join("downloads/.stygian"),
+            "default_sort = \"date-desc\"\nshow_hidden = false\n",
+        )
+        .unwrap();
+        Tree { root }
+    }
+}
+
+fn global() -> Config {
+    let mut config = Config::new();
+    config.default_sort = SortOrder::NameAsc;
+    config.ignore_patterns = vec!["*.bak".to_string()];
+    config
+}
+
+#[test]
+fn settings_follow_the_current_directory() {
+    let tree = Tree::new("follow");
+    let mut config = global();
+
+    assert_eq!(
+        config.enter_dir(&tree.root.join("repo/src")),
+        Vec::<String>::new()
+    );
+    assert_eq!(config.default_sort, SortOrder::TypeAsc);
+    assert!(!config.color_rules[&MarkerColor::Red].allow_delete);
+    assert!(!config.is_listed(Path::new("/x/target")));
+    assert!(!config.is_listed(Path::new("/x/main.o")));
+    assert!(!config.is_listed(Path::new("/x/old.bak")));
+    assert!(config.is_listed(Path::new("/x/.git")));
+    assert_eq!(
+        config.custom_command("build --release"),
+        Some("cargo build --release".to_string())
+    );
+
+    config.enter_dir(&tree.root.join("downloads"));
+    assert_eq!(config.default_sort, SortOrder::DateModifiedDesc);
+    assert!(!config.is_listed(Path::new("/x/.git")));
+    assert!(config.is_listed(Path::new("/x/target")));
+    assert!(config.color_rules.is_empty());
+    assert_eq!(config.custom_command("build"), None);
+
+    config.enter_dir(&tree.root);
+    assert_eq!(config, global());
+}
+
+#[test]
+fn the_saved_config_keeps_global_values_and_later_changes() {
+    let tree = Tree::new("save");
+    let mut config = global();
+    config.enter_dir(&tree.root.join("repo"));
+    config.set_value("search_depth_limit", "9").unwrap();
+    config
+        .color_rules
+        .insert(MarkerColor::Blue, ColorRule::default());
+
+    let path = tree.root.join(".maui");
+    config.save_to(&path).unwrap();
+    let saved = Config::load_from(&path).unwrap();
+    assert_eq!(saved.default_sort, SortOrder::NameAsc);
+    assert_eq!(saved.ignore_patterns, ["*.bak"]);
+    assert_eq!(saved.search_depth_limit, 9);
+    assert_eq!(
+        saved.color_rules.keys().collect::<Vec<_>>(),
+        [&MarkerColor::Blue]
+    );
+    assert!(saved.custom_commands.is_empty());
+    // Still in effect after saving.
+    assert_eq!(config.default_sort, SortOrder::TypeAsc);
+}
+
+#[test]
+fn inner_files_win_and_mistakes_are_reported() {
+    let tree = Tree::new("nested");
+    fs::write(
+        tree.root.join("repo/src/.stygian"),
+        "default_sort = \"size\"\nignore_patterns = [\"*.rlib\"]\ntheme = \"dark\"\nshow_hidden = 1\n",
+    )
+    .unwrap();
+    let mut config = global();
+
+    let messages = config.enter_dir(&tree.root.join("repo/src"));
+    let file = tree.root.join("repo/src/.stygian");
+    assert_eq!(
+        messages,
+        [
+            format!(
+                "{}: line 3: 'theme' can only be set in the main config",
+                file.display()
+            ),
+            format!(
+                "{}: line 4: show_hidden should be a boolean, not an integer",
+                file.display()
+            ),
+        ]
+    );
+    assert_eq!(config.default_sort, SortOrder::SizeAsc);
+    assert!(config.show_hidden);
+    assert_eq!(config.ignore_patterns, ["*.bak", "target", "*.o", "*.rlib"]);
+    assert!(!config.color_rules[&MarkerColor::Red].allow_delete);
+
+    // Moving around below the same files changes nothing and says nothing.
+    assert_eq!(
+        config.enter_dir(&tree.root.join("repo/src")),
+        Vec::<String>::new()
+    );
+    config.enter_dir(&tree.root.join("repo"));
+    assert_eq!(config.default_sort, SortOrder::TypeAsc);
+}
+
+#[test]
+fn glob_patterns() {
+    assert!(glob_match("*.o", "main.o"));
+    assert!(glob_match("*", ""));
+    assert!(glob_match("a*b*c", "aXXbYYbc"));
+    assert!(glob_match("file?.txt", "file1.txt"));
+    assert!(!glob_match("file?.txt", "file.txt"));
+    assert!(!glob_match("*.o", "main.rs"));
+    assert!(!glob_match("target", "target2"));
+}
@@ -0,0 +1,165 @@
+/*
+* Stygian Sift - A Terminal-based File Manager
+ * Copyright (C) 2024 Maui The Magnificent (Charon)
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published
+ * by the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+ * Contact: Maui_The_Magnificent@proton.me
+ * Project repository: https://github.com/Mauitron/StygianSift.git
+ */
+
+// Macros: how their input is written, how they are kept in the config and
+// what playing one on a selection takes.
+
+use std::path::PathBuf;
+use stygian_sift::the_core::*;
+
+fn step(action: Action, args: &[&str], input: &str) -> MacroStep {
+    MacroStep {
+        call: CommandCall {
+            action,
+            args: args.iter().map(|arg| arg.to_string()).collect(),
+        },
+        input: parse_input(input).unwrap(),
+    }
+}
+
+#[test]
+fn input_is_written_the_way_it_is_typed() {
+    let keys = parse_input("Old <lt>1><Enter><Ctrl+c>y").unwrap();
+    assert_eq!(keys.len(), 10);
+    assert_eq!(keys[0], Keystroke::new(Key::Char('O'), Modifiers::SHIFT));
+    assert_eq!(keys[4], Keystroke::new(Key::Char('<'), Modifiers::NONE));
+    assert_eq!(keys[7], Keystroke::new(Key::Enter, Modifiers::NONE));
+    assert_eq!(keys[8], Keystroke::parse("Ctrl+c"));
+    assert_eq!(input_text(&keys), "Old <lt>1><Enter><Ctrl+c>y");
+    assert_eq!(parse_input("<Alt+>>"), Ok(vec![Keystroke::parse("Alt+>")]));
+
+    assert_eq!(
+        parse_input("<Enter"),
+        Err("'<Enter' is not closed with '>'".to_string())
+    );
+    assert_eq!(
+        parse_input("<Bogus>"),
+        Err("unknown key '<Bogus>'".to_string())
+    );
+    assert_eq!(
+        parse_input("<leader>"),
+        Err("unknown key '<leader>'".to_string())
+    );
+}
+
+#[test]
+fn macros_are_kept_in_the_config() {
+    let mut config = Config::new();
+    config.macros.insert(
+        "tidy up".to_string(),
+        vec![
+            step(Action::Rename, &[], "old<Enter>"),
+            step(Action::CycleItemColor, &[], ""),
+            step(Action::GoHome, &["~/archive"], ""),
+        ],
+    );
+    let text = config.to_toml();
+    assert!(
+        text.contains("\n[macros.\"tidy up\".0]\naction = \"Rename\"\ninput = \"old<Enter>\"\n")
+    );
+    let (loaded, errors) = Config::from_toml(&text);
+    assert_eq!(errors, []);
+    assert_eq!(loaded.macros, config.macros);
+
+    let text = "\
+[macros.tidy.1]
+action = \"Copy\"
+
+[macros.tidy.0]
+input = \"x\"
+
+[macros.tidy.first]
+action = \"Copy\"
+
+[macros.tidy.2]
+action = \"Frobnicate\"
+input = \"<Nope>\"
+";
+    let (loaded, errors) = Config::from_toml(text);
+    let errors: Vec<String> = errors.iter().map(|error| error.to_string()).collect();
+    assert_eq!(
+        errors,
+        [
+            "line 5: step 0 of macro 'tidy' has no action",
+            "line 8: 'first' is not a step number",
+            "line 11: unknown action 'Frobnicate'",
+            "line 12: unknown key '<Nope>'",
+        ]
+    );
+    assert_eq!(loaded.macros["tidy"], [step(Action::Copy, &[], "")]);
+}
+
+#[test]
+fn a_recording_gives_each_step_what_was_typed_meanwhile() {
+    let mut recorder = MacroRecorder::new("tidy");
+    recorder.add_input(parse_input("q").unwrap());
+    recorder.begin(CommandCall::new(Action::Rename));
+    recorder.add_input(parse_input("old").unwrap());
+    recorder.add_input(parse_input("<Enter>").unwrap());
+    recorder.begin(CommandCall::new(Action::MoveDown));
+    assert_eq!(
+        recorder.steps,
+        [
+            step(Action::Rename, &[], "old<Enter>"),
+            step(Action::MoveDown, &[], ""),
+        ]
+    );
+    assert!(!MacroStep::records(&Action::PlayMacro));
+    assert!(MacroStep::records(&Action::Rename));
+    assert_eq!(
+        parse_command_line("play tidy up"),
+        Ok(vec![CommandCall {
+            action: Action::PlayMacro,
+            args: vec!["tidy up".to_string()],
+        }])
+    );
+}
+
+#[test]
+fn a_macro_plays_once_on_each_target() {
+    let steps = [
+        step(Action::Rename, &[], "old<Enter>"),
+        step(Action::Copy, &[], ""),
+    ];
+    let plan = replay_plan(&steps, &[]);
+    assert_eq!(
+        plan,
+        [
+            ReplayStep::Run(steps[0].clone()),
+            ReplayStep::Run(steps[1].clone())
+        ]
+    );
+
+    let targets = [PathBuf::from("a"), PathBuf::from("b")];
+    let mut plan = replay_plan(&steps, &targets);
+    assert_eq!(plan.len(), 6);
+    assert_eq!(
+        plan.pop_front(),
+        Some(ReplayStep::Select(PathBuf::from("a")))
+    );
+    // When "a" is gone its steps are left out.
+    skip_target(&mut plan);
+    assert_eq!(
+        plan.pop_front(),
+        Some(ReplayStep::Select(PathBuf::from("b")))
+    );
+    assert_eq!(plan.len(), 2);
+}
@@ -0,0 +1,87 @@
+/*
+* Stygian Sift - A Terminal-based File Manager
+ * Copyright (C) 2024 Maui The Magnificent (Charon)
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published
+ * by the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+ * Contact: Maui_The_Magnificent@proton.me
+ * Project repository: https://github.com/Mauitron/StygianSift.git
+ */
+
+// Reading executables and other binaries by their contents.
+
+use std::fs;
+use stygian_sift::the_core::*;
+
+mod common;
+use common::TempDir;
+
+// A 64-bit little endian x86-64 shared object with one loaded segment, a
+// dynamic section and nothing else.
+fn elf(load_vaddr: u64, load_size: u64, strtab: u64) -> Vec<u8> {
+    let mut data = vec![0u8; 64];
+    data[..7].copy_from_slice(b"\x7FELF\x02\x01\x01");
+    data[16..18].copy_from_slice(&3u16.to_le_bytes());
+    data[18..20].copy_from_slice(&62u16.to_le_bytes());
+    data[32..40].copy_from_slice(&64u64.to_le_bytes());
+    data[54..56].copy_from_slice(&56u16.to_le_bytes());
+    data[56..58].copy_from_slice(&2u16.to_le_bytes());
+
+    let segment = |kind: u32, offset: u64, vaddr: u64, size: u64| {
+        let mut header = vec![0u8; 56];
+        header[..4].copy_from_slice(&kind.to_le_bytes());
+        header[8..16].copy_from_slice(&offset.to_le_bytes());
+        header[16..24].copy_from_slice(&vaddr.to_le_bytes());
+        header[32..40].copy_from_slice(&size.to_le_bytes());
+        header
+    };
+    data.extend(segment(1, 0, load_vaddr, load_size));
+    data.extend(segment(2, 176, 0, 32));
+    for (tag, value) in [(5u64, strtab), (0, 0)] {
+        data.extend(tag.to_le_bytes());
+        data.extend(value.to_le_bytes());
+    }
+    data
+}
+
+#[test]
+fn an_elf_header_is_summarised() {
+    let dir = TempDir::new("magic-elf");
+    let path = dir.join("libplain.so");
+    fs::write(&path, elf(0, 208, 0)).unwrap();
+
+    let info = parse_elf(&path).unwrap();
+    assert_eq!((info.class, info.little_endian), (64, true));
+    assert_eq!(info.machine, "x86-64");
+    assert_eq!(info.kind, "shared object");
+    assert_eq!(info.interpreter, None);
+}
+
+#[test]
+fn a_segment_past_the_end_of_memory_holds_nothing() {
+    let dir = TempDir::new("magic-overflow");
+    let path = dir.join("libwrapped.so");
+    fs::write(&path, elf(u64::MAX - 4, 16, u64::MAX - 1)).unwrap();
+
+    let info = parse_elf(&path).unwrap();
+    assert!(info.needed.is_empty());
+}
+
+#[test]
+fn a_truncated_elf_is_an_error() {
+    let dir = TempDir::new("magic-truncated");
+    let path = dir.join("libshort.so");
+    fs::write(&path, &elf(0, 208, 0)[..40]).unwrap();
+    assert!(parse_elf(&path).is_err());
+}
@@ -0,0 +1,208 @@
+/*
+* Stygian Sift - A Terminal-based File Manager
+ * Copyright (C) 2024 Maui The Magnificent (Charon)
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published
+ * by the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+ * Contact: Maui_The_Magnificent@proton.me
+ * Project repository: https://github.com/Mauitron/StygianSift.git
+ */
+
+// The optional modes: what each one binds, where actions leave them, and
+// how the ":" line reads.
+
+use stygian_sift::the_core::*;
+
+fn keys(text: &str) -> KeySequence {
+    KeySequence::parse(text).unwrap()
+}
+
+fn action_in(config: &Config, mode: Mode, text: &str) -> Option<Action> {
+    config
+        .key_trie(mode)
+        .find(keys(text).keys())
+        .and_then(KeyTrie::action)
+        .cloned()
+}
+
+fn call(action: Action, args: &[&str]) -> CommandCall {
+    CommandCall {
+        action,
+        args: args.iter().map(|arg| arg.to_string()).collect(),
+    }
+}
+
+#[test]
+fn modes_only_bind_with_modal_input_on() {
+    let mut config = Config::new();
+    assert_eq!(
+        action_in(&config, Mode::Normal, ":"),
+        Some(Action::TerminalCommand)
+    );
+    assert_eq!(action_in(&config, Mode::Visual, "v"), None);
+
+    config.modal_input = true;
+    assert_eq!(
+        action_in(&config, Mode::Normal, ":"),
+        Some(Action::CommandLine)
+    );
+    assert_eq!(
+        action_in(&config, Mode::Normal, "v"),
+        Some(Action::VisualMode)
+    );
+    assert_eq!(
+        action_in(&config, Mode::Normal, "j"),
+        Some(Action::MoveDown)
+    );
+    assert_eq!(
+        action_in(&config, Mode::Visual, "Esc"),
+        Some(Action::NormalMode)
+    );
+    assert_eq!(action_in(&config, Mode::Visual, "y"), Some(Action::Copy));
+    assert_eq!(
+        action_in(&config, Mode::Visual, "g g"),
+        Some(Action::GoToTop)
+    );
+    // Without waiting to see if "y p" comes.
+    assert!(!config
+        .key_trie(Mode::Visual)
+        .find(keys("y").keys())
+        .unwrap()
+        .has_more());
+    assert_eq!(
+        action_in(&config, Mode::Normal, "y p"),
+        Some(Action::YankPath)
+    );
+    // The command line is typed into, only its own keys do something.
+    assert_eq!(action_in(&config, Mode::Command, "j"), None);
+    assert_eq!(
+        action_in(&config, Mode::Command, "Enter"),
+        Some(Action::Enter)
+    );
+}
+
+#[test]
+fn the_sequencer_follows_its_mode() {
+    let mut config = Config::new();
+    config.modal_input = true;
+    let mut sequencer = KeySequencer::new();
+    assert_eq!(
+        sequencer.feed(&config, Keystroke::parse("v")),
+        [Action::VisualMode]
+    );
+    sequencer.set_mode(Mode::Visual);
+    assert_eq!(sequencer.feed(&config, Keystroke::parse("g")), []);
+    // Held keys do not carry over into another mode.
+    sequencer.set_mode(Mode::Normal);
+    assert!(!sequencer.is_pending());
+    assert_eq!(sequencer.feed(&config, Keystroke::parse("y")), []);
+    sequencer.set_mode(Mode::Visual);
+    assert_eq!(
+        sequencer.feed(&config, Keystroke::parse("v")),
+        [Action::NormalMode]
+    );
+}
+
+#[test]
+fn visual_mode_lasts_while_the_cursor_moves() {
+    assert_eq!(Mode::Normal.after(&Action::VisualMode), Mode::Visual);
+    assert_eq!(Mode::Normal.after(&Action::MoveDown), Mode::Normal);
+    for action in [Action::MoveDown, Action::GoToBottom, Action::MultiSelectUp] {
+        assert_eq!(Mode::Visual.after(&action), Mode::Visual);
+    }
+    for action in [
+        Action::Copy,
+        Action::MoveRight,
+        Action::NormalMode,
+        Action::CommandLine,
+    ] {
+        assert_eq!(Mode::Visual.after(&action), Mode::Normal);
+    }
+    assert_eq!(Mode::parse("visual"), Some(Mode::Visual));
+    assert_eq!(Mode::parse("insert"), None);
+}
+
+#[test]
+fn command_lines_turn_into_actions() {
+    assert_eq!(parse_command_line("q"), Ok(vec![call(Action::Quit, &[])]));
+    assert_eq!(
+        parse_command_line("12"),
+        Ok(vec![call(Action::GoToTop, &["12"])])
+    );
+    assert_eq!(
+        parse_command_line("movedown 3; sort size-desc ;yank"),
+        Ok(vec![
+            call(Action::MoveDown, &["3"]),
+            call(Action::SortCycleForward, &["size-desc"]),
+            call(Action::YankPath, &[]),
+        ])
+    );
+    assert_eq!(
+        parse_command_line("cd \"my files\"/old;search two words"),
+        Ok(vec![
+            call(Action::GoHome, &["my files/old"]),
+            call(Action::SearchFiles, &["two words"]),
+        ])
+    );
+    assert_eq!(
+        parse_command_line(r"cd a\;b"),
+        Ok(vec![call(Action::GoHome, &["a;b"])])
+    );
+    assert_eq!(parse_command_line("  ; "), Ok(vec![]));
+
+    let calls = parse_command_line("MoveUp 4").unwrap();
+    assert_eq!(calls[0].count(), 4);
+    assert_eq!(CommandCall::new(Action::MoveUp).count(), 1);
+}
+
+#[test]
+fn command_line_mistakes_are_explained() {
+    let error = |line: &str| parse_command_line(line).unwrap_err();
+    assert_eq!(error("frobnicate"), "unknown command 'frobnicate'");
+    assert_eq!(error("copy now"), "Copy takes no arguments");
+    assert_eq!(error("MoveDown lots"), "MoveDown takes a count, not 'lots'");
+    assert_eq!(error("top 0"), "GoToTop takes a line number, not '0'");
+    assert_eq!(error("MoveDown 1 2"), "MoveDown takes only a count");
+    assert_eq!(error("sort sideways"), "unknown sort order 'sideways'");
+    assert_eq!(error("cd \"open"), "a quote is not closed");
+}
+
+#[test]
+fn mode_bindings_load_from_the_config_file() {
+    let text = "\
+modal_input = true
+
+[keybindings.visual]
+d = \"Delete\"
+\"Ctrl+c\" = \"NormalMode\"
+
+[keybindings.insert]
+i = \"Quit\"
+";
+    let (config, errors) = Config::from_toml(text);
+    let errors: Vec<String> = errors.iter().map(|error| error.to_string()).collect();
+    assert_eq!(errors, ["line 8: unknown mode 'insert'"]);
+    assert!(config.modal_input);
+    // The table replaces the visual defaults, the other modes keep theirs.
+    assert_eq!(config.mode_keybindings[&Mode::Visual].len(), 2);
+    assert_eq!(
+        action_in(&config, Mode::Visual, "Ctrl+c"),
+        Some(Action::NormalMode)
+    );
+    assert_eq!(action_in(&config, Mode::Visual, "y"), None);
+    assert_eq!(
+        action_in(&config, Mode::Normal, "v"),
+        Some(Action::VisualMode)
+    );
+}
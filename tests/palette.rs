@@ -0,0 +1,105 @@
+/*
+* Stygian Sift - A Terminal-based File Manager
+ * Copyright (C) 2024 Maui The Magnificent (Charon)
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published
+ * by the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+ * Contact: Maui_The_Magnificent@proton.me
+ * Project repository: https://github.com/Mauitron/StygianSift.git
+ */
+
+// The command palette: what it lists and in which order.
+
+use stygian_sift::the_core::*;
+
+fn names(ranked: &[&PaletteEntry]) -> Vec<String> {
+    ranked.iter().map(|entry| entry.name.clone()).collect()
+}
+
+fn entry<'a>(entries: &'a [PaletteEntry], name: &str) -> &'a PaletteEntry {
+    entries.iter().find(|entry| entry.name == name).unwrap()
+}
+
+#[test]
+fn every_action_is_listed_with_its_keys() {
+    let mut config = Config::new();
+    config
+        .custom_commands
+        .insert("build".to_string(), "cargo build".to_string());
+    let entries = palette_entries(&config, &["Properties".to_string()]);
+    // All but the palette itself, the menu entry and the custom command.
+    assert_eq!(entries.len(), Action::iter().count() - 1 + 2);
+    assert!(entries.iter().all(|entry| !entry.description.is_empty()));
+
+    let down = entry(&entries, "MoveDown");
+    assert_eq!(down.keys, "Down, j");
+    assert_eq!(down.description, "Move down in the file list");
+    assert_eq!(entry(&entries, "GoToTop").keys, "g g");
+    assert_eq!(entry(&entries, "Search").keys, "Shift+S");
+    assert_eq!(entry(&entries, "VisualMode").keys, "");
+    assert_eq!(
+        entry(&entries, "Properties").command,
+        PaletteCommand::Menu("Properties".to_string())
+    );
+    let build = entry(&entries, "build");
+    assert_eq!(build.command, PaletteCommand::Custom("build".to_string()));
+    assert_eq!(build.description, "Runs cargo build");
+
+    // With modal input on the keys of the modes show as well.
+    config.modal_input = true;
+    let entries = palette_entries(&config, &[]);
+    assert_eq!(entry(&entries, "VisualMode").keys, "v in normal");
+    assert_eq!(entry(&entries, "Copy").keys, "p, y in visual");
+}
+
+#[test]
+fn typing_narrows_it_down() {
+    let config = Config::new();
+    let entries = palette_entries(&config, &[]);
+    let ranked = rank_palette(&entries, "gotobottom", &[]);
+    assert_eq!(ranked[0].name, "GoToBottom");
+    // The description counts as well.
+    let ranked = rank_palette(&entries, "clipboard", &[]);
+    assert!(names(&ranked[..3]).contains(&"Paste".to_string()));
+    assert_eq!(
+        rank_palette(&entries, "qqqq", &[]),
+        Vec::<&PaletteEntry>::new()
+    );
+}
+
+#[test]
+fn recent_commands_come_first() {
+    let config = Config::new();
+    let entries = palette_entries(&config, &[]);
+    let mut recent = Vec::new();
+    remember_command(&mut recent, PaletteCommand::Action(Action::Undo));
+    remember_command(&mut recent, PaletteCommand::Action(Action::Help));
+    remember_command(&mut recent, PaletteCommand::Action(Action::Undo));
+    assert_eq!(
+        recent,
+        [
+            PaletteCommand::Action(Action::Undo),
+            PaletteCommand::Action(Action::Help)
+        ]
+    );
+    assert_eq!(
+        names(&rank_palette(&entries, "", &recent)[..3]),
+        ["Undo", "Help", "IncreaseDimDistance"]
+    );
+
+    for action in Action::iter() {
+        remember_command(&mut recent, PaletteCommand::Action(action.clone()));
+    }
+    assert_eq!(recent.len(), RECENT_COMMANDS);
+}
@@ -0,0 +1,294 @@
+/*
+* Stygian Sift - A Terminal-based File Manager
+ * Copyright (C) 2024 Maui The Magnificent (Charon)
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published
+ * by the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+ * Contact: Maui_The_Magnificent@proton.me
+ * Project repository: https://github.com/Mauitron/StygianSift.git
+ */
+
+// Dates, zones and the strftime subset. Zones are built from fixed TZif
+// bytes and TZ strings so nothing depends on where the tests run.
+
+use stygian_sift::the_core::*;
+
+fn utc(date: (i64, i64, i64), hour: i64, minute: i64, second: i64) -> i64 {
+    let (year, month, day) = date;
+    days_from_civil(year, month, day) * 86400 + hour * 3600 + minute * 60 + second
+}
+
+fn tzif_block(time_size: usize, transitions: &[(i64, u8)], types: &[(i32, &str)]) -> Vec<u8> {
+    let mut chars = Vec::new();
+    let mut type_data = Vec::new();
+    for (offset, abbreviation) in types {
+        type_data.extend(offset.to_be_bytes());
+        type_data.push((*offset != types[0].0) as u8);
+        type_data.push(chars.len() as u8);
+        chars.extend(abbreviation.as_bytes());
+        chars.push(0);
+    }
+
+    let mut block = Vec::new();
+    for count in [0, 0, 0, transitions.len(), types.len(), chars.len()] {
+        block.extend((count as u32).to_be_bytes());
+    }
+    for (time, _) in transitions {
+        match time_size {
+            4 => block.extend((*time as i32).to_be_bytes()),
+            _ => block.extend(time.to_be_bytes()),
+        }
+    }
+    block.extend(transitions.iter().map(|(_, index)| index));
+    block.extend(type_data);
+    block.extend(chars);
+    block
+}
+
+fn tzif_header(version: u8) -> Vec<u8> {
+    let mut header = b"TZif".to_vec();
+    header.push(version);
+    header.extend([0; 15]);
+    header
+}
+
+// Version 1 files only have 32-bit times. Version 2 ones get a 32-bit block
+// that is plainly wrong, so what is read has to come from the 64-bit block
+// and the rule in the footer.
+fn tzif(version: u8, transitions: &[(i64, u8)], types: &[(i32, &str)], footer: &str) -> Vec<u8> {
+    let mut data = tzif_header(version);
+    if version == 0 {
+        data.extend(tzif_block(4, transitions, types));
+        return data;
+    }
+    data.extend(tzif_block(4, &[], &[(0, "WRONG")]));
+    data.extend(tzif_header(version));
+    data.extend(tzif_block(8, transitions, types));
+    data.extend(format!("\n{}\n", footer).into_bytes());
+    data
+}
+
+fn amsterdam() -> Vec<u8> {
+    tzif(
+        b'2',
+        &[
+            (utc((1850, 1, 1), 0, 0, 0), 1),
+            (utc((2024, 3, 31), 1, 0, 0), 2),
+            (utc((2024, 10, 27), 1, 0, 0), 1),
+        ],
+        &[(1172, "LMT"), (3600, "CET"), (7200, "CEST")],
+        "CET-1CEST,M3.5.0,M10.5.0/3",
+    )
+}
+
+fn posix(rule: &str) -> TimeZone {
+    TimeZone::from_posix(rule).unwrap()
+}
+
+const CLOCK: &str = "%Y-%m-%d %H:%M:%S %Z %z";
+
+#[test]
+fn civil_dates_round_trip() {
+    assert_eq!(days_from_civil(1970, 1, 1), 0);
+    assert_eq!(days_from_civil(2000, 3, 1), 11017);
+    assert_eq!(days_from_civil(1969, 12, 31), -1);
+    assert_eq!(civil_from_days(-719468), (0, 3, 1));
+    assert_eq!(civil_from_days(days_from_civil(2024, 2, 29)), (2024, 2, 29));
+    // 1900 was not a leap year, 2000 was.
+    assert_eq!(
+        days_from_civil(1900, 3, 1) - days_from_civil(1900, 2, 28),
+        1
+    );
+    assert_eq!(
+        days_from_civil(2000, 3, 1) - days_from_civil(2000, 2, 28),
+        2
+    );
+    for days in (-800_000..800_000).step_by(997) {
+        let (year, month, day) = civil_from_days(days);
+        assert_eq!(days_from_civil(year, month as i64, day as i64), days);
+    }
+}
+
+#[test]
+fn a_version_1_file_follows_its_transitions() {
+    let data = tzif(
+        0,
+        &[
+            (utc((2024, 3, 31), 1, 0, 0), 1),
+            (utc((2024, 10, 27), 1, 0, 0), 0),
+        ],
+        &[(3600, "CET"), (7200, "CEST")],
+        "",
+    );
+    let zone = TimeZone::from_tzif(&data).unwrap();
+    let at = |date, hour, minute, second| zone.format(utc(date, hour, minute, second), CLOCK);
+    assert_eq!(at((2024, 1, 15), 12, 0, 0), "2024-01-15 13:00:00 CET +0100");
+    assert_eq!(
+        at((2024, 3, 31), 0, 59, 59),
+        "2024-03-31 01:59:59 CET +0100"
+    );
+    assert_eq!(at((2024, 3, 31), 1, 0, 0), "2024-03-31 03:00:00 CEST +0200");
+    assert_eq!(at((2024, 10, 27), 1, 0, 0), "2024-10-27 02:00:00 CET +0100");
+    // Without a footer the last transition holds forever.
+    assert_eq!(at((2030, 7, 1), 12, 0, 0), "2030-07-01 13:00:00 CET +0100");
+}
+
+#[test]
+fn a_version_2_file_reads_its_64_bit_block_and_footer() {
+    let zone = TimeZone::from_tzif(&amsterdam()).unwrap();
+    let at = |date, hour, minute, second| zone.format(utc(date, hour, minute, second), CLOCK);
+    assert_eq!(at((1800, 6, 1), 12, 0, 0), "1800-06-01 12:19:32 LMT +0019");
+    assert_eq!(at((2024, 7, 1), 12, 0, 0), "2024-07-01 14:00:00 CEST +0200");
+    // Past the last transition the footer's rule takes over.
+    assert_eq!(at((2030, 1, 15), 12, 0, 0), "2030-01-15 13:00:00 CET +0100");
+    assert_eq!(
+        at((2030, 3, 31), 0, 59, 59),
+        "2030-03-31 01:59:59 CET +0100"
+    );
+    assert_eq!(at((2030, 3, 31), 1, 0, 0), "2030-03-31 03:00:00 CEST +0200");
+    assert_eq!(
+        at((2030, 10, 27), 0, 59, 59),
+        "2030-10-27 02:59:59 CEST +0200"
+    );
+    assert_eq!(at((2030, 10, 27), 1, 0, 0), "2030-10-27 02:00:00 CET +0100");
+}
+
+#[test]
+fn posix_rules_cover_both_hemispheres() {
+    let new_york = posix("EST5EDT,M3.2.0,M11.1.0");
+    assert_eq!(
+        new_york.format(utc((2024, 1, 15), 12, 0, 0), "%H:%M %Z %z"),
+        "07:00 EST -0500"
+    );
+    assert_eq!(
+        new_york.format(utc((2024, 7, 1), 12, 0, 0), "%H:%M %Z %z"),
+        "08:00 EDT -0400"
+    );
+    // Without dates the old US rules apply.
+    let old = posix("EST5EDT");
+    assert_eq!(old.format(utc((2024, 7, 1), 12, 0, 0), "%Z"), "EDT");
+
+    // Daylight time runs over the new year down south.
+    let sydney = posix("AEST-10AEDT,M10.1.0,M4.1.0/3");
+    assert_eq!(
+        sydney.format(utc((2025, 1, 15), 12, 0, 0), "%H:%M %Z"),
+        "23:00 AEDT"
+    );
+    assert_eq!(
+        sydney.format(utc((2025, 7, 15), 12, 0, 0), "%H:%M %Z"),
+        "22:00 AEST"
+    );
+    // First Sunday of April, 03:00 daylight time is 16:00 UTC the day before.
+    assert_eq!(
+        sydney.format(utc((2025, 4, 5), 15, 59, 59), "%H:%M:%S %Z"),
+        "02:59:59 AEDT"
+    );
+    assert_eq!(
+        sydney.format(utc((2025, 4, 5), 16, 0, 0), "%H:%M:%S %Z"),
+        "02:00:00 AEST"
+    );
+
+    // Lord Howe moves by half an hour.
+    let lord_howe = posix("<+1030>-10:30<+11>-11,M10.1.0,M4.1.0");
+    assert_eq!(
+        lord_howe.format(utc((2025, 1, 15), 12, 0, 0), "%H:%M %Z %z"),
+        "23:00 +11 +1100"
+    );
+    assert_eq!(
+        lord_howe.format(utc((2025, 7, 15), 12, 0, 0), "%H:%M %Z %z"),
+        "22:30 +1030 +1030"
+    );
+
+    let kathmandu = posix("<+0545>-5:45");
+    assert_eq!(
+        kathmandu.format(utc((2025, 7, 15), 12, 0, 0), "%H:%M %z"),
+        "17:45 +0545"
+    );
+    // Daylight time without an offset is an hour ahead of standard time.
+    let st_johns = posix("NST3:30NDT,M3.2.0,M11.1.0");
+    assert_eq!(
+        st_johns.format(utc((2025, 1, 15), 12, 0, 0), "%H:%M %z"),
+        "08:30 -0330"
+    );
+    assert_eq!(
+        st_johns.format(utc((2025, 7, 15), 12, 0, 0), "%H:%M %z"),
+        "09:30 -0230"
+    );
+
+    // J60 is March 1st, leap year or not.
+    let julian = posix("AAA0BBB-1,J60/0,J300/0");
+    assert_eq!(julian.format(utc((2024, 2, 29), 23, 59, 59), "%Z"), "AAA");
+    assert_eq!(julian.format(utc((2024, 3, 1), 0, 0, 0), "%Z"), "BBB");
+
+    for broken in [
+        "",
+        "E5",
+        "EST",
+        "EST5EDT,M13.1.0,M11.1.0",
+        "EST5EDT,M3.2.0",
+        "EST5EDT,M3.2.0,M11.1.0junk",
+        "<EST5",
+    ] {
+        assert!(TimeZone::from_posix(broken).is_none(), "{}", broken);
+    }
+}
+
+#[test]
+fn strftime_fields() {
+    let zone = TimeZone::utc();
+    let leap_day = utc((2024, 2, 29), 13, 5, 9);
+    assert_eq!(
+        zone.format(leap_day, "%Y %y %m %d %e %H %I %M %S %p"),
+        "2024 24 02 29 29 13 01 05 09 PM"
+    );
+    assert_eq!(
+        zone.format(leap_day, "%b %B %a %A %j %Z %z"),
+        "Feb February Thu Thursday 060 UTC +0000"
+    );
+    assert_eq!(
+        zone.format(leap_day, "%s %% %q %"),
+        format!("{} % %q %", leap_day)
+    );
+    assert_eq!(
+        zone.format(utc((1969, 7, 5), 0, 0, 0), "%e %I %p %A"),
+        " 5 12 AM Saturday"
+    );
+}
+
+#[test]
+fn a_cut_off_zone_file_never_panics() {
+    let data = amsterdam();
+    for len in 0..data.len() {
+        let zone = TimeZone::from_tzif(&data[..len]);
+        if len < 44 {
+            assert!(zone.is_none(), "{} bytes", len);
+        }
+    }
+    assert!(TimeZone::from_tzif(&data).is_some());
+
+    // Counts that promise more than there is.
+    let mut huge = tzif_header(0);
+    for count in [0u32, 0, 0, u32::MAX, 1, 4] {
+        huge.extend(count.to_be_bytes());
+    }
+    assert!(TimeZone::from_tzif(&huge).is_none());
+}
+
+#[test]
+fn relative_times_round_down() {
+    assert_eq!(relative_time(100, 105), "just now");
+    assert_eq!(relative_time(0, 90), "1 min ago");
+    assert_eq!(relative_time(7200, 0), "in 2 h");
+    assert_eq!(relative_time(0, 400 * 86400), "1 y ago");
+}
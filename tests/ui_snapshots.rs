@@ -0,0 +1,426 @@
+/*
+* Stygian Sift - A Terminal-based File Manager
+ * Copyright (C) 2024 Maui The Magnificent (Charon)
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published
+ * by the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+ * Contact: Maui_The_Magnificent@proton.me
+ * Project repository: https://github.com/Mauitron/StygianSift.git
+ */
+
+// Drives the whole browser through a virtual terminal and compares the
+// navigation pane with the snapshots in tests/snapshots. The preview pane
+// shows file times and inodes, so it is left out.
+// Run with UPDATE_SNAPSHOTS=1 to rewrite the snapshots after a UI change.
+#![cfg(feature = "tui")]
+
+use crossterm::event::{KeyCode, KeyModifiers, MouseButton, MouseEventKind};
+use std::{
+    env, fs, io,
+    path::{Path, PathBuf},
+};
+use stygian_sift::the_tome::*;
+
+mod common;
+use common::TempDir;
+
+struct Fixture {
+    dir: TempDir,
+}
+
+impl Fixture {
+    fn new(name: &str) -> Self {
+        let dir = TempDir::new(&format!("sift-{}", name));
+        fs::create_dir_all(dir.join("docs").join("drafts")).unwrap();
+        fs::create_dir_all(dir.join("src")).unwrap();
+        fs::write(dir.join("notes.txt"), "hello\n").unwrap();
+        fs::write(dir.join("main.rs"), "fn main() {}\n").unwrap();
+        fs::write(dir.join("docs").join("guide.md"), "# Guide\n").unwrap();
+        fs::write(dir.join("src").join("lib.rs"), "").unwrap();
+        Self { dir }
+    }
+
+    fn app(&self) -> AppState {
+        let mut config = Config::new();
+        config.home_folder = Some(self.dir.to_path_buf());
+        let mut app = AppState::with_config(config).unwrap();
+        // Whatever the browser saves stays in the fixture.
+        app.config_path = self.dir.join(".maui");
+        // The page number is picked at random otherwise.
+        app.page_state.left_page = 1;
+        app
+    }
+
+    // Runs the browser until the scripted events run out.
+    fn run(&self) -> AppState {
+        let mut app = self.app();
+        match browse_fuzzy_file(&mut app) {
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => {}
+            Err(e) => panic!("browser failed: {}", e),
+            Ok(_) => panic!("browser quit before the script ended"),
+        }
+        app
+    }
+
+    fn nav_pane(terminal: &VirtualTerminal) -> String {
+        let (width, height) = size().unwrap();
+        let label = "Current directory: ";
+        terminal
+            .region(0, 0, width / 2, height)
+            .into_iter()
+            .map(|line| match line.find(label) {
+                // The fixture lives somewhere different on every machine.
+                Some(start) => format!("{}{}<fixture>", &line[..start], label),
+                None => line,
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+
+fn assert_snapshot(name: &str, actual: &str) {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("snapshots")
+        .join(format!("{}.txt", name));
+    let actual = format!("{}\n", actual.trim_end());
+    if env::var_os("UPDATE_SNAPSHOTS").is_some() || !path.exists() {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, &actual).unwrap();
+        return;
+    }
+    let expected = fs::read_to_string(&path).unwrap();
+    if expected != actual {
+        panic!(
+            "snapshot {} changed\n--- expected\n{}\n--- actual\n{}",
+            name, expected, actual
+        );
+    }
+}
+
+#[test]
+fn opens_on_the_first_entry() {
+    let fixture = Fixture::new("open");
+    let terminal = VirtualTerminal::new(100, 30);
+    fixture.run();
+    assert_snapshot("opens_on_the_first_entry", &Fixture::nav_pane(&terminal));
+}
+
+#[test]
+fn moves_down_and_up() {
+    let fixture = Fixture::new("move");
+    let terminal = VirtualTerminal::new(100, 30);
+    terminal.push_keys("jjjk");
+    fixture.run();
+    assert_snapshot("moves_down_and_up", &Fixture::nav_pane(&terminal));
+}
+
+#[test]
+fn enters_a_directory() {
+    let fixture = Fixture::new("enter");
+    let terminal = VirtualTerminal::new(100, 30);
+    terminal.push_keys("l");
+    fixture.run();
+    assert_snapshot("enters_a_directory", &Fixture::nav_pane(&terminal));
+}
+
+#[test]
+fn leaves_a_directory() {
+    let fixture = Fixture::new("leave");
+    let terminal = VirtualTerminal::new(100, 30);
+    terminal.push_keys("lh");
+    fixture.run();
+    assert_snapshot("leaves_a_directory", &Fixture::nav_pane(&terminal));
+}
+
+#[test]
+fn scrolls_with_the_mouse_wheel() {
+    let fixture = Fixture::new("wheel");
+    let terminal = VirtualTerminal::new(100, 30);
+    terminal.push_mouse(MouseEventKind::ScrollDown, 20, 15);
+    terminal.push_mouse(MouseEventKind::ScrollDown, 20, 15);
+    fixture.run();
+    assert_snapshot("scrolls_with_the_mouse_wheel", &Fixture::nav_pane(&terminal));
+}
+
+#[test]
+fn fits_a_small_terminal() {
+    let fixture = Fixture::new("small");
+    let terminal = VirtualTerminal::new(80, 24);
+    terminal.push_keys("j");
+    fixture.run();
+    assert_snapshot("fits_a_small_terminal", &Fixture::nav_pane(&terminal));
+}
+
+#[test]
+fn redraws_after_a_resize() {
+    let fixture = Fixture::new("resize");
+    let terminal = VirtualTerminal::new(100, 30);
+    terminal.push_keys("j");
+    terminal.resize(120, 40);
+    fixture.run();
+    assert_eq!(size().unwrap(), (120, 40));
+    assert_snapshot("redraws_after_a_resize", &Fixture::nav_pane(&terminal));
+}
+
+#[test]
+fn selects_with_a_click() {
+    let fixture = Fixture::new("click");
+    let terminal = VirtualTerminal::new(100, 30);
+    terminal.push_mouse(MouseEventKind::Down(MouseButton::Left), 12, 10);
+    fixture.run();
+    // The click moves the preview along with it.
+    assert!(terminal.lines().iter().any(|line| line.contains("CHAPTER SRC")));
+    assert_snapshot("selects_with_a_click", &Fixture::nav_pane(&terminal));
+}
+
+fn bytes_sent(keys: &str) -> usize {
+    let fixture = Fixture::new("bytes");
+    let terminal = VirtualTerminal::new(100, 30);
+    terminal.push_keys(keys);
+    fixture.run();
+    terminal.take_output().len()
+}
+
+#[test]
+fn sends_only_what_changed() {
+    let opening = bytes_sent("");
+    let step = bytes_sent("j") - opening;
+    assert!(opening > 0);
+    assert!(step * 4 < opening, "one step sent {} bytes, opening {}", step, opening);
+}
+
+////////////////////////////////////////////////////////Chooser////////////////////////////////////////////////////////////////////
+
+// Runs the browser as a chooser until Enter picks something.
+fn choose(fixture: &Fixture, terminal: &VirtualTerminal, mode: ChooseMode, keys: &str) -> Vec<PathBuf> {
+    let mut app = fixture.app();
+    app.chooser = Some(Chooser::new(mode, PathBuf::from("-")));
+    terminal.push_keys(keys);
+    terminal.push_key(KeyCode::Enter, KeyModifiers::NONE);
+    match browse_fuzzy_file(&mut app) {
+        Ok(BrowseResult::FileSelected(paths)) => paths,
+        Ok(_) => panic!("the browser ended without a choice"),
+        Err(e) => panic!("browser failed: {}", e),
+    }
+}
+
+#[test]
+fn chooses_a_file_from_inside_a_directory() {
+    let fixture = Fixture::new("choose-file");
+    let terminal = VirtualTerminal::new(100, 30);
+    // Enter on a directory still opens it.
+    terminal.push_key(KeyCode::Enter, KeyModifiers::NONE);
+    let chosen = choose(&fixture, &terminal, ChooseMode::Files, "j");
+    assert_eq!(chosen, [fixture.dir.join("docs").join("guide.md")]);
+}
+
+#[test]
+fn chooses_a_directory() {
+    let fixture = Fixture::new("choose-dir");
+    let terminal = VirtualTerminal::new(100, 30);
+    let chosen = choose(&fixture, &terminal, ChooseMode::Directory, "j");
+    assert_eq!(chosen, [fixture.dir.join("src")]);
+
+    // On a file the directory being browsed is picked.
+    let chosen = choose(&fixture, &terminal, ChooseMode::Directory, "jjj");
+    assert_eq!(chosen, [fixture.dir.to_path_buf()]);
+}
+
+#[test]
+fn writes_one_path_per_line() {
+    let fixture = Fixture::new("choose-write");
+    let output = fixture.dir.join("chosen");
+    let paths = [fixture.dir.join("notes.txt"), fixture.dir.join("main.rs")];
+    write_paths(&output, &paths).unwrap();
+    assert_eq!(
+        fs::read_to_string(&output).unwrap(),
+        format!("{}\n{}\n", paths[0].display(), paths[1].display())
+    );
+}
+
+#[test]
+fn opens_on_search_results() {
+    let fixture = Fixture::new("start-search");
+    let terminal = VirtualTerminal::new(100, 30);
+    let mut app = fixture.app();
+    app.start_search = Some("guide".to_string());
+    terminal.push_key(KeyCode::Enter, KeyModifiers::NONE);
+    match browse_fuzzy_file(&mut app) {
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => {}
+        other => panic!("the browser ended early: {:?}", other.err()),
+    }
+    assert_eq!(app.current_dir, fixture.dir.join("docs"));
+}
+
+/////////////////////////////////////////////////////////Modes/////////////////////////////////////////////////////////////////////
+
+fn run_modal(fixture: &Fixture) -> AppState {
+    let mut app = fixture.app();
+    app.config.modal_input = true;
+    match browse_fuzzy_file(&mut app) {
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => {}
+        other => panic!("the browser ended early: {:?}", other.err()),
+    }
+    app
+}
+
+fn selected(app: &AppState) -> Vec<PathBuf> {
+    let mut paths: Vec<PathBuf> = app.multiple_selected_files.iter().flatten().cloned().collect();
+    paths.sort();
+    paths
+}
+
+#[test]
+fn visual_mode_selects_up_to_the_cursor() {
+    let fixture = Fixture::new("visual");
+    let terminal = VirtualTerminal::new(100, 30);
+    // docs, src, notes.txt, main.rs
+    terminal.push_keys("jvjjk");
+    let app = run_modal(&fixture);
+    assert_eq!(app.key_sequence.mode(), Mode::Visual);
+    assert_eq!(selected(&app), [fixture.dir.join("notes.txt"), fixture.dir.join("src")]);
+
+    // Leaving with Esc drops the selection, an action on it keeps it.
+    terminal.push_keys("vj");
+    terminal.push_key(KeyCode::Esc, KeyModifiers::NONE);
+    let app = run_modal(&fixture);
+    assert_eq!(app.key_sequence.mode(), Mode::Normal);
+    assert_eq!(selected(&app), Vec::<PathBuf>::new());
+
+    terminal.push_keys("vjy");
+    let app = run_modal(&fixture);
+    assert_eq!(app.key_sequence.mode(), Mode::Normal);
+    assert_eq!(selected(&app), [fixture.dir.join("docs"), fixture.dir.join("src")]);
+}
+
+#[test]
+fn the_command_line_runs_actions_with_arguments() {
+    let fixture = Fixture::new("command-line");
+    let terminal = VirtualTerminal::new(100, 30);
+    terminal.push_keys(":MoveDown 2; visual");
+    terminal.push_key(KeyCode::Enter, KeyModifiers::NONE);
+    terminal.push_keys(":sort size-desc");
+    terminal.push_key(KeyCode::Enter, KeyModifiers::NONE);
+    // Up and Down go through the lines run before, running the last one
+    // again does not add it twice.
+    terminal.push_keys(":");
+    terminal.push_key(KeyCode::Up, KeyModifiers::NONE);
+    terminal.push_key(KeyCode::Up, KeyModifiers::NONE);
+    terminal.push_key(KeyCode::Down, KeyModifiers::NONE);
+    terminal.push_key(KeyCode::Enter, KeyModifiers::NONE);
+    terminal.push_keys(":cd docs");
+    terminal.push_key(KeyCode::Enter, KeyModifiers::NONE);
+    let app = run_modal(&fixture);
+    assert_eq!(app.command_history, ["MoveDown 2; visual", "sort size-desc", "cd docs"]);
+    assert_eq!(selected(&app), [fixture.dir.join("notes.txt")]);
+    assert_eq!(app.config.default_sort, SortOrder::SizeDesc);
+    assert_eq!(app.current_dir, fs::canonicalize(fixture.dir.join("docs")).unwrap());
+}
+
+// A Null key from the terminal is bound like any other, it must not pass for
+// the timeout that runs held keys.
+#[test]
+fn a_null_key_runs_its_binding() {
+    let fixture = Fixture::new("null-key");
+    let terminal = VirtualTerminal::new(100, 30);
+    terminal.push_key(KeyCode::Null, KeyModifiers::NONE);
+    let mut app = fixture.app();
+    app.config
+        .set_keybinding(KeySequence::parse("Null").unwrap(), Action::SortCycleForward);
+    match browse_fuzzy_file(&mut app) {
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => {}
+        other => panic!("the browser ended early: {:?}", other.err()),
+    }
+    assert_eq!(app.config.default_sort, SortOrder::TypeDesc);
+}
+
+////////////////////////////////////////////////////////Palette////////////////////////////////////////////////////////////////////
+
+#[test]
+fn the_palette_runs_what_is_picked() {
+    let fixture = Fixture::new("palette");
+    let terminal = VirtualTerminal::new(100, 30);
+    terminal.push_key(KeyCode::Char('k'), KeyModifiers::CONTROL);
+    terminal.push_keys("sortcycle");
+    terminal.push_key(KeyCode::Enter, KeyModifiers::NONE);
+    // Esc leaves without running anything.
+    terminal.push_key(KeyCode::Char('k'), KeyModifiers::CONTROL);
+    terminal.push_keys("quit");
+    terminal.push_key(KeyCode::Esc, KeyModifiers::NONE);
+    terminal.push_key(KeyCode::Char('k'), KeyModifiers::CONTROL);
+    terminal.push_keys("mark");
+    terminal.push_key(KeyCode::Enter, KeyModifiers::NONE);
+
+    let mut app = fixture.app();
+    app.config
+        .custom_commands
+        .insert("mark".to_string(), "echo marked> marked.txt".to_string());
+    match browse_fuzzy_file(&mut app) {
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => {}
+        other => panic!("the browser ended early: {:?}", other.err()),
+    }
+    assert_eq!(app.config.default_sort, SortOrder::TypeDesc);
+    assert!(fixture.dir.join("marked.txt").exists());
+    assert_eq!(
+        app.recent_commands,
+        [
+            PaletteCommand::Custom("mark".to_string()),
+            PaletteCommand::Action(Action::SortCycleForward),
+        ]
+    );
+}
+
+/////////////////////////////////////////////////////////Macros////////////////////////////////////////////////////////////////////
+
+#[test]
+fn a_recorded_macro_plays_on_each_selected_entry() {
+    let fixture = Fixture::new("record-macro");
+    let terminal = VirtualTerminal::new(100, 30);
+    // docs, src, notes.txt, main.rs
+    terminal.push_keys("jjqtidy");
+    terminal.push_key(KeyCode::Enter, KeyModifiers::NONE);
+    terminal.push_keys("rold");
+    terminal.push_key(KeyCode::Enter, KeyModifiers::NONE);
+    terminal.push_keys("q");
+    let app = fixture.run();
+    assert!(app.macro_recorder.is_none());
+    assert_eq!(
+        app.config.macros["tidy"],
+        [MacroStep {
+            call: CommandCall::new(Action::Rename),
+            input: parse_input("old<Enter>").unwrap(),
+        }]
+    );
+    assert!(fixture.dir.join("old.txt").exists());
+
+    // Played on src and notes.txt, each keeping its extension.
+    let fixture = Fixture::new("play-macro");
+    terminal.push_keys("jvj");
+    terminal.push_key(KeyCode::Char('Q'), KeyModifiers::SHIFT);
+    let (config, _) = Config::from_toml(&app.config.to_toml());
+    let mut app = fixture.app();
+    app.config.modal_input = true;
+    app.config.macros = config.macros;
+    match browse_fuzzy_file(&mut app) {
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => {}
+        other => panic!("the browser ended early: {:?}", other.err()),
+    }
+    assert!(app.macro_replay.is_empty());
+    assert!(fixture.dir.join("old.txt").exists());
+    assert!(fixture.dir.join("old").join("lib.rs").exists());
+    assert!(!fixture.dir.join("src").exists());
+    assert!(fixture.dir.join("main.rs").exists());
+}